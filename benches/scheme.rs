@@ -0,0 +1,124 @@
+//! Setup, encryption and decryption on BLS12-381, across sizes of channels and densities of sets.
+//!
+//! The channels have from 10 to 100 000 participants, and the sets cover 1%, 10%, 50% or all of
+//! them, with at least one recipient. The encryption and the decryption sum one point per
+//! recipient before their pairings, so their times grow with the set rather than the channel; the
+//! setup grows with the channel. The setup of 100 000 participants takes most of the run, which
+//! filters such as `cargo bench -- decrypt` skip.
+//!
+//! The `aggregate` group compares that sum, over every participant of the channels of 10 000
+//! members and more, with a multi-scalar multiplication of the same points by scalars of one.
+
+use ark_ec::msm::VariableBaseMSM;
+use ark_ec::{PairingEngine, ProjectiveCurve};
+use ark_ff::{One, PrimeField, Zero};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rand::thread_rng;
+
+use ark_bls12_381::Bls12_381;
+use interview_exercise::store::ParamStore;
+use interview_exercise::{BroadcastChannel, Recipient};
+
+type G1Projective = <Bls12_381 as PairingEngine>::G1Projective;
+type Fr = <Bls12_381 as PairingEngine>::Fr;
+
+/// Smallest channel whose aggregation is benchmarked.
+const AGGREGATE_FROM: usize = 10_000;
+
+/// Numbers of participants of the channels.
+const SIZES: [usize; 5] = [10, 100, 1_000, 10_000, 100_000];
+
+/// Percentages of the participants of a channel in the sets.
+const DENSITIES: [usize; 4] = [1, 10, 50, 100];
+
+/// The first `density` percent of the participants of a channel of `n`, at least one.
+fn set_of(n: usize, density: usize) -> Vec<usize> {
+    let size = (n * density / 100).max(1);
+    (1..=size).collect()
+}
+
+fn channels() -> Vec<(BroadcastChannel<Bls12_381>, Vec<Recipient<Bls12_381>>)> {
+    let mut rng = thread_rng();
+    SIZES
+        .iter()
+        .map(|n| BroadcastChannel::init_participants(*n, &mut rng).unwrap())
+        .collect()
+}
+
+fn setup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("setup");
+    group.sample_size(10);
+    for n in SIZES.iter() {
+        group.throughput(Throughput::Elements(*n as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(n), n, |b, n| {
+            let mut rng = thread_rng();
+            b.iter(|| BroadcastChannel::<Bls12_381>::init_participants(*n, &mut rng).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn encrypt_and_decrypt(c: &mut Criterion) {
+    let channels = channels();
+
+    let mut group = c.benchmark_group("encrypt");
+    for (channel, _) in channels.iter() {
+        let n = channel.number_participants();
+        for density in DENSITIES.iter() {
+            let set = set_of(n, *density);
+            let id = BenchmarkId::new(format!("{}%", density), n);
+            group.bench_with_input(id, &set, |b, set| {
+                let mut rng = thread_rng();
+                b.iter(|| channel.encrypt(black_box(set), &mut rng).unwrap())
+            });
+        }
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("decrypt");
+    for (channel, participants) in channels.iter() {
+        let n = channel.number_participants();
+        for density in DENSITIES.iter() {
+            let set = set_of(n, *density);
+            let (header, _) = channel.encrypt(&set, &mut thread_rng()).unwrap();
+            let id = BenchmarkId::new(format!("{}%", density), n);
+            group.bench_with_input(id, &set, |b, set| {
+                b.iter(|| {
+                    participants[0]
+                        .decrypt(black_box(set), channel, black_box(&header))
+                        .unwrap()
+                })
+            });
+        }
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("aggregate");
+    group.sample_size(10);
+    for (channel, _) in channels.iter() {
+        let n = channel.number_participants();
+        if n < AGGREGATE_FROM {
+            continue;
+        }
+        // the points the encryption sums for a set of everyone
+        let points = channel.g1_points(1..n + 1).unwrap();
+        let scalars = vec![Fr::one().into_repr(); n];
+        group.throughput(Throughput::Elements(n as u64));
+        group.bench_with_input(BenchmarkId::new("sum", n), &points, |b, points| {
+            b.iter(|| {
+                let mut sum = G1Projective::zero();
+                for point in black_box(points).iter() {
+                    sum.add_assign_mixed(point);
+                }
+                sum
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("msm", n), &points, |b, points| {
+            b.iter(|| VariableBaseMSM::multi_scalar_mul(black_box(points), &scalars))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, setup, encrypt_and_decrypt);
+criterion_main!(benches);
@@ -0,0 +1,207 @@
+//! Thin clients fetching the points they need over HTTP.
+//!
+//! A [`ParamStore`] only hands out ranges of points, so the parameters of a channel can live
+//! behind any transport. This example serves them over plain HTTP/1.0 with the standard library,
+//! and implements the store on the client side with one GET per range:
+//!
+//! - `/n` gives the number of participants, in decimal;
+//! - `/v` gives the compressed point `V`;
+//! - `/g1/<start>/<end>` and `/g2/<start>/<end>` give the compressed points at positions
+//!   `start..end`, one after the other.
+//!
+//! The client then encrypts and decrypts for a set of three recipients out of a thousand with a
+//! handful of points, where syncing the parameters would fetch three thousand of them. A gRPC
+//! service, or any other transport, implements the trait the same way, and a service handing
+//! out single points implements `store::PointsProvider`, wrapped in a `store::PointStore`.
+//!
+//! The points are public, but whoever serves them could hand out points giving keys it can
+//! compute, and plain HTTP authenticates nothing. The client therefore holds the
+//! `store::TrustedPoints` of the channel, shipped with it by the broadcaster, and wraps the server
+//! in a `store::VerifiedStore`: the points of G2 and `V` come from the trusted points, and every
+//! point of G1 fetched is checked against them with a pairing.
+//!
+//! Run with `cargo run --example remote_params`.
+
+use std::cell::Cell;
+use std::convert::TryFrom;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::ops::Range;
+use std::thread;
+
+use ark_bls12_381::Bls12_381;
+use ark_ec::{AffineCurve, PairingEngine};
+use ark_serialize::CanonicalSerialize;
+use rand::thread_rng;
+
+use interview_exercise::store::{self, ParamStore, VerifiedStore};
+use interview_exercise::{decode_points, BroadcastChannel, Encoding};
+
+type G1Affine = <Bls12_381 as PairingEngine>::G1Affine;
+type G2Affine = <Bls12_381 as PairingEngine>::G2Affine;
+type G1Projective = <Bls12_381 as PairingEngine>::G1Projective;
+
+/// Compressed encodings of `points`, one after the other.
+fn encode<P: CanonicalSerialize>(points: &[P]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for point in points {
+        point.serialize(&mut bytes).unwrap();
+    }
+    bytes
+}
+
+/// Body of the response to `path`, or `None` if it names nothing.
+fn respond(channel: &BroadcastChannel<Bls12_381>, path: &str) -> Option<Vec<u8>> {
+    let parts: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+    let range = |start: &str, end: &str| -> Option<Range<usize>> {
+        let range = start.parse().ok()?..end.parse().ok()?;
+        Some(range).filter(|range: &Range<usize>| range.start <= range.end)
+    };
+    let n = channel.number_participants();
+    match parts.as_slice() {
+        ["n"] => Some(n.to_string().into_bytes()),
+        ["v"] => Some(encode(&[channel.point_v().ok()?])),
+        ["g1", start, end] => {
+            let range = range(start, end).filter(|range| range.end <= 2 * n + 1)?;
+            Some(encode(&channel.g1_points(range).ok()?))
+        }
+        ["g2", start, end] => {
+            let range = range(start, end).filter(|range| range.end <= n + 1)?;
+            Some(encode(&channel.g2_points(range).ok()?))
+        }
+        _ => None,
+    }
+}
+
+/// Answer one request on `stream`.
+fn serve(channel: &BroadcastChannel<Bls12_381>, stream: TcpStream) -> io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    let path = request.split_whitespace().nth(1).unwrap_or("");
+    let mut stream = reader.into_inner();
+    match respond(channel, path) {
+        Some(body) => {
+            write!(
+                stream,
+                "HTTP/1.0 200 OK\r\nContent-Length: {}\r\n\r\n",
+                body.len()
+            )?;
+            stream.write_all(&body)
+        }
+        None => stream.write_all(b"HTTP/1.0 404 Not Found\r\n\r\n"),
+    }
+}
+
+/// Parameters of a channel served over HTTP at `address`.
+struct HttpParams {
+    address: String,
+    number_participants: usize,
+    /// number of points fetched so far
+    fetched: Cell<usize>,
+}
+
+impl HttpParams {
+    fn connect(address: String) -> io::Result<Self> {
+        let mut params = HttpParams {
+            address,
+            number_participants: 0,
+            fetched: Cell::new(0),
+        };
+        let n = String::from_utf8(params.get("/n")?).map_err(invalid)?;
+        params.number_participants = n.parse().map_err(invalid)?;
+        Ok(params)
+    }
+
+    /// Body of the response to a GET of `path`.
+    fn get(&self, path: &str) -> io::Result<Vec<u8>> {
+        let mut stream = TcpStream::connect(&self.address)?;
+        write!(stream, "GET {} HTTP/1.0\r\n\r\n", path)?;
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response)?;
+
+        let end = response
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+            .ok_or_else(|| invalid("no end of headers"))?;
+        if !response.starts_with(b"HTTP/1.0 200") {
+            return Err(invalid("request failed"));
+        }
+        Ok(response.split_off(end + 4))
+    }
+
+    fn points<G: AffineCurve>(&self, group: &str, range: Range<usize>) -> io::Result<Vec<G>> {
+        let bytes = self.get(&format!("/{}/{}/{}", group, range.start, range.end))?;
+        let points = decode_points(&bytes, Encoding::Compressed).map_err(invalid)?;
+        if points.len() != range.len() {
+            return Err(invalid("wrong number of points"));
+        }
+        self.fetched.set(self.fetched.get() + points.len());
+        Ok(points)
+    }
+}
+
+fn invalid<D: std::fmt::Debug>(error: D) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{:?}", error))
+}
+
+impl ParamStore<Bls12_381> for HttpParams {
+    type Error = io::Error;
+
+    fn number_participants(&self) -> usize {
+        self.number_participants
+    }
+
+    fn g1_points(&self, range: Range<usize>) -> io::Result<Vec<G1Affine>> {
+        self.points("g1", range)
+    }
+
+    fn g2_points(&self, range: Range<usize>) -> io::Result<Vec<G2Affine>> {
+        self.points("g2", range)
+    }
+
+    fn point_v(&self) -> io::Result<G1Projective> {
+        let bytes = self.get("/v")?;
+        let point: Vec<G1Affine> = decode_points(&bytes, Encoding::Compressed).map_err(invalid)?;
+        match point.as_slice() {
+            [point] => Ok(point.into_projective()),
+            _ => Err(invalid("not one point")),
+        }
+    }
+}
+
+fn main() -> io::Result<()> {
+    let mut rng = thread_rng();
+    let (channel, participants) =
+        BroadcastChannel::<Bls12_381>::init_participants(1_000, &mut rng).unwrap();
+    let parameters = channel.to_bytes();
+    // shipped with the client, not fetched from the server
+    let trusted = channel.trusted_points();
+
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let address = listener.local_addr()?.to_string();
+    let served = BroadcastChannel::try_from(parameters.as_slice()).unwrap();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let _ = serve(&served, stream);
+        }
+    });
+
+    let remote = VerifiedStore::new(HttpParams::connect(address)?, trusted);
+    let set = [3, 500, 998];
+    let (header, key) = store::encrypt(&remote, &set, &mut rng).map_err(invalid)?;
+    let fetched_by_encryption = remote.store().fetched.get();
+    let decrypted = participants[499]
+        .decrypt_from_store(&set, &remote, &header)
+        .map_err(invalid)?;
+    assert_eq!(decrypted, key);
+    assert_eq!(participants[2].decrypt(&set, &channel, &header), Ok(key));
+
+    println!(
+        "encrypted with {} points and decrypted with {} more, out of {} bytes of parameters",
+        fetched_by_encryption,
+        remote.store().fetched.get() - fetched_by_encryption,
+        parameters.len()
+    );
+    Ok(())
+}
@@ -0,0 +1,176 @@
+#ifndef DGS_BE_H
+#define DGS_BE_H
+
+/* Generated with cbindgen from src/ffi.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/* Outcome of a call. */
+typedef enum BeError {
+  /* The call succeeded */
+  BE_ERROR_OK = 0,
+  /* A pointer argument is null */
+  BE_ERROR_NULL_POINTER = 1,
+  /* An encoding does not decode */
+  BE_ERROR_INVALID_ENCODING = 2,
+  /* The channel cannot be set up */
+  BE_ERROR_SETUP = 3,
+  /* The set cannot be encrypted for */
+  BE_ERROR_ENCRYPT = 4,
+  /* The header cannot be decrypted */
+  BE_ERROR_DECRYPT = 5,
+} BeError;
+
+/* Public parameters of a channel. */
+typedef struct BeChannel BeChannel;
+
+/* Header of an encryption. */
+typedef struct BeHeader BeHeader;
+
+/* Key pair of a participant. */
+typedef struct BeRecipient BeRecipient;
+
+/* Bytes owned by the library. */
+typedef struct BeBuffer {
+  /* first byte */
+  uint8_t *data;
+  /* number of bytes */
+  size_t len;
+} BeBuffer;
+
+/*
+ * Set up a channel for `n` participants. Writes the channel to `channel`, and the key pairs of
+ * participants 1 to `n` to the `n` entries of `recipients`.
+ *
+ * # Safety
+ *
+ * `channel` must be valid for a write, and `recipients` for `n` writes.
+ */
+BeError be_channel_init(size_t n, BeChannel **channel, BeRecipient **recipients);
+
+/*
+ * Encrypt for the `set_len` identifiers at `set`. Writes the header to `header`, and the key
+ * derived from its key to the 32 bytes at `key`.
+ *
+ * # Safety
+ *
+ * `channel` must come from this library, `set` must be valid for `set_len` identifiers,
+ * `header` for a write and `key` for 32 bytes.
+ */
+BeError be_encrypt(const BeChannel *channel,
+                   const uint32_t *set,
+                   size_t set_len,
+                   BeHeader **header,
+                   uint8_t *key);
+
+/*
+ * Decrypt `header`, encrypted for the `set_len` identifiers at `set`, with `recipient`. Writes
+ * the key derived from its key to the 32 bytes at `key`.
+ *
+ * # Safety
+ *
+ * `recipient`, `channel` and `header` must come from this library, `set` must be valid for
+ * `set_len` identifiers and `key` for 32 bytes.
+ */
+BeError be_decrypt(const BeRecipient *recipient,
+                   const BeChannel *channel,
+                   const uint32_t *set,
+                   size_t set_len,
+                   const BeHeader *header,
+                   uint8_t *key);
+
+/*
+ * Write the compressed encoding of `channel` to `output`.
+ *
+ * # Safety
+ *
+ * `channel` must come from this library, and `output` must be valid for a write.
+ */
+BeError be_channel_to_bytes(const BeChannel *channel, BeBuffer *output);
+
+/*
+ * Decode the channel encoded in the `len` bytes at `data`, and write it to `output`.
+ *
+ * # Safety
+ *
+ * `data` must be valid for `len` bytes, and `output` for a write.
+ */
+BeError be_channel_from_bytes(const uint8_t *data, size_t len, BeChannel **output);
+
+/*
+ * Release a channel. Null is ignored.
+ *
+ * # Safety
+ *
+ * `channel` must be null or come from this library, and is not used afterwards.
+ */
+void be_channel_free(BeChannel *channel);
+
+/*
+ * Write the compressed encoding of `recipient` to `output`.
+ *
+ * # Safety
+ *
+ * `recipient` must come from this library, and `output` must be valid for a write.
+ */
+BeError be_recipient_to_bytes(const BeRecipient *recipient, BeBuffer *output);
+
+/*
+ * Decode the key pair encoded in the `len` bytes at `data`, and write it to `output`.
+ *
+ * # Safety
+ *
+ * `data` must be valid for `len` bytes, and `output` for a write.
+ */
+BeError be_recipient_from_bytes(const uint8_t *data, size_t len, BeRecipient **output);
+
+/*
+ * Release a key pair. Null is ignored.
+ *
+ * # Safety
+ *
+ * `recipient` must be null or come from this library, and is not used afterwards.
+ */
+void be_recipient_free(BeRecipient *recipient);
+
+/*
+ * Write the compressed encoding of `header` to `output`.
+ *
+ * # Safety
+ *
+ * `header` must come from this library, and `output` must be valid for a write.
+ */
+BeError be_header_to_bytes(const BeHeader *header, BeBuffer *output);
+
+/*
+ * Decode the header encoded in the `len` bytes at `data`, and write it to `output`.
+ *
+ * # Safety
+ *
+ * `data` must be valid for `len` bytes, and `output` for a write.
+ */
+BeError be_header_from_bytes(const uint8_t *data, size_t len, BeHeader **output);
+
+/*
+ * Release a header. Null is ignored.
+ *
+ * # Safety
+ *
+ * `header` must be null or come from this library, and is not used afterwards.
+ */
+void be_header_free(BeHeader *header);
+
+/*
+ * Release the bytes of `buffer`, and empty it. An empty buffer is ignored.
+ *
+ * # Safety
+ *
+ * `buffer` must be null or hold bytes from this library.
+ */
+void be_buffer_free(BeBuffer *buffer);
+
+#endif /* DGS_BE_H */
@@ -0,0 +1,189 @@
+//! Headers hiding their set from outsiders.
+//!
+//! Decrypting a header of [`BroadcastChannel::encrypt`] takes its set, which therefore travels in
+//! the clear next to it. [`BroadcastChannel::encrypt_anonymous`] is a hybrid mode whose header
+//! hides the set from everyone outside of it: a fresh 32-byte key is wrapped once per recipient,
+//! in a slot that only that recipient can find and open. The header holds a single point
+//! `R = r Q` of G2, and the slot of recipient `i` is keyed by the pairing
+//! `e(r V, Q_i) = e(d_i, R)`, which the encryptor computes from the public key of `i` and the
+//! recipient from its private key. The first 16 bytes derived from the pairing tag the slot and
+//! the remaining 32 mask the key. Slots are sorted by tag, so their order says nothing about the
+//! recipients, and [`Recipient::decrypt_anonymous`] finds its own without knowing the set.
+//!
+//! The header costs one slot of 48 bytes per recipient, and encrypting one pairing per
+//! recipient. The number of slots is the only thing it tells about the set, and random slots pad
+//! it to any larger size chosen by the encryptor.
+
+use alloc::vec::Vec;
+
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{UniformRand, Zero};
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+use crate::error::{DecryptError, EncryptError};
+use crate::events::{self, Event};
+use crate::kdf::{derive_key, HkdfSha256};
+use crate::{BroadcastChannel, Recipient};
+
+/// Info of the derivation of the tag and mask of a slot.
+const SLOT_INFO: &[u8] = b"DGS-BE-ANONYMOUS-SLOT";
+
+/// Size of the tag of a slot.
+const TAG_SIZE: usize = 16;
+
+/// Tag of a slot and the key it wraps.
+type Slot = ([u8; TAG_SIZE], [u8; 32]);
+
+/// Header of an encryption hiding its set.
+#[derive(Clone, PartialEq, Eq)]
+pub struct AnonymousHeader<E: PairingEngine> {
+    /// `r Q`
+    point_r: E::G2Projective,
+    /// slots of the recipients and of the padding, sorted by tag
+    slots: Vec<Slot>,
+}
+
+impl<E: PairingEngine> AnonymousHeader<E> {
+    /// Number of slots of the header.
+    pub fn slots(&self) -> usize {
+        self.slots.len()
+    }
+}
+
+/// Tag and mask of the slot keyed by `shared`.
+fn slot_secrets<E: PairingEngine>(shared: &E::Fqk) -> ([u8; TAG_SIZE], [u8; 32]) {
+    let mut bytes = [0; TAG_SIZE + 32];
+    derive_key::<E, HkdfSha256>(shared, SLOT_INFO, &mut bytes).unwrap();
+    let mut tag = [0; TAG_SIZE];
+    let mut mask = [0; 32];
+    tag.copy_from_slice(&bytes[..TAG_SIZE]);
+    mask.copy_from_slice(&bytes[TAG_SIZE..]);
+    bytes.zeroize();
+    (tag, mask)
+}
+
+/// `key` masked with `mask`.
+fn apply_mask(key: &[u8; 32], mask: &[u8; 32]) -> [u8; 32] {
+    let mut masked = *key;
+    for (byte, mask) in masked.iter_mut().zip(mask.iter()) {
+        *byte ^= mask;
+    }
+    masked
+}
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Generate a 32-byte key and a header for `set_recipients` hiding the set, with `slots`
+    /// slots. Fails if the set is refused as by [`BroadcastChannel::session`], or if it has
+    /// more recipients than `slots`.
+    pub fn encrypt_anonymous<R>(
+        &self,
+        set_recipients: &[usize],
+        slots: usize,
+        rng: &mut R,
+    ) -> Result<(AnonymousHeader<E>, [u8; 32]), EncryptError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        self.check_encryption_set(set_recipients)?;
+        if set_recipients.len() > slots {
+            return Err(EncryptError::SetTooLarge {
+                size: set_recipients.len(),
+                limit: slots,
+            });
+        }
+
+        let mut r = E::Fr::rand(rng);
+        let mut point_r = self.broadcaster_pk_g2[0].into_projective();
+        point_r *= r;
+        let mut point_rv = self.point_v;
+        point_rv *= r;
+        r.zeroize();
+        let point_rv = point_rv.into_affine();
+
+        let mut key = [0; 32];
+        rng.fill_bytes(&mut key);
+        let mut header_slots: Vec<Slot> = set_recipients
+            .iter()
+            .map(|identifier| {
+                let shared = E::pairing(point_rv, self.broadcaster_pk_g2[*identifier]);
+                let (tag, mut mask) = slot_secrets::<E>(&shared);
+                let slot = (tag, apply_mask(&key, &mask));
+                mask.zeroize();
+                slot
+            })
+            .collect();
+        while header_slots.len() < slots {
+            let mut slot = ([0; TAG_SIZE], [0; 32]);
+            rng.fill_bytes(&mut slot.0);
+            rng.fill_bytes(&mut slot.1);
+            header_slots.push(slot);
+        }
+        header_slots.sort_unstable();
+
+        self.emit(Event::Encrypted {
+            set_hash: events::set_hash(set_recipients),
+        });
+        let header = AnonymousHeader {
+            point_r,
+            slots: header_slots,
+        };
+        Ok((header, key))
+    }
+}
+
+impl<E: PairingEngine> Recipient<E> {
+    /// Key of a header of [`BroadcastChannel::encrypt_anonymous`], found without the set. Fails
+    /// if the header has no slot for this recipient, if its point is the identity, or if the
+    /// usage budget of the recipient is spent.
+    pub fn decrypt_anonymous(&self, header: &AnonymousHeader<E>) -> Result<[u8; 32], DecryptError> {
+        if header.point_r.is_zero() {
+            return Err(DecryptError::IdentityPoint);
+        }
+        self.consume_use()?;
+
+        let shared = E::pairing(self.key_pair.private_key, header.point_r);
+        let (tag, mut mask) = slot_secrets::<E>(&shared);
+        let key = header
+            .slots
+            .binary_search_by(|(slot_tag, _)| slot_tag.cmp(&tag))
+            .map(|position| apply_mask(&header.slots[position].1, &mask))
+            .map_err(|_| DecryptError::NotInSet);
+        mask.zeroize();
+        key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn recipients_find_their_slots() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(5, &mut rng).unwrap();
+
+        let (header, key) = channel.encrypt_anonymous(&[4, 1, 2], 6, &mut rng).unwrap();
+        assert_eq!(header.slots(), 6);
+        for participant in participants.iter() {
+            let decrypted = participant.decrypt_anonymous(&header);
+            if [1, 2, 4].contains(&participant.identifier) {
+                assert_eq!(decrypted, Ok(key));
+            } else {
+                assert_eq!(decrypted, Err(DecryptError::NotInSet));
+            }
+        }
+
+        assert!(matches!(
+            channel.encrypt_anonymous(&[1, 2], 1, &mut rng),
+            Err(EncryptError::SetTooLarge { size: 2, limit: 1 })
+        ));
+        assert!(matches!(
+            channel.encrypt_anonymous(&[6], 1, &mut rng),
+            Err(EncryptError::IdentifierOutOfRange(6))
+        ));
+    }
+}
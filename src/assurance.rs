@@ -0,0 +1,78 @@
+//! Fault detection in the decryption.
+//!
+//! With the `high-assurance` feature, [`Recipient::decrypt`] recomputes every key it recovers
+//! along an independent path before releasing it: the aggregate is summed again in reverse order
+//! without the cache, and the two pairings, which the decryption multiplies together before a
+//! single final exponentiation, are computed separately and divided. A glitch corrupting one of
+//! the computations makes the results differ, and the decryption fails with
+//! [`DecryptError::FaultDetected`] instead of releasing a wrong or partially computed value. This
+//! more than doubles the cost of the decryption.
+
+use ark_ec::PairingEngine;
+
+use crate::error::DecryptError;
+use crate::{BroadcastChannel, Header, Recipient};
+
+impl<E: PairingEngine> Recipient<E> {
+    /// Check `key`, recovered from `header`, by computing it again. Does nothing without the
+    /// `high-assurance` feature.
+    pub(crate) fn confirm_decryption(
+        &self,
+        set_recipients: &[usize],
+        channel: &BroadcastChannel<E>,
+        header: &Header<E>,
+        key: &E::Fqk,
+    ) -> Result<(), DecryptError> {
+        self.confirm_key(header, key, || {
+            channel.decryption_sum(set_recipients.iter().rev().copied(), self.identifier)
+        })
+    }
+
+    /// Same as [`Recipient::confirm_decryption`], with the aggregate summed again by `aggregate`.
+    pub(crate) fn confirm_key<F>(
+        &self,
+        header: &Header<E>,
+        key: &E::Fqk,
+        aggregate: F,
+    ) -> Result<(), DecryptError>
+    where
+        F: FnOnce() -> E::G1Projective,
+    {
+        if !cfg!(feature = "high-assurance") {
+            return Ok(());
+        }
+
+        let second = self.key_pair.private_key + aggregate();
+
+        let numerator = E::pairing(header.ctx_0, self.key_pair.public_key);
+        if numerator / E::pairing(second, header.ctx_1) != *key {
+            return Err(DecryptError::FaultDetected);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "high-assurance"))]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn faults_are_detected() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(3, &mut rng).unwrap();
+        let (header, key) = channel.encrypt(&[1, 2, 3], &mut rng).unwrap();
+        assert_eq!(
+            participants[1].decrypt(&[1, 2, 3], &channel, &header),
+            Ok(key.clone())
+        );
+
+        let glitched = *key.key() * key.key();
+        assert_eq!(
+            participants[1].confirm_decryption(&[1, 2, 3], &channel, &header, &glitched),
+            Err(DecryptError::FaultDetected)
+        );
+    }
+}
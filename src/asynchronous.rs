@@ -0,0 +1,246 @@
+//! Operations with secrets behind an asynchronous store.
+//!
+//! A store reached over the network, or an HSM answering through a driver, is slow to answer,
+//! and waiting for it with [`crate::keystore::decrypt`] blocks the thread of an async runtime.
+//! [`AsyncSecretKeyStore`] is the same interface as [`SecretKeyStore`] with futures as results,
+//! and [`decrypt_async`] and [`BroadcastChannel::issue_key_async`] only await the store: the
+//! checks, the sums of points and the pairings outside the store are computed in place, as they
+//! do not wait on anything. Every [`SecretKeyStore`] is an [`AsyncSecretKeyStore`] whose futures
+//! are ready at once.
+//!
+//! No runtime is needed, the futures are polled by the caller's. Only available with the `async`
+//! feature.
+
+use alloc::boxed::Box;
+use core::future::Future;
+use core::pin::Pin;
+
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::Field;
+
+use crate::error::{KeyStoreError, SetupError};
+use crate::events::Event;
+use crate::keystore::SecretKeyStore;
+use crate::{BroadcastChannel, Header, KeyPair, Recipient, SharedKey};
+
+/// Future of the result of an operation of a store.
+pub type StoreFuture<'a, T, S> = Pin<Box<dyn Future<Output = Result<T, S>> + Send + 'a>>;
+
+/// Store of private keys of participants, and possibly of the secret of the broadcaster, whose
+/// operations complete asynchronously.
+pub trait AsyncSecretKeyStore<E: PairingEngine> {
+    /// Error of the store
+    type Error;
+
+    /// `e(d_i + offset, point)`, with `d_i` the private key of participant `identifier`.
+    fn pair_private_key(
+        &self,
+        identifier: usize,
+        offset: E::G1Projective,
+        point: E::G2Projective,
+    ) -> StoreFuture<'_, E::Fqk, Self::Error>;
+
+    /// `gamma point`, with `gamma` the secret of the broadcaster.
+    fn multiply_secret(
+        &self,
+        point: E::G1Projective,
+    ) -> StoreFuture<'_, E::G1Projective, Self::Error>;
+}
+
+impl<E, S> AsyncSecretKeyStore<E> for S
+where
+    E: PairingEngine,
+    S: SecretKeyStore<E> + Sync,
+    S::Error: Send,
+{
+    type Error = S::Error;
+
+    fn pair_private_key(
+        &self,
+        identifier: usize,
+        offset: E::G1Projective,
+        point: E::G2Projective,
+    ) -> StoreFuture<'_, E::Fqk, S::Error> {
+        let result = SecretKeyStore::pair_private_key(self, identifier, &offset, &point);
+        Box::pin(async move { result })
+    }
+
+    fn multiply_secret(
+        &self,
+        point: E::G1Projective,
+    ) -> StoreFuture<'_, E::G1Projective, S::Error> {
+        let result = SecretKeyStore::multiply_secret(self, &point);
+        Box::pin(async move { result })
+    }
+}
+
+/// Same as [`crate::keystore::decrypt`], awaiting the store.
+pub async fn decrypt_async<E, S>(
+    store: &S,
+    identifier: usize,
+    set_recipients: &[usize],
+    channel: &BroadcastChannel<E>,
+    header: &Header<E>,
+) -> Result<SharedKey<E>, KeyStoreError<S::Error>>
+where
+    E: PairingEngine,
+    S: AsyncSecretKeyStore<E>,
+{
+    if let Err(error) = channel.check_decryption(identifier, set_recipients, header) {
+        channel.emit(Event::DecryptFailed {
+            identifier,
+            reason: error,
+        });
+        return Err(KeyStoreError::Decrypt(error));
+    }
+
+    let aggregate = channel.decryption_aggregate(set_recipients, identifier);
+    let second = store
+        .pair_private_key(identifier, aggregate, header.ctx_1)
+        .await
+        .map_err(KeyStoreError::Store)?;
+    let public_key = channel.broadcaster_pk_g2[identifier].into_projective();
+    // pairings of points of the groups are never zero
+    Ok(SharedKey::new(
+        E::pairing(header.ctx_0, public_key) * second.inverse().unwrap(),
+    ))
+}
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Same as [`BroadcastChannel::issue_key_from_store`], awaiting the store.
+    pub async fn issue_key_async<S: AsyncSecretKeyStore<E>>(
+        &self,
+        store: &S,
+        identifier: usize,
+    ) -> Result<Recipient<E>, KeyStoreError<S::Error>> {
+        let point_v = store
+            .multiply_secret(E::G1Projective::prime_subgroup_generator())
+            .await
+            .map_err(KeyStoreError::Store)?;
+        if point_v != self.point_v {
+            return Err(KeyStoreError::Setup(SetupError::WrongSecret));
+        }
+        if self.public_key(identifier).is_none() {
+            return Err(KeyStoreError::Setup(SetupError::NotIssued(identifier)));
+        }
+
+        let private_key = store
+            .multiply_secret(self.broadcaster_pk_g1[identifier].into_projective())
+            .await
+            .map_err(KeyStoreError::Store)?;
+        self.emit(Event::KeyIssued { identifier });
+        Ok(Recipient {
+            identifier,
+            key_pair: KeyPair {
+                public_key: self.broadcaster_pk_g2[identifier].into_projective(),
+                private_key,
+            },
+            budget: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::StoreError;
+    use crate::keystore::MemoryKeyStore;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+    use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+    /// Poll `future` to completion on the current thread.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        fn raw_waker() -> RawWaker {
+            fn clone(_: *const ()) -> RawWaker {
+                raw_waker()
+            }
+            fn noop(_: *const ()) {}
+            static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+            RawWaker::new(std::ptr::null(), &VTABLE)
+        }
+        // SAFETY: the vtable does nothing, so any data pointer is fine
+        let waker = unsafe { Waker::from_raw(raw_waker()) };
+        let mut context = Context::from_waker(&waker);
+        let mut future = Box::pin(future);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+        }
+    }
+
+    /// Future pending once before it is ready, as a store answering later.
+    struct Deferred<T>(Option<T>, bool);
+
+    impl<T: Unpin> Future for Deferred<T> {
+        type Output = T;
+
+        fn poll(mut self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<T> {
+            if !self.1 {
+                self.1 = true;
+                context.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            Poll::Ready(self.0.take().unwrap())
+        }
+    }
+
+    /// Memory store answering asynchronously.
+    struct RemoteStore(MemoryKeyStore<Bls12_381>);
+
+    impl AsyncSecretKeyStore<Bls12_381> for RemoteStore {
+        type Error = StoreError;
+
+        fn pair_private_key(
+            &self,
+            identifier: usize,
+            offset: <Bls12_381 as PairingEngine>::G1Projective,
+            point: <Bls12_381 as PairingEngine>::G2Projective,
+        ) -> StoreFuture<'_, <Bls12_381 as PairingEngine>::Fqk, StoreError> {
+            let result = SecretKeyStore::pair_private_key(&self.0, identifier, &offset, &point);
+            Box::pin(Deferred(Some(result), false))
+        }
+
+        fn multiply_secret(
+            &self,
+            point: <Bls12_381 as PairingEngine>::G1Projective,
+        ) -> StoreFuture<'_, <Bls12_381 as PairingEngine>::G1Projective, StoreError> {
+            let result = SecretKeyStore::multiply_secret(&self.0, &point);
+            Box::pin(Deferred(Some(result), false))
+        }
+    }
+
+    #[test]
+    fn async_stores_decrypt_and_issue() {
+        let mut rng = thread_rng();
+        let (channel, participants, secret) =
+            BroadcastChannel::<Bls12_381>::init_with_secret(3, &mut rng).unwrap();
+        let set = [2, 3];
+        let (header, key) = channel.encrypt(&set, &mut rng).unwrap();
+
+        let mut memory = MemoryKeyStore::new();
+        memory.insert_recipient(&participants[2]);
+        memory.set_broadcaster_secret(secret);
+        let store = RemoteStore(memory);
+        assert_eq!(
+            block_on(decrypt_async(&store, 3, &set, &channel, &header)),
+            Ok(key.clone())
+        );
+        assert_eq!(
+            block_on(decrypt_async(&store, 2, &set, &channel, &header)),
+            Err(KeyStoreError::Store(StoreError::MissingKey(2)))
+        );
+        assert_eq!(
+            block_on(decrypt_async(&participants[1], 2, &set, &channel, &header)),
+            Ok(key.clone())
+        );
+
+        let issued = block_on(channel.issue_key_async(&store, 2)).unwrap();
+        assert_eq!(issued.decrypt(&set, &channel, &header), Ok(key.clone()));
+        assert!(matches!(
+            block_on(channel.issue_key_async(&participants[0], 2)),
+            Err(KeyStoreError::Store(StoreError::MissingSecret))
+        ));
+    }
+}
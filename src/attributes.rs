@@ -0,0 +1,244 @@
+//! Sets of recipients selected by their attributes.
+//!
+//! Access control is usually stated over attributes of the users rather than over their
+//! identifiers: members of the payments team, engineers in Europe. An [`AttributeStore`] keeps
+//! named attributes of the participants, and resolves an [`AccessPolicy`] over them to the
+//! [`RecipientSet`] it selects. [`AttributeStore::encrypt`] resolves the policy when encrypting,
+//! so a header always reaches whoever matches the policy at that time, and returns the set so
+//! that the recipients can decrypt.
+//!
+//! Policies are built from conditions on single attributes, such as
+//! `AccessPolicy::attribute("team").eq("payments")`, combined with [`AccessPolicy::and`],
+//! [`AccessPolicy::or`] and negation. Only the participants of the store are ever selected, in
+//! particular by a negation.
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::ops::Not;
+
+use ark_ec::PairingEngine;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::error::EncryptError;
+use crate::set::RecipientSet;
+use crate::{BroadcastChannel, Header, SharedKey};
+
+/// Condition on the attributes of a participant.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccessPolicy(Condition);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Condition {
+    Present(String),
+    OneOf(String, Vec<String>),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+    Not(Box<Condition>),
+}
+
+/// Attribute of a policy being built, see [`AccessPolicy::attribute`].
+#[derive(Clone, Debug)]
+pub struct Attribute {
+    name: String,
+}
+
+impl Attribute {
+    /// Policy selecting the participants whose attribute is `value`.
+    pub fn eq(self, value: &str) -> AccessPolicy {
+        self.one_of(&[value])
+    }
+
+    /// Policy selecting the participants whose attribute is one of `values`.
+    pub fn one_of(self, values: &[&str]) -> AccessPolicy {
+        let values = values.iter().map(|value| value.to_string()).collect();
+        AccessPolicy(Condition::OneOf(self.name, values))
+    }
+
+    /// Policy selecting the participants with the attribute, whatever its value.
+    pub fn is_present(self) -> AccessPolicy {
+        AccessPolicy(Condition::Present(self.name))
+    }
+}
+
+impl AccessPolicy {
+    /// Start a policy on attribute `name`.
+    pub fn attribute(name: &str) -> Attribute {
+        Attribute {
+            name: name.to_string(),
+        }
+    }
+
+    /// Policy selecting the participants selected by both policies.
+    pub fn and(self, other: AccessPolicy) -> AccessPolicy {
+        AccessPolicy(Condition::And(Box::new(self.0), Box::new(other.0)))
+    }
+
+    /// Policy selecting the participants selected by either policy.
+    pub fn or(self, other: AccessPolicy) -> AccessPolicy {
+        AccessPolicy(Condition::Or(Box::new(self.0), Box::new(other.0)))
+    }
+
+    /// Whether a participant with `attributes` is selected.
+    fn admits(&self, attributes: &BTreeMap<String, String>) -> bool {
+        self.0.admits(attributes)
+    }
+}
+
+impl Not for AccessPolicy {
+    type Output = AccessPolicy;
+
+    /// Policy selecting the participants not selected by this one.
+    fn not(self) -> AccessPolicy {
+        AccessPolicy(Condition::Not(Box::new(self.0)))
+    }
+}
+
+impl Condition {
+    fn admits(&self, attributes: &BTreeMap<String, String>) -> bool {
+        match self {
+            Condition::Present(name) => attributes.contains_key(name),
+            Condition::OneOf(name, values) => attributes
+                .get(name)
+                .is_some_and(|value| values.contains(value)),
+            Condition::And(left, right) => left.admits(attributes) && right.admits(attributes),
+            Condition::Or(left, right) => left.admits(attributes) || right.admits(attributes),
+            Condition::Not(condition) => !condition.admits(attributes),
+        }
+    }
+}
+
+/// Named attributes of the participants of a channel.
+#[derive(Clone, Debug, Default)]
+pub struct AttributeStore {
+    attributes: BTreeMap<usize, BTreeMap<String, String>>,
+}
+
+impl AttributeStore {
+    /// Store without participants.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set attribute `name` of participant `identifier` to `value`, adding the participant to the
+    /// store if needed. Returns the previous value.
+    pub fn set(&mut self, identifier: usize, name: &str, value: &str) -> Option<String> {
+        self.attributes
+            .entry(identifier)
+            .or_default()
+            .insert(name.to_string(), value.to_string())
+    }
+
+    /// Remove attribute `name` of participant `identifier`, returning its value. The participant
+    /// stays in the store.
+    pub fn unset(&mut self, identifier: usize, name: &str) -> Option<String> {
+        self.attributes.get_mut(&identifier)?.remove(name)
+    }
+
+    /// Value of attribute `name` of participant `identifier`.
+    pub fn get(&self, identifier: usize, name: &str) -> Option<&str> {
+        self.attributes
+            .get(&identifier)?
+            .get(name)
+            .map(String::as_str)
+    }
+
+    /// Remove participant `identifier` and all its attributes, so that no policy selects it.
+    /// Returns whether it was in the store.
+    pub fn remove(&mut self, identifier: usize) -> bool {
+        self.attributes.remove(&identifier).is_some()
+    }
+
+    /// Participants of the store selected by `policy`.
+    pub fn resolve(&self, policy: &AccessPolicy) -> RecipientSet {
+        let identifiers: Vec<usize> = self
+            .attributes
+            .iter()
+            .filter(|(_, attributes)| policy.admits(attributes))
+            .map(|(identifier, _)| *identifier)
+            .collect();
+        RecipientSet::new(&identifiers)
+    }
+
+    /// Same as [`BroadcastChannel::encrypt`], for the participants selected by `policy`. Returns
+    /// the selected set along with the header and its key.
+    pub fn encrypt<E, R>(
+        &self,
+        channel: &BroadcastChannel<E>,
+        policy: &AccessPolicy,
+        rng: &mut R,
+    ) -> Result<(RecipientSet, Header<E>, SharedKey<E>), EncryptError>
+    where
+        E: PairingEngine,
+        R: RngCore + CryptoRng,
+    {
+        let set = self.resolve(policy);
+        let (header, key) = channel.encrypt(set.as_slice(), rng)?;
+        Ok((set, header, key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn policies_select_recipients() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(5, &mut rng).unwrap();
+        let mut store = AttributeStore::new();
+        for (identifier, team, region) in [
+            (1, "payments", "eu"),
+            (2, "payments", "us"),
+            (3, "payments", "uk"),
+            (4, "search", "eu"),
+        ]
+        .iter()
+        {
+            store.set(*identifier, "team", team);
+            store.set(*identifier, "region", region);
+        }
+        store.set(3, "manager", "yes");
+        store.set(5, "region", "eu");
+
+        let european = AccessPolicy::attribute("region").one_of(&["eu", "uk"]);
+        let policy = AccessPolicy::attribute("team")
+            .eq("payments")
+            .and(european.clone());
+        assert_eq!(store.resolve(&policy).as_slice(), &[1, 3]);
+        assert_eq!(
+            store
+                .resolve(&!AccessPolicy::attribute("team").is_present())
+                .as_slice(),
+            &[5]
+        );
+        let managers = AccessPolicy::attribute("manager").is_present();
+        assert_eq!(store.resolve(&managers.or(!european)).as_slice(), &[2, 3]);
+
+        let (set, header, key) = store.encrypt(&channel, &policy, &mut rng).unwrap();
+        assert_eq!(
+            participants[2].decrypt(set.as_slice(), &channel, &header),
+            Ok(key)
+        );
+
+        store.set(2, "region", "eu");
+        store.remove(3);
+        assert_eq!(store.get(2, "region"), Some("eu"));
+        assert_eq!(store.resolve(&policy).as_slice(), &[1, 2]);
+        assert_eq!(
+            store
+                .encrypt(
+                    &channel,
+                    &AccessPolicy::attribute("team").eq("legal"),
+                    &mut rng
+                )
+                .err(),
+            Some(EncryptError::EmptySet)
+        );
+    }
+}
@@ -0,0 +1,173 @@
+//! Statistical timing audit of the decryption.
+//!
+//! Available with the `ct-audit` feature. [`measure`] is a dudect-style harness: it runs an
+//! operation on inputs of two classes, interleaved at random, times every run, and compares the
+//! two distributions of timings with Welch's t-test. A t statistic beyond [`LEAK_THRESHOLD`] is
+//! strong evidence that the timing depends on the class. [`audit_decryption_position`] and
+//! [`audit_decryption_key`] apply it to [`Recipient::decrypt`].
+//!
+//! A statistic below the threshold is no proof of constant time: it only means that no leak was
+//! detected with that many samples on that machine. Audits should run on release builds with
+//! many samples, as debug builds are too noisy to be representative.
+
+use std::time::Instant;
+
+use ark_ec::PairingEngine;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::{BroadcastChannel, Recipient};
+
+/// Absolute value of the t statistic beyond which the timings are considered to leak, as in
+/// dudect.
+pub const LEAK_THRESHOLD: f64 = 4.5;
+
+/// Outcome of a timing audit.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimingReport {
+    samples: [usize; 2],
+    t_statistic: f64,
+}
+
+impl TimingReport {
+    /// Welch's t statistic of the timings of the two classes.
+    pub fn t_statistic(&self) -> f64 {
+        self.t_statistic
+    }
+
+    /// Number of runs of each class.
+    pub fn samples(&self) -> [usize; 2] {
+        self.samples
+    }
+
+    /// Whether the timings of the two classes differ significantly.
+    pub fn leaks(&self) -> bool {
+        self.t_statistic.abs() > LEAK_THRESHOLD
+    }
+}
+
+/// Running mean and variance, with Welford's method.
+#[derive(Default)]
+struct Moments {
+    count: usize,
+    mean: f64,
+    m2: f64,
+}
+
+impl Moments {
+    fn push(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    fn variance(&self) -> f64 {
+        if self.count < 2 {
+            return 0.0;
+        }
+        self.m2 / (self.count - 1) as f64
+    }
+}
+
+/// Time `samples` runs of `operation`, each on a class drawn at random from `rng`, and compare
+/// the timings of the two classes. `operation` receives the class of the run.
+pub fn measure<R, F>(samples: usize, rng: &mut R, mut operation: F) -> TimingReport
+where
+    R: RngCore,
+    F: FnMut(bool),
+{
+    let mut moments = [Moments::default(), Moments::default()];
+    for _ in 0..samples {
+        let class = rng.next_u32() & 1 == 1;
+        let start = Instant::now();
+        operation(class);
+        let elapsed = start.elapsed().as_nanos() as f64;
+        moments[class as usize].push(elapsed);
+    }
+
+    let [first, second] = &moments;
+    let deviation = (first.variance() / first.count.max(1) as f64
+        + second.variance() / second.count.max(1) as f64)
+        .sqrt();
+    let t_statistic = if deviation == 0.0 {
+        0.0
+    } else {
+        (first.mean - second.mean) / deviation
+    };
+    TimingReport {
+        samples: [first.count, second.count],
+        t_statistic,
+    }
+}
+
+/// Check whether the decryption time depends on the position of the recipient in the set, by
+/// comparing the first and the last of `n` recipients decrypting the same header. `n` must be at
+/// least 2.
+pub fn audit_decryption_position<E, R>(n: usize, samples: usize, rng: &mut R) -> TimingReport
+where
+    E: PairingEngine,
+    R: RngCore + CryptoRng,
+{
+    assert!(n >= 2, "the audit needs two recipients");
+    let (channel, participants) = BroadcastChannel::<E>::init_participants(n, rng).unwrap();
+    let set: Vec<usize> = (1..=n).collect();
+    // the encryption randomness is spent here, the harness only needs to pick classes
+    let (header, _) = channel.encrypt(&set, rng).unwrap();
+
+    measure(samples, rng, |last| {
+        let recipient = &participants[if last { n - 1 } else { 0 }];
+        assert!(recipient.decrypt(&set, &channel, &header).is_ok());
+    })
+}
+
+/// Check whether the decryption time depends on the private key, fixed against random: runs of
+/// the first class always use the first of `n` recipients, runs of the second class a recipient
+/// drawn at random. `n` must be at least 2.
+pub fn audit_decryption_key<E, R>(n: usize, samples: usize, rng: &mut R) -> TimingReport
+where
+    E: PairingEngine,
+    R: RngCore + CryptoRng,
+{
+    assert!(n >= 2, "the audit needs two recipients");
+    let (channel, participants) = BroadcastChannel::<E>::init_participants(n, rng).unwrap();
+    let set: Vec<usize> = (1..=n).collect();
+    let (header, _) = channel.encrypt(&set, rng).unwrap();
+    // drawn up front, so that no randomness is sampled while timing
+    let random: Vec<&Recipient<E>> = (0..samples)
+        .map(|_| &participants[rng.next_u32() as usize % n])
+        .collect();
+
+    let mut draws = random.into_iter();
+    measure(samples, rng, |random_key| {
+        let drawn = draws.next().unwrap();
+        let recipient = if random_key { drawn } else { &participants[0] };
+        assert!(recipient.decrypt(&set, &channel, &header).is_ok());
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+    use std::thread::sleep;
+    use std::time::Duration;
+
+    #[test]
+    fn leaks_are_detected() {
+        let mut rng = thread_rng();
+        let report = measure(200, &mut rng, |slow| {
+            if slow {
+                sleep(Duration::from_micros(200));
+            }
+        });
+        assert!(report.leaks());
+        assert_eq!(report.samples()[0] + report.samples()[1], 200);
+
+        // too few samples to say anything, this only exercises the audits
+        let report = audit_decryption_position::<Bls12_381, _>(2, 4, &mut rng);
+        assert_eq!(report.samples()[0] + report.samples()[1], 4);
+        let report = audit_decryption_key::<Bls12_381, _>(2, 4, &mut rng);
+        assert_eq!(report.samples()[0] + report.samples()[1], 4);
+    }
+}
@@ -0,0 +1,240 @@
+//! Headers signed by the broadcaster.
+//!
+//! Encrypting only takes the public parameters, so anyone holding them can produce headers that
+//! recipients decrypt as if the broadcaster sent them. A broadcaster holding a [`SigningKey`]
+//! signs its headers with [`BroadcastChannel::encrypt_signed`], and recipients that know its
+//! [`VerifyingKey`] refuse the others with [`Recipient::decrypt_verified`], which checks the
+//! signature before any pairing of the decryption.
+//!
+//! The signed message is the fingerprint of the channel, the canonical encoding of the set and
+//! the encoding of the header, so a signed header is refused in another channel, for another set,
+//! or after the parameters of its channel change. A signed header is encoded as the header
+//! followed by the signature.
+//!
+//! Receivers catching up on many headers check their signatures together with
+//! [`BroadcastChannel::verify_headers_batch`], which costs one multi-pairing for the whole batch
+//! when every signature is right, see [`VerifyingKey::verify_batch`].
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use ark_ec::PairingEngine;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use rand_core::{CryptoRng, RngCore};
+
+use crate::encoding::{from_bytes, to_bytes};
+use crate::error::{BatchVerifyError, DecryptError, EncryptError};
+use crate::set::RecipientSet;
+use crate::signature::{Signature, SigningKey, VerifyingKey};
+use crate::{BroadcastChannel, Header, Recipient, SharedKey};
+
+/// Domain separation tag of the messages signed for headers.
+const HEADER_TAG: &[u8] = b"DGS-BE-SIGNED-HEADER-V1";
+
+/// Header with the signature of the broadcaster.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SignedHeader<E: PairingEngine> {
+    header: Header<E>,
+    signature: Signature<E>,
+}
+
+impl<E: PairingEngine> SignedHeader<E> {
+    /// Header that is signed.
+    pub fn header(&self) -> &Header<E> {
+        &self.header
+    }
+
+    /// Signature of the header.
+    pub fn signature(&self) -> &Signature<E> {
+        &self.signature
+    }
+
+    /// Whether the header was signed by `broadcaster` for `set_recipients` in `channel`.
+    pub fn verify(
+        &self,
+        set_recipients: &[usize],
+        channel: &BroadcastChannel<E>,
+        broadcaster: &VerifyingKey<E>,
+    ) -> bool {
+        broadcaster.verify(
+            &header_message(channel, set_recipients, &self.header),
+            &self.signature,
+        )
+    }
+
+    /// Compressed encoding of the header and its signature.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+}
+
+/// Message signed for `header`, encrypted for `set_recipients` in `channel`.
+pub(crate) fn header_message<E: PairingEngine>(
+    channel: &BroadcastChannel<E>,
+    set_recipients: &[usize],
+    header: &Header<E>,
+) -> Vec<u8> {
+    let mut message = HEADER_TAG.to_vec();
+    message.extend_from_slice(channel.view().fingerprint().as_ref());
+    message.extend_from_slice(&RecipientSet::new(set_recipients).canonical_bytes());
+    message.extend_from_slice(&header.to_bytes());
+    message
+}
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Same as [`BroadcastChannel::encrypt`], with the header signed by `key`.
+    pub fn encrypt_signed<R>(
+        &self,
+        set_recipients: &[usize],
+        key: &SigningKey<E>,
+        rng: &mut R,
+    ) -> Result<(SignedHeader<E>, SharedKey<E>), EncryptError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let (header, key_header) = self.encrypt(set_recipients, rng)?;
+        let signature = key.sign(&header_message(self, set_recipients, &header));
+        Ok((SignedHeader { header, signature }, key_header))
+    }
+
+    /// Check that every header of `headers`, with its set, was signed by `broadcaster` for this
+    /// channel. Fails with the positions of the headers whose signatures do not verify.
+    pub fn verify_headers_batch<R>(
+        &self,
+        broadcaster: &VerifyingKey<E>,
+        headers: &[(&[usize], SignedHeader<E>)],
+        rng: &mut R,
+    ) -> Result<(), BatchVerifyError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let signed: Vec<(Vec<u8>, Signature<E>)> = headers
+            .iter()
+            .map(|(set_recipients, signed)| {
+                (
+                    header_message(self, set_recipients, &signed.header),
+                    signed.signature,
+                )
+            })
+            .collect();
+        let failed = broadcaster.verify_batch(&signed, rng);
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(BatchVerifyError::InvalidSignatures(failed))
+        }
+    }
+}
+
+impl<E: PairingEngine> Recipient<E> {
+    /// Same as [`Recipient::decrypt`], for a header signed by `broadcaster`. Fails if the
+    /// signature does not verify, before decrypting.
+    pub fn decrypt_verified(
+        &self,
+        set_recipients: &[usize],
+        channel: &BroadcastChannel<E>,
+        broadcaster: &VerifyingKey<E>,
+        signed: &SignedHeader<E>,
+    ) -> Result<SharedKey<E>, DecryptError> {
+        if !signed.verify(set_recipients, channel, broadcaster) {
+            return Err(DecryptError::InvalidSignature);
+        }
+        self.decrypt(set_recipients, channel, &signed.header)
+    }
+}
+
+impl<E: PairingEngine> CanonicalSerialize for SignedHeader<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.header.serialize(&mut writer)?;
+        self.signature.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.header.serialized_size() + self.signature.serialized_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for SignedHeader<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let header = Header::deserialize(&mut reader)?;
+        let signature = Signature::deserialize(&mut reader)?;
+        Ok(SignedHeader { header, signature })
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for SignedHeader<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn forged_headers_are_refused() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(4, &mut rng).unwrap();
+        let key = SigningKey::generate(&mut rng);
+        let broadcaster = key.verifying_key();
+
+        let (signed, key_header) = channel.encrypt_signed(&[1, 3], &key, &mut rng).unwrap();
+        let received = SignedHeader::try_from(signed.to_bytes().as_slice()).unwrap();
+        assert_eq!(
+            participants[2].decrypt_verified(&[3, 1], &channel, &broadcaster, &received),
+            Ok(key_header)
+        );
+        assert_eq!(
+            participants[0].decrypt_verified(&[1, 2, 3], &channel, &broadcaster, &signed),
+            Err(DecryptError::InvalidSignature)
+        );
+
+        let (header, _) = channel.encrypt(&[1, 3], &mut rng).unwrap();
+        let forged = SignedHeader {
+            header,
+            signature: signed.signature,
+        };
+        assert_eq!(
+            participants[0].decrypt_verified(&[1, 3], &channel, &broadcaster, &forged),
+            Err(DecryptError::InvalidSignature)
+        );
+        let impostor = SigningKey::generate(&mut rng);
+        let (signed, _) = channel
+            .encrypt_signed(&[1, 3], &impostor, &mut rng)
+            .unwrap();
+        assert_eq!(
+            participants[0].decrypt_verified(&[1, 3], &channel, &broadcaster, &signed),
+            Err(DecryptError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn backlogs_are_verified_together() {
+        let mut rng = thread_rng();
+        let (channel, _) = BroadcastChannel::<Bls12_381>::init_participants(4, &mut rng).unwrap();
+        let key = SigningKey::generate(&mut rng);
+        let broadcaster = key.verifying_key();
+
+        let sets: [&[usize]; 3] = [&[1, 2], &[3], &[2, 4]];
+        let mut headers: Vec<(&[usize], SignedHeader<Bls12_381>)> = sets
+            .iter()
+            .map(|set| (*set, channel.encrypt_signed(set, &key, &mut rng).unwrap().0))
+            .collect();
+        assert_eq!(
+            channel.verify_headers_batch(&broadcaster, &headers, &mut rng),
+            Ok(())
+        );
+
+        headers[1].0 = &[1, 3];
+        assert_eq!(
+            channel.verify_headers_batch(&broadcaster, &headers, &mut rng),
+            Err(BatchVerifyError::InvalidSignatures(vec![1]))
+        );
+    }
+}
@@ -0,0 +1,122 @@
+//! Batch verification of the keys of many recipients.
+//!
+//! [`Recipient::validate`] checks a key with two pairings, `e(d_i, Q) = e(V, Q_i)`, which adds up
+//! to hours when auditing every key of a large channel. [`BroadcastChannel::verify_keys`] combines
+//! the checks of a batch with random scalars `r_i` into the single check
+//! `e(sum r_i d_i, Q) = e(V, sum r_i Q_i)`, computed as one multi-pairing. A wrong key makes it
+//! fail except with negligible probability, in which case the batch is split in halves, each
+//! checked the same way, until the wrong keys are isolated. A batch with `f` wrong keys out of `m`
+//! costs about `2 f log2(m)` multi-pairings on top of the first one.
+
+use alloc::vec::Vec;
+
+use ark_ec::{PairingEngine, ProjectiveCurve};
+use ark_ff::{One, UniformRand, Zero};
+use rand_core::{CryptoRng, RngCore};
+
+use crate::validate::in_subgroup;
+use crate::{BroadcastChannel, Recipient};
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Identifiers of the recipients of `recipients` whose keys are not valid for this channel,
+    /// in the order of `recipients`. A key is valid if it passes [`Recipient::validate`].
+    pub fn verify_keys<R>(&self, recipients: &[Recipient<E>], rng: &mut R) -> Vec<usize>
+    where
+        R: RngCore + CryptoRng,
+    {
+        // structural checks are cheap, and keys failing them are kept out of the pairings
+        let mut failed = Vec::new();
+        let mut candidates = Vec::with_capacity(recipients.len());
+        for (position, recipient) in recipients.iter().enumerate() {
+            let private_key = &recipient.key_pair.private_key;
+            if self.public_key(recipient.identifier) != Some(recipient.public_key())
+                || private_key.is_zero()
+                || !in_subgroup(private_key)
+            {
+                failed.push(position);
+            } else {
+                candidates.push(position);
+            }
+        }
+
+        let weights: Vec<E::Fr> = (0..recipients.len()).map(|_| E::Fr::rand(rng)).collect();
+        self.isolate_failures(recipients, &weights, &candidates, &mut failed);
+
+        failed.sort_unstable();
+        failed
+            .into_iter()
+            .map(|position| recipients[position].identifier)
+            .collect()
+    }
+
+    /// Add to `failed` the positions in `batch` whose keys fail, splitting `batch` until they are
+    /// isolated.
+    fn isolate_failures(
+        &self,
+        recipients: &[Recipient<E>],
+        weights: &[E::Fr],
+        batch: &[usize],
+        failed: &mut Vec<usize>,
+    ) {
+        if batch.is_empty() || self.batch_holds(recipients, weights, batch) {
+            return;
+        }
+        if batch.len() == 1 {
+            failed.push(batch[0]);
+            return;
+        }
+        let (left, right) = batch.split_at(batch.len() / 2);
+        self.isolate_failures(recipients, weights, left, failed);
+        self.isolate_failures(recipients, weights, right, failed);
+    }
+
+    /// Whether `e(sum r_i d_i, Q) e(-V, sum r_i Q_i) = 1` over the positions in `batch`.
+    fn batch_holds(&self, recipients: &[Recipient<E>], weights: &[E::Fr], batch: &[usize]) -> bool {
+        let mut private_sum = E::G1Projective::zero();
+        let mut public_sum = E::G2Projective::zero();
+        for position in batch.iter() {
+            let key_pair = &recipients[*position].key_pair;
+            let mut private_key = key_pair.private_key;
+            private_key *= weights[*position];
+            private_sum += private_key;
+            let mut public_key = key_pair.public_key;
+            public_key *= weights[*position];
+            public_sum += public_key;
+        }
+
+        let pairs = [
+            (
+                private_sum.into_affine().into(),
+                E::G2Projective::prime_subgroup_generator()
+                    .into_affine()
+                    .into(),
+            ),
+            (
+                (-self.point_v).into_affine().into(),
+                public_sum.into_affine().into(),
+            ),
+        ];
+        E::product_of_pairings(pairs.iter()).is_one()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn wrong_keys_are_isolated() {
+        let mut rng = thread_rng();
+        let (channel, mut participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(9, &mut rng).unwrap();
+        assert!(channel.verify_keys(&participants, &mut rng).is_empty());
+
+        participants[2].key_pair.private_key = participants[3].key_pair.private_key;
+        let shift = participants[0].key_pair.private_key;
+        participants[7].key_pair.private_key += shift;
+        participants[5].identifier = 7;
+        assert_eq!(channel.verify_keys(&participants, &mut rng), vec![3, 7, 8]);
+    }
+}
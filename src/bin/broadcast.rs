@@ -0,0 +1,97 @@
+//! Command-line tool running the scheme on BLS12-381 over files.
+//!
+//! Parameters, secrets, keys and headers are stored in their compressed encodings, and content
+//! in the chunked format of [`interview_exercise::chunked`]. Sets are lists of identifiers
+//! separated by commas, such as `1,4,7`.
+//!
+//! The secret written by `setup` derives every private key, and must be stored as carefully as
+//! the broadcaster would store its signing keys.
+//!
+//! Only built with the `cli` feature.
+
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::process;
+
+use ark_bls12_381::Bls12_381;
+use rand_core::OsRng;
+
+use interview_exercise::keystore::MemoryKeyStore;
+use interview_exercise::secret::BroadcasterSecret;
+use interview_exercise::{BroadcastChannel, Header, Recipient};
+
+const USAGE: &str = "usage:
+    broadcast setup <participants> <parameters> <secret>
+    broadcast issue-key <parameters> <secret> <identifier> <key>
+    broadcast encrypt <parameters> <set> <header> <input> <output>
+    broadcast decrypt <parameters> <key> <set> <header> <input> <output>";
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+fn main() {
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+    let arguments: Vec<&str> = arguments.iter().map(String::as_str).collect();
+    if let Err(error) = run(&arguments) {
+        eprintln!("broadcast: {}", error);
+        process::exit(1);
+    }
+}
+
+fn run(arguments: &[&str]) -> Result<()> {
+    match arguments {
+        ["setup", participants, parameters, secret] => {
+            let (channel, _, broadcaster) =
+                BroadcastChannel::<Bls12_381>::init_with_secret(participants.parse()?, &mut OsRng)?;
+            fs::write(parameters, channel.to_bytes())?;
+            fs::write(secret, broadcaster.to_bytes())?;
+        }
+        ["issue-key", parameters, secret, identifier, key] => {
+            let channel = read_parameters(parameters)?;
+            let mut store = MemoryKeyStore::new();
+            store
+                .set_broadcaster_secret(BroadcasterSecret::try_from(fs::read(secret)?.as_slice())?);
+            let recipient = channel.issue_key_from_store(&store, identifier.parse()?)?;
+            fs::write(key, recipient.to_bytes())?;
+        }
+        ["encrypt", parameters, set, header, input, output] => {
+            let channel = read_parameters(parameters)?;
+            let writer = BufWriter::new(File::create(output)?);
+            let (encrypted, mut stream) =
+                channel.encrypt_writer(&parse_set(set)?, writer, &mut OsRng)?;
+            io::copy(&mut BufReader::new(File::open(input)?), &mut stream)?;
+            stream.finish()?;
+            fs::write(header, encrypted.to_bytes())?;
+        }
+        ["decrypt", parameters, key, set, header, input, output] => {
+            let channel = read_parameters(parameters)?;
+            let recipient = Recipient::<Bls12_381>::try_from(fs::read(key)?.as_slice())?;
+            let header = Header::try_from(fs::read(header)?.as_slice())?;
+            let reader = BufReader::new(File::open(input)?);
+            let mut stream =
+                recipient.decrypt_reader(&parse_set(set)?, &channel, &header, reader)?;
+            let mut writer = BufWriter::new(File::create(output)?);
+            // content failing authentication is not left behind
+            if let Err(error) = io::copy(&mut stream, &mut writer) {
+                drop(writer);
+                fs::remove_file(output)?;
+                return Err(error.into());
+            }
+            writer.flush()?;
+        }
+        _ => return Err(USAGE.into()),
+    }
+    Ok(())
+}
+
+fn read_parameters(path: &str) -> Result<BroadcastChannel<Bls12_381>> {
+    Ok(BroadcastChannel::try_from(fs::read(path)?.as_slice())?)
+}
+
+fn parse_set(set: &str) -> Result<Vec<usize>> {
+    Ok(set
+        .split(',')
+        .map(|identifier| identifier.trim().parse())
+        .collect::<std::result::Result<_, _>>()?)
+}
@@ -0,0 +1,399 @@
+//! Sets of recipients as bitmaps.
+//!
+//! For audiences covering a sizeable part of a large channel, slices of identifiers are large
+//! and have to be sorted before anything can be looked up. A [`RecipientBitset`] holds one bit
+//! per identifier of the channel, so insertions, removals and membership tests take constant
+//! time, unions and complements work a word at a time, and an audience of a million members is
+//! encoded in 125 kB. [`BroadcastChannel::encrypt_bitset`] and [`Recipient::decrypt_bitset`]
+//! accept them in place of slices, checking membership with a single bit and summing the points
+//! as they read the bits, without collecting the identifiers.
+//!
+//! The encoding is the bitmap, identifier `i` being bit `(i - 1) % 8` of byte `(i - 1) / 8`,
+//! without trailing zero bytes, so that every set has a single encoding.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::iter::FromIterator;
+
+use ark_ec::{PairingEngine, ProjectiveCurve};
+use ark_ff::Zero;
+use ark_serialize::SerializationError;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::error::{DecryptError, EncryptError};
+use crate::events::{self, Event};
+use crate::keystore::key_of_header;
+use crate::session::EncryptionSession;
+use crate::set::RecipientSet;
+use crate::{BroadcastChannel, Header, Recipient, SharedKey};
+
+/// Set of recipient identifiers, one bit each.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct RecipientBitset {
+    /// bit `(i - 1) % 64` of word `(i - 1) / 64` is set if identifier `i` is in the set, without
+    /// trailing zero words
+    words: Vec<u64>,
+}
+
+impl RecipientBitset {
+    /// Empty set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `identifier`, returning whether it was absent. Identifier 0 is never a recipient and
+    /// is not added.
+    pub fn insert(&mut self, identifier: usize) -> bool {
+        if identifier == 0 {
+            return false;
+        }
+        let (word, bit) = ((identifier - 1) / 64, (identifier - 1) % 64);
+        if word >= self.words.len() {
+            self.words.resize(word + 1, 0);
+        }
+        let absent = self.words[word] & (1 << bit) == 0;
+        self.words[word] |= 1 << bit;
+        absent
+    }
+
+    /// Remove `identifier`, returning whether it was present.
+    pub fn remove(&mut self, identifier: usize) -> bool {
+        if !self.contains(identifier) {
+            return false;
+        }
+        let (word, bit) = ((identifier - 1) / 64, (identifier - 1) % 64);
+        self.words[word] &= !(1 << bit);
+        self.trim();
+        true
+    }
+
+    /// Whether `identifier` is in the set.
+    pub fn contains(&self, identifier: usize) -> bool {
+        if identifier == 0 {
+            return false;
+        }
+        let (word, bit) = ((identifier - 1) / 64, (identifier - 1) % 64);
+        self.words
+            .get(word)
+            .is_some_and(|word| word & (1 << bit) != 0)
+    }
+
+    /// Number of recipients.
+    pub fn len(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    /// Whether the set is empty.
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Identifiers of the set, in increasing order.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(index, word)| {
+            (0..64)
+                .filter(move |bit| word & (1 << bit) != 0)
+                .map(move |bit| 64 * index + bit + 1)
+        })
+    }
+
+    /// Recipients in this set or in `other`.
+    pub fn union(&self, other: &RecipientBitset) -> RecipientBitset {
+        let (long, short) = if self.words.len() >= other.words.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        let mut words = long.words.clone();
+        for (word, other) in words.iter_mut().zip(short.words.iter()) {
+            *word |= other;
+        }
+        RecipientBitset { words }
+    }
+
+    /// Identifiers of a channel with `capacity` participants that are not in this set.
+    pub fn complement(&self, capacity: usize) -> RecipientBitset {
+        let mut words: Vec<u64> = (0..(capacity + 63) / 64)
+            .map(|index| !self.words.get(index).copied().unwrap_or(0))
+            .collect();
+        if capacity % 64 != 0 {
+            if let Some(last) = words.last_mut() {
+                *last &= (1 << (capacity % 64)) - 1;
+            }
+        }
+        let mut complement = RecipientBitset { words };
+        complement.trim();
+        complement
+    }
+
+    /// Compact encoding of the set.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = self
+            .words
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .collect();
+        while bytes.last() == Some(&0) {
+            bytes.pop();
+        }
+        bytes
+    }
+
+    fn trim(&mut self) {
+        while self.words.last() == Some(&0) {
+            self.words.pop();
+        }
+    }
+}
+
+impl FromIterator<usize> for RecipientBitset {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = RecipientBitset::new();
+        for identifier in iter {
+            set.insert(identifier);
+        }
+        set
+    }
+}
+
+impl From<&RecipientSet> for RecipientBitset {
+    fn from(set: &RecipientSet) -> Self {
+        set.iter().collect()
+    }
+}
+
+impl From<&RecipientBitset> for RecipientSet {
+    fn from(set: &RecipientBitset) -> Self {
+        set.iter().collect()
+    }
+}
+
+impl TryFrom<&[u8]> for RecipientBitset {
+    type Error = SerializationError;
+
+    /// Decode the encoding of [`RecipientBitset::to_bytes`], refusing trailing zero bytes.
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        if bytes.last() == Some(&0) {
+            return Err(SerializationError::InvalidData);
+        }
+        let words = bytes
+            .chunks(8)
+            .map(|chunk| {
+                let mut word = [0u8; 8];
+                word[..chunk.len()].copy_from_slice(chunk);
+                u64::from_le_bytes(word)
+            })
+            .collect();
+        Ok(RecipientBitset { words })
+    }
+}
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Same as [`BroadcastChannel::encrypt`], for the recipients in `set`. The bits are summed
+    /// as they are read, without the aggregate cache.
+    pub fn encrypt_bitset<R>(
+        &self,
+        set: &RecipientBitset,
+        rng: &mut R,
+    ) -> Result<(Header<E>, SharedKey<E>), EncryptError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        enter_span!(INFO, "encrypt", set_size = set.len());
+        self.check_encryption_bitset(set)?;
+
+        let n = self.number_participants;
+        let mut aggregate = E::G1Projective::zero();
+        for identifier in set.iter() {
+            aggregate.add_assign_mixed(&self.broadcaster_pk_g1[n + 1 - identifier]);
+        }
+        let event = Event::Encrypted {
+            set_hash: events::sorted_set_hash(set.len(), set.iter()),
+        };
+        Ok(EncryptionSession::new(self, aggregate, event, None).encrypt(rng))
+    }
+
+    /// Check that `set` can be encrypted for, as [`BroadcastChannel::session`] checks slices. A
+    /// bitset has no repetitions, and the retired keys, which are few, are looked up in it.
+    fn check_encryption_bitset(&self, set: &RecipientBitset) -> Result<(), EncryptError> {
+        if let Some(index) = set.iter().find(|index| *index > self.number_participants) {
+            return Err(EncryptError::IdentifierOutOfRange(index));
+        }
+        if set.is_empty() {
+            return Err(EncryptError::EmptySet);
+        }
+        if let Some(identifier) = self.retired.iter().find(|retired| set.contains(**retired)) {
+            return Err(EncryptError::RetiredRecipient(*identifier));
+        }
+        self.policy.check_size(set.len())
+    }
+}
+
+impl<E: PairingEngine> Recipient<E> {
+    /// Same as [`Recipient::decrypt`], for a header encrypted for the recipients in `set`. The
+    /// bits are summed as they are read, without the aggregate cache.
+    pub fn decrypt_bitset(
+        &self,
+        set: &RecipientBitset,
+        channel: &BroadcastChannel<E>,
+        header: &Header<E>,
+    ) -> Result<SharedKey<E>, DecryptError> {
+        enter_span!(
+            INFO,
+            "decrypt",
+            identifier = self.identifier,
+            set_size = set.len()
+        );
+        let key = self
+            .check_bitset_decryption(set, channel, header)
+            .and_then(|()| {
+                let aggregate = in_span!(
+                    "decrypt.aggregate",
+                    channel.decryption_sum(set.iter(), self.identifier)
+                );
+                let key = in_span!(
+                    "decrypt.pairing",
+                    key_of_header(
+                        &self.key_pair.private_key,
+                        &self.key_pair.public_key,
+                        &aggregate,
+                        header,
+                    )
+                );
+                self.confirm_key(header, &key, || {
+                    channel.decryption_sum(set.iter().rev(), self.identifier)
+                })?;
+                Ok(SharedKey::new(key))
+            });
+        if let Err(error) = &key {
+            channel.emit(Event::DecryptFailed {
+                identifier: self.identifier,
+                reason: *error,
+            });
+        }
+        key
+    }
+
+    /// Check that the recipient can decrypt `header` for `set`, spending a use of its budget,
+    /// as [`Recipient::decrypt`] does for slices.
+    fn check_bitset_decryption(
+        &self,
+        set: &RecipientBitset,
+        channel: &BroadcastChannel<E>,
+        header: &Header<E>,
+    ) -> Result<(), DecryptError> {
+        if let Some(index) = set
+            .iter()
+            .find(|index| *index > channel.number_participants)
+        {
+            return Err(DecryptError::IdentifierOutOfRange(index));
+        }
+        if !set.contains(self.identifier) {
+            return Err(DecryptError::NotInSet);
+        }
+        if header.ctx_0.is_zero() || header.ctx_1.is_zero() {
+            return Err(DecryptError::IdentityPoint);
+        }
+        self.consume_use()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::Policy;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn bitsets_behave_as_sets() {
+        let mut set: RecipientBitset = vec![3, 70, 1].into_iter().collect();
+        assert!(set.insert(64));
+        assert!(!set.insert(3));
+        assert!(!set.insert(0));
+        assert!(set.remove(70));
+        assert!(!set.remove(70));
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![1, 3, 64]);
+        assert_eq!(set.len(), 3);
+
+        let other: RecipientBitset = vec![2, 3, 130].into_iter().collect();
+        assert_eq!(
+            set.union(&other).iter().collect::<Vec<_>>(),
+            vec![1, 2, 3, 64, 130]
+        );
+        assert_eq!(
+            other.complement(5).iter().collect::<Vec<_>>(),
+            vec![1, 4, 5]
+        );
+        assert!(RecipientBitset::new().complement(0).is_empty());
+
+        let bytes = set.to_bytes();
+        assert_eq!(bytes.len(), 8);
+        assert_eq!(RecipientBitset::try_from(bytes.as_slice()).unwrap(), set);
+        assert!(RecipientBitset::try_from(&[1u8, 0][..]).is_err());
+        assert_eq!(RecipientSet::from(&set), RecipientSet::new(&[64, 3, 1]));
+    }
+
+    #[test]
+    fn bitsets_encrypt_and_decrypt() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(6, &mut rng).unwrap();
+        let set: RecipientBitset = vec![2, 5].into_iter().collect();
+        let (header, key) = channel.encrypt_bitset(&set, &mut rng).unwrap();
+        assert_eq!(
+            participants[4].decrypt_bitset(&set, &channel, &header),
+            Ok(key.clone())
+        );
+        assert_eq!(
+            participants[0].decrypt_bitset(&set, &channel, &header),
+            Err(DecryptError::NotInSet)
+        );
+
+        assert_eq!(
+            participants[1].decrypt(&[5, 2], &channel, &header),
+            Ok(key.clone())
+        );
+        let (header, key) = channel.encrypt(&[5, 2], &mut rng).unwrap();
+        assert_eq!(
+            participants[1].decrypt_bitset(&set, &channel, &header),
+            Ok(key.clone())
+        );
+        assert_eq!(
+            events::sorted_set_hash(set.len(), set.iter()),
+            events::set_hash(&[5, 2, 5])
+        );
+
+        let everyone_else = set.complement(6);
+        assert!(channel.encrypt_bitset(&everyone_else, &mut rng).is_ok());
+        let outside: RecipientBitset = vec![2, 7].into_iter().collect();
+        assert!(matches!(
+            channel.encrypt_bitset(&outside, &mut rng),
+            Err(EncryptError::IdentifierOutOfRange(7))
+        ));
+        assert_eq!(
+            participants[1].decrypt_bitset(&outside, &channel, &header),
+            Err(DecryptError::IdentifierOutOfRange(7))
+        );
+        assert!(matches!(
+            channel.encrypt_bitset(&RecipientBitset::new(), &mut rng),
+            Err(EncryptError::EmptySet)
+        ));
+
+        // the bitsets are refused as the slices are
+        let mut channel = channel;
+        channel.retire(5).unwrap();
+        assert!(matches!(
+            channel.encrypt_bitset(&everyone_else.union(&set), &mut rng),
+            Err(EncryptError::RetiredRecipient(5))
+        ));
+        channel.set_policy(Policy::default().with_max_set_size(2));
+        assert!(matches!(
+            channel.encrypt_bitset(&everyone_else, &mut rng),
+            Err(EncryptError::SetTooLarge { size: 4, limit: 2 })
+        ));
+    }
+}
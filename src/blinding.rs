@@ -0,0 +1,107 @@
+//! Decryption with a blinded private key.
+//!
+//! A decryption pairs `d_i + A`, the private key plus the aggregate of the set, with `C_1`. On
+//! hardware open to power or electromagnetic analysis, an attacker recording many decryptions
+//! sees the same private key go through the same computations every time, and can average the
+//! traces until it is recovered. [`Recipient::decrypt_blinded`] multiplies the private key, the
+//! aggregate and `C_0` by a fresh scalar `r` before the pairings, which then give `K^r`, and
+//! removes the blinding in the target group by raising to `1/r`. The point actually paired
+//! differs in every decryption, and the key is the one of [`Recipient::decrypt`].
+//!
+//! The blinding costs three multiplications in G1 and an exponentiation in the target group
+//! on top of the decryption, about doubling its cost.
+
+use ark_ec::PairingEngine;
+use ark_ff::{Field, PrimeField, UniformRand};
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+use crate::error::DecryptError;
+use crate::keystore::key_of_header;
+use crate::{BroadcastChannel, Header, Recipient, SharedKey};
+
+impl<E: PairingEngine> Recipient<E> {
+    /// Same as [`Recipient::decrypt`], with the private key blinded by a scalar drawn from `rng`.
+    pub fn decrypt_blinded<R>(
+        &self,
+        set_recipients: &[usize],
+        channel: &BroadcastChannel<E>,
+        header: &Header<E>,
+        rng: &mut R,
+    ) -> Result<SharedKey<E>, DecryptError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let (mut blinding, mut unblinding) = loop {
+            let blinding = E::Fr::rand(rng);
+            if let Some(unblinding) = blinding.inverse() {
+                break (blinding, unblinding);
+            }
+        };
+
+        let key = self.decrypt_with_key(
+            set_recipients,
+            channel,
+            header,
+            || channel.decryption_aggregate(set_recipients, self.identifier),
+            |aggregate| {
+                let mut private_key = self.key_pair.private_key;
+                private_key *= blinding;
+                let mut blinded_aggregate = *aggregate;
+                blinded_aggregate *= blinding;
+                let mut blinded = *header;
+                blinded.ctx_0 *= blinding;
+                // e(r C_0, pk) / e(r d + r A, C_1) is K^r
+                let key = key_of_header(
+                    &private_key,
+                    &self.key_pair.public_key,
+                    &blinded_aggregate,
+                    &blinded,
+                );
+                private_key.zeroize();
+                key.pow(unblinding.into_repr())
+            },
+        );
+        blinding.zeroize();
+        unblinding.zeroize();
+        key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn blinded_decryption_gives_the_key() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(5, &mut rng).unwrap();
+        let (header, key) = channel.encrypt(&[1, 2, 5], &mut rng).unwrap();
+        for identifier in [1, 2, 5].iter() {
+            assert_eq!(
+                participants[identifier - 1].decrypt_blinded(
+                    &[1, 2, 5],
+                    &channel,
+                    &header,
+                    &mut rng
+                ),
+                Ok(key.clone())
+            );
+        }
+        assert_eq!(
+            participants[2].decrypt_blinded(&[1, 2, 5], &channel, &header, &mut rng),
+            Err(DecryptError::NotInSet)
+        );
+
+        let spent = participants[0].clone().with_usage_budget(1);
+        assert!(spent
+            .decrypt_blinded(&[1, 2, 5], &channel, &header, &mut rng)
+            .is_ok());
+        assert!(spent
+            .decrypt_blinded(&[1, 2, 5], &channel, &header, &mut rng)
+            .is_err());
+    }
+}
@@ -0,0 +1,117 @@
+//! Usage budgets of recipient keys.
+//!
+//! A recipient can be limited to a number of decryptions, for instance for a preview key that
+//! works ten times. The budget is state of the recipient, shared between its clones and spent by
+//! every decryption of the recipient that passes the checks, so it is only as strong as the
+//! software holding the key: the private key itself decrypts any number of headers.
+
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use ark_ec::PairingEngine;
+
+use crate::error::DecryptError;
+use crate::Recipient;
+
+impl<E: PairingEngine> Recipient<E> {
+    /// Limit the recipient to `uses` more decryptions, replacing any previous budget. Clones made
+    /// from now on share the budget.
+    ///
+    /// Every decryption method of [`Recipient`] spends a use, including
+    /// [`Recipient::decrypt_heapless`], [`Recipient::decrypt_fixed`] and
+    /// [`Recipient::decrypt_from_store`]. Key material taken out of the recipient is not limited:
+    /// a [`KeyBundle`](crate::keybundle::KeyBundle), the halves of
+    /// [`Recipient::split_in_two`] and keys moved into a key store decrypt without a budget.
+    pub fn with_usage_budget(mut self, uses: u64) -> Self {
+        self.budget = Some(Arc::new(AtomicU64::new(uses)));
+        self
+    }
+
+    /// Number of decryptions left, `None` if the recipient is not limited.
+    pub fn remaining_uses(&self) -> Option<u64> {
+        self.budget
+            .as_ref()
+            .map(|budget| budget.load(Ordering::SeqCst))
+    }
+
+    /// Spend one use of the budget, if there is one.
+    pub(crate) fn consume_use(&self) -> Result<(), DecryptError> {
+        match &self.budget {
+            Some(budget) => budget
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |uses| {
+                    uses.checked_sub(1)
+                })
+                .map(|_| ())
+                .map_err(|_| DecryptError::BudgetExhausted),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::{DecryptError, ParamStoreError};
+    use crate::heapless::{FixedBroadcastChannel, SrsSlice};
+    use crate::BroadcastChannel;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn budgets_are_enforced() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(3, &mut rng).unwrap();
+        let (header, key) = channel.encrypt(&[1, 2], &mut rng).unwrap();
+        let preview = participants[0].clone().with_usage_budget(2);
+        let copy = preview.clone();
+        assert_eq!(participants[0].remaining_uses(), None);
+
+        // refused decryptions are not counted
+        assert_eq!(
+            preview.decrypt(&[2, 3], &channel, &header),
+            Err(DecryptError::NotInSet)
+        );
+        assert_eq!(preview.decrypt(&[1, 2], &channel, &header), Ok(key.clone()));
+        assert_eq!(copy.decrypt(&[1, 2], &channel, &header), Ok(key.clone()));
+        assert_eq!(preview.remaining_uses(), Some(0));
+        assert_eq!(
+            preview.decrypt(&[1, 2], &channel, &header),
+            Err(DecryptError::BudgetExhausted)
+        );
+        assert_eq!(
+            participants[0].decrypt(&[1, 2], &channel, &header),
+            Ok(key.clone())
+        );
+    }
+
+    #[test]
+    fn every_decryption_spends_a_use() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(3, &mut rng).unwrap();
+        let (header, _) = channel.encrypt(&[1, 2], &mut rng).unwrap();
+        let members = [true, true, false];
+        let srs = SrsSlice::<Bls12_381, 3>::extract(&channel, 1).unwrap();
+        let fixed = FixedBroadcastChannel::<Bls12_381, 3>::extract(&channel).unwrap();
+
+        let preview = participants[0].clone().with_usage_budget(3);
+        assert!(preview.decrypt_heapless(&members, &srs, &header).is_ok());
+        assert!(preview.decrypt_fixed(&members, &fixed, &header).is_ok());
+        assert!(preview
+            .decrypt_from_store(&[1, 2], &channel, &header)
+            .is_ok());
+        assert_eq!(preview.remaining_uses(), Some(0));
+        assert_eq!(
+            preview.decrypt_heapless(&members, &srs, &header),
+            Err(DecryptError::BudgetExhausted)
+        );
+        assert_eq!(
+            preview.decrypt_fixed(&members, &fixed, &header),
+            Err(DecryptError::BudgetExhausted)
+        );
+        assert_eq!(
+            preview.decrypt_from_store(&[1, 2], &channel, &header),
+            Err(ParamStoreError::Decrypt(DecryptError::BudgetExhausted))
+        );
+    }
+}
@@ -0,0 +1,170 @@
+//! Distribution of public data through a bulletin board.
+//!
+//! Parameters and headers are public and only need to reach the participants intact. The
+//! [`Bulletin`] trait abstracts the substrate carrying them: entries are published under a topic,
+//! identified by the hash of their contents, fetched by identifier, and announced to subscribers
+//! of the topic. [`MemoryBulletin`] keeps everything in memory, for tests and single-process
+//! deployments; other substrates implement the trait outside of this crate.
+//!
+//! Only available with the `std` feature.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Mutex;
+
+use ark_ec::PairingEngine;
+use ark_serialize::SerializationError;
+use sha2::{Digest, Sha256};
+
+use crate::Header;
+
+/// Domain separation tag of the entry identifiers.
+const ENTRY_TAG: &[u8] = b"DGS-BE-BULLETIN-ENTRY-V1";
+
+/// Identifier of an entry: SHA-256 of its topic and contents.
+pub type EntryId = [u8; 32];
+
+/// Identifier of the entry with `contents` published under `topic`.
+pub fn entry_id(topic: &str, contents: &[u8]) -> EntryId {
+    let mut hasher = Sha256::new();
+    hasher.update(ENTRY_TAG);
+    hasher.update((topic.len() as u64).to_le_bytes());
+    hasher.update(topic.as_bytes());
+    hasher.update(contents);
+
+    let mut id = [0u8; 32];
+    id.copy_from_slice(&hasher.finalize());
+    id
+}
+
+/// Substrate publishing public data.
+pub trait Bulletin {
+    /// Error of the substrate
+    type Error;
+
+    /// Publish `contents` under `topic` and notify its subscribers. Publishing the same contents
+    /// twice under the same topic yields the same identifier.
+    fn publish(&self, topic: &str, contents: &[u8]) -> Result<EntryId, Self::Error>;
+
+    /// Contents of entry `id`, if it has been published.
+    fn fetch(&self, id: &EntryId) -> Result<Option<Vec<u8>>, Self::Error>;
+
+    /// Receiver of the identifiers of the entries published under `topic` from now on.
+    fn subscribe(&self, topic: &str) -> Result<Receiver<EntryId>, Self::Error>;
+}
+
+/// Publish `header` under `topic`.
+pub fn publish_header<E, B>(
+    bulletin: &B,
+    topic: &str,
+    header: &Header<E>,
+) -> Result<EntryId, B::Error>
+where
+    E: PairingEngine,
+    B: Bulletin,
+{
+    bulletin.publish(topic, &header.to_bytes())
+}
+
+/// Header published as entry `id`. Entries that do not decode as a header are reported as
+/// `Ok(Err(_))`, keeping errors of the substrate apart.
+pub fn fetch_header<E, B>(
+    bulletin: &B,
+    id: &EntryId,
+) -> Result<Option<Result<Header<E>, SerializationError>>, B::Error>
+where
+    E: PairingEngine,
+    B: Bulletin,
+{
+    Ok(bulletin
+        .fetch(id)?
+        .map(|contents| Header::try_from(contents.as_slice())))
+}
+
+#[derive(Default)]
+struct Board {
+    entries: HashMap<EntryId, Vec<u8>>,
+    subscribers: HashMap<String, Vec<Sender<EntryId>>>,
+}
+
+/// Bulletin board held in memory.
+#[derive(Default)]
+pub struct MemoryBulletin {
+    board: Mutex<Board>,
+}
+
+impl MemoryBulletin {
+    /// Empty board.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Bulletin for MemoryBulletin {
+    type Error = std::convert::Infallible;
+
+    fn publish(&self, topic: &str, contents: &[u8]) -> Result<EntryId, Self::Error> {
+        let id = entry_id(topic, contents);
+        let mut board = self.board.lock().unwrap();
+        board.entries.insert(id, contents.to_vec());
+        if let Some(subscribers) = board.subscribers.get_mut(topic) {
+            // dropped receivers are forgotten
+            subscribers.retain(|subscriber| subscriber.send(id).is_ok());
+        }
+        Ok(id)
+    }
+
+    fn fetch(&self, id: &EntryId) -> Result<Option<Vec<u8>>, Self::Error> {
+        Ok(self.board.lock().unwrap().entries.get(id).cloned())
+    }
+
+    fn subscribe(&self, topic: &str) -> Result<Receiver<EntryId>, Self::Error> {
+        let (sender, receiver) = channel();
+        self.board
+            .lock()
+            .unwrap()
+            .subscribers
+            .entry(topic.to_string())
+            .or_default()
+            .push(sender);
+        Ok(receiver)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BroadcastChannel;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn headers_go_through_bulletin() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(3, &mut rng).unwrap();
+        let bulletin = MemoryBulletin::new();
+        let headers = bulletin.subscribe("headers").unwrap();
+        let others = bulletin.subscribe("other").unwrap();
+
+        let (header, key) = channel.encrypt(&[1, 2], &mut rng).unwrap();
+        let id = publish_header(&bulletin, "headers", &header).unwrap();
+        assert_eq!(headers.try_recv(), Ok(id));
+        assert!(others.try_recv().is_err());
+
+        let fetched: Header<Bls12_381> = fetch_header(&bulletin, &id).unwrap().unwrap().unwrap();
+        assert_eq!(
+            participants[0].decrypt(&[1, 2], &channel, &fetched),
+            Ok(key)
+        );
+
+        let junk = bulletin.publish("headers", b"junk").unwrap();
+        assert!(fetch_header::<Bls12_381, _>(&bulletin, &junk)
+            .unwrap()
+            .unwrap()
+            .is_err());
+        assert!(bulletin.fetch(&[0u8; 32]).unwrap().is_none());
+        assert_ne!(entry_id("headers", b"junk"), entry_id("header", b"sjunk"));
+    }
+}
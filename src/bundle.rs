@@ -0,0 +1,237 @@
+//! Bundles of headers for several epochs.
+//!
+//! A recipient coming back online may have missed headers for several epochs, each for its own
+//! set. A [`HeaderBundle`] carries them in one envelope, under the fingerprint of the channel they
+//! were all encrypted with, and [`Recipient::decrypt_bundle`] recovers the keys of the entries the
+//! recipient belongs to in one pass, skipping the others. Epochs are labels chosen by the
+//! broadcaster.
+//!
+//! The encoding is the fingerprint, followed by the entries, each being the epoch as a `u64`, the
+//! identifiers of the set in increasing order and the header, in the ark-serialize encodings.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use ark_ec::PairingEngine;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use rand_core::{CryptoRng, RngCore};
+
+use crate::encoding::{from_bytes, to_bytes};
+use crate::error::{DecryptError, EncryptError};
+use crate::set::RecipientSet;
+use crate::view::Fingerprint;
+use crate::{BroadcastChannel, Header, Recipient, SharedKey};
+
+/// Key of an entry of a bundle, or the reason why it cannot be decrypted, with its epoch.
+pub type BundleKey<E> = (u64, Result<SharedKey<E>, DecryptError>);
+
+/// Header of a bundle, for one epoch and one set.
+#[derive(Clone, PartialEq, Eq)]
+pub struct BundleEntry<E: PairingEngine> {
+    epoch: u64,
+    set: RecipientSet,
+    header: Header<E>,
+}
+
+impl<E: PairingEngine> BundleEntry<E> {
+    /// Epoch of the entry.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Set the header was encrypted for.
+    pub fn set(&self) -> &RecipientSet {
+        &self.set
+    }
+
+    /// Header of the entry.
+    pub fn header(&self) -> &Header<E> {
+        &self.header
+    }
+}
+
+/// Headers of one channel for several epochs.
+#[derive(Clone, PartialEq, Eq)]
+pub struct HeaderBundle<E: PairingEngine> {
+    fingerprint: Fingerprint,
+    entries: Vec<BundleEntry<E>>,
+}
+
+impl<E: PairingEngine> HeaderBundle<E> {
+    /// Empty bundle for the channel with fingerprint `fingerprint`.
+    pub fn new(fingerprint: Fingerprint) -> Self {
+        HeaderBundle {
+            fingerprint,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Add `header`, encrypted for `set_recipients`, as the entry of `epoch`.
+    pub fn push(&mut self, epoch: u64, set_recipients: &[usize], header: Header<E>) {
+        self.entries.push(BundleEntry {
+            epoch,
+            set: RecipientSet::new(set_recipients),
+            header,
+        });
+    }
+
+    /// Fingerprint of the channel of the headers.
+    pub fn fingerprint(&self) -> Fingerprint {
+        self.fingerprint
+    }
+
+    /// Entries, in the order they were added.
+    pub fn entries(&self) -> &[BundleEntry<E>] {
+        &self.entries
+    }
+
+    /// Encoding of the bundle.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+}
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Encrypt for every `(epoch, set)` of `epochs`, returning the bundle of the headers and the
+    /// keys, in the same order.
+    pub fn encrypt_bundle<R>(
+        &self,
+        epochs: &[(u64, &[usize])],
+        rng: &mut R,
+    ) -> Result<(HeaderBundle<E>, Vec<SharedKey<E>>), EncryptError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let mut bundle = HeaderBundle::new(self.view().fingerprint());
+        let mut keys = Vec::with_capacity(epochs.len());
+        for (epoch, set_recipients) in epochs.iter() {
+            let (header, key) = self.encrypt(set_recipients, rng)?;
+            bundle.push(*epoch, set_recipients, header);
+            keys.push(key);
+        }
+        Ok((bundle, keys))
+    }
+}
+
+impl<E: PairingEngine> Recipient<E> {
+    /// Keys of the entries of `bundle` whose set contains this recipient, in the order of the
+    /// bundle. Returns `None` if the bundle belongs to another channel.
+    pub fn decrypt_bundle(
+        &self,
+        channel: &BroadcastChannel<E>,
+        bundle: &HeaderBundle<E>,
+    ) -> Option<Vec<BundleKey<E>>> {
+        if bundle.fingerprint != channel.view().fingerprint() {
+            return None;
+        }
+        Some(
+            bundle
+                .entries
+                .iter()
+                .filter(|entry| entry.set.contains(self.identifier))
+                .map(|entry| {
+                    let key = self.decrypt(entry.set.as_slice(), channel, &entry.header);
+                    (entry.epoch, key)
+                })
+                .collect(),
+        )
+    }
+}
+
+impl<E: PairingEngine> CanonicalSerialize for BundleEntry<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.epoch.serialize(&mut writer)?;
+        self.set.as_slice().to_vec().serialize(&mut writer)?;
+        self.header.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.epoch.serialized_size()
+            + self.set.as_slice().to_vec().serialized_size()
+            + self.header.serialized_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for BundleEntry<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let epoch = u64::deserialize(&mut reader)?;
+        let identifiers = Vec::<usize>::deserialize(&mut reader)?;
+        // sets are written in canonical order, anything else is not an encoded bundle
+        if !identifiers.windows(2).all(|pair| pair[0] < pair[1]) {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(BundleEntry {
+            epoch,
+            set: RecipientSet::new(&identifiers),
+            header: Header::deserialize(&mut reader)?,
+        })
+    }
+}
+
+impl<E: PairingEngine> CanonicalSerialize for HeaderBundle<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        writer.write_all(self.fingerprint.as_ref())?;
+        self.entries.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        32 + self.entries.serialized_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for HeaderBundle<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let mut fingerprint = [0u8; 32];
+        reader.read_exact(&mut fingerprint)?;
+        Ok(HeaderBundle {
+            fingerprint: Fingerprint::from(fingerprint),
+            entries: Vec::deserialize(&mut reader)?,
+        })
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for HeaderBundle<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn bundles_are_decrypted_selectively() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(4, &mut rng).unwrap();
+        let epochs: [(u64, &[usize]); 3] = [(7, &[1, 2]), (8, &[3]), (9, &[2, 4])];
+        let (bundle, keys) = channel.encrypt_bundle(&epochs, &mut rng).unwrap();
+
+        let decoded = HeaderBundle::try_from(bundle.to_bytes().as_slice()).unwrap();
+        assert!(decoded == bundle);
+        assert_eq!(decoded.entries()[2].set().as_slice(), &[2, 4]);
+
+        let recovered = participants[1].decrypt_bundle(&channel, &decoded).unwrap();
+        assert_eq!(
+            recovered,
+            vec![(7, Ok(keys[0].clone())), (9, Ok(keys[2].clone()))]
+        );
+        assert!(participants[0]
+            .decrypt_bundle(&channel, &decoded)
+            .unwrap()
+            .iter()
+            .all(|(epoch, _)| *epoch == 7));
+
+        let (other, _) = BroadcastChannel::<Bls12_381>::init_participants(4, &mut rng).unwrap();
+        assert!(participants[1].decrypt_bundle(&other, &decoded).is_none());
+        assert!(matches!(
+            channel.encrypt_bundle(&[(1, &[5])], &mut rng),
+            Err(EncryptError::IdentifierOutOfRange(5))
+        ));
+    }
+}
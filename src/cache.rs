@@ -0,0 +1,183 @@
+//! Shared cache of aggregated SRS points.
+//!
+//! Encrypting for a set sums one SRS point per recipient, and so does every decryption. Services
+//! handling a few hot audiences recompute the same large sums over and over. An
+//! [`AggregateCache`] registered on a channel keeps the most recently used sums, keyed by the
+//! fingerprint of the channel, the hash of the set and the identifier decrypting (zero for the
+//! encryption). One cache can be shared by every channel of a process.
+//!
+//! Only available with the `std` feature, as the cache is behind a lock.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+use ark_ec::PairingEngine;
+
+use crate::events::set_hash;
+use crate::view::Fingerprint;
+use crate::BroadcastChannel;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct Key {
+    fingerprint: Fingerprint,
+    set_hash: [u8; 32],
+    identifier: usize,
+}
+
+struct Entries<E: PairingEngine> {
+    points: HashMap<Key, (E::G1Projective, u64)>,
+    /// keys by time of last use, oldest first
+    recency: BTreeMap<u64, Key>,
+    clock: u64,
+}
+
+/// Bounded cache of aggregated SRS points, evicting the least recently used.
+pub struct AggregateCache<E: PairingEngine> {
+    capacity: usize,
+    entries: Mutex<Entries<E>>,
+}
+
+impl<E: PairingEngine> AggregateCache<E> {
+    /// Empty cache holding at most `capacity` points.
+    pub fn new(capacity: usize) -> Self {
+        AggregateCache {
+            capacity,
+            entries: Mutex::new(Entries {
+                points: HashMap::new(),
+                recency: BTreeMap::new(),
+                clock: 0,
+            }),
+        }
+    }
+
+    /// Number of points held.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().points.len()
+    }
+
+    /// Whether the cache holds no point.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Forget every point.
+    pub fn clear(&self) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.points.clear();
+        entries.recency.clear();
+    }
+
+    fn get_or_insert_with<F>(&self, key: Key, compute: F) -> E::G1Projective
+    where
+        F: FnOnce() -> E::G1Projective,
+    {
+        let mut entries = self.entries.lock().unwrap();
+        entries.clock += 1;
+        let now = entries.clock;
+
+        if let Some((point, last_use)) = entries.points.get_mut(&key) {
+            let point = *point;
+            let previous = std::mem::replace(last_use, now);
+            entries.recency.remove(&previous);
+            entries.recency.insert(now, key);
+            return point;
+        }
+
+        // computed under the lock, so that concurrent users of a hot set sum it only once
+        let point = compute();
+        if self.capacity == 0 {
+            return point;
+        }
+        if entries.points.len() == self.capacity {
+            // the cache is full, so the recency map is not empty
+            let oldest = *entries.recency.keys().next().unwrap();
+            let key = entries.recency.remove(&oldest).unwrap();
+            entries.points.remove(&key);
+        }
+        entries.points.insert(key, (point, now));
+        entries.recency.insert(now, key);
+        point
+    }
+}
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Look up aggregated points in `cache` when encrypting and decrypting with this channel.
+    pub fn set_aggregate_cache(&mut self, cache: Arc<AggregateCache<E>>) {
+        // the points of the channel never change, so neither does the part of the key they give
+        let fingerprint = self.view().fingerprint();
+        self.aggregate_cache = Some((cache, fingerprint));
+    }
+
+    /// Stop using the cache.
+    pub fn clear_aggregate_cache(&mut self) {
+        self.aggregate_cache = None;
+    }
+
+    /// Sum of the points for `set_recipients` as seen by `identifier`, zero for the encryption,
+    /// taken from the cache if there is one. The set must be free of repetitions, which the hash
+    /// does not account for.
+    pub(crate) fn aggregate<F>(
+        &self,
+        set_recipients: &[usize],
+        identifier: usize,
+        compute: F,
+    ) -> E::G1Projective
+    where
+        F: FnOnce() -> E::G1Projective,
+    {
+        match &self.aggregate_cache {
+            Some((cache, fingerprint)) => {
+                let key = Key {
+                    fingerprint: *fingerprint,
+                    set_hash: set_hash(set_recipients),
+                    identifier,
+                };
+                cache.get_or_insert_with(key, compute)
+            }
+            _ => compute(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn cached_aggregates_give_same_keys() {
+        let mut rng = thread_rng();
+        let (mut channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(6, &mut rng).unwrap();
+        let cache = Arc::new(AggregateCache::new(3));
+        channel.set_aggregate_cache(cache.clone());
+
+        let recipients = [4, 1, 6];
+        for _ in 0..2 {
+            let (header, key) = channel.encrypt(&recipients, &mut rng).unwrap();
+            assert_eq!(
+                participants[0].decrypt(&[1, 4, 6], &channel, &header),
+                Ok(key)
+            );
+        }
+        assert_eq!(cache.len(), 2);
+
+        // the encryption was used last, so the decryption of participant 1 is evicted first
+        channel.encrypt(&recipients, &mut rng).unwrap();
+        let (header, key) = channel.encrypt(&[2, 3], &mut rng).unwrap();
+        assert_eq!(participants[2].decrypt(&[2, 3], &channel, &header), Ok(key));
+        assert_eq!(cache.len(), 3);
+
+        let (header, key) = channel.encrypt(&recipients, &mut rng).unwrap();
+        let mut uncached = channel.clone();
+        uncached.clear_aggregate_cache();
+        assert_eq!(
+            participants[5].decrypt(&recipients, &uncached, &header),
+            Ok(key)
+        );
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,183 @@
+//! Opt-in mode refusing modified headers.
+//!
+//! The scheme itself is only secure against chosen plaintexts. Keys recovered by
+//! [`Recipient::decrypt`] are only as good as the header they come from: a header that was
+//! modified on the way decrypts to an unrelated key, which nothing flags. In this mode the key of
+//! the header goes through [`HkdfSha256`] together with the encoding of the header and the
+//! canonical encoding of the set, and the derivation yields both the symmetric key and a 32-byte
+//! key confirmation tag sent along with the header in a [`CcaHeader`]. [`Recipient::decrypt_cca`]
+//! checks the header for subgroup membership, recomputes the tag and refuses the header if it
+//! differs, so a header modified in any way, or presented for another set, yields no key at all.
+//!
+//! The tag is not a signature: whoever encrypts for a set can produce valid headers for it. The
+//! transform has no proof of security against chosen ciphertexts either, it only rules out the
+//! malleability of the headers.
+//!
+//! A header of this mode is encoded as the header followed by the tag.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use ark_ec::PairingEngine;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+use crate::encoding::{from_bytes, to_bytes};
+use crate::error::{CcaError, EncryptError};
+use crate::kdf::{derive_key, HkdfSha256};
+use crate::set::RecipientSet;
+use crate::{BroadcastChannel, Header, Recipient};
+
+/// Info of the derivation of the key and the tag.
+const CCA_INFO: &[u8] = b"DGS-BE-CCA-KEY";
+
+/// Header with the tag confirming its key.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct CcaHeader<E: PairingEngine> {
+    header: Header<E>,
+    tag: [u8; 32],
+}
+
+impl<E: PairingEngine> CcaHeader<E> {
+    /// Header whose key is confirmed.
+    pub fn header(&self) -> &Header<E> {
+        &self.header
+    }
+
+    /// Compressed encoding of the header and its tag.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+}
+
+/// Symmetric key and tag of `header`, encrypted for `set_recipients` with header key `key`.
+fn derive_cca<E: PairingEngine>(
+    key: &E::Fqk,
+    header: &Header<E>,
+    set_recipients: &[usize],
+) -> ([u8; 32], [u8; 32]) {
+    let mut info = CCA_INFO.to_vec();
+    info.extend_from_slice(&header.to_bytes());
+    info.extend_from_slice(&RecipientSet::new(set_recipients).canonical_bytes());
+
+    let mut bytes = [0u8; 64];
+    // 64 bytes are within the output of HKDF
+    derive_key::<E, HkdfSha256>(key, &info, &mut bytes).unwrap();
+    let mut symmetric_key = [0u8; 32];
+    let mut tag = [0u8; 32];
+    symmetric_key.copy_from_slice(&bytes[..32]);
+    tag.copy_from_slice(&bytes[32..]);
+    bytes.zeroize();
+    (symmetric_key, tag)
+}
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Same as [`BroadcastChannel::encrypt`], returning the header with its tag and the
+    /// symmetric key derived from it.
+    pub fn encrypt_cca<R>(
+        &self,
+        set_recipients: &[usize],
+        rng: &mut R,
+    ) -> Result<(CcaHeader<E>, [u8; 32]), EncryptError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let (header, key) = self.encrypt(set_recipients, rng)?;
+        let (symmetric_key, tag) = derive_cca::<E>(key.key(), &header, set_recipients);
+        Ok((CcaHeader { header, tag }, symmetric_key))
+    }
+}
+
+impl<E: PairingEngine> Recipient<E> {
+    /// Symmetric key of `confirmed`, encrypted for set `set_recipients`. Fails if the header is
+    /// not in the subgroup, cannot be decrypted, or if its tag does not match.
+    pub fn decrypt_cca(
+        &self,
+        set_recipients: &[usize],
+        channel: &BroadcastChannel<E>,
+        confirmed: &CcaHeader<E>,
+    ) -> Result<[u8; 32], CcaError> {
+        let header = &confirmed.header;
+        header.check().map_err(CcaError::Decrypt)?;
+        let key = self
+            .decrypt(set_recipients, channel, header)
+            .map_err(CcaError::Decrypt)?;
+
+        let (mut symmetric_key, mut tag) = derive_cca::<E>(key.key(), header, set_recipients);
+        // compared in full, so the time taken does not tell how much of the tag is right
+        let difference = tag
+            .iter()
+            .zip(confirmed.tag.iter())
+            .fold(0u8, |difference, (left, right)| difference | (left ^ right));
+        tag.zeroize();
+        if difference != 0 {
+            symmetric_key.zeroize();
+            return Err(CcaError::TagMismatch);
+        }
+        Ok(symmetric_key)
+    }
+}
+
+impl<E: PairingEngine> CanonicalSerialize for CcaHeader<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.header.serialize(&mut writer)?;
+        writer.write_all(&self.tag)?;
+        Ok(())
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.header.serialized_size() + self.tag.len()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for CcaHeader<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let header = Header::deserialize(&mut reader)?;
+        let mut tag = [0u8; 32];
+        reader.read_exact(&mut tag)?;
+        Ok(CcaHeader { header, tag })
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for CcaHeader<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn modified_headers_are_refused() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(4, &mut rng).unwrap();
+        let (confirmed, key) = channel.encrypt_cca(&[1, 3], &mut rng).unwrap();
+        let received = CcaHeader::try_from(confirmed.to_bytes().as_slice()).unwrap();
+        assert_eq!(
+            participants[2].decrypt_cca(&[3, 1], &channel, &received),
+            Ok(key)
+        );
+
+        let (other, _) = channel.encrypt(&[1, 3], &mut rng).unwrap();
+        let mauled = CcaHeader {
+            header: other,
+            tag: confirmed.tag,
+        };
+        assert_eq!(
+            participants[0].decrypt_cca(&[1, 3], &channel, &mauled),
+            Err(CcaError::TagMismatch)
+        );
+        assert_eq!(
+            participants[0].decrypt_cca(&[1, 2, 3], &channel, &confirmed),
+            Err(CcaError::TagMismatch)
+        );
+    }
+}
@@ -0,0 +1,268 @@
+//! Setup ceremony between any number of contributors.
+//!
+//! [`crate::split`] shares alpha between dealers that follow the protocol. In a ceremony the
+//! contributors need not trust each other: each one takes the latest [`SrsTranscript`], multiplies
+//! its secret by a fresh share `s` in a [`SetupContribution`], and publishes the new transcript
+//! with `sP` and `sQ` and a proof of knowledge of `s`, namely `s H(previous, sP)` with the hash to
+//! G1 of [`crate::hash`]. Anyone can then check with [`verify_ceremony`] that every contribution
+//! builds on the previous one and that the final transcript holds consecutive powers of a single
+//! alpha, which nobody knows as long as one contributor forgot its share.
+//!
+//! The point `V` and the private keys depend on gamma, which the [`crate::split::KeyDealer`]s
+//! share additively over the final transcript. One honest dealer keeps gamma secret, and
+//! [`crate::Recipient::combine`] refuses keys that do not match the combined `V`, so a dealer
+//! choosing its share of `V` after seeing the others cannot issue working keys.
+//!
+//! Transcripts and contributions are encoded with their points compressed, the transcript as the
+//! number of participants followed by the G1 and G2 vectors.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use ark_ec::{PairingEngine, ProjectiveCurve};
+use ark_ff::{UniformRand, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+use crate::encoding::{from_bytes, to_bytes};
+use crate::error::CeremonyError;
+use crate::hash::hash_to_g1;
+use crate::split::SrsTranscript;
+use crate::validate::{check_powers, in_subgroup, ValidationReport};
+
+/// Domain of the hashes in the proofs of knowledge.
+const CONTRIBUTION_DOMAIN: &[u8] = b"DGS-BE-SETUP-CONTRIBUTION-V1";
+
+/// Transcript after one contribution, with the evidence that it extends the previous one.
+#[derive(Clone)]
+pub struct SetupContribution<E: PairingEngine> {
+    transcript: SrsTranscript<E>,
+    /// `sP`
+    g1_share: E::G1Projective,
+    /// `sQ`
+    g2_share: E::G2Projective,
+    /// `s H(previous, sP)`
+    proof: E::G1Projective,
+}
+
+/// Point the share of a contribution over `previous` is proven on.
+fn proof_base<E: PairingEngine>(
+    previous: &SrsTranscript<E>,
+    g1_share: &E::G1Projective,
+) -> E::G1Projective {
+    let mut message = to_bytes(&previous.g1_points[1]);
+    message.extend_from_slice(&to_bytes(g1_share));
+    hash_to_g1::<E>(CONTRIBUTION_DOMAIN, &message)
+}
+
+impl<E: PairingEngine> SetupContribution<E> {
+    /// Contribute a fresh share to `previous`. The share is forgotten afterwards.
+    pub fn new<R: RngCore + CryptoRng>(previous: &SrsTranscript<E>, rng: &mut R) -> Self {
+        let mut share = E::Fr::rand(rng);
+
+        let mut transcript = previous.clone();
+        transcript.update_with(share);
+        let mut g1_share = E::G1Projective::prime_subgroup_generator();
+        g1_share *= share;
+        let mut g2_share = E::G2Projective::prime_subgroup_generator();
+        g2_share *= share;
+        let mut proof = proof_base(previous, &g1_share);
+        proof *= share;
+        share.zeroize();
+
+        SetupContribution {
+            transcript,
+            g1_share,
+            g2_share,
+            proof,
+        }
+    }
+
+    /// Transcript after this contribution.
+    pub fn transcript(&self) -> &SrsTranscript<E> {
+        &self.transcript
+    }
+
+    /// Whether this contribution multiplies the secret of `previous` by a share its contributor
+    /// knows, and leaves a well-formed transcript.
+    pub fn verify(&self, previous: &SrsTranscript<E>) -> bool {
+        let generator_p = E::G1Projective::prime_subgroup_generator();
+        let generator_q = E::G2Projective::prime_subgroup_generator();
+        if self.transcript.number_participants != previous.number_participants
+            || self.g2_share.is_zero()
+            || !in_subgroup(&self.g2_share)
+        {
+            return false;
+        }
+
+        // sP and sQ have the same discrete logarithm, the contributor knows it, and the first
+        // power of the new transcript is the previous one multiplied by it
+        E::pairing(self.g1_share, generator_q) == E::pairing(generator_p, self.g2_share)
+            && E::pairing(self.proof, generator_q)
+                == E::pairing(proof_base(previous, &self.g1_share), self.g2_share)
+            && E::pairing(self.transcript.g1_points[1], generator_q)
+                == E::pairing(previous.g1_points[1], self.g2_share)
+            && self.transcript.validate().is_valid()
+    }
+
+    /// Compressed encoding of the contribution.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+}
+
+/// Final transcript of the ceremony for `n` participants made of `contributions`, in order. Fails
+/// if there is none, or at the first one that does not verify against the transcript before it.
+pub fn verify_ceremony<E: PairingEngine>(
+    n: usize,
+    contributions: &[SetupContribution<E>],
+) -> Result<SrsTranscript<E>, CeremonyError> {
+    if contributions.is_empty() {
+        return Err(CeremonyError::NoContribution);
+    }
+    let mut previous = SrsTranscript::initial(n);
+    for (position, contribution) in contributions.iter().enumerate() {
+        if !contribution.verify(&previous) {
+            return Err(CeremonyError::InvalidContribution(position));
+        }
+        previous = contribution.transcript.clone();
+    }
+    Ok(previous)
+}
+
+impl<E: PairingEngine> SrsTranscript<E> {
+    /// Check that the transcript holds consecutive powers of a single secret, as
+    /// [`crate::BroadcastChannel::validate`] does for the parameters of a channel.
+    pub fn validate(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        check_powers::<E>(
+            self.number_participants,
+            &E::G1Projective::batch_normalization_into_affine(&self.g1_points),
+            &E::G2Projective::batch_normalization_into_affine(&self.g2_points),
+            &mut report,
+        );
+        report
+    }
+
+    /// Compressed encoding of the transcript.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+}
+
+impl<E: PairingEngine> CanonicalSerialize for SrsTranscript<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.number_participants.serialize(&mut writer)?;
+        self.g1_points.serialize(&mut writer)?;
+        self.g2_points.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.number_participants.serialized_size()
+            + self.g1_points.serialized_size()
+            + self.g2_points.serialized_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for SrsTranscript<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let number_participants = usize::deserialize(&mut reader)?;
+        let g1_points = Vec::<E::G1Projective>::deserialize(&mut reader)?;
+        let g2_points = Vec::<E::G2Projective>::deserialize(&mut reader)?;
+        // every index computed from the identifiers relies on these lengths
+        if g1_points.len() != 2 * number_participants + 1
+            || g2_points.len() != number_participants + 1
+        {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(SrsTranscript {
+            number_participants,
+            g1_points,
+            g2_points,
+        })
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for SrsTranscript<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+impl<E: PairingEngine> CanonicalSerialize for SetupContribution<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.transcript.serialize(&mut writer)?;
+        self.g1_share.serialize(&mut writer)?;
+        self.g2_share.serialize(&mut writer)?;
+        self.proof.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.transcript.serialized_size()
+            + self.g1_share.serialized_size()
+            + self.g2_share.serialized_size()
+            + self.proof.serialized_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for SetupContribution<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Ok(SetupContribution {
+            transcript: SrsTranscript::deserialize(&mut reader)?,
+            g1_share: E::G1Projective::deserialize(&mut reader)?,
+            g2_share: E::G2Projective::deserialize(&mut reader)?,
+            proof: E::G1Projective::deserialize(&mut reader)?,
+        })
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for SetupContribution<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::split::KeyDealer;
+    use crate::{BroadcastChannel, Recipient};
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn ceremonies_verify() {
+        let mut rng = thread_rng();
+        let initial = SrsTranscript::<Bls12_381>::initial(3);
+        let first = SetupContribution::new(&initial, &mut rng);
+        let second = SetupContribution::new(first.transcript(), &mut rng);
+        let received = SetupContribution::try_from(second.to_bytes().as_slice()).unwrap();
+        let contributions = vec![first.clone(), received];
+
+        let srs = verify_ceremony(3, &contributions).unwrap();
+        assert!(verify_ceremony::<Bls12_381>(3, &[]).is_err());
+        assert_eq!(
+            verify_ceremony(3, &[second.clone(), first.clone()]).err(),
+            Some(CeremonyError::InvalidContribution(0))
+        );
+        let mut forged = second;
+        forged.proof = first.proof;
+        assert_eq!(
+            verify_ceremony(3, &[first, forged]).err(),
+            Some(CeremonyError::InvalidContribution(1))
+        );
+
+        let dealer = KeyDealer::<Bls12_381>::new(&mut rng);
+        let share = dealer.issue(&srs, 2).unwrap();
+        let channel = BroadcastChannel::from_split(srs, &[dealer.point_v()]);
+        assert!(channel.validate().is_valid());
+        let recipient = Recipient::combine(&channel, 2, &[share]).unwrap();
+        let (header, key) = channel.encrypt(&[1, 2], &mut rng).unwrap();
+        assert_eq!(recipient.decrypt(&[1, 2], &channel, &header), Ok(key));
+    }
+}
@@ -0,0 +1,381 @@
+//! Encryption of large payloads in authenticated chunks.
+//!
+//! [`crate::message`] seals a payload in one piece, which must then fit in memory on both ends.
+//! An [`EncryptStream`] wraps a [`Write`] and seals what is written to it in chunks of
+//! [`CHUNK_SIZE`] bytes with ChaCha20-Poly1305, and a [`DecryptStream`] wraps a [`Read`] and
+//! opens them as they are read, so multi-gigabyte content is encrypted for a set of recipients
+//! with a constant amount of memory.
+//!
+//! Chunks are sealed as in the STREAM construction of Hoang, Reyhanitabar, Rogaway and Vizár:
+//! the nonce of a chunk is its position, as a big-endian `u64`, followed by a byte set on the last
+//! chunk only. Chunks cannot be reordered, dropped or replaced, and a stream cut short at a chunk
+//! boundary is told apart from a complete one: its last chunk was not sealed as such. The stream
+//! starts with a random 16-byte salt, so a key of a header used for several streams still seals
+//! each under its own key, derived from the key of the header and the salt with [`HkdfSha256`].
+//!
+//! The stream is only complete once [`EncryptStream::finish`] seals the last chunk. Errors of the
+//! decryption are reported as [`std::io::Error`]s of kind [`std::io::ErrorKind::InvalidData`]
+//! wrapping a [`ChunkError`]. Bytes read before an error were authenticated, but the content is
+//! only known to be complete once the stream reaches its end.
+//!
+//! Only available with the `chacha20poly1305` and `std` features.
+
+use std::io::{self, Read, Write};
+
+use ark_ec::PairingEngine;
+use chacha20poly1305::aead::{Aead, NewAead};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+use crate::error::{ChunkError, DecryptError, EncryptError};
+use crate::kdf::{derive_key, HkdfSha256, KeyDerivation};
+use crate::{BroadcastChannel, Header, Recipient};
+
+/// Info of the derivation of the stream key from the key of a header.
+const STREAM_KEY_INFO: &[u8] = b"DGS-BE-CHUNKED-CHACHA20POLY1305-V1";
+
+/// Bytes of plaintext per chunk. Sealed chunks are 16 bytes longer.
+pub const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Bytes of a sealed chunk, other than the last one.
+const SEALED_CHUNK_SIZE: usize = CHUNK_SIZE + 16;
+
+/// Bytes of the salt starting a stream.
+const SALT_SIZE: usize = 16;
+
+/// Root of the keys of the streams under the header key `key`.
+fn stream_root<E: PairingEngine>(key: &E::Fqk) -> [u8; 32] {
+    let mut root = [0u8; 32];
+    // 32 bytes are within the output of HKDF
+    derive_key::<E, HkdfSha256>(key, STREAM_KEY_INFO, &mut root).unwrap();
+    root
+}
+
+/// Cipher of the stream with `salt`, whose root is erased.
+fn stream_cipher(root: &mut [u8; 32], salt: &[u8; SALT_SIZE]) -> ChaCha20Poly1305 {
+    let mut key = [0u8; 32];
+    // 32 bytes are within the output of HKDF
+    HkdfSha256::derive(root, salt, &mut key).unwrap();
+    root.zeroize();
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(&key));
+    key.zeroize();
+    cipher
+}
+
+/// Nonce of chunk `counter`.
+fn chunk_nonce(counter: u64, last: bool) -> [u8; 12] {
+    let mut nonce = [0u8; 12];
+    nonce[..8].copy_from_slice(&counter.to_be_bytes());
+    nonce[11] = last as u8;
+    nonce
+}
+
+fn invalid_data(error: ChunkError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+/// Writer sealing what is written to it in chunks, see the [module documentation](self).
+pub struct EncryptStream<W: Write> {
+    writer: W,
+    cipher: ChaCha20Poly1305,
+    /// salt, until it is written
+    salt: Option<[u8; SALT_SIZE]>,
+    /// plaintext of the chunk being filled
+    buffer: Vec<u8>,
+    counter: u64,
+}
+
+impl<W: Write> EncryptStream<W> {
+    /// Stream sealing into `writer` under the header key `key`, with a salt drawn from `rng`.
+    pub fn new<E, R>(key: &E::Fqk, writer: W, rng: &mut R) -> Self
+    where
+        E: PairingEngine,
+        R: RngCore + CryptoRng,
+    {
+        let mut salt = [0u8; SALT_SIZE];
+        rng.fill_bytes(&mut salt);
+        EncryptStream {
+            writer,
+            cipher: stream_cipher(&mut stream_root::<E>(key), &salt),
+            salt: Some(salt),
+            buffer: Vec::with_capacity(CHUNK_SIZE),
+            counter: 0,
+        }
+    }
+
+    /// Seal the last chunk, and return the writer. Without it, the stream is refused as truncated.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.seal_chunk(true)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn seal_chunk(&mut self, last: bool) -> io::Result<()> {
+        if let Some(salt) = self.salt {
+            self.writer.write_all(&salt)?;
+            self.salt = None;
+        }
+        // a stream never reaches 2^64 chunks of 64 KiB
+        let sealed = self
+            .cipher
+            .encrypt(
+                Nonce::from_slice(&chunk_nonce(self.counter, last)),
+                self.buffer.as_slice(),
+            )
+            .expect("chunk too long for ChaCha20-Poly1305");
+        self.writer.write_all(&sealed)?;
+        self.buffer.clear();
+        self.counter += 1;
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for EncryptStream<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        // a full chunk is only sealed once more data shows it is not the last one
+        if self.buffer.len() == CHUNK_SIZE && !data.is_empty() {
+            self.seal_chunk(false)?;
+        }
+        let taken = data.len().min(CHUNK_SIZE - self.buffer.len());
+        self.buffer.extend_from_slice(&data[..taken]);
+        Ok(taken)
+    }
+
+    /// Flush the writer. The chunk being filled is only written once it is full or the stream
+    /// finished.
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Reader opening the chunks of a stream, see the [module documentation](self).
+pub struct DecryptStream<R: Read> {
+    reader: R,
+    /// root of the key, until the salt is read
+    root: [u8; 32],
+    cipher: Option<ChaCha20Poly1305>,
+    /// sealed bytes read ahead of the current chunk
+    pending: Vec<u8>,
+    /// plaintext of the current chunk, and the bytes of it already read
+    plaintext: Vec<u8>,
+    position: usize,
+    counter: u64,
+    finished: bool,
+    failed: Option<ChunkError>,
+}
+
+impl<R: Read> DecryptStream<R> {
+    /// Stream opening the chunks read from `reader` under the header key `key`.
+    pub fn new<E: PairingEngine>(key: &E::Fqk, reader: R) -> Self {
+        DecryptStream {
+            reader,
+            root: stream_root::<E>(key),
+            cipher: None,
+            pending: Vec::with_capacity(SEALED_CHUNK_SIZE + 1),
+            plaintext: Vec::new(),
+            position: 0,
+            counter: 0,
+            finished: false,
+            failed: None,
+        }
+    }
+
+    /// Fill the pending bytes up to `size`, or fewer at the end of the reader.
+    fn fill(&mut self, size: usize) -> io::Result<()> {
+        let start = self.pending.len();
+        self.pending.resize(size, 0);
+        let mut filled = start;
+        while filled < size {
+            match self.reader.read(&mut self.pending[filled..]) {
+                Ok(0) => break,
+                Ok(read) => filled += read,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) => {
+                    self.pending.truncate(filled);
+                    return Err(error);
+                }
+            }
+        }
+        self.pending.truncate(filled);
+        Ok(())
+    }
+
+    fn open_chunk(&mut self) -> Result<(), ChunkError> {
+        let cipher = self.cipher.as_ref().ok_or(ChunkError::Truncated)?;
+        // a chunk followed by more bytes is not the last one
+        let last = self.pending.len() <= SEALED_CHUNK_SIZE;
+        let end = self.pending.len().min(SEALED_CHUNK_SIZE);
+        let sealed = &self.pending[..end];
+        let opened = cipher.decrypt(Nonce::from_slice(&chunk_nonce(self.counter, last)), sealed);
+        self.plaintext = match opened {
+            Ok(plaintext) => plaintext,
+            Err(_) => {
+                let cut = last
+                    && cipher
+                        .decrypt(Nonce::from_slice(&chunk_nonce(self.counter, false)), sealed)
+                        .is_ok();
+                return Err(if cut {
+                    ChunkError::Truncated
+                } else {
+                    ChunkError::Authentication
+                });
+            }
+        };
+        self.pending.drain(..end);
+        self.position = 0;
+        self.counter += 1;
+        self.finished = last;
+        Ok(())
+    }
+
+    fn next_chunk(&mut self) -> io::Result<()> {
+        if self.cipher.is_none() {
+            self.fill(SALT_SIZE)?;
+            if self.pending.len() < SALT_SIZE {
+                return Err(invalid_data(ChunkError::Truncated));
+            }
+            let mut salt = [0u8; SALT_SIZE];
+            salt.copy_from_slice(&self.pending);
+            self.pending.clear();
+            self.cipher = Some(stream_cipher(&mut self.root, &salt));
+        }
+        self.fill(SEALED_CHUNK_SIZE + 1)?;
+        self.open_chunk().map_err(|error| {
+            self.failed = Some(error);
+            invalid_data(error)
+        })
+    }
+}
+
+impl<R: Read> Read for DecryptStream<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if let Some(error) = self.failed {
+                return Err(invalid_data(error));
+            }
+            if self.position < self.plaintext.len() {
+                let read = buf.len().min(self.plaintext.len() - self.position);
+                buf[..read].copy_from_slice(&self.plaintext[self.position..self.position + read]);
+                self.position += read;
+                return Ok(read);
+            }
+            if self.finished || buf.is_empty() {
+                return Ok(0);
+            }
+            self.next_chunk()?;
+        }
+    }
+}
+
+impl<R: Read> Drop for DecryptStream<R> {
+    fn drop(&mut self) {
+        self.root.zeroize();
+    }
+}
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Encrypt a header for `set_recipients`, and return it with a stream sealing into `writer`
+    /// under its key.
+    pub fn encrypt_writer<W, R>(
+        &self,
+        set_recipients: &[usize],
+        writer: W,
+        rng: &mut R,
+    ) -> Result<(Header<E>, EncryptStream<W>), EncryptError>
+    where
+        W: Write,
+        R: RngCore + CryptoRng,
+    {
+        let (header, key) = self.encrypt(set_recipients, rng)?;
+        Ok((header, EncryptStream::new::<E, R>(key.key(), writer, rng)))
+    }
+}
+
+impl<E: PairingEngine> Recipient<E> {
+    /// Stream opening the chunks read from `reader`, sealed under `header` for `set_recipients`.
+    pub fn decrypt_reader<R: Read>(
+        &self,
+        set_recipients: &[usize],
+        channel: &BroadcastChannel<E>,
+        header: &Header<E>,
+        reader: R,
+    ) -> Result<DecryptStream<R>, DecryptError> {
+        let key = self.decrypt(set_recipients, channel, header)?;
+        Ok(DecryptStream::new::<E>(key.key(), reader))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    fn error_of(result: io::Result<usize>) -> Option<ChunkError> {
+        let error = result.err()?;
+        error.get_ref()?.downcast_ref::<ChunkError>().copied()
+    }
+
+    #[test]
+    fn chunked_streams_round_trip() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(3, &mut rng).unwrap();
+
+        for size in [0, 1000, 2 * CHUNK_SIZE, 2 * CHUNK_SIZE + 5].iter() {
+            let content: Vec<u8> = (0..*size).map(|byte| byte as u8).collect();
+            let (header, mut stream) = channel
+                .encrypt_writer(&[1, 3], Vec::new(), &mut rng)
+                .unwrap();
+            stream.write_all(&content).unwrap();
+            let sealed = stream.finish().unwrap();
+            let chunks = ((size + CHUNK_SIZE - 1) / CHUNK_SIZE).max(1);
+            assert_eq!(sealed.len(), SALT_SIZE + size + 16 * chunks);
+
+            let mut opened = Vec::new();
+            participants[2]
+                .decrypt_reader(&[1, 3], &channel, &header, sealed.as_slice())
+                .unwrap()
+                .read_to_end(&mut opened)
+                .unwrap();
+            assert_eq!(opened, content);
+
+            if *size == 2 * CHUNK_SIZE {
+                // dropping the last chunk, or a byte of it
+                for cut in [SALT_SIZE + SEALED_CHUNK_SIZE, sealed.len() - 1].iter() {
+                    let mut stream = participants[0]
+                        .decrypt_reader(&[1, 3], &channel, &header, &sealed[..*cut])
+                        .unwrap();
+                    let expected = if *cut == sealed.len() - 1 {
+                        ChunkError::Authentication
+                    } else {
+                        ChunkError::Truncated
+                    };
+                    assert_eq!(
+                        error_of(stream.read_to_end(&mut Vec::new())),
+                        Some(expected)
+                    );
+                }
+
+                let mut tampered = sealed.clone();
+                tampered[SALT_SIZE + 3] ^= 1;
+                let mut stream = DecryptStream::new::<Bls12_381>(
+                    participants[0]
+                        .decrypt(&[1, 3], &channel, &header)
+                        .unwrap()
+                        .key(),
+                    tampered.as_slice(),
+                );
+                assert_eq!(
+                    error_of(stream.read(&mut [0u8; 10])),
+                    Some(ChunkError::Authentication)
+                );
+                assert_eq!(
+                    error_of(stream.read(&mut [0u8; 10])),
+                    Some(ChunkError::Authentication)
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! Compaction of sparse identifier spaces.
+//!
+//! After churn, the live participants of a channel may be scattered over a large range of
+//! identifiers, while every encryption and decryption pays for the capacity of the channel.
+//! [`BroadcastChannel::compact`] sets up a fresh channel sized for the live participants only,
+//! issues them new keys on the identifiers `1..=m`, and returns a [`Remapping`] translating old
+//! identifiers and sets to the new ones.
+//!
+//! The new channel uses fresh secrets. Reusing the old ones is not an option: the old channel
+//! publishes the point at position `m + 1`, which the smaller channel has to withhold.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use ark_ec::PairingEngine;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use rand_core::{CryptoRng, RngCore};
+
+use crate::encoding::{from_bytes, to_bytes};
+use crate::error::EncryptError;
+use crate::events::Event;
+use crate::set::RecipientSet;
+use crate::{BroadcastChannel, Recipient};
+
+/// Table from the identifiers of a channel to those of its compacted successor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Remapping {
+    /// old identifiers of the live participants, in increasing order. The participant at index
+    /// `j` receives the new identifier `j + 1`.
+    old_identifiers: Vec<usize>,
+}
+
+impl Remapping {
+    /// New identifier of the participant formerly known as `old`, if it was live.
+    pub fn new_identifier(&self, old: usize) -> Option<usize> {
+        self.old_identifiers
+            .binary_search(&old)
+            .ok()
+            .map(|index| index + 1)
+    }
+
+    /// Old identifier of the participant now known as `new`.
+    pub fn old_identifier(&self, new: usize) -> Option<usize> {
+        self.old_identifiers.get(new.checked_sub(1)?).copied()
+    }
+
+    /// Number of participants kept by the compaction.
+    pub fn len(&self) -> usize {
+        self.old_identifiers.len()
+    }
+
+    /// Whether no participant was kept.
+    pub fn is_empty(&self) -> bool {
+        self.old_identifiers.is_empty()
+    }
+
+    /// Translate a set of old identifiers to the new ones. Participants that were not live are
+    /// dropped, as they have no key in the new channel.
+    pub fn translate(&self, set_recipients: &[usize]) -> Vec<usize> {
+        set_recipients
+            .iter()
+            .filter_map(|old| self.new_identifier(*old))
+            .collect()
+    }
+
+    /// Same as [`Remapping::translate`], for a set in its canonical encoding. The mapping
+    /// preserves the order of identifiers, so the result is canonical as well.
+    pub fn translate_bytes(&self, bytes: &[u8]) -> Result<Vec<u8>, SerializationError> {
+        let set = RecipientSet::try_from(bytes)?;
+        Ok(RecipientSet::new(&self.translate(set.as_slice())).canonical_bytes())
+    }
+
+    /// Encoding of the table.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+}
+
+impl CanonicalSerialize for Remapping {
+    fn serialize<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        self.old_identifiers.serialize(writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.old_identifiers.serialized_size()
+    }
+}
+
+impl CanonicalDeserialize for Remapping {
+    fn deserialize<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        let old_identifiers = Vec::<usize>::deserialize(reader)?;
+        // identifiers start at 1 and are strictly increasing, otherwise lookups are meaningless
+        let increasing = old_identifiers.windows(2).all(|pair| pair[0] < pair[1]);
+        if old_identifiers.first() == Some(&0) || !increasing {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(Remapping { old_identifiers })
+    }
+}
+
+impl TryFrom<&[u8]> for Remapping {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Set up a channel holding only the participants in `live`, and issue them new keys on
+    /// consecutive identifiers. This needs to be performed by the trusted entity. Headers of the
+    /// old channel cannot be decrypted with the new keys. The event sink and the policy are
+    /// carried over. Fails with [`EncryptError::EmptySet`] if `live` is empty, and with
+    /// [`EncryptError::RetiredRecipient`] if it contains a retired key.
+    pub fn compact<R>(
+        &self,
+        live: &[usize],
+        rng: &mut R,
+    ) -> Result<(Self, Vec<Recipient<E>>, Remapping), EncryptError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let mut old_identifiers = live.to_vec();
+        old_identifiers.sort_unstable();
+        old_identifiers.dedup();
+        if let Some(unknown) = old_identifiers
+            .iter()
+            .find(|identifier| self.public_key(**identifier).is_none())
+        {
+            return Err(EncryptError::UnknownRecipient(*unknown));
+        }
+        self.check_not_retired(&old_identifiers)?;
+
+        let (mut channel, participants) = Self::init_participants(old_identifiers.len(), rng)
+            .map_err(|_| EncryptError::EmptySet)?;
+        channel.event_sink = self.event_sink.clone();
+        channel.policy = self.policy;
+        for participant in participants.iter() {
+            channel.emit(Event::KeyIssued {
+                identifier: participant.identifier,
+            });
+        }
+
+        Ok((channel, participants, Remapping { old_identifiers }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn compaction_keeps_live_participants() {
+        let mut rng = thread_rng();
+        let (channel, _) = BroadcastChannel::<Bls12_381>::init_participants(8, &mut rng).unwrap();
+
+        let (compacted, participants, remapping) =
+            channel.compact(&[7, 2, 5, 2], &mut rng).unwrap();
+        assert_eq!(compacted.view().capacity(), 3);
+        assert_eq!(remapping.new_identifier(5), Some(2));
+        assert_eq!(remapping.new_identifier(3), None);
+        assert_eq!(remapping.old_identifier(3), Some(7));
+        assert_eq!(remapping.old_identifier(0), None);
+
+        let set = remapping.translate(&[7, 3, 2]);
+        assert_eq!(set, vec![3, 1]);
+        let (header, key) = compacted.encrypt(&set, &mut rng).unwrap();
+        assert_eq!(participants[2].decrypt(&set, &compacted, &header), Ok(key));
+
+        let encoded = RecipientSet::new(&[2, 8, 5]).canonical_bytes();
+        let translated = remapping.translate_bytes(&encoded).unwrap();
+        assert_eq!(translated, RecipientSet::new(&[1, 2]).canonical_bytes());
+
+        let decoded = Remapping::try_from(remapping.to_bytes().as_slice()).unwrap();
+        assert_eq!(decoded, remapping);
+        let unordered = to_bytes(&vec![5usize, 2]);
+        assert!(Remapping::try_from(unordered.as_slice()).is_err());
+
+        assert!(matches!(
+            channel.compact(&[1, 9], &mut rng),
+            Err(EncryptError::UnknownRecipient(9))
+        ));
+    }
+}
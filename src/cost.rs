@@ -0,0 +1,189 @@
+//! Operation counts of encryptions and decryptions, for cost modeling.
+//!
+//! [`BroadcastChannel::encrypt_instrumented`] and [`Recipient::decrypt_instrumented`] return,
+//! along with their usual results, a [`CostReport`] of the group operations they went through.
+//! The counts follow the path actually taken: an aggregate taken from the cache of the channel
+//! costs no addition, and with the `high-assurance` feature the decryption counts the second,
+//! independent computation of its key. A pairing is counted as a Miller loop and a final
+//! exponentiation, since the decryption shares one final exponentiation between two Miller loops.
+//!
+//! Additions in G1 include the mixed additions of points of the parameters, and multiplications
+//! are by the ephemeral scalar. Operations in the target group, and negations, are not counted.
+
+use core::cell::Cell;
+use core::ops::Add;
+
+use ark_ec::PairingEngine;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::error::{DecryptError, EncryptError};
+use crate::{BroadcastChannel, Header, Recipient, SharedKey};
+
+/// Group operations of an encryption or a decryption.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CostReport {
+    /// additions in G1
+    pub g1_additions: usize,
+    /// additions in G2
+    pub g2_additions: usize,
+    /// scalar multiplications in G1
+    pub g1_multiplications: usize,
+    /// scalar multiplications in G2
+    pub g2_multiplications: usize,
+    /// Miller loops
+    pub miller_loops: usize,
+    /// final exponentiations
+    pub final_exponentiations: usize,
+}
+
+impl CostReport {
+    /// Number of pairings, one per Miller loop.
+    pub fn pairings(&self) -> usize {
+        self.miller_loops
+    }
+}
+
+impl Add for CostReport {
+    type Output = CostReport;
+
+    fn add(self, other: CostReport) -> CostReport {
+        CostReport {
+            g1_additions: self.g1_additions + other.g1_additions,
+            g2_additions: self.g2_additions + other.g2_additions,
+            g1_multiplications: self.g1_multiplications + other.g1_multiplications,
+            g2_multiplications: self.g2_multiplications + other.g2_multiplications,
+            miller_loops: self.miller_loops + other.miller_loops,
+            final_exponentiations: self.final_exponentiations + other.final_exponentiations,
+        }
+    }
+}
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Same as [`BroadcastChannel::encrypt`], with the operations it went through.
+    pub fn encrypt_instrumented<R>(
+        &self,
+        set_recipients: &[usize],
+        rng: &mut R,
+    ) -> Result<(Header<E>, SharedKey<E>, CostReport), EncryptError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let summed = Cell::new(false);
+        let session = self.observed_session(set_recipients, || summed.set(true))?;
+        let (header, key) = session.encrypt(rng);
+        let aggregate = if summed.get() {
+            set_recipients.len()
+        } else {
+            0
+        };
+        let cost = CostReport {
+            // the aggregate, then V
+            g1_additions: aggregate + 1,
+            g2_additions: 0,
+            g1_multiplications: 1,
+            // C_1, and Q_1 before the pairing
+            g2_multiplications: 2,
+            miller_loops: 1,
+            final_exponentiations: 1,
+        };
+        Ok((header, key, cost))
+    }
+}
+
+impl<E: PairingEngine> Recipient<E> {
+    /// Same as [`Recipient::decrypt`], with the operations it went through.
+    pub fn decrypt_instrumented(
+        &self,
+        set_recipients: &[usize],
+        channel: &BroadcastChannel<E>,
+        header: &Header<E>,
+    ) -> Result<(SharedKey<E>, CostReport), DecryptError> {
+        let summed = Cell::new(false);
+        let key = self.decrypt_with(set_recipients, channel, header, || {
+            channel
+                .observed_decryption_aggregate(set_recipients, self.identifier, || summed.set(true))
+        })?;
+        // every point of the set, the generator standing in for the own one, which is then
+        // taken away
+        let sum = set_recipients.len() + 1;
+        let aggregate = if summed.get() { sum } else { 0 };
+        let mut cost = CostReport {
+            // the aggregate, then the private key
+            g1_additions: aggregate + 1,
+            g2_additions: 0,
+            g1_multiplications: 0,
+            g2_multiplications: 0,
+            miller_loops: 2,
+            final_exponentiations: 1,
+        };
+        if cfg!(feature = "high-assurance") {
+            cost = cost
+                + CostReport {
+                    g1_additions: sum + 1,
+                    miller_loops: 2,
+                    final_exponentiations: 2,
+                    ..CostReport::default()
+                };
+        }
+        Ok((key, cost))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn operations_are_counted() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(5, &mut rng).unwrap();
+        let set = [1, 3, 4, 5];
+        let (header, key, cost) = channel.encrypt_instrumented(&set, &mut rng).unwrap();
+        assert_eq!(
+            cost,
+            CostReport {
+                g1_additions: 5,
+                g2_additions: 0,
+                g1_multiplications: 1,
+                g2_multiplications: 2,
+                miller_loops: 1,
+                final_exponentiations: 1,
+            }
+        );
+
+        let (decrypted, cost) = participants[2]
+            .decrypt_instrumented(&set, &channel, &header)
+            .unwrap();
+        assert_eq!(decrypted, key);
+        let (additions, miller_loops, final_exponentiations) = if cfg!(feature = "high-assurance") {
+            (12, 4, 3)
+        } else {
+            (6, 2, 1)
+        };
+        assert_eq!(cost.g1_additions, additions);
+        assert_eq!(cost.pairings(), miller_loops);
+        assert_eq!(cost.final_exponentiations, final_exponentiations);
+        assert_eq!(
+            participants[1]
+                .decrypt_instrumented(&set, &channel, &header)
+                .err(),
+            Some(DecryptError::NotInSet)
+        );
+
+        #[cfg(feature = "std")]
+        {
+            use crate::cache::AggregateCache;
+            use alloc::sync::Arc;
+
+            let mut channel = channel;
+            channel.set_aggregate_cache(Arc::new(AggregateCache::new(2)));
+            let (_, _, first) = channel.encrypt_instrumented(&set, &mut rng).unwrap();
+            let (_, _, second) = channel.encrypt_instrumented(&set, &mut rng).unwrap();
+            assert_eq!(first.g1_additions, 5);
+            assert_eq!(second.g1_additions, 1);
+        }
+    }
+}
@@ -0,0 +1,92 @@
+//! Independent broadcasters over one shared set of powers of alpha.
+//!
+//! The points `P_i = alpha^i P` and `Q_i = alpha^i Q` do not depend on the broadcaster: only
+//! `V = gamma P` and the private keys `d_i = gamma P_i` do. As noted by Boneh, Gentry and Waters,
+//! one set of powers can thus serve any number of broadcasters, each with its own gamma, so a
+//! single setup ceremony producing an [`SrsTranscript`] is reused across organizations.
+//! [`BroadcastChannel::new_from_crs`] draws a fresh gamma over the shared transcript and issues
+//! every key of the new channel.
+//!
+//! Headers of different broadcasters are unrelated: the private key of a participant of one
+//! channel does not decrypt the headers of another. Nobody knows alpha after an honest ceremony,
+//! so the broadcasters only hold gamma, in a [`KeyDealer`] that issues further copies of the keys
+//! with [`KeyDealer::issue`] and [`Recipient::combine`]. The transcript should be checked with
+//! [`crate::ceremony::verify_ceremony`] before it is used.
+
+use alloc::vec::Vec;
+
+use ark_ec::PairingEngine;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::error::SetupError;
+use crate::split::{KeyDealer, SrsTranscript};
+use crate::{BroadcastChannel, KeyPair, Recipient};
+
+/// Channel set up over a shared transcript, with its participants and the dealer of its keys.
+pub type CrsSetup<E> = (BroadcastChannel<E>, Vec<Recipient<E>>, KeyDealer<E>);
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Channel of a new broadcaster over the shared powers of `crs`, with a fresh gamma. Fails if
+    /// the transcript is for no participant.
+    pub fn new_from_crs<R>(crs: &SrsTranscript<E>, rng: &mut R) -> Result<CrsSetup<E>, SetupError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if crs.number_participants == 0 {
+            return Err(SetupError::NoParticipants);
+        }
+
+        let dealer = KeyDealer::new(rng);
+        let channel = BroadcastChannel::from_split(crs.clone(), &[dealer.point_v()]);
+        let participants = (1..=crs.number_participants)
+            .map(|identifier| Recipient {
+                identifier,
+                key_pair: KeyPair {
+                    public_key: crs.g2_points[identifier],
+                    private_key: dealer
+                        .issue_point(crs.g1_points[identifier], identifier)
+                        .point,
+                },
+                budget: None,
+            })
+            .collect();
+        Ok((channel, participants, dealer))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn broadcasters_share_a_crs() {
+        let mut rng = thread_rng();
+        let mut crs = SrsTranscript::<Bls12_381>::new(3, &mut rng);
+        crs.update(&mut rng);
+
+        let (first, first_participants, dealer) =
+            BroadcastChannel::new_from_crs(&crs, &mut rng).unwrap();
+        let (second, second_participants, _) =
+            BroadcastChannel::new_from_crs(&crs, &mut rng).unwrap();
+        assert!(first.validate().is_valid());
+        assert!(first.view().fingerprint() != second.view().fingerprint());
+
+        let (header, key) = first.encrypt(&[1, 3], &mut rng).unwrap();
+        assert_eq!(
+            first_participants[2].decrypt(&[1, 3], &first, &header),
+            Ok(key.clone())
+        );
+        assert!(second_participants[2].decrypt(&[1, 3], &second, &header) != Ok(key.clone()));
+        let (header, key) = second.encrypt(&[2], &mut rng).unwrap();
+        assert_eq!(
+            second_participants[1].decrypt(&[2], &second, &header),
+            Ok(key.clone())
+        );
+
+        let share = dealer.issue(&crs, 2).unwrap();
+        let copy = Recipient::combine(&first, 2, &[share]).unwrap();
+        assert!(copy.public_key() == first_participants[1].public_key());
+    }
+}
@@ -0,0 +1,151 @@
+//! The channel on the curves it is deployed on.
+//!
+//! Everything in this crate is generic over the pairing engine, and each module below fixes one:
+//! it names the types of the channel on the curve and gives the sizes of their encodings, for
+//! callers reserving buffers or checking the length of what they receive before decoding it.
+//! Each module is enabled by the feature of the same name.
+//!
+//! - `bls12_381`, the curve of the examples and tests of this crate, for about 128 bits of
+//!   security;
+//! - `bls12_377`, the inner curve of the two-chain of BW6-761;
+//! - `bn254`, also known as alt_bn128, with the smallest headers, for stacks built on the
+//!   precompiles of Ethereum; it gives about 100 bits of security;
+//! - `bw6_761`, whose scalar field is the base field of BLS12-377, with the largest headers.
+//!
+//! The sizes are those of the compressed encodings of [`crate::encoding`].
+
+/// Module of the channel on one curve.
+macro_rules! curve {
+    ($(#[$doc:meta])* $feature:literal, $module:ident, $engine:ty, $g1:expr, $g2:expr) => {
+        $(#[$doc])*
+        #[cfg(feature = $feature)]
+        pub mod $module {
+            /// Pairing engine of the curve.
+            pub type Engine = $engine;
+
+            /// Channel on the curve.
+            pub type BroadcastChannel = crate::BroadcastChannel<Engine>;
+            /// Recipient on the curve.
+            pub type Recipient = crate::Recipient<Engine>;
+            /// Header on the curve.
+            pub type Header = crate::Header<Engine>;
+
+            /// Size of an encoded point of G1.
+            pub const G1_SIZE: usize = $g1;
+            /// Size of an encoded point of G2.
+            pub const G2_SIZE: usize = $g2;
+            /// Size of an encoded header: a point of each group.
+            pub const HEADER_SIZE: usize = G1_SIZE + G2_SIZE;
+            /// Size of an encoded recipient: its identifier, its public key in G2 and its private
+            /// key in G1.
+            pub const RECIPIENT_SIZE: usize = 8 + G2_SIZE + G1_SIZE;
+
+            #[cfg(test)]
+            mod tests {
+                use super::*;
+
+                #[test]
+                fn the_channel_works_on_the_curve() {
+                    super::super::tests::exercise::<Engine>(
+                        G1_SIZE,
+                        G2_SIZE,
+                        HEADER_SIZE,
+                        RECIPIENT_SIZE,
+                    );
+                }
+            }
+        }
+    };
+}
+
+curve!(
+    /// BLS12-381.
+    "bls12-381",
+    bls12_381,
+    ark_bls12_381::Bls12_381,
+    48,
+    96
+);
+
+curve!(
+    /// BLS12-377.
+    "bls12-377",
+    bls12_377,
+    ark_bls12_377::Bls12_377,
+    48,
+    96
+);
+
+curve!(
+    /// BN254.
+    "bn254",
+    bn254,
+    ark_bn254::Bn254,
+    32,
+    64
+);
+
+curve!(
+    /// BW6-761.
+    "bw6-761",
+    bw6_761,
+    ark_bw6_761::BW6_761,
+    96,
+    96
+);
+
+#[cfg(test)]
+mod tests {
+    use core::convert::TryFrom;
+
+    use ark_ec::{AffineCurve, PairingEngine};
+    use ark_serialize::CanonicalSerialize;
+    use rand::thread_rng;
+
+    use crate::{BroadcastChannel, Header, Recipient};
+
+    /// Encryption, decryption and encodings on `E`, with the sizes given for the curve.
+    #[allow(dead_code)]
+    pub(super) fn exercise<E: PairingEngine>(
+        g1_size: usize,
+        g2_size: usize,
+        header_size: usize,
+        recipient_size: usize,
+    ) {
+        assert_eq!(
+            E::G1Affine::prime_subgroup_generator().serialized_size(),
+            g1_size
+        );
+        assert_eq!(
+            E::G2Affine::prime_subgroup_generator().serialized_size(),
+            g2_size
+        );
+
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<E>::init_participants(4, &mut rng).unwrap();
+        let (header, key) = channel.encrypt(&[1, 3], &mut rng).unwrap();
+        for identifier in [1, 3].iter() {
+            assert_eq!(
+                participants[identifier - 1].decrypt(&[1, 3], &channel, &header),
+                Ok(key.clone())
+            );
+        }
+
+        let bytes = header.to_bytes();
+        assert_eq!(bytes.len(), header_size);
+        assert!(Header::<E>::try_from(bytes.as_slice()).unwrap() == header);
+
+        let bytes = participants[0].to_bytes();
+        assert_eq!(bytes.len(), recipient_size);
+        let recipient = Recipient::<E>::try_from(bytes.as_slice()).unwrap();
+        assert!(recipient == participants[0]);
+
+        let decoded = BroadcastChannel::<E>::try_from(channel.to_bytes().as_slice()).unwrap();
+        assert!(decoded == channel);
+        assert_eq!(
+            recipient.decrypt(&[1, 3], &decoded, &header),
+            Ok(key.clone())
+        );
+    }
+}
@@ -0,0 +1,332 @@
+//! Encryption rights delegated for a subset of the recipients.
+//!
+//! Encrypting only takes the public parameters, so the right to encrypt cannot be restricted by
+//! the scheme itself. It is restricted with signatures instead, as in [`crate::authenticated`]: the
+//! broadcaster hands a third party, typically a regional server, an [`EncryptionToken`] signing the
+//! [`VerifyingKey`] of the delegate and the set of recipients it may address. The delegate signs
+//! its headers with [`BroadcastChannel::encrypt_delegated`], which refuses sets outside the
+//! token, and recipients check the whole chain with [`Recipient::decrypt_delegated`]: the token
+//! against the key of the broadcaster, the set against the token, and the header against the key
+//! of the delegate. Headers for other sets, or signed by anyone but a delegate, are refused
+//! before any pairing of the decryption.
+//!
+//! The token binds the fingerprint of the channel. A token is encoded as the canonical encoding of
+//! its set, as a byte vector, followed by the key of the delegate and the signature, and a
+//! delegated header as the token followed by the signed header.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use ark_ec::PairingEngine;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use rand_core::{CryptoRng, RngCore};
+
+use crate::authenticated::SignedHeader;
+use crate::encoding::{from_bytes, to_bytes};
+use crate::error::DelegationError;
+use crate::set::RecipientSet;
+use crate::signature::{Signature, SigningKey, VerifyingKey};
+use crate::{BroadcastChannel, Recipient, SharedKey};
+
+/// Domain separation tag of the messages signed by tokens.
+const TOKEN_TAG: &[u8] = b"DGS-BE-ENCRYPTION-TOKEN-V1";
+
+/// Right, signed by the broadcaster, for a delegate to encrypt for subsets of a set.
+#[derive(Clone, PartialEq, Eq)]
+pub struct EncryptionToken<E: PairingEngine> {
+    allowed: RecipientSet,
+    delegate: VerifyingKey<E>,
+    signature: Signature<E>,
+}
+
+/// Message signed by a token of `delegate` for `allowed` in `channel`.
+fn token_message<E: PairingEngine>(
+    channel: &BroadcastChannel<E>,
+    delegate: &VerifyingKey<E>,
+    allowed: &RecipientSet,
+) -> Vec<u8> {
+    let mut message = TOKEN_TAG.to_vec();
+    message.extend_from_slice(channel.view().fingerprint().as_ref());
+    message.extend_from_slice(&delegate.to_bytes());
+    message.extend_from_slice(&allowed.canonical_bytes());
+    message
+}
+
+impl<E: PairingEngine> EncryptionToken<E> {
+    /// Token signed by `broadcaster` allowing `delegate` to encrypt for subsets of `allowed` in
+    /// `channel`.
+    pub fn issue(
+        broadcaster: &SigningKey<E>,
+        channel: &BroadcastChannel<E>,
+        delegate: VerifyingKey<E>,
+        allowed: &[usize],
+    ) -> Self {
+        let allowed = RecipientSet::new(allowed);
+        let signature = broadcaster.sign(&token_message(channel, &delegate, &allowed));
+        EncryptionToken {
+            allowed,
+            delegate,
+            signature,
+        }
+    }
+
+    /// Recipients the delegate may encrypt for.
+    pub fn allowed(&self) -> &RecipientSet {
+        &self.allowed
+    }
+
+    /// Key of the delegate.
+    pub fn delegate(&self) -> &VerifyingKey<E> {
+        &self.delegate
+    }
+
+    /// Whether the token was signed by `broadcaster` for `channel`.
+    pub fn verify(&self, channel: &BroadcastChannel<E>, broadcaster: &VerifyingKey<E>) -> bool {
+        broadcaster.verify(
+            &token_message(channel, &self.delegate, &self.allowed),
+            &self.signature,
+        )
+    }
+
+    /// First identifier of `set_recipients` the token does not allow, if any.
+    fn first_outside(&self, set_recipients: &[usize]) -> Option<usize> {
+        RecipientSet::new(set_recipients)
+            .iter()
+            .find(|identifier| !self.allowed.contains(*identifier))
+    }
+
+    /// Compressed encoding of the token.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+}
+
+/// Header signed by a delegate, with the token of the delegate.
+#[derive(Clone, PartialEq, Eq)]
+pub struct DelegatedHeader<E: PairingEngine> {
+    token: EncryptionToken<E>,
+    signed: SignedHeader<E>,
+}
+
+impl<E: PairingEngine> DelegatedHeader<E> {
+    /// Token of the delegate that signed the header.
+    pub fn token(&self) -> &EncryptionToken<E> {
+        &self.token
+    }
+
+    /// Header signed by the delegate.
+    pub fn signed(&self) -> &SignedHeader<E> {
+        &self.signed
+    }
+
+    /// Compressed encoding of the token and the signed header.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+}
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Encrypt for `set_recipients` as the delegate of `token`, with the header signed by `key`.
+    /// Fails if a recipient of the set is not allowed by the token, or if `key` is not the key
+    /// of its delegate.
+    pub fn encrypt_delegated<R>(
+        &self,
+        token: &EncryptionToken<E>,
+        key: &SigningKey<E>,
+        set_recipients: &[usize],
+        rng: &mut R,
+    ) -> Result<(DelegatedHeader<E>, SharedKey<E>), DelegationError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if let Some(identifier) = token.first_outside(set_recipients) {
+            return Err(DelegationError::OutsideDelegation(identifier));
+        }
+        if key.verifying_key() != token.delegate {
+            return Err(DelegationError::WrongDelegate);
+        }
+        let (signed, key_header) = self
+            .encrypt_signed(set_recipients, key, rng)
+            .map_err(DelegationError::Encrypt)?;
+        let header = DelegatedHeader {
+            token: token.clone(),
+            signed,
+        };
+        Ok((header, key_header))
+    }
+}
+
+impl<E: PairingEngine> Recipient<E> {
+    /// Same as [`Recipient::decrypt`], for a header of a delegate of `broadcaster`. Fails if the
+    /// token was not signed by `broadcaster` for the channel, if it does not allow the set, or if
+    /// the header was not signed by its delegate, before decrypting.
+    pub fn decrypt_delegated(
+        &self,
+        set_recipients: &[usize],
+        channel: &BroadcastChannel<E>,
+        broadcaster: &VerifyingKey<E>,
+        header: &DelegatedHeader<E>,
+    ) -> Result<SharedKey<E>, DelegationError> {
+        if !header.token.verify(channel, broadcaster) {
+            return Err(DelegationError::InvalidToken);
+        }
+        if let Some(identifier) = header.token.first_outside(set_recipients) {
+            return Err(DelegationError::OutsideDelegation(identifier));
+        }
+        self.decrypt_verified(
+            set_recipients,
+            channel,
+            &header.token.delegate,
+            &header.signed,
+        )
+        .map_err(DelegationError::Decrypt)
+    }
+}
+
+impl<E: PairingEngine> CanonicalSerialize for EncryptionToken<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.allowed.canonical_bytes().serialize(&mut writer)?;
+        self.delegate.serialize(&mut writer)?;
+        self.signature.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.allowed.canonical_bytes().serialized_size()
+            + self.delegate.serialized_size()
+            + self.signature.serialized_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for EncryptionToken<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let allowed = RecipientSet::try_from(Vec::<u8>::deserialize(&mut reader)?.as_slice())?;
+        Ok(EncryptionToken {
+            allowed,
+            delegate: VerifyingKey::deserialize(&mut reader)?,
+            signature: Signature::deserialize(&mut reader)?,
+        })
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for EncryptionToken<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+impl<E: PairingEngine> CanonicalSerialize for DelegatedHeader<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.token.serialize(&mut writer)?;
+        self.signed.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.token.serialized_size() + self.signed.serialized_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for DelegatedHeader<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Ok(DelegatedHeader {
+            token: EncryptionToken::deserialize(&mut reader)?,
+            signed: SignedHeader::deserialize(&mut reader)?,
+        })
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for DelegatedHeader<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::DecryptError;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn delegates_encrypt_within_their_token() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(5, &mut rng).unwrap();
+        let broadcaster = SigningKey::generate(&mut rng);
+        let regional = SigningKey::generate(&mut rng);
+        let token =
+            EncryptionToken::issue(&broadcaster, &channel, regional.verifying_key(), &[2, 3, 4]);
+        let token = EncryptionToken::try_from(token.to_bytes().as_slice()).unwrap();
+        assert!(token.verify(&channel, &broadcaster.verifying_key()));
+
+        let (header, key) = channel
+            .encrypt_delegated(&token, &regional, &[2, 4], &mut rng)
+            .unwrap();
+        let header = DelegatedHeader::try_from(header.to_bytes().as_slice()).unwrap();
+        assert_eq!(
+            participants[3].decrypt_delegated(
+                &[2, 4],
+                &channel,
+                &broadcaster.verifying_key(),
+                &header
+            ),
+            Ok(key)
+        );
+
+        assert_eq!(
+            channel
+                .encrypt_delegated(&token, &regional, &[1, 2], &mut rng)
+                .err(),
+            Some(DelegationError::OutsideDelegation(1))
+        );
+        assert_eq!(
+            channel
+                .encrypt_delegated(&token, &broadcaster, &[2], &mut rng)
+                .err(),
+            Some(DelegationError::WrongDelegate)
+        );
+        assert_eq!(
+            participants[3].decrypt_delegated(
+                &[2, 4],
+                &channel,
+                &regional.verifying_key(),
+                &header
+            ),
+            Err(DelegationError::InvalidToken)
+        );
+
+        // a header of the delegate for a set outside its token is refused by the recipients
+        let rogue = SigningKey::generate(&mut rng);
+        let (signed, _) = channel
+            .encrypt_signed(&[1, 2], &regional, &mut rng)
+            .unwrap();
+        let forged = DelegatedHeader {
+            token: token.clone(),
+            signed,
+        };
+        assert_eq!(
+            participants[0].decrypt_delegated(
+                &[1, 2],
+                &channel,
+                &broadcaster.verifying_key(),
+                &forged
+            ),
+            Err(DelegationError::OutsideDelegation(1))
+        );
+        let (signed, _) = channel.encrypt_signed(&[2], &rogue, &mut rng).unwrap();
+        let forged = DelegatedHeader { token, signed };
+        assert_eq!(
+            participants[1].decrypt_delegated(
+                &[2],
+                &channel,
+                &broadcaster.verifying_key(),
+                &forged
+            ),
+            Err(DelegationError::Decrypt(DecryptError::InvalidSignature))
+        );
+    }
+}
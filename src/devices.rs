@@ -0,0 +1,217 @@
+//! Several devices for one user.
+//!
+//! A user reading on a phone, a laptop and a television holds one key per device, each in a slot
+//! of its own, rather than one key copied to all of them: losing the phone then only takes the
+//! phone's key away. [`Devices`] maps users to the slots of their devices, so that callers
+//! encrypt for sets of users, which it resolves to the slots of every device they have enrolled.
+//!
+//! [`Devices::enroll`] issues the key of a new device with [`BroadcastChannel::add_participant`],
+//! and [`Devices::revoke`] retires the key of a lost one with [`BroadcastChannel::retire`], so
+//! that no header is produced for it anymore, while the other devices of the user keep their
+//! keys. A set of users resolves to different slots once their devices change, so the resolved
+//! set is what travels with a header, as for every other set.
+//!
+//! Only available with the `std` feature.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::hash::Hash;
+
+use ark_ec::PairingEngine;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::error::DeviceError;
+use crate::secret::BroadcasterSecret;
+use crate::{BroadcastChannel, Header, Recipient, SharedKey};
+
+/// Users mapped to the identifiers of their devices in a channel.
+#[derive(Clone, Debug)]
+pub struct Devices<Id> {
+    devices: HashMap<Id, BTreeSet<usize>>,
+    users: BTreeMap<usize, Id>,
+}
+
+impl<Id> Default for Devices<Id> {
+    fn default() -> Self {
+        Devices {
+            devices: HashMap::new(),
+            users: BTreeMap::new(),
+        }
+    }
+}
+
+impl<Id: Hash + Eq + Clone> Devices<Id> {
+    /// No users.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Issue the key of a new device of `user` in `channel`, enrolling the user if it has no
+    /// device yet. Fails if `secret` is not the one of the channel.
+    pub fn enroll<E: PairingEngine>(
+        &mut self,
+        user: Id,
+        channel: &mut BroadcastChannel<E>,
+        secret: &BroadcasterSecret<E>,
+    ) -> Result<Recipient<E>, DeviceError<Id>> {
+        let recipient = channel
+            .add_participant(secret)
+            .map_err(DeviceError::Setup)?;
+        self.devices
+            .entry(user.clone())
+            .or_default()
+            .insert(recipient.identifier());
+        self.users.insert(recipient.identifier(), user);
+        Ok(recipient)
+    }
+
+    /// Retire the key of device `identifier` in `channel`, returning the user it belonged to.
+    /// The user stays enrolled with its other devices, if any. Fails, leaving both unchanged, if
+    /// the identifier is not the one of a device.
+    pub fn revoke<E: PairingEngine>(
+        &mut self,
+        identifier: usize,
+        channel: &mut BroadcastChannel<E>,
+    ) -> Result<Id, DeviceError<Id>> {
+        if !self.users.contains_key(&identifier) {
+            return Err(DeviceError::UnknownDevice(identifier));
+        }
+        channel.retire(identifier).map_err(DeviceError::Setup)?;
+
+        let user = self.users.remove(&identifier).unwrap();
+        if let Some(devices) = self.devices.get_mut(&user) {
+            devices.remove(&identifier);
+            if devices.is_empty() {
+                self.devices.remove(&user);
+            }
+        }
+        Ok(user)
+    }
+
+    /// Identifiers of the devices of `user`, in increasing order.
+    pub fn devices(&self, user: &Id) -> impl Iterator<Item = usize> + '_ {
+        self.devices.get(user).into_iter().flatten().copied()
+    }
+
+    /// User device `identifier` belongs to.
+    pub fn user(&self, identifier: usize) -> Option<&Id> {
+        self.users.get(&identifier)
+    }
+
+    /// Number of users with at least one device.
+    pub fn len(&self) -> usize {
+        self.devices.len()
+    }
+
+    /// Whether no user has a device.
+    pub fn is_empty(&self) -> bool {
+        self.devices.is_empty()
+    }
+
+    /// Identifiers of the devices of `users`, user after user. Fails at the first user without
+    /// devices.
+    pub fn resolve(&self, users: &[Id]) -> Result<Vec<usize>, DeviceError<Id>> {
+        let mut identifiers = Vec::new();
+        for user in users {
+            match self.devices.get(user) {
+                Some(devices) => identifiers.extend(devices.iter().copied()),
+                None => return Err(DeviceError::Unknown(user.clone())),
+            }
+        }
+        Ok(identifiers)
+    }
+
+    /// Same as [`BroadcastChannel::encrypt`], for every device of `users`.
+    pub fn encrypt<E, R>(
+        &self,
+        channel: &BroadcastChannel<E>,
+        users: &[Id],
+        rng: &mut R,
+    ) -> Result<(Header<E>, SharedKey<E>), DeviceError<Id>>
+    where
+        E: PairingEngine,
+        R: RngCore + CryptoRng,
+    {
+        let identifiers = self.resolve(users)?;
+        channel
+            .encrypt(&identifiers, rng)
+            .map_err(DeviceError::Encrypt)
+    }
+
+    /// Same as [`Recipient::decrypt`], by device `recipient`, for a header encrypted for every
+    /// device of `users`.
+    pub fn decrypt<E: PairingEngine>(
+        &self,
+        recipient: &Recipient<E>,
+        users: &[Id],
+        channel: &BroadcastChannel<E>,
+        header: &Header<E>,
+    ) -> Result<SharedKey<E>, DeviceError<Id>> {
+        let identifiers = self.resolve(users)?;
+        recipient
+            .decrypt(&identifiers, channel, header)
+            .map_err(DeviceError::Decrypt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{EncryptError, SetupError};
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn devices_are_enrolled_and_revoked() {
+        let mut rng = thread_rng();
+        let (mut channel, _, secret) =
+            BroadcastChannel::<Bls12_381>::init_with_reserve(0, 4, &mut rng).unwrap();
+        let mut devices = Devices::new();
+        let phone = devices.enroll("alice", &mut channel, &secret).unwrap();
+        let laptop = devices.enroll("alice", &mut channel, &secret).unwrap();
+        let television = devices.enroll("bob", &mut channel, &secret).unwrap();
+        assert!(devices.devices(&"alice").eq(vec![1, 2]));
+        assert_eq!(devices.user(3), Some(&"bob"));
+        assert_eq!(devices.len(), 2);
+
+        let (header, key) = devices
+            .encrypt(&channel, &["alice", "bob"], &mut rng)
+            .unwrap();
+        for device in [&phone, &laptop, &television].iter() {
+            assert_eq!(
+                devices.decrypt(device, &["alice", "bob"], &channel, &header),
+                Ok(key.clone())
+            );
+        }
+
+        // the lost phone is no longer encrypted for, and the laptop still is
+        assert_eq!(
+            devices.revoke(phone.identifier(), &mut channel),
+            Ok("alice")
+        );
+        assert!(devices.devices(&"alice").eq(vec![2]));
+        let (header, key) = devices.encrypt(&channel, &["alice"], &mut rng).unwrap();
+        assert_eq!(
+            devices.decrypt(&laptop, &["alice"], &channel, &header),
+            Ok(key.clone())
+        );
+        assert_eq!(
+            channel.encrypt(&[1, 2], &mut rng).err(),
+            Some(EncryptError::RetiredRecipient(1))
+        );
+        assert_eq!(
+            devices.revoke(1, &mut channel),
+            Err(DeviceError::UnknownDevice(1))
+        );
+
+        assert_eq!(devices.revoke(3, &mut channel), Ok("bob"));
+        assert_eq!(
+            devices.encrypt(&channel, &["bob"], &mut rng).err(),
+            Some(DeviceError::Unknown("bob"))
+        );
+        let (_, _, other) = BroadcastChannel::<Bls12_381>::init_with_secret(1, &mut rng).unwrap();
+        assert_eq!(
+            devices.enroll("carol", &mut channel, &other).err(),
+            Some(DeviceError::Setup(SetupError::WrongSecret))
+        );
+    }
+}
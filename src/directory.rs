@@ -0,0 +1,163 @@
+//! Application identifiers mapped to identifiers of the channel.
+//!
+//! Applications identify their users by UUIDs, account numbers or e-mail addresses, while the
+//! scheme numbers its participants from 1 to `n`. A [`Directory`] keeps the mapping in both
+//! directions, so that callers encrypt for and decrypt with sets of their own identifiers, which
+//! the directory resolves before handing them to the channel.
+//!
+//! Only available with the `std` feature.
+
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+use ark_ec::PairingEngine;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::error::DirectoryError;
+use crate::{BroadcastChannel, Header, Recipient, SharedKey};
+
+/// Two-way mapping between application identifiers and identifiers of a channel.
+#[derive(Clone, Debug)]
+pub struct Directory<Id> {
+    identifiers: HashMap<Id, usize>,
+    ids: BTreeMap<usize, Id>,
+}
+
+impl<Id> Default for Directory<Id> {
+    fn default() -> Self {
+        Directory {
+            identifiers: HashMap::new(),
+            ids: BTreeMap::new(),
+        }
+    }
+}
+
+impl<Id: Hash + Eq + Clone> Directory<Id> {
+    /// Empty directory.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Map `id` to `identifier`. Returns `false`, leaving the directory unchanged, if either is
+    /// already mapped.
+    pub fn bind(&mut self, id: Id, identifier: usize) -> bool {
+        if self.identifiers.contains_key(&id) || self.ids.contains_key(&identifier) {
+            return false;
+        }
+        self.identifiers.insert(id.clone(), identifier);
+        self.ids.insert(identifier, id);
+        true
+    }
+
+    /// Remove the mapping of `id`, returning the identifier it was mapped to.
+    pub fn unbind(&mut self, id: &Id) -> Option<usize> {
+        let identifier = self.identifiers.remove(id)?;
+        self.ids.remove(&identifier);
+        Some(identifier)
+    }
+
+    /// Identifier `id` is mapped to.
+    pub fn identifier(&self, id: &Id) -> Option<usize> {
+        self.identifiers.get(id).copied()
+    }
+
+    /// Application identifier mapped to `identifier`.
+    pub fn id(&self, identifier: usize) -> Option<&Id> {
+        self.ids.get(&identifier)
+    }
+
+    /// Number of mappings.
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Whether the directory has no mappings.
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Identifiers of `ids`, in the same order. Fails at the first unmapped one.
+    pub fn resolve(&self, ids: &[Id]) -> Result<Vec<usize>, DirectoryError<Id>> {
+        ids.iter()
+            .map(|id| {
+                self.identifier(id)
+                    .ok_or_else(|| DirectoryError::Unknown(id.clone()))
+            })
+            .collect()
+    }
+
+    /// Same as [`BroadcastChannel::encrypt`], for the recipients mapped from `ids`.
+    pub fn encrypt<E, R>(
+        &self,
+        channel: &BroadcastChannel<E>,
+        ids: &[Id],
+        rng: &mut R,
+    ) -> Result<(Header<E>, SharedKey<E>), DirectoryError<Id>>
+    where
+        E: PairingEngine,
+        R: RngCore + CryptoRng,
+    {
+        let identifiers = self.resolve(ids)?;
+        channel
+            .encrypt(&identifiers, rng)
+            .map_err(DirectoryError::Encrypt)
+    }
+
+    /// Same as [`Recipient::decrypt`], for a header encrypted for the recipients mapped from
+    /// `ids`.
+    pub fn decrypt<E: PairingEngine>(
+        &self,
+        recipient: &Recipient<E>,
+        ids: &[Id],
+        channel: &BroadcastChannel<E>,
+        header: &Header<E>,
+    ) -> Result<SharedKey<E>, DirectoryError<Id>> {
+        let identifiers = self.resolve(ids)?;
+        recipient
+            .decrypt(&identifiers, channel, header)
+            .map_err(DirectoryError::Decrypt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::DecryptError;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn application_identifiers_are_resolved() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(3, &mut rng).unwrap();
+        let mut directory = Directory::new();
+        assert!(directory.bind("alice", 2));
+        assert!(directory.bind("bob", 3));
+        assert!(!directory.bind("carol", 3));
+        assert!(!directory.bind("alice", 1));
+        assert_eq!(directory.id(3), Some(&"bob"));
+
+        let (header, key) = directory
+            .encrypt(&channel, &["bob", "alice"], &mut rng)
+            .unwrap();
+        assert_eq!(
+            directory.decrypt(&participants[1], &["bob", "alice"], &channel, &header),
+            Ok(key)
+        );
+        assert_eq!(
+            directory.decrypt(&participants[0], &["bob", "alice"], &channel, &header),
+            Err(DirectoryError::Decrypt(DecryptError::NotInSet))
+        );
+        assert_eq!(
+            directory
+                .encrypt(&channel, &["alice", "carol"], &mut rng)
+                .err(),
+            Some(DirectoryError::Unknown("carol"))
+        );
+
+        assert_eq!(directory.unbind(&"bob"), Some(3));
+        assert!(directory.bind("carol", 3));
+        assert_eq!(directory.len(), 2);
+    }
+}
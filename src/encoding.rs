@@ -0,0 +1,1050 @@
+//! Byte encodings of the wire types.
+//!
+//! Headers, versioned headers, shared keys, public keys, key pairs, recipients and channels
+//! implement the ark-serialize traits, from which `to_bytes` and the `TryFrom<&[u8]>`
+//! conversions are derived. Decoding is strict: it goes through the checked deserialization and
+//! rejects trailing bytes.
+//!
+//! Channels encode their public parameters followed by their state: the policy, the retired keys
+//! and the epoch, in a layout of version [`CHANNEL_STATE_VERSION`], so that a channel decoded
+//! from its encoding has the fingerprint of the original and refuses the same sets. The usage
+//! budget of a recipient and the event sink and aggregate cache of a channel are not encoded, and
+//! the receiving side sets them up again.
+//!
+//! Points are compressed by default, which halves their size at the cost of a square root per
+//! point when decoding: the right trade for headers sent over the network. Parameter files
+//! loaded often are better stored with [`Encoding::Uncompressed`], which keeps both coordinates
+//! of every point, so that nothing is recomputed when they are read; every type with a
+//! `to_bytes` also has `to_bytes_with` and `from_bytes_with` taking the [`Encoding`].
+//! [`BroadcastChannel::from_bytes_batched`] decodes the points of large parameters in batches,
+//! on the threads of the pool with the `parallel` feature, where the subgroup checks and the
+//! square roots dominate the loading time.
+//!
+//! Equality and hashing of these types follow the encodings too: two values are equal when they
+//! encode to the same bytes, so what is not encoded is ignored, and key pairs are compared in
+//! constant time. Key pairs and recipients only hash their public part, which keeps private keys
+//! out of the hashers of maps. `Debug` prints encodings in hexadecimal and redacts private keys.
+
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+
+use ark_ec::{AffineCurve, PairingEngine};
+use ark_ff::Zero;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::kdf::VersionedHeader;
+use crate::policy::Policy;
+use crate::session::EncryptionTables;
+use crate::wire::{take, take_u64};
+use crate::{BroadcastChannel, Header, KeyPair, PublicKey, Recipient, SharedKey};
+
+/// Form of the points in an encoding.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    /// The x coordinate and the sign of y, from which y is recomputed when decoding
+    #[default]
+    Compressed,
+    /// Both coordinates, twice the size
+    Uncompressed,
+}
+
+/// Serialize `value` in compressed form.
+pub(crate) fn to_bytes<T: CanonicalSerialize>(value: &T) -> Vec<u8> {
+    to_bytes_with(value, Encoding::Compressed)
+}
+
+/// Serialize `value` with its points in `encoding`.
+pub(crate) fn to_bytes_with<T: CanonicalSerialize>(value: &T, encoding: Encoding) -> Vec<u8> {
+    // serializing into a vector cannot fail
+    match encoding {
+        Encoding::Compressed => {
+            let mut bytes = Vec::with_capacity(value.serialized_size());
+            value.serialize(&mut bytes).unwrap();
+            bytes
+        }
+        Encoding::Uncompressed => {
+            let mut bytes = Vec::with_capacity(value.uncompressed_size());
+            value.serialize_uncompressed(&mut bytes).unwrap();
+            bytes
+        }
+    }
+}
+
+/// Deserialize a `T` from exactly `bytes`.
+pub(crate) fn from_bytes<T: CanonicalDeserialize>(bytes: &[u8]) -> Result<T, SerializationError> {
+    from_bytes_with(bytes, Encoding::Compressed)
+}
+
+/// Deserialize a `T` with its points in `encoding` from exactly `bytes`.
+pub(crate) fn from_bytes_with<T: CanonicalDeserialize>(
+    mut bytes: &[u8],
+    encoding: Encoding,
+) -> Result<T, SerializationError> {
+    let value = match encoding {
+        Encoding::Compressed => T::deserialize(&mut bytes)?,
+        Encoding::Uncompressed => T::deserialize_uncompressed(&mut bytes)?,
+    };
+    if !bytes.is_empty() {
+        return Err(SerializationError::InvalidData);
+    }
+    Ok(value)
+}
+
+/// Points encoded one after the other in `encoding` in exactly `bytes`, decoded in batches on
+/// the threads of the pool with the `parallel` feature.
+pub fn decode_points<G: AffineCurve>(
+    bytes: &[u8],
+    encoding: Encoding,
+) -> Result<Vec<G>, SerializationError> {
+    let size = match encoding {
+        Encoding::Compressed => G::zero().serialized_size(),
+        Encoding::Uncompressed => G::zero().uncompressed_size(),
+    };
+    if bytes.len() % size != 0 {
+        return Err(SerializationError::InvalidData);
+    }
+    #[cfg(feature = "parallel")]
+    let points = bytes.par_chunks(size);
+    #[cfg(not(feature = "parallel"))]
+    let points = bytes.chunks(size);
+    points
+        .map(|point| from_bytes_with(point, encoding))
+        .collect()
+}
+
+/// `to_bytes_with` and `from_bytes_with` of a wire type.
+macro_rules! with_encoding {
+    ($type:ident, $what:literal) => {
+        impl<E: PairingEngine> $type<E> {
+            #[doc = concat!("Encoding of the ", $what, " with its points in `encoding`.")]
+            pub fn to_bytes_with(&self, encoding: Encoding) -> Vec<u8> {
+                to_bytes_with(self, encoding)
+            }
+
+            #[doc = concat!("The ", $what, " encoded in `bytes` with its points in `encoding`.")]
+            pub fn from_bytes_with(
+                bytes: &[u8],
+                encoding: Encoding,
+            ) -> Result<Self, SerializationError> {
+                from_bytes_with(bytes, encoding)
+            }
+        }
+    };
+}
+
+with_encoding!(Header, "header");
+with_encoding!(VersionedHeader, "versioned header");
+with_encoding!(PublicKey, "public key");
+with_encoding!(Recipient, "recipient, private key included,");
+with_encoding!(BroadcastChannel, "public parameters");
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+/// Lowercase hexadecimal encoding of `bytes`.
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(2 * bytes.len());
+    for byte in bytes {
+        hex.push(HEX_DIGITS[(byte >> 4) as usize] as char);
+        hex.push(HEX_DIGITS[(byte & 0x0f) as usize] as char);
+    }
+    hex
+}
+
+/// Bytes encoded by `hex`, in either case, if it is hexadecimal.
+pub(crate) fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    fn digit(character: u8) -> Option<u8> {
+        match character {
+            b'0'..=b'9' => Some(character - b'0'),
+            b'a'..=b'f' => Some(character - b'a' + 10),
+            b'A'..=b'F' => Some(character - b'A' + 10),
+            _ => None,
+        }
+    }
+
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    hex.as_bytes()
+        .chunks(2)
+        .map(|pair| Some(digit(pair[0])? << 4 | digit(pair[1])?))
+        .collect()
+}
+
+impl<E: PairingEngine> CanonicalSerialize for Header<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.ctx_0.serialize(&mut writer)?;
+        self.ctx_1.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.ctx_0.serialized_size() + self.ctx_1.serialized_size()
+    }
+
+    fn serialize_uncompressed<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.ctx_0.serialize_uncompressed(&mut writer)?;
+        self.ctx_1.serialize_uncompressed(&mut writer)
+    }
+
+    fn uncompressed_size(&self) -> usize {
+        self.ctx_0.uncompressed_size() + self.ctx_1.uncompressed_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for Header<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Ok(Header {
+            ctx_0: E::G1Projective::deserialize(&mut reader)?,
+            ctx_1: E::G2Projective::deserialize(&mut reader)?,
+        })
+    }
+
+    fn deserialize_uncompressed<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Ok(Header {
+            ctx_0: E::G1Projective::deserialize_uncompressed(&mut reader)?,
+            ctx_1: E::G2Projective::deserialize_uncompressed(&mut reader)?,
+        })
+    }
+
+    fn deserialize_unchecked<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Ok(Header {
+            ctx_0: E::G1Projective::deserialize_unchecked(&mut reader)?,
+            ctx_1: E::G2Projective::deserialize_unchecked(&mut reader)?,
+        })
+    }
+}
+
+impl<E: PairingEngine> Header<E> {
+    /// Compressed encoding of the header.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for Header<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+impl<E: PairingEngine> CanonicalSerialize for VersionedHeader<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.version.serialize(&mut writer)?;
+        self.header.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        1 + self.header.serialized_size()
+    }
+
+    fn serialize_uncompressed<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.version.serialize(&mut writer)?;
+        self.header.serialize_uncompressed(&mut writer)
+    }
+
+    fn uncompressed_size(&self) -> usize {
+        1 + self.header.uncompressed_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for VersionedHeader<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Ok(VersionedHeader {
+            version: u8::deserialize(&mut reader)?,
+            header: Header::deserialize(&mut reader)?,
+        })
+    }
+
+    fn deserialize_uncompressed<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Ok(VersionedHeader {
+            version: u8::deserialize(&mut reader)?,
+            header: Header::deserialize_uncompressed(&mut reader)?,
+        })
+    }
+
+    fn deserialize_unchecked<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Ok(VersionedHeader {
+            version: u8::deserialize(&mut reader)?,
+            header: Header::deserialize_unchecked(&mut reader)?,
+        })
+    }
+}
+
+impl<E: PairingEngine> VersionedHeader<E> {
+    /// Compressed encoding of the versioned header.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for VersionedHeader<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+impl<E: PairingEngine> CanonicalSerialize for SharedKey<E> {
+    fn serialize<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        self.key.serialize(writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.key.serialized_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for SharedKey<E> {
+    fn deserialize<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        let key = E::Fqk::deserialize(reader)?;
+        // the key of a header is a pairing, never zero
+        if key.is_zero() {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(SharedKey { key })
+    }
+}
+
+impl<E: PairingEngine> SharedKey<E> {
+    /// Encoding of the key, secret like the key itself.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for SharedKey<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+impl<E: PairingEngine> CanonicalSerialize for PublicKey<E> {
+    fn serialize<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        self.point.serialize(writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.point.serialized_size()
+    }
+
+    fn serialize_uncompressed<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        self.point.serialize_uncompressed(writer)
+    }
+
+    fn uncompressed_size(&self) -> usize {
+        self.point.uncompressed_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for PublicKey<E> {
+    fn deserialize<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        Ok(PublicKey {
+            point: E::G2Projective::deserialize(reader)?,
+        })
+    }
+
+    fn deserialize_uncompressed<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        Ok(PublicKey {
+            point: E::G2Projective::deserialize_uncompressed(reader)?,
+        })
+    }
+
+    fn deserialize_unchecked<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        Ok(PublicKey {
+            point: E::G2Projective::deserialize_unchecked(reader)?,
+        })
+    }
+}
+
+impl<E: PairingEngine> PublicKey<E> {
+    /// Compressed encoding of the public key.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for PublicKey<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+impl<E: PairingEngine> CanonicalSerialize for KeyPair<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.public_key.serialize(&mut writer)?;
+        self.private_key.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.public_key.serialized_size() + self.private_key.serialized_size()
+    }
+
+    fn serialize_uncompressed<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.public_key.serialize_uncompressed(&mut writer)?;
+        self.private_key.serialize_uncompressed(&mut writer)
+    }
+
+    fn uncompressed_size(&self) -> usize {
+        self.public_key.uncompressed_size() + self.private_key.uncompressed_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for KeyPair<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Ok(KeyPair {
+            public_key: E::G2Projective::deserialize(&mut reader)?,
+            private_key: E::G1Projective::deserialize(&mut reader)?,
+        })
+    }
+
+    fn deserialize_uncompressed<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Ok(KeyPair {
+            public_key: E::G2Projective::deserialize_uncompressed(&mut reader)?,
+            private_key: E::G1Projective::deserialize_uncompressed(&mut reader)?,
+        })
+    }
+
+    fn deserialize_unchecked<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Ok(KeyPair {
+            public_key: E::G2Projective::deserialize_unchecked(&mut reader)?,
+            private_key: E::G1Projective::deserialize_unchecked(&mut reader)?,
+        })
+    }
+}
+
+impl<E: PairingEngine> CanonicalSerialize for Recipient<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.identifier.serialize(&mut writer)?;
+        self.key_pair.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.identifier.serialized_size() + self.key_pair.serialized_size()
+    }
+
+    fn serialize_uncompressed<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.identifier.serialize(&mut writer)?;
+        self.key_pair.serialize_uncompressed(&mut writer)
+    }
+
+    fn uncompressed_size(&self) -> usize {
+        self.identifier.serialized_size() + self.key_pair.uncompressed_size()
+    }
+}
+
+/// Identifier of a recipient, which is never 0.
+pub(crate) fn identifier<R: Read>(reader: R) -> Result<usize, SerializationError> {
+    match usize::deserialize(reader)? {
+        0 => Err(SerializationError::InvalidData),
+        identifier => Ok(identifier),
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for Recipient<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Ok(Recipient {
+            identifier: identifier(&mut reader)?,
+            key_pair: KeyPair::deserialize(&mut reader)?,
+            budget: None,
+        })
+    }
+
+    fn deserialize_uncompressed<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Ok(Recipient {
+            identifier: identifier(&mut reader)?,
+            key_pair: KeyPair::deserialize_uncompressed(&mut reader)?,
+            budget: None,
+        })
+    }
+
+    fn deserialize_unchecked<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Ok(Recipient {
+            identifier: identifier(&mut reader)?,
+            key_pair: KeyPair::deserialize_unchecked(&mut reader)?,
+            budget: None,
+        })
+    }
+}
+
+impl<E: PairingEngine> Recipient<E> {
+    /// Compressed encoding of the recipient, private key included.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for Recipient<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+/// Version of the encoding of the state of a channel, written after its points.
+pub const CHANNEL_STATE_VERSION: u8 = 1;
+
+/// Size of the state of a channel without retired keys: the version, the flag and the limit of
+/// the policy, the epoch and the number of retired keys.
+pub(crate) const STATE_PREFIX_SIZE: usize = 2 + 3 * 8;
+
+/// Policy, retired keys and epoch of a channel, as encoded after its points.
+pub(crate) struct ChannelState {
+    pub(crate) policy: Policy,
+    pub(crate) retired: BTreeSet<usize>,
+    pub(crate) epoch: u64,
+}
+
+/// Write the state of a channel: the version, the maximum set size as a flag and a length, the
+/// epoch, then the retired identifiers as a vector, in increasing order.
+pub(crate) fn serialize_state<W: Write>(
+    policy: &Policy,
+    epoch: u64,
+    retired: &BTreeSet<usize>,
+    mut writer: W,
+) -> Result<(), SerializationError> {
+    CHANNEL_STATE_VERSION.serialize(&mut writer)?;
+    let limit = policy.max_set_size();
+    limit.is_some().serialize(&mut writer)?;
+    limit.unwrap_or(0).serialize(&mut writer)?;
+    epoch.serialize(&mut writer)?;
+    retired.len().serialize(&mut writer)?;
+    for identifier in retired.iter() {
+        identifier.serialize(&mut writer)?;
+    }
+    Ok(())
+}
+
+/// Size of the state of `channel`.
+fn state_size<E: PairingEngine>(channel: &BroadcastChannel<E>) -> usize {
+    STATE_PREFIX_SIZE + 8 * channel.retired.len()
+}
+
+/// State written by [`serialize_state`] for a channel of `issued` keys. Retired identifiers must
+/// be issued and strictly increasing, so that every state has a single encoding.
+pub(crate) fn deserialize_state<R: Read>(
+    mut reader: R,
+    issued: usize,
+) -> Result<ChannelState, SerializationError> {
+    if u8::deserialize(&mut reader)? != CHANNEL_STATE_VERSION {
+        return Err(SerializationError::InvalidData);
+    }
+    let limited = bool::deserialize(&mut reader)?;
+    let limit = usize::deserialize(&mut reader)?;
+    let policy = match (limited, limit) {
+        (true, limit) => Policy::default().with_max_set_size(limit),
+        (false, 0) => Policy::default(),
+        (false, _) => return Err(SerializationError::InvalidData),
+    };
+    let epoch = u64::deserialize(&mut reader)?;
+
+    let count = usize::deserialize(&mut reader)?;
+    if count > issued {
+        return Err(SerializationError::InvalidData);
+    }
+    let mut retired = BTreeSet::new();
+    let mut previous = 0;
+    for _ in 0..count {
+        let identifier = usize::deserialize(&mut reader)?;
+        if identifier <= previous || identifier > issued {
+            return Err(SerializationError::InvalidData);
+        }
+        retired.insert(identifier);
+        previous = identifier;
+    }
+    Ok(ChannelState {
+        policy,
+        retired,
+        epoch,
+    })
+}
+
+impl<E: PairingEngine> CanonicalSerialize for BroadcastChannel<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.number_participants.serialize(&mut writer)?;
+        self.issued.serialize(&mut writer)?;
+        self.broadcaster_pk_g1.serialize(&mut writer)?;
+        self.broadcaster_pk_g2.serialize(&mut writer)?;
+        self.point_v.serialize(&mut writer)?;
+        serialize_state(&self.policy, self.epoch, &self.retired, writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.number_participants.serialized_size()
+            + self.issued.serialized_size()
+            + self.broadcaster_pk_g1.serialized_size()
+            + self.broadcaster_pk_g2.serialized_size()
+            + self.point_v.serialized_size()
+            + state_size(self)
+    }
+
+    fn serialize_uncompressed<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.number_participants.serialize(&mut writer)?;
+        self.issued.serialize(&mut writer)?;
+        self.broadcaster_pk_g1.serialize_uncompressed(&mut writer)?;
+        self.broadcaster_pk_g2.serialize_uncompressed(&mut writer)?;
+        self.point_v.serialize_uncompressed(&mut writer)?;
+        serialize_state(&self.policy, self.epoch, &self.retired, writer)
+    }
+
+    fn uncompressed_size(&self) -> usize {
+        self.number_participants.serialized_size()
+            + self.issued.serialized_size()
+            + self.broadcaster_pk_g1.uncompressed_size()
+            + self.broadcaster_pk_g2.uncompressed_size()
+            + self.point_v.uncompressed_size()
+            + state_size(self)
+    }
+}
+
+/// Channel with the decoded parts, if the lengths are consistent.
+fn channel_from_parts<E: PairingEngine, R: Read>(
+    number_participants: usize,
+    issued: usize,
+    broadcaster_pk_g1: Vec<E::G1Affine>,
+    broadcaster_pk_g2: Vec<E::G2Affine>,
+    point_v: E::G1Projective,
+    state: R,
+) -> Result<BroadcastChannel<E>, SerializationError> {
+    // every index computed from the identifiers relies on these lengths
+    if broadcaster_pk_g1.len() != 2 * number_participants + 1
+        || broadcaster_pk_g2.len() != number_participants + 1
+        || issued > number_participants
+    {
+        return Err(SerializationError::InvalidData);
+    }
+    let ChannelState {
+        policy,
+        retired,
+        epoch,
+    } = deserialize_state(state, issued)?;
+    let encryption_tables = Arc::new(EncryptionTables::new(&broadcaster_pk_g2));
+    let mut channel = BroadcastChannel {
+        number_participants,
+        broadcaster_pk_g1: Arc::new(broadcaster_pk_g1),
+        broadcaster_pk_g2: Arc::new(broadcaster_pk_g2),
+        point_v,
+        issued,
+        event_sink: None,
+        policy,
+        retired,
+        epoch,
+        issued_aggregate: E::G1Projective::zero(),
+        encryption_tables,
+        #[cfg(feature = "std")]
+        aggregate_cache: None,
+    };
+    channel.refresh_issued_aggregate();
+    Ok(channel)
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for BroadcastChannel<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        channel_from_parts(
+            usize::deserialize(&mut reader)?,
+            usize::deserialize(&mut reader)?,
+            Vec::deserialize(&mut reader)?,
+            Vec::deserialize(&mut reader)?,
+            E::G1Projective::deserialize(&mut reader)?,
+            reader,
+        )
+    }
+
+    fn deserialize_uncompressed<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        channel_from_parts(
+            usize::deserialize(&mut reader)?,
+            usize::deserialize(&mut reader)?,
+            Vec::deserialize_uncompressed(&mut reader)?,
+            Vec::deserialize_uncompressed(&mut reader)?,
+            E::G1Projective::deserialize_uncompressed(&mut reader)?,
+            reader,
+        )
+    }
+
+    fn deserialize_unchecked<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        channel_from_parts(
+            usize::deserialize(&mut reader)?,
+            usize::deserialize(&mut reader)?,
+            Vec::deserialize_unchecked(&mut reader)?,
+            Vec::deserialize_unchecked(&mut reader)?,
+            E::G1Projective::deserialize_unchecked(&mut reader)?,
+            reader,
+        )
+    }
+}
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Compressed encoding of the public parameters of the channel.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+
+    /// Same as [`BroadcastChannel::from_bytes_with`], with the points of the parameters
+    /// decoded by [`decode_points`].
+    pub fn from_bytes_batched(
+        bytes: &[u8],
+        encoding: Encoding,
+    ) -> Result<Self, SerializationError> {
+        let size = |point_size: usize, count: u64| {
+            usize::try_from(count)
+                .ok()
+                .and_then(|count| count.checked_mul(point_size))
+                .ok_or(SerializationError::InvalidData)
+        };
+        let (g1_size, g2_size) = match encoding {
+            Encoding::Compressed => (
+                E::G1Affine::zero().serialized_size(),
+                E::G2Affine::zero().serialized_size(),
+            ),
+            Encoding::Uncompressed => (
+                E::G1Affine::zero().uncompressed_size(),
+                E::G2Affine::zero().uncompressed_size(),
+            ),
+        };
+
+        let (number_participants, bytes) = take_u64(bytes)?;
+        let (issued, bytes) = take_u64(bytes)?;
+        let (count, bytes) = take_u64(bytes)?;
+        let (points_g1, bytes) = take(bytes, size(g1_size, count)?)?;
+        let (count, bytes) = take_u64(bytes)?;
+        let (points_g2, bytes) = take(bytes, size(g2_size, count)?)?;
+        let (point_v, mut state) = take(bytes, g1_size)?;
+        let channel = channel_from_parts(
+            usize::try_from(number_participants).map_err(|_| SerializationError::InvalidData)?,
+            usize::try_from(issued).map_err(|_| SerializationError::InvalidData)?,
+            decode_points(points_g1, encoding)?,
+            decode_points(points_g2, encoding)?,
+            from_bytes_with(point_v, encoding)?,
+            &mut state,
+        )?;
+        if !state.is_empty() {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(channel)
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for BroadcastChannel<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+/// Equality of `a` and `b` in a time that only depends on their lengths.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+impl<E: PairingEngine> Hash for Header<E> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bytes().hash(state);
+    }
+}
+
+impl<E: PairingEngine> fmt::Debug for Header<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Header")
+            .field(&format_args!("{}", to_hex(&self.to_bytes())))
+            .finish()
+    }
+}
+
+impl<E: PairingEngine> PartialEq for SharedKey<E> {
+    fn eq(&self, other: &Self) -> bool {
+        constant_time_eq(&self.to_bytes(), &other.to_bytes())
+    }
+}
+
+impl<E: PairingEngine> Eq for SharedKey<E> {}
+
+impl<E: PairingEngine> fmt::Debug for SharedKey<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SharedKey")
+            .field(&format_args!("<redacted>"))
+            .finish()
+    }
+}
+
+impl<E: PairingEngine> PartialEq for KeyPair<E> {
+    fn eq(&self, other: &Self) -> bool {
+        constant_time_eq(&to_bytes(self), &to_bytes(other))
+    }
+}
+
+impl<E: PairingEngine> Eq for KeyPair<E> {}
+
+impl<E: PairingEngine> Hash for KeyPair<E> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        to_bytes(&self.public_key).hash(state);
+    }
+}
+
+impl<E: PairingEngine> fmt::Debug for KeyPair<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyPair")
+            .field(
+                "public_key",
+                &format_args!("{}", to_hex(&to_bytes(&self.public_key))),
+            )
+            .field("private_key", &format_args!("<redacted>"))
+            .finish()
+    }
+}
+
+impl<E: PairingEngine> PartialEq for Recipient<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.identifier == other.identifier && self.key_pair == other.key_pair
+    }
+}
+
+impl<E: PairingEngine> Eq for Recipient<E> {}
+
+impl<E: PairingEngine> Hash for Recipient<E> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.identifier.hash(state);
+        self.key_pair.hash(state);
+    }
+}
+
+impl<E: PairingEngine> fmt::Debug for Recipient<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Recipient")
+            .field("identifier", &self.identifier)
+            .field("key_pair", &self.key_pair)
+            .finish()
+    }
+}
+
+impl<E: PairingEngine> PartialEq for BroadcastChannel<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_bytes() == other.to_bytes()
+    }
+}
+
+impl<E: PairingEngine> Eq for BroadcastChannel<E> {}
+
+impl<E: PairingEngine> Hash for BroadcastChannel<E> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bytes().hash(state);
+    }
+}
+
+impl<E: PairingEngine> fmt::Debug for BroadcastChannel<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BroadcastChannel")
+            .field("number_participants", &self.number_participants)
+            .field("issued", &self.issued)
+            .field("epoch", &self.epoch)
+            .field("retired", &self.retired)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::view::Fingerprint;
+    use crate::BroadcastChannel;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn byte_round_trips() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(3, &mut rng).unwrap();
+        let (header, key) = channel.encrypt(&[1, 3], &mut rng).unwrap();
+
+        let bytes = header.to_bytes();
+        assert_eq!(bytes.len(), 48 + 96);
+        let decoded = Header::<Bls12_381>::try_from(bytes.as_slice()).unwrap();
+        assert!(decoded == header);
+        assert_eq!(
+            participants[2].decrypt(&[1, 3], &channel, &decoded),
+            Ok(key.clone())
+        );
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(Header::<Bls12_381>::try_from(trailing.as_slice()).is_err());
+        assert!(Header::<Bls12_381>::try_from(&bytes[1..]).is_err());
+
+        let bytes = key.to_bytes();
+        assert_eq!(bytes.len(), 576);
+        let decoded = SharedKey::<Bls12_381>::try_from(bytes.as_slice()).unwrap();
+        assert_eq!(decoded, key);
+        assert_eq!(decoded.clone().into_key(), *key.key());
+        assert_eq!(format!("{:?}", decoded), "SharedKey(<redacted>)");
+        assert!(SharedKey::<Bls12_381>::try_from(&bytes[1..]).is_err());
+
+        let public_key = participants[0].public_key();
+        let decoded = PublicKey::<Bls12_381>::try_from(public_key.to_bytes().as_slice()).unwrap();
+        assert!(decoded == public_key);
+
+        let fingerprint = channel.view().fingerprint();
+        assert_eq!(
+            Fingerprint::try_from(fingerprint.as_ref()).unwrap(),
+            fingerprint
+        );
+        assert_eq!(Fingerprint::from(fingerprint.to_bytes()), fingerprint);
+        assert!(Fingerprint::try_from(&[0u8; 31][..]).is_err());
+    }
+
+    #[test]
+    fn channels_and_recipients_round_trip() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(3, &mut rng).unwrap();
+        let (header, key) = channel.encrypt(&[1, 2], &mut rng).unwrap();
+
+        let decoded =
+            BroadcastChannel::<Bls12_381>::try_from(channel.to_bytes().as_slice()).unwrap();
+        assert_eq!(decoded.view().fingerprint(), channel.view().fingerprint());
+        let recipient =
+            Recipient::<Bls12_381>::try_from(participants[1].to_bytes().as_slice()).unwrap();
+        assert!(recipient.validate(&decoded).is_valid());
+        assert_eq!(recipient.decrypt(&[1, 2], &decoded, &header), Ok(key));
+
+        let mut uncompressed = Vec::new();
+        channel.serialize_uncompressed(&mut uncompressed).unwrap();
+        assert_eq!(uncompressed.len(), channel.uncompressed_size());
+        let decoded =
+            BroadcastChannel::<Bls12_381>::deserialize_uncompressed(&uncompressed[..]).unwrap();
+        assert_eq!(decoded.view().fingerprint(), channel.view().fingerprint());
+
+        // a channel claiming more participants than it has points
+        let mut bytes = channel.to_bytes();
+        bytes[0] = 4;
+        assert!(BroadcastChannel::<Bls12_381>::try_from(bytes.as_slice()).is_err());
+
+        // the policy and the retired keys are part of the fingerprint, and so of the encoding
+        let mut channel = channel;
+        channel.set_policy(Policy::default().with_max_set_size(2));
+        channel.retire(3).unwrap();
+        let fingerprint = channel.view().fingerprint();
+        let bytes = channel.to_bytes();
+        let decoded = BroadcastChannel::<Bls12_381>::try_from(bytes.as_slice()).unwrap();
+        assert_eq!(decoded.view().fingerprint(), fingerprint);
+        assert_eq!(decoded.policy(), channel.policy());
+        assert!(decoded.view().retired().eq([3].iter().copied()));
+        for encoding in [Encoding::Compressed, Encoding::Uncompressed].iter() {
+            let bytes = channel.to_bytes_with(*encoding);
+            let batched = BroadcastChannel::<Bls12_381>::from_bytes_batched(&bytes, *encoding);
+            assert_eq!(batched.unwrap().view().fingerprint(), fingerprint);
+        }
+
+        // the state starts with its version, and lists issued keys only, once each
+        let state = bytes.len() - STATE_PREFIX_SIZE - 8;
+        let mut changed = bytes.clone();
+        changed[state] = CHANNEL_STATE_VERSION + 1;
+        assert!(BroadcastChannel::<Bls12_381>::try_from(changed.as_slice()).is_err());
+        let mut changed = bytes.clone();
+        changed[bytes.len() - 8] = 4;
+        assert!(BroadcastChannel::<Bls12_381>::try_from(changed.as_slice()).is_err());
+        let mut changed = bytes.clone();
+        changed[bytes.len() - 16] = 2;
+        changed.extend_from_slice(&3u64.to_le_bytes());
+        assert!(BroadcastChannel::<Bls12_381>::try_from(changed.as_slice()).is_err());
+    }
+
+    #[test]
+    fn points_are_encoded_in_either_form() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(3, &mut rng).unwrap();
+        let (header, key) = channel.encrypt(&[2, 3], &mut rng).unwrap();
+        assert_eq!(header.to_bytes_with(Encoding::default()), header.to_bytes());
+
+        let bytes = header.to_bytes_with(Encoding::Uncompressed);
+        assert_eq!(bytes.len(), 2 * (48 + 96));
+        let decoded = Header::<Bls12_381>::from_bytes_with(&bytes, Encoding::Uncompressed).unwrap();
+        assert!(decoded == header);
+        assert!(Header::<Bls12_381>::from_bytes_with(&bytes, Encoding::Compressed).is_err());
+        let bytes = participants[2].to_bytes_with(Encoding::Uncompressed);
+        let recipient = Recipient::from_bytes_with(&bytes, Encoding::Uncompressed).unwrap();
+        assert!(recipient == participants[2]);
+
+        for encoding in [Encoding::Compressed, Encoding::Uncompressed].iter() {
+            let bytes = channel.to_bytes_with(*encoding);
+            let batched = BroadcastChannel::from_bytes_batched(&bytes, *encoding).unwrap();
+            assert_eq!(batched, channel);
+            assert_eq!(
+                BroadcastChannel::from_bytes_with(&bytes, *encoding).unwrap(),
+                batched
+            );
+            assert!(BroadcastChannel::<Bls12_381>::from_bytes_batched(
+                &bytes[..bytes.len() - 1],
+                *encoding
+            )
+            .is_err());
+        }
+        let decoded = BroadcastChannel::from_bytes_batched(
+            &channel.to_bytes_with(Encoding::Uncompressed),
+            Encoding::Uncompressed,
+        )
+        .unwrap();
+        assert_eq!(recipient.decrypt(&[2, 3], &decoded, &header), Ok(key));
+        assert!(decode_points::<<Bls12_381 as PairingEngine>::G1Affine>(
+            &[0u8; 47],
+            Encoding::Compressed
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn standard_traits_follow_the_encodings() {
+        use std::collections::HashSet;
+
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(3, &mut rng).unwrap();
+        let (header, _) = channel.encrypt(&[1, 3], &mut rng).unwrap();
+
+        let decoded =
+            BroadcastChannel::<Bls12_381>::try_from(channel.to_bytes().as_slice()).unwrap();
+        assert_eq!(decoded, channel);
+        let recipient =
+            Recipient::<Bls12_381>::try_from(participants[1].to_bytes().as_slice()).unwrap();
+        assert_eq!(recipient, participants[1]);
+        assert_ne!(recipient, participants[2]);
+        assert_eq!(recipient.key_pair(), participants[1].key_pair());
+
+        let headers: HashSet<_> = vec![header, header, channel.encrypt(&[2], &mut rng).unwrap().0]
+            .into_iter()
+            .collect();
+        assert_eq!(headers.len(), 2);
+        let key_pairs: HashSet<_> = participants
+            .iter()
+            .chain(Some(&recipient))
+            .map(|participant| participant.key_pair().clone())
+            .collect();
+        assert_eq!(key_pairs.len(), 3);
+
+        assert_eq!(
+            format!("{:?}", header),
+            format!("Header({})", to_hex(&header.to_bytes()))
+        );
+        let debug = format!("{:?}", participants[0]);
+        assert!(debug.contains("private_key: <redacted>"));
+        assert!(!debug.contains(&to_hex(&to_bytes(&participants[0].key_pair.private_key))));
+        assert_eq!(
+            format!("{:?}", channel),
+            "BroadcastChannel { number_participants: 3, issued: 3, epoch: 0, retired: {}, .. }"
+        );
+    }
+}
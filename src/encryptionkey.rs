@@ -0,0 +1,185 @@
+//! Encryption capability without the rest of the channel.
+//!
+//! Encrypting for a set only takes the points `P_1` to `P_n` in G1, the point `V`, and the
+//! generator `Q` with `Q_1` in G2: the headers are `k (V + sum of P_{n+1-j})` and `k Q`, and the
+//! key is `e(P_n, k Q_1)`. An [`EncryptionKey`] holds those points only, about a quarter of the
+//! encoding of the channel, so that handing out the ability to encrypt does not look like handing
+//! out the whole state of the channel.
+//!
+//! Only the parameters travel: the policy, retired keys and event sink of the channel stay with
+//! it, so an encryption key checks the range and repetitions of a set, but not its policy.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use rand_core::{CryptoRng, RngCore};
+
+use crate::encoding::{from_bytes, to_bytes};
+use crate::error::EncryptError;
+use crate::policy::Policy;
+use crate::session::EphemeralScalar;
+use crate::set;
+use crate::{BroadcastChannel, Header, SharedKey};
+
+/// Points of a channel needed to encrypt for its sets.
+#[derive(Clone, PartialEq, Eq)]
+pub struct EncryptionKey<E: PairingEngine> {
+    number_participants: usize,
+    /// `P_1` to `P_n`, in this order
+    points_g1: Vec<E::G1Affine>,
+    point_v: E::G1Projective,
+    /// generator `Q` of G2
+    point_q: E::G2Affine,
+    /// `Q_1`, alpha times the generator of G2
+    point_q_1: E::G2Affine,
+}
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Key encrypting for the sets of this channel.
+    pub fn encryption_key(&self) -> EncryptionKey<E> {
+        let n = self.number_participants;
+        EncryptionKey {
+            number_participants: n,
+            points_g1: self.broadcaster_pk_g1[1..n + 1].to_vec(),
+            point_v: self.point_v,
+            point_q: self.broadcaster_pk_g2[0],
+            point_q_1: self.broadcaster_pk_g2[1],
+        }
+    }
+}
+
+impl<E: PairingEngine> EncryptionKey<E> {
+    /// Number of participants of the channel.
+    pub fn number_participants(&self) -> usize {
+        self.number_participants
+    }
+
+    /// Generate a symmetric key and its header for set `set_recipients`. Fails if the set is
+    /// empty, or contains identifiers outside of the channel or more than once.
+    pub fn encrypt<R>(
+        &self,
+        set_recipients: &[usize],
+        rng: &mut R,
+    ) -> Result<(Header<E>, SharedKey<E>), EncryptError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        self.encrypt_with_randomness(set_recipients, EphemeralScalar::random(rng))
+    }
+
+    /// Same as [`EncryptionKey::encrypt`], with the given ephemeral scalar, which is consumed.
+    pub fn encrypt_with_randomness(
+        &self,
+        set_recipients: &[usize],
+        ephemeral: EphemeralScalar<E>,
+    ) -> Result<(Header<E>, SharedKey<E>), EncryptError> {
+        let n = self.number_participants;
+        set::check_encryption_set(n, set_recipients, |_| false, &Policy::default())?;
+
+        let mut ctx_0 = self.point_v;
+        for index in set_recipients.iter() {
+            // P_{n+1-j} is at position n - j
+            ctx_0.add_assign_mixed(&self.points_g1[n - index]);
+        }
+        ctx_0 *= ephemeral.k;
+        let ctx_1 = self.point_q.mul(ephemeral.k);
+        let key = E::pairing(self.points_g1[n - 1], self.point_q_1.mul(ephemeral.k));
+        Ok((Header { ctx_0, ctx_1 }, SharedKey::new(key)))
+    }
+
+    /// Compressed encoding of the key.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+}
+
+impl<E: PairingEngine> CanonicalSerialize for EncryptionKey<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.number_participants.serialize(&mut writer)?;
+        self.points_g1.serialize(&mut writer)?;
+        self.point_v.serialize(&mut writer)?;
+        self.point_q.serialize(&mut writer)?;
+        self.point_q_1.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.number_participants.serialized_size()
+            + self.points_g1.serialized_size()
+            + self.point_v.serialized_size()
+            + self.point_q.serialized_size()
+            + self.point_q_1.serialized_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for EncryptionKey<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let number_participants = usize::deserialize(&mut reader)?;
+        let points_g1 = Vec::<E::G1Affine>::deserialize(&mut reader)?;
+        let point_v = E::G1Projective::deserialize(&mut reader)?;
+        let point_q = E::G2Affine::deserialize(&mut reader)?;
+        let point_q_1 = E::G2Affine::deserialize(&mut reader)?;
+        // encryption indexes the points by identifier
+        if number_participants == 0 || points_g1.len() != number_participants {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(EncryptionKey {
+            number_participants,
+            points_g1,
+            point_v,
+            point_q,
+            point_q_1,
+        })
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for EncryptionKey<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn encryption_keys_encrypt_like_the_channel() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(6, &mut rng).unwrap();
+        let bytes = channel.encryption_key().to_bytes();
+        assert_eq!(bytes.len(), 8 + (8 + 6 * 48) + 48 + 96 + 96);
+        assert!(bytes.len() < channel.params_byte_len());
+        let encryption_key = EncryptionKey::<Bls12_381>::try_from(bytes.as_slice()).unwrap();
+        assert!(encryption_key == channel.encryption_key());
+
+        let (header, key) = encryption_key.encrypt(&[2, 6], &mut rng).unwrap();
+        assert_eq!(participants[5].decrypt(&[2, 6], &channel, &header), Ok(key));
+        let k = || EphemeralScalar::from_scalar(<Bls12_381 as PairingEngine>::Fr::from(7u64));
+        let (ours, _) = encryption_key
+            .encrypt_with_randomness(&[1, 3, 4], k().unwrap())
+            .unwrap();
+        let (theirs, _) = channel
+            .encrypt_with_randomness(&[1, 3, 4], k().unwrap())
+            .unwrap();
+        assert!(ours == theirs);
+
+        assert_eq!(
+            encryption_key.encrypt(&[7], &mut rng).err(),
+            Some(EncryptError::IdentifierOutOfRange(7))
+        );
+        assert_eq!(
+            encryption_key.encrypt(&[2, 2], &mut rng).err(),
+            Some(EncryptError::DuplicateRecipient(2))
+        );
+        let mut truncated = bytes.clone();
+        truncated[0] = 5;
+        assert!(EncryptionKey::<Bls12_381>::try_from(truncated.as_slice()).is_err());
+    }
+}
@@ -0,0 +1,235 @@
+//! Single-shot sealing of payloads for a set, in self-describing envelopes.
+//!
+//! [`seal`] and [`open`] follow the single-shot API of HPKE, for a set rather than one recipient:
+//! [`seal`] encrypts a header for the set, derives a 32-byte key from its key with
+//! [`crate::kdf::HkdfSha256`], and seals the payload with ChaCha20-Poly1305, and the resulting
+//! [`Envelope`] carries everything a recipient needs besides the parameters of the channel. The
+//! associated data of the caller is carried in the clear and authenticated, and so are the
+//! header and the set, so an envelope whose set or associated data were changed does not open.
+//!
+//! An envelope is encoded as:
+//!
+//! - the version of the format, [`ENVELOPE_VERSION`], in one byte;
+//! - the compressed `C_0` in G1, then the compressed `C_1` in G2, as in the encoding of a
+//!   [`Header`];
+//! - the canonical encoding of the [`RecipientSet`];
+//! - the length of the associated data as a little-endian `u64`, then the associated data;
+//! - the length of the ciphertext as a little-endian `u64`, then the ciphertext.
+//!
+//! Everything before the ciphertext is the associated data of the AEAD. As for
+//! [`crate::wire`], decoding is strict.
+//!
+//! Only available with the `chacha20poly1305` feature.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use ark_ec::PairingEngine;
+use ark_ff::Zero;
+use ark_serialize::{CanonicalSerialize, SerializationError};
+use chacha20poly1305::aead::{Aead, Payload};
+use chacha20poly1305::Nonce;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::encoding::from_bytes;
+use crate::error::{EncryptError, MessageError};
+use crate::message::cipher;
+use crate::set::RecipientSet;
+use crate::wire::{take, take_set, take_u64};
+use crate::{BroadcastChannel, Header, Recipient};
+
+/// Version of the encoding of envelopes.
+pub const ENVELOPE_VERSION: u8 = 1;
+
+/// Info of the derivation of the keys of envelopes.
+const ENVELOPE_KEY_INFO: &[u8] = b"DGS-BE-ENVELOPE-CHACHA20POLY1305";
+
+/// Header, set, associated data and sealed payload of a message.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Envelope<E: PairingEngine> {
+    header: Header<E>,
+    set: RecipientSet,
+    aad: Vec<u8>,
+    ciphertext: Vec<u8>,
+}
+
+impl<E: PairingEngine> Envelope<E> {
+    /// Header of the envelope.
+    pub fn header(&self) -> &Header<E> {
+        &self.header
+    }
+
+    /// Set the envelope is sealed for.
+    pub fn set(&self) -> &RecipientSet {
+        &self.set
+    }
+
+    /// Associated data of the envelope, authenticated but not encrypted.
+    pub fn aad(&self) -> &[u8] {
+        &self.aad
+    }
+
+    /// Sealed payload, 16 bytes longer than the plaintext.
+    pub fn ciphertext(&self) -> &[u8] {
+        &self.ciphertext
+    }
+
+    /// Encoding of everything but the ciphertext, which is the associated data of the AEAD.
+    fn authenticated_bytes(
+        header: &Header<E>,
+        set: &RecipientSet,
+        aad: &[u8],
+        capacity: usize,
+    ) -> Vec<u8> {
+        let set = set.canonical_bytes();
+        let mut bytes =
+            Vec::with_capacity(1 + header.serialized_size() + set.len() + 8 + aad.len() + capacity);
+        bytes.push(ENVELOPE_VERSION);
+        bytes.extend_from_slice(&header.to_bytes());
+        bytes.extend_from_slice(&set);
+        bytes.extend_from_slice(&(aad.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(aad);
+        bytes
+    }
+
+    /// Encoding of the envelope.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Self::authenticated_bytes(
+            &self.header,
+            &self.set,
+            &self.aad,
+            8 + self.ciphertext.len(),
+        );
+        bytes.extend_from_slice(&(self.ciphertext.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&self.ciphertext);
+        bytes
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for Envelope<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        let (version, bytes) = take(bytes, 1)?;
+        if version[0] != ENVELOPE_VERSION {
+            return Err(SerializationError::InvalidData);
+        }
+
+        let header_size =
+            E::G1Projective::zero().serialized_size() + E::G2Projective::zero().serialized_size();
+        let (header, bytes) = take(bytes, header_size)?;
+        let header = from_bytes(header)?;
+        let (set, bytes) = take_set(bytes)?;
+        let (length, bytes) = take_u64(bytes)?;
+        let length = usize::try_from(length).map_err(|_| SerializationError::InvalidData)?;
+        let (aad, bytes) = take(bytes, length)?;
+
+        let (length, ciphertext) = take_u64(bytes)?;
+        if length != ciphertext.len() as u64 {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(Envelope {
+            header,
+            set,
+            aad: aad.to_vec(),
+            ciphertext: ciphertext.to_vec(),
+        })
+    }
+}
+
+/// Seal `plaintext` for `set` of `channel`, with the associated data `aad`.
+pub fn seal<E, R>(
+    channel: &BroadcastChannel<E>,
+    set: &RecipientSet,
+    aad: &[u8],
+    plaintext: &[u8],
+    rng: &mut R,
+) -> Result<Envelope<E>, EncryptError>
+where
+    E: PairingEngine,
+    R: RngCore + CryptoRng,
+{
+    let (header, key) = channel.encrypt(set.as_slice(), rng)?;
+    let authenticated = Envelope::authenticated_bytes(&header, set, aad, 0);
+    let payload = Payload {
+        msg: plaintext,
+        aad: &authenticated,
+    };
+    // sealing only fails beyond 256 GiB, far more than fits in a slice
+    let ciphertext = cipher::<E>(key.key(), ENVELOPE_KEY_INFO)
+        .encrypt(Nonce::from_slice(&[0u8; 12]), payload)
+        .expect("payload too long for ChaCha20-Poly1305");
+    Ok(Envelope {
+        header,
+        set: set.clone(),
+        aad: aad.to_vec(),
+        ciphertext,
+    })
+}
+
+/// Payload of `envelope`, opened by `recipient` with the parameters `channel`.
+pub fn open<E: PairingEngine>(
+    recipient: &Recipient<E>,
+    channel: &BroadcastChannel<E>,
+    envelope: &Envelope<E>,
+) -> Result<Vec<u8>, MessageError> {
+    let key = recipient
+        .decrypt(envelope.set.as_slice(), channel, &envelope.header)
+        .map_err(MessageError::Decrypt)?;
+    let authenticated =
+        Envelope::authenticated_bytes(&envelope.header, &envelope.set, &envelope.aad, 0);
+    let payload = Payload {
+        msg: &envelope.ciphertext,
+        aad: &authenticated,
+    };
+    cipher::<E>(key.key(), ENVELOPE_KEY_INFO)
+        .decrypt(Nonce::from_slice(&[0u8; 12]), payload)
+        .map_err(|_| MessageError::Authentication)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::DecryptError;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn envelopes_round_trip() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(4, &mut rng).unwrap();
+        let set = RecipientSet::new(&[2, 4]);
+        let envelope = seal(&channel, &set, b"channel 7", b"attack at dawn", &mut rng).unwrap();
+        assert_eq!(envelope.ciphertext().len(), 14 + 16);
+
+        let bytes = envelope.to_bytes();
+        assert_eq!(
+            bytes.len(),
+            1 + 48 + 96 + (1 + 8 + 8 * 2) + (8 + 9) + (8 + 30)
+        );
+        let decoded = Envelope::<Bls12_381>::try_from(bytes.as_slice()).unwrap();
+        assert!(decoded == envelope);
+        assert_eq!(decoded.aad(), b"channel 7");
+        assert_eq!(
+            open(&participants[3], &channel, &decoded),
+            Ok(b"attack at dawn".to_vec())
+        );
+        assert_eq!(
+            open(&participants[0], &channel, &decoded),
+            Err(MessageError::Decrypt(DecryptError::NotInSet))
+        );
+
+        // the associated data is authenticated
+        let mut other_aad = envelope.clone();
+        other_aad.aad = b"channel 8".to_vec();
+        assert_eq!(
+            open(&participants[1], &channel, &other_aad),
+            Err(MessageError::Authentication)
+        );
+        let mut longer = bytes.clone();
+        longer.push(0);
+        assert!(Envelope::<Bls12_381>::try_from(longer.as_slice()).is_err());
+        assert!(Envelope::<Bls12_381>::try_from(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
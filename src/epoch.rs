@@ -0,0 +1,185 @@
+//! Epochs of a channel, for subscription-style access.
+//!
+//! A channel starts in epoch zero. [`BroadcastChannel::advance_epoch`] moves it to the next one
+//! by replacing gamma, as [`BroadcastChannel::rotate`] does, and issues an [`EpochKey`] only to
+//! the subscribers of the new epoch. Participants left out keep their slot and their public key,
+//! but the private keys they hold stop decrypting: a header is derived from the `V` of the epoch
+//! it is produced in, and only the keys derived from the same gamma recover its key.
+//!
+//! [`BroadcastChannel::encrypt_epoch`] binds headers to the number of the current epoch, and
+//! [`EpochKey::decrypt`] refuses the headers of another epoch before any pairing. A subscriber
+//! that deletes the key of a past epoch can no longer read the headers of that epoch, even if its
+//! later keys leak. The number of the epoch is part of the encoding of the channel, so that a
+//! broadcaster loading the parameters again goes on in the same epoch.
+
+use alloc::vec::Vec;
+
+use ark_ec::{PairingEngine, ProjectiveCurve};
+use ark_ff::UniformRand;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::error::{DecryptError, EncryptError, SetupError};
+use crate::secret::BroadcasterSecret;
+use crate::{BroadcastChannel, Header, Recipient, SharedKey};
+
+/// Key of a participant for one epoch.
+#[derive(Clone)]
+pub struct EpochKey<E: PairingEngine> {
+    epoch: u64,
+    recipient: Recipient<E>,
+}
+
+/// Header bound to the epoch it was produced in.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct EpochHeader<E: PairingEngine> {
+    epoch: u64,
+    header: Header<E>,
+}
+
+impl<E: PairingEngine> EpochHeader<E> {
+    /// Epoch the header was produced in.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Header, without its epoch.
+    pub fn header(&self) -> &Header<E> {
+        &self.header
+    }
+}
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Number of the current epoch.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Move to the next epoch, replacing gamma, and issue the keys of the new epoch to
+    /// `subscribers`, in the same order. Fails, leaving the channel unchanged, if `secret` is not
+    /// the one of the channel, or if a subscriber has no key issued or a retired one.
+    pub fn advance_epoch<R>(
+        &mut self,
+        secret: &mut BroadcasterSecret<E>,
+        subscribers: &[usize],
+        rng: &mut R,
+    ) -> Result<Vec<EpochKey<E>>, SetupError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if !secret.belongs_to(self) {
+            return Err(SetupError::WrongSecret);
+        }
+        if let Some(identifier) = subscribers.iter().find(|identifier| {
+            self.public_key(**identifier).is_none() || self.retired.contains(identifier)
+        }) {
+            return Err(SetupError::NotIssued(*identifier));
+        }
+
+        secret.gamma = E::Fr::rand(rng);
+        let mut point_v = E::G1Projective::prime_subgroup_generator();
+        point_v *= secret.gamma;
+        self.point_v = point_v;
+        self.epoch += 1;
+        self.refresh_cache_fingerprint();
+
+        Ok(subscribers
+            .iter()
+            .map(|identifier| EpochKey {
+                epoch: self.epoch,
+                recipient: self.issue_key(secret, *identifier),
+            })
+            .collect())
+    }
+
+    /// Same as [`BroadcastChannel::encrypt`], with the header bound to the current epoch.
+    pub fn encrypt_epoch<R>(
+        &self,
+        set_recipients: &[usize],
+        rng: &mut R,
+    ) -> Result<(EpochHeader<E>, SharedKey<E>), EncryptError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let (header, key) = self.encrypt(set_recipients, rng)?;
+        let header = EpochHeader {
+            epoch: self.epoch,
+            header,
+        };
+        Ok((header, key))
+    }
+}
+
+impl<E: PairingEngine> EpochKey<E> {
+    /// Epoch of the key.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Key of the participant, for the headers of the epoch.
+    pub fn recipient(&self) -> &Recipient<E> {
+        &self.recipient
+    }
+
+    /// Same as [`Recipient::decrypt`], for a header of the epoch of the key. Fails if the header
+    /// is from another epoch.
+    pub fn decrypt(
+        &self,
+        set_recipients: &[usize],
+        channel: &BroadcastChannel<E>,
+        header: &EpochHeader<E>,
+    ) -> Result<SharedKey<E>, DecryptError> {
+        if header.epoch != self.epoch {
+            return Err(DecryptError::WrongEpoch(header.epoch));
+        }
+        self.recipient
+            .decrypt(set_recipients, channel, &header.header)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use core::convert::TryFrom;
+    use rand::thread_rng;
+
+    #[test]
+    fn unsubscribed_participants_lose_access() {
+        let mut rng = thread_rng();
+        let (mut channel, participants, mut secret) =
+            BroadcastChannel::<Bls12_381>::init_with_secret(3, &mut rng).unwrap();
+        assert_eq!(channel.epoch(), 0);
+
+        let keys = channel
+            .advance_epoch(&mut secret, &[1, 3], &mut rng)
+            .unwrap();
+        assert_eq!(channel.epoch(), 1);
+        let (header, key) = channel.encrypt_epoch(&[1, 2, 3], &mut rng).unwrap();
+        assert_eq!(
+            keys[1].decrypt(&[1, 2, 3], &channel, &header),
+            Ok(key.clone())
+        );
+        assert_ne!(
+            participants[1].decrypt(&[1, 2, 3], &channel, header.header()),
+            Ok(key.clone())
+        );
+
+        let later = channel.advance_epoch(&mut secret, &[1], &mut rng).unwrap();
+        assert_eq!(
+            later[0].decrypt(&[1, 2, 3], &channel, &header),
+            Err(DecryptError::WrongEpoch(1))
+        );
+        assert_eq!(
+            keys[0].decrypt(&[1, 2, 3], &channel, &header),
+            Ok(key.clone())
+        );
+
+        assert!(matches!(
+            channel.advance_epoch(&mut secret, &[4], &mut rng),
+            Err(SetupError::NotIssued(4))
+        ));
+        assert_eq!(channel.epoch(), 2);
+        let decoded = BroadcastChannel::<Bls12_381>::try_from(channel.to_bytes().as_slice());
+        assert_eq!(decoded.unwrap().epoch(), 2);
+    }
+}
@@ -0,0 +1,1086 @@
+//! Errors returned by the setup, ceremonies, encryption, decryption, key derivation, issuance and
+//! federation.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::view::Fingerprint;
+
+/// Reason why a header cannot be decrypted. These are all detected before any pairing is
+/// computed, except for faults.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecryptError {
+    /// The recipient is not part of the set the header was encrypted for
+    NotInSet,
+    /// The set of recipients contains an identifier outside of the channel
+    IdentifierOutOfRange(usize),
+    /// A component of the header is the identity, which no encryption produces
+    IdentityPoint,
+    /// A component of the header is not in the prime order subgroup
+    NotInSubgroup,
+    /// The usage budget of the recipient is spent
+    BudgetExhausted,
+    /// Two computations of the key disagree, see the `high-assurance` feature
+    FaultDetected,
+    /// The set of recipients contains this identifier more than once
+    DuplicateRecipient(usize),
+    /// The header is from this epoch, not the one of the key
+    WrongEpoch(u64),
+    /// The signature of the header does not verify under the key of the broadcaster
+    InvalidSignature,
+    /// The header is from this version of the parameters, which has left the window or which
+    /// the recipient holds no key for
+    UnknownVersion(u64),
+    /// The parameters were extracted for the recipient with this identifier
+    WrongSlice(usize),
+}
+
+impl fmt::Display for DecryptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecryptError::NotInSet => write!(f, "recipient is not in the set of the header"),
+            DecryptError::IdentifierOutOfRange(identifier) => {
+                write!(f, "identifier {} is outside of the channel", identifier)
+            }
+            DecryptError::IdentityPoint => write!(f, "header contains the identity"),
+            DecryptError::NotInSubgroup => {
+                write!(f, "header is not in the prime order subgroup")
+            }
+            DecryptError::BudgetExhausted => write!(f, "usage budget of the key is spent"),
+            DecryptError::FaultDetected => write!(f, "fault detected while decrypting"),
+            DecryptError::DuplicateRecipient(identifier) => {
+                write!(f, "identifier {} appears more than once", identifier)
+            }
+            DecryptError::WrongEpoch(epoch) => {
+                write!(f, "the header is from epoch {}", epoch)
+            }
+            DecryptError::InvalidSignature => write!(f, "signature of the header does not verify"),
+            DecryptError::UnknownVersion(version) => {
+                write!(f, "no key for version {} of the parameters", version)
+            }
+            DecryptError::WrongSlice(identifier) => {
+                write!(f, "parameters were extracted for identifier {}", identifier)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecryptError {}
+
+/// Reason why a set of recipients cannot be encrypted for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EncryptError {
+    /// The set contains an identifier outside of the channel
+    IdentifierOutOfRange(usize),
+    /// The set is larger than the policy of the channel allows
+    SetTooLarge {
+        /// number of identifiers in the set
+        size: usize,
+        /// maximum allowed by the policy
+        limit: usize,
+    },
+    /// No key has been issued for this identifier
+    UnknownRecipient(usize),
+    /// The public key of the handle differs from the one published for this identifier
+    PublicKeyMismatch(usize),
+    /// The set has no recipients
+    EmptySet,
+    /// The set contains this identifier more than once
+    DuplicateRecipient(usize),
+    /// The key of this identifier has been retired
+    RetiredRecipient(usize),
+    /// The session was built from a stream, so its set cannot be changed
+    StreamedSession,
+    /// The identifier is not in the set of the session
+    NotInSession(usize),
+}
+
+impl fmt::Display for EncryptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncryptError::IdentifierOutOfRange(identifier) => {
+                write!(f, "identifier {} is outside of the channel", identifier)
+            }
+            EncryptError::SetTooLarge { size, limit } => write!(
+                f,
+                "set of {} recipients exceeds the limit of {}",
+                size, limit
+            ),
+            EncryptError::UnknownRecipient(identifier) => {
+                write!(f, "no key issued for identifier {}", identifier)
+            }
+            EncryptError::PublicKeyMismatch(identifier) => {
+                write!(
+                    f,
+                    "public key of identifier {} does not match the channel",
+                    identifier
+                )
+            }
+            EncryptError::EmptySet => write!(f, "set of recipients is empty"),
+            EncryptError::DuplicateRecipient(identifier) => {
+                write!(f, "identifier {} appears more than once", identifier)
+            }
+            EncryptError::RetiredRecipient(identifier) => {
+                write!(f, "key of identifier {} has been retired", identifier)
+            }
+            EncryptError::StreamedSession => write!(f, "set of a streamed session is not kept"),
+            EncryptError::NotInSession(identifier) => {
+                write!(
+                    f,
+                    "identifier {} is not in the set of the session",
+                    identifier
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EncryptError {}
+
+/// Reason why a channel cannot be set up or grown.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SetupError {
+    /// A channel needs at least one participant
+    NoParticipants,
+    /// The secret given is not the one the channel was set up with
+    WrongSecret,
+    /// No key has been issued for this identifier, or it has already been retired
+    NotIssued(usize),
+    /// Every slot of the channel has been issued
+    NoUnusedSlot,
+    /// Buckets of a generalized channel need at least one participant
+    EmptyBuckets,
+    /// The channel has more slots than this number, to grow to, or fewer, to be taken back to
+    InvalidCapacity(usize),
+    /// The pool of threads of the setup cannot be started
+    #[cfg(feature = "parallel")]
+    ThreadPool,
+}
+
+impl fmt::Display for SetupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SetupError::NoParticipants => write!(f, "a channel needs at least one participant"),
+            SetupError::WrongSecret => write!(f, "secret does not belong to the channel"),
+            SetupError::NotIssued(identifier) => {
+                write!(f, "no key to retire for identifier {}", identifier)
+            }
+            SetupError::NoUnusedSlot => write!(f, "every slot of the channel is issued"),
+            SetupError::EmptyBuckets => write!(f, "buckets need at least one participant"),
+            SetupError::InvalidCapacity(capacity) => {
+                write!(f, "channel cannot be brought to {} slots", capacity)
+            }
+            #[cfg(feature = "parallel")]
+            SetupError::ThreadPool => write!(f, "threads of the setup cannot be started"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SetupError {}
+
+/// Reason why a batch of signed headers is refused.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BatchVerifyError {
+    /// The signatures of the headers at these positions do not verify
+    InvalidSignatures(Vec<usize>),
+}
+
+impl fmt::Display for BatchVerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BatchVerifyError::InvalidSignatures(positions) => {
+                write!(
+                    f,
+                    "{} signatures of the batch do not verify",
+                    positions.len()
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BatchVerifyError {}
+
+/// Reason why a key cannot be split into shares, or its shares combined.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ThresholdError {
+    /// The threshold is zero or larger than the number of shares
+    InvalidThreshold {
+        /// shares needed
+        threshold: usize,
+        /// shares produced
+        shares: usize,
+    },
+    /// Fewer shares than the threshold were given
+    NotEnoughShares {
+        /// shares given
+        shares: usize,
+        /// shares needed
+        threshold: usize,
+    },
+    /// The share with this index was given more than once
+    DuplicateShare(usize),
+    /// The share with this index does not match the commitments of the sharing
+    InvalidShare(usize),
+    /// The refresh dealing of the custodian with this index is refused
+    InvalidDealing(usize),
+}
+
+impl fmt::Display for ThresholdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThresholdError::InvalidThreshold { threshold, shares } => {
+                write!(
+                    f,
+                    "threshold {} is invalid for {} shares",
+                    threshold, shares
+                )
+            }
+            ThresholdError::NotEnoughShares { shares, threshold } => {
+                write!(f, "{} shares given, {} needed", shares, threshold)
+            }
+            ThresholdError::DuplicateShare(index) => {
+                write!(f, "share {} appears more than once", index)
+            }
+            ThresholdError::InvalidShare(index) => {
+                write!(f, "share {} does not match the commitments", index)
+            }
+            ThresholdError::InvalidDealing(dealer) => {
+                write!(f, "dealing of custodian {} is refused", dealer)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ThresholdError {}
+
+/// Reason why the transcript of a setup ceremony is refused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CeremonyError {
+    /// The ceremony has no contribution, so its secret is one
+    NoContribution,
+    /// The contribution at this position does not extend the transcript before it
+    InvalidContribution(usize),
+}
+
+impl fmt::Display for CeremonyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CeremonyError::NoContribution => write!(f, "ceremony has no contribution"),
+            CeremonyError::InvalidContribution(position) => {
+                write!(f, "contribution {} does not verify", position)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CeremonyError {}
+
+/// Reason why an encryption for a streamed set fails.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StreamError<S> {
+    /// The source of the identifiers failed
+    Source(S),
+    /// The identifiers read so far cannot be encrypted for
+    Encrypt(EncryptError),
+}
+
+impl<S: fmt::Display> fmt::Display for StreamError<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamError::Source(error) => write!(f, "reading the recipients failed: {}", error),
+            StreamError::Encrypt(error) => error.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<S: fmt::Debug + fmt::Display> std::error::Error for StreamError<S> {}
+
+/// Reason why an encryption or decryption for application identifiers fails.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DirectoryError<Id> {
+    /// The identifier is not mapped to a recipient of the channel
+    Unknown(Id),
+    /// The resolved set cannot be encrypted for
+    Encrypt(EncryptError),
+    /// The header cannot be decrypted for the resolved set
+    Decrypt(DecryptError),
+}
+
+impl<Id: fmt::Debug> fmt::Display for DirectoryError<Id> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DirectoryError::Unknown(id) => write!(f, "no recipient is mapped to {:?}", id),
+            DirectoryError::Encrypt(error) => error.fmt(f),
+            DirectoryError::Decrypt(error) => error.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<Id: fmt::Debug> std::error::Error for DirectoryError<Id> {}
+
+/// Reason why a device cannot be enrolled or revoked, or a set of users encrypted or decrypted
+/// for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeviceError<Id> {
+    /// The user has no device
+    Unknown(Id),
+    /// The identifier is not the one of a device
+    UnknownDevice(usize),
+    /// The key of the device cannot be issued or retired
+    Setup(SetupError),
+    /// The resolved set cannot be encrypted for
+    Encrypt(EncryptError),
+    /// The header cannot be decrypted for the resolved set
+    Decrypt(DecryptError),
+}
+
+impl<Id: fmt::Debug> fmt::Display for DeviceError<Id> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeviceError::Unknown(id) => write!(f, "{:?} has no device", id),
+            DeviceError::UnknownDevice(identifier) => {
+                write!(f, "identifier {} is not a device", identifier)
+            }
+            DeviceError::Setup(error) => error.fmt(f),
+            DeviceError::Encrypt(error) => error.fmt(f),
+            DeviceError::Decrypt(error) => error.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<Id: fmt::Debug> std::error::Error for DeviceError<Id> {}
+
+/// Reason why a symmetric key cannot be derived.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyDerivationError {
+    /// The requested output is longer than the derivation can produce
+    OutputTooLong,
+    /// The header was tagged with a derivation context version that is not accepted
+    UnsupportedVersion(u8),
+}
+
+impl fmt::Display for KeyDerivationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyDerivationError::OutputTooLong => write!(f, "requested key is too long"),
+            KeyDerivationError::UnsupportedVersion(version) => {
+                write!(
+                    f,
+                    "key derivation context version {} is not accepted",
+                    version
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for KeyDerivationError {}
+
+/// Reason why key material cannot be issued.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IssuanceError {
+    /// No key has been issued for this identifier
+    UnknownRecipient(usize),
+    /// The encoding would exceed the size limit
+    BundleTooLarge {
+        /// size of the encoding, in bytes
+        size: usize,
+        /// maximum allowed, in bytes
+        limit: usize,
+    },
+    /// Fewer administrators than required approved the issuance
+    InsufficientApprovals {
+        /// number of distinct valid approvals
+        valid: usize,
+        /// number of approvals required
+        threshold: usize,
+    },
+}
+
+impl fmt::Display for IssuanceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IssuanceError::UnknownRecipient(identifier) => {
+                write!(f, "no key issued for identifier {}", identifier)
+            }
+            IssuanceError::BundleTooLarge { size, limit } => {
+                write!(f, "bundle of {} bytes exceeds the limit of {}", size, limit)
+            }
+            IssuanceError::InsufficientApprovals { valid, threshold } => {
+                write!(f, "{} approvals out of the {} required", valid, threshold)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for IssuanceError {}
+
+/// Reason why a federation refuses a channel or an encryption.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FederationError {
+    /// The channel is not a member of the federation
+    NotMember(Fingerprint),
+    /// The channel is already a member of the federation
+    AlreadyMember(Fingerprint),
+    /// No valid certificate of `subject` by the dealer of `issuer` was presented
+    MissingCertificate {
+        /// channel whose dealer has to sign
+        issuer: Fingerprint,
+        /// channel to be certified
+        subject: Fingerprint,
+    },
+    /// One of the sets cannot be encrypted for
+    Encrypt(EncryptError),
+}
+
+impl fmt::Display for FederationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FederationError::NotMember(_) => write!(f, "channel is not part of the federation"),
+            FederationError::AlreadyMember(_) => {
+                write!(f, "channel is already part of the federation")
+            }
+            FederationError::MissingCertificate { .. } => {
+                write!(f, "missing cross-certificate between channels")
+            }
+            FederationError::Encrypt(error) => error.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FederationError {}
+
+/// Reason why a header of the CCA mode is refused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CcaError {
+    /// The header cannot be decrypted
+    Decrypt(DecryptError),
+    /// The tag does not confirm the key, so the header or the set was modified
+    TagMismatch,
+}
+
+impl fmt::Display for CcaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CcaError::Decrypt(error) => error.fmt(f),
+            CcaError::TagMismatch => write!(f, "tag does not confirm the key of the header"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CcaError {}
+
+/// Reason why a sealed payload cannot be decrypted.
+#[cfg(feature = "chacha20poly1305")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MessageError {
+    /// The header cannot be decrypted
+    Decrypt(DecryptError),
+    /// The payload was modified, or sealed under another header
+    Authentication,
+}
+
+#[cfg(feature = "chacha20poly1305")]
+impl fmt::Display for MessageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MessageError::Decrypt(error) => error.fmt(f),
+            MessageError::Authentication => write!(f, "payload failed authentication"),
+        }
+    }
+}
+
+#[cfg(all(feature = "chacha20poly1305", feature = "std"))]
+impl std::error::Error for MessageError {}
+
+/// Reason why a chunked stream cannot be opened.
+#[cfg(feature = "chacha20poly1305")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChunkError {
+    /// A chunk was modified, reordered, or sealed under another key
+    Authentication,
+    /// The stream ends before its last chunk
+    Truncated,
+}
+
+#[cfg(feature = "chacha20poly1305")]
+impl fmt::Display for ChunkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChunkError::Authentication => write!(f, "chunk failed authentication"),
+            ChunkError::Truncated => write!(f, "stream was truncated"),
+        }
+    }
+}
+
+#[cfg(all(feature = "chacha20poly1305", feature = "std"))]
+impl std::error::Error for ChunkError {}
+
+/// Reason why a call of the mobile bindings fails.
+#[cfg(feature = "mobile")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, uniffi::Error)]
+#[uniffi(flat_error)]
+pub enum MobileError {
+    /// An encoding does not decode
+    InvalidEncoding,
+    /// The channel cannot be set up
+    Setup(SetupError),
+    /// The set cannot be encrypted for
+    Encrypt(EncryptError),
+    /// The sealed payload cannot be decrypted
+    Message(MessageError),
+}
+
+#[cfg(feature = "mobile")]
+impl fmt::Display for MobileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MobileError::InvalidEncoding => write!(f, "encoding does not decode"),
+            MobileError::Setup(error) => error.fmt(f),
+            MobileError::Encrypt(error) => error.fmt(f),
+            MobileError::Message(error) => error.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "mobile")]
+impl std::error::Error for MobileError {}
+
+/// Reason why a message of a group session cannot be opened.
+#[cfg(feature = "chacha20poly1305")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GroupSessionError {
+    /// The sender of the message is not a sender of the session
+    UnknownSender(usize),
+    /// The message was already opened, or its key erased
+    Replayed,
+    /// The message is too far ahead of the last one of its sender
+    TooFarAhead,
+    /// The message was modified, or sealed in another session
+    Authentication,
+}
+
+#[cfg(feature = "chacha20poly1305")]
+impl fmt::Display for GroupSessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GroupSessionError::UnknownSender(sender) => {
+                write!(f, "{} is not a sender of the session", sender)
+            }
+            GroupSessionError::Replayed => write!(f, "message was already opened"),
+            GroupSessionError::TooFarAhead => {
+                write!(f, "message skips too many messages of its sender")
+            }
+            GroupSessionError::Authentication => write!(f, "message failed authentication"),
+        }
+    }
+}
+
+#[cfg(all(feature = "chacha20poly1305", feature = "std"))]
+impl std::error::Error for GroupSessionError {}
+
+/// Reason why a private key cannot be wrapped or unwrapped.
+#[cfg(feature = "key-wrap")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WrapError {
+    /// The key cannot be issued
+    Setup(SetupError),
+    /// The transport key has small order, so the wrapped key would be readable by anyone
+    WeakTransportKey,
+    /// The wrapped key was modified, or wrapped to another transport key or for another slot
+    Authentication,
+    /// The unwrapped key pair does not decode, or is not the one of its slot
+    InvalidKey,
+    /// The batch does not start at the first unused slot, which is this one
+    NotUnusedSlots(usize),
+    /// There is not one transport key per identifier of the batch, of which there are this many
+    TransportKeyCount(usize),
+}
+
+#[cfg(feature = "key-wrap")]
+impl fmt::Display for WrapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WrapError::Setup(error) => error.fmt(f),
+            WrapError::WeakTransportKey => write!(f, "transport key has small order"),
+            WrapError::Authentication => write!(f, "wrapped key failed authentication"),
+            WrapError::InvalidKey => write!(f, "wrapped key is not the key of its slot"),
+            WrapError::NotUnusedSlots(identifier) => {
+                write!(
+                    f,
+                    "batch does not start at the first unused slot {}",
+                    identifier
+                )
+            }
+            WrapError::TransportKeyCount(count) => {
+                write!(
+                    f,
+                    "batch of {} identifiers needs as many transport keys",
+                    count
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "key-wrap")]
+impl std::error::Error for WrapError {}
+
+/// Reason why a store of private keys fails.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StoreError {
+    /// The store holds no private key for this identifier
+    MissingKey(usize),
+    /// The store does not hold the secret of the broadcaster
+    MissingSecret,
+    /// The file of the store cannot be read or written
+    #[cfg(all(feature = "chacha20poly1305", feature = "std"))]
+    Io(std::io::ErrorKind),
+    /// The file of the store was modified, or sealed under another key
+    #[cfg(all(feature = "chacha20poly1305", feature = "std"))]
+    Authentication,
+    /// The opened file does not decode
+    #[cfg(all(feature = "chacha20poly1305", feature = "std"))]
+    Corrupted,
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::MissingKey(identifier) => {
+                write!(f, "no private key stored for identifier {}", identifier)
+            }
+            StoreError::MissingSecret => write!(f, "no broadcaster secret stored"),
+            #[cfg(all(feature = "chacha20poly1305", feature = "std"))]
+            StoreError::Io(kind) => write!(f, "key store file cannot be accessed: {:?}", kind),
+            #[cfg(all(feature = "chacha20poly1305", feature = "std"))]
+            StoreError::Authentication => write!(f, "key store file failed authentication"),
+            #[cfg(all(feature = "chacha20poly1305", feature = "std"))]
+            StoreError::Corrupted => write!(f, "key store file does not decode"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for StoreError {}
+
+/// Reason why an operation with secrets in a [`crate::keystore::SecretKeyStore`] fails.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyStoreError<S> {
+    /// The store failed
+    Store(S),
+    /// The header cannot be decrypted for the set
+    Decrypt(DecryptError),
+    /// The key cannot be issued
+    Setup(SetupError),
+}
+
+impl<S: fmt::Display> fmt::Display for KeyStoreError<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyStoreError::Store(error) => write!(f, "key store failed: {}", error),
+            KeyStoreError::Decrypt(error) => error.fmt(f),
+            KeyStoreError::Setup(error) => error.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<S: fmt::Debug + fmt::Display> std::error::Error for KeyStoreError<S> {}
+
+/// Reason why the membership state of a registry cannot be read or updated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RegistryError<S> {
+    /// The storage failed
+    Storage(S),
+    /// The channel has no key for the identifier, or refuses to retire it
+    Setup(SetupError),
+    /// The user is already registered
+    UserRegistered,
+    /// The identifier is already registered
+    IdentifierRegistered(usize),
+    /// The identifier is not registered
+    NotRegistered(usize),
+}
+
+impl<S: fmt::Display> fmt::Display for RegistryError<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::Storage(error) => write!(f, "registry storage failed: {}", error),
+            RegistryError::Setup(error) => error.fmt(f),
+            RegistryError::UserRegistered => write!(f, "user is already registered"),
+            RegistryError::IdentifierRegistered(identifier) => {
+                write!(f, "identifier {} is already registered", identifier)
+            }
+            RegistryError::NotRegistered(identifier) => {
+                write!(f, "identifier {} is not registered", identifier)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<S: fmt::Debug + fmt::Display> std::error::Error for RegistryError<S> {}
+
+/// Reason why the sled storage of a registry fails.
+#[cfg(all(feature = "sled", feature = "std"))]
+#[derive(Debug)]
+pub enum SledStorageError {
+    /// The database failed
+    Database(sled::Error),
+    /// A stored registration does not decode
+    Corrupted,
+}
+
+#[cfg(all(feature = "sled", feature = "std"))]
+impl fmt::Display for SledStorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SledStorageError::Database(error) => write!(f, "database failed: {}", error),
+            SledStorageError::Corrupted => write!(f, "stored registration does not decode"),
+        }
+    }
+}
+
+#[cfg(all(feature = "sled", feature = "std"))]
+impl std::error::Error for SledStorageError {}
+
+/// Reason why an operation on the channels of a manager fails.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ManagerError {
+    /// A channel with this identifier is already managed
+    DuplicateChannel,
+    /// No channel with this identifier is managed
+    UnknownChannel,
+    /// The recipient is not a participant of the channel of the header
+    WrongChannel,
+    /// The channel cannot be set up
+    Setup(SetupError),
+    /// The set cannot be encrypted for
+    Encrypt(EncryptError),
+    /// The header cannot be decrypted
+    Decrypt(DecryptError),
+}
+
+impl fmt::Display for ManagerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ManagerError::DuplicateChannel => write!(f, "channel is already managed"),
+            ManagerError::UnknownChannel => write!(f, "channel is not managed"),
+            ManagerError::WrongChannel => {
+                write!(
+                    f,
+                    "recipient is not a participant of the channel of the header"
+                )
+            }
+            ManagerError::Setup(error) => error.fmt(f),
+            ManagerError::Encrypt(error) => error.fmt(f),
+            ManagerError::Decrypt(error) => error.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ManagerError {}
+
+/// Reason why a delegated encryption or its decryption is refused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DelegationError {
+    /// The token does not allow encrypting for this recipient
+    OutsideDelegation(usize),
+    /// The signing key is not the key of the delegate of the token
+    WrongDelegate,
+    /// The token was not signed by the broadcaster for the channel
+    InvalidToken,
+    /// The set cannot be encrypted for
+    Encrypt(EncryptError),
+    /// The header cannot be decrypted
+    Decrypt(DecryptError),
+}
+
+impl fmt::Display for DelegationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DelegationError::OutsideDelegation(identifier) => {
+                write!(f, "recipient {} is outside the delegation", identifier)
+            }
+            DelegationError::WrongDelegate => write!(f, "key is not the one of the delegate"),
+            DelegationError::InvalidToken => write!(f, "encryption token does not verify"),
+            DelegationError::Encrypt(error) => error.fmt(f),
+            DelegationError::Decrypt(error) => error.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DelegationError {}
+
+/// Reason why an operation on a tree of groups fails.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GroupError {
+    /// The node is not a node of the tree
+    UnknownNode,
+    /// The recipient is already a member of a group
+    AlreadyMember(usize),
+    /// The members of the node cannot be encrypted for
+    Encrypt(EncryptError),
+}
+
+impl fmt::Display for GroupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GroupError::UnknownNode => write!(f, "node is not in the tree"),
+            GroupError::AlreadyMember(identifier) => {
+                write!(f, "recipient {} is already a member of a group", identifier)
+            }
+            GroupError::Encrypt(error) => error.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for GroupError {}
+
+/// Reason why a header cannot be re-encrypted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReEncryptionError {
+    /// The secret given is not the one the channel was set up with
+    WrongSecret,
+    /// The set of the header is not a set of the channel
+    Source(EncryptError),
+    /// The new set cannot be encrypted for
+    Encrypt(EncryptError),
+    /// The polynomial of the set of the header vanishes, which only happens with negligible
+    /// probability
+    Degenerate,
+}
+
+impl fmt::Display for ReEncryptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReEncryptionError::WrongSecret => write!(f, "secret does not belong to the channel"),
+            ReEncryptionError::Source(error) => write!(f, "set of the header: {}", error),
+            ReEncryptionError::Encrypt(error) => error.fmt(f),
+            ReEncryptionError::Degenerate => write!(f, "set of the header is degenerate"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ReEncryptionError {}
+
+/// Reason why a known-answer test cannot be computed or read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KatError {
+    /// The channel of the test cannot be set up
+    Setup(SetupError),
+    /// The set of the test cannot be encrypted for
+    Encrypt(EncryptError),
+    /// The line with this number is not a field, or names an unknown field
+    Syntax(usize),
+    /// The value on the line with this number does not decode
+    InvalidValue(usize),
+    /// The record ending on the line with this number lacks a field
+    MissingField(usize),
+}
+
+impl fmt::Display for KatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KatError::Setup(error) => error.fmt(f),
+            KatError::Encrypt(error) => error.fmt(f),
+            KatError::Syntax(line) => write!(f, "line {} is not a known field", line),
+            KatError::InvalidValue(line) => write!(f, "value on line {} does not decode", line),
+            KatError::MissingField(line) => {
+                write!(f, "record ending on line {} lacks a field", line)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for KatError {}
+
+/// Reason why a file of known-answer tests cannot be checked.
+#[cfg(feature = "testvectors")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TestVectorError {
+    /// The file or the directory cannot be read
+    Io(std::io::ErrorKind),
+    /// The file is not in the format of [`crate::kat`], or a test cannot be computed
+    Kat(KatError),
+}
+
+#[cfg(feature = "testvectors")]
+impl fmt::Display for TestVectorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TestVectorError::Io(kind) => write!(f, "test vectors cannot be read: {:?}", kind),
+            TestVectorError::Kat(error) => error.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "testvectors")]
+impl std::error::Error for TestVectorError {}
+
+/// Reason why a transcript cannot be extended or verified.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TranscriptError {
+    /// The exported transcript is truncated, or longer than its entries
+    Malformed,
+    /// The timestamp of the entry at this position is before the one of the entry before
+    OutOfOrder(usize),
+    /// The digest of the entry at this position does not follow from the ones before
+    BrokenChain(usize),
+}
+
+impl fmt::Display for TranscriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TranscriptError::Malformed => f.write_str("transcript is malformed"),
+            TranscriptError::OutOfOrder(index) => {
+                write!(f, "entry {} is timestamped before the one before it", index)
+            }
+            TranscriptError::BrokenChain(index) => {
+                write!(f, "entry {} does not follow from the ones before it", index)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TranscriptError {}
+
+/// Reason why headers for several subsets cannot be encrypted or decrypted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MultiHeaderError {
+    /// No subsets were given
+    NoSubsets,
+    /// This identifier is in more than one subset
+    Overlap(usize),
+    /// A subset cannot be encrypted for
+    Encrypt(EncryptError),
+    /// The recipient is in none of the subsets
+    NotInSubsets,
+    /// The header of the subset of the recipient cannot be decrypted
+    Decrypt(DecryptError),
+    /// The content key or the payload was modified, or sealed under other headers
+    Authentication,
+}
+
+impl fmt::Display for MultiHeaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MultiHeaderError::NoSubsets => write!(f, "no subsets were given"),
+            MultiHeaderError::Overlap(identifier) => {
+                write!(f, "recipient {} is in more than one subset", identifier)
+            }
+            MultiHeaderError::Encrypt(error) => error.fmt(f),
+            MultiHeaderError::NotInSubsets => write!(f, "recipient is in none of the subsets"),
+            MultiHeaderError::Decrypt(error) => error.fmt(f),
+            MultiHeaderError::Authentication => write!(f, "payload failed authentication"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MultiHeaderError {}
+
+/// Reason why an encryption or a decryption against a [`crate::store::ParamStore`] fails.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParamStoreError<S> {
+    /// The store failed to hand out the points
+    Store(S),
+    /// The store handed out a number of points other than the one asked for
+    WrongLength {
+        /// number of points asked for
+        expected: usize,
+        /// number of points handed out
+        found: usize,
+    },
+    /// The set cannot be encrypted for
+    Encrypt(EncryptError),
+    /// The header cannot be decrypted for the set
+    Decrypt(DecryptError),
+}
+
+impl<S: fmt::Display> fmt::Display for ParamStoreError<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParamStoreError::Store(error) => write!(f, "parameter store failed: {}", error),
+            ParamStoreError::WrongLength { expected, found } => write!(
+                f,
+                "parameter store handed out {} points instead of {}",
+                found, expected
+            ),
+            ParamStoreError::Encrypt(error) => error.fmt(f),
+            ParamStoreError::Decrypt(error) => error.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<S: fmt::Debug + fmt::Display> std::error::Error for ParamStoreError<S> {}
+
+/// Reason why a [`crate::store::VerifiedStore`] does not hand out points.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerifiedStoreError<S> {
+    /// The underlying store failed to hand out the points
+    Store(S),
+    /// The point of G1 at this position does not match the trusted points
+    Unverified(usize),
+}
+
+impl<S: fmt::Display> fmt::Display for VerifiedStoreError<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifiedStoreError::Store(error) => write!(f, "parameter store failed: {}", error),
+            VerifiedStoreError::Unverified(position) => {
+                write!(
+                    f,
+                    "point at position {} does not match the trusted points",
+                    position
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<S: fmt::Debug + fmt::Display> std::error::Error for VerifiedStoreError<S> {}
+
+/// Reason why parameters cannot be read from an object with [`crate::object::ObjectParams`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ObjectStoreError<O> {
+    /// The object cannot be read
+    Object(O),
+    /// The object does not hold the encoding of a channel, or a point of it does not decode
+    Malformed,
+}
+
+impl<O: fmt::Display> fmt::Display for ObjectStoreError<O> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjectStoreError::Object(error) => write!(f, "object cannot be read: {}", error),
+            ObjectStoreError::Malformed => write!(f, "object does not hold a channel"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<O: fmt::Debug + fmt::Display> std::error::Error for ObjectStoreError<O> {}
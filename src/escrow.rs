@@ -0,0 +1,280 @@
+//! Escrow of the secret of the trusted entity among custodians.
+//!
+//! A [`BroadcasterSecret`] stored in one place is lost with it, and copies of it are as many
+//! places to steal it from. [`BroadcasterSecret::escrow`] splits `alpha` and `gamma` with Shamir
+//! sharing into `m` [`EscrowShare`]s, one per custodian, `t` of which rebuild the secret with
+//! [`BroadcasterSecret::recover_from_shares`]. Any `t - 1` shares are independent of the secret.
+//!
+//! The sharing is verifiable after Feldman: the polynomials have their coefficients `a_k`
+//! committed to as `a_k P` in the [`EscrowCommitments`], which are public. A custodian checks its
+//! share `(x, f(x))` against them with `f(x) P = sum_k x^k (a_k P)`, and the recovery refuses the
+//! shares that fail. The constant terms commit to `alpha P` and `gamma P = V`, which are public
+//! parameters of the channel, so [`EscrowCommitments::belongs_to`] tells which channel the
+//! escrow is for. The commitments are encoded as the two lists of points.
+//!
+//! Shares are renewed without recovering the secret by the rounds of [`crate::refresh`].
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{UniformRand, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use rand_core::{CryptoRng, RngCore};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::encoding::{from_bytes, to_bytes};
+use crate::error::ThresholdError;
+use crate::secret::BroadcasterSecret;
+use crate::threshold::{check_threshold, lagrange_at_zero};
+use crate::BroadcastChannel;
+
+/// Share of the secret of a channel, held by one custodian.
+pub struct EscrowShare<E: PairingEngine> {
+    /// point at which the polynomials were evaluated, in `1..=m`
+    pub(crate) index: usize,
+    pub(crate) alpha: E::Fr,
+    pub(crate) gamma: E::Fr,
+}
+
+impl<E: PairingEngine> Drop for EscrowShare<E> {
+    fn drop(&mut self) {
+        self.alpha.zeroize();
+        self.gamma.zeroize();
+    }
+}
+
+impl<E: PairingEngine> ZeroizeOnDrop for EscrowShare<E> {}
+
+/// Commitments to the coefficients of the polynomials sharing `alpha` and `gamma`.
+#[derive(Clone, PartialEq, Eq)]
+pub struct EscrowCommitments<E: PairingEngine> {
+    pub(crate) alpha: Vec<E::G1Projective>,
+    pub(crate) gamma: Vec<E::G1Projective>,
+}
+
+/// Value at `index` of the polynomial with `coefficients`, from the constant term up.
+pub(crate) fn evaluate<E: PairingEngine>(coefficients: &[E::Fr], index: usize) -> E::Fr {
+    let x = E::Fr::from(index as u64);
+    coefficients
+        .iter()
+        .rev()
+        .fold(E::Fr::zero(), |value, coefficient| value * x + coefficient)
+}
+
+/// Value at `index` of the polynomial committed to by `commitments`, times `P`.
+pub(crate) fn evaluate_committed<E: PairingEngine>(
+    commitments: &[E::G1Projective],
+    index: usize,
+) -> E::G1Projective {
+    let x = E::Fr::from(index as u64);
+    let mut value = E::G1Projective::zero();
+    for commitment in commitments.iter().rev() {
+        value *= x;
+        value += commitment;
+    }
+    value
+}
+
+/// Commitments to `coefficients`.
+pub(crate) fn commit<E: PairingEngine>(coefficients: &[E::Fr]) -> Vec<E::G1Projective> {
+    coefficients
+        .iter()
+        .map(|coefficient| {
+            let mut commitment = E::G1Projective::prime_subgroup_generator();
+            commitment *= *coefficient;
+            commitment
+        })
+        .collect()
+}
+
+impl<E: PairingEngine> BroadcasterSecret<E> {
+    /// Split the secret into `shares` shares, `threshold` of which recover it, with the
+    /// commitments checking them. Fails if `threshold` is zero or larger than `shares`.
+    pub fn escrow<R>(
+        &self,
+        threshold: usize,
+        shares: usize,
+        rng: &mut R,
+    ) -> Result<(EscrowCommitments<E>, Vec<EscrowShare<E>>), ThresholdError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        check_threshold(threshold, shares)?;
+        let mut alpha_coefficients = vec![self.alpha];
+        let mut gamma_coefficients = vec![self.gamma];
+        for _ in 1..threshold {
+            alpha_coefficients.push(E::Fr::rand(rng));
+            gamma_coefficients.push(E::Fr::rand(rng));
+        }
+
+        let commitments = EscrowCommitments {
+            alpha: commit::<E>(&alpha_coefficients),
+            gamma: commit::<E>(&gamma_coefficients),
+        };
+        let escrow_shares = (1..=shares)
+            .map(|index| EscrowShare {
+                index,
+                alpha: evaluate::<E>(&alpha_coefficients, index),
+                gamma: evaluate::<E>(&gamma_coefficients, index),
+            })
+            .collect();
+        alpha_coefficients.zeroize();
+        gamma_coefficients.zeroize();
+        Ok((commitments, escrow_shares))
+    }
+
+    /// Secret shared by `shares`, at least the threshold of `commitments` of them. Fails if there
+    /// are fewer, if two shares have the same index, or if a share does not match the
+    /// commitments.
+    pub fn recover_from_shares(
+        commitments: &EscrowCommitments<E>,
+        shares: &[EscrowShare<E>],
+    ) -> Result<Self, ThresholdError> {
+        let threshold = commitments.threshold();
+        if shares.len() < threshold {
+            return Err(ThresholdError::NotEnoughShares {
+                shares: shares.len(),
+                threshold,
+            });
+        }
+        if let Some(position) = (1..shares.len()).find(|position| {
+            shares[..*position]
+                .iter()
+                .any(|share| share.index == shares[*position].index)
+        }) {
+            return Err(ThresholdError::DuplicateShare(shares[position].index));
+        }
+        if let Some(share) = shares.iter().find(|share| !commitments.verify(share)) {
+            return Err(ThresholdError::InvalidShare(share.index));
+        }
+
+        let shares = &shares[..threshold];
+        let indices: Vec<usize> = shares.iter().map(|share| share.index).collect();
+        let coefficients = lagrange_at_zero::<E::Fr>(&indices);
+        let mut secret = BroadcasterSecret {
+            alpha: E::Fr::zero(),
+            gamma: E::Fr::zero(),
+        };
+        for (share, coefficient) in shares.iter().zip(coefficients.iter()) {
+            secret.alpha += share.alpha * coefficient;
+            secret.gamma += share.gamma * coefficient;
+        }
+        Ok(secret)
+    }
+}
+
+impl<E: PairingEngine> EscrowShare<E> {
+    /// Point at which the share was evaluated, in `1..=m`.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl<E: PairingEngine> EscrowCommitments<E> {
+    /// Number of shares recovering the secret.
+    pub fn threshold(&self) -> usize {
+        self.alpha.len()
+    }
+
+    /// Whether `share` is the share of its index committed to.
+    pub fn verify(&self, share: &EscrowShare<E>) -> bool {
+        if share.index == 0 {
+            return false;
+        }
+        let mut alpha = E::G1Projective::prime_subgroup_generator();
+        alpha *= share.alpha;
+        let mut gamma = E::G1Projective::prime_subgroup_generator();
+        gamma *= share.gamma;
+        alpha == evaluate_committed::<E>(&self.alpha, share.index)
+            && gamma == evaluate_committed::<E>(&self.gamma, share.index)
+    }
+
+    /// Whether the escrowed secret is the one `channel` was set up with.
+    pub fn belongs_to(&self, channel: &BroadcastChannel<E>) -> bool {
+        self.alpha[0] == channel.broadcaster_pk_g1[1].into_projective()
+            && self.gamma[0] == channel.point_v
+    }
+
+    /// Compressed encoding of the commitments.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+}
+
+impl<E: PairingEngine> CanonicalSerialize for EscrowCommitments<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.alpha.serialize(&mut writer)?;
+        self.gamma.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.alpha.serialized_size() + self.gamma.serialized_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for EscrowCommitments<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let alpha = Vec::deserialize(&mut reader)?;
+        let gamma = Vec::deserialize(&mut reader)?;
+        // both polynomials have the degree of the threshold, which is at least one
+        if alpha.is_empty() || alpha.len() != gamma.len() {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(EscrowCommitments { alpha, gamma })
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for EscrowCommitments<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn custodians_recover_the_secret() {
+        let mut rng = thread_rng();
+        let (channel, _, secret) =
+            BroadcastChannel::<Bls12_381>::init_with_secret(3, &mut rng).unwrap();
+        let (commitments, mut shares) = secret.escrow(3, 5, &mut rng).unwrap();
+        assert!(commitments.belongs_to(&channel));
+        assert!(shares.iter().all(|share| commitments.verify(share)));
+        let decoded = EscrowCommitments::try_from(commitments.to_bytes().as_slice()).unwrap();
+        assert!(decoded == commitments);
+
+        let kept: Vec<EscrowShare<Bls12_381>> = shares.drain(2..).collect();
+        let recovered = BroadcasterSecret::recover_from_shares(&commitments, &kept).unwrap();
+        assert!(recovered.belongs_to(&channel));
+        assert!(matches!(
+            BroadcasterSecret::recover_from_shares(&commitments, &shares),
+            Err(ThresholdError::NotEnoughShares {
+                shares: 2,
+                threshold: 3
+            })
+        ));
+
+        let mut tampered = kept;
+        let shift = tampered[0].gamma;
+        tampered[1].gamma += shift;
+        assert!(matches!(
+            BroadcasterSecret::recover_from_shares(&commitments, &tampered),
+            Err(ThresholdError::InvalidShare(4))
+        ));
+        assert!(matches!(
+            secret.escrow(0, 5, &mut rng),
+            Err(ThresholdError::InvalidThreshold {
+                threshold: 0,
+                shares: 5
+            })
+        ));
+    }
+}
@@ -0,0 +1,196 @@
+//! Typed audit events.
+//!
+//! Applications embedding the library can register an [`EventSink`] on a [`BroadcastChannel`] to
+//! be told when keys are issued, when headers are produced and when a decryption is known to fail,
+//! and forward these to their own monitoring pipelines. Events never carry key material: the set
+//! of recipients of an encryption is identified by its hash only.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use ark_ec::PairingEngine;
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha256};
+
+use crate::error::{DecryptError, SetupError};
+use crate::set::{RecipientSet, SET_ENCODING_VERSION};
+use crate::{BroadcastChannel, Recipient};
+
+/// Domain separation tag of the hash identifying a set of recipients.
+const SET_HASH_TAG: &[u8] = b"DGS-BE-RECIPIENT-SET-V1";
+
+/// Event reported to the sink of a channel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// The key of participant `identifier` has been issued
+    KeyIssued {
+        /// identifier of the participant
+        identifier: usize,
+    },
+    /// The key of participant `identifier` has been retired, see [`crate::revocation`]
+    KeyRetired {
+        /// identifier of the participant
+        identifier: usize,
+    },
+    /// Administrator `administrator` approved issuance `serial` of the key of participant
+    /// `identifier`, see [`crate::quorum`]
+    IssuanceApproved {
+        /// identifier of the participant
+        identifier: usize,
+        /// serial number of the issuance
+        serial: u64,
+        /// position of the administrator in the quorum
+        administrator: usize,
+    },
+    /// A header has been produced for the set of recipients with hash `set_hash`
+    Encrypted {
+        /// hash of the set of recipients, see [`set_hash`]
+        set_hash: [u8; 32],
+    },
+    /// A header has been produced for a set of `recipients` read from a stream, which cannot be
+    /// hashed canonically without being collected first
+    EncryptedStream {
+        /// number of identifiers read, repetitions included
+        recipients: usize,
+        /// SHA-256 of the identifiers in the order they were read, see [`crate::stream`]
+        digest: [u8; 32],
+    },
+    /// Participant `identifier` tried to decrypt a header and was refused
+    DecryptFailed {
+        /// identifier of the participant
+        identifier: usize,
+        /// why the decryption fails
+        reason: DecryptError,
+    },
+}
+
+/// Receiver of the events of a channel. Sinks are shared between clones of a channel, and may
+/// be called from several threads.
+pub trait EventSink: Send + Sync {
+    /// Record `event`. This is called synchronously, so slow sinks should queue the event and
+    /// return.
+    fn record(&self, event: &Event);
+}
+
+/// Hash identifying a set of recipients, computed over its canonical encoding, so independent
+/// of the order and repetitions of the identifiers.
+pub fn set_hash(set_recipients: &[usize]) -> [u8; 32] {
+    let set = RecipientSet::new(set_recipients);
+    sorted_set_hash(set.len(), set.iter())
+}
+
+/// Same as [`set_hash`], for the `len` identifiers of a set given in increasing order and
+/// without repetitions, hashed as they come rather than encoded first.
+pub(crate) fn sorted_set_hash<I>(len: usize, identifiers: I) -> [u8; 32]
+where
+    I: Iterator<Item = usize>,
+{
+    let mut hasher = Sha256::new();
+    hasher.update(SET_HASH_TAG);
+    hasher.update([SET_ENCODING_VERSION]);
+    hasher.update((len as u64).to_le_bytes());
+    for identifier in identifiers {
+        hasher.update((identifier as u64).to_le_bytes());
+    }
+
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&hasher.finalize());
+    hash
+}
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Same as [`BroadcastChannel::init_participants`], registering `sink` on the channel before
+    /// the keys are issued, so that it is told about each of them.
+    pub fn init_with_event_sink<R>(
+        n: usize,
+        sink: Arc<dyn EventSink>,
+        rng: &mut R,
+    ) -> Result<(Self, Vec<Recipient<E>>), SetupError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let (mut channel, participants) = Self::init_participants(n, rng)?;
+        channel.set_event_sink(sink);
+        for participant in participants.iter() {
+            channel.emit(Event::KeyIssued {
+                identifier: participant.identifier,
+            });
+        }
+
+        Ok((channel, participants))
+    }
+
+    /// Register `sink` to receive the events of this channel, replacing any previous one.
+    pub fn set_event_sink(&mut self, sink: Arc<dyn EventSink>) {
+        self.event_sink = Some(sink);
+    }
+
+    /// Stop reporting events.
+    pub fn clear_event_sink(&mut self) {
+        self.event_sink = None;
+    }
+
+    pub(crate) fn emit(&self, event: Event) {
+        trace_event!(DEBUG, ?event, "channel event");
+        if let Some(sink) = &self.event_sink {
+            sink.record(&event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Collect(Mutex<Vec<Event>>);
+
+    impl EventSink for Collect {
+        fn record(&self, event: &Event) {
+            self.0.lock().unwrap().push(*event);
+        }
+    }
+
+    #[test]
+    fn events_are_reported() {
+        let mut rng = thread_rng();
+        let sink = Arc::new(Collect::default());
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_with_event_sink(3, sink.clone(), &mut rng).unwrap();
+
+        let recipients = [3, 1];
+        let (header, _) = channel.encrypt(&recipients, &mut rng).unwrap();
+        assert!(participants[0]
+            .decrypt(&recipients, &channel, &header)
+            .is_ok());
+        assert!(participants[1]
+            .decrypt(&recipients, &channel, &header)
+            .is_err());
+
+        assert_eq!(set_hash(&recipients), set_hash(&[1, 3, 3]));
+        assert_ne!(set_hash(&recipients), set_hash(&[1, 2]));
+        assert_eq!(
+            *sink.0.lock().unwrap(),
+            vec![
+                Event::KeyIssued { identifier: 1 },
+                Event::KeyIssued { identifier: 2 },
+                Event::KeyIssued { identifier: 3 },
+                Event::Encrypted {
+                    set_hash: set_hash(&recipients)
+                },
+                Event::DecryptFailed {
+                    identifier: 2,
+                    reason: DecryptError::NotInSet
+                },
+            ]
+        );
+
+        let mut silent = channel.clone();
+        silent.clear_event_sink();
+        silent.encrypt(&recipients, &mut rng).unwrap();
+        assert_eq!(sink.0.lock().unwrap().len(), 5);
+    }
+}
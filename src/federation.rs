@@ -0,0 +1,420 @@
+//! Federation of channels run by different dealers.
+//!
+//! Organizations operating their own channels can federate them, so that one encryption reaches
+//! sets spread across all of them. Every dealer holds a [`SigningKey`], and trust is established
+//! by cross-certification: a [`CrossCertificate`] is the signature by the dealer of one channel
+//! of the fingerprint of another. A [`Federation`] only admits a channel once every member has
+//! certified it and it has certified every member, so every pair of members vouches for each
+//! other.
+//!
+//! [`Federation::encrypt`] draws a random 32-byte content key and encrypts a header for each
+//! target set, each in its own channel. The content key is wrapped under a key derived from the
+//! key of each header, so members of any of the sets recover the same content key from the
+//! [`FederatedEnvelope`] with [`Recipient::open_envelope`].
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use ark_ec::PairingEngine;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use rand_core::{CryptoRng, RngCore};
+
+use crate::encoding::{from_bytes, to_bytes};
+use crate::error::{DecryptError, FederationError};
+use crate::kdf::{derive_key, HkdfSha256};
+use crate::set::RecipientSet;
+use crate::signature::{Signature, SigningKey, VerifyingKey};
+use crate::view::Fingerprint;
+use crate::{BroadcastChannel, Header, Recipient};
+
+/// Domain separation tag of the messages signed by cross-certificates.
+const CERTIFICATE_TAG: &[u8] = b"DGS-BE-CROSS-CERTIFICATE-V1";
+
+/// Domain separation tag of the keys wrapping the content key.
+const WRAP_TAG: &[u8] = b"DGS-BE-FEDERATION-WRAP-V1";
+
+/// Statement by the dealer of channel `issuer` that it trusts channel `subject`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CrossCertificate<E: PairingEngine> {
+    issuer: Fingerprint,
+    subject: Fingerprint,
+    signature: Signature<E>,
+}
+
+impl<E: PairingEngine> CrossCertificate<E> {
+    /// Certificate of `subject` by the dealer of `issuer`, holding `key`.
+    pub fn issue(key: &SigningKey<E>, issuer: Fingerprint, subject: Fingerprint) -> Self {
+        CrossCertificate {
+            issuer,
+            subject,
+            signature: key.sign(&certificate_message(issuer, subject)),
+        }
+    }
+
+    /// Channel of the dealer that signed the certificate.
+    pub fn issuer(&self) -> Fingerprint {
+        self.issuer
+    }
+
+    /// Channel certified.
+    pub fn subject(&self) -> Fingerprint {
+        self.subject
+    }
+
+    /// Whether the certificate was signed with the key of `dealer`.
+    pub fn verify(&self, dealer: &VerifyingKey<E>) -> bool {
+        dealer.verify(
+            &certificate_message(self.issuer, self.subject),
+            &self.signature,
+        )
+    }
+
+    /// Encoding of the certificate.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+}
+
+fn certificate_message(issuer: Fingerprint, subject: Fingerprint) -> Vec<u8> {
+    let mut message = CERTIFICATE_TAG.to_vec();
+    message.extend_from_slice(issuer.as_ref());
+    message.extend_from_slice(subject.as_ref());
+    message
+}
+
+/// Channels whose dealers have all certified each other.
+#[derive(Clone, Debug)]
+pub struct Federation<E: PairingEngine> {
+    members: Vec<(Fingerprint, VerifyingKey<E>)>,
+}
+
+impl<E: PairingEngine> Federation<E> {
+    /// Federation of the single channel `fingerprint`, whose dealer has key `dealer`.
+    pub fn new(fingerprint: Fingerprint, dealer: VerifyingKey<E>) -> Self {
+        Federation {
+            members: vec![(fingerprint, dealer)],
+        }
+    }
+
+    /// Admit channel `fingerprint`, whose dealer has key `dealer`. `certificates` must contain a
+    /// valid certificate of the channel by every member, and of every member by the channel.
+    pub fn admit(
+        &mut self,
+        fingerprint: Fingerprint,
+        dealer: VerifyingKey<E>,
+        certificates: &[CrossCertificate<E>],
+    ) -> Result<(), FederationError> {
+        if self.is_member(fingerprint) {
+            return Err(FederationError::AlreadyMember(fingerprint));
+        }
+        let certified = |issuer: Fingerprint, key: &VerifyingKey<E>, subject: Fingerprint| {
+            certificates.iter().any(|certificate| {
+                certificate.issuer == issuer
+                    && certificate.subject == subject
+                    && certificate.verify(key)
+            })
+        };
+        for (member, key) in self.members.iter() {
+            if !certified(*member, key, fingerprint) {
+                return Err(FederationError::MissingCertificate {
+                    issuer: *member,
+                    subject: fingerprint,
+                });
+            }
+            if !certified(fingerprint, &dealer, *member) {
+                return Err(FederationError::MissingCertificate {
+                    issuer: fingerprint,
+                    subject: *member,
+                });
+            }
+        }
+        self.members.push((fingerprint, dealer));
+        Ok(())
+    }
+
+    /// Whether channel `fingerprint` is a member.
+    pub fn is_member(&self, fingerprint: Fingerprint) -> bool {
+        self.members
+            .iter()
+            .any(|(member, _)| *member == fingerprint)
+    }
+
+    /// Key of the dealer of member `fingerprint`.
+    pub fn dealer(&self, fingerprint: Fingerprint) -> Option<&VerifyingKey<E>> {
+        self.members
+            .iter()
+            .find(|(member, _)| *member == fingerprint)
+            .map(|(_, key)| key)
+    }
+
+    /// Encrypt a fresh content key for every `(channel, set)` of `targets`, returning the
+    /// envelope and the content key. Every channel must be a member.
+    pub fn encrypt<R>(
+        &self,
+        targets: &[(&BroadcastChannel<E>, &[usize])],
+        rng: &mut R,
+    ) -> Result<(FederatedEnvelope<E>, [u8; 32]), FederationError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let mut content_key = [0u8; 32];
+        rng.fill_bytes(&mut content_key);
+
+        let mut entries = Vec::with_capacity(targets.len());
+        for (channel, set_recipients) in targets.iter() {
+            let fingerprint = channel.view().fingerprint();
+            if !self.is_member(fingerprint) {
+                return Err(FederationError::NotMember(fingerprint));
+            }
+            let (header, key) = channel
+                .encrypt(set_recipients, rng)
+                .map_err(FederationError::Encrypt)?;
+            let mut wrapped = wrapping_key::<E>(key.key(), fingerprint);
+            for (byte, content) in wrapped.iter_mut().zip(content_key.iter()) {
+                *byte ^= content;
+            }
+            entries.push(EnvelopeEntry {
+                fingerprint,
+                set: RecipientSet::new(set_recipients),
+                header,
+                wrapped,
+            });
+        }
+        Ok((FederatedEnvelope { entries }, content_key))
+    }
+}
+
+/// Key wrapping the content key under the key `key` of a header of channel `fingerprint`.
+fn wrapping_key<E: PairingEngine>(key: &E::Fqk, fingerprint: Fingerprint) -> [u8; 32] {
+    let mut info = WRAP_TAG.to_vec();
+    info.extend_from_slice(fingerprint.as_ref());
+    let mut wrapping = [0u8; 32];
+    // 32 bytes are within the output of HKDF
+    derive_key::<E, HkdfSha256>(key, &info, &mut wrapping).unwrap();
+    wrapping
+}
+
+/// Header of an envelope, for one set of one channel.
+#[derive(Clone, PartialEq, Eq)]
+pub struct EnvelopeEntry<E: PairingEngine> {
+    fingerprint: Fingerprint,
+    set: RecipientSet,
+    header: Header<E>,
+    wrapped: [u8; 32],
+}
+
+impl<E: PairingEngine> EnvelopeEntry<E> {
+    /// Channel of the header.
+    pub fn fingerprint(&self) -> Fingerprint {
+        self.fingerprint
+    }
+
+    /// Set the header was encrypted for.
+    pub fn set(&self) -> &RecipientSet {
+        &self.set
+    }
+
+    /// Header of the entry.
+    pub fn header(&self) -> &Header<E> {
+        &self.header
+    }
+}
+
+/// Content key encrypted for sets of several federated channels.
+#[derive(Clone, PartialEq, Eq)]
+pub struct FederatedEnvelope<E: PairingEngine> {
+    entries: Vec<EnvelopeEntry<E>>,
+}
+
+impl<E: PairingEngine> FederatedEnvelope<E> {
+    /// Entries, in the order of the targets.
+    pub fn entries(&self) -> &[EnvelopeEntry<E>] {
+        &self.entries
+    }
+
+    /// Encoding of the envelope.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+}
+
+impl<E: PairingEngine> Recipient<E> {
+    /// Content key of `envelope`, from the first entry of `channel` whose set contains this
+    /// recipient. Returns `None` if there is no such entry.
+    pub fn open_envelope(
+        &self,
+        channel: &BroadcastChannel<E>,
+        envelope: &FederatedEnvelope<E>,
+    ) -> Option<Result<[u8; 32], DecryptError>> {
+        let fingerprint = channel.view().fingerprint();
+        let entry = envelope.entries.iter().find(|entry| {
+            entry.fingerprint == fingerprint && entry.set.contains(self.identifier)
+        })?;
+        Some(
+            self.decrypt(entry.set.as_slice(), channel, &entry.header)
+                .map(|key| {
+                    let mut content_key = wrapping_key::<E>(key.key(), fingerprint);
+                    for (byte, wrapped) in content_key.iter_mut().zip(entry.wrapped.iter()) {
+                        *byte ^= wrapped;
+                    }
+                    content_key
+                }),
+        )
+    }
+}
+
+impl<E: PairingEngine> CanonicalSerialize for CrossCertificate<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        writer.write_all(self.issuer.as_ref())?;
+        writer.write_all(self.subject.as_ref())?;
+        self.signature.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        64 + self.signature.serialized_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for CrossCertificate<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let mut issuer = [0u8; 32];
+        reader.read_exact(&mut issuer)?;
+        let mut subject = [0u8; 32];
+        reader.read_exact(&mut subject)?;
+        Ok(CrossCertificate {
+            issuer: Fingerprint::from(issuer),
+            subject: Fingerprint::from(subject),
+            signature: Signature::deserialize(&mut reader)?,
+        })
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for CrossCertificate<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+impl<E: PairingEngine> CanonicalSerialize for EnvelopeEntry<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        writer.write_all(self.fingerprint.as_ref())?;
+        self.set.as_slice().to_vec().serialize(&mut writer)?;
+        self.header.serialize(&mut writer)?;
+        writer.write_all(&self.wrapped)?;
+        Ok(())
+    }
+
+    fn serialized_size(&self) -> usize {
+        32 + self.set.as_slice().to_vec().serialized_size() + self.header.serialized_size() + 32
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for EnvelopeEntry<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let mut fingerprint = [0u8; 32];
+        reader.read_exact(&mut fingerprint)?;
+        let identifiers = Vec::<usize>::deserialize(&mut reader)?;
+        // sets are written in canonical order, anything else is not an encoded envelope
+        if !identifiers.windows(2).all(|pair| pair[0] < pair[1]) {
+            return Err(SerializationError::InvalidData);
+        }
+        let header = Header::deserialize(&mut reader)?;
+        let mut wrapped = [0u8; 32];
+        reader.read_exact(&mut wrapped)?;
+        Ok(EnvelopeEntry {
+            fingerprint: Fingerprint::from(fingerprint),
+            set: RecipientSet::new(&identifiers),
+            header,
+            wrapped,
+        })
+    }
+}
+
+impl<E: PairingEngine> CanonicalSerialize for FederatedEnvelope<E> {
+    fn serialize<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        self.entries.serialize(writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.entries.serialized_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for FederatedEnvelope<E> {
+    fn deserialize<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        Ok(FederatedEnvelope {
+            entries: Vec::deserialize(reader)?,
+        })
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for FederatedEnvelope<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn federated_envelopes_open_in_every_channel() {
+        let mut rng = thread_rng();
+        let (first, first_participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(4, &mut rng).unwrap();
+        let (second, second_participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(6, &mut rng).unwrap();
+        let (first_id, second_id) = (first.view().fingerprint(), second.view().fingerprint());
+        let first_dealer = SigningKey::<Bls12_381>::generate(&mut rng);
+        let second_dealer = SigningKey::<Bls12_381>::generate(&mut rng);
+
+        let mut federation = Federation::new(first_id, first_dealer.verifying_key());
+        let inbound = CrossCertificate::issue(&first_dealer, first_id, second_id);
+        let outbound = CrossCertificate::issue(&second_dealer, second_id, first_id);
+        let forged = CrossCertificate::issue(&second_dealer, first_id, second_id);
+        assert_eq!(
+            federation.admit(
+                second_id,
+                second_dealer.verifying_key(),
+                &[forged, outbound]
+            ),
+            Err(FederationError::MissingCertificate {
+                issuer: first_id,
+                subject: second_id
+            })
+        );
+        assert!(federation.encrypt(&[(&second, &[1])], &mut rng).is_err());
+
+        let decoded = CrossCertificate::try_from(inbound.to_bytes().as_slice()).unwrap();
+        federation
+            .admit(
+                second_id,
+                second_dealer.verifying_key(),
+                &[decoded, outbound],
+            )
+            .unwrap();
+        assert!(federation.is_member(second_id));
+
+        let (envelope, content_key) = federation
+            .encrypt(&[(&first, &[1, 3]), (&second, &[2, 5, 6])], &mut rng)
+            .unwrap();
+        let envelope = FederatedEnvelope::try_from(envelope.to_bytes().as_slice()).unwrap();
+        assert_eq!(
+            first_participants[2].open_envelope(&first, &envelope),
+            Some(Ok(content_key))
+        );
+        assert_eq!(
+            second_participants[4].open_envelope(&second, &envelope),
+            Some(Ok(content_key))
+        );
+        assert_eq!(first_participants[1].open_envelope(&first, &envelope), None);
+    }
+}
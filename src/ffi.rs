@@ -0,0 +1,446 @@
+//! C API over BLS12-381.
+//!
+//! Channels, key pairs and headers are handed to C as opaque pointers, created by the functions
+//! here and released with their `_free` function. Encodings are exchanged in [`BeBuffer`]s owned
+//! by the library and released with [`be_buffer_free`], and sets as arrays of `uint32_t`
+//! identifiers. Every function returns a [`BeError`], `BE_ERROR_OK` on success, and only writes
+//! its outputs on success.
+//!
+//! The key of a header lives in the target group, so [`be_encrypt`] and [`be_decrypt`] output a
+//! 32-byte key derived from it with [`HkdfSha256`], from which applications derive their own keys.
+//! Randomness is taken from the operating system. The header `include/dgs_be.h` is generated
+//! from this module with `cbindgen --config cbindgen.toml --output include/dgs_be.h`.
+//!
+//! Only available with the `ffi` feature.
+
+use std::convert::TryFrom;
+use std::ptr;
+
+use ark_bls12_381::Bls12_381;
+
+use crate::kdf::{derive_key, HkdfSha256};
+use crate::{BroadcastChannel, Header, Recipient};
+
+/// Info of the derivation of the keys output from the keys of the headers.
+const FFI_KEY_INFO: &[u8] = b"DGS-BE-FFI-KEY-V1";
+
+/// Outcome of a call.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BeError {
+    /// The call succeeded
+    Ok = 0,
+    /// A pointer argument is null
+    NullPointer = 1,
+    /// An encoding does not decode
+    InvalidEncoding = 2,
+    /// The channel cannot be set up
+    Setup = 3,
+    /// The set cannot be encrypted for
+    Encrypt = 4,
+    /// The header cannot be decrypted
+    Decrypt = 5,
+}
+
+/// Public parameters of a channel.
+pub struct BeChannel(BroadcastChannel<Bls12_381>);
+
+/// Key pair of a participant.
+pub struct BeRecipient(Recipient<Bls12_381>);
+
+/// Header of an encryption.
+pub struct BeHeader(Header<Bls12_381>);
+
+/// Bytes owned by the library.
+#[repr(C)]
+pub struct BeBuffer {
+    /// first byte
+    pub data: *mut u8,
+    /// number of bytes
+    pub len: usize,
+}
+
+impl BeBuffer {
+    fn new(bytes: Vec<u8>) -> Self {
+        let len = bytes.len();
+        BeBuffer {
+            data: Box::into_raw(bytes.into_boxed_slice()) as *mut u8,
+            len,
+        }
+    }
+}
+
+/// Slice of `len` elements at `data`, which may be null if `len` is 0.
+unsafe fn slice<'a, T>(data: *const T, len: usize) -> Option<&'a [T]> {
+    if len == 0 {
+        Some(&[])
+    } else if data.is_null() {
+        None
+    } else {
+        Some(std::slice::from_raw_parts(data, len))
+    }
+}
+
+fn to_set(set_recipients: &[u32]) -> Vec<usize> {
+    set_recipients
+        .iter()
+        .map(|identifier| *identifier as usize)
+        .collect()
+}
+
+/// Write the key derived from the header key `key` to the 32 bytes at `output`.
+unsafe fn derive(key: &<Bls12_381 as ark_ec::PairingEngine>::Fqk, output: *mut u8) {
+    let output = std::slice::from_raw_parts_mut(output, 32);
+    // 32 bytes are within the output of HKDF
+    derive_key::<Bls12_381, HkdfSha256>(key, FFI_KEY_INFO, output).unwrap();
+}
+
+/// Set up a channel for `n` participants. Writes the channel to `channel`, and the key pairs of
+/// participants 1 to `n` to the `n` entries of `recipients`.
+///
+/// # Safety
+///
+/// `channel` must be valid for a write, and `recipients` for `n` writes.
+#[no_mangle]
+pub unsafe extern "C" fn be_channel_init(
+    n: usize,
+    channel: *mut *mut BeChannel,
+    recipients: *mut *mut BeRecipient,
+) -> BeError {
+    if channel.is_null() || recipients.is_null() {
+        return BeError::NullPointer;
+    }
+    let (parameters, participants) =
+        match BroadcastChannel::init_participants(n, &mut rand_core::OsRng) {
+            Ok(setup) => setup,
+            Err(_) => return BeError::Setup,
+        };
+    for (index, participant) in participants.into_iter().enumerate() {
+        *recipients.add(index) = Box::into_raw(Box::new(BeRecipient(participant)));
+    }
+    *channel = Box::into_raw(Box::new(BeChannel(parameters)));
+    BeError::Ok
+}
+
+/// Encrypt for the `set_len` identifiers at `set`. Writes the header to `header`, and the key
+/// derived from its key to the 32 bytes at `key`.
+///
+/// # Safety
+///
+/// `channel` must come from this library, `set` must be valid for `set_len` identifiers,
+/// `header` for a write and `key` for 32 bytes.
+#[no_mangle]
+pub unsafe extern "C" fn be_encrypt(
+    channel: *const BeChannel,
+    set: *const u32,
+    set_len: usize,
+    header: *mut *mut BeHeader,
+    key: *mut u8,
+) -> BeError {
+    let (channel, set) = match (channel.as_ref(), slice(set, set_len)) {
+        (Some(channel), Some(set)) if !header.is_null() && !key.is_null() => (channel, set),
+        _ => return BeError::NullPointer,
+    };
+    let (encrypted, header_key) = match channel.0.encrypt(&to_set(set), &mut rand_core::OsRng) {
+        Ok(encryption) => encryption,
+        Err(_) => return BeError::Encrypt,
+    };
+    derive(header_key.key(), key);
+    *header = Box::into_raw(Box::new(BeHeader(encrypted)));
+    BeError::Ok
+}
+
+/// Decrypt `header`, encrypted for the `set_len` identifiers at `set`, with `recipient`. Writes
+/// the key derived from its key to the 32 bytes at `key`.
+///
+/// # Safety
+///
+/// `recipient`, `channel` and `header` must come from this library, `set` must be valid for
+/// `set_len` identifiers and `key` for 32 bytes.
+#[no_mangle]
+pub unsafe extern "C" fn be_decrypt(
+    recipient: *const BeRecipient,
+    channel: *const BeChannel,
+    set: *const u32,
+    set_len: usize,
+    header: *const BeHeader,
+    key: *mut u8,
+) -> BeError {
+    let (recipient, channel, header) = match (recipient.as_ref(), channel.as_ref(), header.as_ref())
+    {
+        (Some(recipient), Some(channel), Some(header)) => (recipient, channel, header),
+        _ => return BeError::NullPointer,
+    };
+    let set = match slice(set, set_len) {
+        Some(set) if !key.is_null() => set,
+        _ => return BeError::NullPointer,
+    };
+    match recipient.0.decrypt(&to_set(set), &channel.0, &header.0) {
+        Ok(header_key) => {
+            derive(header_key.key(), key);
+            BeError::Ok
+        }
+        Err(_) => BeError::Decrypt,
+    }
+}
+
+/// Write the compressed encoding of `channel` to `output`.
+///
+/// # Safety
+///
+/// `channel` must come from this library, and `output` must be valid for a write.
+#[no_mangle]
+pub unsafe extern "C" fn be_channel_to_bytes(
+    channel: *const BeChannel,
+    output: *mut BeBuffer,
+) -> BeError {
+    match channel.as_ref() {
+        Some(channel) if !output.is_null() => {
+            *output = BeBuffer::new(channel.0.to_bytes());
+            BeError::Ok
+        }
+        _ => BeError::NullPointer,
+    }
+}
+
+/// Decode the channel encoded in the `len` bytes at `data`, and write it to `output`.
+///
+/// # Safety
+///
+/// `data` must be valid for `len` bytes, and `output` for a write.
+#[no_mangle]
+pub unsafe extern "C" fn be_channel_from_bytes(
+    data: *const u8,
+    len: usize,
+    output: *mut *mut BeChannel,
+) -> BeError {
+    let bytes = match slice(data, len) {
+        Some(bytes) if !output.is_null() => bytes,
+        _ => return BeError::NullPointer,
+    };
+    match BroadcastChannel::try_from(bytes) {
+        Ok(channel) => {
+            *output = Box::into_raw(Box::new(BeChannel(channel)));
+            BeError::Ok
+        }
+        Err(_) => BeError::InvalidEncoding,
+    }
+}
+
+/// Release a channel. Null is ignored.
+///
+/// # Safety
+///
+/// `channel` must be null or come from this library, and is not used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn be_channel_free(channel: *mut BeChannel) {
+    if !channel.is_null() {
+        drop(Box::from_raw(channel));
+    }
+}
+
+/// Write the compressed encoding of `recipient` to `output`.
+///
+/// # Safety
+///
+/// `recipient` must come from this library, and `output` must be valid for a write.
+#[no_mangle]
+pub unsafe extern "C" fn be_recipient_to_bytes(
+    recipient: *const BeRecipient,
+    output: *mut BeBuffer,
+) -> BeError {
+    match recipient.as_ref() {
+        Some(recipient) if !output.is_null() => {
+            *output = BeBuffer::new(recipient.0.to_bytes());
+            BeError::Ok
+        }
+        _ => BeError::NullPointer,
+    }
+}
+
+/// Decode the key pair encoded in the `len` bytes at `data`, and write it to `output`.
+///
+/// # Safety
+///
+/// `data` must be valid for `len` bytes, and `output` for a write.
+#[no_mangle]
+pub unsafe extern "C" fn be_recipient_from_bytes(
+    data: *const u8,
+    len: usize,
+    output: *mut *mut BeRecipient,
+) -> BeError {
+    let bytes = match slice(data, len) {
+        Some(bytes) if !output.is_null() => bytes,
+        _ => return BeError::NullPointer,
+    };
+    match Recipient::try_from(bytes) {
+        Ok(recipient) => {
+            *output = Box::into_raw(Box::new(BeRecipient(recipient)));
+            BeError::Ok
+        }
+        Err(_) => BeError::InvalidEncoding,
+    }
+}
+
+/// Release a key pair. Null is ignored.
+///
+/// # Safety
+///
+/// `recipient` must be null or come from this library, and is not used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn be_recipient_free(recipient: *mut BeRecipient) {
+    if !recipient.is_null() {
+        drop(Box::from_raw(recipient));
+    }
+}
+
+/// Write the compressed encoding of `header` to `output`.
+///
+/// # Safety
+///
+/// `header` must come from this library, and `output` must be valid for a write.
+#[no_mangle]
+pub unsafe extern "C" fn be_header_to_bytes(
+    header: *const BeHeader,
+    output: *mut BeBuffer,
+) -> BeError {
+    match header.as_ref() {
+        Some(header) if !output.is_null() => {
+            *output = BeBuffer::new(header.0.to_bytes());
+            BeError::Ok
+        }
+        _ => BeError::NullPointer,
+    }
+}
+
+/// Decode the header encoded in the `len` bytes at `data`, and write it to `output`.
+///
+/// # Safety
+///
+/// `data` must be valid for `len` bytes, and `output` for a write.
+#[no_mangle]
+pub unsafe extern "C" fn be_header_from_bytes(
+    data: *const u8,
+    len: usize,
+    output: *mut *mut BeHeader,
+) -> BeError {
+    let bytes = match slice(data, len) {
+        Some(bytes) if !output.is_null() => bytes,
+        _ => return BeError::NullPointer,
+    };
+    match Header::try_from(bytes) {
+        Ok(header) => {
+            *output = Box::into_raw(Box::new(BeHeader(header)));
+            BeError::Ok
+        }
+        Err(_) => BeError::InvalidEncoding,
+    }
+}
+
+/// Release a header. Null is ignored.
+///
+/// # Safety
+///
+/// `header` must be null or come from this library, and is not used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn be_header_free(header: *mut BeHeader) {
+    if !header.is_null() {
+        drop(Box::from_raw(header));
+    }
+}
+
+/// Release the bytes of `buffer`, and empty it. An empty buffer is ignored.
+///
+/// # Safety
+///
+/// `buffer` must be null or hold bytes from this library.
+#[no_mangle]
+pub unsafe extern "C" fn be_buffer_free(buffer: *mut BeBuffer) {
+    if let Some(buffer) = buffer.as_mut() {
+        if !buffer.data.is_null() {
+            drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+                buffer.data,
+                buffer.len,
+            )));
+        }
+        buffer.data = ptr::null_mut();
+        buffer.len = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn c_api_round_trips() {
+        unsafe {
+            let mut channel = ptr::null_mut();
+            let mut recipients = [ptr::null_mut(); 3];
+            assert_eq!(
+                be_channel_init(3, &mut channel, recipients.as_mut_ptr()),
+                BeError::Ok
+            );
+
+            let mut encoded = BeBuffer {
+                data: ptr::null_mut(),
+                len: 0,
+            };
+            assert_eq!(be_channel_to_bytes(channel, &mut encoded), BeError::Ok);
+            let mut decoded = ptr::null_mut();
+            assert_eq!(
+                be_channel_from_bytes(encoded.data, encoded.len, &mut decoded),
+                BeError::Ok
+            );
+            be_buffer_free(&mut encoded);
+            assert!(encoded.data.is_null());
+
+            let set = [1u32, 3];
+            let mut header = ptr::null_mut();
+            let mut key = [0u8; 32];
+            assert_eq!(
+                be_encrypt(decoded, set.as_ptr(), 2, &mut header, key.as_mut_ptr()),
+                BeError::Ok
+            );
+            let mut decrypted = [0u8; 32];
+            assert_eq!(
+                be_decrypt(
+                    recipients[2],
+                    channel,
+                    set.as_ptr(),
+                    2,
+                    header,
+                    decrypted.as_mut_ptr()
+                ),
+                BeError::Ok
+            );
+            assert_eq!(decrypted, key);
+            assert_eq!(
+                be_decrypt(
+                    recipients[1],
+                    channel,
+                    set.as_ptr(),
+                    2,
+                    header,
+                    decrypted.as_mut_ptr()
+                ),
+                BeError::Decrypt
+            );
+            assert_eq!(
+                be_header_from_bytes([1u8, 2].as_ptr(), 2, &mut header),
+                BeError::InvalidEncoding
+            );
+            assert_eq!(
+                be_encrypt(channel, ptr::null(), 2, &mut header, key.as_mut_ptr()),
+                BeError::NullPointer
+            );
+
+            be_header_free(header);
+            be_channel_free(decoded);
+            be_channel_free(channel);
+            for recipient in recipients.iter() {
+                be_recipient_free(*recipient);
+            }
+        }
+    }
+}
@@ -0,0 +1,327 @@
+//! Generalized construction trading the size of headers against the size of the parameters.
+//!
+//! Section 3 of the paper splits the `n` participants into `A = n / B` buckets of `B`. Every
+//! bucket is a channel of `B` slots, and all of them share the powers of alpha while each has its
+//! own gamma. A header carries one point of G1 per bucket and a single point of G2, so it grows
+//! as `n / B`, while the parameters shrink to the `3B + 1` powers of alpha and the `A` points
+//! `V_a`. A participant only needs the powers and the point `V_a` of its own bucket.
+//!
+//! With `B = n` this is the channel of the crate root, with a single bucket; with `B = 1` every
+//! participant has a bucket of its own and headers are as long as the channel.
+//!
+//! Participant `i` is in bucket `(i - 1) / B`, on the slot `(i - 1) % B + 1` of that bucket. The
+//! last bucket is not full if `B` does not divide `n`.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{UniformRand, Zero};
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+use crate::error::{DecryptError, EncryptError, SetupError};
+use crate::set::first_repetition;
+use crate::KeyPair;
+
+/// Public parameters of a channel whose participants are split into buckets.
+#[derive(Clone)]
+pub struct GeneralBroadcastChannel<E: PairingEngine> {
+    number_participants: usize,
+    bucket_size: usize,
+    /// `2B + 1` powers of alpha in G1, with the one at position `B + 1` set to zero
+    g1_points: Vec<E::G1Affine>,
+    /// `B + 1` powers of alpha in G2
+    g2_points: Vec<E::G2Affine>,
+    /// point `V_a` of each bucket
+    points_v: Vec<E::G1Affine>,
+}
+
+/// Key of a participant of a [`GeneralBroadcastChannel`].
+#[derive(Clone)]
+pub struct GeneralRecipient<E: PairingEngine> {
+    identifier: usize,
+    key_pair: KeyPair<E>,
+}
+
+/// Header of an encryption with a [`GeneralBroadcastChannel`].
+#[derive(Clone, PartialEq, Eq)]
+pub struct GeneralHeader<E: PairingEngine> {
+    /// component in G1 of each bucket
+    ctx_0: Vec<E::G1Projective>,
+    /// component in G2
+    ctx_1: E::G2Projective,
+}
+
+impl<E: PairingEngine> GeneralHeader<E> {
+    /// Number of points of the header, one more than the number of buckets.
+    pub fn len(&self) -> usize {
+        self.ctx_0.len() + 1
+    }
+
+    /// Whether the header has no point, which never happens.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+}
+
+impl<E: PairingEngine> GeneralBroadcastChannel<E> {
+    /// Set up a channel of `n` participants in buckets of `bucket_size`, and issue every key.
+    /// Fails if either is zero.
+    pub fn init<R>(
+        n: usize,
+        bucket_size: usize,
+        rng: &mut R,
+    ) -> Result<(Self, Vec<GeneralRecipient<E>>), SetupError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if n == 0 {
+            return Err(SetupError::NoParticipants);
+        }
+        if bucket_size == 0 {
+            return Err(SetupError::EmptyBuckets);
+        }
+        let buckets = n / bucket_size + usize::from(n % bucket_size != 0);
+
+        let mut alpha = E::Fr::rand(rng);
+        let mut g1_points = vec![E::G1Projective::prime_subgroup_generator(); 2 * bucket_size + 1];
+        let mut g2_points = vec![E::G2Projective::prime_subgroup_generator(); bucket_size + 1];
+        let mut power = alpha;
+        for (position, point) in g1_points.iter_mut().enumerate().skip(1) {
+            *point *= power;
+            if let Some(point) = g2_points.get_mut(position) {
+                *point *= power;
+            }
+            power *= &alpha;
+        }
+        g1_points[bucket_size + 1] = E::G1Projective::zero();
+        alpha.zeroize();
+        power.zeroize();
+
+        let mut gammas: Vec<E::Fr> = (0..buckets).map(|_| E::Fr::rand(rng)).collect();
+        let points_v: Vec<E::G1Projective> = gammas
+            .iter()
+            .map(|gamma| {
+                let mut point_v = E::G1Projective::prime_subgroup_generator();
+                point_v *= *gamma;
+                point_v
+            })
+            .collect();
+
+        let recipients = (1..=n)
+            .map(|identifier| {
+                let (bucket, slot) = Self::position(bucket_size, identifier);
+                let mut private_key = g1_points[slot];
+                private_key *= gammas[bucket];
+                GeneralRecipient {
+                    identifier,
+                    key_pair: KeyPair {
+                        public_key: g2_points[slot],
+                        private_key,
+                    },
+                }
+            })
+            .collect();
+        gammas.zeroize();
+
+        let channel = GeneralBroadcastChannel {
+            number_participants: n,
+            bucket_size,
+            g1_points: E::G1Projective::batch_normalization_into_affine(&g1_points),
+            g2_points: E::G2Projective::batch_normalization_into_affine(&g2_points),
+            points_v: E::G1Projective::batch_normalization_into_affine(&points_v),
+        };
+        Ok((channel, recipients))
+    }
+
+    /// Bucket, counted from zero, and slot in the bucket, counted from one, of `identifier`.
+    fn position(bucket_size: usize, identifier: usize) -> (usize, usize) {
+        (
+            (identifier - 1) / bucket_size,
+            (identifier - 1) % bucket_size + 1,
+        )
+    }
+
+    /// Number of participants of the channel.
+    pub fn number_participants(&self) -> usize {
+        self.number_participants
+    }
+
+    /// Number of participants in each bucket.
+    pub fn bucket_size(&self) -> usize {
+        self.bucket_size
+    }
+
+    /// Number of buckets, and of points of G1 in every header.
+    pub fn buckets(&self) -> usize {
+        self.points_v.len()
+    }
+
+    /// Generate a symmetric key and its header for `set_recipients`. Fails if the set is
+    /// empty, or contains identifiers outside of the channel or more than once.
+    pub fn encrypt<R>(
+        &self,
+        set_recipients: &[usize],
+        rng: &mut R,
+    ) -> Result<(GeneralHeader<E>, E::Fqk), EncryptError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if let Some(index) = set_recipients
+            .iter()
+            .find(|index| **index == 0 || **index > self.number_participants)
+        {
+            return Err(EncryptError::IdentifierOutOfRange(*index));
+        }
+        if set_recipients.is_empty() {
+            return Err(EncryptError::EmptySet);
+        }
+        if let Some(identifier) = first_repetition(set_recipients) {
+            return Err(EncryptError::DuplicateRecipient(identifier));
+        }
+
+        let b = self.bucket_size;
+        let mut aggregates: Vec<E::G1Projective> = self
+            .points_v
+            .iter()
+            .map(|point_v| point_v.into_projective())
+            .collect();
+        for identifier in set_recipients.iter() {
+            let (bucket, slot) = Self::position(b, *identifier);
+            aggregates[bucket].add_assign_mixed(&self.g1_points[b + 1 - slot]);
+        }
+
+        let mut k = E::Fr::rand(rng);
+        let mut g_2_point = self.g2_points[1].into_projective();
+        g_2_point *= k;
+        let K = E::pairing(self.g1_points[b], g_2_point);
+
+        let mut header_point_in_g2 = self.g2_points[0].into_projective();
+        header_point_in_g2 *= k;
+        for aggregate in aggregates.iter_mut() {
+            *aggregate *= k;
+        }
+        k.zeroize();
+
+        let header = GeneralHeader {
+            ctx_0: aggregates,
+            ctx_1: header_point_in_g2,
+        };
+        Ok((header, K))
+    }
+}
+
+impl<E: PairingEngine> GeneralRecipient<E> {
+    /// Identifier of the participant in the channel.
+    pub fn identifier(&self) -> usize {
+        self.identifier
+    }
+
+    /// Decrypt a header of `channel` encrypted for `set_recipients`. Fails if the set contains
+    /// identifiers outside of the channel or more than once, if it does not contain this
+    /// recipient, or if the header contains the identity. Only the points of the recipients in
+    /// the same bucket are summed.
+    pub fn decrypt(
+        &self,
+        set_recipients: &[usize],
+        channel: &GeneralBroadcastChannel<E>,
+        header: &GeneralHeader<E>,
+    ) -> Result<E::Fqk, DecryptError> {
+        if let Some(index) = set_recipients
+            .iter()
+            .find(|index| **index == 0 || **index > channel.number_participants)
+        {
+            return Err(DecryptError::IdentifierOutOfRange(*index));
+        }
+        if let Some(identifier) = first_repetition(set_recipients) {
+            return Err(DecryptError::DuplicateRecipient(identifier));
+        }
+        if !set_recipients.contains(&self.identifier) {
+            return Err(DecryptError::NotInSet);
+        }
+
+        let b = channel.bucket_size;
+        let (bucket, slot) = GeneralBroadcastChannel::<E>::position(b, self.identifier);
+        // a header of a channel with fewer buckets was not encrypted for this recipient
+        let ctx_0 = header.ctx_0.get(bucket).ok_or(DecryptError::NotInSet)?;
+        if ctx_0.is_zero() || header.ctx_1.is_zero() {
+            return Err(DecryptError::IdentityPoint);
+        }
+
+        let mut aggregate = self.key_pair.private_key;
+        for identifier in set_recipients.iter() {
+            let (other_bucket, other_slot) = GeneralBroadcastChannel::<E>::position(b, *identifier);
+            if other_bucket == bucket && *identifier != self.identifier {
+                aggregate.add_assign_mixed(&channel.g1_points[b + 1 - other_slot + slot]);
+            }
+        }
+
+        // e(ctx_0, pk) / e(d + aggregate, ctx_1), as in the channel of the crate root
+        let pairs = [
+            (
+                ctx_0.into_affine().into(),
+                self.key_pair.public_key.into_affine().into(),
+            ),
+            (
+                (-aggregate).into_affine().into(),
+                header.ctx_1.into_affine().into(),
+            ),
+        ];
+        Ok(E::product_of_pairings(pairs.iter()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn buckets_decrypt_their_members() {
+        let mut rng = thread_rng();
+        let (channel, recipients) =
+            GeneralBroadcastChannel::<Bls12_381>::init(7, 3, &mut rng).unwrap();
+        assert_eq!(channel.buckets(), 3);
+
+        let set = [1, 3, 4, 7];
+        let (header, key) = channel.encrypt(&set, &mut rng).unwrap();
+        assert_eq!(header.len(), 4);
+        for recipient in recipients.iter() {
+            let decrypted = recipient.decrypt(&set, &channel, &header);
+            if set.contains(&recipient.identifier()) {
+                assert_eq!(decrypted, Ok(key));
+            } else {
+                assert_eq!(decrypted, Err(DecryptError::NotInSet));
+            }
+        }
+        // only the recipients of the same bucket matter to a decryption
+        assert_eq!(recipients[0].decrypt(&[1, 3], &channel, &header), Ok(key));
+        assert_ne!(
+            recipients[0].decrypt(&[1, 4, 7], &channel, &header),
+            Ok(key)
+        );
+
+        assert!(matches!(
+            channel.encrypt(&[8], &mut rng),
+            Err(EncryptError::IdentifierOutOfRange(8))
+        ));
+        assert!(matches!(
+            GeneralBroadcastChannel::<Bls12_381>::init(4, 0, &mut rng),
+            Err(SetupError::EmptyBuckets)
+        ));
+    }
+
+    #[test]
+    fn extreme_bucket_sizes() {
+        let mut rng = thread_rng();
+        for bucket_size in [1, 5].iter() {
+            let (channel, recipients) =
+                GeneralBroadcastChannel::<Bls12_381>::init(5, *bucket_size, &mut rng).unwrap();
+            let (header, key) = channel.encrypt(&[2, 5], &mut rng).unwrap();
+            assert_eq!(header.len(), 5 / bucket_size + 1);
+            assert_eq!(recipients[4].decrypt(&[2, 5], &channel, &header), Ok(key));
+        }
+    }
+}
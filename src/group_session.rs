@@ -0,0 +1,357 @@
+//! Group messaging over the key of a header.
+//!
+//! A header gives its recipients one shared key. A [`GroupSession`] turns it into a secure group
+//! channel: every member, the broadcaster included, sends on its own chain of keys derived from
+//! the shared key and its identifier, and every message is sealed with ChaCha20-Poly1305 under a
+//! fresh key of the chain of its sender. The chains of all the senders are derived when the
+//! session starts, after which the shared key is no longer needed. A chain moves forward with
+//! each message, and keys are erased once used, so the state of a session compromised later does
+//! not reveal the messages it already sealed or opened. The private key of a recipient decrypts
+//! the header again, and with it the whole session, so this only holds once the header is
+//! discarded as well.
+//!
+//! Messages carry their sender and their counter on its chain, which are authenticated along with
+//! the payload. Messages may arrive out of order: [`GroupSession::open`] keeps the keys of the
+//! messages it skipped until they arrive, at most [`MAX_SKIP`] ahead of the last one of the
+//! sender, and refuses any message it has already opened. The state of the session only changes
+//! once a message is authenticated, so forged messages cannot make it skip keys.
+//!
+//! Members send under distinct identifiers, their identifier in the channel, and the broadcaster
+//! sends as 0. Every member knows the senders of the session when it starts, and refuses messages
+//! of anyone else. A message is encoded as its sender and its counter, as 64-bit
+//! integers, followed by the sealed payload as a byte vector.
+//!
+//! Only available with the `chacha20poly1305` feature.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use ark_ec::PairingEngine;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use chacha20poly1305::aead::{Aead, NewAead, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::encoding::{from_bytes, to_bytes};
+use crate::error::{GroupSessionError, MessageError};
+use crate::kdf::{derive_key, HkdfSha256, KeyDerivation};
+use crate::{BroadcastChannel, Header, Recipient};
+
+/// Info of the derivation of the root key of a session from the key of its header.
+const ROOT_INFO: &[u8] = b"DGS-BE-GROUP-SESSION-V1";
+/// Info of the derivation of the chain of a sender from the root key, followed by the sender.
+const CHAIN_INFO: &[u8] = b"DGS-BE-GROUP-SESSION-CHAIN";
+/// Info of the derivation of a message key from a chain key.
+const MESSAGE_INFO: &[u8] = b"DGS-BE-GROUP-SESSION-MESSAGE";
+/// Info of the derivation of the next chain key from a chain key.
+const NEXT_INFO: &[u8] = b"DGS-BE-GROUP-SESSION-NEXT";
+
+/// Maximum number of messages of a sender skipped by [`GroupSession::open`] at once.
+pub const MAX_SKIP: u64 = 1024;
+
+/// Message sealed in a group session.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GroupMessage {
+    sender: usize,
+    counter: u64,
+    sealed: Vec<u8>,
+}
+
+impl GroupMessage {
+    /// Identifier the message was sent under.
+    pub fn sender(&self) -> usize {
+        self.sender
+    }
+
+    /// Position of the message in the chain of its sender, from 0.
+    pub fn counter(&self) -> u64 {
+        self.counter
+    }
+
+    /// Compressed encoding of the message.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+
+    /// Associated data of the payload, binding the sender and the counter.
+    fn associated_data(&self) -> [u8; 16] {
+        let mut data = [0u8; 16];
+        data[..8].copy_from_slice(&(self.sender as u64).to_le_bytes());
+        data[8..].copy_from_slice(&self.counter.to_le_bytes());
+        data
+    }
+}
+
+/// Position in the chain of keys of a sender.
+#[derive(Clone)]
+struct Chain {
+    /// key of the next message and of the rest of the chain
+    key: [u8; 32],
+    /// counter of the next message
+    counter: u64,
+}
+
+impl Chain {
+    fn new(root: &[u8; 32], sender: usize) -> Self {
+        let mut info = CHAIN_INFO.to_vec();
+        info.extend_from_slice(&(sender as u64).to_le_bytes());
+        let mut key = [0u8; 32];
+        // 32 bytes are within the output of HKDF
+        HkdfSha256::derive(root, &info, &mut key).unwrap();
+        Chain { key, counter: 0 }
+    }
+
+    /// Key of the next message, moving the chain forward.
+    fn step(&mut self) -> [u8; 32] {
+        let mut message_key = [0u8; 32];
+        let mut next = [0u8; 32];
+        // 32 bytes are within the output of HKDF
+        HkdfSha256::derive(&self.key, MESSAGE_INFO, &mut message_key).unwrap();
+        HkdfSha256::derive(&self.key, NEXT_INFO, &mut next).unwrap();
+        self.key.zeroize();
+        self.key = next;
+        self.counter += 1;
+        message_key
+    }
+}
+
+impl Drop for Chain {
+    fn drop(&mut self) {
+        self.key.zeroize();
+    }
+}
+
+/// Secure group channel of the recipients of a header.
+pub struct GroupSession {
+    sender: usize,
+    sending: Chain,
+    receiving: BTreeMap<usize, Chain>,
+    /// keys of the skipped messages, by sender and counter
+    skipped: BTreeMap<(usize, u64), [u8; 32]>,
+}
+
+impl GroupSession {
+    /// Session over the header key `key` between `senders`, sending as `sender`, which is added
+    /// to the senders if needed.
+    pub fn new<E: PairingEngine>(key: &E::Fqk, sender: usize, senders: &[usize]) -> Self {
+        let mut root = [0u8; 32];
+        // 32 bytes are within the output of HKDF
+        derive_key::<E, HkdfSha256>(key, ROOT_INFO, &mut root).unwrap();
+        let receiving = senders
+            .iter()
+            .chain(core::iter::once(&sender))
+            .map(|sender| (*sender, Chain::new(&root, *sender)))
+            .collect();
+        let session = GroupSession {
+            sending: Chain::new(&root, sender),
+            sender,
+            receiving,
+            skipped: BTreeMap::new(),
+        };
+        root.zeroize();
+        session
+    }
+
+    /// Identifier this session sends as.
+    pub fn sender(&self) -> usize {
+        self.sender
+    }
+
+    /// Number of skipped messages whose keys are kept.
+    pub fn skipped(&self) -> usize {
+        self.skipped.len()
+    }
+
+    /// Seal `plaintext` as the next message of the session.
+    pub fn seal(&mut self, plaintext: &[u8]) -> GroupMessage {
+        let mut message = GroupMessage {
+            sender: self.sender,
+            counter: self.sending.counter,
+            sealed: Vec::new(),
+        };
+        let mut key = self.sending.step();
+        let payload = Payload {
+            msg: plaintext,
+            aad: &message.associated_data(),
+        };
+        // each key seals a single payload, and sealing only fails beyond 256 GiB
+        message.sealed = ChaCha20Poly1305::new(Key::from_slice(&key))
+            .encrypt(Nonce::from_slice(&[0u8; 12]), payload)
+            .expect("payload too long for ChaCha20-Poly1305");
+        key.zeroize();
+        message
+    }
+
+    /// Open `message`. Fails if its sender is not a sender of the session, if it was already
+    /// opened, if it is too far ahead of the last message of its sender, or if it was modified or
+    /// sealed in another session.
+    pub fn open(&mut self, message: &GroupMessage) -> Result<Vec<u8>, GroupSessionError> {
+        let index = (message.sender, message.counter);
+        if let Some(key) = self.skipped.get(&index) {
+            let plaintext = Self::decrypt(key, message)?;
+            if let Some(mut key) = self.skipped.remove(&index) {
+                key.zeroize();
+            }
+            return Ok(plaintext);
+        }
+
+        let mut chain = self
+            .receiving
+            .get(&message.sender)
+            .ok_or(GroupSessionError::UnknownSender(message.sender))?
+            .clone();
+        if message.counter < chain.counter {
+            return Err(GroupSessionError::Replayed);
+        }
+        if message.counter - chain.counter > MAX_SKIP {
+            return Err(GroupSessionError::TooFarAhead);
+        }
+        let mut skipped = Vec::new();
+        while chain.counter < message.counter {
+            let counter = chain.counter;
+            skipped.push(((message.sender, counter), chain.step()));
+        }
+        let mut key = chain.step();
+        let opened = Self::decrypt(&key, message);
+        key.zeroize();
+        let plaintext = match opened {
+            Ok(plaintext) => plaintext,
+            Err(error) => {
+                skipped.iter_mut().for_each(|(_, key)| key.zeroize());
+                return Err(error);
+            }
+        };
+
+        self.skipped.extend(skipped);
+        self.receiving.insert(message.sender, chain);
+        Ok(plaintext)
+    }
+
+    fn decrypt(key: &[u8; 32], message: &GroupMessage) -> Result<Vec<u8>, GroupSessionError> {
+        let payload = Payload {
+            msg: &message.sealed,
+            aad: &message.associated_data(),
+        };
+        ChaCha20Poly1305::new(Key::from_slice(key))
+            .decrypt(Nonce::from_slice(&[0u8; 12]), payload)
+            .map_err(|_| GroupSessionError::Authentication)
+    }
+}
+
+impl Drop for GroupSession {
+    fn drop(&mut self) {
+        self.skipped.values_mut().for_each(Zeroize::zeroize);
+    }
+}
+
+impl ZeroizeOnDrop for GroupSession {}
+
+impl<E: PairingEngine> Recipient<E> {
+    /// Group session over `header`, encrypted for `set_recipients`, between the recipients of the
+    /// set and the broadcaster, sending as the identifier of the recipient.
+    pub fn group_session(
+        &self,
+        set_recipients: &[usize],
+        channel: &BroadcastChannel<E>,
+        header: &Header<E>,
+    ) -> Result<GroupSession, MessageError> {
+        let key = self
+            .decrypt(set_recipients, channel, header)
+            .map_err(MessageError::Decrypt)?;
+        let mut senders = set_recipients.to_vec();
+        senders.push(0);
+        Ok(GroupSession::new::<E>(key.key(), self.identifier, &senders))
+    }
+}
+
+impl CanonicalSerialize for GroupMessage {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        (self.sender as u64).serialize(&mut writer)?;
+        self.counter.serialize(&mut writer)?;
+        self.sealed.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        (self.sender as u64).serialized_size()
+            + self.counter.serialized_size()
+            + self.sealed.serialized_size()
+    }
+}
+
+impl CanonicalDeserialize for GroupMessage {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let sender = usize::try_from(u64::deserialize(&mut reader)?)
+            .map_err(|_| SerializationError::InvalidData)?;
+        Ok(GroupMessage {
+            sender,
+            counter: u64::deserialize(&mut reader)?,
+            sealed: Vec::deserialize(&mut reader)?,
+        })
+    }
+}
+
+impl TryFrom<&[u8]> for GroupMessage {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn members_exchange_messages() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(3, &mut rng).unwrap();
+        let (header, key) = channel.encrypt(&[1, 2], &mut rng).unwrap();
+        let mut broadcaster = GroupSession::new::<Bls12_381>(key.key(), 0, &[1, 2]);
+        let mut first = participants[0]
+            .group_session(&[1, 2], &channel, &header)
+            .unwrap();
+        let mut second = participants[1]
+            .group_session(&[1, 2], &channel, &header)
+            .unwrap();
+
+        let hello = broadcaster.seal(b"hello");
+        let welcome = broadcaster.seal(b"welcome");
+        let hello = GroupMessage::try_from(hello.to_bytes().as_slice()).unwrap();
+        assert_eq!((hello.sender(), hello.counter()), (0, 0));
+
+        // out of order, then replayed
+        assert_eq!(first.open(&welcome), Ok(b"welcome".to_vec()));
+        assert_eq!(first.skipped(), 1);
+        assert_eq!(first.open(&hello), Ok(b"hello".to_vec()));
+        assert_eq!(first.skipped(), 0);
+        assert_eq!(first.open(&hello), Err(GroupSessionError::Replayed));
+
+        let reply = second.seal(b"thanks");
+        assert_eq!(first.open(&reply), Ok(b"thanks".to_vec()));
+        assert_eq!(broadcaster.open(&reply), Ok(b"thanks".to_vec()));
+
+        let mut forged = first.seal(b"pay 10");
+        forged.counter += 5;
+        assert_eq!(second.open(&forged), Err(GroupSessionError::Authentication));
+        assert_eq!(second.skipped(), 0);
+        forged.counter = MAX_SKIP + 2;
+        assert_eq!(second.open(&forged), Err(GroupSessionError::TooFarAhead));
+
+        let (_, other_key) = channel.encrypt(&[1, 2], &mut rng).unwrap();
+        let mut other = GroupSession::new::<Bls12_381>(other_key.key(), 1, &[0, 2]);
+        assert_eq!(
+            other.open(&second.seal(b"")),
+            Err(GroupSessionError::Authentication)
+        );
+        let mut outsider = GroupSession::new::<Bls12_381>(key.key(), 3, &[]);
+        assert_eq!(
+            first.open(&outsider.seal(b"")),
+            Err(GroupSessionError::UnknownSender(3))
+        );
+    }
+}
@@ -0,0 +1,291 @@
+//! Hierarchical groups of recipients.
+//!
+//! Organizations address their members by groups: everyone in a department, everyone in a team
+//! of it. A [`GroupTree`] arranges groups in a tree, from the root down, and every recipient is a
+//! direct member of at most one group. Encrypting to a node with [`GroupTree::encrypt_to_node`]
+//! addresses the direct members of the node and of every group beneath it.
+//!
+//! The aggregate of a node is the sum of the aggregates of its children and of the points of its
+//! direct members, so [`GroupTree::precompute`] computes every aggregate bottom-up with one point
+//! addition per member and per edge of the tree. Encryptions then cost no sum at all. The
+//! aggregates are kept with the fingerprint of the channel they were computed for, and a tree
+//! whose aggregates are missing or stale sums the points of the node instead. Recipients
+//! decrypt as usual, for the set [`GroupTree::members`] of the node.
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use ark_ec::{PairingEngine, ProjectiveCurve};
+use ark_ff::Zero;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::error::{EncryptError, GroupError};
+use crate::events::{self, Event};
+use crate::session::EncryptionSession;
+use crate::view::Fingerprint;
+use crate::{BroadcastChannel, Header, SharedKey};
+
+/// Node of a [`GroupTree`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(usize);
+
+#[derive(Clone, Debug)]
+struct Node {
+    name: String,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// direct members
+    members: Vec<usize>,
+}
+
+/// Tree of groups of recipients.
+#[derive(Clone)]
+pub struct GroupTree<E: PairingEngine> {
+    /// nodes by identifier, children always after their parent
+    nodes: Vec<Node>,
+    /// group of every direct member
+    groups: BTreeMap<usize, usize>,
+    /// aggregates of the nodes, and the channel they were computed for
+    aggregates: Option<(Fingerprint, Vec<E::G1Projective>)>,
+}
+
+impl<E: PairingEngine> GroupTree<E> {
+    /// Tree with a single group, the root, named `name`.
+    pub fn new(name: &str) -> Self {
+        GroupTree {
+            nodes: vec![Node {
+                name: name.to_string(),
+                parent: None,
+                children: Vec::new(),
+                members: Vec::new(),
+            }],
+            groups: BTreeMap::new(),
+            aggregates: None,
+        }
+    }
+
+    /// Root of the tree.
+    pub fn root(&self) -> NodeId {
+        NodeId(0)
+    }
+
+    /// Add a group named `name` beneath `parent`. Fails if `parent` is not a node of the tree.
+    pub fn add_group(&mut self, parent: NodeId, name: &str) -> Result<NodeId, GroupError> {
+        self.node(parent)?;
+        let node = self.nodes.len();
+        self.nodes.push(Node {
+            name: name.to_string(),
+            parent: Some(parent.0),
+            children: Vec::new(),
+            members: Vec::new(),
+        });
+        self.nodes[parent.0].children.push(node);
+        if let Some((_, aggregates)) = &mut self.aggregates {
+            aggregates.push(E::G1Projective::zero());
+        }
+        Ok(NodeId(node))
+    }
+
+    /// Make `identifiers` direct members of `node`. Fails if `node` is not a node of the tree, or
+    /// if one of the identifiers is already a member of a group, in which case none is added.
+    pub fn add_members(&mut self, node: NodeId, identifiers: &[usize]) -> Result<(), GroupError> {
+        self.node(node)?;
+        let mut seen = BTreeSet::new();
+        if let Some(identifier) = identifiers
+            .iter()
+            .find(|identifier| self.groups.contains_key(*identifier) || !seen.insert(**identifier))
+        {
+            return Err(GroupError::AlreadyMember(*identifier));
+        }
+
+        for identifier in identifiers {
+            self.groups.insert(*identifier, node.0);
+        }
+        self.nodes[node.0].members.extend_from_slice(identifiers);
+        self.aggregates = None;
+        Ok(())
+    }
+
+    /// Name of `node`, if it is a node of the tree.
+    pub fn name(&self, node: NodeId) -> Option<&str> {
+        self.nodes.get(node.0).map(|node| node.name.as_str())
+    }
+
+    /// Parent of `node`, if it is a node of the tree other than the root.
+    pub fn parent(&self, node: NodeId) -> Option<NodeId> {
+        self.nodes.get(node.0)?.parent.map(NodeId)
+    }
+
+    /// Groups directly beneath `node`.
+    pub fn children(&self, node: NodeId) -> Result<Vec<NodeId>, GroupError> {
+        Ok(self
+            .node(node)?
+            .children
+            .iter()
+            .copied()
+            .map(NodeId)
+            .collect())
+    }
+
+    /// Group `identifier` is a direct member of, if any.
+    pub fn group_of(&self, identifier: usize) -> Option<NodeId> {
+        self.groups.get(&identifier).copied().map(NodeId)
+    }
+
+    /// Recipients addressed by `node`: its direct members and those of every group beneath it,
+    /// in increasing order.
+    pub fn members(&self, node: NodeId) -> Result<Vec<usize>, GroupError> {
+        self.node(node)?;
+        let mut members = Vec::new();
+        let mut pending = vec![node.0];
+        while let Some(current) = pending.pop() {
+            members.extend_from_slice(&self.nodes[current].members);
+            pending.extend_from_slice(&self.nodes[current].children);
+        }
+        members.sort_unstable();
+        Ok(members)
+    }
+
+    /// Compute the aggregate of every node for `channel`. Fails if a member is outside of the
+    /// channel.
+    pub fn precompute(&mut self, channel: &BroadcastChannel<E>) -> Result<(), GroupError> {
+        let n = channel.number_participants;
+        if let Some(identifier) = self
+            .groups
+            .keys()
+            .find(|identifier| **identifier == 0 || **identifier > n)
+        {
+            return Err(GroupError::Encrypt(EncryptError::IdentifierOutOfRange(
+                *identifier,
+            )));
+        }
+
+        let mut aggregates = vec![E::G1Projective::zero(); self.nodes.len()];
+        // children come after their parent, so they are summed before it
+        for (index, node) in self.nodes.iter().enumerate().rev() {
+            let mut aggregate = E::G1Projective::zero();
+            for identifier in node.members.iter() {
+                aggregate.add_assign_mixed(&channel.broadcaster_pk_g1[n + 1 - identifier]);
+            }
+            for child in node.children.iter() {
+                aggregate += &aggregates[*child];
+            }
+            aggregates[index] = aggregate;
+        }
+        self.aggregates = Some((channel.view().fingerprint(), aggregates));
+        Ok(())
+    }
+
+    /// Encrypt for the recipients addressed by `node`, with the aggregate computed by
+    /// [`GroupTree::precompute`] if it is for `channel`. Fails if `node` is not a node of the
+    /// tree, or if its members cannot be encrypted for, as for [`BroadcastChannel::encrypt`].
+    pub fn encrypt_to_node<R>(
+        &self,
+        channel: &BroadcastChannel<E>,
+        node: NodeId,
+        rng: &mut R,
+    ) -> Result<(Header<E>, SharedKey<E>), GroupError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let members = self.members(node)?;
+        let aggregate = match &self.aggregates {
+            Some((fingerprint, aggregates)) if *fingerprint == channel.view().fingerprint() => {
+                channel
+                    .check_encryption_set(&members)
+                    .map_err(GroupError::Encrypt)?;
+                aggregates[node.0]
+            }
+            _ => {
+                return channel.encrypt(&members, rng).map_err(GroupError::Encrypt);
+            }
+        };
+
+        let event = Event::Encrypted {
+            set_hash: events::set_hash(&members),
+        };
+        let session = EncryptionSession::new(
+            channel,
+            aggregate,
+            event,
+            Some(members.into_iter().collect()),
+        );
+        Ok(session.encrypt(rng))
+    }
+
+    fn node(&self, node: NodeId) -> Result<&Node, GroupError> {
+        self.nodes.get(node.0).ok_or(GroupError::UnknownNode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn nodes_address_their_subtree() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(6, &mut rng).unwrap();
+
+        let mut tree = GroupTree::<Bls12_381>::new("org");
+        let root = tree.root();
+        let engineering = tree.add_group(root, "engineering").unwrap();
+        let crypto = tree.add_group(engineering, "crypto").unwrap();
+        let sales = tree.add_group(root, "sales").unwrap();
+        tree.add_members(root, &[1]).unwrap();
+        tree.add_members(engineering, &[2]).unwrap();
+        tree.add_members(crypto, &[3, 4]).unwrap();
+        tree.add_members(sales, &[6]).unwrap();
+        assert_eq!(
+            tree.add_members(sales, &[5, 3]),
+            Err(GroupError::AlreadyMember(3))
+        );
+        assert_eq!(tree.group_of(5), None);
+        assert_eq!(tree.members(engineering).unwrap(), vec![2, 3, 4]);
+        assert_eq!(tree.members(root).unwrap(), vec![1, 2, 3, 4, 6]);
+        assert_eq!(tree.parent(crypto), Some(engineering));
+
+        for precomputed in [false, true].iter() {
+            if *precomputed {
+                tree.precompute(&channel).unwrap();
+            }
+            let (header, key) = tree
+                .encrypt_to_node(&channel, engineering, &mut rng)
+                .unwrap();
+            let members = tree.members(engineering).unwrap();
+            assert_eq!(
+                participants[2].decrypt(&members, &channel, &header),
+                Ok(key.clone())
+            );
+            assert_eq!(
+                participants[1].decrypt(&members, &channel, &header),
+                Ok(key.clone())
+            );
+            assert!(participants[0]
+                .decrypt(&members, &channel, &header)
+                .is_err());
+        }
+
+        let (header, key) = tree.encrypt_to_node(&channel, root, &mut rng).unwrap();
+        let members = tree.members(root).unwrap();
+        assert_eq!(
+            participants[5].decrypt(&members, &channel, &header),
+            Ok(key.clone())
+        );
+        assert_eq!(
+            tree.encrypt_to_node(&channel, NodeId(9), &mut rng).err(),
+            Some(GroupError::UnknownNode)
+        );
+        let empty = tree.add_group(sales, "empty").unwrap();
+        assert_eq!(
+            tree.encrypt_to_node(&channel, empty, &mut rng).err(),
+            Some(GroupError::Encrypt(EncryptError::EmptySet))
+        );
+    }
+}
@@ -0,0 +1,160 @@
+//! Encryption for recipient handles instead of raw identifiers.
+//!
+//! Applications usually keep recipient objects or records of their public keys rather than bare
+//! indices into the channel. [`BroadcastChannel::encrypt_for`] takes such handles, checks that
+//! each of them belongs to the channel, and builds the set of identifiers itself.
+
+use alloc::vec::Vec;
+
+use ark_ec::PairingEngine;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::error::EncryptError;
+use crate::{BroadcastChannel, Header, PublicKey, Recipient, SharedKey};
+
+/// Header, key and set of identifiers produced by [`BroadcastChannel::encrypt_for`].
+pub type Encryption<E> = (Header<E>, SharedKey<E>, Vec<usize>);
+
+/// Public record of a recipient: its identifier in the channel and its public key.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct RecipientPublicInfo<E: PairingEngine> {
+    identifier: usize,
+    public_key: PublicKey<E>,
+}
+
+impl<E: PairingEngine> RecipientPublicInfo<E> {
+    /// Record of participant `identifier` with public key `public_key`.
+    pub fn new(identifier: usize, public_key: PublicKey<E>) -> Self {
+        RecipientPublicInfo {
+            identifier,
+            public_key,
+        }
+    }
+
+    /// Identifier of the recipient
+    pub fn identifier(&self) -> usize {
+        self.identifier
+    }
+
+    /// Public key of the recipient
+    pub fn public_key(&self) -> PublicKey<E> {
+        self.public_key
+    }
+}
+
+/// Anything identifying a recipient of a channel.
+pub trait RecipientHandle<E: PairingEngine> {
+    /// Public record of the recipient.
+    fn public_info(&self) -> RecipientPublicInfo<E>;
+}
+
+impl<E: PairingEngine> RecipientHandle<E> for RecipientPublicInfo<E> {
+    fn public_info(&self) -> RecipientPublicInfo<E> {
+        *self
+    }
+}
+
+impl<E: PairingEngine> RecipientHandle<E> for Recipient<E> {
+    fn public_info(&self) -> RecipientPublicInfo<E> {
+        RecipientPublicInfo::new(self.identifier, self.public_key())
+    }
+}
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Public record of participant `identifier`, if its key has been issued.
+    pub fn public_info(&self, identifier: usize) -> Option<RecipientPublicInfo<E>> {
+        let public_key = self.public_key(identifier)?;
+        Some(RecipientPublicInfo::new(identifier, public_key))
+    }
+
+    /// Encrypt for the recipients behind `recipients`. Every handle must refer to an issued
+    /// participant and carry the public key the channel published for it. Returns the header,
+    /// the key and the set of identifiers, sorted and without repetitions, that recipients
+    /// need to decrypt.
+    pub fn encrypt_for<H, R>(
+        &self,
+        recipients: &[&H],
+        rng: &mut R,
+    ) -> Result<Encryption<E>, EncryptError>
+    where
+        H: RecipientHandle<E> + ?Sized,
+        R: RngCore + CryptoRng,
+    {
+        let mut set_recipients = Vec::with_capacity(recipients.len());
+        for handle in recipients.iter() {
+            let info = handle.public_info();
+            match self.public_key(info.identifier) {
+                None => return Err(EncryptError::UnknownRecipient(info.identifier)),
+                Some(public_key) if public_key != info.public_key => {
+                    return Err(EncryptError::PublicKeyMismatch(info.identifier))
+                }
+                Some(_) => set_recipients.push(info.identifier),
+            }
+        }
+        set_recipients.sort_unstable();
+        set_recipients.dedup();
+
+        let (header, key) = self.encrypt(&set_recipients, rng)?;
+        Ok((header, key, set_recipients))
+    }
+
+    /// Same as [`BroadcastChannel::encrypt_for`], for the union of several groups of recipients.
+    pub fn encrypt_for_groups<H, R>(
+        &self,
+        groups: &[&[&H]],
+        rng: &mut R,
+    ) -> Result<Encryption<E>, EncryptError>
+    where
+        H: RecipientHandle<E> + ?Sized,
+        R: RngCore + CryptoRng,
+    {
+        let recipients: Vec<&H> = groups
+            .iter()
+            .flat_map(|group| group.iter().copied())
+            .collect();
+        self.encrypt_for(&recipients, rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn encrypt_for_handles() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(5, &mut rng).unwrap();
+
+        let (header, key, set) = channel
+            .encrypt_for(
+                &[&participants[3], &participants[1], &participants[3]],
+                &mut rng,
+            )
+            .unwrap();
+        assert_eq!(set, vec![2, 4]);
+        assert_eq!(participants[1].decrypt(&set, &channel, &header), Ok(key));
+
+        let info = channel.public_info(5).unwrap();
+        let first = [&participants[0].public_info()];
+        let second = [&info];
+        let (header, key, set) = channel
+            .encrypt_for_groups(&[&first, &second], &mut rng)
+            .unwrap();
+        assert_eq!(set, vec![1, 5]);
+        assert_eq!(participants[4].decrypt(&set, &channel, &header), Ok(key));
+
+        let forged = RecipientPublicInfo::new(2, participants[0].public_key());
+        assert!(matches!(
+            channel.encrypt_for(&[&forged], &mut rng),
+            Err(EncryptError::PublicKeyMismatch(2))
+        ));
+        let unknown = RecipientPublicInfo::new(6, participants[0].public_key());
+        assert!(matches!(
+            channel.encrypt_for(&[&unknown], &mut rng),
+            Err(EncryptError::UnknownRecipient(6))
+        ));
+    }
+}
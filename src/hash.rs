@@ -0,0 +1,112 @@
+//! Hashing to the groups of the pairing.
+//!
+//! Binding identities or statements to group elements requires points whose discrete logarithm
+//! nobody knows, so multiplying a generator by a hash is not an option. [`hash_to_g1`] and
+//! [`hash_to_g2`] use try-and-increment: SHA-256 in counter mode yields candidate x-coordinates
+//! until one lies on the curve, and the point found is multiplied by the cofactor to land in the
+//! prime order subgroup. This works for any pairing engine, but is not the constant-time
+//! construction of the hash-to-curve RFC, so it should only be fed public inputs.
+//!
+//! [`hash_to_scalar`] reduces 64 bytes of SHA-256 output modulo the order of the groups, so that
+//! the bias of the result is negligible.
+//!
+//! Every hash is separated by a crate-specific tag per group and by a domain chosen by the
+//! caller, so that points derived for one purpose cannot be replayed in another.
+
+use alloc::vec::Vec;
+
+use ark_ec::{AffineCurve, PairingEngine};
+use ark_ff::{PrimeField, Zero};
+use sha2::{Digest, Sha256};
+
+/// Tag of the hashes to G1.
+const HASH_TO_G1_TAG: &[u8] = b"DGS-BE-HASH-TO-G1-V1";
+
+/// Tag of the hashes to G2.
+const HASH_TO_G2_TAG: &[u8] = b"DGS-BE-HASH-TO-G2-V1";
+
+/// Tag of the hashes to the scalar field.
+const HASH_TO_SCALAR_TAG: &[u8] = b"DGS-BE-HASH-TO-SCALAR-V1";
+
+/// Point of G1, in the prime order subgroup and not the identity, derived from `message` within
+/// `domain`.
+pub fn hash_to_g1<E: PairingEngine>(domain: &[u8], message: &[u8]) -> E::G1Projective {
+    hash_to_curve::<E::G1Affine>(HASH_TO_G1_TAG, domain, message)
+}
+
+/// Point of G2, in the prime order subgroup and not the identity, derived from `message` within
+/// `domain`.
+pub fn hash_to_g2<E: PairingEngine>(domain: &[u8], message: &[u8]) -> E::G2Projective {
+    hash_to_curve::<E::G2Affine>(HASH_TO_G2_TAG, domain, message)
+}
+
+/// Scalar derived from `message` within `domain`.
+pub fn hash_to_scalar<E: PairingEngine>(domain: &[u8], message: &[u8]) -> E::Fr {
+    let prefix = hash_prefix(HASH_TO_SCALAR_TAG, domain, message);
+    let mut bytes = Vec::with_capacity(64);
+    for block in 0u64..2 {
+        let mut hasher = prefix.clone();
+        hasher.update(block.to_le_bytes());
+        bytes.extend_from_slice(&hasher.finalize());
+    }
+    E::Fr::from_le_bytes_mod_order(&bytes)
+}
+
+/// Hash of the tag, domain and message, each but the tag prefixed with its length.
+fn hash_prefix(tag: &[u8], domain: &[u8], message: &[u8]) -> Sha256 {
+    let mut prefix = Sha256::new();
+    prefix.update(tag);
+    prefix.update((domain.len() as u64).to_le_bytes());
+    prefix.update(domain);
+    prefix.update((message.len() as u64).to_le_bytes());
+    prefix.update(message);
+    prefix
+}
+
+fn hash_to_curve<G: AffineCurve>(tag: &[u8], domain: &[u8], message: &[u8]) -> G::Projective {
+    // enough bytes for a compressed point, i.e. a coordinate and its flags
+    let length = G::zero().serialized_size();
+    let prefix = hash_prefix(tag, domain, message);
+
+    // about half of the candidates lie on the curve, so this terminates quickly
+    for attempt in 0u64.. {
+        let mut candidate = Vec::with_capacity(length + 32);
+        for block in 0u64.. {
+            if candidate.len() >= length {
+                break;
+            }
+            let mut hasher = prefix.clone();
+            hasher.update(attempt.to_le_bytes());
+            hasher.update(block.to_le_bytes());
+            candidate.extend_from_slice(&hasher.finalize());
+        }
+        candidate.truncate(length);
+
+        if let Some(point) = G::from_random_bytes(&candidate) {
+            let point = point.mul_by_cofactor_to_projective();
+            if !point.is_zero() {
+                return point;
+            }
+        }
+    }
+    unreachable!("the attempt counter does not overflow")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validate::in_subgroup;
+    use ark_bls12_381::Bls12_381;
+
+    #[test]
+    fn hashes_are_separated() {
+        let point = hash_to_g1::<Bls12_381>(b"identities", b"alice");
+        assert!(point == hash_to_g1::<Bls12_381>(b"identities", b"alice"));
+        assert!(point != hash_to_g1::<Bls12_381>(b"identities", b"bob"));
+        assert!(point != hash_to_g1::<Bls12_381>(b"identitiesa", b"lice"));
+        assert!(!point.is_zero() && in_subgroup(&point));
+
+        let point = hash_to_g2::<Bls12_381>(b"identities", b"alice");
+        assert!(!point.is_zero() && in_subgroup(&point));
+    }
+}
@@ -0,0 +1,311 @@
+//! Heap-free decryption for constrained recipients.
+//!
+//! A recipient only ever needs `n` of the points published by the broadcast channel, namely the
+//! points at positions `n + 1 - j + i` for every other participant `j`. Here those points are
+//! extracted once, on a machine that can hold the whole channel, into an [`SrsSlice`] whose size
+//! is fixed at compile time. The device then decrypts from the slice alone, with the recipient
+//! set given as a fixed-size membership buffer, so no `Vec` is involved on the device side.
+//!
+//! Firmware whose group has a fixed, small size uses a [`FixedBroadcastChannel`] instead: the
+//! points of a channel of `N` participants in arrays, from which both encryption and decryption
+//! run without allocating. Sets are membership arrays of length `N` there too, so an identifier
+//! outside of the channel cannot even be written, and a mismatch in size fails to compile. Only
+//! channels with every key issued, none retired and no policy can be copied, since the arrays
+//! carry none of that state.
+
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::Zero;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::error::{DecryptError, EncryptError};
+use crate::keystore::key_of_header;
+use crate::policy::Policy;
+use crate::session::EphemeralScalar;
+use crate::{BroadcastChannel, Header, Recipient, SharedKey};
+
+/// Public parameters needed by a single recipient of a channel with `N` participants.
+#[derive(Clone)]
+pub struct SrsSlice<E: PairingEngine, const N: usize> {
+    /// identifier of the recipient this slice was extracted for
+    identifier: usize,
+    /// `points[j - 1]` is the point the recipient adds when participant `j` is in the set. The
+    /// entry of the recipient itself is the identity, so it can be added unconditionally.
+    points: [E::G1Projective; N],
+}
+
+impl<E: PairingEngine, const N: usize> SrsSlice<E, N> {
+    /// Extract the slice of `channel` needed by recipient `identifier`. Returns `None` if the
+    /// channel does not have exactly `N` participants, or if `identifier` is not in `1..=N`.
+    pub fn extract(channel: &BroadcastChannel<E>, identifier: usize) -> Option<Self> {
+        if channel.number_participants != N || identifier == 0 || identifier > N {
+            return None;
+        }
+
+        let mut points = [E::G1Projective::zero(); N];
+        for (index, point) in (1..=N).zip(points.iter_mut()) {
+            if index != identifier {
+                *point = channel.broadcaster_pk_g1[N + 1 - index + identifier].into_projective();
+            }
+        }
+
+        Some(SrsSlice { identifier, points })
+    }
+
+    /// Identifier of the recipient this slice belongs to.
+    pub fn identifier(&self) -> usize {
+        self.identifier
+    }
+}
+
+impl<E: PairingEngine> Recipient<E> {
+    /// Decrypt a header using only the parameters in `srs`. `members[j - 1]` states whether
+    /// participant `j` is part of the recipient set. Fails if `srs` was extracted for a different
+    /// recipient, if the recipient is not in the set, if a component of the header is the
+    /// identity, or if the usage budget of the recipient is spent.
+    pub fn decrypt_heapless<const N: usize>(
+        &self,
+        members: &[bool; N],
+        srs: &SrsSlice<E, N>,
+        header: &Header<E>,
+    ) -> Result<SharedKey<E>, DecryptError> {
+        if srs.identifier != self.identifier {
+            return Err(DecryptError::WrongSlice(srs.identifier));
+        }
+        // the identifier of a slice is in 1..=N
+        if !members[self.identifier - 1] {
+            return Err(DecryptError::NotInSet);
+        }
+        if header.ctx_0.is_zero() || header.ctx_1.is_zero() {
+            return Err(DecryptError::IdentityPoint);
+        }
+        self.consume_use()?;
+
+        let mut aggregate = E::G1Projective::zero();
+        for (is_member, point) in members.iter().zip(srs.points.iter()) {
+            if *is_member {
+                aggregate += point;
+            }
+        }
+        Ok(SharedKey::new(key_of_header(
+            &self.key_pair.private_key,
+            &self.key_pair.public_key,
+            &aggregate,
+            header,
+        )))
+    }
+}
+
+/// Public parameters of a channel with exactly `N` participants, in arrays.
+#[derive(Clone)]
+pub struct FixedBroadcastChannel<E: PairingEngine, const N: usize> {
+    /// `P_1` to `P_N`
+    low: [E::G1Affine; N],
+    /// `P_{N+1}`, the identity, to `P_{2N}`
+    high: [E::G1Affine; N],
+    point_v: E::G1Projective,
+    /// generator `Q` of G2
+    point_q: E::G2Affine,
+    /// `Q_1`, alpha times the generator of G2
+    point_q_1: E::G2Affine,
+}
+
+impl<E: PairingEngine, const N: usize> FixedBroadcastChannel<E, N> {
+    /// Copy the points of `channel`. Returns `None` if it does not have exactly `N`
+    /// participants, or if its encryptions refuse sets the arrays cannot tell apart: some keys
+    /// are retired or not issued yet, or the channel has a policy.
+    pub fn extract(channel: &BroadcastChannel<E>) -> Option<Self> {
+        if channel.number_participants != N || N == 0 {
+            return None;
+        }
+        // the fixed channel encrypts for every member array, so it only copies channels that do
+        if channel.issued != N || !channel.retired.is_empty() || channel.policy != Policy::default()
+        {
+            return None;
+        }
+
+        let mut low = [E::G1Affine::zero(); N];
+        let mut high = [E::G1Affine::zero(); N];
+        low.copy_from_slice(&channel.broadcaster_pk_g1[1..N + 1]);
+        high.copy_from_slice(&channel.broadcaster_pk_g1[N + 1..2 * N + 1]);
+        Some(FixedBroadcastChannel {
+            low,
+            high,
+            point_v: channel.point_v,
+            point_q: channel.broadcaster_pk_g2[0],
+            point_q_1: channel.broadcaster_pk_g2[1],
+        })
+    }
+
+    /// Point `P_index`, for `index` in `1..=2N`.
+    fn point(&self, index: usize) -> &E::G1Affine {
+        if index <= N {
+            &self.low[index - 1]
+        } else {
+            &self.high[index - N - 1]
+        }
+    }
+
+    /// Generate a symmetric key and its header for the set where `members[j - 1]` states
+    /// whether participant `j` is in it. Fails if the set is empty.
+    pub fn encrypt<R>(
+        &self,
+        members: &[bool; N],
+        rng: &mut R,
+    ) -> Result<(Header<E>, SharedKey<E>), EncryptError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        self.encrypt_with_randomness(members, EphemeralScalar::random(rng))
+    }
+
+    /// Same as [`FixedBroadcastChannel::encrypt`], with the given ephemeral scalar, which is
+    /// consumed.
+    pub fn encrypt_with_randomness(
+        &self,
+        members: &[bool; N],
+        ephemeral: EphemeralScalar<E>,
+    ) -> Result<(Header<E>, SharedKey<E>), EncryptError> {
+        if !members.iter().any(|is_member| *is_member) {
+            return Err(EncryptError::EmptySet);
+        }
+
+        let mut ctx_0 = self.point_v;
+        for (identifier, _) in (1..=N).zip(members.iter()).filter(|(_, m)| **m) {
+            ctx_0.add_assign_mixed(self.point(N + 1 - identifier));
+        }
+        ctx_0 *= ephemeral.k;
+        let ctx_1 = self.point_q.mul(ephemeral.k);
+        let key = E::pairing(*self.point(N), self.point_q_1.mul(ephemeral.k));
+        Ok((Header { ctx_0, ctx_1 }, SharedKey::new(key)))
+    }
+}
+
+impl<E: PairingEngine> Recipient<E> {
+    /// Decrypt a header for the set given by `members`, as in
+    /// [`FixedBroadcastChannel::encrypt`], with the parameters in `channel` only.
+    pub fn decrypt_fixed<const N: usize>(
+        &self,
+        members: &[bool; N],
+        channel: &FixedBroadcastChannel<E, N>,
+        header: &Header<E>,
+    ) -> Result<SharedKey<E>, DecryptError> {
+        if self.identifier == 0 || self.identifier > N {
+            return Err(DecryptError::IdentifierOutOfRange(self.identifier));
+        }
+        if !members[self.identifier - 1] {
+            return Err(DecryptError::NotInSet);
+        }
+        if header.ctx_0.is_zero() || header.ctx_1.is_zero() {
+            return Err(DecryptError::IdentityPoint);
+        }
+        self.consume_use()?;
+
+        let mut aggregate = E::G1Projective::zero();
+        for (index, _) in (1..=N).zip(members.iter()).filter(|(_, m)| **m) {
+            if index != self.identifier {
+                aggregate.add_assign_mixed(channel.point(N + 1 - index + self.identifier));
+            }
+        }
+        Ok(SharedKey::new(key_of_header(
+            &self.key_pair.private_key,
+            &self.key_pair.public_key,
+            &aggregate,
+            header,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn heapless_decryption_matches() {
+        let mut rng = thread_rng();
+
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(6, &mut rng).unwrap();
+
+        let recipients = [2, 3, 6];
+        let members = [false, true, true, false, false, true];
+        let (header, key) = channel.encrypt(&recipients, &mut rng).unwrap();
+
+        for participant in participants.iter() {
+            let srs = SrsSlice::<Bls12_381, 6>::extract(&channel, participant.identifier).unwrap();
+            let dec_key = participant.decrypt_heapless(&members, &srs, &header);
+            if recipients.contains(&participant.identifier) {
+                assert_eq!(dec_key, Ok(key.clone()));
+                assert_eq!(
+                    Ok(key.clone()),
+                    participant.decrypt(&recipients, &channel, &header)
+                );
+            } else {
+                assert_eq!(dec_key, Err(DecryptError::NotInSet));
+            }
+        }
+
+        let srs = SrsSlice::<Bls12_381, 6>::extract(&channel, 1).unwrap();
+        assert_eq!(
+            participants[1].decrypt_heapless(&members, &srs, &header),
+            Err(DecryptError::WrongSlice(1))
+        );
+        let srs = SrsSlice::<Bls12_381, 6>::extract(&channel, 2).unwrap();
+        let identity = Header {
+            ctx_0: Zero::zero(),
+            ctx_1: header.ctx_1,
+        };
+        assert_eq!(
+            participants[1].decrypt_heapless(&members, &srs, &identity),
+            Err(DecryptError::IdentityPoint)
+        );
+        assert!(SrsSlice::<Bls12_381, 5>::extract(&channel, 1).is_none());
+        assert!(SrsSlice::<Bls12_381, 6>::extract(&channel, 7).is_none());
+    }
+
+    #[test]
+    fn fixed_channels_encrypt_and_decrypt() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(4, &mut rng).unwrap();
+        let fixed = FixedBroadcastChannel::<Bls12_381, 4>::extract(&channel).unwrap();
+        assert!(FixedBroadcastChannel::<Bls12_381, 3>::extract(&channel).is_none());
+
+        // retired keys, unissued slots and policies are refused rather than dropped
+        let mut retired = channel.clone();
+        retired.retire(2).unwrap();
+        assert!(FixedBroadcastChannel::<Bls12_381, 4>::extract(&retired).is_none());
+        let mut limited = channel.clone();
+        limited.set_policy(Policy::default().with_max_set_size(2));
+        assert!(FixedBroadcastChannel::<Bls12_381, 4>::extract(&limited).is_none());
+        let (reserved, _, _) =
+            BroadcastChannel::<Bls12_381>::init_with_reserve(3, 1, &mut rng).unwrap();
+        assert!(FixedBroadcastChannel::<Bls12_381, 4>::extract(&reserved).is_none());
+
+        let members = [true, false, true, true];
+        let (header, key) = fixed.encrypt(&members, &mut rng).unwrap();
+        assert_eq!(
+            participants[3].decrypt(&[1, 3, 4], &channel, &header),
+            Ok(key.clone())
+        );
+        assert_eq!(
+            participants[0].decrypt_fixed(&members, &fixed, &header),
+            Ok(key.clone())
+        );
+        assert_eq!(
+            participants[1].decrypt_fixed(&members, &fixed, &header),
+            Err(DecryptError::NotInSet)
+        );
+
+        let (header, key) = channel.encrypt(&[2, 3], &mut rng).unwrap();
+        assert_eq!(
+            participants[2].decrypt_fixed(&[false, true, true, false], &fixed, &header),
+            Ok(key.clone())
+        );
+        assert_eq!(
+            fixed.encrypt(&[false; 4], &mut rng).err(),
+            Some(EncryptError::EmptySet)
+        );
+    }
+}
@@ -0,0 +1,301 @@
+//! Identity-based broadcast encryption, after Delerablée, "Identity-Based Broadcast Encryption
+//! with Constant Size Ciphertexts and Private Keys", Asiacrypt 2007.
+//!
+//! Recipients are addressed by arbitrary byte strings instead of slots of a channel. Every
+//! identity is hashed to a scalar `H(ID)` with [`crate::hash::hash_to_scalar`], and the holder of
+//! the [`MasterKey`] gamma extracts its key `P / (gamma + H(ID))`. The public parameters do not
+//! grow with the number of identities but with the largest set of an encryption, `m`: they are
+//! `W = gamma P`, `e(P, Q)` and the powers `gamma^j Q` for `j` up to `m`.
+//!
+//! The header for a set `S` is `(-k W, k prod_{ID in S}(gamma + H(ID)) Q)` and its key is
+//! `e(P, Q)^k`, both of constant size. The second component expands the product as a polynomial
+//! in gamma and multiplies the powers published in G2 by its coefficients, so encrypting and
+//! decrypting cost quadratic time in the size of the set.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{Field, PrimeField, UniformRand, Zero};
+use rand_core::{CryptoRng, RngCore};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::error::{DecryptError, EncryptError, SetupError};
+use crate::hash::hash_to_scalar;
+
+/// Domain of the hashes of identities.
+const IDENTITY_DOMAIN: &[u8] = b"DGS-BE-IBBE-IDENTITY-V1";
+
+/// Public parameters of identity-based encryption for sets of at most `m` identities.
+#[derive(Clone)]
+pub struct IdentityChannel<E: PairingEngine> {
+    /// `gamma P`
+    point_w: E::G1Affine,
+    /// `e(P, Q)`
+    key_base: E::Fqk,
+    /// `gamma^j Q` for `j` in `0..=m`
+    g2_points: Vec<E::G2Affine>,
+}
+
+/// Secret gamma of identity-based encryption, from which every key is extracted.
+pub struct MasterKey<E: PairingEngine> {
+    gamma: E::Fr,
+}
+
+impl<E: PairingEngine> Drop for MasterKey<E> {
+    fn drop(&mut self) {
+        self.gamma.zeroize();
+    }
+}
+
+impl<E: PairingEngine> ZeroizeOnDrop for MasterKey<E> {}
+
+/// Key extracted for an identity.
+#[derive(Clone)]
+pub struct IdentityKey<E: PairingEngine> {
+    identity: Vec<u8>,
+    /// `P / (gamma + H(ID))`
+    point: E::G1Projective,
+}
+
+impl<E: PairingEngine> Drop for IdentityKey<E> {
+    fn drop(&mut self) {
+        self.point.zeroize();
+    }
+}
+
+impl<E: PairingEngine> ZeroizeOnDrop for IdentityKey<E> {}
+
+/// Header of an identity-based encryption.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct IdentityHeader<E: PairingEngine> {
+    /// component in G1
+    ctx_0: E::G1Projective,
+    /// component in G2
+    ctx_1: E::G2Projective,
+}
+
+/// Scalar of `identity`.
+fn hash_identity<E: PairingEngine>(identity: &[u8]) -> E::Fr {
+    hash_to_scalar::<E>(IDENTITY_DOMAIN, identity)
+}
+
+/// Coefficients of `prod (x + root)` over `roots`, from the constant one up.
+fn polynomial<F: Field>(roots: &[F]) -> Vec<F> {
+    let mut coefficients = vec![F::one()];
+    for root in roots.iter() {
+        coefficients.push(F::zero());
+        for position in (0..coefficients.len()).rev() {
+            let lower = if position == 0 {
+                F::zero()
+            } else {
+                coefficients[position - 1]
+            };
+            coefficients[position] = lower + coefficients[position] * root;
+        }
+    }
+    coefficients
+}
+
+impl<E: PairingEngine> IdentityChannel<E> {
+    /// Parameters and master key for sets of at most `max_recipients` identities. Fails if
+    /// `max_recipients` is zero.
+    pub fn init<R>(max_recipients: usize, rng: &mut R) -> Result<(Self, MasterKey<E>), SetupError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if max_recipients == 0 {
+            return Err(SetupError::NoParticipants);
+        }
+        let master = MasterKey {
+            gamma: E::Fr::rand(rng),
+        };
+
+        let mut point_w = E::G1Projective::prime_subgroup_generator();
+        point_w *= master.gamma;
+        let mut g2_points = Vec::with_capacity(max_recipients + 1);
+        let mut point = E::G2Projective::prime_subgroup_generator();
+        for _ in 0..=max_recipients {
+            g2_points.push(point);
+            point *= master.gamma;
+        }
+
+        let channel = IdentityChannel {
+            point_w: point_w.into_affine(),
+            key_base: E::pairing(
+                E::G1Affine::prime_subgroup_generator(),
+                E::G2Affine::prime_subgroup_generator(),
+            ),
+            g2_points: E::G2Projective::batch_normalization_into_affine(&g2_points),
+        };
+        Ok((channel, master))
+    }
+
+    /// Largest number of identities of a set.
+    pub fn max_recipients(&self) -> usize {
+        self.g2_points.len() - 1
+    }
+
+    /// Sum of the powers of gamma in G2 weighted by `coefficients`.
+    fn g2_combination(&self, coefficients: &[E::Fr]) -> E::G2Projective {
+        let mut sum = E::G2Projective::zero();
+        for (coefficient, point) in coefficients.iter().zip(self.g2_points.iter()) {
+            sum += &point.mul(*coefficient);
+        }
+        sum
+    }
+
+    /// Generate a symmetric key and its header for the identities of `set_recipients`. Fails if
+    /// the set is empty, larger than the parameters allow, or contains an identity more than
+    /// once, in which case the error carries the position of its second occurrence.
+    pub fn encrypt<R>(
+        &self,
+        set_recipients: &[&[u8]],
+        rng: &mut R,
+    ) -> Result<(IdentityHeader<E>, E::Fqk), EncryptError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if set_recipients.is_empty() {
+            return Err(EncryptError::EmptySet);
+        }
+        if set_recipients.len() > self.max_recipients() {
+            return Err(EncryptError::SetTooLarge {
+                size: set_recipients.len(),
+                limit: self.max_recipients(),
+            });
+        }
+        if let Some(position) = (1..set_recipients.len())
+            .find(|position| set_recipients[..*position].contains(&set_recipients[*position]))
+        {
+            return Err(EncryptError::DuplicateRecipient(position));
+        }
+
+        let scalars: Vec<E::Fr> = set_recipients
+            .iter()
+            .map(|identity| hash_identity::<E>(identity))
+            .collect();
+        let mut k = E::Fr::rand(rng);
+        let mut ctx_0 = -self.point_w.into_projective();
+        ctx_0 *= k;
+        let mut ctx_1 = self.g2_combination(&polynomial(&scalars));
+        ctx_1 *= k;
+        let key = self.key_base.pow(k.into_repr());
+        k.zeroize();
+
+        Ok((IdentityHeader { ctx_0, ctx_1 }, key))
+    }
+}
+
+impl<E: PairingEngine> MasterKey<E> {
+    /// Key of `identity`. Returns `None` in the negligible case of an identity hashing to minus
+    /// gamma.
+    pub fn extract(&self, identity: &[u8]) -> Option<IdentityKey<E>> {
+        let mut exponent = (self.gamma + hash_identity::<E>(identity)).inverse()?;
+        let mut point = E::G1Projective::prime_subgroup_generator();
+        point *= exponent;
+        exponent.zeroize();
+        Some(IdentityKey {
+            identity: identity.to_vec(),
+            point,
+        })
+    }
+}
+
+impl<E: PairingEngine> IdentityKey<E> {
+    /// Identity the key was extracted for.
+    pub fn identity(&self) -> &[u8] {
+        &self.identity
+    }
+
+    /// Decrypt a header encrypted for the identities of `set_recipients`. Fails if the set does
+    /// not contain the identity of the key, or if the header contains the identity point.
+    pub fn decrypt(
+        &self,
+        set_recipients: &[&[u8]],
+        channel: &IdentityChannel<E>,
+        header: &IdentityHeader<E>,
+    ) -> Result<E::Fqk, DecryptError> {
+        let position = set_recipients
+            .iter()
+            .position(|identity| *identity == self.identity.as_slice())
+            .ok_or(DecryptError::NotInSet)?;
+        if header.ctx_0.is_zero() || header.ctx_1.is_zero() {
+            return Err(DecryptError::IdentityPoint);
+        }
+
+        let others: Vec<E::Fr> = set_recipients
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| *index != position)
+            .map(|(_, identity)| hash_identity::<E>(identity))
+            .collect();
+        // prod_{j != i}(gamma + H_j) = c + gamma p(gamma), with c the product of the hashes
+        let coefficients = polynomial(&others);
+        // an identity hashing to zero, which never happens
+        let constant = coefficients[0].inverse().ok_or(DecryptError::NotInSet)?;
+        let point_p = channel.g2_combination(&coefficients[1..]);
+
+        // (e(ctx_0, p(gamma) Q) e(d, ctx_1))^(1 / c)
+        let pairs = [
+            (
+                header.ctx_0.into_affine().into(),
+                point_p.into_affine().into(),
+            ),
+            (
+                self.point.into_affine().into(),
+                header.ctx_1.into_affine().into(),
+            ),
+        ];
+        Ok(E::product_of_pairings(pairs.iter()).pow(constant.into_repr()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn identities_decrypt_their_sets() {
+        let mut rng = thread_rng();
+        let (channel, master) = IdentityChannel::<Bls12_381>::init(3, &mut rng).unwrap();
+        let alice = master.extract(b"alice").unwrap();
+        let bob = master.extract(b"bob").unwrap();
+
+        let set: [&[u8]; 3] = [b"carol", b"alice", b"bob"];
+        let (header, key) = channel.encrypt(&set, &mut rng).unwrap();
+        assert_eq!(alice.decrypt(&set, &channel, &header), Ok(key));
+        assert_eq!(bob.decrypt(&set, &channel, &header), Ok(key));
+        assert_ne!(
+            alice.decrypt(&[b"alice", b"bob"], &channel, &header),
+            Ok(key)
+        );
+
+        let (header, key) = channel.encrypt(&[b"alice"], &mut rng).unwrap();
+        assert_eq!(alice.decrypt(&[b"alice"], &channel, &header), Ok(key));
+        assert_eq!(
+            bob.decrypt(&[b"alice"], &channel, &header),
+            Err(DecryptError::NotInSet)
+        );
+    }
+
+    #[test]
+    fn sets_are_checked() {
+        let mut rng = thread_rng();
+        let (channel, _) = IdentityChannel::<Bls12_381>::init(2, &mut rng).unwrap();
+        assert!(matches!(
+            channel.encrypt(&[b"a", b"b", b"c"], &mut rng),
+            Err(EncryptError::SetTooLarge { size: 3, limit: 2 })
+        ));
+        assert!(matches!(
+            channel.encrypt(&[b"a", b"a"], &mut rng),
+            Err(EncryptError::DuplicateRecipient(1))
+        ));
+        assert!(matches!(
+            channel.encrypt(&[], &mut rng),
+            Err(EncryptError::EmptySet)
+        ));
+    }
+}
@@ -0,0 +1,407 @@
+//! Known-answer tests in a textual format, for other implementations of the scheme.
+//!
+//! A [`KnownAnswer`] fixes every input of a setup and an encryption, and records what this crate
+//! computes from them, so that another implementation can check its outputs byte for byte. The
+//! only input is a seed: alpha, gamma and the ephemeral scalar `k` are
+//! [`crate::hash::hash_to_scalar`] of the seed in the domains [`KAT_ALPHA_DOMAIN`],
+//! [`KAT_GAMMA_DOMAIN`] and [`KAT_K_DOMAIN`], which every implementation can reproduce without
+//! agreeing on a random number generator. The derived key is 32 bytes of
+//! [`crate::kdf::derive_key`] with [`HkdfSha256`] and the info [`KAT_INFO`].
+//!
+//! The format follows the response files of the NIST known-answer tests: records are separated
+//! by blank lines, every line of a record is `name = value`, lines starting with `#` are
+//! comments, and byte strings are hexadecimal. A record starts with its `count`, then gives the
+//! `seed`, the number of `participants` and the `set` as identifiers separated by commas, then
+//! the scalars `alpha`, `gamma` and `k`, the `parameters` of the channel, one `recipient` line per
+//! key pair in the order of the identifiers, the `header`, the `key` of the header and the
+//! `derived_key`. Scalars, points, keys and parameters are in their compressed encodings.
+//!
+//! Public parameters alone are exported by [`export_parameters`], as a record with the
+//! `participants` and `parameters` fields.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt::Write;
+
+use ark_ec::PairingEngine;
+
+use crate::encoding::{from_hex, to_bytes, to_hex};
+use crate::error::{KatError, SetupError};
+use crate::hash::hash_to_scalar;
+use crate::kdf::{derive_key, HkdfSha256};
+use crate::session::EphemeralScalar;
+use crate::set::RecipientSet;
+use crate::BroadcastChannel;
+
+/// Domain of the hash of the seed giving alpha.
+pub const KAT_ALPHA_DOMAIN: &[u8] = b"DGS-BE-KAT-ALPHA-V1";
+
+/// Domain of the hash of the seed giving gamma.
+pub const KAT_GAMMA_DOMAIN: &[u8] = b"DGS-BE-KAT-GAMMA-V1";
+
+/// Domain of the hash of the seed giving the ephemeral scalar of the encryption.
+pub const KAT_K_DOMAIN: &[u8] = b"DGS-BE-KAT-K-V1";
+
+/// Info of the derived key.
+pub const KAT_INFO: &[u8] = b"DGS-BE-KAT-V1";
+
+/// Inputs of a setup and an encryption, with the outputs expected from them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KnownAnswer {
+    /// seed alpha, gamma and `k` are derived from
+    pub seed: Vec<u8>,
+    /// number of participants of the channel
+    pub participants: usize,
+    /// set the header is encrypted for
+    pub set: RecipientSet,
+    /// encoding of alpha
+    pub alpha: Vec<u8>,
+    /// encoding of gamma
+    pub gamma: Vec<u8>,
+    /// encoding of the ephemeral scalar
+    pub k: Vec<u8>,
+    /// encoding of the channel
+    pub parameters: Vec<u8>,
+    /// encodings of the key pairs of participants 1 to `participants`
+    pub recipients: Vec<Vec<u8>>,
+    /// encoding of the header
+    pub header: Vec<u8>,
+    /// encoding of the key of the header
+    pub key: Vec<u8>,
+    /// 32-byte key derived from the key of the header
+    pub derived_key: Vec<u8>,
+}
+
+impl KnownAnswer {
+    /// Set up a channel of `participants` participants from `seed`, and encrypt for `set`.
+    pub fn compute<E: PairingEngine>(
+        seed: &[u8],
+        participants: usize,
+        set: &RecipientSet,
+    ) -> Result<Self, KatError> {
+        if participants == 0 {
+            return Err(KatError::Setup(SetupError::NoParticipants));
+        }
+
+        let alpha = hash_to_scalar::<E>(KAT_ALPHA_DOMAIN, seed);
+        let gamma = hash_to_scalar::<E>(KAT_GAMMA_DOMAIN, seed);
+        let k = hash_to_scalar::<E>(KAT_K_DOMAIN, seed);
+        let (channel, recipients, _) = BroadcastChannel::<E>::setup(participants, alpha, gamma);
+        // a hash is zero with negligible probability
+        let ephemeral = EphemeralScalar::from_scalar(k).unwrap();
+        let (header, key) = channel
+            .encrypt_with_randomness(set.as_slice(), ephemeral)
+            .map_err(KatError::Encrypt)?;
+        let mut derived_key = vec![0u8; 32];
+        // 32 bytes are within the output of HKDF
+        derive_key::<E, HkdfSha256>(key.key(), KAT_INFO, &mut derived_key).unwrap();
+
+        Ok(KnownAnswer {
+            seed: seed.to_vec(),
+            participants,
+            set: set.clone(),
+            alpha: to_bytes(&alpha),
+            gamma: to_bytes(&gamma),
+            k: to_bytes(&k),
+            parameters: channel.to_bytes(),
+            recipients: recipients
+                .iter()
+                .map(|recipient| recipient.to_bytes())
+                .collect(),
+            header: header.to_bytes(),
+            key: to_bytes(&key),
+            derived_key,
+        })
+    }
+
+    /// Known answer from the fields of a record, the last of which is on line `end`.
+    fn from_fields(fields: &[Field<'_>], end: usize) -> Result<Self, KatError> {
+        let mut count = None;
+        let mut seed = None;
+        let mut participants = None;
+        let mut set = None;
+        let mut alpha = None;
+        let mut gamma = None;
+        let mut k = None;
+        let mut parameters = None;
+        let mut recipients = Vec::new();
+        let mut header = None;
+        let mut key = None;
+        let mut derived_key = None;
+
+        for field in fields {
+            let slot = match field.name {
+                "count" => {
+                    set_once(&mut count, field, field.number()?)?;
+                    continue;
+                }
+                "participants" => {
+                    set_once(&mut participants, field, field.number()?)?;
+                    continue;
+                }
+                "set" => {
+                    set_once(&mut set, field, field.set()?)?;
+                    continue;
+                }
+                "recipient" => {
+                    recipients.push(field.bytes()?);
+                    continue;
+                }
+                "seed" => &mut seed,
+                "alpha" => &mut alpha,
+                "gamma" => &mut gamma,
+                "k" => &mut k,
+                "parameters" => &mut parameters,
+                "header" => &mut header,
+                "key" => &mut key,
+                "derived_key" => &mut derived_key,
+                _ => return Err(KatError::Syntax(field.line)),
+            };
+            set_once(slot, field, field.bytes()?)?;
+        }
+
+        let missing = KatError::MissingField(end);
+        count.ok_or(missing)?;
+        Ok(KnownAnswer {
+            seed: seed.ok_or(missing)?,
+            participants: participants.ok_or(missing)?,
+            set: set.ok_or(missing)?,
+            alpha: alpha.ok_or(missing)?,
+            gamma: gamma.ok_or(missing)?,
+            k: k.ok_or(missing)?,
+            parameters: parameters.ok_or(missing)?,
+            recipients,
+            header: header.ok_or(missing)?,
+            key: key.ok_or(missing)?,
+            derived_key: derived_key.ok_or(missing)?,
+        })
+    }
+}
+
+/// `name = value` line of a record.
+struct Field<'a> {
+    line: usize,
+    name: &'a str,
+    value: &'a str,
+}
+
+impl Field<'_> {
+    fn bytes(&self) -> Result<Vec<u8>, KatError> {
+        from_hex(self.value).ok_or(KatError::InvalidValue(self.line))
+    }
+
+    fn number(&self) -> Result<usize, KatError> {
+        self.value
+            .parse()
+            .map_err(|_| KatError::InvalidValue(self.line))
+    }
+
+    /// Set given as increasing identifiers separated by commas, empty for the empty set.
+    fn set(&self) -> Result<RecipientSet, KatError> {
+        let invalid = KatError::InvalidValue(self.line);
+        let identifiers = match self.value {
+            "" => Vec::new(),
+            value => value
+                .split(',')
+                .map(|identifier| identifier.trim().parse().map_err(|_| invalid))
+                .collect::<Result<Vec<usize>, _>>()?,
+        };
+        let set = RecipientSet::new(&identifiers);
+        if set.as_slice() != identifiers.as_slice() {
+            return Err(invalid);
+        }
+        Ok(set)
+    }
+}
+
+fn set_once<T>(slot: &mut Option<T>, field: &Field<'_>, value: T) -> Result<(), KatError> {
+    if slot.replace(value).is_some() {
+        return Err(KatError::Syntax(field.line));
+    }
+    Ok(())
+}
+
+/// Records of `text`, with the number of the line each ends on.
+fn records(text: &str) -> Result<Vec<(Vec<Field<'_>>, usize)>, KatError> {
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut end = 0;
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            continue;
+        }
+        if line.is_empty() {
+            if !fields.is_empty() {
+                records.push((core::mem::take(&mut fields), end));
+            }
+            continue;
+        }
+
+        let (name, value) = line.split_once('=').ok_or(KatError::Syntax(index + 1))?;
+        fields.push(Field {
+            line: index + 1,
+            name: name.trim(),
+            value: value.trim(),
+        });
+        end = index + 1;
+    }
+    if !fields.is_empty() {
+        records.push((fields, end));
+    }
+    Ok(records)
+}
+
+/// Text of `answers`, numbered from zero.
+pub fn export(answers: &[KnownAnswer]) -> String {
+    let mut text = String::from(
+        "# DGS broadcast encryption, known-answer tests\n\
+         # alpha, gamma and k are hash_to_scalar of the seed in DGS-BE-KAT-ALPHA-V1,\n\
+         # DGS-BE-KAT-GAMMA-V1 and DGS-BE-KAT-K-V1, derived_key uses HKDF-SHA256 and \
+         DGS-BE-KAT-V1\n",
+    );
+    for (count, answer) in answers.iter().enumerate() {
+        let set: Vec<String> = answer
+            .set
+            .iter()
+            .map(|identifier| format!("{}", identifier))
+            .collect();
+        // writing into a string cannot fail
+        write!(
+            text,
+            "\ncount = {}\nseed = {}\nparticipants = {}\nset = {}\nalpha = {}\ngamma = {}\n\
+             k = {}\nparameters = {}\n",
+            count,
+            to_hex(&answer.seed),
+            answer.participants,
+            set.join(","),
+            to_hex(&answer.alpha),
+            to_hex(&answer.gamma),
+            to_hex(&answer.k),
+            to_hex(&answer.parameters),
+        )
+        .unwrap();
+        for recipient in answer.recipients.iter() {
+            writeln!(text, "recipient = {}", to_hex(recipient)).unwrap();
+        }
+        write!(
+            text,
+            "header = {}\nkey = {}\nderived_key = {}\n",
+            to_hex(&answer.header),
+            to_hex(&answer.key),
+            to_hex(&answer.derived_key),
+        )
+        .unwrap();
+    }
+    text
+}
+
+/// Known answers of `text`, as written by [`export`] or by another implementation. Only the
+/// syntax is checked here: the answers are as given, right or wrong.
+pub fn import(text: &str) -> Result<Vec<KnownAnswer>, KatError> {
+    records(text)?
+        .iter()
+        .map(|(fields, end)| KnownAnswer::from_fields(fields, *end))
+        .collect()
+}
+
+/// Text of the public parameters of `channel`.
+pub fn export_parameters<E: PairingEngine>(channel: &BroadcastChannel<E>) -> String {
+    format!(
+        "participants = {}\nparameters = {}\n",
+        channel.number_participants,
+        to_hex(&channel.to_bytes())
+    )
+}
+
+/// Channel of the single record of `text`, as written by [`export_parameters`]. The number of
+/// participants must be the one of the parameters.
+pub fn import_parameters<E: PairingEngine>(text: &str) -> Result<BroadcastChannel<E>, KatError> {
+    let records = records(text)?;
+    let (fields, end) = match records.as_slice() {
+        [record] => record,
+        _ => return Err(KatError::Syntax(1)),
+    };
+
+    let mut participants = None;
+    let mut parameters = None;
+    for field in fields {
+        match field.name {
+            "participants" => set_once(&mut participants, field, field.number()?)?,
+            "parameters" => set_once(&mut parameters, field, field)?,
+            _ => return Err(KatError::Syntax(field.line)),
+        }
+    }
+    let participants = participants.ok_or(KatError::MissingField(*end))?;
+    let field = parameters.ok_or(KatError::MissingField(*end))?;
+    let channel = BroadcastChannel::<E>::try_from(field.bytes()?.as_slice())
+        .map_err(|_| KatError::InvalidValue(field.line))?;
+    if channel.number_participants != participants {
+        return Err(KatError::InvalidValue(field.line));
+    }
+    Ok(channel)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Header, Recipient};
+    use ark_bls12_381::Bls12_381;
+
+    #[test]
+    fn known_answers_round_trip() {
+        let set = RecipientSet::new(&[1, 3]);
+        let answer = KnownAnswer::compute::<Bls12_381>(&[7; 32], 4, &set).unwrap();
+        assert_eq!(
+            KnownAnswer::compute::<Bls12_381>(&[7; 32], 4, &set).unwrap(),
+            answer
+        );
+        let other =
+            KnownAnswer::compute::<Bls12_381>(&[8; 32], 4, &RecipientSet::new(&[2])).unwrap();
+        assert_ne!(other.parameters, answer.parameters);
+
+        // the recorded outputs are consistent with each other
+        let channel =
+            BroadcastChannel::<Bls12_381>::try_from(answer.parameters.as_slice()).unwrap();
+        let recipient = Recipient::<Bls12_381>::try_from(answer.recipients[2].as_slice()).unwrap();
+        let header = Header::<Bls12_381>::try_from(answer.header.as_slice()).unwrap();
+        let key = recipient
+            .decrypt(set.as_slice(), &channel, &header)
+            .unwrap();
+        assert_eq!(to_bytes(&key), answer.key);
+        assert_eq!(answer.recipients.len(), 4);
+
+        let text = export(&[answer.clone(), other.clone()]);
+        assert_eq!(import(&text), Ok(vec![answer, other]));
+        let parameters = export_parameters(&channel);
+        let imported = import_parameters::<Bls12_381>(&parameters).unwrap();
+        assert_eq!(imported.to_bytes(), channel.to_bytes());
+
+        assert_eq!(
+            import("count = 0\nseed = 00\nsalt = 00\n"),
+            Err(KatError::Syntax(3))
+        );
+        assert_eq!(
+            import("count = 0\nseed = 0g\n"),
+            Err(KatError::InvalidValue(2))
+        );
+        assert_eq!(
+            import("count = 0\nset = 3,1\n"),
+            Err(KatError::InvalidValue(2))
+        );
+        assert_eq!(
+            import("# comment\ncount = 0\nseed = 00\n\n"),
+            Err(KatError::MissingField(3))
+        );
+        assert_eq!(
+            import_parameters::<Bls12_381>(
+                &parameters.replace("participants = 4", "participants = 5")
+            )
+            .err(),
+            Some(KatError::InvalidValue(2))
+        );
+    }
+}
@@ -0,0 +1,326 @@
+//! Derivation of symmetric keys from the keys of the headers.
+//!
+//! The key recovered from a header is an element of the target group, which has to go through a
+//! key derivation function before it can key a symmetric cipher. The [`KeyDerivation`] trait lets
+//! deployments pick the hash behind it. [`HkdfSha256`] is always available, [`Shake256Kdf`] and
+//! [`Blake3Kdf`] are behind the `sha3` and `blake3` features. The identifier of the derivation is
+//! part of the cipher suite identifier, which is mixed into every derived key.
+//!
+//! The cipher suite identifier also carries the version of the derivation context. Headers sent
+//! as a [`VersionedHeader`] tell recipients which version the sender derived with, and recipients
+//! derive through [`KdfVersions`], the set of versions they accept. Migrating to a new context is
+//! then a matter of accepting both versions for as long as old headers are around.
+//!
+//! [`SharedKey::derive_key`] is the shortcut for the common case, a key of 32 bytes derived with
+//! [`HkdfSha256`] in the current version.
+
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use ark_ec::PairingEngine;
+use ark_serialize::CanonicalSerialize;
+use hkdf::Hkdf;
+use sha2::Sha256;
+
+use crate::error::KeyDerivationError;
+use crate::{Header, SharedKey};
+
+/// Version of the derivation context used by [`derive_key`].
+pub const KDF_CONTEXT_VERSION: u8 = 1;
+
+/// Function deriving uniform bytes from a secret.
+pub trait KeyDerivation {
+    /// Identifier of the derivation within the cipher suite identifier.
+    const ID: &'static str;
+
+    /// Fill `output` with bytes derived from `secret`, bound to `info`.
+    fn derive(secret: &[u8], info: &[u8], output: &mut [u8]) -> Result<(), KeyDerivationError>;
+}
+
+/// HKDF with SHA-256. Derives at most `255 * 32` bytes.
+pub struct HkdfSha256;
+
+impl KeyDerivation for HkdfSha256 {
+    const ID: &'static str = "HKDF-SHA256";
+
+    fn derive(secret: &[u8], info: &[u8], output: &mut [u8]) -> Result<(), KeyDerivationError> {
+        Hkdf::<Sha256>::new(None, secret)
+            .expand(info, output)
+            .map_err(|_| KeyDerivationError::OutputTooLong)
+    }
+}
+
+/// SHAKE256 over the length-prefixed secret followed by `info`.
+#[cfg(feature = "sha3")]
+pub struct Shake256Kdf;
+
+#[cfg(feature = "sha3")]
+impl KeyDerivation for Shake256Kdf {
+    const ID: &'static str = "SHAKE256";
+
+    fn derive(secret: &[u8], info: &[u8], output: &mut [u8]) -> Result<(), KeyDerivationError> {
+        use sha3::digest::{ExtendableOutput, Update, XofReader};
+
+        let mut shake = sha3::Shake256::default();
+        shake.update((secret.len() as u64).to_le_bytes());
+        shake.update(secret);
+        shake.update(info);
+        shake.finalize_xof().read(output);
+        Ok(())
+    }
+}
+
+/// BLAKE3 in key derivation mode, over the length-prefixed secret followed by `info`.
+#[cfg(feature = "blake3")]
+pub struct Blake3Kdf;
+
+#[cfg(feature = "blake3")]
+impl KeyDerivation for Blake3Kdf {
+    const ID: &'static str = "BLAKE3";
+
+    fn derive(secret: &[u8], info: &[u8], output: &mut [u8]) -> Result<(), KeyDerivationError> {
+        blake3::Hasher::new_derive_key("DGS-BE key derivation V1")
+            .update(&(secret.len() as u64).to_le_bytes())
+            .update(secret)
+            .update(info)
+            .finalize_xof()
+            .fill(output);
+        Ok(())
+    }
+}
+
+/// Identifier of the cipher suite using derivation `K` in the current context version.
+pub fn cipher_suite_id<K: KeyDerivation>() -> String {
+    versioned_suite_id::<K>(KDF_CONTEXT_VERSION)
+}
+
+/// Identifier of the cipher suite using derivation `K` in context version `version`.
+pub fn versioned_suite_id<K: KeyDerivation>(version: u8) -> String {
+    format!("DGS-BE-V{}_{}", version, K::ID)
+}
+
+/// Fill `output` with a symmetric key derived from the header key `key` with `K`, bound to the
+/// cipher suite and to `info`, in the current context version.
+pub fn derive_key<E, K>(
+    key: &E::Fqk,
+    info: &[u8],
+    output: &mut [u8],
+) -> Result<(), KeyDerivationError>
+where
+    E: PairingEngine,
+    K: KeyDerivation,
+{
+    derive_versioned_key::<E, K>(key, KDF_CONTEXT_VERSION, info, output)
+}
+
+impl<E: PairingEngine> SharedKey<E> {
+    /// Key of 32 bytes derived from this key with [`HkdfSha256`], as by [`derive_key`], bound to
+    /// `context`.
+    pub fn derive_key(&self, context: &[u8]) -> [u8; 32] {
+        let mut output = [0u8; 32];
+        // HKDF-SHA256 derives up to 255 blocks of 32 bytes
+        derive_key::<E, HkdfSha256>(self.key(), context, &mut output).unwrap();
+        output
+    }
+}
+
+/// Same as [`derive_key`], in context version `version`.
+pub fn derive_versioned_key<E, K>(
+    key: &E::Fqk,
+    version: u8,
+    info: &[u8],
+    output: &mut [u8],
+) -> Result<(), KeyDerivationError>
+where
+    E: PairingEngine,
+    K: KeyDerivation,
+{
+    let mut secret = Vec::with_capacity(key.serialized_size());
+    // serializing into a vector cannot fail
+    key.serialize(&mut secret).unwrap();
+
+    let mut bound_info = versioned_suite_id::<K>(version).into_bytes();
+    bound_info.extend_from_slice(info);
+    K::derive(&secret, &bound_info, output)
+}
+
+/// Header tagged with the context version its key is derived in. Encoded as the version byte
+/// followed by the header.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct VersionedHeader<E: PairingEngine> {
+    pub(crate) version: u8,
+    pub(crate) header: Header<E>,
+}
+
+impl<E: PairingEngine> VersionedHeader<E> {
+    /// Tag `header` with the current context version.
+    pub fn new(header: Header<E>) -> Self {
+        Self::with_version(header, KDF_CONTEXT_VERSION)
+    }
+
+    /// Tag `header` with context version `version`.
+    pub fn with_version(header: Header<E>, version: u8) -> Self {
+        VersionedHeader { version, header }
+    }
+
+    /// Context version of the header.
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// Untagged header, to be decrypted.
+    pub fn header(&self) -> &Header<E> {
+        &self.header
+    }
+}
+
+/// Context versions a recipient accepts to derive keys in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KdfVersions {
+    accepted: BTreeSet<u8>,
+}
+
+impl Default for KdfVersions {
+    /// Only the current version.
+    fn default() -> Self {
+        Self::new(&[KDF_CONTEXT_VERSION])
+    }
+}
+
+impl KdfVersions {
+    /// Accept the versions in `versions`.
+    pub fn new(versions: &[u8]) -> Self {
+        KdfVersions {
+            accepted: versions.iter().copied().collect(),
+        }
+    }
+
+    /// Also accept `version`.
+    pub fn accept(&mut self, version: u8) {
+        self.accepted.insert(version);
+    }
+
+    /// Stop accepting `version`, once no header of that version is expected anymore.
+    pub fn retire(&mut self, version: u8) {
+        self.accepted.remove(&version);
+    }
+
+    /// Whether `version` is accepted.
+    pub fn accepts(&self, version: u8) -> bool {
+        self.accepted.contains(&version)
+    }
+
+    /// Derive the symmetric key of `header` from its header key `key`, as [`derive_versioned_key`]
+    /// does in the version of the header, if that version is accepted.
+    pub fn derive_key<E, K>(
+        &self,
+        header: &VersionedHeader<E>,
+        key: &E::Fqk,
+        info: &[u8],
+        output: &mut [u8],
+    ) -> Result<(), KeyDerivationError>
+    where
+        E: PairingEngine,
+        K: KeyDerivation,
+    {
+        if !self.accepts(header.version) {
+            return Err(KeyDerivationError::UnsupportedVersion(header.version));
+        }
+        derive_versioned_key::<E, K>(key, header.version, info, output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BroadcastChannel;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn recipients_derive_same_key() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(3, &mut rng).unwrap();
+        let (header, key) = channel.encrypt(&[1, 3], &mut rng).unwrap();
+        let recovered = participants[2].decrypt(&[1, 3], &channel, &header).unwrap();
+
+        let mut sender = [0u8; 32];
+        let mut recipient = [0u8; 32];
+        derive_key::<Bls12_381, HkdfSha256>(key.key(), b"app", &mut sender).unwrap();
+        derive_key::<Bls12_381, HkdfSha256>(recovered.key(), b"app", &mut recipient).unwrap();
+        assert_eq!(sender, recipient);
+        assert_eq!(recovered.derive_key(b"app"), sender);
+
+        let mut other_info = [0u8; 32];
+        derive_key::<Bls12_381, HkdfSha256>(key.key(), b"other", &mut other_info).unwrap();
+        assert_ne!(sender, other_info);
+
+        let mut too_long = vec![0u8; 255 * 32 + 1];
+        assert_eq!(
+            derive_key::<Bls12_381, HkdfSha256>(key.key(), b"app", &mut too_long),
+            Err(KeyDerivationError::OutputTooLong)
+        );
+        assert_eq!(cipher_suite_id::<HkdfSha256>(), "DGS-BE-V1_HKDF-SHA256");
+    }
+
+    #[test]
+    fn versions_are_negotiated() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(2, &mut rng).unwrap();
+        let (header, key) = channel.encrypt(&[2], &mut rng).unwrap();
+        let mut current = [0u8; 32];
+        derive_key::<Bls12_381, HkdfSha256>(key.key(), b"app", &mut current).unwrap();
+
+        let old = VersionedHeader::new(header);
+        let new = VersionedHeader::with_version(header, 2);
+        let bytes = old.to_bytes();
+        assert_eq!(bytes[0], KDF_CONTEXT_VERSION);
+        let decoded = VersionedHeader::<Bls12_381>::try_from(bytes.as_slice()).unwrap();
+        assert!(decoded == old);
+
+        let recovered = participants[1]
+            .decrypt(&[2], &channel, decoded.header())
+            .unwrap();
+        let mut versions = KdfVersions::default();
+        let mut output = [0u8; 32];
+        versions
+            .derive_key::<_, HkdfSha256>(&decoded, recovered.key(), b"app", &mut output)
+            .unwrap();
+        assert_eq!(output, current);
+        assert_eq!(
+            versions.derive_key::<_, HkdfSha256>(&new, recovered.key(), b"app", &mut output),
+            Err(KeyDerivationError::UnsupportedVersion(2))
+        );
+
+        // during a migration both versions are accepted, and derive different keys
+        versions.accept(2);
+        versions
+            .derive_key::<_, HkdfSha256>(&new, recovered.key(), b"app", &mut output)
+            .unwrap();
+        assert_ne!(output, current);
+        versions.retire(KDF_CONTEXT_VERSION);
+        assert!(versions
+            .derive_key::<_, HkdfSha256>(&old, recovered.key(), b"app", &mut output)
+            .is_err());
+    }
+
+    #[cfg(all(feature = "sha3", feature = "blake3"))]
+    #[test]
+    fn derivations_differ() {
+        let mut rng = thread_rng();
+        let (channel, _) = BroadcastChannel::<Bls12_381>::init_participants(2, &mut rng).unwrap();
+        let (_, key) = channel.encrypt(&[1], &mut rng).unwrap();
+
+        let mut outputs = [[0u8; 64]; 3];
+        derive_key::<Bls12_381, HkdfSha256>(key.key(), b"", &mut outputs[0]).unwrap();
+        derive_key::<Bls12_381, Shake256Kdf>(key.key(), b"", &mut outputs[1]).unwrap();
+        derive_key::<Bls12_381, Blake3Kdf>(key.key(), b"", &mut outputs[2]).unwrap();
+        assert_ne!(outputs[0], outputs[1]);
+        assert_ne!(outputs[1], outputs[2]);
+    }
+}
@@ -0,0 +1,153 @@
+//! The scheme through the KEM traits of RustCrypto.
+//!
+//! A broadcast encryption is a key encapsulation whose public key is a set: [`EncapsulationKey`]
+//! holds the parameters of a channel and the set the headers are for, and implements
+//! [`Encapsulate`], while [`DecapsulationKey`] holds the key pair of a participant with the same
+//! parameters and set, and implements [`Decapsulate`]. The encapsulated key is the [`Header`],
+//! and the shared secret is 32 bytes of [`crate::kdf::derive_key`] with [`HkdfSha256`] and the
+//! info [`KEM_INFO`], so that protocols built on the traits get uniform bytes rather than an
+//! element of the target group.
+//!
+//! The `kem` crate draws randomness with rand_core 0.6, whose traits are not the ones of the rest
+//! of the crate: the ephemeral scalar is reduced from 64 bytes of the generator instead.
+//!
+//! Only available with the `kem` feature.
+
+use ::kem::{Decapsulate, Encapsulate};
+use ark_ec::PairingEngine;
+use ark_ff::PrimeField;
+use rand_core_06::CryptoRngCore;
+
+use crate::error::{DecryptError, EncryptError};
+use crate::kdf::{derive_key, HkdfSha256};
+use crate::session::EphemeralScalar;
+use crate::set::RecipientSet;
+use crate::{BroadcastChannel, Header, Recipient};
+
+/// Info of the derivation of the shared secret from the key of a header.
+pub const KEM_INFO: &[u8] = b"DGS-BE-KEM-V1";
+
+/// Secret shared by the encapsulation and the decapsulation.
+pub type SharedSecret = [u8; 32];
+
+fn shared_secret<E: PairingEngine>(key: &E::Fqk) -> SharedSecret {
+    let mut secret = [0u8; 32];
+    // 32 bytes are within the output of HKDF
+    derive_key::<E, HkdfSha256>(key, KEM_INFO, &mut secret).unwrap();
+    secret
+}
+
+/// Parameters of a channel and the set headers are encapsulated for.
+#[derive(Clone)]
+pub struct EncapsulationKey<'a, E: PairingEngine> {
+    channel: &'a BroadcastChannel<E>,
+    set: RecipientSet,
+}
+
+impl<'a, E: PairingEngine> EncapsulationKey<'a, E> {
+    /// Key encapsulating for `set` in `channel`.
+    pub fn new(channel: &'a BroadcastChannel<E>, set: RecipientSet) -> Self {
+        EncapsulationKey { channel, set }
+    }
+
+    /// Set the headers are encapsulated for.
+    pub fn set(&self) -> &RecipientSet {
+        &self.set
+    }
+}
+
+impl<E: PairingEngine> Encapsulate<Header<E>, SharedSecret> for EncapsulationKey<'_, E> {
+    type Error = EncryptError;
+
+    fn encapsulate(
+        &self,
+        rng: &mut impl CryptoRngCore,
+    ) -> Result<(Header<E>, SharedSecret), EncryptError> {
+        // twice the size of the scalar field, so that the reduction is close to uniform
+        let ephemeral = loop {
+            let mut wide = [0u8; 64];
+            rng.fill_bytes(&mut wide);
+            if let Some(ephemeral) =
+                EphemeralScalar::from_scalar(E::Fr::from_le_bytes_mod_order(&wide))
+            {
+                break ephemeral;
+            }
+        };
+        let (header, key) = self
+            .channel
+            .encrypt_with_randomness(self.set.as_slice(), ephemeral)?;
+        Ok((header, shared_secret::<E>(key.key())))
+    }
+}
+
+/// Key pair of a participant, with the parameters of its channel and the set headers are
+/// decapsulated for.
+#[derive(Clone)]
+pub struct DecapsulationKey<'a, E: PairingEngine> {
+    recipient: &'a Recipient<E>,
+    channel: &'a BroadcastChannel<E>,
+    set: RecipientSet,
+}
+
+impl<'a, E: PairingEngine> DecapsulationKey<'a, E> {
+    /// Key of `recipient` decapsulating the headers for `set` in `channel`.
+    pub fn new(
+        recipient: &'a Recipient<E>,
+        channel: &'a BroadcastChannel<E>,
+        set: RecipientSet,
+    ) -> Self {
+        DecapsulationKey {
+            recipient,
+            channel,
+            set,
+        }
+    }
+
+    /// Key encapsulating for the same set.
+    pub fn encapsulation_key(&self) -> EncapsulationKey<'a, E> {
+        EncapsulationKey::new(self.channel, self.set.clone())
+    }
+}
+
+impl<E: PairingEngine> Decapsulate<Header<E>, SharedSecret> for DecapsulationKey<'_, E> {
+    type Error = DecryptError;
+
+    fn decapsulate(&self, header: &Header<E>) -> Result<SharedSecret, DecryptError> {
+        let key = self
+            .recipient
+            .decrypt(self.set.as_slice(), self.channel, header)?;
+        Ok(shared_secret::<E>(key.key()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+    use rand_core_06::OsRng;
+
+    #[test]
+    fn kem_traits_round_trip() {
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(4, &mut thread_rng()).unwrap();
+        let set = RecipientSet::new(&[1, 3]);
+        let decapsulation = DecapsulationKey::new(&participants[2], &channel, set.clone());
+        let encapsulation = decapsulation.encapsulation_key();
+        assert_eq!(encapsulation.set(), &set);
+
+        let (header, secret) = encapsulation.encapsulate(&mut OsRng).unwrap();
+        assert_eq!(decapsulation.decapsulate(&header), Ok(secret));
+        let (other, other_secret) = encapsulation.encapsulate(&mut OsRng).unwrap();
+        assert!(other != header && other_secret != secret);
+
+        let outsider = DecapsulationKey::new(&participants[1], &channel, set);
+        assert_eq!(outsider.decapsulate(&header), Err(DecryptError::NotInSet));
+        assert_eq!(
+            EncapsulationKey::new(&channel, RecipientSet::new(&[5]))
+                .encapsulate(&mut OsRng)
+                .err(),
+            Some(EncryptError::IdentifierOutOfRange(5))
+        );
+    }
+}
@@ -0,0 +1,254 @@
+//! Self-contained key bundles for constrained recipients.
+//!
+//! A recipient decrypting from a full [`BroadcastChannel`] has to hold every point of the
+//! parameters, which for large channels means megabytes. The points it actually uses are the
+//! `P_{n+1-j+i}` of the other members `j` of the sets it is addressed in. A [`KeyBundle`] holds
+//! the key pair of the recipient and those points only, for an audience chosen when issuing it,
+//! in compressed form: about 48 bytes per member of the audience with BLS12-381, plus the keys.
+//!
+//! The [`BundleProfile`] of a deployment picks the tradeoff: the smaller the audience, the
+//! smaller the bundle, but headers whose set leaves the audience cannot be decrypted from it.
+//! A size limit makes issuance fail instead of exceeding the budget of the devices.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use ark_ec::{AffineCurve, PairingEngine};
+use ark_ff::Zero;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::encoding::{from_bytes, to_bytes};
+use crate::error::{DecryptError, IssuanceError};
+use crate::keystore::key_of_header;
+use crate::set::{first_repetition, RecipientSet};
+use crate::{BroadcastChannel, Header, Recipient, SharedKey};
+
+/// Choice of the audience and size limit of the bundles of a deployment. The default profile
+/// covers the whole channel, without limit.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BundleProfile {
+    audience: Option<RecipientSet>,
+    max_size: Option<usize>,
+}
+
+impl BundleProfile {
+    /// Copy of this profile covering only the sets included in `audience`.
+    pub fn with_audience(self, audience: &[usize]) -> Self {
+        BundleProfile {
+            audience: Some(RecipientSet::new(audience)),
+            ..self
+        }
+    }
+
+    /// Copy of this profile refusing bundles whose encoding exceeds `bytes`.
+    pub fn with_max_size(self, bytes: usize) -> Self {
+        BundleProfile {
+            max_size: Some(bytes),
+            ..self
+        }
+    }
+}
+
+/// Everything a recipient needs to decrypt headers for sets within its audience.
+#[derive(Clone, PartialEq, Eq)]
+pub struct KeyBundle<E: PairingEngine> {
+    identifier: usize,
+    public_key: E::G2Projective,
+    private_key: E::G1Projective,
+    /// other members of the audience, in increasing order
+    audience: Vec<usize>,
+    /// point added to the private key when the member at the same position is in the set
+    points: Vec<E::G1Projective>,
+}
+
+impl<E: PairingEngine> Drop for KeyBundle<E> {
+    fn drop(&mut self) {
+        self.private_key.zeroize();
+    }
+}
+
+impl<E: PairingEngine> ZeroizeOnDrop for KeyBundle<E> {}
+
+impl<E: PairingEngine> Recipient<E> {
+    /// Bundle of the key of this recipient with the points of `channel` that `profile` calls
+    /// for. Members of the audience whose keys have not been issued are refused.
+    pub fn key_bundle(
+        &self,
+        channel: &BroadcastChannel<E>,
+        profile: &BundleProfile,
+    ) -> Result<KeyBundle<E>, IssuanceError> {
+        let audience: Vec<usize> = match &profile.audience {
+            Some(audience) => audience.iter().collect(),
+            None => channel.issued().collect(),
+        };
+        if let Some(unknown) = audience
+            .iter()
+            .find(|identifier| channel.public_key(**identifier).is_none())
+        {
+            return Err(IssuanceError::UnknownRecipient(*unknown));
+        }
+
+        let n = channel.number_participants;
+        let audience: Vec<usize> = audience
+            .into_iter()
+            .filter(|identifier| *identifier != self.identifier)
+            .collect();
+        let points = audience
+            .iter()
+            .map(|identifier| {
+                channel.broadcaster_pk_g1[n + 1 - identifier + self.identifier].into_projective()
+            })
+            .collect();
+        let bundle = KeyBundle {
+            identifier: self.identifier,
+            public_key: self.key_pair.public_key,
+            private_key: self.key_pair.private_key,
+            audience,
+            points,
+        };
+
+        match profile.max_size {
+            Some(limit) if bundle.serialized_size() > limit => Err(IssuanceError::BundleTooLarge {
+                size: bundle.serialized_size(),
+                limit,
+            }),
+            _ => Ok(bundle),
+        }
+    }
+}
+
+impl<E: PairingEngine> KeyBundle<E> {
+    /// Identifier of the recipient.
+    pub fn identifier(&self) -> usize {
+        self.identifier
+    }
+
+    /// Decrypt a header encrypted for set `set_recipients`. Fails with
+    /// [`DecryptError::IdentifierOutOfRange`] if the set leaves the audience of the bundle.
+    pub fn decrypt(
+        &self,
+        set_recipients: &[usize],
+        header: &Header<E>,
+    ) -> Result<SharedKey<E>, DecryptError> {
+        if let Some(identifier) = first_repetition(set_recipients) {
+            return Err(DecryptError::DuplicateRecipient(identifier));
+        }
+        if !set_recipients.contains(&self.identifier) {
+            return Err(DecryptError::NotInSet);
+        }
+        if header.ctx_0.is_zero() || header.ctx_1.is_zero() {
+            return Err(DecryptError::IdentityPoint);
+        }
+
+        let mut aggregate = E::G1Projective::zero();
+        for index in set_recipients.iter() {
+            if *index == self.identifier {
+                continue;
+            }
+            let position = self
+                .audience
+                .binary_search(index)
+                .map_err(|_| DecryptError::IdentifierOutOfRange(*index))?;
+            aggregate += self.points[position];
+        }
+        Ok(SharedKey::new(key_of_header(
+            &self.private_key,
+            &self.public_key,
+            &aggregate,
+            header,
+        )))
+    }
+
+    /// Compressed encoding of the bundle.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+}
+
+impl<E: PairingEngine> CanonicalSerialize for KeyBundle<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.identifier.serialize(&mut writer)?;
+        self.public_key.serialize(&mut writer)?;
+        self.private_key.serialize(&mut writer)?;
+        self.audience.serialize(&mut writer)?;
+        self.points.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.identifier.serialized_size()
+            + self.public_key.serialized_size()
+            + self.private_key.serialized_size()
+            + self.audience.serialized_size()
+            + self.points.serialized_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for KeyBundle<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let identifier = usize::deserialize(&mut reader)?;
+        let public_key = E::G2Projective::deserialize(&mut reader)?;
+        let private_key = E::G1Projective::deserialize(&mut reader)?;
+        let audience = Vec::<usize>::deserialize(&mut reader)?;
+        let points = Vec::<E::G1Projective>::deserialize(&mut reader)?;
+        // lookups rely on the audience being increasing and matching the points
+        let increasing = audience.windows(2).all(|pair| pair[0] < pair[1]);
+        if !increasing || audience.len() != points.len() || audience.contains(&identifier) {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(KeyBundle {
+            identifier,
+            public_key,
+            private_key,
+            audience,
+            points,
+        })
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for KeyBundle<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn bundles_decrypt_within_audience() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(20, &mut rng).unwrap();
+        let profile = BundleProfile::default().with_audience(&[2, 5, 9, 11]);
+        let bundle = participants[4].key_bundle(&channel, &profile).unwrap();
+        let bytes = bundle.to_bytes();
+        assert_eq!(bytes.len(), 8 + 96 + 48 + 8 + 3 * 8 + 8 + 3 * 48);
+        let bundle = KeyBundle::<Bls12_381>::try_from(bytes.as_slice()).unwrap();
+
+        let (header, key) = channel.encrypt(&[9, 5, 2], &mut rng).unwrap();
+        assert_eq!(bundle.decrypt(&[9, 5, 2], &header), Ok(key.clone()));
+        assert_eq!(
+            bundle.decrypt(&[5, 7], &header),
+            Err(DecryptError::IdentifierOutOfRange(7))
+        );
+        assert_eq!(
+            bundle.decrypt(&[2, 9], &header),
+            Err(DecryptError::NotInSet)
+        );
+
+        let full = participants[4]
+            .key_bundle(&channel, &BundleProfile::default())
+            .unwrap();
+        assert_eq!(full.decrypt(&[9, 5, 2], &header), Ok(key.clone()));
+        assert!(matches!(
+            participants[4].key_bundle(&channel, &BundleProfile::default().with_max_size(1024)),
+            Err(IssuanceError::BundleTooLarge { limit: 1024, .. })
+        ));
+    }
+}
@@ -0,0 +1,570 @@
+//! Private keys held outside of the process.
+//!
+//! A [`Recipient`] and a [`BroadcasterSecret`] hold their secrets in plain fields, which suits
+//! neither an HSM nor an OS keychain. [`SecretKeyStore`] is the interface to such a store: it
+//! only has to pair the private key `d_i` of a participant with a point of G2, and multiply a
+//! point of G1 by the secret `gamma` of the broadcaster, so the secrets never need to leave it.
+//!
+//! [`decrypt`] recovers the key of a header with the private key in a store, and
+//! [`BroadcastChannel::issue_key_from_store`] issues keys with the secret in a store. Recipients
+//! are stores of their own key, and [`Recipient::decrypt`] goes through the same interface. Two
+//! stores are provided: [`MemoryKeyStore`], which holds the secrets in memory, and
+//! [`EncryptedFileKeyStore`], with the `chacha20poly1305` and `std` features, which keeps them in
+//! a file sealed with ChaCha20-Poly1305 and reads it on every operation.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::Field;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::encoding::{from_bytes, to_bytes};
+use crate::error::{KeyStoreError, SetupError, StoreError};
+use crate::events::Event;
+use crate::secret::BroadcasterSecret;
+use crate::{BroadcastChannel, Header, KeyPair, Recipient, SharedKey};
+
+#[cfg(all(feature = "chacha20poly1305", feature = "std"))]
+pub use self::file::EncryptedFileKeyStore;
+
+/// Store of private keys of participants, and possibly of the secret of the broadcaster.
+pub trait SecretKeyStore<E: PairingEngine> {
+    /// Error of the store
+    type Error;
+
+    /// `e(d_i + offset, point)`, with `d_i` the private key of participant `identifier`.
+    fn pair_private_key(
+        &self,
+        identifier: usize,
+        offset: &E::G1Projective,
+        point: &E::G2Projective,
+    ) -> Result<E::Fqk, Self::Error>;
+
+    /// `gamma point`, with `gamma` the secret of the broadcaster.
+    fn multiply_secret(&self, point: &E::G1Projective) -> Result<E::G1Projective, Self::Error>;
+
+    /// Key `e(ctx_0, Q_i) / e(d_i + A, ctx_1)` of `header` for participant `identifier` with
+    /// public key `Q_i`, given the sum `A` of the points of the other recipients. Stores holding
+    /// the key in memory compute both pairings with a single final exponentiation.
+    fn header_key(
+        &self,
+        identifier: usize,
+        public_key: &E::G2Projective,
+        aggregate: &E::G1Projective,
+        header: &Header<E>,
+    ) -> Result<E::Fqk, Self::Error> {
+        let second = self.pair_private_key(identifier, aggregate, &header.ctx_1)?;
+        // pairings of points of the groups are never zero
+        Ok(E::pairing(header.ctx_0, *public_key) * second.inverse().unwrap())
+    }
+}
+
+/// Key of `header` for the key pair `(private_key, public_key)`, as in
+/// [`SecretKeyStore::header_key`].
+pub(crate) fn key_of_header<E: PairingEngine>(
+    private_key: &E::G1Projective,
+    public_key: &E::G2Projective,
+    aggregate: &E::G1Projective,
+    header: &Header<E>,
+) -> E::Fqk {
+    // e(ctx_0, pk) / e(d + aggregate, ctx_1), with the Miller loops of both pairings
+    // multiplied together before the single final exponentiation
+    let pairs = [
+        (
+            header.ctx_0.into_affine().into(),
+            public_key.into_affine().into(),
+        ),
+        (
+            (-(*private_key + aggregate)).into_affine().into(),
+            header.ctx_1.into_affine().into(),
+        ),
+    ];
+    E::product_of_pairings(pairs.iter())
+}
+
+impl<E: PairingEngine> SecretKeyStore<E> for Recipient<E> {
+    type Error = StoreError;
+
+    fn pair_private_key(
+        &self,
+        identifier: usize,
+        offset: &E::G1Projective,
+        point: &E::G2Projective,
+    ) -> Result<E::Fqk, StoreError> {
+        if identifier != self.identifier {
+            return Err(StoreError::MissingKey(identifier));
+        }
+        Ok(E::pairing(self.key_pair.private_key + offset, *point))
+    }
+
+    fn multiply_secret(&self, _point: &E::G1Projective) -> Result<E::G1Projective, StoreError> {
+        Err(StoreError::MissingSecret)
+    }
+
+    fn header_key(
+        &self,
+        identifier: usize,
+        public_key: &E::G2Projective,
+        aggregate: &E::G1Projective,
+        header: &Header<E>,
+    ) -> Result<E::Fqk, StoreError> {
+        if identifier != self.identifier {
+            return Err(StoreError::MissingKey(identifier));
+        }
+        Ok(key_of_header(
+            &self.key_pair.private_key,
+            public_key,
+            aggregate,
+            header,
+        ))
+    }
+}
+
+/// Decrypt a header encrypted for set `set_recipients` with the private key of participant
+/// `identifier` in `store`. The set and the header are checked as by [`Recipient::decrypt`],
+/// before the store is used.
+pub fn decrypt<E, S>(
+    store: &S,
+    identifier: usize,
+    set_recipients: &[usize],
+    channel: &BroadcastChannel<E>,
+    header: &Header<E>,
+) -> Result<SharedKey<E>, KeyStoreError<S::Error>>
+where
+    E: PairingEngine,
+    S: SecretKeyStore<E>,
+{
+    if let Err(error) = channel.check_decryption(identifier, set_recipients, header) {
+        channel.emit(Event::DecryptFailed {
+            identifier,
+            reason: error,
+        });
+        return Err(KeyStoreError::Decrypt(error));
+    }
+
+    let aggregate = channel.decryption_aggregate(set_recipients, identifier);
+    let public_key = channel.broadcaster_pk_g2[identifier].into_projective();
+    store
+        .header_key(identifier, &public_key, &aggregate, header)
+        .map(SharedKey::new)
+        .map_err(KeyStoreError::Store)
+}
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Key of participant `identifier`, computed with the secret in `store`. Fails if the secret
+    /// of the store is not the one of the channel, or if no key is issued for `identifier`.
+    pub fn issue_key_from_store<S: SecretKeyStore<E>>(
+        &self,
+        store: &S,
+        identifier: usize,
+    ) -> Result<Recipient<E>, KeyStoreError<S::Error>> {
+        let generator = E::G1Projective::prime_subgroup_generator();
+        if store
+            .multiply_secret(&generator)
+            .map_err(KeyStoreError::Store)?
+            != self.point_v
+        {
+            return Err(KeyStoreError::Setup(SetupError::WrongSecret));
+        }
+        if self.public_key(identifier).is_none() {
+            return Err(KeyStoreError::Setup(SetupError::NotIssued(identifier)));
+        }
+
+        let private_key = store
+            .multiply_secret(&self.broadcaster_pk_g1[identifier].into_projective())
+            .map_err(KeyStoreError::Store)?;
+        self.emit(Event::KeyIssued { identifier });
+        Ok(Recipient {
+            identifier,
+            key_pair: KeyPair {
+                public_key: self.broadcaster_pk_g2[identifier].into_projective(),
+                private_key,
+            },
+            budget: None,
+        })
+    }
+}
+
+/// Secrets held in memory, zeroized when dropped.
+pub struct MemoryKeyStore<E: PairingEngine> {
+    secret: Option<BroadcasterSecret<E>>,
+    /// private keys, by identifier
+    keys: BTreeMap<usize, E::G1Projective>,
+}
+
+impl<E: PairingEngine> MemoryKeyStore<E> {
+    /// Empty store.
+    pub fn new() -> Self {
+        MemoryKeyStore {
+            secret: None,
+            keys: BTreeMap::new(),
+        }
+    }
+
+    /// Add the private key of `recipient`, replacing any key of the same identifier.
+    pub fn insert_recipient(&mut self, recipient: &Recipient<E>) {
+        if let Some(mut previous) = self
+            .keys
+            .insert(recipient.identifier, recipient.key_pair.private_key)
+        {
+            previous.zeroize();
+        }
+    }
+
+    /// Hold `secret` as the secret of the broadcaster, replacing any previous one.
+    pub fn set_broadcaster_secret(&mut self, secret: BroadcasterSecret<E>) {
+        self.secret = Some(secret);
+    }
+
+    /// Drop the private key of `identifier`. Returns whether the store held one.
+    pub fn remove(&mut self, identifier: usize) -> bool {
+        match self.keys.remove(&identifier) {
+            Some(mut key) => {
+                key.zeroize();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Identifiers of the private keys in the store, in increasing order.
+    pub fn identifiers(&self) -> impl Iterator<Item = usize> + '_ {
+        self.keys.keys().copied()
+    }
+
+    /// Encoding of the secrets of the store, in the clear.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+
+    fn private_key(&self, identifier: usize) -> Result<&E::G1Projective, StoreError> {
+        self.keys
+            .get(&identifier)
+            .ok_or(StoreError::MissingKey(identifier))
+    }
+}
+
+impl<E: PairingEngine> Default for MemoryKeyStore<E> {
+    fn default() -> Self {
+        MemoryKeyStore::new()
+    }
+}
+
+impl<E: PairingEngine> Drop for MemoryKeyStore<E> {
+    fn drop(&mut self) {
+        for key in self.keys.values_mut() {
+            key.zeroize();
+        }
+    }
+}
+
+impl<E: PairingEngine> ZeroizeOnDrop for MemoryKeyStore<E> {}
+
+impl<E: PairingEngine> SecretKeyStore<E> for MemoryKeyStore<E> {
+    type Error = StoreError;
+
+    fn pair_private_key(
+        &self,
+        identifier: usize,
+        offset: &E::G1Projective,
+        point: &E::G2Projective,
+    ) -> Result<E::Fqk, StoreError> {
+        Ok(E::pairing(*self.private_key(identifier)? + offset, *point))
+    }
+
+    fn multiply_secret(&self, point: &E::G1Projective) -> Result<E::G1Projective, StoreError> {
+        let secret = self.secret.as_ref().ok_or(StoreError::MissingSecret)?;
+        let mut product = *point;
+        product *= secret.gamma;
+        Ok(product)
+    }
+
+    fn header_key(
+        &self,
+        identifier: usize,
+        public_key: &E::G2Projective,
+        aggregate: &E::G1Projective,
+        header: &Header<E>,
+    ) -> Result<E::Fqk, StoreError> {
+        Ok(key_of_header(
+            self.private_key(identifier)?,
+            public_key,
+            aggregate,
+            header,
+        ))
+    }
+}
+
+impl<E: PairingEngine> CanonicalSerialize for MemoryKeyStore<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        match &self.secret {
+            Some(secret) => {
+                true.serialize(&mut writer)?;
+                secret.serialize(&mut writer)?;
+            }
+            None => false.serialize(&mut writer)?,
+        }
+        self.keys.len().serialize(&mut writer)?;
+        for (identifier, key) in self.keys.iter() {
+            identifier.serialize(&mut writer)?;
+            key.serialize(&mut writer)?;
+        }
+        Ok(())
+    }
+
+    fn serialized_size(&self) -> usize {
+        let secret = self
+            .secret
+            .as_ref()
+            .map_or(0, |secret| secret.serialized_size());
+        let keys: usize = self
+            .keys
+            .iter()
+            .map(|(identifier, key)| identifier.serialized_size() + key.serialized_size())
+            .sum();
+        true.serialized_size() + secret + self.keys.len().serialized_size() + keys
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for MemoryKeyStore<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let mut store = MemoryKeyStore::new();
+        if bool::deserialize(&mut reader)? {
+            store.secret = Some(BroadcasterSecret::deserialize(&mut reader)?);
+        }
+        let length = usize::deserialize(&mut reader)?;
+        for _ in 0..length {
+            let identifier = usize::deserialize(&mut reader)?;
+            let key = E::G1Projective::deserialize(&mut reader)?;
+            if store.keys.insert(identifier, key).is_some() {
+                return Err(SerializationError::InvalidData);
+            }
+        }
+        Ok(store)
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for MemoryKeyStore<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+#[cfg(all(feature = "chacha20poly1305", feature = "std"))]
+mod file {
+    use core::marker::PhantomData;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use ark_ec::PairingEngine;
+    use chacha20poly1305::aead::{Aead, NewAead, Payload};
+    use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+    use rand_core::{CryptoRng, RngCore};
+    use zeroize::Zeroize;
+
+    use super::{MemoryKeyStore, SecretKeyStore};
+    use crate::encoding::from_bytes;
+    use crate::error::StoreError;
+    use crate::kdf::{HkdfSha256, KeyDerivation};
+    use crate::Header;
+
+    /// Info of the derivation of the file keys.
+    const FILE_KEY_INFO: &[u8] = b"DGS-BE-KEYSTORE-FILE-CHACHA20POLY1305";
+
+    /// Associated data of the sealed files.
+    const FILE_AAD: &[u8] = b"DGS-BE-KEYSTORE-V1";
+
+    /// Size of the nonce at the start of the file.
+    const NONCE_SIZE: usize = 12;
+
+    /// Secrets held in a file sealed under a 32-byte key.
+    ///
+    /// The file holds a random nonce followed by the encoding of a [`MemoryKeyStore`], sealed
+    /// with ChaCha20-Poly1305 under a key derived from the key of the store with [`HkdfSha256`].
+    /// Every operation reads and opens the file, and zeroizes the secrets once done.
+    pub struct EncryptedFileKeyStore<E: PairingEngine> {
+        path: PathBuf,
+        key: [u8; 32],
+        _engine: PhantomData<E>,
+    }
+
+    impl<E: PairingEngine> EncryptedFileKeyStore<E> {
+        /// Store in the file at `path`, sealed under `key`. The file is only read when used.
+        pub fn new<P: AsRef<Path>>(path: P, key: [u8; 32]) -> Self {
+            EncryptedFileKeyStore {
+                path: path.as_ref().to_path_buf(),
+                key,
+                _engine: PhantomData,
+            }
+        }
+
+        fn cipher(&self) -> ChaCha20Poly1305 {
+            let mut bytes = [0u8; 32];
+            // 32 bytes are within the output of HKDF
+            HkdfSha256::derive(&self.key, FILE_KEY_INFO, &mut bytes).unwrap();
+            let cipher = ChaCha20Poly1305::new(Key::from_slice(&bytes));
+            bytes.zeroize();
+            cipher
+        }
+
+        /// Seal the secrets of `store` into the file, replacing its content.
+        pub fn save<R>(&self, store: &MemoryKeyStore<E>, rng: &mut R) -> Result<(), StoreError>
+        where
+            R: RngCore + CryptoRng,
+        {
+            let mut nonce = [0u8; NONCE_SIZE];
+            rng.fill_bytes(&mut nonce);
+            let mut plaintext = store.to_bytes();
+            let ciphertext = self
+                .cipher()
+                .encrypt(
+                    Nonce::from_slice(&nonce),
+                    Payload {
+                        msg: &plaintext,
+                        aad: FILE_AAD,
+                    },
+                )
+                .unwrap();
+            plaintext.zeroize();
+
+            let mut contents = nonce.to_vec();
+            contents.extend_from_slice(&ciphertext);
+            fs::write(&self.path, contents).map_err(|error| StoreError::Io(error.kind()))
+        }
+
+        /// Secrets sealed in the file. Fails if the file cannot be read, if it was sealed under
+        /// another key or modified, or if its content does not decode.
+        pub fn load(&self) -> Result<MemoryKeyStore<E>, StoreError> {
+            let contents = fs::read(&self.path).map_err(|error| StoreError::Io(error.kind()))?;
+            if contents.len() < NONCE_SIZE {
+                return Err(StoreError::Authentication);
+            }
+            let (nonce, ciphertext) = contents.split_at(NONCE_SIZE);
+            let mut plaintext = self
+                .cipher()
+                .decrypt(
+                    Nonce::from_slice(nonce),
+                    Payload {
+                        msg: ciphertext,
+                        aad: FILE_AAD,
+                    },
+                )
+                .map_err(|_| StoreError::Authentication)?;
+            let store = from_bytes(&plaintext);
+            plaintext.zeroize();
+            store.map_err(|_| StoreError::Corrupted)
+        }
+    }
+
+    impl<E: PairingEngine> Drop for EncryptedFileKeyStore<E> {
+        fn drop(&mut self) {
+            self.key.zeroize();
+        }
+    }
+
+    impl<E: PairingEngine> SecretKeyStore<E> for EncryptedFileKeyStore<E> {
+        type Error = StoreError;
+
+        fn pair_private_key(
+            &self,
+            identifier: usize,
+            offset: &E::G1Projective,
+            point: &E::G2Projective,
+        ) -> Result<E::Fqk, StoreError> {
+            self.load()?.pair_private_key(identifier, offset, point)
+        }
+
+        fn multiply_secret(&self, point: &E::G1Projective) -> Result<E::G1Projective, StoreError> {
+            self.load()?.multiply_secret(point)
+        }
+
+        fn header_key(
+            &self,
+            identifier: usize,
+            public_key: &E::G2Projective,
+            aggregate: &E::G1Projective,
+            header: &Header<E>,
+        ) -> Result<E::Fqk, StoreError> {
+            self.load()?
+                .header_key(identifier, public_key, aggregate, header)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn memory_store_decrypts_and_issues() {
+        let mut rng = thread_rng();
+        let (channel, participants, secret) =
+            BroadcastChannel::<Bls12_381>::init_with_secret(4, &mut rng).unwrap();
+        let set = [1, 2, 4];
+        let (header, key) = channel.encrypt(&set, &mut rng).unwrap();
+
+        let mut store = MemoryKeyStore::new();
+        store.insert_recipient(&participants[1]);
+        store.insert_recipient(&participants[3]);
+        assert_eq!(decrypt(&store, 2, &set, &channel, &header), Ok(key.clone()));
+        assert_eq!(decrypt(&store, 4, &set, &channel, &header), Ok(key.clone()));
+        assert_eq!(
+            decrypt(&store, 1, &set, &channel, &header),
+            Err(KeyStoreError::Store(StoreError::MissingKey(1)))
+        );
+        assert_eq!(
+            decrypt(&participants[0], 1, &set, &channel, &header),
+            Ok(key.clone())
+        );
+        assert!(store.remove(4));
+        assert_eq!(store.identifiers().collect::<Vec<usize>>(), vec![2]);
+
+        assert!(matches!(
+            channel.issue_key_from_store(&store, 3),
+            Err(KeyStoreError::Store(StoreError::MissingSecret))
+        ));
+        store.set_broadcaster_secret(secret);
+        let issued = channel.issue_key_from_store(&store, 3).unwrap();
+        assert!(issued.public_key() == participants[2].public_key());
+        let (header, key) = channel.encrypt(&[3], &mut rng).unwrap();
+        assert_eq!(issued.decrypt(&[3], &channel, &header), Ok(key.clone()));
+
+        let decoded = MemoryKeyStore::<Bls12_381>::try_from(store.to_bytes().as_slice()).unwrap();
+        assert_eq!(decoded.identifiers().collect::<Vec<usize>>(), vec![2]);
+        assert!(channel.issue_key_from_store(&decoded, 1).is_ok());
+    }
+
+    #[cfg(all(feature = "chacha20poly1305", feature = "std"))]
+    #[test]
+    fn file_store_round_trip() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(3, &mut rng).unwrap();
+        let path = std::env::temp_dir().join(format!("dgs-be-keystore-{}", std::process::id()));
+        let mut memory = MemoryKeyStore::new();
+        memory.insert_recipient(&participants[2]);
+
+        let store = EncryptedFileKeyStore::new(&path, [7u8; 32]);
+        store.save(&memory, &mut rng).unwrap();
+        let (header, key) = channel.encrypt(&[1, 3], &mut rng).unwrap();
+        assert_eq!(
+            decrypt(&store, 3, &[1, 3], &channel, &header),
+            Ok(key.clone())
+        );
+
+        let other = EncryptedFileKeyStore::<Bls12_381>::new(&path, [8u8; 32]);
+        assert_eq!(
+            decrypt(&other, 3, &[1, 3], &channel, &header),
+            Err(KeyStoreError::Store(StoreError::Authentication))
+        );
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(store.load(), Err(StoreError::Io(_))));
+    }
+}
@@ -0,0 +1,102 @@
+//! Positions of the parts of the compressed encoding of a channel.
+//!
+//! Compressed points all have the same size, so the position of every point of an encoding
+//! written by [`crate::BroadcastChannel::to_bytes`] follows from the number of participants it
+//! starts with, and so does the position of the state written after them. The parameter stores
+//! reading encodings in place use this to fetch single points.
+
+use core::convert::TryFrom;
+use core::ops::Range;
+
+use ark_ec::PairingEngine;
+use ark_ff::Zero;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+
+/// Size of the encoding of a length.
+pub(crate) const LENGTH_SIZE: usize = 8;
+
+/// Offset of the first point of the G1 vector, after the number of participants, the number of
+/// issued keys and the length of the vector.
+pub(crate) const G1_START: usize = 3 * LENGTH_SIZE;
+
+/// Offsets in the encoding of a channel of some number of participants.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Layout {
+    pub(crate) number_participants: usize,
+    /// size of a compressed point of G1
+    pub(crate) g1_size: usize,
+    /// size of a compressed point of G2
+    pub(crate) g2_size: usize,
+    /// offset of the length of the G2 vector
+    pub(crate) g2_length_start: usize,
+    /// offset of the point `V`
+    pub(crate) point_v_start: usize,
+    /// offset of the state of the channel, after the point `V`
+    pub(crate) state_start: usize,
+}
+
+impl Layout {
+    /// Layout of a channel of `number_participants` on `E`, or `None` if the offsets overflow.
+    pub(crate) fn new<E: PairingEngine>(number_participants: usize) -> Option<Self> {
+        let g1_size = E::G1Affine::zero().serialized_size();
+        let g2_size = E::G2Affine::zero().serialized_size();
+        let g1_length = number_participants.checked_mul(2)?.checked_add(1)?;
+        let g2_length = number_participants.checked_add(1)?;
+        let g2_length_start = G1_START.checked_add(g1_length.checked_mul(g1_size)?)?;
+        let g2_start = g2_length_start.checked_add(LENGTH_SIZE)?;
+        let point_v_start = g2_start.checked_add(g2_length.checked_mul(g2_size)?)?;
+        let state_start = point_v_start.checked_add(g1_size)?;
+        Some(Layout {
+            number_participants,
+            g1_size,
+            g2_size,
+            g2_length_start,
+            point_v_start,
+            state_start,
+        })
+    }
+
+    /// Number of points of the G1 vector.
+    pub(crate) fn g1_length(&self) -> usize {
+        2 * self.number_participants + 1
+    }
+
+    /// Number of points of the G2 vector.
+    pub(crate) fn g2_length(&self) -> usize {
+        self.number_participants + 1
+    }
+
+    /// Bytes of the points of the G1 vector at positions `range`, or `None` if the range is not
+    /// in the vector.
+    pub(crate) fn g1_bytes(&self, range: &Range<usize>) -> Option<Range<usize>> {
+        points_bytes(G1_START, self.g1_length(), self.g1_size, range)
+    }
+
+    /// Bytes of the points of the G2 vector at positions `range`, or `None` if the range is not
+    /// in the vector.
+    pub(crate) fn g2_bytes(&self, range: &Range<usize>) -> Option<Range<usize>> {
+        let start = self.g2_length_start + LENGTH_SIZE;
+        points_bytes(start, self.g2_length(), self.g2_size, range)
+    }
+}
+
+fn points_bytes(
+    start: usize,
+    length: usize,
+    size: usize,
+    range: &Range<usize>,
+) -> Option<Range<usize>> {
+    if range.start > range.end || range.end > length {
+        return None;
+    }
+    Some(start + range.start * size..start + range.end * size)
+}
+
+/// Encoded length at `offset` of `bytes`.
+pub(crate) fn length_at(bytes: &[u8], offset: usize) -> Result<usize, SerializationError> {
+    let slice = bytes
+        .get(offset..offset + LENGTH_SIZE)
+        .ok_or(SerializationError::InvalidData)?;
+    let length = u64::deserialize(slice)?;
+    usize::try_from(length).map_err(|_| SerializationError::InvalidData)
+}
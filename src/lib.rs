@@ -1,13 +1,193 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![warn(rust_2018_idioms, missing_docs)]
 #![allow(non_snake_case)]
 //! Implementation of Broadcast encryption scheme present in paper by Dubois, Guillevic, Sengelin
 //! Le Breton, "Improved Broadcast Encryption Scheme with Constant-Size Ciphertext", available
 //! here https://eprint.iacr.org/2012/370.pdf
+//!
+//! Without the default `std` feature the crate is `no_std`, and only needs an allocator and
+//! 64-bit atomics, so that set-top boxes and other embedded recipients can decrypt. The modules
+//! that need files, threads, locks or hash maps are then left out, as are the features that
+//! depend on them.
+
+extern crate alloc;
+
+use alloc::collections::BTreeSet;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::AtomicU64;
+
+use ark_ec::msm::FixedBaseMSM;
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{One, PrimeField, UniformRand, Zero};
+use ark_serialize::CanonicalSerialize;
+
+use rand_core::{CryptoRng, RngCore, SeedableRng};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+#[cfg(feature = "std")]
+use cache::AggregateCache;
+use error::{DecryptError, EncryptError, SetupError};
+use events::{Event, EventSink};
+use keystore::SecretKeyStore;
+use policy::Policy;
+use secret::{BroadcasterSecret, SecretSetup};
+use session::EncryptionTables;
+#[cfg(feature = "std")]
+use view::Fingerprint;
+
+pub use encoding::{decode_points, Encoding};
+/// The RNG traits bounding the API, re-exported so callers can name them without depending on a
+/// particular version of `rand`.
+pub use rand_core;
+
+// scaffolding of the Swift and Kotlin bindings, see `mobile`
+#[cfg(feature = "mobile")]
+uniffi::setup_scaffolding!();
+
+// Instrumentation with the `tracing` crate, which compiles to nothing without the feature of the
+// same name. Setup, encryption and decryption are spans at the info level, with their phases as
+// spans at the debug level, so subscribers can time every phase, and the events reported to the
+// sink of a channel are mirrored as debug events. Paths start with `::` as `tracing` is also the
+// name of a module of the crate.
+
+/// Enter a span for the rest of the enclosing block.
+macro_rules! enter_span {
+    ($level:ident, $name:literal $(, $($fields:tt)*)?) => {
+        #[cfg(feature = "tracing")]
+        let _span = ::tracing::span!(::tracing::Level::$level, $name $(, $($fields)*)?).entered();
+    };
+}
 
-use ark_ec::{PairingEngine, ProjectiveCurve};
-use ark_ff::UniformRand;
+/// Evaluate `$body` within a span at the debug level.
+macro_rules! in_span {
+    ($name:literal, $body:expr) => {{
+        enter_span!(DEBUG, $name);
+        $body
+    }};
+}
 
-use rand::{CryptoRng, Rng};
+/// Record an event.
+macro_rules! trace_event {
+    ($level:ident, $($arguments:tt)*) => {
+        #[cfg(feature = "tracing")]
+        ::tracing::event!(::tracing::Level::$level, $($arguments)*);
+    };
+}
+
+pub mod anonymous;
+mod assurance;
+#[cfg(feature = "async")]
+pub mod asynchronous;
+pub mod attributes;
+#[cfg(feature = "ct-audit")]
+pub mod audit;
+pub mod authenticated;
+pub mod batch;
+pub mod bitset;
+pub mod blinding;
+pub mod budget;
+#[cfg(feature = "std")]
+pub mod bulletin;
+pub mod bundle;
+#[cfg(feature = "std")]
+pub mod cache;
+pub mod cca;
+pub mod ceremony;
+#[cfg(all(feature = "chacha20poly1305", feature = "std"))]
+pub mod chunked;
+pub mod compact;
+pub mod cost;
+pub mod crs;
+pub mod curves;
+pub mod delegation;
+#[cfg(feature = "std")]
+pub mod devices;
+#[cfg(feature = "std")]
+pub mod directory;
+mod encoding;
+pub mod encryptionkey;
+#[cfg(feature = "chacha20poly1305")]
+pub mod envelope;
+pub mod epoch;
+pub mod error;
+pub mod escrow;
+pub mod events;
+pub mod federation;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod general;
+#[cfg(feature = "chacha20poly1305")]
+pub mod group_session;
+pub mod groups;
+pub mod handle;
+pub mod hash;
+pub mod heapless;
+pub mod ibbe;
+pub mod kat;
+pub mod kdf;
+#[cfg(feature = "kem")]
+pub mod kem;
+pub mod keybundle;
+pub mod keystore;
+#[cfg(any(feature = "mmap", feature = "object-store"))]
+mod layout;
+pub mod manager;
+#[cfg(feature = "mmap")]
+pub mod mapped;
+pub mod membership;
+#[cfg(feature = "chacha20poly1305")]
+pub mod message;
+#[cfg(feature = "mobile")]
+pub mod mobile;
+pub mod multiheader;
+#[cfg(feature = "object-store")]
+pub mod object;
+pub mod padding;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+pub mod policy;
+pub mod prepared;
+pub mod proof;
+pub mod provisioning;
+pub mod quorum;
+pub mod reencryption;
+pub mod refresh;
+#[cfg(feature = "std")]
+pub mod registry;
+pub mod revocation;
+pub mod scheme;
+pub mod secret;
+pub mod secretbox;
+#[cfg(feature = "serde")]
+mod serialization;
+pub mod session;
+pub mod set;
+pub mod signature;
+#[cfg(feature = "testing")]
+pub mod sim;
+pub mod split;
+pub mod store;
+pub mod stream;
+pub mod subset_difference;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "testvectors")]
+pub mod testvectors;
+pub mod threshold;
+pub mod tracing;
+pub mod transcript;
+pub mod twoparty;
+pub mod validate;
+pub mod versioned;
+pub mod view;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod wire;
+#[cfg(feature = "key-wrap")]
+pub mod wrap;
 
 /// Structure of the recipients
 #[derive(Clone)]
@@ -16,38 +196,122 @@ pub struct Recipient<E: PairingEngine> {
     identifier: usize,
     /// key pair
     key_pair: KeyPair<E>,
+    /// remaining decryptions, shared between clones
+    budget: Option<Arc<AtomicU64>>,
 }
 
 impl<E: PairingEngine> Recipient<E> {
-    /// Decrypt a ciphertext encrypted for set `set_recipients`
+    /// Decrypt a header encrypted for set `set_recipients`. The set and the header are checked
+    /// before any pairing is computed, and the decryption counts against the usage budget of the
+    /// recipient, if it has one. Subgroup membership is not checked here: headers decoded
+    /// with the checked deserialization already are in the subgroup, others should go through
+    /// [`Header::check`].
     pub fn decrypt(
         &self,
         set_recipients: &[usize],
         channel: &BroadcastChannel<E>,
-        ctx_0: &E::G1Projective,
-        ctx_1: &E::G2Projective,
-    ) -> E::Fqk {
-        let mut K = E::pairing(ctx_0.clone(), self.key_pair.public_key);
+        header: &Header<E>,
+    ) -> Result<SharedKey<E>, DecryptError> {
+        self.decrypt_with(set_recipients, channel, header, || {
+            channel.decryption_aggregate(set_recipients, self.identifier)
+        })
+    }
 
-        let mut g_1point_second_pairing = self.key_pair.private_key;
+    /// Same as [`Recipient::decrypt`], with the sum of the points of the other recipients given
+    /// by `aggregate`, which is only called once the set and the header have been checked.
+    pub(crate) fn decrypt_with<F>(
+        &self,
+        set_recipients: &[usize],
+        channel: &BroadcastChannel<E>,
+        header: &Header<E>,
+        aggregate: F,
+    ) -> Result<SharedKey<E>, DecryptError>
+    where
+        F: FnOnce() -> E::G1Projective,
+    {
+        self.decrypt_with_key(set_recipients, channel, header, aggregate, |aggregate| {
+            // a recipient is the store of its own key, so this cannot fail
+            self.header_key(
+                self.identifier,
+                &self.key_pair.public_key,
+                aggregate,
+                header,
+            )
+            .unwrap()
+        })
+    }
 
-        for index in set_recipients.iter() {
-            if *index == self.identifier {
-                continue;
-            }
+    /// Same as [`Recipient::decrypt_with`], with the key of the header computed by `key` from
+    /// the aggregate.
+    pub(crate) fn decrypt_with_key<F, G>(
+        &self,
+        set_recipients: &[usize],
+        channel: &BroadcastChannel<E>,
+        header: &Header<E>,
+        aggregate: F,
+        key: G,
+    ) -> Result<SharedKey<E>, DecryptError>
+    where
+        F: FnOnce() -> E::G1Projective,
+        G: FnOnce(&E::G1Projective) -> E::Fqk,
+    {
+        enter_span!(
+            INFO,
+            "decrypt",
+            identifier = self.identifier,
+            set_size = set_recipients.len()
+        );
+        if let Err(error) = self.check_decryption(set_recipients, channel, header) {
+            channel.emit(Event::DecryptFailed {
+                identifier: self.identifier,
+                reason: error,
+            });
+            return Err(error);
+        }
 
-            g_1point_second_pairing += channel.broadcaster_pk_g1
-                [channel.number_participants + 1 - index + self.identifier];
+        let aggregate = in_span!("decrypt.aggregate", aggregate());
+        let K = in_span!("decrypt.pairing", key(&aggregate));
+
+        if let Err(error) = self.confirm_decryption(set_recipients, channel, header, &K) {
+            channel.emit(Event::DecryptFailed {
+                identifier: self.identifier,
+                reason: error,
+            });
+            return Err(error);
         }
+        Ok(SharedKey::new(K))
+    }
+
+    fn check_decryption(
+        &self,
+        set_recipients: &[usize],
+        channel: &BroadcastChannel<E>,
+        header: &Header<E>,
+    ) -> Result<(), DecryptError> {
+        channel.check_decryption(self.identifier, set_recipients, header)?;
+        // last, so that only decryptions that go ahead are counted
+        self.consume_use()
+    }
 
-        let denominator_pairing = E::pairing(g_1point_second_pairing, ctx_1.clone());
-        K /= denominator_pairing;
+    /// Identifier of the recipient in its channel
+    pub fn identifier(&self) -> usize {
+        self.identifier
+    }
 
-        K
+    /// Public key of the recipient
+    pub fn public_key(&self) -> PublicKey<E> {
+        PublicKey {
+            point: self.key_pair.public_key,
+        }
+    }
+
+    /// Key pair of the recipient
+    pub fn key_pair(&self) -> &KeyPair<E> {
+        &self.key_pair
     }
 }
 
-/// Key pair of recipients.
+/// Key pair of recipients. The private key is zeroized when dropped, in every clone.
 #[derive(Clone)]
 pub struct KeyPair<E: PairingEngine> {
     /// public key
@@ -56,109 +320,438 @@ pub struct KeyPair<E: PairingEngine> {
     private_key: E::G1Projective,
 }
 
-/// Broadcast channel. This is initiated by the trusted party, and includes all recipients
+impl<E: PairingEngine> Drop for KeyPair<E> {
+    fn drop(&mut self) {
+        self.private_key.zeroize();
+    }
+}
+
+impl<E: PairingEngine> ZeroizeOnDrop for KeyPair<E> {}
+
+/// Public key of a recipient, as published by the trusted party.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct PublicKey<E: PairingEngine> {
+    /// point in G2
+    point: E::G2Projective,
+}
+
+/// Header of an encryption. It is only built by [`BroadcastChannel::encrypt`] or decoded from
+/// bytes, so its two components always belong to the same message.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Header<E: PairingEngine> {
+    /// component in G1
+    ctx_0: E::G1Projective,
+    /// component in G2
+    ctx_1: E::G2Projective,
+}
+
+impl<E: PairingEngine> Header<E> {
+    /// Component of the header in G1
+    pub fn ctx_0(&self) -> &E::G1Projective {
+        &self.ctx_0
+    }
+
+    /// Component of the header in G2
+    pub fn ctx_1(&self) -> &E::G2Projective {
+        &self.ctx_1
+    }
+
+    /// Check that no component of the header is the identity and that both lie in the prime
+    /// order subgroup. Meant for headers decoded without the checked deserialization.
+    pub fn check(&self) -> Result<(), DecryptError> {
+        if self.ctx_0.is_zero() || self.ctx_1.is_zero() {
+            return Err(DecryptError::IdentityPoint);
+        }
+        if !validate::in_subgroup(&self.ctx_0) || !validate::in_subgroup(&self.ctx_1) {
+            return Err(DecryptError::NotInSubgroup);
+        }
+
+        Ok(())
+    }
+}
+
+/// Key of a header, as returned by [`BroadcastChannel::encrypt`] and [`Recipient::decrypt`]. It
+/// is an element of the target group, which goes through a key derivation before it keys a
+/// cipher, and is zeroized when dropped.
+#[derive(Clone)]
+pub struct SharedKey<E: PairingEngine> {
+    key: E::Fqk,
+}
+
+impl<E: PairingEngine> Drop for SharedKey<E> {
+    fn drop(&mut self) {
+        self.key.zeroize();
+    }
+}
+
+impl<E: PairingEngine> ZeroizeOnDrop for SharedKey<E> {}
+
+impl<E: PairingEngine> SharedKey<E> {
+    /// Wrap `key`, the key of a header.
+    pub fn new(key: E::Fqk) -> Self {
+        SharedKey { key }
+    }
+
+    /// Element of the target group
+    pub fn key(&self) -> &E::Fqk {
+        &self.key
+    }
+
+    /// Element of the target group, taking the wrapper apart.
+    pub fn into_key(self) -> E::Fqk {
+        self.key
+    }
+}
+
+/// Broadcast channel. This is initiated by the trusted party, and includes all recipients.
+/// Clones share the vectors of points, which are only copied when a clone changes them.
 #[derive(Clone)]
 pub struct BroadcastChannel<E: PairingEngine> {
     number_participants: usize,
-    broadcaster_pk_g1: Vec<E::G1Projective>,
-    broadcaster_pk_g2: Vec<E::G2Projective>,
+    /// powers of alpha times the generator of G1, normalized to affine coordinates, with the
+    /// identity at position n + 1
+    broadcaster_pk_g1: Arc<Vec<E::G1Affine>>,
+    /// powers of alpha times the generator of G2, normalized to affine coordinates
+    broadcaster_pk_g2: Arc<Vec<E::G2Affine>>,
+    point_v: E::G1Projective,
+    /// number of slots, starting from 1, for which keys have been issued
+    issued: usize,
+    /// receiver of the audit events, shared between clones
+    event_sink: Option<Arc<dyn EventSink>>,
+    /// limits checked by the encryption
+    policy: Policy,
+    /// identifiers whose keys have been retired, refused by the encryption
+    retired: BTreeSet<usize>,
+    /// number of the current epoch, see [`epoch`]
+    epoch: u64,
+    /// sum of the points of the issued participants in the encryption, retired ones included
+    issued_aggregate: E::G1Projective,
+    /// window tables of the points of G2 multiplied in every encryption, shared between clones
+    encryption_tables: Arc<EncryptionTables<E>>,
+    /// cache of aggregated points, with the fingerprint of the channel
+    #[cfg(feature = "std")]
+    aggregate_cache: Option<(Arc<AggregateCache<E>>, Fingerprint)>,
 }
 
 impl<E: PairingEngine> BroadcastChannel<E> {
-    /// Init broadcast channel. This needs to be performed by a trusted entity
-    pub fn init_participants<R>(n: usize, rng: &mut R) -> (Self, Vec<Recipient<E>>)
+    /// Init broadcast channel. This needs to be performed by a trusted entity. Fails if `n` is
+    /// zero.
+    pub fn init_participants<R>(
+        n: usize,
+        rng: &mut R,
+    ) -> Result<(Self, Vec<Recipient<E>>), SetupError>
     where
-        R: Rng + CryptoRng,
+        R: RngCore + CryptoRng,
     {
-        let generator_p = E::G1Projective::prime_subgroup_generator();
-        let generator_q = E::G2Projective::prime_subgroup_generator();
-
-        let alpha = E::Fr::rand(rng);
+        let (channel, participants, _) = Self::init_with_secret(n, rng)?;
+        Ok((channel, participants))
+    }
 
-        // vectors containing the generated points
-        let mut p_points_vec: Vec<E::G1Projective> = Vec::new();
-        p_points_vec.push(generator_p);
-        let mut q_points_vec: Vec<E::G2Projective> = Vec::new();
-        q_points_vec.push(generator_q);
+    /// Same as [`BroadcastChannel::init_participants`], also returning the secret of the trusted
+    /// entity, with which [`BroadcastChannel::add_participant`] grows the channel later on.
+    pub fn init_with_secret<R>(n: usize, rng: &mut R) -> Result<SecretSetup<E>, SetupError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if n == 0 {
+            return Err(SetupError::NoParticipants);
+        }
 
-        // counter to keep the state of each multiplication by \alpha
-        let mut counter_p = generator_p;
-        let mut counter_q = generator_q;
+        let alpha = E::Fr::rand(rng);
+        let gamma = E::Fr::rand(rng);
+        Ok(Self::setup(n, alpha, gamma))
+    }
 
-        for _ in 0..2 * n {
-            counter_p *= alpha;
-            p_points_vec.push(counter_p);
-        }
+    /// Same as [`BroadcastChannel::init_with_secret`], with randomness drawn from an `R` seeded
+    /// with `seed`, such as a ChaCha generator seeded by a hardware RNG. The same seed always
+    /// gives the same channel and keys, so it must be kept as secret as the broadcaster secret.
+    pub fn init_seeded<R>(n: usize, seed: R::Seed) -> Result<SecretSetup<E>, SetupError>
+    where
+        R: SeedableRng + RngCore + CryptoRng,
+    {
+        Self::init_with_secret(n, &mut R::from_seed(seed))
+    }
 
-        // now we remove values at position n + 1
-        // p_points_vec.remove(n+1);
+    /// Channel of `n` participants with secrets `alpha` and `gamma`, which are zeroized once
+    /// moved to the returned secret. `n` must not be zero.
+    pub(crate) fn setup(n: usize, mut alpha: E::Fr, mut gamma: E::Fr) -> SecretSetup<E> {
+        enter_span!(INFO, "setup", participants = n);
+        let generator_p = E::G1Projective::prime_subgroup_generator();
+        let generator_q = E::G2Projective::prime_subgroup_generator();
 
-        for _ in 0..n {
-            counter_q *= alpha;
-            q_points_vec.push(counter_q);
+        // powers of alpha, computed in the scalar field so that each point is a single
+        // multiplication of a generator, which the window tables below make cheap
+        let mut powers = Vec::with_capacity(2 * n + 1);
+        let mut power = E::Fr::one();
+        for _ in 0..=2 * n {
+            powers.push(power);
+            power *= alpha;
         }
+        power.zeroize();
+        #[cfg(feature = "parallel")]
+        let key_powers = powers[1..=n].par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let key_powers = powers[1..=n].iter();
+        let mut key_scalars: Vec<E::Fr> = key_powers.map(|power| *power * gamma).collect();
+
+        let scalar_size = E::Fr::size_in_bits();
+        let window_p = FixedBaseMSM::get_mul_window_size(3 * n + 1);
+        let window_q = FixedBaseMSM::get_mul_window_size(n + 1);
+        let (table_p, table_q) = in_span!(
+            "setup.window_tables",
+            (
+                FixedBaseMSM::get_window_table(scalar_size, window_p, generator_p),
+                FixedBaseMSM::get_window_table(scalar_size, window_q, generator_q),
+            )
+        );
+
+        let mut p_points_vec: Vec<E::G1Projective> = in_span!(
+            "setup.powers_g1",
+            FixedBaseMSM::multi_scalar_mul(scalar_size, window_p, &table_p, &powers)
+        );
+        // the point at position n + 1 is what every header key is derived from, so it must never
+        // be published. We replace it by the identity to keep the positions of the other points
+        p_points_vec[n + 1] = E::G1Projective::zero();
+
+        let q_points_vec: Vec<E::G2Projective> = in_span!(
+            "setup.powers_g2",
+            FixedBaseMSM::multi_scalar_mul(scalar_size, window_q, &table_q, &powers[..=n])
+        );
 
         // Now we proceed with the generation of the keys
-        let gamma = E::Fr::rand(rng);
-        let mut point_v = E::G1Projective::prime_subgroup_generator();
+        let mut point_v = generator_p;
         point_v *= gamma;
+        let mut secret_keys: Vec<E::G1Projective> = in_span!(
+            "setup.keys",
+            FixedBaseMSM::multi_scalar_mul(scalar_size, window_p, &table_p, &key_scalars)
+        );
+        powers.zeroize();
+        key_scalars.zeroize();
+
+        let participants: Vec<Recipient<E>> = secret_keys
+            .iter()
+            .zip(&q_points_vec[1..])
+            .enumerate()
+            .map(|(index, (secret_key, public_key))| Recipient {
+                identifier: index + 1,
+                key_pair: KeyPair {
+                    public_key: *public_key,
+                    private_key: *secret_key,
+                },
+                budget: None,
+            })
+            .collect();
+        secret_keys.zeroize();
+
+        // one inversion per vector, so that every aggregation adds mixed points
+        let (broadcaster_pk_g1, broadcaster_pk_g2) = in_span!(
+            "setup.normalization",
+            (
+                E::G1Projective::batch_normalization_into_affine(&p_points_vec),
+                E::G2Projective::batch_normalization_into_affine(&q_points_vec),
+            )
+        );
+        let encryption_tables = Arc::new(EncryptionTables::new(&broadcaster_pk_g2));
+        let mut parameters = BroadcastChannel {
+            number_participants: n,
+            broadcaster_pk_g1: Arc::new(broadcaster_pk_g1),
+            point_v,
+            broadcaster_pk_g2: Arc::new(broadcaster_pk_g2),
+            issued: n,
+            event_sink: None,
+            policy: Policy::default(),
+            retired: BTreeSet::new(),
+            epoch: 0,
+            issued_aggregate: E::G1Projective::zero(),
+            encryption_tables,
+            #[cfg(feature = "std")]
+            aggregate_cache: None,
+        };
+        parameters.refresh_issued_aggregate();
 
-        let mut participants: Vec<Recipient<E>> = Vec::new();
-
-        for i in 1..(n + 1) {
-            let mut secret_key = p_points_vec[i];
-            secret_key *= gamma;
+        let secret = BroadcasterSecret { alpha, gamma };
+        // the secret holds the only copies that are meant to outlive the setup
+        alpha.zeroize();
+        gamma.zeroize();
+        (parameters, participants, secret)
+    }
 
-            let key_pair = KeyPair {
-                public_key: q_points_vec[i],
-                private_key: secret_key,
-            };
+    /// Encrypt for set of recipients. To be precise, what we do here is generate the symmetric
+    /// key, together with the header recipients need to recover it. Fails if the set is empty,
+    /// contains identifiers outside of the channel or more than once, or breaks its [`Policy`].
+    /// Encryptions for the same set can share a [`session::EncryptionSession`].
+    pub fn encrypt<R>(
+        &self,
+        set_recipients: &[usize],
+        rng: &mut R,
+    ) -> Result<(Header<E>, SharedKey<E>), EncryptError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        enter_span!(INFO, "encrypt", set_size = set_recipients.len());
+        Ok(self.session(set_recipients)?.encrypt(rng))
+    }
 
-            participants.push(Recipient {
-                identifier: i,
-                key_pair,
-            });
+    /// Check that `identifier` can decrypt `header` for `set_recipients` before any pairing.
+    pub(crate) fn check_decryption(
+        &self,
+        identifier: usize,
+        set_recipients: &[usize],
+        header: &Header<E>,
+    ) -> Result<(), DecryptError> {
+        self.check_decryption_set(identifier, set_recipients)?;
+        if header.ctx_0.is_zero() || header.ctx_1.is_zero() {
+            return Err(DecryptError::IdentityPoint);
         }
 
-        // we append the V vector to the G1 points. This is not super elegant, but functional
-        p_points_vec.push(point_v);
+        Ok(())
+    }
 
-        let parameters = BroadcastChannel {
-            number_participants: n,
-            broadcaster_pk_g1: p_points_vec,
-            broadcaster_pk_g2: q_points_vec[..2].to_vec(),
-        };
+    /// Check that `identifier` can decrypt headers for `set_recipients`.
+    pub(crate) fn check_decryption_set(
+        &self,
+        identifier: usize,
+        set_recipients: &[usize],
+    ) -> Result<(), DecryptError> {
+        set::check_decryption_set(self.number_participants, identifier, set_recipients)
+    }
 
-        (parameters, participants)
+    /// Sum of the points of the other recipients of `set_recipients` added to the private key of
+    /// `identifier` in the decryption.
+    pub(crate) fn decryption_aggregate(
+        &self,
+        set_recipients: &[usize],
+        identifier: usize,
+    ) -> E::G1Projective {
+        self.observed_decryption_aggregate(set_recipients, identifier, || ())
     }
 
-    /// Encrypt for set of recipients. To be precise, what we do here is generate the symmetric
-    /// key.
-    pub fn encrypt<R>(
+    /// Same as [`BroadcastChannel::decryption_aggregate`], calling `summed` when the aggregate
+    /// is summed rather than taken from the cache. With the `parallel` feature, the aggregate is
+    /// summed in chunks on the threads of the pool.
+    pub(crate) fn observed_decryption_aggregate<F: FnOnce()>(
         &self,
         set_recipients: &[usize],
-        rng: &mut R,
-    ) -> (E::G1Projective, E::G2Projective, E::Fqk)
+        identifier: usize,
+        summed: F,
+    ) -> E::G1Projective {
+        self.aggregate(set_recipients, identifier, || {
+            summed();
+            #[cfg(feature = "parallel")]
+            let sum = self.chunked_decryption_sum(
+                set_recipients,
+                identifier,
+                parallel::AGGREGATION_CHUNK,
+            );
+            #[cfg(not(feature = "parallel"))]
+            let sum = self.decryption_sum(set_recipients.iter().copied(), identifier);
+            sum
+        })
+    }
+
+    /// Sum of the points of `set_recipients`, in this order, once the term of `identifier`
+    /// itself is taken away. The sets are checked for repetitions before.
+    ///
+    /// The loop does not branch on which element of the set is the recipient. Its own term is
+    /// the identity withheld at position n + 1, which the mixed addition would skip, so the
+    /// generator at position 0 is added in its place, picked without a branch, and taken away at
+    /// the end; every addition then goes down the same path, wherever the recipient is in the
+    /// set. The positions of the points read still depend on the identifier.
+    pub(crate) fn decryption_sum<I>(&self, set_recipients: I, identifier: usize) -> E::G1Projective
     where
-        R: Rng + CryptoRng,
+        I: Iterator<Item = usize>,
     {
-        let k = E::Fr::rand(rng);
-        let mut g_2_point = self.broadcaster_pk_g2[1];
-        g_2_point *= k;
-        let K = E::pairing(self.broadcaster_pk_g1[self.number_participants], g_2_point);
+        let n = self.number_participants;
+        let generator = self.broadcaster_pk_g1[0];
+        let mut sum = E::G1Projective::zero();
+        let mut own = 0;
+        for index in set_recipients {
+            let position = n + 1 - index + identifier;
+            let is_own = (position == n + 1) as usize;
+            let candidates = [self.broadcaster_pk_g1[position], generator];
+            sum.add_assign_mixed(&candidates[is_own]);
+            own += is_own;
+        }
+        // whether the recipient is in the set is public, unlike where it is in it
+        let offsets = [E::G1Projective::zero(), generator.into_projective()];
+        sum - offsets[own.min(1)]
+    }
 
-        let mut header_point_in_g2 = self.broadcaster_pk_g2[0];
-        header_point_in_g2 *= k;
+    /// Number of participants of the channel, issued or not.
+    pub fn number_participants(&self) -> usize {
+        self.number_participants
+    }
 
-        let mut header_point_in_g1 = *self.broadcaster_pk_g1.last().unwrap();
+    /// Length of the compressed encoding of the public parameters, as given by
+    /// [`BroadcastChannel::to_bytes`].
+    pub fn params_byte_len(&self) -> usize {
+        self.serialized_size()
+    }
+
+    /// Identifiers of the participants whose keys have been issued.
+    pub fn issued(&self) -> impl Iterator<Item = usize> {
+        1..self.issued + 1
+    }
 
-        for index in set_recipients.iter() {
-            header_point_in_g1 += self.broadcaster_pk_g1[self.number_participants + 1 - index];
+    /// Public key of participant `identifier`, if its key has been issued.
+    pub fn public_key(&self, identifier: usize) -> Option<PublicKey<E>> {
+        if identifier == 0 || identifier > self.issued {
+            return None;
         }
 
-        header_point_in_g1 *= k;
+        Some(PublicKey {
+            point: self.broadcaster_pk_g2[identifier].into_projective(),
+        })
+    }
+
+    /// Identifiers and public keys of the participants whose keys have been issued, in the order
+    /// of the identifiers.
+    pub fn public_keys(&self) -> impl Iterator<Item = (usize, PublicKey<E>)> + '_ {
+        self.broadcaster_pk_g2[1..self.issued + 1]
+            .iter()
+            .zip(1..)
+            .map(|(point, identifier)| {
+                (
+                    identifier,
+                    PublicKey {
+                        point: point.into_projective(),
+                    },
+                )
+            })
+    }
 
-        (header_point_in_g1, header_point_in_g2, K)
+    /// Identifiers within the capacity of the channel whose keys have not been issued.
+    pub fn unused_capacity(&self) -> impl Iterator<Item = usize> {
+        self.issued + 1..self.number_participants + 1
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Sum of the points for `set_recipients` as seen by `identifier`, which is always computed
+    /// without the standard library, as there is no cache to take it from.
+    pub(crate) fn aggregate<F>(&self, _: &[usize], _: usize, compute: F) -> E::G1Projective
+    where
+        F: FnOnce() -> E::G1Projective,
+    {
+        compute()
+    }
+}
+
+#[cfg(feature = "getrandom")]
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Same as [`BroadcastChannel::init_participants`], with randomness taken from the operating
+    /// system.
+    pub fn init_default(n: usize) -> Result<(Self, Vec<Recipient<E>>), SetupError> {
+        Self::init_participants(n, &mut rand_core::OsRng)
+    }
+
+    /// Same as [`BroadcastChannel::encrypt`], with randomness taken from the operating system.
+    pub fn encrypt_default(
+        &self,
+        set_recipients: &[usize],
+    ) -> Result<(Header<E>, SharedKey<E>), EncryptError> {
+        self.encrypt(set_recipients, &mut rand_core::OsRng)
     }
 }
 
@@ -174,25 +767,148 @@ mod tests {
         let mut rng = thread_rng();
 
         let (channel, participants) =
-            BroadcastChannel::<Bls12_381>::init_participants(number_participants, &mut rng);
-
-        assert_eq!(participants.clone().len(), number_participants);
+            BroadcastChannel::<Bls12_381>::init_participants(number_participants, &mut rng)
+                .unwrap();
+
+        assert_eq!(participants.len(), number_participants);
+        assert!(channel.broadcaster_pk_g1[number_participants + 1].is_zero());
+        assert!(channel.issued().eq(1..=number_participants));
+        assert_eq!(channel.unused_capacity().count(), 0);
+        for participant in participants.iter() {
+            assert!(channel.public_key(participant.identifier) == Some(participant.public_key()));
+        }
+        assert!(channel.public_key(0).is_none());
+        assert!(channel.public_key(number_participants + 1).is_none());
+        assert_eq!(channel.number_participants(), number_participants);
+        assert_eq!(channel.params_byte_len(), channel.to_bytes().len());
+        assert!(channel.public_keys().eq(participants
+            .iter()
+            .map(|p| (p.identifier(), p.public_key()))));
 
         let recipients = vec![1, 3, 5];
 
-        let (ctx_0, ctx_1, ctx_2) = channel.encrypt(&recipients, &mut rng);
+        let (header, key) = channel.encrypt(&recipients, &mut rng).unwrap();
 
         let participant_1: Recipient<Bls12_381> = participants[0].clone();
         let participant_2: Recipient<Bls12_381> = participants[1].clone();
         let participant_3: Recipient<Bls12_381> = participants[2].clone();
 
-        let dec_key_1 = participant_1.decrypt(&recipients, &channel, &ctx_0, &ctx_1);
-        assert_eq!(ctx_2, dec_key_1);
+        let dec_key_1 = participant_1.decrypt(&recipients, &channel, &header);
+        assert_eq!(Ok(key.clone()), dec_key_1);
+
+        let dec_key_2 = participant_2.decrypt(&recipients, &channel, &header);
+        assert_eq!(Err(DecryptError::NotInSet), dec_key_2);
+
+        let dec_key_3 = participant_3.decrypt(&recipients, &channel, &header);
+        assert_eq!(Ok(key.clone()), dec_key_3);
+        assert!(header.check().is_ok());
+
+        // the term of the recipient is taken away wherever it is in the set
+        for identifier in recipients.iter() {
+            let mut expected = <Bls12_381 as PairingEngine>::G1Projective::zero();
+            for index in recipients.iter().filter(|index| *index != identifier) {
+                expected += channel.broadcaster_pk_g1[number_participants + 1 - index + identifier]
+                    .into_projective();
+            }
+            assert_eq!(
+                channel.decryption_sum(recipients.iter().copied(), *identifier),
+                expected
+            );
+            assert_eq!(
+                channel.decryption_sum(recipients.iter().rev().copied(), *identifier),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn malformed_inputs_are_refused() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(4, &mut rng).unwrap();
+        let (header, _) = channel.encrypt(&[1, 2], &mut rng).unwrap();
+        assert!(matches!(
+            channel.encrypt(&[1, 5], &mut rng),
+            Err(EncryptError::IdentifierOutOfRange(5))
+        ));
+
+        assert_eq!(
+            participants[0].decrypt(&[1, 5], &channel, &header),
+            Err(DecryptError::IdentifierOutOfRange(5))
+        );
+        assert!(matches!(
+            channel.encrypt(&[0], &mut rng),
+            Err(EncryptError::IdentifierOutOfRange(0))
+        ));
+        assert!(matches!(
+            channel.encrypt(&[], &mut rng),
+            Err(EncryptError::EmptySet)
+        ));
+        assert!(matches!(
+            channel.encrypt(&[3, 1, 3], &mut rng),
+            Err(EncryptError::DuplicateRecipient(3))
+        ));
+        assert_eq!(
+            participants[0].decrypt(&[1, 2, 1], &channel, &header),
+            Err(DecryptError::DuplicateRecipient(1))
+        );
+        assert_eq!(
+            participants[0].decrypt(&[], &channel, &header),
+            Err(DecryptError::NotInSet)
+        );
+
+        let identity = Header {
+            ctx_0: header.ctx_0,
+            ctx_1: <Bls12_381 as PairingEngine>::G2Projective::zero(),
+        };
+        assert_eq!(
+            participants[0].decrypt(&[1, 2], &channel, &identity),
+            Err(DecryptError::IdentityPoint)
+        );
+        assert_eq!(identity.check(), Err(DecryptError::IdentityPoint));
+
+        assert!(matches!(
+            BroadcastChannel::<Bls12_381>::init_participants(0, &mut rng),
+            Err(SetupError::NoParticipants)
+        ));
+    }
 
-        let dec_key_2 = participant_2.decrypt(&recipients, &channel, &ctx_0, &ctx_1);
-        assert_ne!(ctx_2, dec_key_2);
+    #[cfg(feature = "getrandom")]
+    #[test]
+    fn default_rng() {
+        let (channel, participants) = BroadcastChannel::<Bls12_381>::init_default(4).unwrap();
+
+        let recipients = vec![2, 4];
+        let (header, key) = channel.encrypt_default(&recipients).unwrap();
+        assert_eq!(
+            Ok(key.clone()),
+            participants[3].decrypt(&recipients, &channel, &header)
+        );
+    }
 
-        let dec_key_3 = participant_3.decrypt(&recipients, &channel, &ctx_0, &ctx_1);
-        assert_eq!(ctx_2, dec_key_3);
+    #[test]
+    fn seeded_setup() {
+        use rand::rngs::StdRng;
+
+        let (channel, participants, secret) =
+            BroadcastChannel::<Bls12_381>::init_seeded::<StdRng>(3, [7; 32]).unwrap();
+        let (again, _, same_secret) =
+            BroadcastChannel::<Bls12_381>::init_with_secret(3, &mut StdRng::from_seed([7; 32]))
+                .unwrap();
+        assert_eq!(channel.to_bytes(), again.to_bytes());
+        assert_eq!(secret.to_bytes(), same_secret.to_bytes());
+
+        let (other, _, _) =
+            BroadcastChannel::<Bls12_381>::init_seeded::<StdRng>(3, [8; 32]).unwrap();
+        assert_ne!(channel.to_bytes(), other.to_bytes());
+        let (header, key) = channel.encrypt(&[1, 3], &mut thread_rng()).unwrap();
+        assert_eq!(
+            participants[2].decrypt(&[1, 3], &channel, &header),
+            Ok(key.clone())
+        );
+        assert!(matches!(
+            BroadcastChannel::<Bls12_381>::init_seeded::<StdRng>(0, [7; 32]),
+            Err(SetupError::NoParticipants)
+        ));
     }
 }
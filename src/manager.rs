@@ -0,0 +1,264 @@
+//! Many independent channels run by one service.
+//!
+//! A [`ChannelManager`] holds channels keyed by a channel identifier, chosen by the service.
+//! Headers of different channels look alike, and nothing in a [`Header`] says which channel it
+//! belongs to, so a recipient of one channel handed a header of another would recover garbage
+//! without noticing. The manager tags every header with its channel in a [`ChannelHeader`], and
+//! [`ChannelManager::decrypt`] refuses headers of another channel than the recipient's, which it
+//! tells by the public key of the recipient.
+//!
+//! The manager hands out symmetric keys rather than keys of the target group. They are derived
+//! with [`derive_key`] and [`HkdfSha256`] under a domain separation tag of the channel, built from
+//! its identifier and the fingerprint of its parameters, so that keys of different channels are
+//! independent even if their header keys were equal.
+//!
+//! A tagged header is encoded as the channel identifier, as a byte vector, followed by the header.
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use ark_ec::PairingEngine;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use rand_core::{CryptoRng, RngCore};
+
+use crate::encoding::{from_bytes, to_bytes};
+use crate::error::ManagerError;
+use crate::kdf::{derive_key, HkdfSha256};
+use crate::{BroadcastChannel, Header, Recipient};
+
+/// Prefix of the domain separation tags of the channels.
+const CHANNEL_TAG: &[u8] = b"DGS-BE-MANAGED-CHANNEL-V1";
+
+/// Header tagged with the identifier of the channel it was encrypted in.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ChannelHeader<E: PairingEngine> {
+    channel: String,
+    header: Header<E>,
+}
+
+impl<E: PairingEngine> ChannelHeader<E> {
+    /// Identifier of the channel of the header.
+    pub fn channel(&self) -> &str {
+        &self.channel
+    }
+
+    /// Header itself.
+    pub fn header(&self) -> &Header<E> {
+        &self.header
+    }
+
+    /// Compressed encoding of the tagged header.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+}
+
+impl<E: PairingEngine> CanonicalSerialize for ChannelHeader<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.channel.as_bytes().to_vec().serialize(&mut writer)?;
+        self.header.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.channel.as_bytes().to_vec().serialized_size() + self.header.serialized_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for ChannelHeader<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let channel = String::from_utf8(Vec::deserialize(&mut reader)?)
+            .map_err(|_| SerializationError::InvalidData)?;
+        Ok(ChannelHeader {
+            channel,
+            header: Header::deserialize(&mut reader)?,
+        })
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for ChannelHeader<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+/// Channels of a service, by identifier.
+pub struct ChannelManager<E: PairingEngine> {
+    channels: BTreeMap<String, BroadcastChannel<E>>,
+}
+
+impl<E: PairingEngine> ChannelManager<E> {
+    /// Manager without channels.
+    pub fn new() -> Self {
+        ChannelManager {
+            channels: BTreeMap::new(),
+        }
+    }
+
+    /// Set up channel `id` for `n` participants, and return them. Fails if there is already a
+    /// channel `id`, or if the setup fails.
+    pub fn create<R>(
+        &mut self,
+        id: &str,
+        n: usize,
+        rng: &mut R,
+    ) -> Result<Vec<Recipient<E>>, ManagerError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if self.channels.contains_key(id) {
+            return Err(ManagerError::DuplicateChannel);
+        }
+        let (channel, participants) =
+            BroadcastChannel::init_participants(n, rng).map_err(ManagerError::Setup)?;
+        self.channels.insert(id.to_string(), channel);
+        Ok(participants)
+    }
+
+    /// Manage `channel` as channel `id`. Fails if there is already a channel `id`.
+    pub fn insert(&mut self, id: &str, channel: BroadcastChannel<E>) -> Result<(), ManagerError> {
+        if self.channels.contains_key(id) {
+            return Err(ManagerError::DuplicateChannel);
+        }
+        self.channels.insert(id.to_string(), channel);
+        Ok(())
+    }
+
+    /// Stop managing channel `id`, and return it.
+    pub fn remove(&mut self, id: &str) -> Option<BroadcastChannel<E>> {
+        self.channels.remove(id)
+    }
+
+    /// Channel `id`, if it is managed.
+    pub fn get(&self, id: &str) -> Option<&BroadcastChannel<E>> {
+        self.channels.get(id)
+    }
+
+    /// Channel `id`, to issue or retire keys, if it is managed.
+    pub fn get_mut(&mut self, id: &str) -> Option<&mut BroadcastChannel<E>> {
+        self.channels.get_mut(id)
+    }
+
+    /// Identifiers of the managed channels, in increasing order.
+    pub fn ids(&self) -> impl Iterator<Item = &str> + '_ {
+        self.channels.keys().map(String::as_str)
+    }
+
+    /// Domain separation tag of channel `id`, binding its identifier and its parameters. Fails
+    /// if the channel is not managed.
+    pub fn domain_tag(&self, id: &str) -> Result<Vec<u8>, ManagerError> {
+        let channel = self.channel(id)?;
+        let mut tag = CHANNEL_TAG.to_vec();
+        tag.extend_from_slice(&(id.len() as u64).to_le_bytes());
+        tag.extend_from_slice(id.as_bytes());
+        tag.extend_from_slice(channel.view().fingerprint().as_ref());
+        Ok(tag)
+    }
+
+    /// Encrypt for `set_recipients` of channel `id`. Returns the tagged header and the 32-byte
+    /// key of the channel derived from its key.
+    pub fn encrypt<R>(
+        &self,
+        id: &str,
+        set_recipients: &[usize],
+        rng: &mut R,
+    ) -> Result<(ChannelHeader<E>, [u8; 32]), ManagerError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let (header, key) = self
+            .channel(id)?
+            .encrypt(set_recipients, rng)
+            .map_err(ManagerError::Encrypt)?;
+        let header = ChannelHeader {
+            channel: id.to_string(),
+            header,
+        };
+        Ok((header, self.derive(id, key.key())?))
+    }
+
+    /// Key of `header`, encrypted for `set_recipients`, for `recipient`. Fails if the channel of
+    /// the header is not managed, if the recipient is not a participant of it, or if the header
+    /// cannot be decrypted.
+    pub fn decrypt(
+        &self,
+        recipient: &Recipient<E>,
+        set_recipients: &[usize],
+        header: &ChannelHeader<E>,
+    ) -> Result<[u8; 32], ManagerError> {
+        let channel = self.channel(&header.channel)?;
+        if channel.public_key(recipient.identifier) != Some(recipient.public_key()) {
+            return Err(ManagerError::WrongChannel);
+        }
+        let key = recipient
+            .decrypt(set_recipients, channel, &header.header)
+            .map_err(ManagerError::Decrypt)?;
+        self.derive(&header.channel, key.key())
+    }
+
+    fn channel(&self, id: &str) -> Result<&BroadcastChannel<E>, ManagerError> {
+        self.channels.get(id).ok_or(ManagerError::UnknownChannel)
+    }
+
+    fn derive(&self, id: &str, key: &E::Fqk) -> Result<[u8; 32], ManagerError> {
+        let mut output = [0u8; 32];
+        // 32 bytes are within the output of HKDF
+        derive_key::<E, HkdfSha256>(key, &self.domain_tag(id)?, &mut output).unwrap();
+        Ok(output)
+    }
+}
+
+impl<E: PairingEngine> Default for ChannelManager<E> {
+    fn default() -> Self {
+        ChannelManager::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn channels_are_separated() {
+        let mut rng = thread_rng();
+        let mut manager = ChannelManager::<Bls12_381>::new();
+        let first = manager.create("first", 3, &mut rng).unwrap();
+        let second = manager.create("second", 3, &mut rng).unwrap();
+        assert_eq!(
+            manager.create("first", 2, &mut rng).err(),
+            Some(ManagerError::DuplicateChannel)
+        );
+        assert_eq!(
+            manager.ids().collect::<Vec<&str>>(),
+            vec!["first", "second"]
+        );
+        assert!(manager.domain_tag("first").unwrap() != manager.domain_tag("second").unwrap());
+
+        let (header, key) = manager.encrypt("first", &[1, 2], &mut rng).unwrap();
+        let header = ChannelHeader::try_from(header.to_bytes().as_slice()).unwrap();
+        assert_eq!(header.channel(), "first");
+        assert_eq!(manager.decrypt(&first[1], &[1, 2], &header), Ok(key));
+        assert_eq!(
+            manager.decrypt(&second[1], &[1, 2], &header),
+            Err(ManagerError::WrongChannel)
+        );
+        assert!(matches!(
+            manager.decrypt(&first[2], &[1, 2], &header),
+            Err(ManagerError::Decrypt(_))
+        ));
+
+        let removed = manager.remove("first").unwrap();
+        assert_eq!(
+            manager.decrypt(&first[1], &[1, 2], &header),
+            Err(ManagerError::UnknownChannel)
+        );
+        manager.insert("first", removed).unwrap();
+        assert_eq!(manager.decrypt(&first[0], &[1, 2], &header), Ok(key));
+    }
+}
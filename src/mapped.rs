@@ -0,0 +1,154 @@
+//! Public parameters read from a memory-mapped file.
+//!
+//! With millions of participants the parameters take hundreds of megabytes, which not every
+//! encryptor can hold. [`MappedParams`] maps a file holding the compressed encoding of a channel,
+//! as written from [`BroadcastChannel::to_bytes`](crate::BroadcastChannel::to_bytes), and only
+//! decodes the points [`crate::store::encrypt`] and [`crate::Recipient::decrypt_from_store`] ask
+//! for. Compressed points all have the same size, so the position of every point in the file
+//! follows from the number of participants. Only the lengths and the state of the channel written
+//! after the points, its policy and retired keys, are checked when the file is opened; points go
+//! through the checked deserialization as they are read.
+//!
+//! The file must not be modified while it is mapped.
+
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::marker::PhantomData;
+use std::ops::Range;
+use std::path::Path;
+
+use ark_ec::PairingEngine;
+use ark_serialize::{CanonicalDeserialize, SerializationError};
+use memmap2::Mmap;
+
+use crate::encoding::deserialize_state;
+use crate::layout::{length_at, Layout, LENGTH_SIZE};
+use crate::policy::Policy;
+use crate::store::ParamStore;
+
+/// Parameters of a channel in a memory-mapped file.
+pub struct MappedParams<E: PairingEngine> {
+    map: Mmap,
+    layout: Layout,
+    policy: Policy,
+    retired: BTreeSet<usize>,
+    _engine: PhantomData<E>,
+}
+
+impl<E: PairingEngine> MappedParams<E> {
+    /// Map the channel encoded in the file at `path`. Fails if the file cannot be read, if its
+    /// lengths do not match the number of participants it announces, or if its state is malformed.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, SerializationError> {
+        let file = File::open(path)?;
+        // SAFETY: the mapping is only read, and the module requires that the file is not
+        // modified while it is mapped
+        let map = unsafe { Mmap::map(&file)? };
+
+        let number_participants = length_at(&map, 0)?;
+        let layout =
+            Layout::new::<E>(number_participants).ok_or(SerializationError::InvalidData)?;
+
+        let issued = length_at(&map, LENGTH_SIZE)?;
+        if map.len() < layout.state_start
+            || issued > number_participants
+            || length_at(&map, 2 * LENGTH_SIZE)? != layout.g1_length()
+            || length_at(&map, layout.g2_length_start)? != layout.g2_length()
+        {
+            return Err(SerializationError::InvalidData);
+        }
+        let mut bytes = &map[layout.state_start..];
+        let state = deserialize_state(&mut bytes, issued)?;
+        if !bytes.is_empty() {
+            return Err(SerializationError::InvalidData);
+        }
+
+        Ok(MappedParams {
+            policy: state.policy,
+            retired: state.retired,
+            map,
+            layout,
+            _engine: PhantomData,
+        })
+    }
+
+    /// Points at `bytes` of the map, or an error if the range of positions was not in the vector.
+    fn points<T: CanonicalDeserialize>(
+        &self,
+        bytes: Option<Range<usize>>,
+        count: usize,
+    ) -> Result<Vec<T>, SerializationError> {
+        let mut bytes = &self.map[bytes.ok_or(SerializationError::InvalidData)?];
+        (0..count).map(|_| T::deserialize(&mut bytes)).collect()
+    }
+}
+
+impl<E: PairingEngine> ParamStore<E> for MappedParams<E> {
+    type Error = SerializationError;
+
+    fn number_participants(&self) -> usize {
+        self.layout.number_participants
+    }
+
+    fn g1_points(&self, range: Range<usize>) -> Result<Vec<E::G1Affine>, SerializationError> {
+        self.points(self.layout.g1_bytes(&range), range.len())
+    }
+
+    fn g2_points(&self, range: Range<usize>) -> Result<Vec<E::G2Affine>, SerializationError> {
+        self.points(self.layout.g2_bytes(&range), range.len())
+    }
+
+    fn point_v(&self) -> Result<E::G1Projective, SerializationError> {
+        let point_v = &self.map[self.layout.point_v_start..self.layout.state_start];
+        E::G1Projective::deserialize(point_v)
+    }
+
+    fn policy(&self) -> Policy {
+        self.policy
+    }
+
+    fn is_retired(&self, identifier: usize) -> bool {
+        self.retired.contains(&identifier)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::encrypt;
+    use crate::BroadcastChannel;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn mapped_round_trip() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(6, &mut rng).unwrap();
+        let path = std::env::temp_dir().join(format!("dgs-be-mapped-{}", std::process::id()));
+        std::fs::write(&path, channel.to_bytes()).unwrap();
+
+        let mapped = MappedParams::<Bls12_381>::open(&path).unwrap();
+        assert_eq!(mapped.number_participants(), 6);
+        assert_eq!(
+            mapped.g1_points(0..13).unwrap(),
+            channel.g1_points(0..13).unwrap()
+        );
+        assert!(mapped.g2_points(5..8).is_err());
+
+        let (header, key) = encrypt(&mapped, &[2, 3, 6], &mut rng).unwrap();
+        assert_eq!(
+            participants[5].decrypt(&[2, 3, 6], &channel, &header),
+            Ok(key.clone())
+        );
+        assert_eq!(
+            participants[1]
+                .decrypt_from_store(&[2, 3, 6], &mapped, &header)
+                .ok(),
+            Some(key.clone())
+        );
+
+        std::fs::write(&path, &channel.to_bytes()[1..]).unwrap();
+        assert!(MappedParams::<Bls12_381>::open(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,258 @@
+//! Zero-knowledge proofs of decryption capability.
+//!
+//! Recipient `i` can decrypt a header for set `S` if `i` is in `S` and holds `d_i`, which
+//! satisfies `e(d_i, Q) = e(V, Q_i)`. A [`MembershipProof`] shows that the prover knows such a
+//! `d_i` for some `i` in `S`, without revealing `d_i` nor `i`: it is a disjunction of Schnorr-like
+//! proofs for the map `d -> e(d, Q)`, one per member of the set, all of them simulated but the
+//! prover's (Cramer, Damgård, Schoenmakers), made non-interactive with Fiat-Shamir.
+//!
+//! The challenge binds the parameters of the channel, the set, the header and a context chosen by
+//! the verifier, typically a fresh nonce so that proofs cannot be replayed. Headers do not commit
+//! to their set, so the verifier has to know the set of the header from elsewhere. Proofs hold
+//! one challenge and one point of G1 per member of the set, and cost two pairings per member to
+//! produce and to verify.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use ark_ec::PairingEngine;
+use ark_ff::{PrimeField, UniformRand, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha256};
+
+use crate::encoding::{from_bytes, to_bytes};
+use crate::error::DecryptError;
+use crate::set::RecipientSet;
+use crate::{BroadcastChannel, Header, Recipient};
+
+/// Domain separation tag of the challenges.
+const MEMBERSHIP_TAG: &[u8] = b"DGS-BE-MEMBERSHIP-PROOF-V1";
+
+/// Proof that the prover can decrypt a header, without saying which recipient they are.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MembershipProof<E: PairingEngine> {
+    /// challenge of every member, in increasing order of identifiers
+    challenges: Vec<E::Fr>,
+    /// response of every member
+    responses: Vec<E::G1Projective>,
+}
+
+impl<E: PairingEngine> MembershipProof<E> {
+    /// Compressed encoding of the proof.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+
+    /// Check the proof for `set_recipients` and `header` within `context`.
+    pub fn verify(
+        &self,
+        set_recipients: &[usize],
+        channel: &BroadcastChannel<E>,
+        header: &Header<E>,
+        context: &[u8],
+    ) -> bool {
+        let set = RecipientSet::new(set_recipients);
+        let statements = match statements(&set, channel) {
+            Ok(statements) => statements,
+            Err(_) => return false,
+        };
+        if self.challenges.len() != set.len() || self.responses.len() != set.len() {
+            return false;
+        }
+
+        let commitments: Vec<E::Fqk> = statements
+            .iter()
+            .zip(self.challenges.iter().zip(self.responses.iter()))
+            .map(|(public_key, (challenge, response))| {
+                simulated_commitment::<E>(channel, *public_key, *challenge, *response)
+            })
+            .collect();
+        let sum = self
+            .challenges
+            .iter()
+            .fold(E::Fr::zero(), |sum, challenge| sum + challenge);
+        sum == challenge::<E>(&set, channel, header, context, &commitments)
+    }
+}
+
+impl<E: PairingEngine> Recipient<E> {
+    /// Prove that this recipient can decrypt `header`, encrypted for `set_recipients`, without
+    /// revealing its identifier. `context` is bound to the proof and should come from the
+    /// verifier. Fails if the recipient is not in the set, or if the set contains identifiers
+    /// whose keys have not been issued.
+    pub fn prove_membership<R>(
+        &self,
+        set_recipients: &[usize],
+        channel: &BroadcastChannel<E>,
+        header: &Header<E>,
+        context: &[u8],
+        rng: &mut R,
+    ) -> Result<MembershipProof<E>, DecryptError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let set = RecipientSet::new(set_recipients);
+        let statements = statements(&set, channel)?;
+        let position = set
+            .iter()
+            .position(|identifier| identifier == self.identifier)
+            .ok_or(DecryptError::NotInSet)?;
+
+        let mut challenges = Vec::with_capacity(set.len());
+        let mut responses = Vec::with_capacity(set.len());
+        let mut commitments = Vec::with_capacity(set.len());
+        let nonce = E::G1Projective::rand(rng);
+        for (index, public_key) in statements.iter().enumerate() {
+            if index == position {
+                challenges.push(E::Fr::zero());
+                responses.push(E::G1Projective::zero());
+                commitments.push(E::pairing(nonce, channel.broadcaster_pk_g2[0]));
+            } else {
+                let challenge = E::Fr::rand(rng);
+                let response = E::G1Projective::rand(rng);
+                challenges.push(challenge);
+                responses.push(response);
+                commitments.push(simulated_commitment::<E>(
+                    channel,
+                    *public_key,
+                    challenge,
+                    response,
+                ));
+            }
+        }
+
+        let total = challenge::<E>(&set, channel, header, context, &commitments);
+        let own = challenges
+            .iter()
+            .fold(total, |own, challenge| own - challenge);
+        let mut response = self.key_pair.private_key;
+        response *= own;
+        challenges[position] = own;
+        responses[position] = nonce + response;
+
+        Ok(MembershipProof {
+            challenges,
+            responses,
+        })
+    }
+}
+
+/// Public keys of the members of `set`, all of which must have been issued.
+fn statements<E: PairingEngine>(
+    set: &RecipientSet,
+    channel: &BroadcastChannel<E>,
+) -> Result<Vec<E::G2Projective>, DecryptError> {
+    set.iter()
+        .map(|identifier| {
+            channel
+                .public_key(identifier)
+                .map(|public_key| public_key.point)
+                .ok_or(DecryptError::IdentifierOutOfRange(identifier))
+        })
+        .collect()
+}
+
+/// Commitment `e(z, Q) / e(cV, Q_i)` that a response `z` to challenge `c` answers for the member
+/// with public key `Q_i`.
+fn simulated_commitment<E: PairingEngine>(
+    channel: &BroadcastChannel<E>,
+    public_key: E::G2Projective,
+    challenge: E::Fr,
+    response: E::G1Projective,
+) -> E::Fqk {
+    let mut point_v = channel.point_v;
+    point_v *= challenge;
+    E::pairing(response, channel.broadcaster_pk_g2[0]) / E::pairing(point_v, public_key)
+}
+
+/// Fiat-Shamir challenge over the statement and the commitments.
+fn challenge<E: PairingEngine>(
+    set: &RecipientSet,
+    channel: &BroadcastChannel<E>,
+    header: &Header<E>,
+    context: &[u8],
+    commitments: &[E::Fqk],
+) -> E::Fr {
+    let mut hasher = Sha256::new();
+    hasher.update(MEMBERSHIP_TAG);
+    hasher.update(channel.view().fingerprint());
+    hasher.update(set.canonical_bytes());
+    hasher.update(header.to_bytes());
+    hasher.update((context.len() as u64).to_le_bytes());
+    hasher.update(context);
+    hasher.update(to_bytes(&commitments.to_vec()));
+    let seed = hasher.finalize();
+
+    // twice the size of the scalar field, so that the reduction is close to uniform
+    let mut wide = Vec::with_capacity(64);
+    for block in 0u8..2 {
+        let mut hasher = Sha256::new();
+        hasher.update(&seed);
+        hasher.update([block]);
+        wide.extend_from_slice(&hasher.finalize());
+    }
+    E::Fr::from_le_bytes_mod_order(&wide)
+}
+
+impl<E: PairingEngine> CanonicalSerialize for MembershipProof<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.challenges.serialize(&mut writer)?;
+        self.responses.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.challenges.serialized_size() + self.responses.serialized_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for MembershipProof<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Ok(MembershipProof {
+            challenges: Vec::deserialize(&mut reader)?,
+            responses: Vec::deserialize(&mut reader)?,
+        })
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for MembershipProof<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn members_prove_capability() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(4, &mut rng).unwrap();
+        let set = [4, 2, 3];
+        let (header, _) = channel.encrypt(&set, &mut rng).unwrap();
+
+        let proof = participants[2]
+            .prove_membership(&set, &channel, &header, b"nonce", &mut rng)
+            .unwrap();
+        assert!(proof.verify(&set, &channel, &header, b"nonce"));
+        assert!(proof.verify(&[2, 3, 4], &channel, &header, b"nonce"));
+        assert!(!proof.verify(&set, &channel, &header, b"other nonce"));
+        assert!(!proof.verify(&[1, 2, 3], &channel, &header, b"nonce"));
+        let (other_header, _) = channel.encrypt(&set, &mut rng).unwrap();
+        assert!(!proof.verify(&set, &channel, &other_header, b"nonce"));
+
+        let decoded = MembershipProof::try_from(proof.to_bytes().as_slice()).unwrap();
+        assert_eq!(decoded, proof);
+
+        assert_eq!(
+            participants[0].prove_membership(&set, &channel, &header, b"nonce", &mut rng),
+            Err(DecryptError::NotInSet)
+        );
+    }
+}
@@ -0,0 +1,121 @@
+//! Encryption of payloads under the key of a header.
+//!
+//! [`BroadcastChannel::encrypt_message`] is the usual KEM-DEM construction: it encrypts a header
+//! for the set, derives a 32-byte key from the key of the header with [`HkdfSha256`], and seals
+//! the payload with ChaCha20-Poly1305, the encoding of the header being the associated data. Each
+//! header has a fresh key, which seals a single payload, so the nonce is fixed to zero.
+//! [`Recipient::decrypt_message`] reverses the steps, and refuses payloads that were modified or
+//! sealed under another header.
+//!
+//! Only available with the `chacha20poly1305` feature.
+
+use alloc::vec::Vec;
+
+use ark_ec::PairingEngine;
+use chacha20poly1305::aead::{Aead, NewAead, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+use crate::error::{EncryptError, MessageError};
+use crate::kdf::{derive_key, HkdfSha256};
+use crate::{BroadcastChannel, Header, Recipient};
+
+/// Info of the derivation of the payload keys.
+const MESSAGE_KEY_INFO: &[u8] = b"DGS-BE-MESSAGE-CHACHA20POLY1305";
+
+/// Cipher keyed with the payload key of the header key `key`, derived with `info`.
+pub(crate) fn cipher<E: PairingEngine>(key: &E::Fqk, info: &[u8]) -> ChaCha20Poly1305 {
+    let mut bytes = [0u8; 32];
+    // 32 bytes are within the output of HKDF
+    derive_key::<E, HkdfSha256>(key, info, &mut bytes).unwrap();
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(&bytes));
+    bytes.zeroize();
+    cipher
+}
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Encrypt `plaintext` for set `set_recipients`, returning the header and the sealed
+    /// payload, which is 16 bytes longer than the plaintext.
+    pub fn encrypt_message<R>(
+        &self,
+        set_recipients: &[usize],
+        plaintext: &[u8],
+        rng: &mut R,
+    ) -> Result<(Header<E>, Vec<u8>), EncryptError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let (header, key) = self.encrypt(set_recipients, rng)?;
+        let payload = Payload {
+            msg: plaintext,
+            aad: &header.to_bytes(),
+        };
+        // sealing only fails beyond 256 GiB, far more than fits in a slice
+        let sealed = cipher::<E>(key.key(), MESSAGE_KEY_INFO)
+            .encrypt(Nonce::from_slice(&[0u8; 12]), payload)
+            .expect("payload too long for ChaCha20-Poly1305");
+        Ok((header, sealed))
+    }
+}
+
+impl<E: PairingEngine> Recipient<E> {
+    /// Decrypt the payload `sealed`, encrypted with `header` for set `set_recipients`.
+    pub fn decrypt_message(
+        &self,
+        set_recipients: &[usize],
+        channel: &BroadcastChannel<E>,
+        header: &Header<E>,
+        sealed: &[u8],
+    ) -> Result<Vec<u8>, MessageError> {
+        let key = self
+            .decrypt(set_recipients, channel, header)
+            .map_err(MessageError::Decrypt)?;
+        let payload = Payload {
+            msg: sealed,
+            aad: &header.to_bytes(),
+        };
+        cipher::<E>(key.key(), MESSAGE_KEY_INFO)
+            .decrypt(Nonce::from_slice(&[0u8; 12]), payload)
+            .map_err(|_| MessageError::Authentication)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::DecryptError;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn messages_round_trip() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(4, &mut rng).unwrap();
+        let (header, sealed) = channel
+            .encrypt_message(&[1, 4], b"attack at dawn", &mut rng)
+            .unwrap();
+        assert_eq!(sealed.len(), 14 + 16);
+        assert_eq!(
+            participants[3].decrypt_message(&[1, 4], &channel, &header, &sealed),
+            Ok(b"attack at dawn".to_vec())
+        );
+        assert_eq!(
+            participants[2].decrypt_message(&[1, 4], &channel, &header, &sealed),
+            Err(MessageError::Decrypt(DecryptError::NotInSet))
+        );
+
+        let mut tampered = sealed.clone();
+        tampered[0] ^= 1;
+        assert_eq!(
+            participants[0].decrypt_message(&[1, 4], &channel, &header, &tampered),
+            Err(MessageError::Authentication)
+        );
+        let (other, _) = channel.encrypt_message(&[1, 4], b"", &mut rng).unwrap();
+        assert_eq!(
+            participants[0].decrypt_message(&[1, 4], &channel, &other, &sealed),
+            Err(MessageError::Authentication)
+        );
+    }
+}
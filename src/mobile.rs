@@ -0,0 +1,176 @@
+//! Bindings for mobile recipients, through UniFFI.
+//!
+//! The objects here expose the scheme on BLS12-381 to Swift and Kotlin. UniFFI generates the
+//! foreign bindings from the library itself, with `uniffi-bindgen generate --library`. As for
+//! the other bindings, parameters, keys and headers cross the boundary in their compressed
+//! encodings, sets as lists of identifiers, and payloads are sealed as in [`crate::message`].
+//! Errors are thrown as [`MobileError`]s, whose message is the one of the underlying error.
+//!
+//! Only available with the `mobile` feature.
+
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+use ark_bls12_381::Bls12_381;
+
+use crate::error::MobileError;
+use crate::{BroadcastChannel, Header, Recipient};
+
+fn to_set(set_recipients: &[u32]) -> Vec<usize> {
+    set_recipients
+        .iter()
+        .map(|identifier| *identifier as usize)
+        .collect()
+}
+
+/// Encoded parameters of a new channel, and the encoded key pairs of its participants.
+#[derive(uniffi::Record)]
+pub struct MobileSetup {
+    /// compressed encoding of the channel
+    pub parameters: Vec<u8>,
+    /// compressed encodings of the key pairs of participants 1 to `n`
+    pub keys: Vec<Vec<u8>>,
+}
+
+/// Encoded header, and the payload sealed under its key.
+#[derive(uniffi::Record)]
+pub struct MobileMessage {
+    /// compressed encoding of the header
+    pub header: Vec<u8>,
+    /// sealed payload
+    pub sealed: Vec<u8>,
+}
+
+/// Set up a channel for `participants` participants, with randomness taken from the operating
+/// system.
+#[uniffi::export]
+pub fn setup(participants: u32) -> Result<MobileSetup, MobileError> {
+    let (channel, recipients) = BroadcastChannel::<Bls12_381>::init_default(participants as usize)
+        .map_err(MobileError::Setup)?;
+    Ok(MobileSetup {
+        parameters: channel.to_bytes(),
+        keys: recipients.iter().map(Recipient::to_bytes).collect(),
+    })
+}
+
+/// Public parameters of a channel.
+#[derive(uniffi::Object)]
+pub struct MobileChannel(BroadcastChannel<Bls12_381>);
+
+#[uniffi::export]
+impl MobileChannel {
+    /// Parameters from their compressed encoding.
+    #[uniffi::constructor]
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Arc<Self>, MobileError> {
+        BroadcastChannel::try_from(bytes.as_slice())
+            .map(|channel| Arc::new(MobileChannel(channel)))
+            .map_err(|_| MobileError::InvalidEncoding)
+    }
+
+    /// Compressed encoding of the parameters.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes()
+    }
+
+    /// Number of participants of the channel.
+    pub fn number_participants(&self) -> u32 {
+        self.0.number_participants as u32
+    }
+
+    /// Seal `plaintext` for `set_recipients`, with randomness taken from the operating system.
+    pub fn encrypt_message(
+        &self,
+        set_recipients: Vec<u32>,
+        plaintext: Vec<u8>,
+    ) -> Result<MobileMessage, MobileError> {
+        let (header, sealed) = self
+            .0
+            .encrypt_message(&to_set(&set_recipients), &plaintext, &mut rand_core::OsRng)
+            .map_err(MobileError::Encrypt)?;
+        Ok(MobileMessage {
+            header: header.to_bytes(),
+            sealed,
+        })
+    }
+}
+
+/// Key pair of a participant.
+#[derive(uniffi::Object)]
+pub struct MobileRecipient(Recipient<Bls12_381>);
+
+#[uniffi::export]
+impl MobileRecipient {
+    /// Import a key pair from its compressed encoding.
+    #[uniffi::constructor]
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Arc<Self>, MobileError> {
+        Recipient::try_from(bytes.as_slice())
+            .map(|recipient| Arc::new(MobileRecipient(recipient)))
+            .map_err(|_| MobileError::InvalidEncoding)
+    }
+
+    /// Compressed encoding of the key pair.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes()
+    }
+
+    /// Identifier of the participant.
+    pub fn identifier(&self) -> u32 {
+        self.0.identifier as u32
+    }
+
+    /// Payload of `message`, sealed for `set_recipients` of `channel`.
+    pub fn decrypt_message(
+        &self,
+        set_recipients: Vec<u32>,
+        channel: Arc<MobileChannel>,
+        message: MobileMessage,
+    ) -> Result<Vec<u8>, MobileError> {
+        let header = Header::try_from(message.header.as_slice())
+            .map_err(|_| MobileError::InvalidEncoding)?;
+        self.0
+            .decrypt_message(
+                &to_set(&set_recipients),
+                &channel.0,
+                &header,
+                &message.sealed,
+            )
+            .map_err(MobileError::Message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{DecryptError, MessageError};
+
+    #[test]
+    fn mobile_recipients_decrypt_messages() {
+        let encoded = setup(3).unwrap();
+        let channel = MobileChannel::from_bytes(encoded.parameters).unwrap();
+        let recipient = MobileRecipient::from_bytes(encoded.keys[1].clone()).unwrap();
+        assert_eq!(recipient.identifier(), 2);
+
+        let message = channel
+            .encrypt_message(vec![1, 2], b"hello".to_vec())
+            .unwrap();
+        let MobileMessage { header, sealed } = message;
+        let copy = || MobileMessage {
+            header: header.clone(),
+            sealed: sealed.clone(),
+        };
+        assert_eq!(
+            recipient.decrypt_message(vec![1, 2], channel.clone(), copy()),
+            Ok(b"hello".to_vec())
+        );
+        assert_eq!(
+            recipient.decrypt_message(vec![1, 3], channel.clone(), copy()),
+            Err(MobileError::Message(MessageError::Decrypt(
+                DecryptError::NotInSet
+            )))
+        );
+        assert_eq!(
+            MobileRecipient::from_bytes(vec![0; 3]).err(),
+            Some(MobileError::InvalidEncoding)
+        );
+    }
+}
@@ -0,0 +1,440 @@
+//! Headers for several disjoint subsets in one transmission.
+//!
+//! A broadcaster serving content in tiers sends each tier to its own set of recipients. Rather
+//! than sending one message per tier, [`BroadcastChannel::encrypt_multi`] encrypts a header for
+//! each subset and gathers them in a [`MultiHeader`], and derives a symmetric key per subset from
+//! the key of its header, bound to the position and to the set of the subset. A recipient finds
+//! the subset it belongs to with [`Recipient::decrypt_multi`], and recovers the key of that
+//! subset only: the subsets are disjoint, so no recipient learns the key of another tier.
+//!
+//! With the `chacha20poly1305` feature, [`seal_multi`] sends one payload to every subset: the
+//! payload is sealed once under a fresh content key, which is sealed under the key of each
+//! subset, and [`open_multi`] unwraps it with the key of the subset of the recipient. The cost
+//! of a new subset is then a header and 48 bytes, whatever the size of the payload.
+//!
+//! A multi-header is encoded as the number of subsets as a little-endian `u64`, then, for each
+//! subset, the canonical encoding of its [`RecipientSet`] followed by its compressed header.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use ark_ec::PairingEngine;
+use ark_ff::Zero;
+use ark_serialize::{CanonicalSerialize, SerializationError};
+use rand_core::{CryptoRng, RngCore};
+
+use crate::encoding::from_bytes;
+use crate::error::MultiHeaderError;
+use crate::kdf::{derive_key, HkdfSha256};
+use crate::set::RecipientSet;
+use crate::wire::{take, take_set, take_u64};
+use crate::{BroadcastChannel, Header, Recipient};
+
+/// Info of the derivation of the keys of the subsets.
+const MULTI_KEY_INFO: &[u8] = b"DGS-BE-MULTI-HEADER-SUBSET";
+
+/// Headers for disjoint subsets of the participants of a channel.
+#[derive(Clone, PartialEq, Eq)]
+pub struct MultiHeader<E: PairingEngine> {
+    subsets: Vec<(RecipientSet, Header<E>)>,
+}
+
+impl<E: PairingEngine> MultiHeader<E> {
+    /// Number of subsets.
+    pub fn len(&self) -> usize {
+        self.subsets.len()
+    }
+
+    /// Whether there are no subsets.
+    pub fn is_empty(&self) -> bool {
+        self.subsets.is_empty()
+    }
+
+    /// Set and header of subset `index`.
+    pub fn subset(&self, index: usize) -> Option<(&RecipientSet, &Header<E>)> {
+        self.subsets.get(index).map(|(set, header)| (set, header))
+    }
+
+    /// Sets of the subsets, in order.
+    pub fn sets(&self) -> impl Iterator<Item = &RecipientSet> + '_ {
+        self.subsets.iter().map(|(set, _)| set)
+    }
+
+    /// Position of the subset holding `identifier`, if any.
+    pub fn position(&self, identifier: usize) -> Option<usize> {
+        self.subsets
+            .iter()
+            .position(|(set, _)| set.contains(identifier))
+    }
+
+    /// Encoding of the multi-header.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&(self.subsets.len() as u64).to_le_bytes());
+        for (set, header) in self.subsets.iter() {
+            bytes.extend_from_slice(&set.canonical_bytes());
+            bytes.extend_from_slice(&header.to_bytes());
+        }
+        bytes
+    }
+}
+
+/// Multi-header at the start of `bytes`, whose subsets must be disjoint, and the rest.
+fn take_multi_header<E: PairingEngine>(
+    bytes: &[u8],
+) -> Result<(MultiHeader<E>, &[u8]), SerializationError> {
+    let header_size =
+        E::G1Projective::zero().serialized_size() + E::G2Projective::zero().serialized_size();
+    let (count, mut bytes) = take_u64(bytes)?;
+    let mut subsets = Vec::new();
+    for _ in 0..count {
+        let (set, rest) = take_set(bytes)?;
+        let (header, rest) = take(rest, header_size)?;
+        subsets.push((set, from_bytes(header)?));
+        bytes = rest;
+    }
+    if overlap(subsets.iter().map(|(set, _)| set)).is_some() {
+        return Err(SerializationError::InvalidData);
+    }
+    Ok((MultiHeader { subsets }, bytes))
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for MultiHeader<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        match take_multi_header(bytes)? {
+            (header, []) => Ok(header),
+            _ => Err(SerializationError::InvalidData),
+        }
+    }
+}
+
+/// Smallest identifier in more than one of `sets`.
+fn overlap<'s, I: Iterator<Item = &'s RecipientSet>>(sets: I) -> Option<usize> {
+    let mut identifiers: Vec<usize> = sets.flat_map(|set| set.iter()).collect();
+    identifiers.sort_unstable();
+    identifiers
+        .windows(2)
+        .find(|pair| pair[0] == pair[1])
+        .map(|pair| pair[0])
+}
+
+/// Key of subset `index`, with set `set`, derived from the key of its header.
+fn subset_key<E: PairingEngine>(key: &E::Fqk, index: usize, set: &RecipientSet) -> [u8; 32] {
+    let mut info = MULTI_KEY_INFO.to_vec();
+    info.extend_from_slice(&(index as u64).to_le_bytes());
+    info.extend_from_slice(&set.canonical_bytes());
+    let mut subset_key = [0u8; 32];
+    // 32 bytes are within the output of HKDF
+    derive_key::<E, HkdfSha256>(key, &info, &mut subset_key).unwrap();
+    subset_key
+}
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Encrypt a header for each of `subsets`, returning them with the keys of the subsets, in
+    /// the same order. Fails if there are no subsets, if a participant is in two of them, or if a
+    /// subset cannot be encrypted for.
+    pub fn encrypt_multi<R>(
+        &self,
+        subsets: &[RecipientSet],
+        rng: &mut R,
+    ) -> Result<(MultiHeader<E>, Vec<[u8; 32]>), MultiHeaderError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if subsets.is_empty() {
+            return Err(MultiHeaderError::NoSubsets);
+        }
+        if let Some(identifier) = overlap(subsets.iter()) {
+            return Err(MultiHeaderError::Overlap(identifier));
+        }
+
+        let mut headers = Vec::with_capacity(subsets.len());
+        let mut keys = Vec::with_capacity(subsets.len());
+        for (index, set) in subsets.iter().enumerate() {
+            let (header, key) = self
+                .encrypt(set.as_slice(), rng)
+                .map_err(MultiHeaderError::Encrypt)?;
+            keys.push(subset_key::<E>(key.key(), index, set));
+            headers.push((set.clone(), header));
+        }
+        Ok((MultiHeader { subsets: headers }, keys))
+    }
+}
+
+impl<E: PairingEngine> Recipient<E> {
+    /// Position of the subset of `header` the recipient is in, and the key of that subset.
+    /// Fails if the recipient is in none of them.
+    pub fn decrypt_multi(
+        &self,
+        channel: &BroadcastChannel<E>,
+        header: &MultiHeader<E>,
+    ) -> Result<(usize, [u8; 32]), MultiHeaderError> {
+        let index = header
+            .position(self.identifier)
+            .ok_or(MultiHeaderError::NotInSubsets)?;
+        let (set, subset_header) = &header.subsets[index];
+        let key = self
+            .decrypt(set.as_slice(), channel, subset_header)
+            .map_err(MultiHeaderError::Decrypt)?;
+        Ok((index, subset_key::<E>(key.key(), index, set)))
+    }
+}
+
+#[cfg(feature = "chacha20poly1305")]
+pub use self::shared::{open_multi, seal_multi, MultiEnvelope};
+
+#[cfg(feature = "chacha20poly1305")]
+mod shared {
+    use alloc::vec::Vec;
+    use core::convert::TryFrom;
+
+    use ark_ec::PairingEngine;
+    use ark_serialize::SerializationError;
+    use chacha20poly1305::aead::{Aead, NewAead, Payload};
+    use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+    use rand_core::{CryptoRng, RngCore};
+    use zeroize::Zeroize;
+
+    use super::{take_multi_header, MultiHeader};
+    use crate::error::MultiHeaderError;
+    use crate::set::RecipientSet;
+    use crate::wire::{take, take_u64};
+    use crate::{BroadcastChannel, Recipient};
+
+    /// Length of a sealed content key.
+    const WRAPPED_KEY_SIZE: usize = 32 + 16;
+
+    /// Multi-header, content key sealed for each subset, and payload sealed once.
+    #[derive(Clone, PartialEq, Eq)]
+    pub struct MultiEnvelope<E: PairingEngine> {
+        header: MultiHeader<E>,
+        wrapped_keys: Vec<Vec<u8>>,
+        ciphertext: Vec<u8>,
+    }
+
+    impl<E: PairingEngine> MultiEnvelope<E> {
+        /// Headers of the subsets.
+        pub fn header(&self) -> &MultiHeader<E> {
+            &self.header
+        }
+
+        /// Sealed payload, 16 bytes longer than the plaintext.
+        pub fn ciphertext(&self) -> &[u8] {
+            &self.ciphertext
+        }
+
+        /// Encoding of the multi-header and of the sealed content keys, which is the associated
+        /// data of the payload.
+        fn authenticated_bytes(&self) -> Vec<u8> {
+            let mut bytes = self.header.to_bytes();
+            for wrapped in self.wrapped_keys.iter() {
+                bytes.extend_from_slice(wrapped);
+            }
+            bytes
+        }
+
+        /// Encoding of the envelope: the multi-header, the content key sealed for each subset,
+        /// in order, then the length of the ciphertext as a little-endian `u64` and the
+        /// ciphertext.
+        pub fn to_bytes(&self) -> Vec<u8> {
+            let mut bytes = self.authenticated_bytes();
+            bytes.extend_from_slice(&(self.ciphertext.len() as u64).to_le_bytes());
+            bytes.extend_from_slice(&self.ciphertext);
+            bytes
+        }
+    }
+
+    impl<E: PairingEngine> TryFrom<&[u8]> for MultiEnvelope<E> {
+        type Error = SerializationError;
+
+        fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+            let (header, mut bytes) = take_multi_header(bytes)?;
+            let mut wrapped_keys = Vec::with_capacity(header.len());
+            for _ in 0..header.len() {
+                let (wrapped, rest) = take(bytes, WRAPPED_KEY_SIZE)?;
+                wrapped_keys.push(wrapped.to_vec());
+                bytes = rest;
+            }
+            let (length, ciphertext) = take_u64(bytes)?;
+            if length != ciphertext.len() as u64 {
+                return Err(SerializationError::InvalidData);
+            }
+            Ok(MultiEnvelope {
+                header,
+                wrapped_keys,
+                ciphertext: ciphertext.to_vec(),
+            })
+        }
+    }
+
+    /// Seal `msg` under `key`, which seals a single message, so that the nonce is zero.
+    fn seal_once(key: &[u8; 32], msg: &[u8], aad: &[u8]) -> Vec<u8> {
+        // sealing only fails beyond 256 GiB, far more than fits in a slice
+        ChaCha20Poly1305::new(Key::from_slice(key))
+            .encrypt(Nonce::from_slice(&[0u8; 12]), Payload { msg, aad })
+            .expect("payload too long for ChaCha20-Poly1305")
+    }
+
+    /// Open `msg`, sealed by [`seal_once`].
+    fn open_once(key: &[u8; 32], msg: &[u8], aad: &[u8]) -> Result<Vec<u8>, MultiHeaderError> {
+        ChaCha20Poly1305::new(Key::from_slice(key))
+            .decrypt(Nonce::from_slice(&[0u8; 12]), Payload { msg, aad })
+            .map_err(|_| MultiHeaderError::Authentication)
+    }
+
+    /// Seal `plaintext` once for every subset of `subsets` of `channel`.
+    pub fn seal_multi<E, R>(
+        channel: &BroadcastChannel<E>,
+        subsets: &[RecipientSet],
+        plaintext: &[u8],
+        rng: &mut R,
+    ) -> Result<MultiEnvelope<E>, MultiHeaderError>
+    where
+        E: PairingEngine,
+        R: RngCore + CryptoRng,
+    {
+        let (header, mut keys) = channel.encrypt_multi(subsets, rng)?;
+        let mut content_key = [0u8; 32];
+        rng.fill_bytes(&mut content_key);
+
+        let header_bytes = header.to_bytes();
+        let wrapped_keys = keys
+            .iter()
+            .map(|key| seal_once(key, &content_key, &header_bytes))
+            .collect();
+        keys.zeroize();
+        let mut envelope = MultiEnvelope {
+            header,
+            wrapped_keys,
+            ciphertext: Vec::new(),
+        };
+        envelope.ciphertext = seal_once(&content_key, plaintext, &envelope.authenticated_bytes());
+        content_key.zeroize();
+        Ok(envelope)
+    }
+
+    /// Payload of `envelope`, opened by `recipient` with the parameters `channel`.
+    pub fn open_multi<E: PairingEngine>(
+        recipient: &Recipient<E>,
+        channel: &BroadcastChannel<E>,
+        envelope: &MultiEnvelope<E>,
+    ) -> Result<Vec<u8>, MultiHeaderError> {
+        let (index, mut key) = recipient.decrypt_multi(channel, &envelope.header)?;
+        let wrapped = open_once(
+            &key,
+            &envelope.wrapped_keys[index],
+            &envelope.header.to_bytes(),
+        );
+        key.zeroize();
+        let mut unwrapped = wrapped?;
+        if unwrapped.len() != 32 {
+            unwrapped.zeroize();
+            return Err(MultiHeaderError::Authentication);
+        }
+        let mut content_key = [0u8; 32];
+        content_key.copy_from_slice(&unwrapped);
+        unwrapped.zeroize();
+        let plaintext = open_once(
+            &content_key,
+            &envelope.ciphertext,
+            &envelope.authenticated_bytes(),
+        );
+        content_key.zeroize();
+        plaintext
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::EncryptError;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn each_subset_gets_its_own_key() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(6, &mut rng).unwrap();
+        let tiers = [
+            RecipientSet::new(&[1, 2]),
+            RecipientSet::new(&[3]),
+            RecipientSet::new(&[4, 6]),
+        ];
+        let (header, keys) = channel.encrypt_multi(&tiers, &mut rng).unwrap();
+        assert_eq!(header.len(), 3);
+        assert!(keys[0] != keys[1] && keys[1] != keys[2]);
+
+        let decoded = MultiHeader::<Bls12_381>::try_from(header.to_bytes().as_slice()).unwrap();
+        assert!(decoded == header);
+        for (identifier, tier) in [(1, 0), (2, 0), (3, 1), (4, 2), (6, 2)].iter() {
+            assert_eq!(
+                participants[identifier - 1].decrypt_multi(&channel, &decoded),
+                Ok((*tier, keys[*tier]))
+            );
+        }
+        assert_eq!(
+            participants[4].decrypt_multi(&channel, &header),
+            Err(MultiHeaderError::NotInSubsets)
+        );
+
+        let overlapping = [RecipientSet::new(&[1, 2]), RecipientSet::new(&[2, 3])];
+        assert_eq!(
+            channel.encrypt_multi(&overlapping, &mut rng).err(),
+            Some(MultiHeaderError::Overlap(2))
+        );
+        assert_eq!(
+            channel.encrypt_multi(&[], &mut rng).err(),
+            Some(MultiHeaderError::NoSubsets)
+        );
+        assert_eq!(
+            channel
+                .encrypt_multi(&[RecipientSet::new(&[7])], &mut rng)
+                .err(),
+            Some(MultiHeaderError::Encrypt(
+                EncryptError::IdentifierOutOfRange(7)
+            ))
+        );
+        let mut bytes = header.to_bytes();
+        bytes.push(0);
+        assert!(MultiHeader::<Bls12_381>::try_from(bytes.as_slice()).is_err());
+    }
+
+    #[cfg(feature = "chacha20poly1305")]
+    #[test]
+    fn one_payload_is_sealed_for_every_subset() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(5, &mut rng).unwrap();
+        let subsets = [RecipientSet::new(&[1, 4]), RecipientSet::new(&[2])];
+        let envelope = seal_multi(&channel, &subsets, b"match highlights", &mut rng).unwrap();
+        assert_eq!(envelope.ciphertext().len(), 16 + 16);
+
+        let bytes = envelope.to_bytes();
+        let decoded = MultiEnvelope::<Bls12_381>::try_from(bytes.as_slice()).unwrap();
+        assert!(decoded == envelope);
+        for identifier in [1, 2, 4].iter() {
+            assert_eq!(
+                open_multi(&participants[identifier - 1], &channel, &decoded),
+                Ok(b"match highlights".to_vec())
+            );
+        }
+        assert_eq!(
+            open_multi(&participants[2], &channel, &decoded),
+            Err(MultiHeaderError::NotInSubsets)
+        );
+
+        // the sealed content keys are authenticated with the payload
+        let mut tampered = bytes.clone();
+        let wrapped = bytes.len() - (8 + 32) - 1;
+        tampered[wrapped] ^= 1;
+        let tampered = MultiEnvelope::<Bls12_381>::try_from(tampered.as_slice()).unwrap();
+        assert_eq!(
+            open_multi(&participants[1], &channel, &tampered),
+            Err(MultiHeaderError::Authentication)
+        );
+        assert!(MultiEnvelope::<Bls12_381>::try_from(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
@@ -0,0 +1,302 @@
+//! Public parameters read by ranges from an object store.
+//!
+//! Parameters of hundreds of megabytes do not belong in service images. [`ObjectParams`] reads
+//! the compressed encoding of a channel, as written by
+//! [`BroadcastChannel::to_bytes`](crate::BroadcastChannel::to_bytes) and uploaded as a single
+//! object, and only fetches the bytes of the points [`crate::store::encrypt`] and
+//! [`crate::Recipient::decrypt_from_store`] ask for, with one ranged read per contiguous range.
+//! Opening it reads the lengths at the start of the object, the one before the G2 vector and the
+//! state of the channel after the points, its policy and retired keys, and nothing else; points
+//! go through the checked deserialization as they are read.
+//! [`ObjectParams::channel`] fetches the whole object for the services that keep the state of
+//! the channel, such as the broadcaster issuing keys.
+//!
+//! An object is anything implementing [`RangedObject`]. [`SignedUrl`] reads an object over HTTPS
+//! from a presigned URL of S3 or a signed URL of GCS, which both serve ranges of bytes, so that
+//! the crate holds no cloud credentials: the service that holds them signs the URLs. Vectors of
+//! bytes are objects as well, for tests and local caches.
+
+use std::collections::BTreeSet;
+use std::convert::{Infallible, TryFrom};
+use std::io::{self, Read};
+use std::marker::PhantomData;
+use std::ops::Range;
+
+use ark_ec::PairingEngine;
+use ark_serialize::CanonicalDeserialize;
+
+use crate::encoding::{deserialize_state, STATE_PREFIX_SIZE};
+use crate::error::ObjectStoreError;
+use crate::layout::{length_at, Layout, G1_START, LENGTH_SIZE};
+use crate::policy::Policy;
+use crate::store::ParamStore;
+use crate::BroadcastChannel;
+
+/// Object of a store, read by ranges of bytes.
+pub trait RangedObject {
+    /// Error returned when the object cannot be read
+    type Error;
+
+    /// Bytes of the object at `range`, fewer if the object ends before.
+    fn read(&self, range: Range<u64>) -> Result<Vec<u8>, Self::Error>;
+}
+
+impl RangedObject for Vec<u8> {
+    type Error = Infallible;
+
+    fn read(&self, range: Range<u64>) -> Result<Vec<u8>, Infallible> {
+        let clamp = |offset: u64| {
+            usize::try_from(offset)
+                .unwrap_or(usize::MAX)
+                .min(self.len())
+        };
+        let start = clamp(range.start);
+        Ok(self[start..clamp(range.end).max(start)].to_vec())
+    }
+}
+
+/// Object behind a URL that needs no further authentication, such as a presigned URL of S3 or a
+/// signed URL of GCS.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignedUrl {
+    url: String,
+}
+
+impl SignedUrl {
+    /// Object at `url`.
+    pub fn new<U: Into<String>>(url: U) -> Self {
+        SignedUrl { url: url.into() }
+    }
+
+    /// URL of the object.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+impl RangedObject for SignedUrl {
+    type Error = io::Error;
+
+    fn read(&self, range: Range<u64>) -> io::Result<Vec<u8>> {
+        if range.start >= range.end {
+            return Ok(Vec::new());
+        }
+        let response = ureq::get(&self.url)
+            .set("Range", &format!("bytes={}-{}", range.start, range.end - 1))
+            .call()
+            .map_err(|error| io::Error::new(io::ErrorKind::Other, error))?;
+        // a server ignoring the range would send the whole object
+        if response.status() != 206 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "range of the object not served",
+            ));
+        }
+
+        let mut bytes = Vec::new();
+        response
+            .into_reader()
+            .take(range.end - range.start)
+            .read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+/// Parameters of a channel encoded in an object.
+pub struct ObjectParams<E: PairingEngine, O> {
+    object: O,
+    layout: Layout,
+    /// size of the whole encoding
+    end: usize,
+    policy: Policy,
+    retired: BTreeSet<usize>,
+    _engine: PhantomData<E>,
+}
+
+/// Bytes of `object` at `range`, which must all be there.
+fn read<O: RangedObject>(
+    object: &O,
+    range: Range<usize>,
+) -> Result<Vec<u8>, ObjectStoreError<O::Error>> {
+    let bytes = object
+        .read(range.start as u64..range.end as u64)
+        .map_err(ObjectStoreError::Object)?;
+    if bytes.len() != range.len() {
+        return Err(ObjectStoreError::Malformed);
+    }
+    Ok(bytes)
+}
+
+impl<E: PairingEngine, O: RangedObject> ObjectParams<E, O> {
+    /// Parameters of the channel encoded in `object`. Fails if the object cannot be read, if its
+    /// lengths do not match the number of participants it announces, or if its state is
+    /// malformed.
+    pub fn open(object: O) -> Result<Self, ObjectStoreError<O::Error>> {
+        let malformed = |_| ObjectStoreError::Malformed;
+        let start = read(&object, 0..G1_START)?;
+        let number_participants = length_at(&start, 0).map_err(malformed)?;
+        let layout = Layout::new::<E>(number_participants).ok_or(ObjectStoreError::Malformed)?;
+        let issued = length_at(&start, LENGTH_SIZE).map_err(malformed)?;
+        if issued > number_participants
+            || length_at(&start, 2 * LENGTH_SIZE).map_err(malformed)? != layout.g1_length()
+        {
+            return Err(ObjectStoreError::Malformed);
+        }
+
+        let g2_length = read(
+            &object,
+            layout.g2_length_start..layout.g2_length_start + LENGTH_SIZE,
+        )?;
+        if length_at(&g2_length, 0).map_err(malformed)? != layout.g2_length() {
+            return Err(ObjectStoreError::Malformed);
+        }
+
+        // the number of retired keys ends the fixed part of the state, and gives its size
+        let prefix_end = layout.state_start + STATE_PREFIX_SIZE;
+        let mut state = read(&object, layout.state_start..prefix_end)?;
+        let retired = length_at(&state, STATE_PREFIX_SIZE - LENGTH_SIZE).map_err(malformed)?;
+        if retired > issued {
+            return Err(ObjectStoreError::Malformed);
+        }
+        let end = prefix_end + retired * LENGTH_SIZE;
+        if end > prefix_end {
+            state.extend_from_slice(&read(&object, prefix_end..end)?);
+        }
+        let state = deserialize_state(state.as_slice(), issued).map_err(malformed)?;
+
+        Ok(ObjectParams {
+            object,
+            layout,
+            end,
+            policy: state.policy,
+            retired: state.retired,
+            _engine: PhantomData,
+        })
+    }
+
+    /// Object the parameters are read from.
+    pub fn object(&self) -> &O {
+        &self.object
+    }
+
+    /// Whole channel, fetched in a single read and decoded.
+    pub fn channel(&self) -> Result<BroadcastChannel<E>, ObjectStoreError<O::Error>> {
+        let bytes = read(&self.object, 0..self.end)?;
+        BroadcastChannel::try_from(bytes.as_slice()).map_err(|_| ObjectStoreError::Malformed)
+    }
+
+    /// Points at `bytes` of the object, or an error if the range of positions was not in the
+    /// vector.
+    fn points<T: CanonicalDeserialize>(
+        &self,
+        bytes: Option<Range<usize>>,
+        count: usize,
+    ) -> Result<Vec<T>, ObjectStoreError<O::Error>> {
+        let bytes = read(&self.object, bytes.ok_or(ObjectStoreError::Malformed)?)?;
+        let mut bytes = bytes.as_slice();
+        (0..count)
+            .map(|_| T::deserialize(&mut bytes).map_err(|_| ObjectStoreError::Malformed))
+            .collect()
+    }
+}
+
+impl<E: PairingEngine, O: RangedObject> ParamStore<E> for ObjectParams<E, O> {
+    type Error = ObjectStoreError<O::Error>;
+
+    fn number_participants(&self) -> usize {
+        self.layout.number_participants
+    }
+
+    fn g1_points(&self, range: Range<usize>) -> Result<Vec<E::G1Affine>, Self::Error> {
+        self.points(self.layout.g1_bytes(&range), range.len())
+    }
+
+    fn g2_points(&self, range: Range<usize>) -> Result<Vec<E::G2Affine>, Self::Error> {
+        self.points(self.layout.g2_bytes(&range), range.len())
+    }
+
+    fn point_v(&self) -> Result<E::G1Projective, Self::Error> {
+        let start = self.layout.point_v_start;
+        let bytes = read(&self.object, start..self.layout.state_start)?;
+        E::G1Projective::deserialize(bytes.as_slice()).map_err(|_| ObjectStoreError::Malformed)
+    }
+
+    fn policy(&self) -> Policy {
+        self.policy
+    }
+
+    fn is_retired(&self, identifier: usize) -> bool {
+        self.retired.contains(&identifier)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::encrypt;
+    use crate::BroadcastChannel;
+    use ark_bls12_381::Bls12_381;
+    use core::cell::Cell;
+    use rand::thread_rng;
+
+    /// Object counting the bytes read from it.
+    struct Counted {
+        bytes: Vec<u8>,
+        read: Cell<usize>,
+    }
+
+    impl RangedObject for Counted {
+        type Error = Infallible;
+
+        fn read(&self, range: Range<u64>) -> Result<Vec<u8>, Infallible> {
+            let bytes = self.bytes.read(range)?;
+            self.read.set(self.read.get() + bytes.len());
+            Ok(bytes)
+        }
+    }
+
+    #[test]
+    fn object_round_trip() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(64, &mut rng).unwrap();
+        let encoded = channel.to_bytes();
+        let object = Counted {
+            bytes: encoded.clone(),
+            read: Cell::new(0),
+        };
+
+        let params = ObjectParams::<Bls12_381, _>::open(object).unwrap();
+        assert_eq!(params.number_participants(), 64);
+        let (header, key) = encrypt(&params, &[2, 40], &mut rng).unwrap();
+        assert_eq!(
+            participants[39]
+                .decrypt_from_store(&[2, 40], &params, &header)
+                .ok(),
+            Some(key.clone())
+        );
+        assert_eq!(
+            participants[1].decrypt(&[2, 40], &channel, &header),
+            Ok(key.clone())
+        );
+        assert!(params.object().read.get() * 10 < encoded.len());
+        assert!(params.g2_points(60..66).is_err());
+        assert_eq!(
+            params.channel().unwrap().view().fingerprint(),
+            channel.view().fingerprint()
+        );
+
+        // the lengths and the state are checked when opening, the points when reading them
+        let layout = Layout::new::<Bls12_381>(64).unwrap();
+        let mut corrupted = encoded.clone();
+        for byte in corrupted[layout.point_v_start..layout.state_start].iter_mut() {
+            *byte = 0xff;
+        }
+        let params = ObjectParams::<Bls12_381, _>::open(corrupted).unwrap();
+        assert_eq!(params.point_v(), Err(ObjectStoreError::Malformed));
+        let mut truncated = encoded.clone();
+        truncated.pop();
+        assert!(ObjectParams::<Bls12_381, _>::open(truncated).is_err());
+        assert!(ObjectParams::<Bls12_381, _>::open(encoded[..100].to_vec()).is_err());
+    }
+}
@@ -0,0 +1,128 @@
+//! Length-hiding padding of payloads.
+//!
+//! The symmetric encryption of a payload under the key of a header reveals the length of the
+//! payload, and lengths alone can tell broadcasts apart. Payloads are padded before being sealed:
+//! a `0x80` byte marks the end of the message, followed by zeros up to the length chosen by the
+//! [`Padding`] scheme, as in ISO/IEC 7816-4. [`unpad`] removes it after opening, whatever the
+//! scheme.
+//!
+//! [`Padding::Buckets`] rounds up to one of a few fixed sizes, hiding everything but the bucket.
+//! [`Padding::Padme`] is the scheme of Nikitin et al., "Reducing Metadata Leakage from Encrypted
+//! Files and Communication with PURBs", which leaks `O(log log L)` bits of a length `L` for an
+//! overhead of at most 12%.
+
+use alloc::vec::Vec;
+
+/// Marker of the end of the message.
+const MARKER: u8 = 0x80;
+
+/// Scheme choosing the padded length of payloads.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Padding {
+    /// Only the end marker, the length is not hidden
+    Exact,
+    /// Smallest of the sizes, in increasing order, that fits the payload. Longer payloads are
+    /// padded to a multiple of the largest size
+    Buckets(Vec<usize>),
+    /// PADMÉ
+    #[default]
+    Padme,
+}
+
+impl Padding {
+    /// Padding to the bucket sizes in `sizes`, in any order. Zero sizes are ignored, and without
+    /// any other size this is the same as [`Padding::Exact`].
+    pub fn buckets(sizes: &[usize]) -> Self {
+        let mut sizes: Vec<usize> = sizes.iter().copied().filter(|size| *size > 0).collect();
+        sizes.sort_unstable();
+        sizes.dedup();
+        if sizes.is_empty() {
+            return Padding::Exact;
+        }
+        Padding::Buckets(sizes)
+    }
+
+    /// Length of a payload of `length` bytes once padded.
+    pub fn padded_len(&self, length: usize) -> usize {
+        let length = length + 1;
+        match self {
+            Padding::Exact => length,
+            Padding::Buckets(sizes) => match sizes.iter().find(|size| **size >= length) {
+                Some(size) => *size,
+                None => match sizes.last() {
+                    Some(largest) => {
+                        (length / largest + usize::from(length % largest != 0)) * largest
+                    }
+                    None => length,
+                },
+            },
+            Padding::Padme => padme(length),
+        }
+    }
+
+    /// Pad `message`.
+    pub fn pad(&self, message: &[u8]) -> Vec<u8> {
+        let length = self.padded_len(message.len());
+        let mut padded = Vec::with_capacity(length);
+        padded.extend_from_slice(message);
+        padded.push(MARKER);
+        padded.resize(length, 0);
+        padded
+    }
+}
+
+/// Message padded into `padded` by any scheme, `None` if the padding is malformed.
+pub fn unpad(padded: &[u8]) -> Option<&[u8]> {
+    let end = padded.iter().rposition(|byte| *byte != 0)?;
+    if padded[end] != MARKER {
+        return None;
+    }
+    Some(&padded[..end])
+}
+
+/// PADMÉ length of `length`: its lowest bits are cleared, keeping as many significant bits as the
+/// bit length of its exponent.
+fn padme(length: usize) -> usize {
+    if length < 2 {
+        return length;
+    }
+    let exponent = (usize::BITS - 1 - length.leading_zeros()) as usize;
+    let significant = (usize::BITS - exponent.leading_zeros()) as usize;
+    let mask = (1usize << (exponent - significant)) - 1;
+    (length + mask) & !mask
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paddings_round_trip() {
+        let message = b"a broadcast of some length";
+        for padding in [
+            Padding::Exact,
+            Padding::buckets(&[256, 64, 0]),
+            Padding::Padme,
+        ]
+        .iter()
+        {
+            let padded = padding.pad(message);
+            assert_eq!(padded.len(), padding.padded_len(message.len()));
+            assert_eq!(unpad(&padded), Some(&message[..]));
+            assert_eq!(unpad(&padding.pad(b"")), Some(&b""[..]));
+        }
+
+        let buckets = Padding::buckets(&[256, 64]);
+        assert_eq!(buckets.padded_len(10), 64);
+        assert_eq!(buckets.padded_len(64), 256);
+        assert_eq!(buckets.padded_len(600), 768);
+        assert_eq!(Padding::buckets(&[0]), Padding::Exact);
+
+        assert_eq!(Padding::Padme.padded_len(8), 10);
+        assert_eq!(Padding::Padme.padded_len(1000), 1024);
+        assert_eq!(Padding::Padme.padded_len(1_000_000), 1_015_808);
+
+        assert_eq!(unpad(&[1, 2, 0]), None);
+        assert_eq!(unpad(&[0, 0]), None);
+    }
+}
@@ -0,0 +1,104 @@
+//! Setup on a pool of threads.
+//!
+//! Once the powers of the secret are known in the scalar field, every point of the setup and
+//! every key is an independent multiplication of a generator. With the `parallel` feature these
+//! multiplications, and the derivation of the scalars of the keys, are spread over the threads of
+//! a [`rayon`] pool. The powers of the secret themselves are computed one after the other.
+//!
+//! The aggregate of a decryption is summed in chunks of [`AGGREGATION_CHUNK`] recipients on the
+//! same pool, so that decrypting for sets of hundreds of thousands of recipients scales with the
+//! cores. Each chunk is summed with the mixed additions of the sequential decryption: the affine
+//! points of arkworks hide their coordinates behind the generic traits, so there is no addition
+//! of affine points batching the inversions of a chunk.
+
+use ark_ec::PairingEngine;
+use ark_ff::UniformRand;
+use rand_core::{CryptoRng, RngCore};
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
+
+use crate::error::SetupError;
+use crate::secret::SecretSetup;
+use crate::BroadcastChannel;
+
+/// Number of recipients of a set whose points are summed on one thread, in a decryption.
+pub const AGGREGATION_CHUNK: usize = 4096;
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Same as [`BroadcastChannel::init_with_secret`], computed on a pool of `threads` threads,
+    /// or of one thread per CPU if `threads` is zero. Fails if `n` is zero or if the threads
+    /// cannot be started.
+    pub fn init_with_threads<R>(
+        n: usize,
+        threads: usize,
+        rng: &mut R,
+    ) -> Result<SecretSetup<E>, SetupError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if n == 0 {
+            return Err(SetupError::NoParticipants);
+        }
+
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .map_err(|_| SetupError::ThreadPool)?;
+        // drawn here, in the same order as the sequential setup, so the generator need not be
+        // shared with the pool
+        let alpha = E::Fr::rand(rng);
+        let gamma = E::Fr::rand(rng);
+        Ok(pool.install(|| Self::setup(n, alpha, gamma)))
+    }
+
+    /// Same as [`BroadcastChannel::decryption_sum`], with chunks of `chunk` elements of the set
+    /// summed on the threads of the pool.
+    pub(crate) fn chunked_decryption_sum(
+        &self,
+        set_recipients: &[usize],
+        identifier: usize,
+        chunk: usize,
+    ) -> E::G1Projective {
+        set_recipients
+            .par_chunks(chunk)
+            .map(|chunk| self.decryption_sum(chunk.iter().copied(), identifier))
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn threads_do_not_change_the_setup() {
+        let (channel, participants, _) =
+            BroadcastChannel::<Bls12_381>::init_with_secret(6, &mut StdRng::seed_from_u64(7))
+                .unwrap();
+        let (pooled, pooled_participants, _) =
+            BroadcastChannel::<Bls12_381>::init_with_threads(6, 3, &mut StdRng::seed_from_u64(7))
+                .unwrap();
+        assert_eq!(pooled.view().fingerprint(), channel.view().fingerprint());
+        let (header, key) = channel.encrypt(&[2, 5], &mut rand::thread_rng()).unwrap();
+        assert_eq!(
+            pooled_participants[4].decrypt(&[2, 5], &pooled, &header),
+            Ok(key.clone())
+        );
+        assert_eq!(
+            participants[4].decrypt(&[2, 5], &channel, &header),
+            Ok(key.clone())
+        );
+
+        // chunks holding the term of the recipient or not add up to the sequential sum
+        let set = [6, 1, 3, 5, 2];
+        for chunk in 1..=set.len() {
+            assert!(
+                channel.chunked_decryption_sum(&set, 5, chunk)
+                    == channel.decryption_sum(set.iter().copied(), 5)
+            );
+        }
+    }
+}
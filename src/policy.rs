@@ -0,0 +1,98 @@
+//! Policies enforced by the encryption of a channel.
+//!
+//! The trusted party may attach a [`Policy`] to a channel. It is part of the public parameters:
+//! it is written in their encoding and covered by their fingerprint, so every broadcaster loading
+//! them sees the same limits, and [`BroadcastChannel::encrypt`] refuses sets that break them.
+
+use ark_ec::PairingEngine;
+
+use crate::error::EncryptError;
+use crate::BroadcastChannel;
+
+/// Limits on the encryptions of a channel. The default policy places no limit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Policy {
+    max_set_size: Option<usize>,
+}
+
+impl Policy {
+    /// Copy of this policy allowing at most `limit` identifiers per encryption.
+    pub fn with_max_set_size(self, limit: usize) -> Self {
+        Policy {
+            max_set_size: Some(limit),
+        }
+    }
+
+    /// Maximum number of identifiers per encryption, if limited.
+    pub fn max_set_size(&self) -> Option<usize> {
+        self.max_set_size
+    }
+
+    /// Whether this policy places no limit.
+    pub fn is_unrestricted(&self) -> bool {
+        *self == Policy::default()
+    }
+
+    /// Check that an encryption for `set_recipients` is allowed.
+    pub fn check(&self, set_recipients: &[usize]) -> Result<(), EncryptError> {
+        self.check_size(set_recipients.len())
+    }
+
+    /// Same as [`Policy::check`], for a set of `size` identifiers.
+    pub(crate) fn check_size(&self, size: usize) -> Result<(), EncryptError> {
+        match self.max_set_size {
+            Some(limit) if size > limit => Err(EncryptError::SetTooLarge { size, limit }),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Attach `policy` to the channel, replacing the previous one. This is done by the trusted
+    /// party before publishing the parameters, as the policy is part of their encoding and of
+    /// their fingerprint.
+    pub fn set_policy(&mut self, policy: Policy) {
+        self.policy = policy;
+    }
+
+    /// Policy enforced by the encryption.
+    pub fn policy(&self) -> &Policy {
+        &self.policy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use core::convert::TryFrom;
+    use rand::thread_rng;
+
+    #[test]
+    fn policy_is_enforced() {
+        let mut rng = thread_rng();
+        let (mut channel, _) =
+            BroadcastChannel::<Bls12_381>::init_participants(5, &mut rng).unwrap();
+        assert!(channel.policy().is_unrestricted());
+        assert!(channel.encrypt(&[1, 2, 3, 4, 5], &mut rng).is_ok());
+
+        let fingerprint = channel.view().fingerprint();
+        channel.set_policy(Policy::default().with_max_set_size(2));
+        assert_ne!(channel.view().fingerprint(), fingerprint);
+        assert_eq!(channel.view().policy().max_set_size(), Some(2));
+
+        assert!(channel.encrypt(&[1, 4], &mut rng).is_ok());
+        assert!(matches!(
+            channel.encrypt(&[1, 2, 4], &mut rng),
+            Err(EncryptError::SetTooLarge { size: 3, limit: 2 })
+        ));
+
+        let decoded = BroadcastChannel::<Bls12_381>::try_from(channel.to_bytes().as_slice());
+        let decoded = decoded.unwrap();
+        assert_eq!(decoded.policy().max_set_size(), Some(2));
+        assert!(matches!(
+            decoded.encrypt(&[1, 2, 4], &mut rng),
+            Err(EncryptError::SetTooLarge { size: 3, limit: 2 })
+        ));
+    }
+}
@@ -0,0 +1,182 @@
+//! Decryption of many headers for the same set.
+//!
+//! Besides the two Miller loops, a decryption sums the points of the other recipients and prepares
+//! the public key for the pairing, and neither depends on the header. A [`PreparedDecryptor`]
+//! does both once, along with the checks of the set, and then decrypts any number of headers for
+//! that set at the cost of the pairing alone. Each decryption still counts against the usage
+//! budget of the recipient and is reported to the event sink of the channel.
+//!
+//! [`Recipient::decrypt_batch`] prepares a decryptor and runs it over a backlog of headers. With
+//! the `parallel` feature the headers are decrypted on the global [`rayon`] pool.
+
+use alloc::vec::Vec;
+
+use ark_ec::{PairingEngine, ProjectiveCurve};
+use ark_ff::Zero;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::error::DecryptError;
+use crate::events::Event;
+use crate::{BroadcastChannel, Header, Recipient, SharedKey};
+
+/// Decryptions by one recipient of headers for one set, borrowing the parameters of the channel.
+pub struct PreparedDecryptor<'a, E: PairingEngine> {
+    channel: &'a BroadcastChannel<E>,
+    recipient: Recipient<E>,
+    set_recipients: Vec<usize>,
+    /// public key of the recipient, prepared as the second input of the first pairing
+    public_key: E::G2Prepared,
+    /// opposite of the private key plus the aggregate, prepared as the first input of the second
+    /// pairing
+    second: E::G1Prepared,
+}
+
+impl<E: PairingEngine> Recipient<E> {
+    /// Decryptor of headers encrypted for `set_recipients`. Fails if the set contains identifiers
+    /// outside of the channel or more than once, or does not contain this recipient.
+    pub fn prepare<'a>(
+        &self,
+        set_recipients: &[usize],
+        channel: &'a BroadcastChannel<E>,
+    ) -> Result<PreparedDecryptor<'a, E>, DecryptError> {
+        if let Err(error) = channel.check_decryption_set(self.identifier, set_recipients) {
+            channel.emit(Event::DecryptFailed {
+                identifier: self.identifier,
+                reason: error,
+            });
+            return Err(error);
+        }
+
+        let aggregate = channel.decryption_aggregate(set_recipients, self.identifier);
+        let second = -(self.key_pair.private_key + aggregate);
+        Ok(PreparedDecryptor {
+            channel,
+            recipient: self.clone(),
+            set_recipients: set_recipients.to_vec(),
+            public_key: self.key_pair.public_key.into_affine().into(),
+            second: second.into_affine().into(),
+        })
+    }
+
+    /// Keys of `headers`, all encrypted for `set_recipients`, in the same order. Fails if the set
+    /// is refused as by [`Recipient::prepare`]; otherwise every header is decrypted on its own
+    /// and a failed one does not stop the others.
+    pub fn decrypt_batch(
+        &self,
+        set_recipients: &[usize],
+        channel: &BroadcastChannel<E>,
+        headers: &[Header<E>],
+    ) -> Result<Vec<Result<SharedKey<E>, DecryptError>>, DecryptError> {
+        let decryptor = self.prepare(set_recipients, channel)?;
+        #[cfg(feature = "parallel")]
+        let headers = headers.par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let headers = headers.iter();
+        Ok(headers
+            .map(|header| decryptor.decrypt_header(header))
+            .collect())
+    }
+}
+
+impl<'a, E: PairingEngine> PreparedDecryptor<'a, E> {
+    /// Same as [`Recipient::decrypt`], for a header encrypted for the set of the decryptor.
+    pub fn decrypt_header(&self, header: &Header<E>) -> Result<SharedKey<E>, DecryptError> {
+        let key = self.check(header).map(|()| {
+            let pairs = [
+                (header.ctx_0.into_affine().into(), self.public_key.clone()),
+                (self.second.clone(), header.ctx_1.into_affine().into()),
+            ];
+            E::product_of_pairings(pairs.iter())
+        })?;
+
+        if let Err(error) =
+            self.recipient
+                .confirm_decryption(&self.set_recipients, self.channel, header, &key)
+        {
+            self.fail(error);
+            return Err(error);
+        }
+        Ok(SharedKey::new(key))
+    }
+
+    fn check(&self, header: &Header<E>) -> Result<(), DecryptError> {
+        let checked = if header.ctx_0.is_zero() || header.ctx_1.is_zero() {
+            Err(DecryptError::IdentityPoint)
+        } else {
+            // last, so that only decryptions that go ahead are counted
+            self.recipient.consume_use()
+        };
+        if let Err(error) = checked {
+            self.fail(error);
+        }
+        checked
+    }
+
+    fn fail(&self, reason: DecryptError) {
+        self.channel.emit(Event::DecryptFailed {
+            identifier: self.recipient.identifier,
+            reason,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn prepared_decryptions_match() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(5, &mut rng).unwrap();
+        let decryptor = participants[3].prepare(&[2, 4, 5], &channel).unwrap();
+        for _ in 0..3 {
+            let (header, key) = channel.encrypt(&[2, 4, 5], &mut rng).unwrap();
+            assert_eq!(decryptor.decrypt_header(&header), Ok(key));
+        }
+
+        let limited = participants[3].clone().with_usage_budget(1);
+        let decryptor = limited.prepare(&[4, 5], &channel).unwrap();
+        let (header, key) = channel.encrypt(&[4, 5], &mut rng).unwrap();
+        assert_eq!(decryptor.decrypt_header(&header), Ok(key));
+        assert_eq!(
+            decryptor.decrypt_header(&header),
+            Err(DecryptError::BudgetExhausted)
+        );
+
+        assert!(matches!(
+            participants[0].prepare(&[2, 4], &channel),
+            Err(DecryptError::NotInSet)
+        ));
+    }
+
+    #[test]
+    fn batch_decryptions_keep_order() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(4, &mut rng).unwrap();
+        let (mut headers, keys): (Vec<_>, Vec<_>) = (0..4)
+            .map(|_| channel.encrypt(&[1, 3], &mut rng).unwrap())
+            .unzip();
+        headers[2].ctx_1 = Zero::zero();
+
+        let decrypted = participants[2]
+            .decrypt_batch(&[1, 3], &channel, &headers)
+            .unwrap();
+        assert_eq!(decrypted.len(), 4);
+        for (index, key) in keys.iter().enumerate() {
+            if index == 2 {
+                assert_eq!(decrypted[index], Err(DecryptError::IdentityPoint));
+            } else {
+                assert_eq!(decrypted[index], Ok(key.clone()));
+            }
+        }
+        assert!(participants[1]
+            .decrypt_batch(&[1, 3], &channel, &headers)
+            .is_err());
+    }
+}
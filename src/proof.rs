@@ -0,0 +1,236 @@
+//! Proofs that a key was correctly decrypted.
+//!
+//! The key of a header recovered by participant `i` is `K = e(ctx_0, Q_i) / e(d_i + A, ctx_1)`,
+//! with `A` the sum of the points of the other recipients. Nobody but `i` can recompute it, so
+//! a participant claiming a key, for billing or in a dispute, has nothing to show for it. With
+//! [`Recipient::decrypt_with_proof`] the participant also proves, without revealing `d_i`, that
+//! the same point `d_i` satisfies both
+//!
+//! - `e(d_i, Q) = e(V, Q_i)`, so it is the private key of the public key `Q_i` of the channel,
+//! - `e(d_i, ctx_1) = e(ctx_0, Q_i) / (K e(A, ctx_1))`, so `K` is its decryption of the header.
+//!
+//! The [`DecryptionProof`] is a Chaum-Pedersen proof of equality for the map
+//! `X -> (e(X, Q), e(X, ctx_1))` from G1, made non-interactive with the Fiat-Shamir transform. The
+//! prover draws `U` in G1, the challenge `c` hashes the statement and the commitments
+//! `(e(U, Q), e(U, ctx_1))`, and the response is `Z = U + c d_i`. The proof is `(c, Z)`: the
+//! verifier recomputes the commitments as `e(Z, Q) / e(V, Q_i)^c` and
+//! `e(Z, ctx_1) / T^c`, with `T` the right-hand side of the second equation, and checks that they
+//! hash to `c`. Verifying costs five pairings, and only takes the public parameters.
+//!
+//! A proof is encoded as the challenge followed by the response.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use ark_ec::{PairingEngine, ProjectiveCurve};
+use ark_ff::{Field, PrimeField, UniformRand};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+use crate::encoding::{from_bytes, to_bytes};
+use crate::error::DecryptError;
+use crate::hash::hash_to_scalar;
+use crate::set::RecipientSet;
+use crate::{BroadcastChannel, Header, Recipient, SharedKey};
+
+/// Domain of the challenges of the proofs.
+const PROOF_DOMAIN: &[u8] = b"DGS-BE-DECRYPTION-PROOF-V1";
+
+/// Proof that a key is the decryption of a header by the holder of a public key of the channel.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct DecryptionProof<E: PairingEngine> {
+    /// challenge `c`
+    challenge: E::Fr,
+    /// response `U + c d_i`
+    response: E::G1Projective,
+}
+
+/// Statement proved for the key `key` of `header`, decrypted by `identifier` for
+/// `set_recipients`: its encoding, the base in G2 of the second equation and the targets of both.
+struct Statement<E: PairingEngine> {
+    context: Vec<u8>,
+    second_base: E::G2Projective,
+    key_target: E::Fqk,
+    decryption_target: E::Fqk,
+}
+
+impl<E: PairingEngine> Statement<E> {
+    fn new(
+        channel: &BroadcastChannel<E>,
+        identifier: usize,
+        set_recipients: &[usize],
+        header: &Header<E>,
+        key: &E::Fqk,
+    ) -> Option<Self> {
+        let public_key = channel.broadcaster_pk_g2[identifier];
+        let aggregate = channel.decryption_aggregate(set_recipients, identifier);
+        let key_target = E::pairing(channel.point_v, public_key);
+        // e(ctx_0, Q_i) / (K e(A, ctx_1))
+        let decryption_target = E::pairing(header.ctx_0, public_key)
+            * (*key * E::pairing(aggregate, header.ctx_1)).inverse()?;
+
+        let mut context = channel.view().fingerprint().as_ref().to_vec();
+        context.extend_from_slice(&(identifier as u64).to_le_bytes());
+        context.extend_from_slice(&RecipientSet::new(set_recipients).canonical_bytes());
+        context.extend_from_slice(&header.to_bytes());
+        context.extend_from_slice(&to_bytes(key));
+        Some(Statement {
+            context,
+            second_base: header.ctx_1,
+            key_target,
+            decryption_target,
+        })
+    }
+
+    /// Challenge of the commitments `first` and `second` for the statement.
+    fn challenge(&self, first: &E::Fqk, second: &E::Fqk) -> E::Fr {
+        let mut message = self.context.clone();
+        message.extend_from_slice(&to_bytes(first));
+        message.extend_from_slice(&to_bytes(second));
+        hash_to_scalar::<E>(PROOF_DOMAIN, &message)
+    }
+}
+
+impl<E: PairingEngine> Recipient<E> {
+    /// Same as [`Recipient::decrypt`], with a proof that the key is the decryption of `header`
+    /// by the holder of the public key of this recipient.
+    pub fn decrypt_with_proof<R>(
+        &self,
+        set_recipients: &[usize],
+        channel: &BroadcastChannel<E>,
+        header: &Header<E>,
+        rng: &mut R,
+    ) -> Result<(SharedKey<E>, DecryptionProof<E>), DecryptError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let key = self.decrypt(set_recipients, channel, header)?;
+        // the key of a decryption is never zero
+        let statement = Statement::new(channel, self.identifier, set_recipients, header, key.key())
+            .ok_or(DecryptError::IdentityPoint)?;
+
+        let mut nonce = E::Fr::rand(rng);
+        let mut commitment = E::G1Projective::prime_subgroup_generator();
+        commitment *= nonce;
+        nonce.zeroize();
+        let first = E::pairing(commitment, E::G2Projective::prime_subgroup_generator());
+        let second = E::pairing(commitment, statement.second_base);
+        let challenge = statement.challenge(&first, &second);
+
+        let mut response = self.key_pair.private_key;
+        response *= challenge;
+        response += commitment;
+        commitment.zeroize();
+        Ok((
+            key,
+            DecryptionProof {
+                challenge,
+                response,
+            },
+        ))
+    }
+}
+
+impl<E: PairingEngine> DecryptionProof<E> {
+    /// Whether the proof shows that `key` is the decryption of `header`, encrypted for
+    /// `set_recipients`, by the holder of the public key of `identifier` in `channel`.
+    pub fn verify(
+        &self,
+        identifier: usize,
+        set_recipients: &[usize],
+        channel: &BroadcastChannel<E>,
+        header: &Header<E>,
+        key: &SharedKey<E>,
+    ) -> bool {
+        if channel
+            .check_decryption(identifier, set_recipients, header)
+            .is_err()
+        {
+            return false;
+        }
+        let statement = match Statement::new(channel, identifier, set_recipients, header, key.key())
+        {
+            Some(statement) => statement,
+            None => return false,
+        };
+
+        let exponent = self.challenge.into_repr();
+        let first = E::pairing(self.response, E::G2Projective::prime_subgroup_generator())
+            * statement.key_target.pow(exponent).inverse().unwrap();
+        let second = E::pairing(self.response, statement.second_base)
+            * statement.decryption_target.pow(exponent).inverse().unwrap();
+        statement.challenge(&first, &second) == self.challenge
+    }
+
+    /// Compressed encoding of the proof.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+}
+
+impl<E: PairingEngine> CanonicalSerialize for DecryptionProof<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.challenge.serialize(&mut writer)?;
+        self.response.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.challenge.serialized_size() + self.response.serialized_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for DecryptionProof<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let challenge = E::Fr::deserialize(&mut reader)?;
+        let response = E::G1Projective::deserialize(&mut reader)?;
+        Ok(DecryptionProof {
+            challenge,
+            response,
+        })
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for DecryptionProof<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn decryptions_are_proved() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(4, &mut rng).unwrap();
+        let set = [1, 2, 4];
+        let (header, key) = channel.encrypt(&set, &mut rng).unwrap();
+
+        let (decrypted, proof) = participants[1]
+            .decrypt_with_proof(&set, &channel, &header, &mut rng)
+            .unwrap();
+        assert_eq!(decrypted, key);
+        let proof = DecryptionProof::try_from(proof.to_bytes().as_slice()).unwrap();
+        assert!(proof.verify(2, &set, &channel, &header, &key));
+
+        assert!(!proof.verify(4, &set, &channel, &header, &key));
+        assert!(!proof.verify(2, &[1, 2], &channel, &header, &key));
+        assert!(!proof.verify(
+            2,
+            &set,
+            &channel,
+            &header,
+            &SharedKey::new(*key.key() * key.key())
+        ));
+        let (other, _) = channel.encrypt(&set, &mut rng).unwrap();
+        assert!(!proof.verify(2, &set, &channel, &other, &key));
+        assert!(!proof.verify(3, &set, &channel, &header, &key));
+    }
+}
@@ -0,0 +1,342 @@
+//! Setup of channels too large to hold every private key at once.
+//!
+//! [`BroadcastChannel::init_participants`] returns every participant in a vector, next to the
+//! full public parameters. A dealer provisioning millions of participants sets the channel up
+//! with [`BroadcastChannel::init_streaming`] instead, which only draws the secret. The public
+//! parameters are then written out point by point, in the encoding of [`BroadcastChannel`], and
+//! the participants are derived one at a time as they are handed out, each from the previous
+//! one. The memory used does not grow with the number of participants.
+//!
+//! A setup that may be interrupted writes the parameters a few points at a time with
+//! [`StreamingSetup::write_parameters_part`] and hands out participants with
+//! [`StreamingSetup::next_recipient`], taking a [`StreamingSetup::checkpoint`] now and then. The
+//! checkpoint records how far both have gone, and [`BroadcastChannel::resume_setup`] continues
+//! from there, after the output has been truncated to the length it had when the checkpoint was
+//! taken. The checkpoint contains the [`BroadcasterSecret`] and must be stored as carefully.
+
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use ark_ec::{PairingEngine, ProjectiveCurve};
+use ark_ff::{Field, UniformRand, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError, Write};
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+use crate::encoding::{from_bytes, serialize_state, to_bytes};
+use crate::error::SetupError;
+use crate::policy::Policy;
+use crate::secret::BroadcasterSecret;
+use crate::{BroadcastChannel, KeyPair, Recipient};
+
+/// Secret of a channel whose parameters and participants are produced on demand.
+pub struct StreamingSetup<E: PairingEngine> {
+    number_participants: usize,
+    secret: BroadcasterSecret<E>,
+    /// points of the parameters written by `write_parameters_part`
+    parameters_written: usize,
+    /// participants handed out by `next_recipient`
+    recipients_issued: usize,
+}
+
+/// Participants of a [`StreamingSetup`], in order of identifier.
+pub struct RecipientStream<'a, E: PairingEngine> {
+    setup: &'a StreamingSetup<E>,
+    /// identifier of the next participant
+    identifier: usize,
+    /// private key of the previous participant, `V` before the first one
+    private_key: E::G1Projective,
+    /// public key of the previous participant, the generator before the first one
+    public_key: E::G2Projective,
+}
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Same as [`BroadcastChannel::init_with_secret`], without computing anything but the
+    /// secret. Fails if `n` is zero.
+    pub fn init_streaming<R>(n: usize, rng: &mut R) -> Result<StreamingSetup<E>, SetupError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if n == 0 {
+            return Err(SetupError::NoParticipants);
+        }
+
+        // drawn in the same order as the other setups
+        let alpha = E::Fr::rand(rng);
+        let gamma = E::Fr::rand(rng);
+        Ok(StreamingSetup {
+            number_participants: n,
+            secret: BroadcasterSecret { alpha, gamma },
+            parameters_written: 0,
+            recipients_issued: 0,
+        })
+    }
+
+    /// Setup continuing from `checkpoint`, taken with [`StreamingSetup::checkpoint`].
+    pub fn resume_setup(checkpoint: &[u8]) -> Result<StreamingSetup<E>, SerializationError> {
+        let mut bytes = checkpoint;
+        let number_participants = usize::deserialize(&mut bytes)?;
+        let parameters_written = usize::deserialize(&mut bytes)?;
+        let recipients_issued = usize::deserialize(&mut bytes)?;
+        let secret = from_bytes(bytes)?;
+
+        let setup = StreamingSetup {
+            number_participants,
+            secret,
+            parameters_written,
+            recipients_issued,
+        };
+        if number_participants == 0
+            || parameters_written > setup.parameter_points()
+            || recipients_issued > number_participants
+        {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(setup)
+    }
+}
+
+impl<E: PairingEngine> StreamingSetup<E> {
+    /// Number of participants of the channel.
+    pub fn number_participants(&self) -> usize {
+        self.number_participants
+    }
+
+    /// Secret of the channel, with which [`BroadcastChannel::add_participant`] grows it later on.
+    pub fn secret(&self) -> &BroadcasterSecret<E> {
+        &self.secret
+    }
+
+    /// Write the public parameters to `writer`, in the compressed encoding of
+    /// [`BroadcastChannel`], with every key issued.
+    pub fn write_parameters<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        self.write_points(0..self.parameter_points(), writer)
+    }
+
+    /// Write at most `count` more points of the parameters to `writer`, after those written by
+    /// the previous calls. Returns the number of points written, which is zero once the
+    /// parameters are complete.
+    pub fn write_parameters_part<W: Write>(
+        &mut self,
+        count: usize,
+        writer: W,
+    ) -> Result<usize, SerializationError> {
+        let start = self.parameters_written;
+        let end = self.parameter_points().min(start.saturating_add(count));
+        self.write_points(start..end, writer)?;
+        self.parameters_written = end;
+        Ok(end - start)
+    }
+
+    /// Next participant, after those handed out by the previous calls.
+    pub fn next_recipient(&mut self) -> Option<Recipient<E>> {
+        if self.recipients_issued == self.number_participants {
+            return None;
+        }
+
+        self.recipients_issued += 1;
+        let identifier = self.recipients_issued;
+        let mut power = self.secret.alpha.pow([identifier as u64]);
+        let mut public_key = E::G2Projective::prime_subgroup_generator();
+        public_key *= power;
+        power *= self.secret.gamma;
+        let mut private_key = E::G1Projective::prime_subgroup_generator();
+        private_key *= power;
+        power.zeroize();
+
+        Some(Recipient {
+            identifier,
+            key_pair: KeyPair {
+                public_key,
+                private_key,
+            },
+            budget: None,
+        })
+    }
+
+    /// Encoding of the progress of [`StreamingSetup::write_parameters_part`] and
+    /// [`StreamingSetup::next_recipient`], together with the secret.
+    pub fn checkpoint(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        // serializing into a vector cannot fail
+        self.number_participants.serialize(&mut bytes).unwrap();
+        self.parameters_written.serialize(&mut bytes).unwrap();
+        self.recipients_issued.serialize(&mut bytes).unwrap();
+        bytes.extend_from_slice(&to_bytes(&self.secret));
+        bytes
+    }
+
+    /// Number of points in the parameters: 2n + 1 in G1, n + 1 in G2, and `V`.
+    fn parameter_points(&self) -> usize {
+        3 * self.number_participants + 3
+    }
+
+    /// Write the points of the parameters at `positions`, in the order of the encoding, with the
+    /// lengths of the vectors before their first point and the state of the channel after `V`.
+    fn write_points<W: Write>(
+        &self,
+        positions: Range<usize>,
+        mut writer: W,
+    ) -> Result<(), SerializationError> {
+        let n = self.number_participants;
+        let alpha = self.secret.alpha;
+        let g2_start = 2 * n + 1;
+        // multiples of the generators by the power of alpha of the current position, computed
+        // at the first position written and then updated incrementally
+        let mut g1_point = None;
+        let mut g2_point = None;
+
+        for position in positions {
+            if position == 0 {
+                n.serialize(&mut writer)?;
+                n.serialize(&mut writer)?;
+                (2 * n + 1).serialize(&mut writer)?;
+            }
+
+            if position < g2_start {
+                let point = g1_point.get_or_insert_with(|| {
+                    let mut point = E::G1Projective::prime_subgroup_generator();
+                    point *= alpha.pow([position as u64]);
+                    point
+                });
+                // the point at position n + 1 is withheld, as in the other setups
+                if position == n + 1 {
+                    E::G1Affine::zero().serialize(&mut writer)?;
+                } else {
+                    point.into_affine().serialize(&mut writer)?;
+                }
+                *point *= alpha;
+            } else if position < g2_start + n + 1 {
+                if position == g2_start {
+                    (n + 1).serialize(&mut writer)?;
+                }
+                let point = g2_point.get_or_insert_with(|| {
+                    let mut point = E::G2Projective::prime_subgroup_generator();
+                    point *= alpha.pow([(position - g2_start) as u64]);
+                    point
+                });
+                point.into_affine().serialize(&mut writer)?;
+                *point *= alpha;
+            } else {
+                let mut point_v = E::G1Projective::prime_subgroup_generator();
+                point_v *= self.secret.gamma;
+                point_v.serialize(&mut writer)?;
+                // a new channel has no policy, no retired key, and is in epoch zero
+                serialize_state(&Policy::default(), 0, &BTreeSet::new(), &mut writer)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Participants of the channel, derived as they are read.
+    pub fn recipients(&self) -> RecipientStream<'_, E> {
+        let mut private_key = E::G1Projective::prime_subgroup_generator();
+        private_key *= self.secret.gamma;
+        RecipientStream {
+            setup: self,
+            identifier: 1,
+            private_key,
+            public_key: E::G2Projective::prime_subgroup_generator(),
+        }
+    }
+}
+
+impl<'a, E: PairingEngine> Iterator for RecipientStream<'a, E> {
+    type Item = Recipient<E>;
+
+    fn next(&mut self) -> Option<Recipient<E>> {
+        if self.identifier > self.setup.number_participants {
+            return None;
+        }
+
+        self.private_key *= self.setup.secret.alpha;
+        self.public_key *= self.setup.secret.alpha;
+        let recipient = Recipient {
+            identifier: self.identifier,
+            key_pair: KeyPair {
+                public_key: self.public_key,
+                private_key: self.private_key,
+            },
+            budget: None,
+        };
+        self.identifier += 1;
+        Some(recipient)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.setup.number_participants + 1 - self.identifier;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a, E: PairingEngine> ExactSizeIterator for RecipientStream<'a, E> {}
+
+impl<'a, E: PairingEngine> Drop for RecipientStream<'a, E> {
+    fn drop(&mut self) {
+        self.private_key.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn streaming_matches_setup() {
+        let (channel, participants, _) =
+            BroadcastChannel::<Bls12_381>::init_with_secret(5, &mut StdRng::seed_from_u64(3))
+                .unwrap();
+        let setup = BroadcastChannel::<Bls12_381>::init_streaming(5, &mut StdRng::seed_from_u64(3))
+            .unwrap();
+
+        let mut parameters = Vec::new();
+        setup.write_parameters(&mut parameters).unwrap();
+        assert_eq!(parameters, channel.to_bytes());
+        let streamed = BroadcastChannel::<Bls12_381>::try_from(parameters.as_slice()).unwrap();
+        assert!(streamed.validate().is_valid());
+
+        let recipients = setup.recipients();
+        assert_eq!(recipients.len(), 5);
+        for (recipient, participant) in recipients.zip(participants.iter()) {
+            assert_eq!(recipient.to_bytes(), participant.to_bytes());
+        }
+    }
+
+    #[test]
+    fn interrupted_setup_resumes() {
+        let (channel, participants, _) =
+            BroadcastChannel::<Bls12_381>::init_with_secret(4, &mut StdRng::seed_from_u64(5))
+                .unwrap();
+        let mut setup =
+            BroadcastChannel::<Bls12_381>::init_streaming(4, &mut StdRng::seed_from_u64(5))
+                .unwrap();
+
+        let mut parameters = Vec::new();
+        assert_eq!(setup.write_parameters_part(6, &mut parameters).unwrap(), 6);
+        let first = setup.next_recipient().unwrap();
+        let checkpoint = setup.checkpoint();
+        let length = parameters.len();
+        // lost when the setup is interrupted
+        setup.write_parameters_part(3, &mut parameters).unwrap();
+        drop(setup);
+
+        parameters.truncate(length);
+        let mut setup = BroadcastChannel::<Bls12_381>::resume_setup(&checkpoint).unwrap();
+        while setup.write_parameters_part(4, &mut parameters).unwrap() > 0 {}
+        assert_eq!(parameters, channel.to_bytes());
+
+        let mut recipients = vec![first];
+        recipients.extend(std::iter::from_fn(|| setup.next_recipient()));
+        assert_eq!(recipients.len(), 4);
+        for (recipient, participant) in recipients.iter().zip(participants.iter()) {
+            assert_eq!(recipient.to_bytes(), participant.to_bytes());
+        }
+
+        assert!(BroadcastChannel::<Bls12_381>::resume_setup(&checkpoint[1..]).is_err());
+    }
+}
@@ -0,0 +1,246 @@
+//! Issuance of key shares under the approval of a quorum of administrators.
+//!
+//! A [`KeyDealer`] can produce the share of any participant at any time, which makes re-issuing a
+//! key as easy as issuing it. Deployments that want a second pair of eyes on every issuance
+//! register the verifying keys of their administrators in an [`AdminQuorum`], with a threshold.
+//! Each issuance is described by an [`IssuanceRequest`], naming the channel, the participant and a
+//! serial number distinguishing successive issuances of the same key, and the administrators
+//! approve it by signing it. [`KeyDealer::issue_approved`] checks the approvals before computing
+//! anything, and reports every approval it counted, followed by the issuance, to the event sink of
+//! the channel.
+
+use alloc::vec::Vec;
+
+use ark_ec::{AffineCurve, PairingEngine};
+
+use crate::error::IssuanceError;
+use crate::events::Event;
+use crate::signature::{Signature, SigningKey, VerifyingKey};
+use crate::split::{KeyDealer, KeyShare};
+use crate::view::Fingerprint;
+use crate::BroadcastChannel;
+
+/// Domain separation tag of the messages signed by approvals.
+const REQUEST_TAG: &[u8] = b"DGS-BE-ISSUANCE-REQUEST-V1";
+
+/// Administrators of a deployment, of which `threshold` must approve every issuance.
+#[derive(Clone, Debug)]
+pub struct AdminQuorum<E: PairingEngine> {
+    administrators: Vec<VerifyingKey<E>>,
+    threshold: usize,
+}
+
+impl<E: PairingEngine> AdminQuorum<E> {
+    /// Quorum of `threshold` out of `administrators`. Returns `None` if the threshold is zero or
+    /// exceeds the number of administrators.
+    pub fn new(administrators: Vec<VerifyingKey<E>>, threshold: usize) -> Option<Self> {
+        if threshold == 0 || threshold > administrators.len() {
+            return None;
+        }
+        Some(AdminQuorum {
+            administrators,
+            threshold,
+        })
+    }
+
+    /// Number of approvals required.
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// Positions of the administrators with a valid approval of `request` in `approvals`, each
+    /// counted once, in increasing order.
+    pub fn approvers(&self, request: &IssuanceRequest, approvals: &[Approval<E>]) -> Vec<usize> {
+        let message = request.message();
+        let mut approvers: Vec<usize> = approvals
+            .iter()
+            .filter(|approval| {
+                self.administrators
+                    .get(approval.administrator)
+                    .is_some_and(|key| key.verify(&message, &approval.signature))
+            })
+            .map(|approval| approval.administrator)
+            .collect();
+        approvers.sort_unstable();
+        approvers.dedup();
+        approvers
+    }
+}
+
+/// Issuance, or re-issuance, of the key of one participant of one channel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IssuanceRequest {
+    fingerprint: Fingerprint,
+    identifier: usize,
+    serial: u64,
+}
+
+impl IssuanceRequest {
+    /// Request for issuance number `serial` of the key of participant `identifier` of the channel
+    /// with fingerprint `fingerprint`.
+    pub fn new(fingerprint: Fingerprint, identifier: usize, serial: u64) -> Self {
+        IssuanceRequest {
+            fingerprint,
+            identifier,
+            serial,
+        }
+    }
+
+    /// Participant whose key is requested.
+    pub fn identifier(&self) -> usize {
+        self.identifier
+    }
+
+    /// Serial number of the issuance.
+    pub fn serial(&self) -> u64 {
+        self.serial
+    }
+
+    /// Approval of the request by administrator `administrator`, holding `key`.
+    pub fn approve<E: PairingEngine>(
+        &self,
+        administrator: usize,
+        key: &SigningKey<E>,
+    ) -> Approval<E> {
+        Approval {
+            administrator,
+            signature: key.sign(&self.message()),
+        }
+    }
+
+    fn message(&self) -> Vec<u8> {
+        let mut message = REQUEST_TAG.to_vec();
+        message.extend_from_slice(self.fingerprint.as_ref());
+        message.extend_from_slice(&(self.identifier as u64).to_le_bytes());
+        message.extend_from_slice(&self.serial.to_le_bytes());
+        message
+    }
+}
+
+/// Signature of a request by the administrator at position `administrator` of the quorum.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Approval<E: PairingEngine> {
+    administrator: usize,
+    signature: Signature<E>,
+}
+
+impl<E: PairingEngine> KeyDealer<E> {
+    /// Share of the key requested by `request`, for `channel`, if `approvals` meet the threshold
+    /// of `quorum`.
+    pub fn issue_approved(
+        &self,
+        channel: &BroadcastChannel<E>,
+        request: &IssuanceRequest,
+        approvals: &[Approval<E>],
+        quorum: &AdminQuorum<E>,
+    ) -> Result<KeyShare<E>, IssuanceError> {
+        if request.fingerprint != channel.view().fingerprint()
+            || request.identifier == 0
+            || request.identifier > channel.number_participants
+        {
+            return Err(IssuanceError::UnknownRecipient(request.identifier));
+        }
+        let approvers = quorum.approvers(request, approvals);
+        if approvers.len() < quorum.threshold {
+            return Err(IssuanceError::InsufficientApprovals {
+                valid: approvers.len(),
+                threshold: quorum.threshold,
+            });
+        }
+
+        for administrator in approvers {
+            channel.emit(Event::IssuanceApproved {
+                identifier: request.identifier,
+                serial: request.serial,
+                administrator,
+            });
+        }
+        channel.emit(Event::KeyIssued {
+            identifier: request.identifier,
+        });
+        Ok(self.issue_point(
+            channel.broadcaster_pk_g1[request.identifier].into_projective(),
+            request.identifier,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::EventSink;
+    use crate::split::SrsTranscript;
+    use crate::Recipient;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct Log(Mutex<Vec<Event>>);
+
+    impl EventSink for Log {
+        fn record(&self, event: &Event) {
+            self.0.lock().unwrap().push(*event);
+        }
+    }
+
+    #[test]
+    fn issuance_requires_a_quorum() {
+        let mut rng = thread_rng();
+        let dealer = KeyDealer::<Bls12_381>::new(&mut rng);
+        let mut channel =
+            BroadcastChannel::from_split(SrsTranscript::new(3, &mut rng), &[dealer.point_v()]);
+        let log = Arc::new(Log::default());
+        channel.set_event_sink(log.clone());
+
+        let admins: Vec<SigningKey<Bls12_381>> =
+            (0..3).map(|_| SigningKey::generate(&mut rng)).collect();
+        let quorum =
+            AdminQuorum::new(admins.iter().map(|key| key.verifying_key()).collect(), 2).unwrap();
+        assert!(AdminQuorum::<Bls12_381>::new(Vec::new(), 1).is_none());
+
+        let request = IssuanceRequest::new(channel.view().fingerprint(), 2, 1);
+        let other = IssuanceRequest::new(channel.view().fingerprint(), 2, 2);
+        let approvals = [
+            request.approve(0, &admins[0]),
+            request.approve(0, &admins[0]),
+            other.approve(2, &admins[2]),
+            request.approve(1, &admins[2]),
+        ];
+        assert_eq!(
+            dealer
+                .issue_approved(&channel, &request, &approvals, &quorum)
+                .err(),
+            Some(IssuanceError::InsufficientApprovals {
+                valid: 1,
+                threshold: 2
+            })
+        );
+        assert!(log.0.lock().unwrap().is_empty());
+
+        let approvals = [
+            request.approve(2, &admins[2]),
+            request.approve(0, &admins[0]),
+        ];
+        let share = dealer
+            .issue_approved(&channel, &request, &approvals, &quorum)
+            .unwrap();
+        assert!(Recipient::combine(&channel, 2, &[share]).is_some());
+        assert_eq!(
+            *log.0.lock().unwrap(),
+            vec![
+                Event::IssuanceApproved {
+                    identifier: 2,
+                    serial: 1,
+                    administrator: 0
+                },
+                Event::IssuanceApproved {
+                    identifier: 2,
+                    serial: 1,
+                    administrator: 2
+                },
+                Event::KeyIssued { identifier: 2 },
+            ]
+        );
+    }
+}
@@ -0,0 +1,230 @@
+//! Headers transformed from one set of recipients to another, keeping their key.
+//!
+//! The G1 component of a header for `S` is `k (gamma + sum_{j in S} alpha^(n+1-j)) P`, and its
+//! key only depends on `k`. Multiplying that component by the ratio `f(S') / f(S)` of these
+//! polynomials turns the header into one for `S'` with the same key, so content archived under
+//! the key is re-shared with a new set without decrypting the header or the content. The G2
+//! component is left as is.
+//!
+//! The broadcaster computes the ratio from its [`BroadcasterSecret`], and re-encrypts directly
+//! with [`BroadcastChannel::reencrypt`], or hands a proxy a [`ReEncryptionToken`] for one pair of
+//! sets. The proxy learns neither the key nor the secret from its token, and transforms any
+//! header for the first set into one for the second. A header for another set becomes garbage
+//! for everyone, which the proxy cannot detect.
+//!
+//! Each token relates `alpha` and `gamma` by one polynomial equation: two tokens of the same
+//! channel give a polynomial in `alpha` alone, whose roots reveal both. A proxy holding more than
+//! one token of a channel must thus be trusted as much as the broadcaster itself. The recipients
+//! of the first set keep reading the transformed header only if they are also in the second.
+//!
+//! A token is encoded as the canonical encodings of its two sets, as byte vectors, followed by
+//! the ratio. It is zeroized when dropped.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use ark_ec::PairingEngine;
+use ark_ff::{Field, One, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::encoding::{from_bytes, to_bytes};
+use crate::error::{EncryptError, ReEncryptionError};
+use crate::secret::BroadcasterSecret;
+use crate::set::RecipientSet;
+use crate::{BroadcastChannel, Header};
+
+/// Right, for a proxy, to turn headers for one set into headers for another with the same key.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ReEncryptionToken<E: PairingEngine> {
+    from: RecipientSet,
+    to: RecipientSet,
+    factor: E::Fr,
+}
+
+impl<E: PairingEngine> ReEncryptionToken<E> {
+    /// Token of `channel` turning headers for `from` into headers for `to`. Fails if `secret` is
+    /// not the one the channel was set up with, if `from` is empty or has identifiers outside of
+    /// the channel, or if `to` cannot be encrypted for, as for [`BroadcastChannel::encrypt`].
+    pub fn issue(
+        secret: &BroadcasterSecret<E>,
+        channel: &BroadcastChannel<E>,
+        from: &[usize],
+        to: &[usize],
+    ) -> Result<Self, ReEncryptionError> {
+        if !secret.belongs_to(channel) {
+            return Err(ReEncryptionError::WrongSecret);
+        }
+        let n = channel.number_participants;
+        if let Some(identifier) = from
+            .iter()
+            .find(|identifier| **identifier == 0 || **identifier > n)
+        {
+            return Err(ReEncryptionError::Source(
+                EncryptError::IdentifierOutOfRange(*identifier),
+            ));
+        }
+        let from = RecipientSet::new(from);
+        if from.is_empty() {
+            return Err(ReEncryptionError::Source(EncryptError::EmptySet));
+        }
+        channel
+            .check_encryption_set(to)
+            .map_err(ReEncryptionError::Encrypt)?;
+        let to = RecipientSet::new(to);
+
+        // powers[i] = alpha^i
+        let mut powers = vec![E::Fr::one(); n + 1];
+        for i in 1..=n {
+            powers[i] = powers[i - 1] * secret.alpha;
+        }
+        let evaluate = |set: &RecipientSet| {
+            set.iter().fold(secret.gamma, |sum, identifier| {
+                sum + powers[n + 1 - identifier]
+            })
+        };
+        // the polynomial of `from` only vanishes with negligible probability
+        let factor = evaluate(&to)
+            * evaluate(&from)
+                .inverse()
+                .ok_or(ReEncryptionError::Degenerate)?;
+        powers.zeroize();
+
+        Ok(ReEncryptionToken { from, to, factor })
+    }
+
+    /// Set of the headers the token transforms.
+    pub fn from(&self) -> &RecipientSet {
+        &self.from
+    }
+
+    /// Set of the transformed headers.
+    pub fn to(&self) -> &RecipientSet {
+        &self.to
+    }
+
+    /// Header for the target set with the key of `header`, which must be for the source set.
+    pub fn reencrypt(&self, header: &Header<E>) -> Header<E> {
+        let mut ctx_0 = header.ctx_0;
+        ctx_0 *= self.factor;
+        Header {
+            ctx_0,
+            ctx_1: header.ctx_1,
+        }
+    }
+
+    /// Compressed encoding of the token.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+}
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Header for `to` with the key of `header`, which must be for `from`. Fails as
+    /// [`ReEncryptionToken::issue`].
+    pub fn reencrypt(
+        &self,
+        secret: &BroadcasterSecret<E>,
+        header: &Header<E>,
+        from: &[usize],
+        to: &[usize],
+    ) -> Result<Header<E>, ReEncryptionError> {
+        Ok(ReEncryptionToken::issue(secret, self, from, to)?.reencrypt(header))
+    }
+}
+
+impl<E: PairingEngine> Drop for ReEncryptionToken<E> {
+    fn drop(&mut self) {
+        self.factor.zeroize();
+    }
+}
+
+impl<E: PairingEngine> ZeroizeOnDrop for ReEncryptionToken<E> {}
+
+impl<E: PairingEngine> CanonicalSerialize for ReEncryptionToken<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.from.canonical_bytes().serialize(&mut writer)?;
+        self.to.canonical_bytes().serialize(&mut writer)?;
+        self.factor.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.from.canonical_bytes().serialized_size()
+            + self.to.canonical_bytes().serialized_size()
+            + self.factor.serialized_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for ReEncryptionToken<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let from = RecipientSet::try_from(Vec::<u8>::deserialize(&mut reader)?.as_slice())?;
+        let to = RecipientSet::try_from(Vec::<u8>::deserialize(&mut reader)?.as_slice())?;
+        let factor = E::Fr::deserialize(&mut reader)?;
+        if factor.is_zero() {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(ReEncryptionToken { from, to, factor })
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for ReEncryptionToken<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn headers_are_reencrypted_with_their_key() {
+        let mut rng = thread_rng();
+        let (channel, participants, secret) =
+            BroadcastChannel::<Bls12_381>::init_with_secret(5, &mut rng).unwrap();
+        let (header, key) = channel.encrypt(&[1, 2], &mut rng).unwrap();
+
+        let token = ReEncryptionToken::issue(&secret, &channel, &[2, 1], &[3, 4, 5]).unwrap();
+        let token = ReEncryptionToken::try_from(token.to_bytes().as_slice()).unwrap();
+        assert_eq!(token.from().as_slice(), &[1, 2]);
+        let shared = token.reencrypt(&header);
+        assert_eq!(
+            participants[3].decrypt(&[3, 4, 5], &channel, &shared),
+            Ok(key.clone())
+        );
+        assert!(participants[0].decrypt(&[1, 3, 4, 5], &channel, &shared) != Ok(key.clone()));
+
+        let direct = channel
+            .reencrypt(&secret, &header, &[1, 2], &[2, 5])
+            .unwrap();
+        assert_eq!(
+            participants[1].decrypt(&[2, 5], &channel, &direct),
+            Ok(key.clone())
+        );
+        assert_eq!(
+            participants[4].decrypt(&[2, 5], &channel, &direct),
+            Ok(key.clone())
+        );
+
+        let (_, _, other) = BroadcastChannel::<Bls12_381>::init_with_secret(5, &mut rng).unwrap();
+        assert_eq!(
+            channel.reencrypt(&other, &header, &[1, 2], &[3]).err(),
+            Some(ReEncryptionError::WrongSecret)
+        );
+        assert_eq!(
+            channel.reencrypt(&secret, &header, &[1, 6], &[3]).err(),
+            Some(ReEncryptionError::Source(
+                EncryptError::IdentifierOutOfRange(6)
+            ))
+        );
+        assert_eq!(
+            channel.reencrypt(&secret, &header, &[1, 2], &[]).err(),
+            Some(ReEncryptionError::Encrypt(EncryptError::EmptySet))
+        );
+    }
+}
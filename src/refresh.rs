@@ -0,0 +1,501 @@
+//! Proactive refresh of the shares of an escrow.
+//!
+//! Shares stolen from custodians add up over time: an attacker collecting `t` of them, one at a
+//! time, recovers the secret. A refresh round replaces every share by a fresh sharing of the same
+//! secret without recovering it, so that shares stolen before the round are useless together with
+//! shares stolen after it.
+//!
+//! Each custodian taking part deals a [`RefreshDealing`] with [`CustodianKey::deal_refresh`]: a
+//! random sharing of zero, of the threshold of the escrow, with its Feldman commitments and one
+//! sub-share for every custodian. Every custodian then adds the sub-shares dealt to it to its
+//! share with [`EscrowShare::refresh`], which also returns the commitments of the new sharing, the
+//! sum of the old ones and of the dealings. As the dealt polynomials vanish at zero, the secret
+//! stays the same. All custodians must apply the same dealings, in any order.
+//!
+//! The dealings travel over untrusted channels. Each custodian has a [`CustodianKey`], whose
+//! public half is known to the others: the sub-share for custodian `k` is masked with scalars
+//! derived from the Diffie-Hellman point `r Y_k` of an ephemeral `r P` of the dealing and the
+//! transport key `Y_k` of `k`, and the dealing is signed by its dealer, over the commitments of
+//! the round it is for. A dealing that is forged, replayed from another round, or whose
+//! sub-share does not match its commitments is refused before any share changes.
+//!
+//! Refreshing does not change `alpha` or `gamma`: keys leaked with the secret itself stay
+//! leaked, and [`crate::BroadcastChannel::rotate`] is the remedy for those.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use ark_ec::{PairingEngine, ProjectiveCurve};
+use ark_ff::{UniformRand, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use rand_core::{CryptoRng, RngCore};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::encoding::{from_bytes, to_bytes};
+use crate::error::ThresholdError;
+use crate::escrow::{commit, evaluate, EscrowCommitments, EscrowShare};
+use crate::hash::hash_to_scalar;
+use crate::signature::{Signature, SigningKey, VerifyingKey};
+
+/// Domain separation tag of the messages signed by dealers.
+const DEALING_TAG: &[u8] = b"DGS-BE-REFRESH-DEALING-V1";
+
+/// Domain of the masks of the sub-shares.
+const MASK_DOMAIN: &[u8] = b"DGS-BE-REFRESH-MASK-V1";
+
+/// Keys of a custodian: a transport key receiving sub-shares and a key signing dealings.
+pub struct CustodianKey<E: PairingEngine> {
+    index: usize,
+    transport: E::Fr,
+    signing: SigningKey<E>,
+}
+
+impl<E: PairingEngine> Drop for CustodianKey<E> {
+    fn drop(&mut self) {
+        self.transport.zeroize();
+    }
+}
+
+impl<E: PairingEngine> ZeroizeOnDrop for CustodianKey<E> {}
+
+/// Public keys of a custodian, known to the other custodians.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CustodianPublicKey<E: PairingEngine> {
+    index: usize,
+    /// `y P` for the transport key `y`
+    transport: E::G1Projective,
+    verifying: VerifyingKey<E>,
+}
+
+/// Sub-share of a dealing for one custodian, masked.
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct SealedShare<E: PairingEngine> {
+    recipient: usize,
+    alpha: E::Fr,
+    gamma: E::Fr,
+}
+
+/// Sharing of zero dealt by one custodian in a refresh round.
+#[derive(Clone, PartialEq, Eq)]
+pub struct RefreshDealing<E: PairingEngine> {
+    dealer: usize,
+    /// `r P`
+    ephemeral: E::G1Projective,
+    commitments: EscrowCommitments<E>,
+    sealed: Vec<SealedShare<E>>,
+    signature: Signature<E>,
+}
+
+/// Masks of the sub-share of `recipient`, from the Diffie-Hellman point `shared`.
+fn masks<E: PairingEngine>(shared: &E::G1Projective, recipient: usize) -> (E::Fr, E::Fr) {
+    let mut message = to_bytes(shared);
+    message.extend_from_slice(&(recipient as u64).to_le_bytes());
+    message.push(0);
+    let alpha = hash_to_scalar::<E>(MASK_DOMAIN, &message);
+    *message.last_mut().unwrap() = 1;
+    let gamma = hash_to_scalar::<E>(MASK_DOMAIN, &message);
+    message.zeroize();
+    (alpha, gamma)
+}
+
+/// Message signed by `dealer` for its dealing in the round refreshing the sharing of `current`.
+fn dealing_message<E: PairingEngine>(
+    current: &EscrowCommitments<E>,
+    dealer: usize,
+    ephemeral: &E::G1Projective,
+    commitments: &EscrowCommitments<E>,
+    sealed: &[SealedShare<E>],
+) -> Vec<u8> {
+    let mut message = DEALING_TAG.to_vec();
+    message.extend_from_slice(&current.to_bytes());
+    message.extend_from_slice(&(dealer as u64).to_le_bytes());
+    message.extend_from_slice(&to_bytes(ephemeral));
+    message.extend_from_slice(&commitments.to_bytes());
+    message.extend_from_slice(&to_bytes(&sealed));
+    message
+}
+
+impl<E: PairingEngine> CustodianKey<E> {
+    /// Fresh keys for the custodian holding the share of index `index`.
+    pub fn generate<R: RngCore + CryptoRng>(index: usize, rng: &mut R) -> Self {
+        CustodianKey {
+            index,
+            transport: E::Fr::rand(rng),
+            signing: SigningKey::generate(rng),
+        }
+    }
+
+    /// Index of the share of the custodian.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Public keys of the custodian.
+    pub fn public_key(&self) -> CustodianPublicKey<E> {
+        let mut transport = E::G1Projective::prime_subgroup_generator();
+        transport *= self.transport;
+        CustodianPublicKey {
+            index: self.index,
+            transport,
+            verifying: self.signing.verifying_key(),
+        }
+    }
+
+    /// Dealing of this custodian for the round refreshing the sharing of `current`, with a
+    /// sub-share for each of `custodians`.
+    pub fn deal_refresh<R>(
+        &self,
+        current: &EscrowCommitments<E>,
+        custodians: &[CustodianPublicKey<E>],
+        rng: &mut R,
+    ) -> RefreshDealing<E>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let mut alpha_coefficients = vec![E::Fr::zero()];
+        let mut gamma_coefficients = vec![E::Fr::zero()];
+        for _ in 1..current.threshold() {
+            alpha_coefficients.push(E::Fr::rand(rng));
+            gamma_coefficients.push(E::Fr::rand(rng));
+        }
+        let commitments = EscrowCommitments {
+            alpha: commit::<E>(&alpha_coefficients),
+            gamma: commit::<E>(&gamma_coefficients),
+        };
+
+        let mut r = E::Fr::rand(rng);
+        let mut ephemeral = E::G1Projective::prime_subgroup_generator();
+        ephemeral *= r;
+        let sealed: Vec<SealedShare<E>> = custodians
+            .iter()
+            .map(|custodian| {
+                let mut shared = custodian.transport;
+                shared *= r;
+                let (alpha_mask, gamma_mask) = masks::<E>(&shared, custodian.index);
+                shared.zeroize();
+                SealedShare {
+                    recipient: custodian.index,
+                    alpha: evaluate::<E>(&alpha_coefficients, custodian.index) + alpha_mask,
+                    gamma: evaluate::<E>(&gamma_coefficients, custodian.index) + gamma_mask,
+                }
+            })
+            .collect();
+        r.zeroize();
+        alpha_coefficients.zeroize();
+        gamma_coefficients.zeroize();
+
+        let signature = self.signing.sign(&dealing_message(
+            current,
+            self.index,
+            &ephemeral,
+            &commitments,
+            &sealed,
+        ));
+        RefreshDealing {
+            dealer: self.index,
+            ephemeral,
+            commitments,
+            sealed,
+            signature,
+        }
+    }
+}
+
+impl<E: PairingEngine> CustodianPublicKey<E> {
+    /// Index of the share of the custodian.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl<E: PairingEngine> RefreshDealing<E> {
+    /// Index of the custodian that dealt.
+    pub fn dealer(&self) -> usize {
+        self.dealer
+    }
+
+    /// Sub-share of `key` in the dealing, if the dealing is signed by its dealer in
+    /// `custodians` for the round of `current` and the sub-share matches its commitments.
+    fn open(
+        &self,
+        key: &CustodianKey<E>,
+        current: &EscrowCommitments<E>,
+        custodians: &[CustodianPublicKey<E>],
+    ) -> Option<(E::Fr, E::Fr)> {
+        let dealer = custodians
+            .iter()
+            .find(|custodian| custodian.index == self.dealer)?;
+        if !dealer.verifying.verify(
+            &dealing_message(
+                current,
+                self.dealer,
+                &self.ephemeral,
+                &self.commitments,
+                &self.sealed,
+            ),
+            &self.signature,
+        ) {
+            return None;
+        }
+        if self.commitments.threshold() != current.threshold()
+            || !self.commitments.alpha[0].is_zero()
+            || !self.commitments.gamma[0].is_zero()
+        {
+            return None;
+        }
+
+        let sealed = self
+            .sealed
+            .iter()
+            .find(|sealed| sealed.recipient == key.index)?;
+        let mut shared = self.ephemeral;
+        shared *= key.transport;
+        let (alpha_mask, gamma_mask) = masks::<E>(&shared, key.index);
+        shared.zeroize();
+        let sub_share = EscrowShare {
+            index: key.index,
+            alpha: sealed.alpha - alpha_mask,
+            gamma: sealed.gamma - gamma_mask,
+        };
+        if !self.commitments.verify(&sub_share) {
+            return None;
+        }
+        Some((sub_share.alpha, sub_share.gamma))
+    }
+
+    /// Compressed encoding of the dealing.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+}
+
+impl<E: PairingEngine> EscrowShare<E> {
+    /// Share of the sharing refreshed by `dealings`, with its commitments, from this share of
+    /// the sharing of `current`. `key` is the key of the custodian of this share, and
+    /// `custodians` the public keys of all of them. Fails with the dealer of the first dealing
+    /// that is refused, leaving this share as it is, or with the index of this share if `key`
+    /// is not for it.
+    pub fn refresh(
+        &self,
+        key: &CustodianKey<E>,
+        current: &EscrowCommitments<E>,
+        custodians: &[CustodianPublicKey<E>],
+        dealings: &[RefreshDealing<E>],
+    ) -> Result<(EscrowCommitments<E>, EscrowShare<E>), ThresholdError> {
+        if key.index != self.index {
+            return Err(ThresholdError::InvalidShare(self.index));
+        }
+        let mut refreshed = current.clone();
+        let mut share = EscrowShare {
+            index: self.index,
+            alpha: self.alpha,
+            gamma: self.gamma,
+        };
+        for (position, dealing) in dealings.iter().enumerate() {
+            let repeated = dealings[..position]
+                .iter()
+                .any(|other| other.dealer == dealing.dealer);
+            let (alpha, gamma) = match dealing.open(key, current, custodians) {
+                Some(sub_share) if !repeated => sub_share,
+                _ => return Err(ThresholdError::InvalidDealing(dealing.dealer)),
+            };
+            share.alpha += alpha;
+            share.gamma += gamma;
+            for (sum, commitment) in refreshed
+                .alpha
+                .iter_mut()
+                .zip(dealing.commitments.alpha.iter())
+            {
+                *sum += commitment;
+            }
+            for (sum, commitment) in refreshed
+                .gamma
+                .iter_mut()
+                .zip(dealing.commitments.gamma.iter())
+            {
+                *sum += commitment;
+            }
+        }
+        Ok((refreshed, share))
+    }
+}
+
+impl<E: PairingEngine> CanonicalSerialize for CustodianPublicKey<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        (self.index as u64).serialize(&mut writer)?;
+        self.transport.serialize(&mut writer)?;
+        self.verifying.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        8 + self.transport.serialized_size() + self.verifying.serialized_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for CustodianPublicKey<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Ok(CustodianPublicKey {
+            index: u64::deserialize(&mut reader)? as usize,
+            transport: E::G1Projective::deserialize(&mut reader)?,
+            verifying: VerifyingKey::deserialize(&mut reader)?,
+        })
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for CustodianPublicKey<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+impl<E: PairingEngine> CanonicalSerialize for SealedShare<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        (self.recipient as u64).serialize(&mut writer)?;
+        self.alpha.serialize(&mut writer)?;
+        self.gamma.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        8 + self.alpha.serialized_size() + self.gamma.serialized_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for SealedShare<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Ok(SealedShare {
+            recipient: u64::deserialize(&mut reader)? as usize,
+            alpha: E::Fr::deserialize(&mut reader)?,
+            gamma: E::Fr::deserialize(&mut reader)?,
+        })
+    }
+}
+
+impl<E: PairingEngine> CanonicalSerialize for RefreshDealing<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        (self.dealer as u64).serialize(&mut writer)?;
+        self.ephemeral.serialize(&mut writer)?;
+        self.commitments.serialize(&mut writer)?;
+        self.sealed.serialize(&mut writer)?;
+        self.signature.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        8 + self.ephemeral.serialized_size()
+            + self.commitments.serialized_size()
+            + self.sealed.serialized_size()
+            + self.signature.serialized_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for RefreshDealing<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Ok(RefreshDealing {
+            dealer: u64::deserialize(&mut reader)? as usize,
+            ephemeral: E::G1Projective::deserialize(&mut reader)?,
+            commitments: EscrowCommitments::deserialize(&mut reader)?,
+            sealed: Vec::deserialize(&mut reader)?,
+            signature: Signature::deserialize(&mut reader)?,
+        })
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for RefreshDealing<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secret::BroadcasterSecret;
+    use crate::BroadcastChannel;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn refreshed_shares_recover_the_same_secret() {
+        let mut rng = thread_rng();
+        let (channel, _, secret) =
+            BroadcastChannel::<Bls12_381>::init_with_secret(3, &mut rng).unwrap();
+        let (commitments, shares) = secret.escrow(2, 3, &mut rng).unwrap();
+        let keys: Vec<CustodianKey<Bls12_381>> = (1..=3)
+            .map(|index| CustodianKey::generate(index, &mut rng))
+            .collect();
+        let custodians: Vec<CustodianPublicKey<Bls12_381>> =
+            keys.iter().map(|key| key.public_key()).collect();
+
+        let dealings: Vec<RefreshDealing<Bls12_381>> = keys
+            .iter()
+            .map(|key| {
+                let dealing = key.deal_refresh(&commitments, &custodians, &mut rng);
+                RefreshDealing::try_from(dealing.to_bytes().as_slice()).unwrap()
+            })
+            .collect();
+        let mut refreshed = Vec::new();
+        let mut new_shares = Vec::new();
+        for (share, key) in shares.iter().zip(keys.iter()) {
+            let (new_commitments, new_share) = share
+                .refresh(key, &commitments, &custodians, &dealings)
+                .unwrap();
+            refreshed.push(new_commitments);
+            new_shares.push(new_share);
+        }
+        assert!(refreshed.iter().all(|other| *other == refreshed[0]));
+        assert!(refreshed[0].belongs_to(&channel));
+        assert!(refreshed[0] != commitments);
+
+        let recovered =
+            BroadcasterSecret::recover_from_shares(&refreshed[0], &new_shares[1..]).unwrap();
+        assert!(recovered.belongs_to(&channel));
+        // an old share does not combine with a new one
+        let mixed = [new_shares.remove(0), shares.into_iter().nth(1).unwrap()];
+        assert!(matches!(
+            BroadcasterSecret::recover_from_shares(&refreshed[0], &mixed),
+            Err(ThresholdError::InvalidShare(2))
+        ));
+    }
+
+    #[test]
+    fn forged_dealings_are_refused() {
+        let mut rng = thread_rng();
+        let (_, _, secret) = BroadcastChannel::<Bls12_381>::init_with_secret(3, &mut rng).unwrap();
+        let (commitments, shares) = secret.escrow(2, 2, &mut rng).unwrap();
+        let keys: Vec<CustodianKey<Bls12_381>> = (1..=2)
+            .map(|index| CustodianKey::generate(index, &mut rng))
+            .collect();
+        let custodians: Vec<CustodianPublicKey<Bls12_381>> =
+            keys.iter().map(|key| key.public_key()).collect();
+
+        let mut forged = keys[1].deal_refresh(&commitments, &custodians, &mut rng);
+        let shift = forged.sealed[1].alpha;
+        forged.sealed[0].alpha += shift;
+        assert!(matches!(
+            shares[0].refresh(&keys[0], &commitments, &custodians, &[forged]),
+            Err(ThresholdError::InvalidDealing(2))
+        ));
+
+        let (other, _) = secret.escrow(2, 2, &mut rng).unwrap();
+        let replayed = keys[1].deal_refresh(&other, &custodians, &mut rng);
+        assert!(matches!(
+            shares[0].refresh(&keys[0], &commitments, &custodians, &[replayed]),
+            Err(ThresholdError::InvalidDealing(2))
+        ));
+
+        let impostor = CustodianKey::generate(2, &mut rng);
+        let dealing = impostor.deal_refresh(&commitments, &custodians, &mut rng);
+        assert!(matches!(
+            shares[0].refresh(&keys[0], &commitments, &custodians, &[dealing]),
+            Err(ThresholdError::InvalidDealing(2))
+        ));
+        assert!(matches!(
+            shares[0].refresh(&keys[1], &commitments, &custodians, &[]),
+            Err(ThresholdError::InvalidShare(1))
+        ));
+    }
+}
@@ -0,0 +1,459 @@
+//! Persistent membership state of a channel.
+//!
+//! The channel only knows which slots have a key and which keys were retired. Deployments also
+//! have to remember who each participant is, whether it was revoked, and which key it was given,
+//! across restarts. A [`Registry`] tracks a [`Registration`] per identifier: the user it was
+//! issued to, application-defined metadata, its [`Status`] and the fingerprint of its public key.
+//! The registrations live in a [`RegistryStorage`], which [`MemoryStorage`] implements in memory,
+//! and [`SledStorage`] in a sled database with the `sled` feature. The registry keeps the index
+//! from users to identifiers in memory, rebuilt from the storage when it is opened.
+//!
+//! A registration is encoded as the identifier, the user and the metadata as byte vectors, the
+//! status as one byte and the fingerprint.
+//!
+//! Only available with the `std` feature.
+
+use std::collections::{BTreeMap, HashMap};
+use std::convert::{Infallible, TryFrom};
+
+use ark_ec::PairingEngine;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use sha2::{Digest, Sha256};
+
+use crate::encoding::{from_bytes, to_bytes};
+use crate::error::{RegistryError, SetupError};
+use crate::{BroadcastChannel, PublicKey};
+
+#[cfg(feature = "sled")]
+pub use self::sled_storage::SledStorage;
+
+/// Domain separation tag of the key fingerprints.
+const KEY_FINGERPRINT_TAG: &[u8] = b"DGS-BE-KEY-FINGERPRINT-V1";
+
+/// Status of a registered participant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    /// The key of the participant is in use
+    Issued,
+    /// The key of the participant was retired from the channel
+    Revoked,
+}
+
+/// Membership record of one identifier of a channel.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Registration {
+    identifier: usize,
+    user: String,
+    metadata: Vec<u8>,
+    status: Status,
+    key_fingerprint: [u8; 32],
+}
+
+impl Registration {
+    /// Identifier of the participant in the channel.
+    pub fn identifier(&self) -> usize {
+        self.identifier
+    }
+
+    /// User the key was issued to.
+    pub fn user(&self) -> &str {
+        &self.user
+    }
+
+    /// Metadata attached by the application.
+    pub fn metadata(&self) -> &[u8] {
+        &self.metadata
+    }
+
+    /// Status of the participant.
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    /// Fingerprint of the public key of the participant, as computed by [`key_fingerprint`].
+    pub fn key_fingerprint(&self) -> [u8; 32] {
+        self.key_fingerprint
+    }
+
+    /// Encoding of the registration.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+}
+
+impl CanonicalSerialize for Registration {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.identifier.serialize(&mut writer)?;
+        self.user.as_bytes().to_vec().serialize(&mut writer)?;
+        self.metadata.serialize(&mut writer)?;
+        let status: u8 = match self.status {
+            Status::Issued => 0,
+            Status::Revoked => 1,
+        };
+        status.serialize(&mut writer)?;
+        writer.write_all(&self.key_fingerprint)?;
+        Ok(())
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.identifier.serialized_size()
+            + self.user.as_bytes().to_vec().serialized_size()
+            + self.metadata.serialized_size()
+            + 1
+            + self.key_fingerprint.len()
+    }
+}
+
+impl CanonicalDeserialize for Registration {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let identifier = usize::deserialize(&mut reader)?;
+        let user = String::from_utf8(Vec::deserialize(&mut reader)?)
+            .map_err(|_| SerializationError::InvalidData)?;
+        let metadata = Vec::deserialize(&mut reader)?;
+        let status = match u8::deserialize(&mut reader)? {
+            0 => Status::Issued,
+            1 => Status::Revoked,
+            _ => return Err(SerializationError::InvalidData),
+        };
+        let mut key_fingerprint = [0u8; 32];
+        reader.read_exact(&mut key_fingerprint)?;
+        Ok(Registration {
+            identifier,
+            user,
+            metadata,
+            status,
+            key_fingerprint,
+        })
+    }
+}
+
+impl TryFrom<&[u8]> for Registration {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+/// SHA-256 fingerprint of `public_key`, over its compressed encoding.
+pub fn key_fingerprint<E: PairingEngine>(public_key: &PublicKey<E>) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(KEY_FINGERPRINT_TAG);
+    hasher.update(to_bytes(&public_key.point));
+    hasher.finalize().into()
+}
+
+/// Storage of the registrations of a registry.
+pub trait RegistryStorage {
+    /// Error of the storage
+    type Error;
+
+    /// Store `registration`, replacing any registration of the same identifier.
+    fn put(&mut self, registration: &Registration) -> Result<(), Self::Error>;
+
+    /// Registration of `identifier`, if there is one.
+    fn get(&self, identifier: usize) -> Result<Option<Registration>, Self::Error>;
+
+    /// Every stored registration, in increasing order of identifier.
+    fn registrations(&self) -> Result<Vec<Registration>, Self::Error>;
+}
+
+/// Registrations held in memory.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryStorage {
+    registrations: BTreeMap<usize, Registration>,
+}
+
+impl MemoryStorage {
+    /// Empty storage.
+    pub fn new() -> Self {
+        MemoryStorage::default()
+    }
+}
+
+impl RegistryStorage for MemoryStorage {
+    type Error = Infallible;
+
+    fn put(&mut self, registration: &Registration) -> Result<(), Infallible> {
+        self.registrations
+            .insert(registration.identifier, registration.clone());
+        Ok(())
+    }
+
+    fn get(&self, identifier: usize) -> Result<Option<Registration>, Infallible> {
+        Ok(self.registrations.get(&identifier).cloned())
+    }
+
+    fn registrations(&self) -> Result<Vec<Registration>, Infallible> {
+        Ok(self.registrations.values().cloned().collect())
+    }
+}
+
+/// Membership state of a channel, kept in a storage.
+pub struct Registry<S: RegistryStorage> {
+    storage: S,
+    /// identifiers of the users
+    users: HashMap<String, usize>,
+}
+
+impl<S: RegistryStorage> Registry<S> {
+    /// Registry of the registrations in `storage`.
+    pub fn open(storage: S) -> Result<Self, RegistryError<S::Error>> {
+        let users = storage
+            .registrations()
+            .map_err(RegistryError::Storage)?
+            .into_iter()
+            .map(|registration| (registration.user, registration.identifier))
+            .collect();
+        Ok(Registry { storage, users })
+    }
+
+    /// Record that the key of `identifier` in `channel` was issued to `user`, with `metadata`.
+    /// Fails if no key is issued for `identifier`, or if the identifier or the user are already
+    /// registered.
+    pub fn register<E: PairingEngine>(
+        &mut self,
+        channel: &BroadcastChannel<E>,
+        identifier: usize,
+        user: &str,
+        metadata: &[u8],
+    ) -> Result<(), RegistryError<S::Error>> {
+        let public_key = channel
+            .public_key(identifier)
+            .ok_or(RegistryError::Setup(SetupError::NotIssued(identifier)))?;
+        if self.users.contains_key(user) {
+            return Err(RegistryError::UserRegistered);
+        }
+        if self.get(identifier)?.is_some() {
+            return Err(RegistryError::IdentifierRegistered(identifier));
+        }
+
+        let registration = Registration {
+            identifier,
+            user: user.to_string(),
+            metadata: metadata.to_vec(),
+            status: Status::Issued,
+            key_fingerprint: key_fingerprint(&public_key),
+        };
+        self.storage
+            .put(&registration)
+            .map_err(RegistryError::Storage)?;
+        self.users.insert(registration.user, identifier);
+        Ok(())
+    }
+
+    /// Retire the key of `identifier` from `channel` and record it as revoked. Fails if the
+    /// identifier is not registered, or if the channel refuses to retire it.
+    pub fn revoke<E: PairingEngine>(
+        &mut self,
+        channel: &mut BroadcastChannel<E>,
+        identifier: usize,
+    ) -> Result<(), RegistryError<S::Error>> {
+        let mut registration = self
+            .get(identifier)?
+            .ok_or(RegistryError::NotRegistered(identifier))?;
+        if registration.status != Status::Revoked {
+            channel.retire(identifier).map_err(RegistryError::Setup)?;
+            registration.status = Status::Revoked;
+        }
+        self.storage
+            .put(&registration)
+            .map_err(RegistryError::Storage)
+    }
+
+    /// Replace the metadata of `identifier`. Fails if the identifier is not registered.
+    pub fn update_metadata(
+        &mut self,
+        identifier: usize,
+        metadata: &[u8],
+    ) -> Result<(), RegistryError<S::Error>> {
+        let mut registration = self
+            .get(identifier)?
+            .ok_or(RegistryError::NotRegistered(identifier))?;
+        registration.metadata = metadata.to_vec();
+        self.storage
+            .put(&registration)
+            .map_err(RegistryError::Storage)
+    }
+
+    /// Registration of `identifier`, if there is one.
+    pub fn get(&self, identifier: usize) -> Result<Option<Registration>, RegistryError<S::Error>> {
+        self.storage.get(identifier).map_err(RegistryError::Storage)
+    }
+
+    /// Identifier registered to `user`, if there is one.
+    pub fn identifier(&self, user: &str) -> Option<usize> {
+        self.users.get(user).copied()
+    }
+
+    /// Identifiers of the registered participants that are not revoked, in increasing order.
+    pub fn issued(&self) -> Result<Vec<usize>, RegistryError<S::Error>> {
+        Ok(self
+            .storage
+            .registrations()
+            .map_err(RegistryError::Storage)?
+            .into_iter()
+            .filter(|registration| registration.status == Status::Issued)
+            .map(|registration| registration.identifier)
+            .collect())
+    }
+
+    /// Whether `public_key` is the key registered for `identifier`.
+    pub fn matches_key<E: PairingEngine>(
+        &self,
+        identifier: usize,
+        public_key: &PublicKey<E>,
+    ) -> Result<bool, RegistryError<S::Error>> {
+        Ok(self.get(identifier)?.is_some_and(|registration| {
+            registration.key_fingerprint == key_fingerprint(public_key)
+        }))
+    }
+
+    /// Storage of the registry.
+    pub fn storage(&self) -> &S {
+        &self.storage
+    }
+}
+
+#[cfg(feature = "sled")]
+mod sled_storage {
+    use std::convert::TryFrom;
+    use std::path::Path;
+
+    use super::{Registration, RegistryStorage};
+    use crate::error::SledStorageError;
+
+    /// Name of the tree of the registrations.
+    const TREE_NAME: &[u8] = b"dgs-be-registry";
+
+    /// Registrations held in a tree of a sled database, keyed by the big-endian identifier so
+    /// that they iterate in increasing order.
+    #[derive(Clone)]
+    pub struct SledStorage {
+        tree: sled::Tree,
+    }
+
+    impl SledStorage {
+        /// Storage in the database at `path`, created if it does not exist.
+        pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, SledStorageError> {
+            let db = sled::open(path).map_err(SledStorageError::Database)?;
+            SledStorage::from_db(&db)
+        }
+
+        /// Storage in the registry tree of an open database.
+        pub fn from_db(db: &sled::Db) -> Result<Self, SledStorageError> {
+            let tree = db
+                .open_tree(TREE_NAME)
+                .map_err(SledStorageError::Database)?;
+            Ok(SledStorage { tree })
+        }
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Registration, SledStorageError> {
+        Registration::try_from(bytes).map_err(|_| SledStorageError::Corrupted)
+    }
+
+    impl RegistryStorage for SledStorage {
+        type Error = SledStorageError;
+
+        fn put(&mut self, registration: &Registration) -> Result<(), SledStorageError> {
+            let key = (registration.identifier as u64).to_be_bytes();
+            self.tree
+                .insert(key, registration.to_bytes())
+                .map_err(SledStorageError::Database)?;
+            self.tree.flush().map_err(SledStorageError::Database)?;
+            Ok(())
+        }
+
+        fn get(&self, identifier: usize) -> Result<Option<Registration>, SledStorageError> {
+            let key = (identifier as u64).to_be_bytes();
+            match self.tree.get(key).map_err(SledStorageError::Database)? {
+                Some(bytes) => decode(&bytes).map(Some),
+                None => Ok(None),
+            }
+        }
+
+        fn registrations(&self) -> Result<Vec<Registration>, SledStorageError> {
+            self.tree
+                .iter()
+                .map(|entry| {
+                    let (_, bytes) = entry.map_err(SledStorageError::Database)?;
+                    decode(&bytes)
+                })
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn registry_tracks_membership() {
+        let mut rng = thread_rng();
+        let (mut channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(3, &mut rng).unwrap();
+
+        let mut registry = Registry::open(MemoryStorage::new()).unwrap();
+        registry
+            .register(&channel, 1, "alice", b"team=red")
+            .unwrap();
+        registry.register(&channel, 2, "bob", b"").unwrap();
+        assert_eq!(
+            registry.register(&channel, 3, "alice", b"").err(),
+            Some(RegistryError::UserRegistered)
+        );
+        assert_eq!(
+            registry.register(&channel, 2, "carol", b"").err(),
+            Some(RegistryError::IdentifierRegistered(2))
+        );
+        assert_eq!(registry.identifier("bob"), Some(2));
+        assert!(registry
+            .matches_key(1, &participants[0].public_key())
+            .unwrap());
+        assert!(!registry
+            .matches_key(1, &participants[1].public_key())
+            .unwrap());
+
+        registry.revoke(&mut channel, 1).unwrap();
+        assert_eq!(registry.get(1).unwrap().unwrap().status(), Status::Revoked);
+        assert_eq!(registry.issued().unwrap(), vec![2]);
+        assert!(channel.encrypt(&[1, 2], &mut rng).is_err());
+        assert_eq!(
+            registry.revoke(&mut channel, 3),
+            Err(RegistryError::NotRegistered(3))
+        );
+
+        registry.update_metadata(2, b"team=blue").unwrap();
+        let registration = registry.get(2).unwrap().unwrap();
+        assert_eq!(registration.metadata(), b"team=blue");
+        assert_eq!(
+            Registration::try_from(registration.to_bytes().as_slice()).ok(),
+            Some(registration)
+        );
+
+        let reopened = Registry::open(registry.storage().clone()).unwrap();
+        assert_eq!(reopened.identifier("alice"), Some(1));
+    }
+
+    #[cfg(feature = "sled")]
+    #[test]
+    fn sled_storage_persists() {
+        let mut rng = thread_rng();
+        let (channel, _) = BroadcastChannel::<Bls12_381>::init_participants(2, &mut rng).unwrap();
+        let path = std::env::temp_dir().join(format!("dgs-be-registry-{}", std::process::id()));
+
+        let mut registry = Registry::open(SledStorage::open(&path).unwrap()).unwrap();
+        registry.register(&channel, 2, "dave", b"admin").unwrap();
+        drop(registry);
+
+        let registry = Registry::open(SledStorage::open(&path).unwrap()).unwrap();
+        assert_eq!(registry.identifier("dave"), Some(2));
+        assert_eq!(registry.get(2).unwrap().unwrap().metadata(), b"admin");
+        assert_eq!(registry.issued().unwrap(), vec![2]);
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+}
@@ -0,0 +1,329 @@
+//! Encryption for every participant except a revoked few.
+//!
+//! Broadcasting to all the participants minus a short list of revoked ones is the most common
+//! audience of large channels. [`BroadcastChannel::encrypt_excluding`] and
+//! [`Recipient::decrypt_excluding`] take the revoked identifiers and encrypt for the other issued
+//! participants. Their aggregate is the one of every issued participant minus the points of the
+//! revoked ones. The channel keeps the aggregate of every issued participant up to date as keys
+//! are issued, so only the revoked points are summed for each header, and
+//! [`BroadcastChannel::encrypt_to_all`] sums none.
+//!
+//! Headers are the same as those of [`BroadcastChannel::encrypt`] for the complement of the
+//! revoked set, and either way of decrypting recovers the key.
+//!
+//! Keys that leak are retired for good with [`BroadcastChannel::retire`]. The channel records
+//! them, encryptions refuse sets containing them, and the revoked sets above always include them.
+//! A slot cannot be handed to someone else: its private key is fixed by gamma and the slot, and
+//! the leaked copy would keep decrypting. [`BroadcastChannel::replace`] issues the newcomer a key
+//! on a fresh slot instead. Headers produced before the retirement stay readable with the leaked
+//! key. The retired keys are part of the fingerprint and of the encoding of the channel, so that a
+//! broadcaster loading its parameters again keeps refusing them.
+
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+
+use ark_ec::{PairingEngine, ProjectiveCurve};
+use ark_ff::Zero;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::error::{DecryptError, EncryptError, SetupError};
+use crate::events::{self, Event};
+use crate::secret::BroadcasterSecret;
+use crate::session::EncryptionSession;
+use crate::set::first_repetition;
+use crate::{BroadcastChannel, Header, Recipient, SharedKey};
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Identifier of `revoked` outside of the channel, if any.
+    fn out_of_range(&self, revoked: &[usize]) -> Option<usize> {
+        revoked
+            .iter()
+            .copied()
+            .find(|index| *index == 0 || *index > self.number_participants)
+    }
+
+    /// Issued identifiers that are revoked or retired.
+    fn excluded(&self, revoked: &[usize]) -> BTreeSet<usize> {
+        revoked
+            .iter()
+            .chain(self.retired.iter())
+            .copied()
+            .filter(|index| *index <= self.issued)
+            .collect()
+    }
+
+    /// Issued participants that are not in `excluded`, in increasing order.
+    fn audience_excluding(&self, excluded: &BTreeSet<usize>) -> Vec<usize> {
+        self.issued()
+            .filter(|identifier| !excluded.contains(identifier))
+            .collect()
+    }
+
+    /// Recompute the aggregate of every issued participant from the parameters.
+    pub(crate) fn refresh_issued_aggregate(&mut self) {
+        let n = self.number_participants;
+        let mut sum = E::G1Projective::zero();
+        for index in self.issued() {
+            sum.add_assign_mixed(&self.broadcaster_pk_g1[n + 1 - index]);
+        }
+        self.issued_aggregate = sum;
+    }
+
+    /// First identifier of `set_recipients` whose key has been retired, as an error.
+    pub(crate) fn check_not_retired(&self, set_recipients: &[usize]) -> Result<(), EncryptError> {
+        match set_recipients
+            .iter()
+            .find(|identifier| self.retired.contains(identifier))
+        {
+            Some(identifier) => Err(EncryptError::RetiredRecipient(*identifier)),
+            None => Ok(()),
+        }
+    }
+
+    /// Retire the key of participant `identifier`, so that no header is produced for it anymore.
+    /// Fails if its key has not been issued or is already retired.
+    pub fn retire(&mut self, identifier: usize) -> Result<(), SetupError> {
+        if self.public_key(identifier).is_none() || !self.retired.insert(identifier) {
+            return Err(SetupError::NotIssued(identifier));
+        }
+        self.refresh_cache_fingerprint();
+        self.emit(Event::KeyRetired { identifier });
+        Ok(())
+    }
+
+    /// Retire the key of participant `identifier` and issue a key to its replacement with
+    /// [`BroadcastChannel::add_participant`]. Fails, leaving the channel unchanged, if `secret`
+    /// is not the one of the channel or if the key cannot be retired.
+    pub fn replace(
+        &mut self,
+        identifier: usize,
+        secret: &BroadcasterSecret<E>,
+    ) -> Result<Recipient<E>, SetupError> {
+        if !secret.belongs_to(self) {
+            return Err(SetupError::WrongSecret);
+        }
+        self.retire(identifier)?;
+        self.add_participant(secret)
+    }
+
+    /// Same as [`BroadcastChannel::session`], for every issued participant not in `revoked` and
+    /// whose key is not retired. Fails if `revoked` contains identifiers outside of the channel or
+    /// more than once, if it leaves nobody, or if the remaining set breaks the policy.
+    pub fn session_excluding(
+        &self,
+        revoked: &[usize],
+    ) -> Result<EncryptionSession<'_, E>, EncryptError> {
+        if let Some(index) = self.out_of_range(revoked) {
+            return Err(EncryptError::IdentifierOutOfRange(index));
+        }
+        if let Some(identifier) = first_repetition(revoked) {
+            return Err(EncryptError::DuplicateRecipient(identifier));
+        }
+        let excluded = self.excluded(revoked);
+        let audience = self.audience_excluding(&excluded);
+        if audience.is_empty() {
+            return Err(EncryptError::EmptySet);
+        }
+        self.policy.check(&audience)?;
+
+        let n = self.number_participants;
+        let mut aggregate = self.issued_aggregate;
+        for index in excluded.iter() {
+            aggregate.add_assign_mixed(&-self.broadcaster_pk_g1[n + 1 - index]);
+        }
+
+        let event = Event::Encrypted {
+            set_hash: events::set_hash(&audience),
+        };
+        let audience = audience.into_iter().collect();
+        Ok(EncryptionSession::new(
+            self,
+            aggregate,
+            event,
+            Some(audience),
+        ))
+    }
+
+    /// Same as [`BroadcastChannel::encrypt`], for every issued participant not in `revoked` and
+    /// whose key is not retired.
+    pub fn encrypt_excluding<R>(
+        &self,
+        revoked: &[usize],
+        rng: &mut R,
+    ) -> Result<(Header<E>, SharedKey<E>), EncryptError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        Ok(self.session_excluding(revoked)?.encrypt(rng))
+    }
+
+    /// Same as [`BroadcastChannel::encrypt_excluding`] with nobody revoked, for every issued
+    /// participant whose key is not retired. Headers are decrypted with
+    /// [`Recipient::decrypt_excluding`] and an empty revoked set.
+    pub fn encrypt_to_all<R>(&self, rng: &mut R) -> Result<(Header<E>, SharedKey<E>), EncryptError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        self.encrypt_excluding(&[], rng)
+    }
+}
+
+impl<E: PairingEngine> Recipient<E> {
+    /// Same as [`Recipient::decrypt`], for a header encrypted for every issued participant not
+    /// in `revoked` and whose key is not retired. The keys retired on `channel` must be the ones
+    /// retired when the header was produced.
+    pub fn decrypt_excluding(
+        &self,
+        revoked: &[usize],
+        channel: &BroadcastChannel<E>,
+        header: &Header<E>,
+    ) -> Result<SharedKey<E>, DecryptError> {
+        if let Some(index) = channel.out_of_range(revoked) {
+            return Err(DecryptError::IdentifierOutOfRange(index));
+        }
+        if let Some(identifier) = first_repetition(revoked) {
+            return Err(DecryptError::DuplicateRecipient(identifier));
+        }
+        let excluded = channel.excluded(revoked);
+        let audience = channel.audience_excluding(&excluded);
+
+        self.decrypt_with(&audience, channel, header, || {
+            let n = channel.number_participants;
+            let everyone: Vec<usize> = channel.issued().collect();
+            let mut aggregate = channel.decryption_aggregate(&everyone, self.identifier);
+            // the recipient is in the audience, so not among the excluded
+            for index in excluded.iter() {
+                aggregate
+                    .add_assign_mixed(&-channel.broadcaster_pk_g1[n + 1 - index + self.identifier]);
+            }
+            aggregate
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "std")]
+    use crate::cache::AggregateCache;
+    use ark_bls12_381::Bls12_381;
+    use core::convert::TryFrom;
+    use rand::thread_rng;
+    #[cfg(feature = "std")]
+    use std::sync::Arc;
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn revoked_participants_are_excluded() {
+        let mut rng = thread_rng();
+        let (mut channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(5, &mut rng).unwrap();
+
+        for cached in [false, true].iter() {
+            if *cached {
+                channel.set_aggregate_cache(Arc::new(AggregateCache::new(8)));
+            }
+            let (header, key) = channel.encrypt_excluding(&[4, 2], &mut rng).unwrap();
+            for participant in participants.iter() {
+                let decrypted = participant.decrypt_excluding(&[4, 2], &channel, &header);
+                if [1, 3, 5].contains(&participant.identifier) {
+                    assert_eq!(decrypted, Ok(key.clone()));
+                } else {
+                    assert_eq!(decrypted, Err(DecryptError::NotInSet));
+                }
+            }
+            assert_eq!(
+                participants[2].decrypt(&[5, 1, 3], &channel, &header),
+                Ok(key.clone())
+            );
+        }
+
+        assert!(matches!(
+            channel.encrypt_excluding(&[6], &mut rng),
+            Err(EncryptError::IdentifierOutOfRange(6))
+        ));
+        assert!(matches!(
+            channel.encrypt_excluding(&[2, 2], &mut rng),
+            Err(EncryptError::DuplicateRecipient(2))
+        ));
+        assert!(matches!(
+            channel.encrypt_excluding(&[1, 2, 3, 4, 5], &mut rng),
+            Err(EncryptError::EmptySet)
+        ));
+        let (header, key) = channel.encrypt_excluding(&[], &mut rng).unwrap();
+        assert_eq!(
+            participants[3].decrypt_excluding(&[], &channel, &header),
+            Ok(key.clone())
+        );
+    }
+
+    #[test]
+    fn retired_keys_are_refused() {
+        let mut rng = thread_rng();
+        let (mut channel, participants, secret) =
+            BroadcastChannel::<Bls12_381>::init_with_secret(4, &mut rng).unwrap();
+        let fingerprint = channel.view().fingerprint();
+
+        channel.retire(2).unwrap();
+        assert_eq!(channel.retire(2), Err(SetupError::NotIssued(2)));
+        assert_eq!(channel.retire(5), Err(SetupError::NotIssued(5)));
+        assert!(channel.view().retired().eq(vec![2]));
+        assert_ne!(channel.view().fingerprint(), fingerprint);
+        assert!(matches!(
+            channel.encrypt(&[1, 2], &mut rng),
+            Err(EncryptError::RetiredRecipient(2))
+        ));
+
+        let (header, key) = channel.encrypt_excluding(&[2, 3], &mut rng).unwrap();
+        assert_eq!(
+            participants[3].decrypt(&[1, 4], &channel, &header),
+            Ok(key.clone())
+        );
+        assert_eq!(
+            participants[0].decrypt_excluding(&[3], &channel, &header),
+            Ok(key.clone())
+        );
+
+        let newcomer = channel.replace(4, &secret).unwrap();
+        assert_eq!(newcomer.identifier, 5);
+        let (header, key) = channel.encrypt_to_all(&mut rng).unwrap();
+        assert_eq!(
+            newcomer.decrypt(&[1, 3, 5], &channel, &header),
+            Ok(key.clone())
+        );
+        assert_eq!(
+            participants[3].decrypt_excluding(&[], &channel, &header),
+            Err(DecryptError::NotInSet)
+        );
+    }
+
+    #[test]
+    fn retired_keys_are_refused_after_decoding() {
+        let mut rng = thread_rng();
+        let (mut channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(4, &mut rng).unwrap();
+        channel.retire(2).unwrap();
+
+        let decoded = BroadcastChannel::<Bls12_381>::try_from(channel.to_bytes().as_slice());
+        let decoded = decoded.unwrap();
+        assert!(matches!(
+            decoded.encrypt(&[1, 2], &mut rng),
+            Err(EncryptError::RetiredRecipient(2))
+        ));
+        assert!(matches!(
+            crate::store::encrypt(&decoded, &[2, 4], &mut rng),
+            Err(crate::error::ParamStoreError::Encrypt(
+                EncryptError::RetiredRecipient(2)
+            ))
+        ));
+        let (header, key) = decoded.encrypt_to_all(&mut rng).unwrap();
+        assert_eq!(
+            participants[0].decrypt(&[1, 3, 4], &decoded, &header),
+            Ok(key.clone())
+        );
+        assert_eq!(
+            participants[1].decrypt_excluding(&[], &decoded, &header),
+            Err(DecryptError::NotInSet)
+        );
+    }
+}
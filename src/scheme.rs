@@ -0,0 +1,159 @@
+//! Broadcast encryption schemes behind one interface.
+//!
+//! [`BroadcastScheme`] covers the life of any broadcast encryption: an authority sets up the
+//! public parameters for `n` participants and issues their keys, an encryptor produces a header
+//! and a key for a set, and the participants in the set recover the key from the header.
+//!
+//! Two schemes implement it. [`Dgs`] is the pairing-based channel of the crate root, whose headers
+//! have two points whatever the set and which anyone holding the parameters encrypts for.
+//! [`crate::subset_difference::SubsetDifference`] is the tree-based scheme of Naor, Naor and
+//! Lotspiech, which only hashes and whose headers grow with the number of participants left
+//! out, but which only the authority encrypts for. The encryptor of a scheme says which: it is
+//! the public parameters for the first, the secret of the authority for the second.
+
+use core::marker::PhantomData;
+
+use ark_ec::PairingEngine;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::error::{DecryptError, EncryptError, SetupError};
+use crate::secret::BroadcasterSecret;
+use crate::{BroadcastChannel, Header, Recipient, SharedKey};
+
+/// Setup, key issuance, encryption and decryption of a broadcast encryption scheme.
+pub trait BroadcastScheme {
+    /// Public parameters, needed to decrypt
+    type Params;
+    /// Secret of the authority issuing the keys
+    type Authority;
+    /// What encrypting takes
+    type Encryptor;
+    /// Key of one participant
+    type Decryptor;
+    /// Header produced for a set
+    type Header;
+    /// Key shared with the set
+    type Key;
+
+    /// Parameters and authority for `n` participants. Fails if `n` is zero.
+    fn setup<R: RngCore + CryptoRng>(
+        n: usize,
+        rng: &mut R,
+    ) -> Result<(Self::Params, Self::Authority), SetupError>;
+
+    /// Key of participant `identifier`, if it is in the channel and `authority` belongs to
+    /// `params`.
+    fn keygen(
+        params: &Self::Params,
+        authority: &Self::Authority,
+        identifier: usize,
+    ) -> Option<Self::Decryptor>;
+
+    /// Encryptor of the channel.
+    fn encryptor(params: &Self::Params, authority: &Self::Authority) -> Self::Encryptor;
+
+    /// Generate a key and its header for `set_recipients`.
+    fn encrypt<R: RngCore + CryptoRng>(
+        encryptor: &Self::Encryptor,
+        set_recipients: &[usize],
+        rng: &mut R,
+    ) -> Result<(Self::Header, Self::Key), EncryptError>;
+
+    /// Recover the key of `header`, encrypted for `set_recipients`.
+    fn decrypt(
+        params: &Self::Params,
+        decryptor: &Self::Decryptor,
+        set_recipients: &[usize],
+        header: &Self::Header,
+    ) -> Result<Self::Key, DecryptError>;
+}
+
+/// The scheme of the crate root, with headers of constant size.
+pub struct Dgs<E: PairingEngine>(PhantomData<E>);
+
+impl<E: PairingEngine> BroadcastScheme for Dgs<E> {
+    type Params = BroadcastChannel<E>;
+    type Authority = BroadcasterSecret<E>;
+    type Encryptor = BroadcastChannel<E>;
+    type Decryptor = Recipient<E>;
+    type Header = Header<E>;
+    type Key = SharedKey<E>;
+
+    fn setup<R: RngCore + CryptoRng>(
+        n: usize,
+        rng: &mut R,
+    ) -> Result<(Self::Params, Self::Authority), SetupError> {
+        let (channel, _, secret) = BroadcastChannel::init_with_secret(n, rng)?;
+        Ok((channel, secret))
+    }
+
+    fn keygen(
+        params: &Self::Params,
+        authority: &Self::Authority,
+        identifier: usize,
+    ) -> Option<Self::Decryptor> {
+        if params.public_key(identifier).is_none() || !authority.belongs_to(params) {
+            return None;
+        }
+        Some(params.issue_key(authority, identifier))
+    }
+
+    fn encryptor(params: &Self::Params, _: &Self::Authority) -> Self::Encryptor {
+        params.clone()
+    }
+
+    fn encrypt<R: RngCore + CryptoRng>(
+        encryptor: &Self::Encryptor,
+        set_recipients: &[usize],
+        rng: &mut R,
+    ) -> Result<(Self::Header, Self::Key), EncryptError> {
+        encryptor.encrypt(set_recipients, rng)
+    }
+
+    fn decrypt(
+        params: &Self::Params,
+        decryptor: &Self::Decryptor,
+        set_recipients: &[usize],
+        header: &Self::Header,
+    ) -> Result<Self::Key, DecryptError> {
+        decryptor.decrypt(set_recipients, params, header)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subset_difference::SubsetDifference;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+    use std::fmt::Debug;
+
+    /// Encrypt for a set with any scheme and check who recovers the key.
+    fn round_trip<S: BroadcastScheme>()
+    where
+        S::Key: Debug + PartialEq,
+    {
+        let mut rng = thread_rng();
+        let (params, authority) = S::setup(6, &mut rng).unwrap();
+        let encryptor = S::encryptor(&params, &authority);
+        let set = [1, 4, 5];
+        let (header, key) = S::encrypt(&encryptor, &set, &mut rng).unwrap();
+        for identifier in 1..=6 {
+            let decryptor = S::keygen(&params, &authority, identifier).unwrap();
+            let decrypted = S::decrypt(&params, &decryptor, &set, &header);
+            if set.contains(&identifier) {
+                assert_eq!(decrypted.as_ref(), Ok(&key));
+            } else {
+                assert_ne!(decrypted.as_ref(), Ok(&key));
+            }
+        }
+        assert!(S::keygen(&params, &authority, 7).is_none());
+        assert!(S::encrypt(&encryptor, &[], &mut rng).is_err());
+    }
+
+    #[test]
+    fn schemes_share_the_interface() {
+        round_trip::<Dgs<Bls12_381>>();
+        round_trip::<SubsetDifference>();
+    }
+}
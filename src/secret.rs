@@ -0,0 +1,473 @@
+//! Secret of the trusted entity, kept to grow channels after the setup.
+//!
+//! [`BroadcastChannel::init_participants`] forgets `alpha` and `gamma` as soon as the keys are
+//! issued, which fixes the number of participants for good. A trusted entity that expects newcomers
+//! sets the channel up with [`BroadcastChannel::init_with_secret`] instead, stores the
+//! [`BroadcasterSecret`], and hands it to [`BroadcastChannel::add_participant`] for every new
+//! participant. The keys already issued stay valid.
+//!
+//! Growing a channel from `n` to `n + 1` participants publishes `alpha^(n+1) P`, which is the point
+//! every header key of the smaller channel is derived from. Headers produced before the channel
+//! grew must be considered readable by anyone, and what they protect re-encrypted if needed.
+//!
+//! [`BroadcastChannel::extend_capacity`] grows a channel from `n` to any `n'` slots at once,
+//! computing only the powers of alpha it lacks, and leaves the new slots unused for
+//! [`BroadcastChannel::join`]. The private key of participant `i` does not depend on the number of
+//! slots, so the keys already issued stay valid. What moves is the position of the point of `i`,
+//! from `n + 1 - i` to `n' + 1 - i`, and with it the key of a header: a header produced at `n`
+//! slots decrypts with the parameters the channel had then, which
+//! [`BroadcastChannel::at_capacity`] takes back out of the grown ones, and not with the grown
+//! parameters. Growing to `n'` publishes `alpha^(n+1) P` as well, with the same consequence for
+//! these headers as above.
+//!
+//! Channels expecting newcomers avoid growing by reserving slots at the setup with
+//! [`BroadcastChannel::init_with_reserve`]. [`BroadcastChannel::join`] issues the key of the next
+//! reserved slot: the parameters, the keys already issued and the format of the headers stay as
+//! they are, so every header produced before stays readable by its recipients and by them only.
+//! Only the number of issued keys changes. Once the reserve is used up, `join` fails and
+//! [`BroadcastChannel::add_participant`] grows the channel as above.
+//!
+//! [`BroadcastChannel::rotate`] replaces gamma, and with it `V` and every private key, for
+//! deployments that renew their keys periodically without running the setup of alpha again.
+//!
+//! The secret allows decrypting every header and deriving every private key. It is zeroized when
+//! dropped, and its encoding must be stored as carefully as the entity would store its signing
+//! keys.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{Field, UniformRand, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use rand_core::{CryptoRng, RngCore};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::encoding::{from_bytes, to_bytes};
+use crate::error::SetupError;
+use crate::events::Event;
+use crate::{BroadcastChannel, KeyPair, Recipient};
+
+/// Channel, participants and secret returned by [`BroadcastChannel::init_with_secret`].
+pub type SecretSetup<E> = (BroadcastChannel<E>, Vec<Recipient<E>>, BroadcasterSecret<E>);
+
+/// `alpha` and `gamma` of a channel.
+pub struct BroadcasterSecret<E: PairingEngine> {
+    pub(crate) alpha: E::Fr,
+    pub(crate) gamma: E::Fr,
+}
+
+impl<E: PairingEngine> BroadcasterSecret<E> {
+    /// Compressed encoding of the secret.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+
+    /// Whether this is the secret `channel` was set up with.
+    pub(crate) fn belongs_to(&self, channel: &BroadcastChannel<E>) -> bool {
+        let mut alpha_p = E::G1Projective::prime_subgroup_generator();
+        alpha_p *= self.alpha;
+        let mut point_v = E::G1Projective::prime_subgroup_generator();
+        point_v *= self.gamma;
+        alpha_p.into_affine() == channel.broadcaster_pk_g1[1] && point_v == channel.point_v
+    }
+}
+
+impl<E: PairingEngine> Drop for BroadcasterSecret<E> {
+    fn drop(&mut self) {
+        self.alpha.zeroize();
+        self.gamma.zeroize();
+    }
+}
+
+impl<E: PairingEngine> ZeroizeOnDrop for BroadcasterSecret<E> {}
+
+impl<E: PairingEngine> CanonicalSerialize for BroadcasterSecret<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.alpha.serialize(&mut writer)?;
+        self.gamma.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.alpha.serialized_size() + self.gamma.serialized_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for BroadcasterSecret<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Ok(BroadcasterSecret {
+            alpha: E::Fr::deserialize(&mut reader)?,
+            gamma: E::Fr::deserialize(&mut reader)?,
+        })
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for BroadcasterSecret<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Issue the key of a new participant, taking the first unused slot of the channel if there
+    /// is one and adding a slot otherwise. Fails if `secret` is not the one the channel was set
+    /// up with. See the [module documentation](self) for what adding a slot reveals.
+    pub fn add_participant(
+        &mut self,
+        secret: &BroadcasterSecret<E>,
+    ) -> Result<Recipient<E>, SetupError> {
+        if !secret.belongs_to(self) {
+            return Err(SetupError::WrongSecret);
+        }
+
+        if self.issued == self.number_participants {
+            self.grow(secret, self.number_participants + 1);
+        }
+        self.join(secret)
+    }
+
+    /// Grow the channel to `number_participants` slots, computing only the powers of alpha it did
+    /// not have yet. The new slots are unused, and [`BroadcastChannel::join`] issues their keys.
+    /// Fails if `secret` is not the one the channel was set up with, or if the channel already
+    /// has more slots. See the [module documentation](self) for what growing reveals, and for the
+    /// headers produced before.
+    pub fn extend_capacity(
+        &mut self,
+        number_participants: usize,
+        secret: &BroadcasterSecret<E>,
+    ) -> Result<(), SetupError> {
+        if !secret.belongs_to(self) {
+            return Err(SetupError::WrongSecret);
+        }
+        if number_participants < self.number_participants {
+            return Err(SetupError::InvalidCapacity(number_participants));
+        }
+
+        if number_participants > self.number_participants {
+            self.grow(secret, number_participants);
+        }
+        Ok(())
+    }
+
+    /// Parameters of the channel as they were when it had `number_participants` slots, before
+    /// it grew, to decrypt the headers produced then. Only the keys issued for these slots are
+    /// kept. Fails if there are no slots, or if the channel has fewer.
+    pub fn at_capacity(&self, number_participants: usize) -> Result<Self, SetupError> {
+        if number_participants == 0 {
+            return Err(SetupError::NoParticipants);
+        }
+        if number_participants > self.number_participants {
+            return Err(SetupError::InvalidCapacity(number_participants));
+        }
+
+        // the powers of alpha are the same at every capacity: the smaller channel has the first
+        // ones, with its own position n + 1 withheld
+        let mut channel = self.clone();
+        let g1 = Arc::make_mut(&mut channel.broadcaster_pk_g1);
+        g1.truncate(2 * number_participants + 1);
+        g1[number_participants + 1] = E::G1Affine::zero();
+        Arc::make_mut(&mut channel.broadcaster_pk_g2).truncate(number_participants + 1);
+
+        channel.number_participants = number_participants;
+        channel.issued = channel.issued.min(number_participants);
+        channel
+            .retired
+            .retain(|identifier| *identifier <= number_participants);
+        channel.refresh_issued_aggregate();
+        channel.refresh_cache_fingerprint();
+        Ok(channel)
+    }
+
+    /// Same as [`BroadcastChannel::init_with_secret`], for `n + reserved` slots of which only the
+    /// first `n` keys are issued. Fails if there are no slots at all.
+    pub fn init_with_reserve<R>(
+        n: usize,
+        reserved: usize,
+        rng: &mut R,
+    ) -> Result<SecretSetup<E>, SetupError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let (mut channel, mut participants, secret) = Self::init_with_secret(n + reserved, rng)?;
+        participants.truncate(n);
+        channel.issued = n;
+        channel.refresh_issued_aggregate();
+        Ok((channel, participants, secret))
+    }
+
+    /// Issue the key of the first unused slot of the channel, without changing its parameters,
+    /// so that every header produced before stays readable by its recipients only. Fails if
+    /// `secret` is not the one the channel was set up with, or if every slot is issued.
+    pub fn join(&mut self, secret: &BroadcasterSecret<E>) -> Result<Recipient<E>, SetupError> {
+        if !secret.belongs_to(self) {
+            return Err(SetupError::WrongSecret);
+        }
+        if self.issued == self.number_participants {
+            return Err(SetupError::NoUnusedSlot);
+        }
+
+        self.issued += 1;
+        self.issued_aggregate
+            .add_assign_mixed(&self.broadcaster_pk_g1[self.number_participants + 1 - self.issued]);
+        Ok(self.issue_key(secret, self.issued))
+    }
+
+    /// Replace gamma by a fresh one and issue new keys to every issued participant whose key is
+    /// not retired, in order of their identifiers. The keys issued before stop working, and so do
+    /// the headers produced before, which have to be produced again. The powers of alpha are
+    /// untouched. Fails if `secret` is not the one the channel was set up with.
+    pub fn rotate<R>(
+        &mut self,
+        secret: &mut BroadcasterSecret<E>,
+        rng: &mut R,
+    ) -> Result<Vec<Recipient<E>>, SetupError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if !secret.belongs_to(self) {
+            return Err(SetupError::WrongSecret);
+        }
+
+        secret.gamma = E::Fr::rand(rng);
+        let mut point_v = E::G1Projective::prime_subgroup_generator();
+        point_v *= secret.gamma;
+        self.point_v = point_v;
+        self.refresh_cache_fingerprint();
+
+        Ok(self
+            .issued()
+            .filter(|identifier| !self.retired.contains(identifier))
+            .map(|identifier| self.issue_key(secret, identifier))
+            .collect())
+    }
+
+    /// Key of participant `identifier`, which the caller has checked to be issued.
+    pub(crate) fn issue_key(
+        &self,
+        secret: &BroadcasterSecret<E>,
+        identifier: usize,
+    ) -> Recipient<E> {
+        let mut private_key = self.broadcaster_pk_g1[identifier].into_projective();
+        private_key *= secret.gamma;
+        self.emit(Event::KeyIssued { identifier });
+
+        Recipient {
+            identifier,
+            key_pair: KeyPair {
+                public_key: self.broadcaster_pk_g2[identifier].into_projective(),
+                private_key,
+            },
+            budget: None,
+        }
+    }
+
+    /// Recompute the fingerprint cached sums are keyed by, after the parameters change.
+    pub(crate) fn refresh_cache_fingerprint(&mut self) {
+        #[cfg(feature = "std")]
+        if let Some((cache, _)) = self.aggregate_cache.take() {
+            let fingerprint = self.view().fingerprint();
+            self.aggregate_cache = Some((cache, fingerprint));
+        }
+    }
+
+    /// Grow the parameters from `n` to `grown` slots, for `grown` above `n`.
+    fn grow(&mut self, secret: &BroadcasterSecret<E>, grown: usize) {
+        let n = self.number_participants;
+
+        // clones of the channel keep the parameters they were made with
+        let g1 = Arc::make_mut(&mut self.broadcaster_pk_g1);
+
+        // the point at position n + 1 is now that of a participant
+        let mut point = g1[n].into_projective();
+        point *= secret.alpha;
+        g1[n + 1] = point.into_affine();
+
+        // the powers continue from position 2n, which is n + 1, filled in above, when n is 1
+        while g1.len() < 2 * grown + 1 {
+            let mut point = g1[g1.len() - 1].into_projective();
+            point *= secret.alpha;
+            g1.push(point.into_affine());
+        }
+        // the point at position grown + 1 is withheld instead
+        g1[grown + 1] = E::G1Affine::zero();
+
+        // Q and alpha Q, of the tables of the encryptions, stay where they are
+        let g2 = Arc::make_mut(&mut self.broadcaster_pk_g2);
+        while g2.len() < grown + 1 {
+            let mut point = g2[g2.len() - 1].into_projective();
+            point *= secret.alpha;
+            g2.push(point.into_affine());
+        }
+
+        // every point of the issued participants moves grown - n powers up
+        self.issued_aggregate *= secret.alpha.pow([(grown - n) as u64]);
+        self.number_participants = grown;
+        self.refresh_cache_fingerprint();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn participants_are_added() {
+        let mut rng = thread_rng();
+        let (mut channel, mut participants, secret) =
+            BroadcastChannel::<Bls12_381>::init_with_secret(1, &mut rng).unwrap();
+        let stored =
+            BroadcasterSecret::<Bls12_381>::try_from(secret.to_bytes().as_slice()).unwrap();
+
+        let before = channel.clone();
+        participants.push(channel.add_participant(&stored).unwrap());
+        participants.push(channel.add_participant(&stored).unwrap());
+        assert!(channel.issued().eq(1..=3));
+        assert!(channel.validate().is_valid());
+        assert_eq!(before.view().capacity(), 1);
+        assert!(before.validate().is_valid());
+
+        let (header, key) = channel.encrypt(&[1, 3], &mut rng).unwrap();
+        assert_eq!(
+            participants[0].decrypt(&[1, 3], &channel, &header),
+            Ok(key.clone())
+        );
+        assert_eq!(
+            participants[2].decrypt(&[1, 3], &channel, &header),
+            Ok(key.clone())
+        );
+        for participant in participants.iter() {
+            assert!(participant.validate(&channel).is_valid());
+        }
+
+        let old = participants[0].clone();
+        let mut secret = stored;
+        let participants = channel.rotate(&mut secret, &mut rng).unwrap();
+        assert_eq!(participants.len(), 3);
+        assert!(channel.validate().is_valid());
+        assert!(!old.validate(&channel).is_valid());
+        let (header, key) = channel.encrypt(&[1, 2], &mut rng).unwrap();
+        assert_eq!(
+            participants[1].decrypt(&[1, 2], &channel, &header),
+            Ok(key.clone())
+        );
+        assert_ne!(old.decrypt(&[1, 2], &channel, &header), Ok(key.clone()));
+        assert!(channel.add_participant(&secret).is_ok());
+
+        // retired participants get no new key
+        channel.retire(2).unwrap();
+        let participants = channel.rotate(&mut secret, &mut rng).unwrap();
+        assert_eq!(
+            participants
+                .iter()
+                .map(Recipient::identifier)
+                .collect::<Vec<_>>(),
+            vec![1, 3, 4]
+        );
+
+        let (_, _, other) = BroadcastChannel::<Bls12_381>::init_with_secret(1, &mut rng).unwrap();
+        assert!(matches!(
+            channel.add_participant(&other),
+            Err(SetupError::WrongSecret)
+        ));
+    }
+
+    #[test]
+    fn reserved_slots_are_joined() {
+        let mut rng = thread_rng();
+        let (mut channel, participants, secret) =
+            BroadcastChannel::<Bls12_381>::init_with_reserve(2, 1, &mut rng).unwrap();
+        assert_eq!(participants.len(), 2);
+        assert!(channel.unused_capacity().eq(3..=3));
+        let fingerprint = channel.view().fingerprint();
+        let (header, key) = channel.encrypt_to_all(&mut rng).unwrap();
+
+        let newcomer = channel.join(&secret).unwrap();
+        assert_eq!(newcomer.identifier, 3);
+        assert_eq!(channel.view().fingerprint(), fingerprint);
+        assert_eq!(
+            participants[1].decrypt(&[1, 2], &channel, &header),
+            Ok(key.clone())
+        );
+        assert_ne!(
+            newcomer.decrypt(&[1, 2, 3], &channel, &header),
+            Ok(key.clone())
+        );
+        let (header, key) = channel.encrypt_to_all(&mut rng).unwrap();
+        assert_eq!(
+            newcomer.decrypt(&[1, 2, 3], &channel, &header),
+            Ok(key.clone())
+        );
+
+        assert!(matches!(
+            channel.join(&secret),
+            Err(SetupError::NoUnusedSlot)
+        ));
+    }
+
+    #[test]
+    fn capacity_is_extended() {
+        let mut rng = thread_rng();
+        let (mut channel, participants, secret) =
+            BroadcastChannel::<Bls12_381>::init_with_secret(2, &mut rng).unwrap();
+        let before = channel.clone();
+        let (old, old_key) = channel.encrypt(&[1, 2], &mut rng).unwrap();
+
+        channel.extend_capacity(5, &secret).unwrap();
+        assert!(channel.validate().is_valid());
+        assert!(channel.unused_capacity().eq(3..=5));
+        let newcomer = channel.join(&secret).unwrap();
+        assert_eq!(newcomer.identifier, 3);
+        let (header, key) = channel.encrypt(&[1, 3], &mut rng).unwrap();
+        assert_eq!(
+            participants[0].decrypt(&[1, 3], &channel, &header),
+            Ok(key.clone())
+        );
+        assert_eq!(
+            newcomer.decrypt(&[1, 3], &channel, &header),
+            Ok(key.clone())
+        );
+
+        // the headers of the two slots decrypt with the parameters taken back to two slots
+        assert_ne!(
+            participants[1].decrypt(&[1, 2], &channel, &old),
+            Ok(old_key.clone())
+        );
+        let smaller = channel.at_capacity(2).unwrap();
+        assert_eq!(smaller.view().fingerprint(), before.view().fingerprint());
+        assert!(smaller.issued().eq(1..=2));
+        assert_eq!(
+            participants[1].decrypt(&[1, 2], &smaller, &old),
+            Ok(old_key.clone())
+        );
+
+        let mut added = before.clone();
+        for _ in 0..3 {
+            added.add_participant(&secret).unwrap();
+        }
+        assert_eq!(added.view().fingerprint(), channel.view().fingerprint());
+
+        assert!(channel.extend_capacity(5, &secret).is_ok());
+        assert!(matches!(
+            channel.extend_capacity(4, &secret),
+            Err(SetupError::InvalidCapacity(4))
+        ));
+        assert!(matches!(
+            channel.at_capacity(6),
+            Err(SetupError::InvalidCapacity(6))
+        ));
+        assert!(matches!(
+            channel.at_capacity(0),
+            Err(SetupError::NoParticipants)
+        ));
+        let (_, _, other) = BroadcastChannel::<Bls12_381>::init_with_secret(1, &mut rng).unwrap();
+        assert!(matches!(
+            channel.extend_capacity(6, &other),
+            Err(SetupError::WrongSecret)
+        ));
+    }
+}
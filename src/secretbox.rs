@@ -0,0 +1,181 @@
+//! Secrets in allocations of their own, locked in memory where possible.
+//!
+//! The private keys and the secret of the broadcaster are zeroized when dropped, but while they
+//! live, the pages holding them may be written to swap, and a stray `{:?}` or clone spreads them
+//! further. A daemon holding the keys of many users keeps them in [`SecretBox`]es instead: each
+//! secret is moved to an allocation of its own, which is zeroized and freed with the box, and the
+//! box is neither `Clone` nor printed by `Debug`. The secret is reached with
+//! [`SecretBox::expose_secret`], so that every use stands out in review.
+//!
+//! With the `mlock` feature, the allocation is aligned to and rounded up to whole pages, and
+//! locked with `mlock`, or `VirtualLock` on Windows, so that it is never swapped out. Pages are
+//! not shared between boxes, since unlocking a page when one box is dropped would unlock it for
+//! every other secret on it. Locking fails past the limit of locked memory of the process, in
+//! which case the box holds the secret unlocked, as [`SecretBox::is_locked`] tells.
+//!
+//! The value given to [`SecretBox::new`] is moved into the box, and the copy it was moved from is
+//! not zeroized: secrets should be boxed as soon as they are created, on a stack that is used
+//! for nothing else.
+
+use alloc::alloc::{alloc, dealloc, handle_alloc_error, Layout};
+use core::fmt;
+use core::marker::PhantomData;
+use core::ptr::{self, NonNull};
+
+use ark_ec::PairingEngine;
+use zeroize::Zeroize;
+
+use crate::secret::BroadcasterSecret;
+use crate::{KeyPair, Recipient};
+
+/// Secret in an allocation of its own, zeroized when dropped.
+pub struct SecretBox<T: Zeroize> {
+    pointer: NonNull<T>,
+    layout: Layout,
+    locked: bool,
+    _owned: PhantomData<T>,
+}
+
+// the box owns its value, as a `Box<T>` would
+unsafe impl<T: Zeroize + Send> Send for SecretBox<T> {}
+unsafe impl<T: Zeroize + Sync> Sync for SecretBox<T> {}
+
+impl<T: Zeroize> SecretBox<T> {
+    /// Move `secret` to an allocation of its own, locked with the `mlock` feature.
+    pub fn new(secret: T) -> Self {
+        let layout = Self::layout();
+        // the layout is never of size zero
+        let pointer = unsafe { alloc(layout) } as *mut T;
+        let pointer = match NonNull::new(pointer) {
+            Some(pointer) => pointer,
+            None => handle_alloc_error(layout),
+        };
+        unsafe { pointer.as_ptr().write(secret) };
+
+        #[cfg(feature = "mlock")]
+        let locked = match region::lock(pointer.as_ptr() as *const u8, layout.size()) {
+            // unlocked explicitly when the box is dropped
+            Ok(guard) => {
+                core::mem::forget(guard);
+                true
+            }
+            Err(_) => false,
+        };
+        #[cfg(not(feature = "mlock"))]
+        let locked = false;
+
+        SecretBox {
+            pointer,
+            layout,
+            locked,
+            _owned: PhantomData,
+        }
+    }
+
+    /// Layout of the allocation of a `T`, in whole pages with the `mlock` feature.
+    fn layout() -> Layout {
+        let size = core::mem::size_of::<T>().max(1);
+        let align = core::mem::align_of::<T>();
+        #[cfg(feature = "mlock")]
+        let (size, align) = {
+            let page = region::page::size();
+            ((size + page - 1) / page * page, align.max(page))
+        };
+        // sizes of types and pages are far from overflowing
+        Layout::from_size_align(size, align).unwrap()
+    }
+
+    /// The secret.
+    pub fn expose_secret(&self) -> &T {
+        unsafe { self.pointer.as_ref() }
+    }
+
+    /// The secret, mutably.
+    pub fn expose_secret_mut(&mut self) -> &mut T {
+        unsafe { self.pointer.as_mut() }
+    }
+
+    /// Whether the pages of the secret are locked in memory. Always false without the `mlock`
+    /// feature.
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+}
+
+impl<T: Zeroize> Drop for SecretBox<T> {
+    fn drop(&mut self) {
+        let pointer = self.pointer.as_ptr();
+        unsafe {
+            (*pointer).zeroize();
+            ptr::drop_in_place(pointer);
+        }
+        #[cfg(feature = "mlock")]
+        if self.locked {
+            // nothing else is on these pages, and the secret is already zeroized
+            let _ = region::unlock(pointer as *const u8, self.layout.size());
+        }
+        unsafe { dealloc(pointer as *mut u8, self.layout) };
+    }
+}
+
+impl<T: Zeroize> fmt::Debug for SecretBox<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretBox(<redacted>)")
+    }
+}
+
+impl<E: PairingEngine> Zeroize for KeyPair<E> {
+    fn zeroize(&mut self) {
+        self.private_key.zeroize();
+    }
+}
+
+impl<E: PairingEngine> Zeroize for Recipient<E> {
+    fn zeroize(&mut self) {
+        self.key_pair.zeroize();
+    }
+}
+
+impl<E: PairingEngine> Zeroize for BroadcasterSecret<E> {
+    fn zeroize(&mut self) {
+        self.alpha.zeroize();
+        self.gamma.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BroadcastChannel;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn boxed_secrets_work_and_are_redacted() {
+        let mut rng = thread_rng();
+        let (mut channel, participants, secret) =
+            BroadcastChannel::<Bls12_381>::init_with_secret(3, &mut rng).unwrap();
+        let secret = SecretBox::new(secret);
+        let mut boxed: Vec<SecretBox<Recipient<Bls12_381>>> =
+            participants.into_iter().map(SecretBox::new).collect();
+        boxed.push(SecretBox::new(
+            channel.add_participant(secret.expose_secret()).unwrap(),
+        ));
+
+        let (header, key) = channel.encrypt(&[2, 4], &mut rng).unwrap();
+        for identifier in [2, 4].iter() {
+            assert_eq!(
+                boxed[identifier - 1]
+                    .expose_secret()
+                    .decrypt(&[2, 4], &channel, &header),
+                Ok(key.clone())
+            );
+        }
+        assert_eq!(format!("{:?}", secret), "SecretBox(<redacted>)");
+        assert!(cfg!(feature = "mlock") || !boxed[0].is_locked());
+
+        let mut key_pair = SecretBox::new(boxed[0].expose_secret().key_pair().clone());
+        key_pair.expose_secret_mut().zeroize();
+        assert!(key_pair.expose_secret() != boxed[0].expose_secret().key_pair());
+    }
+}
@@ -0,0 +1,154 @@
+//! Serde support for the public parameters, headers, keys and sets.
+//!
+//! Every type is serialized as its canonical encoding, so that the bytes stored in a JSON
+//! document or a database are the ones the rest of the crate, and other implementations, read.
+//! Human-readable formats get the encoding as a lowercase hexadecimal string, and binary formats
+//! as a byte string. Deserializing goes through the same checked decoding as the `TryFrom`
+//! implementations, so malformed points are refused there too.
+//!
+//! Only available with the `serde` feature.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+use core::marker::PhantomData;
+
+use ark_ec::PairingEngine;
+use serde::de::{self, SeqAccess, Unexpected, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::encoding::{from_hex, to_hex};
+use crate::kdf::VersionedHeader;
+use crate::set::RecipientSet;
+use crate::{BroadcastChannel, Header, PublicKey, Recipient};
+
+fn serialize_encoding<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() {
+        serializer.serialize_str(&to_hex(bytes))
+    } else {
+        serializer.serialize_bytes(bytes)
+    }
+}
+
+/// Visitor decoding a `T` from its encoding, given as a string or as bytes.
+struct EncodingVisitor<T> {
+    what: &'static str,
+    decoded: PhantomData<T>,
+}
+
+impl<T> EncodingVisitor<T>
+where
+    T: for<'a> TryFrom<&'a [u8]>,
+{
+    fn decode<E: de::Error>(&self, bytes: &[u8]) -> Result<T, E> {
+        T::try_from(bytes).map_err(|_| E::invalid_value(Unexpected::Bytes(bytes), self))
+    }
+}
+
+impl<'de, T> Visitor<'de> for EncodingVisitor<T>
+where
+    T: for<'a> TryFrom<&'a [u8]>,
+{
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "the encoding of {}", self.what)
+    }
+
+    fn visit_str<E: de::Error>(self, hex: &str) -> Result<T, E> {
+        let bytes = from_hex(hex).ok_or_else(|| E::invalid_value(Unexpected::Str(hex), &self))?;
+        self.decode(&bytes)
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<T, E> {
+        self.decode(bytes)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut sequence: A) -> Result<T, A::Error> {
+        let mut bytes = Vec::with_capacity(sequence.size_hint().unwrap_or(0));
+        while let Some(byte) = sequence.next_element::<u8>()? {
+            bytes.push(byte);
+        }
+        self.decode(&bytes)
+    }
+}
+
+fn deserialize_encoding<'de, D, T>(deserializer: D, what: &'static str) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: for<'a> TryFrom<&'a [u8]>,
+{
+    let visitor = EncodingVisitor {
+        what,
+        decoded: PhantomData,
+    };
+    if deserializer.is_human_readable() {
+        deserializer.deserialize_str(visitor)
+    } else {
+        deserializer.deserialize_byte_buf(visitor)
+    }
+}
+
+/// `Serialize` and `Deserialize` for `$type`, through the encoding given by `$encode`.
+macro_rules! impl_serde {
+    ($type:ty, $encode:ident, $what:literal $(, $engine:ident)?) => {
+        impl$(<$engine: PairingEngine>)? Serialize for $type {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serialize_encoding(&self.$encode(), serializer)
+            }
+        }
+
+        impl<'de $(, $engine: PairingEngine)?> Deserialize<'de> for $type {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                deserialize_encoding(deserializer, $what)
+            }
+        }
+    };
+}
+
+impl_serde!(BroadcastChannel<E>, to_bytes, "a broadcast channel", E);
+impl_serde!(Header<E>, to_bytes, "a header", E);
+impl_serde!(VersionedHeader<E>, to_bytes, "a versioned header", E);
+impl_serde!(PublicKey<E>, to_bytes, "a public key", E);
+impl_serde!(Recipient<E>, to_bytes, "a key pair", E);
+impl_serde!(RecipientSet, canonical_bytes, "a set of recipients");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn json_round_trip() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(3, &mut rng).unwrap();
+        let set = RecipientSet::new(&[3, 1]);
+        let (header, key) = channel.encrypt(set.as_slice(), &mut rng).unwrap();
+
+        let hex = to_hex(&header.to_bytes());
+        let json = serde_json::to_string(&header).unwrap();
+        assert_eq!(json, format!("\"{}\"", hex));
+        let header: Header<Bls12_381> = serde_json::from_str(&json).unwrap();
+        let channel: BroadcastChannel<Bls12_381> =
+            serde_json::from_str(&serde_json::to_string(&channel).unwrap()).unwrap();
+        let recipient: Recipient<Bls12_381> =
+            serde_json::from_str(&serde_json::to_string(&participants[2]).unwrap()).unwrap();
+        let decoded: RecipientSet =
+            serde_json::from_str(&serde_json::to_string(&set).unwrap()).unwrap();
+        assert_eq!(decoded, set);
+        assert_eq!(
+            recipient.decrypt(decoded.as_slice(), &channel, &header),
+            Ok(key)
+        );
+
+        // a byte array is accepted as well, but not a truncated or odd-length string
+        let bytes = serde_json::to_string(&set.canonical_bytes()[..]).unwrap();
+        assert_eq!(serde_json::from_str::<RecipientSet>(&bytes).unwrap(), set);
+        for truncated in [&hex[2..], &hex[1..]].iter() {
+            let json = format!("\"{}\"", truncated);
+            assert!(serde_json::from_str::<Header<Bls12_381>>(&json).is_err());
+        }
+    }
+}
@@ -0,0 +1,495 @@
+//! Lifetimes of the encryptor's key material.
+//!
+//! An encryptor handles three kinds of values. The parameters of the [`BroadcastChannel`] are
+//! public and live as long as the channel. An [`EncryptionSession`] borrows them for one set of
+//! recipients and holds the aggregate of that set, which is public too and can serve any number
+//! of messages. An [`EphemeralScalar`] is the secret randomness of a single message: anyone
+//! learning it recovers the key of that header, and using it twice gives two headers with the same
+//! key. It is neither `Clone` nor `Copy`, is consumed by the encryption it belongs to, and is
+//! zeroized when dropped.
+//!
+//! Known-answer tests, and protocols deriving the randomness from a transcript, build the scalar
+//! with [`EphemeralScalar::from_scalar`] and encrypt with
+//! [`BroadcastChannel::encrypt_with_randomness`]. The same scalar and set always give the same
+//! header and key, so the scalar must be as unpredictable as a random one, and never reused.
+//!
+//! Every encryption multiplies the same two points of G2 by the ephemeral scalar, `Q` for `C_1`
+//! and `Q_1` for the key. The channel keeps fixed-base window tables of both, built with its
+//! parameters and shared between its clones, so that each multiplication is about fifty mixed
+//! additions instead of a full double-and-add. The tables take about 300 KiB each on BLS12-381.
+//! The point of G1 multiplied in `C_0` is `V` plus the aggregate of the set, which changes with
+//! the set, so it gets no table: one for `V` alone would add a multiplication rather than
+//! replace one.
+//!
+//! [`BroadcastChannel::encrypt_many`] encrypts for many sets at once. The key of a header is
+//! `e(P_n, Q_1)^k` whatever the set, so that pairing is computed once for all of them, and with
+//! the `parallel` feature the sets are spread over the global [`rayon`] pool.
+
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+
+use ark_ec::msm::FixedBaseMSM;
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{Field, PrimeField, UniformRand, Zero};
+use rand_core::{CryptoRng, RngCore};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::error::EncryptError;
+use crate::events::{self, Event};
+use crate::set::{self, RecipientSet};
+use crate::{BroadcastChannel, Header, SharedKey};
+
+/// Header and key of one of the sets of [`BroadcastChannel::encrypt_many`].
+pub type SetEncryption<E> = (Header<E>, SharedKey<E>);
+
+/// Width in bits of the windows of the tables of `Q` and `Q_1`.
+const TABLE_WINDOW: usize = 5;
+
+/// Fixed-base window tables of `Q` and `Q_1`, the points of G2 multiplied in every encryption.
+pub(crate) struct EncryptionTables<E: PairingEngine> {
+    q: Vec<Vec<E::G2Affine>>,
+    q_1: Vec<Vec<E::G2Affine>>,
+}
+
+impl<E: PairingEngine> EncryptionTables<E> {
+    /// Tables of the first two of `points_g2`, the powers of alpha in G2 of a channel. Parameters
+    /// too short to encrypt for anyone get tables of the identity.
+    pub(crate) fn new(points_g2: &[E::G2Affine]) -> Self {
+        let table = |index: usize| {
+            let point = points_g2.get(index).copied().unwrap_or_else(Zero::zero);
+            FixedBaseMSM::get_window_table(
+                E::Fr::size_in_bits(),
+                TABLE_WINDOW,
+                point.into_projective(),
+            )
+        };
+        EncryptionTables {
+            q: table(0),
+            q_1: table(1),
+        }
+    }
+
+    fn multiply(table: &[Vec<E::G2Affine>], k: &E::Fr) -> E::G2Projective {
+        let outer = (E::Fr::size_in_bits() + TABLE_WINDOW - 1) / TABLE_WINDOW;
+        FixedBaseMSM::windowed_mul(outer, TABLE_WINDOW, table, k)
+    }
+}
+
+/// Randomness of a single encryption.
+pub struct EphemeralScalar<E: PairingEngine> {
+    pub(crate) k: E::Fr,
+}
+
+impl<E: PairingEngine> EphemeralScalar<E> {
+    /// Fresh scalar drawn from `rng`.
+    pub fn random<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        EphemeralScalar {
+            k: E::Fr::rand(rng),
+        }
+    }
+
+    /// Scalar `k`, chosen by the caller. Returns `None` if `k` is zero, which would give a header
+    /// made of identities.
+    pub fn from_scalar(k: E::Fr) -> Option<Self> {
+        if k.is_zero() {
+            return None;
+        }
+        Some(EphemeralScalar { k })
+    }
+}
+
+impl<E: PairingEngine> Drop for EphemeralScalar<E> {
+    fn drop(&mut self) {
+        self.k.zeroize();
+    }
+}
+
+impl<E: PairingEngine> ZeroizeOnDrop for EphemeralScalar<E> {}
+
+/// Encryptions for one set of recipients, borrowing the parameters of the channel.
+pub struct EncryptionSession<'a, E: PairingEngine> {
+    channel: &'a BroadcastChannel<E>,
+    /// reported for every encryption
+    event: Event,
+    /// `V` plus the points of the recipients, multiplied by the ephemeral scalar in `ctx_0`
+    base: E::G1Projective,
+    /// recipients of the session, unless it was built from a stream
+    recipients: Option<BTreeSet<usize>>,
+}
+
+impl<'a, E: PairingEngine> EncryptionSession<'a, E> {
+    /// Session over the parameters of `channel` for a set with aggregate `aggregate`, whose
+    /// identifiers are `recipients` if they are known.
+    pub(crate) fn new(
+        channel: &'a BroadcastChannel<E>,
+        aggregate: E::G1Projective,
+        event: Event,
+        recipients: Option<BTreeSet<usize>>,
+    ) -> Self {
+        EncryptionSession {
+            channel,
+            event,
+            base: channel.point_v + aggregate,
+            recipients,
+        }
+    }
+
+    /// Add participant `identifier` to the set of the session, with a single point addition.
+    /// Fails if it is outside of the channel, already in the set or retired, if the set would
+    /// break the policy, or if the session was built from a stream.
+    pub fn add_recipient(&mut self, identifier: usize) -> Result<(), EncryptError> {
+        let channel = self.channel;
+        let recipients = self
+            .recipients
+            .as_mut()
+            .ok_or(EncryptError::StreamedSession)?;
+        if identifier == 0 || identifier > channel.number_participants {
+            return Err(EncryptError::IdentifierOutOfRange(identifier));
+        }
+        if recipients.contains(&identifier) {
+            return Err(EncryptError::DuplicateRecipient(identifier));
+        }
+        channel.check_not_retired(&[identifier])?;
+        match channel.policy.max_set_size() {
+            Some(limit) if recipients.len() >= limit => {
+                return Err(EncryptError::SetTooLarge {
+                    size: recipients.len() + 1,
+                    limit,
+                })
+            }
+            _ => {}
+        }
+
+        recipients.insert(identifier);
+        self.base.add_assign_mixed(
+            &channel.broadcaster_pk_g1[channel.number_participants + 1 - identifier],
+        );
+        self.refresh_event();
+        Ok(())
+    }
+
+    /// Remove participant `identifier` from the set of the session, with a single point
+    /// addition. Fails if it is not in the set, if it is the last recipient, or if the session was
+    /// built from a stream.
+    pub fn remove_recipient(&mut self, identifier: usize) -> Result<(), EncryptError> {
+        let channel = self.channel;
+        let recipients = self
+            .recipients
+            .as_mut()
+            .ok_or(EncryptError::StreamedSession)?;
+        if !recipients.contains(&identifier) {
+            return Err(EncryptError::NotInSession(identifier));
+        }
+        if recipients.len() == 1 {
+            return Err(EncryptError::EmptySet);
+        }
+
+        recipients.remove(&identifier);
+        self.base.add_assign_mixed(
+            &-channel.broadcaster_pk_g1[channel.number_participants + 1 - identifier],
+        );
+        self.refresh_event();
+        Ok(())
+    }
+
+    /// Report the set of the session as it is now.
+    fn refresh_event(&mut self) {
+        if let Some(recipients) = &self.recipients {
+            let recipients: Vec<usize> = recipients.iter().copied().collect();
+            self.event = Event::Encrypted {
+                set_hash: events::set_hash(&recipients),
+            };
+        }
+    }
+
+    /// Hash of the set of the session, as reported in the [`Event::Encrypted`] events. Sessions
+    /// built from a stream have none.
+    pub fn set_hash(&self) -> Option<[u8; 32]> {
+        match self.event {
+            Event::Encrypted { set_hash } => Some(set_hash),
+            _ => None,
+        }
+    }
+
+    /// Generate a symmetric key and its header for the set of the session, with a fresh
+    /// ephemeral scalar.
+    pub fn encrypt<R>(&self, rng: &mut R) -> (Header<E>, SharedKey<E>)
+    where
+        R: RngCore + CryptoRng,
+    {
+        self.encrypt_with(EphemeralScalar::random(rng))
+    }
+
+    /// Same as [`EncryptionSession::encrypt`], with the given ephemeral scalar, which is consumed.
+    pub fn encrypt_with(&self, ephemeral: EphemeralScalar<E>) -> (Header<E>, SharedKey<E>) {
+        let parameters = self.channel;
+        let n = parameters.number_participants;
+
+        let g_2_point =
+            EncryptionTables::<E>::multiply(&parameters.encryption_tables.q_1, &ephemeral.k);
+        let K = in_span!(
+            "encrypt.pairing",
+            E::pairing(parameters.broadcaster_pk_g1[n], g_2_point)
+        );
+        self.header_for(&ephemeral, K)
+    }
+
+    /// Header of the session for `ephemeral`, whose key `key` the caller has computed.
+    fn header_for(&self, ephemeral: &EphemeralScalar<E>, key: E::Fqk) -> (Header<E>, SharedKey<E>) {
+        let parameters = self.channel;
+        let header_point_in_g2 =
+            EncryptionTables::<E>::multiply(&parameters.encryption_tables.q, &ephemeral.k);
+        let mut header_point_in_g1 = self.base;
+        header_point_in_g1 *= ephemeral.k;
+
+        parameters.emit(self.event);
+
+        let header = Header {
+            ctx_0: header_point_in_g1,
+            ctx_1: header_point_in_g2,
+        };
+        (header, SharedKey::new(key))
+    }
+}
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Session encrypting for `set_recipients`. Fails if the set is empty, contains identifiers
+    /// outside of the channel, more than once or whose keys are retired, or breaks its policy.
+    pub fn session(
+        &self,
+        set_recipients: &[usize],
+    ) -> Result<EncryptionSession<'_, E>, EncryptError> {
+        self.observed_session(set_recipients, || ())
+    }
+
+    /// Same as [`BroadcastChannel::session`], calling `summed` when the aggregate is summed
+    /// rather than taken from the cache.
+    pub(crate) fn observed_session<F: FnOnce()>(
+        &self,
+        set_recipients: &[usize],
+        summed: F,
+    ) -> Result<EncryptionSession<'_, E>, EncryptError> {
+        self.check_encryption_set(set_recipients)?;
+
+        let aggregate = in_span!(
+            "encrypt.aggregate",
+            self.aggregate(set_recipients, 0, || {
+                summed();
+                let mut sum = E::G1Projective::zero();
+                for index in set_recipients.iter() {
+                    sum.add_assign_mixed(
+                        &self.broadcaster_pk_g1[self.number_participants + 1 - index],
+                    );
+                }
+                sum
+            })
+        );
+
+        let event = Event::Encrypted {
+            set_hash: events::set_hash(set_recipients),
+        };
+        let recipients = set_recipients.iter().copied().collect();
+        Ok(EncryptionSession::new(
+            self,
+            aggregate,
+            event,
+            Some(recipients),
+        ))
+    }
+
+    /// Check that `set_recipients` can be encrypted for, as described in
+    /// [`BroadcastChannel::session`].
+    pub(crate) fn check_encryption_set(
+        &self,
+        set_recipients: &[usize],
+    ) -> Result<(), EncryptError> {
+        set::check_encryption_set(
+            self.number_participants,
+            set_recipients,
+            |identifier| self.retired.contains(&identifier),
+            &self.policy,
+        )
+    }
+
+    /// Same as [`BroadcastChannel::encrypt`], with the given ephemeral scalar, which is consumed.
+    pub fn encrypt_with_randomness(
+        &self,
+        set_recipients: &[usize],
+        ephemeral: EphemeralScalar<E>,
+    ) -> Result<(Header<E>, SharedKey<E>), EncryptError> {
+        Ok(self.session(set_recipients)?.encrypt_with(ephemeral))
+    }
+
+    /// Headers and keys for each of `sets`, in the same order, with fresh ephemeral scalars.
+    /// Fails, encrypting nothing, if one of the sets is refused by [`BroadcastChannel::session`].
+    pub fn encrypt_many<R>(
+        &self,
+        sets: &[RecipientSet],
+        rng: &mut R,
+    ) -> Result<Vec<SetEncryption<E>>, EncryptError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let sessions = sets
+            .iter()
+            .map(|set| self.session(set.as_slice()))
+            .collect::<Result<Vec<_>, _>>()?;
+        // drawn here, so the generator need not be shared between threads
+        let ephemerals: Vec<EphemeralScalar<E>> = sessions
+            .iter()
+            .map(|_| EphemeralScalar::random(rng))
+            .collect();
+        let key_base = E::pairing(
+            self.broadcaster_pk_g1[self.number_participants],
+            self.broadcaster_pk_g2[1],
+        );
+
+        #[cfg(feature = "parallel")]
+        let pairs = sessions.par_iter().zip(ephemerals.into_par_iter());
+        #[cfg(not(feature = "parallel"))]
+        let pairs = sessions.iter().zip(ephemerals);
+        Ok(pairs
+            .map(|(session, ephemeral)| {
+                let key = key_base.pow(ephemeral.k.into_repr());
+                session.header_for(&ephemeral, key)
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn sessions_draw_fresh_scalars() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(4, &mut rng).unwrap();
+        let session = channel.session(&[1, 4]).unwrap();
+
+        let (first, first_key) = session.encrypt(&mut rng);
+        let (second, second_key) = session.encrypt(&mut rng);
+        assert!(first != second);
+        assert_ne!(first_key, second_key);
+        for (header, key) in [(first, first_key), (second, second_key)].iter() {
+            assert_eq!(
+                participants[3].decrypt(&[1, 4], &channel, header),
+                Ok(key.clone())
+            );
+        }
+
+        assert!(matches!(
+            channel.session(&[0]),
+            Err(EncryptError::IdentifierOutOfRange(0))
+        ));
+    }
+
+    #[test]
+    fn sets_are_updated_in_place() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(5, &mut rng).unwrap();
+        let mut session = channel.session(&[1, 4]).unwrap();
+
+        session.add_recipient(2).unwrap();
+        session.remove_recipient(1).unwrap();
+        assert_eq!(session.set_hash(), Some(events::set_hash(&[2, 4])));
+        let (header, key) = session.encrypt(&mut rng);
+        assert_eq!(
+            participants[1].decrypt(&[2, 4], &channel, &header),
+            Ok(key.clone())
+        );
+        assert_ne!(
+            participants[0].decrypt(&[1, 2, 4], &channel, &header),
+            Ok(key.clone())
+        );
+
+        assert_eq!(
+            session.add_recipient(4),
+            Err(EncryptError::DuplicateRecipient(4))
+        );
+        assert_eq!(
+            session.remove_recipient(3),
+            Err(EncryptError::NotInSession(3))
+        );
+        assert_eq!(
+            session.add_recipient(6),
+            Err(EncryptError::IdentifierOutOfRange(6))
+        );
+        session.remove_recipient(2).unwrap();
+        assert_eq!(session.remove_recipient(4), Err(EncryptError::EmptySet));
+    }
+
+    #[test]
+    fn chosen_scalars_give_the_same_header() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(3, &mut rng).unwrap();
+        let k = <Bls12_381 as PairingEngine>::Fr::rand(&mut rng);
+
+        let ephemeral = EphemeralScalar::from_scalar(k).unwrap();
+        let (header, key) = channel.encrypt_with_randomness(&[1, 3], ephemeral).unwrap();
+        let ephemeral = EphemeralScalar::from_scalar(k).unwrap();
+        let (same, same_key) = channel.encrypt_with_randomness(&[1, 3], ephemeral).unwrap();
+        assert!(header == same);
+        assert_eq!(key, same_key);
+        assert_eq!(
+            participants[0].decrypt(&[1, 3], &channel, &header),
+            Ok(key.clone())
+        );
+
+        assert!(EphemeralScalar::<Bls12_381>::from_scalar(Zero::zero()).is_none());
+    }
+
+    #[test]
+    fn many_sets_are_encrypted_in_order() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(4, &mut rng).unwrap();
+        let sets = [
+            RecipientSet::new(&[1, 2]),
+            RecipientSet::new(&[3]),
+            RecipientSet::new(&[2, 3, 4]),
+        ];
+
+        let encrypted = channel.encrypt_many(&sets, &mut rng).unwrap();
+        assert_eq!(encrypted.len(), 3);
+        for (set, (header, key)) in sets.iter().zip(encrypted.iter()) {
+            let recipient = &participants[set.as_slice()[0] - 1];
+            assert_eq!(
+                recipient.decrypt(set.as_slice(), &channel, header),
+                Ok(key.clone())
+            );
+        }
+
+        let refused = [RecipientSet::new(&[1]), RecipientSet::new(&[5])];
+        assert!(matches!(
+            channel.encrypt_many(&refused, &mut rng),
+            Err(EncryptError::IdentifierOutOfRange(5))
+        ));
+    }
+
+    #[test]
+    fn tables_multiply_like_the_points() {
+        let mut rng = thread_rng();
+        let (channel, _) = BroadcastChannel::<Bls12_381>::init_participants(3, &mut rng).unwrap();
+        let tables = &channel.encryption_tables;
+        for _ in 0..4 {
+            let k = <Bls12_381 as PairingEngine>::Fr::rand(&mut rng);
+            let mut q = channel.broadcaster_pk_g2[0].into_projective();
+            q *= k;
+            let mut q_1 = channel.broadcaster_pk_g2[1].into_projective();
+            q_1 *= k;
+            assert_eq!(EncryptionTables::<Bls12_381>::multiply(&tables.q, &k), q);
+            assert_eq!(
+                EncryptionTables::<Bls12_381>::multiply(&tables.q_1, &k),
+                q_1
+            );
+        }
+    }
+}
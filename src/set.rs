@@ -0,0 +1,295 @@
+//! Canonical sets of recipients.
+//!
+//! The same audience can be described by many slices of identifiers, in any order and with
+//! repetitions. A [`RecipientSet`] is sorted and free of repetitions, and its
+//! [`RecipientSet::canonical_bytes`] are what commitments, key derivations and cache keys must be
+//! computed over, so that two parties describing the same audience agree byte for byte.
+//!
+//! The encoding is the version byte, the number of identifiers as a little-endian `u64`, and then
+//! every identifier as a little-endian `u64`, in increasing order.
+//!
+//! Identifiers are 1-based: the participants of a channel of `n` are `1..=n`, as in the paper,
+//! and 0 must never address the generator at position 0 of the parameters. The APIs still take
+//! identifiers as `usize`, as the paper and the wire formats do, and check them where they use
+//! them. A [`RecipientId`] carries the check in its type, for callers who would rather validate
+//! identifiers once, when they enter the application, than at every call.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::iter::FromIterator;
+use core::num::NonZeroUsize;
+
+use ark_ec::PairingEngine;
+use ark_serialize::SerializationError;
+
+use crate::error::{DecryptError, EncryptError};
+use crate::policy::Policy;
+use crate::{BroadcastChannel, Recipient};
+
+/// Identifier of a participant, never 0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RecipientId(NonZeroUsize);
+
+impl RecipientId {
+    /// Identifier `identifier`, if it is not 0.
+    pub fn new(identifier: usize) -> Option<Self> {
+        NonZeroUsize::new(identifier).map(RecipientId)
+    }
+
+    /// Identifier as a number, at least 1.
+    pub fn get(self) -> usize {
+        self.0.get()
+    }
+}
+
+impl From<RecipientId> for usize {
+    fn from(identifier: RecipientId) -> usize {
+        identifier.get()
+    }
+}
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Identifier `identifier`, if it is within the capacity of the channel.
+    pub fn recipient_id(&self, identifier: usize) -> Option<RecipientId> {
+        if identifier > self.number_participants {
+            return None;
+        }
+        RecipientId::new(identifier)
+    }
+}
+
+impl<E: PairingEngine> Recipient<E> {
+    /// Identifier of the recipient, as a [`RecipientId`]. `None` for a recipient with identifier
+    /// 0, which no channel issues.
+    pub fn id(&self) -> Option<RecipientId> {
+        RecipientId::new(self.identifier)
+    }
+}
+
+/// Version of the canonical encoding.
+pub const SET_ENCODING_VERSION: u8 = 1;
+
+/// Sorted set of recipient identifiers, without repetitions.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RecipientSet {
+    identifiers: Vec<usize>,
+}
+
+/// Smallest identifier appearing more than once in `identifiers`.
+pub(crate) fn first_repetition(identifiers: &[usize]) -> Option<usize> {
+    let mut sorted = identifiers.to_vec();
+    sorted.sort_unstable();
+    sorted
+        .windows(2)
+        .find(|pair| pair[0] == pair[1])
+        .map(|pair| pair[0])
+}
+
+/// Check that `set_recipients` can be encrypted for in a channel of `number_participants`, whose
+/// retired keys are those for which `is_retired` holds, under `policy`.
+pub(crate) fn check_encryption_set(
+    number_participants: usize,
+    set_recipients: &[usize],
+    is_retired: impl Fn(usize) -> bool,
+    policy: &Policy,
+) -> Result<(), EncryptError> {
+    if let Some(index) = set_recipients
+        .iter()
+        .find(|index| **index == 0 || **index > number_participants)
+    {
+        return Err(EncryptError::IdentifierOutOfRange(*index));
+    }
+    if set_recipients.is_empty() {
+        return Err(EncryptError::EmptySet);
+    }
+    if let Some(identifier) = first_repetition(set_recipients) {
+        return Err(EncryptError::DuplicateRecipient(identifier));
+    }
+    if let Some(identifier) = set_recipients
+        .iter()
+        .find(|identifier| is_retired(**identifier))
+    {
+        return Err(EncryptError::RetiredRecipient(*identifier));
+    }
+    policy.check(set_recipients)
+}
+
+/// Check that `identifier` can decrypt headers for `set_recipients` in a channel of
+/// `number_participants`.
+pub(crate) fn check_decryption_set(
+    number_participants: usize,
+    identifier: usize,
+    set_recipients: &[usize],
+) -> Result<(), DecryptError> {
+    if let Some(index) = set_recipients
+        .iter()
+        .find(|index| **index == 0 || **index > number_participants)
+    {
+        return Err(DecryptError::IdentifierOutOfRange(*index));
+    }
+    if let Some(identifier) = first_repetition(set_recipients) {
+        return Err(DecryptError::DuplicateRecipient(identifier));
+    }
+    if !set_recipients.contains(&identifier) {
+        return Err(DecryptError::NotInSet);
+    }
+
+    Ok(())
+}
+
+impl RecipientSet {
+    /// Set of the identifiers in `identifiers`, in any order and possibly repeated.
+    pub fn new(identifiers: &[usize]) -> Self {
+        identifiers.iter().copied().collect()
+    }
+
+    /// Identifiers of the set, in increasing order.
+    pub fn as_slice(&self) -> &[usize] {
+        &self.identifiers
+    }
+
+    /// Number of recipients.
+    pub fn len(&self) -> usize {
+        self.identifiers.len()
+    }
+
+    /// Whether the set is empty.
+    pub fn is_empty(&self) -> bool {
+        self.identifiers.is_empty()
+    }
+
+    /// Whether `identifier` is in the set.
+    pub fn contains(&self, identifier: usize) -> bool {
+        self.identifiers.binary_search(&identifier).is_ok()
+    }
+
+    /// Identifiers of the set, in increasing order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.identifiers.iter().copied()
+    }
+
+    /// Identifiers of the set as [`RecipientId`]s, in increasing order, skipping 0 if a set built
+    /// from raw identifiers contains it.
+    pub fn ids(&self) -> impl Iterator<Item = RecipientId> + '_ {
+        self.iter().filter_map(RecipientId::new)
+    }
+
+    /// Canonical encoding of the set.
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(1 + 8 * (self.len() + 1));
+        bytes.push(SET_ENCODING_VERSION);
+        bytes.extend_from_slice(&(self.len() as u64).to_le_bytes());
+        for identifier in self.iter() {
+            bytes.extend_from_slice(&(identifier as u64).to_le_bytes());
+        }
+        bytes
+    }
+}
+
+impl FromIterator<usize> for RecipientSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut identifiers: Vec<usize> = iter.into_iter().collect();
+        identifiers.sort_unstable();
+        identifiers.dedup();
+        RecipientSet { identifiers }
+    }
+}
+
+impl FromIterator<RecipientId> for RecipientSet {
+    fn from_iter<I: IntoIterator<Item = RecipientId>>(iter: I) -> Self {
+        iter.into_iter().map(RecipientId::get).collect()
+    }
+}
+
+impl AsRef<[usize]> for RecipientSet {
+    fn as_ref(&self) -> &[usize] {
+        &self.identifiers
+    }
+}
+
+impl TryFrom<&[u8]> for RecipientSet {
+    type Error = SerializationError;
+
+    /// Decode canonical bytes. Anything that [`RecipientSet::canonical_bytes`] would not have
+    /// produced is rejected, including unsorted or repeated identifiers.
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        let (version, bytes) = bytes.split_first().ok_or(SerializationError::InvalidData)?;
+        if *version != SET_ENCODING_VERSION || bytes.len() % 8 != 0 || bytes.is_empty() {
+            return Err(SerializationError::InvalidData);
+        }
+
+        let mut words = bytes.chunks(8).map(|chunk| {
+            let mut word = [0u8; 8];
+            word.copy_from_slice(chunk);
+            u64::from_le_bytes(word)
+        });
+        // the length is checked to be non-zero above
+        let length = words.next().unwrap();
+        let identifiers = words
+            .map(|word| usize::try_from(word).map_err(|_| SerializationError::InvalidData))
+            .collect::<Result<Vec<usize>, _>>()?;
+
+        let canonical = identifiers.windows(2).all(|pair| pair[0] < pair[1]);
+        if identifiers.len() as u64 != length || !canonical {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(RecipientSet { identifiers })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn encoding_is_canonical() {
+        let set = RecipientSet::new(&[5, 1, 3, 1]);
+        assert_eq!(set.as_slice(), &[1, 3, 5]);
+        assert_eq!(
+            set.canonical_bytes(),
+            RecipientSet::new(&[3, 5, 1]).canonical_bytes()
+        );
+        assert!(set.contains(3));
+        assert!(!set.contains(2));
+
+        let bytes = set.canonical_bytes();
+        assert_eq!(bytes.len(), 1 + 8 * 4);
+        assert_eq!(RecipientSet::try_from(bytes.as_slice()).unwrap(), set);
+        let empty = RecipientSet::default().canonical_bytes();
+        assert!(RecipientSet::try_from(empty.as_slice()).unwrap().is_empty());
+
+        let mut unsorted = vec![SET_ENCODING_VERSION];
+        for word in [2u64, 3, 1].iter() {
+            unsorted.extend_from_slice(&word.to_le_bytes());
+        }
+        assert!(RecipientSet::try_from(unsorted.as_slice()).is_err());
+
+        let mut wrong_version = bytes.clone();
+        wrong_version[0] = 0;
+        assert!(RecipientSet::try_from(wrong_version.as_slice()).is_err());
+        assert!(RecipientSet::try_from(&bytes[..bytes.len() - 8]).is_err());
+    }
+
+    #[test]
+    fn recipient_ids_are_validated() {
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(3, &mut thread_rng()).unwrap();
+        assert_eq!(RecipientId::new(0), None);
+        assert_eq!(channel.recipient_id(0), None);
+        assert_eq!(channel.recipient_id(4), None);
+        let id = channel.recipient_id(3).unwrap();
+        assert_eq!(participants[2].id(), Some(id));
+        assert_eq!(usize::from(id), 3);
+
+        let set: RecipientSet = participants
+            .iter()
+            .filter_map(Recipient::id)
+            .rev()
+            .collect();
+        assert_eq!(set.as_slice(), &[1, 2, 3]);
+        assert!(set.ids().eq(participants.iter().filter_map(Recipient::id)));
+        assert_eq!(RecipientSet::new(&[0, 2]).ids().count(), 1);
+    }
+}
@@ -0,0 +1,280 @@
+//! BLS signatures over the groups of the pairing.
+//!
+//! Operators of channels sign statements about them, such as the fingerprints of the channels
+//! they vouch for. These are BLS signatures: a [`SigningKey`] is a scalar `x`, its
+//! [`VerifyingKey`] is `xQ` in G2, and the signature of a message is `x H(m)` in G1, with `H` the
+//! hash to G1 of [`crate::hash`]. A signature is checked with `e(sigma, Q) = e(H(m), xQ)`.
+//!
+//! Signatures of many messages under one key are checked together by
+//! [`VerifyingKey::verify_batch`], which combines them with random scalars `r_i` into the single
+//! check `e(sum r_i sigma_i, Q) = e(sum r_i H(m_i), xQ)`, computed as one multi-pairing. When it
+//! fails, the batch is split in halves until the wrong signatures are isolated, as for the keys
+//! of [`crate::batch`].
+//!
+//! Signing keys are unrelated to the secrets of the channels, and are zeroized when dropped.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use ark_ec::{PairingEngine, ProjectiveCurve};
+use ark_ff::{One, UniformRand, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use rand_core::{CryptoRng, RngCore};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::encoding::{from_bytes, to_bytes};
+use crate::hash::hash_to_g1;
+
+/// Domain of the hashes of signed messages.
+const SIGNATURE_DOMAIN: &[u8] = b"DGS-BE-SIGNATURE-V1";
+
+/// Secret key signing messages.
+pub struct SigningKey<E: PairingEngine> {
+    secret: E::Fr,
+}
+
+/// Public key checking the signatures of a [`SigningKey`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct VerifyingKey<E: PairingEngine> {
+    point: E::G2Projective,
+}
+
+/// Signature of a message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Signature<E: PairingEngine> {
+    point: E::G1Projective,
+}
+
+impl<E: PairingEngine> SigningKey<E> {
+    /// Fresh signing key.
+    pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        SigningKey {
+            secret: E::Fr::rand(rng),
+        }
+    }
+
+    /// Public key of this signing key.
+    pub fn verifying_key(&self) -> VerifyingKey<E> {
+        let mut point = E::G2Projective::prime_subgroup_generator();
+        point *= self.secret;
+        VerifyingKey { point }
+    }
+
+    /// Signature of `message`.
+    pub fn sign(&self, message: &[u8]) -> Signature<E> {
+        let mut point = hash_to_g1::<E>(SIGNATURE_DOMAIN, message);
+        point *= self.secret;
+        Signature { point }
+    }
+}
+
+impl<E: PairingEngine> Drop for SigningKey<E> {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+impl<E: PairingEngine> ZeroizeOnDrop for SigningKey<E> {}
+
+impl<E: PairingEngine> VerifyingKey<E> {
+    /// Whether `signature` is a signature of `message` under this key.
+    pub fn verify(&self, message: &[u8], signature: &Signature<E>) -> bool {
+        if self.point.is_zero() || signature.point.is_zero() {
+            return false;
+        }
+        let hash = hash_to_g1::<E>(SIGNATURE_DOMAIN, message);
+        E::pairing(signature.point, E::G2Projective::prime_subgroup_generator())
+            == E::pairing(hash, self.point)
+    }
+
+    /// Positions of the pairs of `signed` whose signature is not a signature of their message
+    /// under this key, in increasing order.
+    pub fn verify_batch<M, R>(&self, signed: &[(M, Signature<E>)], rng: &mut R) -> Vec<usize>
+    where
+        M: AsRef<[u8]>,
+        R: RngCore + CryptoRng,
+    {
+        if self.point.is_zero() {
+            return (0..signed.len()).collect();
+        }
+        let mut failed = Vec::new();
+        let mut candidates = Vec::with_capacity(signed.len());
+        for (position, (_, signature)) in signed.iter().enumerate() {
+            if signature.point.is_zero() {
+                failed.push(position);
+            } else {
+                candidates.push(position);
+            }
+        }
+
+        let hashes: Vec<E::G1Projective> = signed
+            .iter()
+            .map(|(message, _)| hash_to_g1::<E>(SIGNATURE_DOMAIN, message.as_ref()))
+            .collect();
+        let weights: Vec<E::Fr> = (0..signed.len()).map(|_| E::Fr::rand(rng)).collect();
+        self.isolate_failures(signed, &hashes, &weights, &candidates, &mut failed);
+        failed.sort_unstable();
+        failed
+    }
+
+    /// Add to `failed` the positions in `batch` whose signatures fail, splitting `batch` until
+    /// they are isolated.
+    fn isolate_failures<M>(
+        &self,
+        signed: &[(M, Signature<E>)],
+        hashes: &[E::G1Projective],
+        weights: &[E::Fr],
+        batch: &[usize],
+        failed: &mut Vec<usize>,
+    ) {
+        if batch.is_empty() || self.batch_holds(signed, hashes, weights, batch) {
+            return;
+        }
+        if batch.len() == 1 {
+            failed.push(batch[0]);
+            return;
+        }
+        let (left, right) = batch.split_at(batch.len() / 2);
+        self.isolate_failures(signed, hashes, weights, left, failed);
+        self.isolate_failures(signed, hashes, weights, right, failed);
+    }
+
+    /// Whether `e(sum r_i sigma_i, -Q) e(sum r_i H(m_i), xQ) = 1` over the positions in `batch`.
+    fn batch_holds<M>(
+        &self,
+        signed: &[(M, Signature<E>)],
+        hashes: &[E::G1Projective],
+        weights: &[E::Fr],
+        batch: &[usize],
+    ) -> bool {
+        let mut signature_sum = E::G1Projective::zero();
+        let mut hash_sum = E::G1Projective::zero();
+        for position in batch.iter() {
+            let mut signature = signed[*position].1.point;
+            signature *= weights[*position];
+            signature_sum += signature;
+            let mut hash = hashes[*position];
+            hash *= weights[*position];
+            hash_sum += hash;
+        }
+
+        let pairs = [
+            (
+                signature_sum.into_affine().into(),
+                (-E::G2Projective::prime_subgroup_generator())
+                    .into_affine()
+                    .into(),
+            ),
+            (
+                hash_sum.into_affine().into(),
+                self.point.into_affine().into(),
+            ),
+        ];
+        E::product_of_pairings(pairs.iter()).is_one()
+    }
+
+    /// Compressed encoding of the key.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+}
+
+impl<E: PairingEngine> Signature<E> {
+    /// Compressed encoding of the signature.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+}
+
+impl<E: PairingEngine> CanonicalSerialize for VerifyingKey<E> {
+    fn serialize<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        self.point.serialize(writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.point.serialized_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for VerifyingKey<E> {
+    fn deserialize<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        Ok(VerifyingKey {
+            point: E::G2Projective::deserialize(reader)?,
+        })
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for VerifyingKey<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+impl<E: PairingEngine> CanonicalSerialize for Signature<E> {
+    fn serialize<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        self.point.serialize(writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.point.serialized_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for Signature<E> {
+    fn deserialize<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        Ok(Signature {
+            point: E::G1Projective::deserialize(reader)?,
+        })
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for Signature<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn signatures_verify() {
+        let mut rng = thread_rng();
+        let key = SigningKey::<Bls12_381>::generate(&mut rng);
+        let verifying_key = key.verifying_key();
+        let signature = key.sign(b"message");
+        assert!(verifying_key.verify(b"message", &signature));
+        assert!(!verifying_key.verify(b"other message", &signature));
+
+        let other = SigningKey::<Bls12_381>::generate(&mut rng).verifying_key();
+        assert!(!other.verify(b"message", &signature));
+
+        let decoded = Signature::try_from(signature.to_bytes().as_slice()).unwrap();
+        assert!(verifying_key.verify(b"message", &decoded));
+        let decoded = VerifyingKey::try_from(verifying_key.to_bytes().as_slice()).unwrap();
+        assert_eq!(decoded, verifying_key);
+    }
+
+    #[test]
+    fn wrong_signatures_are_isolated() {
+        let mut rng = thread_rng();
+        let key = SigningKey::<Bls12_381>::generate(&mut rng);
+        let verifying_key = key.verifying_key();
+        let mut signed: Vec<(Vec<u8>, Signature<Bls12_381>)> = (0..7u8)
+            .map(|index| (vec![index], key.sign(&[index])))
+            .collect();
+        assert!(verifying_key.verify_batch(&signed, &mut rng).is_empty());
+
+        signed[1].1 = signed[2].1;
+        signed[4].0 = b"other message".to_vec();
+        signed[6].1.point = Zero::zero();
+        assert_eq!(verifying_key.verify_batch(&signed, &mut rng), vec![1, 4, 6]);
+    }
+}
@@ -0,0 +1,368 @@
+//! In-memory simulation of the protocol. Enabled by the `testing` feature.
+//!
+//! A [`Simulation`] models the dealer, the broadcaster and every recipient as separate actors that
+//! only communicate through serialized messages on an in-memory network. The dealer hands out key
+//! bundles and publishes revocation lists, the broadcaster encrypts for every participant that is
+//! not revoked, and recipients decrypt whatever they can. [`Fault`]s injected into the network let
+//! tests check how the protocol behaves when messages are dropped or replayed.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use ark_ec::PairingEngine;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+
+use crate::testing::{mock_rng, MockRng};
+use crate::{BroadcastChannel, Header, KeyPair, Recipient, SharedKey};
+
+/// Fault applied by the network to the messages it delivers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fault {
+    /// The key bundle of participant `identifier` never arrives
+    DropKeyBundle(usize),
+    /// No revocation list reaches the broadcaster, which keeps encrypting with a stale one
+    DropRevocationLists,
+    /// Every revocation list is delivered a second time, together with the one preceding it
+    ReplayRevocationLists,
+}
+
+/// Destination of a message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Address {
+    Broadcaster,
+    Recipient(usize),
+}
+
+/// Messages exchanged by the actors.
+enum Message<E: PairingEngine> {
+    /// Keys of a participant, sent by the dealer
+    KeyBundle {
+        identifier: usize,
+        private_key: E::G1Projective,
+        public_key: E::G2Projective,
+    },
+    /// Revoked participants, sent by the dealer to the broadcaster
+    RevocationList { version: u64, revoked: Vec<usize> },
+    /// Header of a round, sent by the broadcaster to every participant
+    Broadcast {
+        round: u64,
+        recipients: Vec<usize>,
+        header: Header<E>,
+    },
+}
+
+impl<E: PairingEngine> Message<E> {
+    fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        // serializing into a vector cannot fail
+        match self {
+            Message::KeyBundle {
+                identifier,
+                private_key,
+                public_key,
+            } => {
+                0u8.serialize(&mut bytes).unwrap();
+                identifier.serialize(&mut bytes).unwrap();
+                private_key.serialize(&mut bytes).unwrap();
+                public_key.serialize(&mut bytes).unwrap();
+            }
+            Message::RevocationList { version, revoked } => {
+                1u8.serialize(&mut bytes).unwrap();
+                version.serialize(&mut bytes).unwrap();
+                revoked.serialize(&mut bytes).unwrap();
+            }
+            Message::Broadcast {
+                round,
+                recipients,
+                header,
+            } => {
+                2u8.serialize(&mut bytes).unwrap();
+                round.serialize(&mut bytes).unwrap();
+                recipients.serialize(&mut bytes).unwrap();
+                header.serialize(&mut bytes).unwrap();
+            }
+        }
+        bytes
+    }
+
+    fn decode(mut bytes: &[u8]) -> Result<Self, SerializationError> {
+        let reader = &mut bytes;
+        let message = match u8::deserialize(&mut *reader)? {
+            0 => Message::KeyBundle {
+                identifier: usize::deserialize(&mut *reader)?,
+                private_key: E::G1Projective::deserialize(&mut *reader)?,
+                public_key: E::G2Projective::deserialize(&mut *reader)?,
+            },
+            1 => Message::RevocationList {
+                version: u64::deserialize(&mut *reader)?,
+                revoked: Vec::deserialize(&mut *reader)?,
+            },
+            2 => Message::Broadcast {
+                round: u64::deserialize(&mut *reader)?,
+                recipients: Vec::deserialize(&mut *reader)?,
+                header: Header::deserialize(&mut *reader)?,
+            },
+            _ => return Err(SerializationError::InvalidData),
+        };
+        if !reader.is_empty() {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(message)
+    }
+}
+
+/// Broadcaster: knows the public parameters and the latest revocation list it received.
+struct Broadcaster<E: PairingEngine> {
+    channel: BroadcastChannel<E>,
+    version: u64,
+    revoked: Vec<usize>,
+}
+
+/// Recipient: knows the public parameters and, once its bundle arrives, its keys.
+struct SimRecipient<E: PairingEngine> {
+    channel: BroadcastChannel<E>,
+    recipient: Option<Recipient<E>>,
+    recovered: BTreeMap<u64, SharedKey<E>>,
+}
+
+/// Dealer, broadcaster and recipients of a channel, connected by an in-memory network.
+pub struct Simulation<E: PairingEngine> {
+    rng: MockRng,
+    /// keys generated by the dealer, not yet sent
+    dealer_keys: Vec<Recipient<E>>,
+    /// version of the last revocation list published by the dealer
+    dealer_version: u64,
+    dealer_revoked: Vec<usize>,
+    broadcaster: Broadcaster<E>,
+    recipients: Vec<SimRecipient<E>>,
+    network: VecDeque<(Address, Vec<u8>)>,
+    faults: Vec<Fault>,
+    round: u64,
+}
+
+impl<E: PairingEngine> Simulation<E> {
+    /// Simulation of a channel with `n` participants, with all randomness derived from `seed`.
+    /// No key has been distributed yet. Panics if `n` is zero.
+    pub fn new(n: usize, seed: u64) -> Self {
+        let mut rng = mock_rng(seed);
+        let (channel, dealer_keys) =
+            BroadcastChannel::init_participants(n, &mut rng).expect("no participants");
+
+        let recipients = (0..n)
+            .map(|_| SimRecipient {
+                channel: channel.clone(),
+                recipient: None,
+                recovered: BTreeMap::new(),
+            })
+            .collect();
+
+        Simulation {
+            rng,
+            dealer_keys,
+            dealer_version: 0,
+            dealer_revoked: Vec::new(),
+            broadcaster: Broadcaster {
+                channel,
+                version: 0,
+                revoked: Vec::new(),
+            },
+            recipients,
+            network: VecDeque::new(),
+            faults: Vec::new(),
+            round: 0,
+        }
+    }
+
+    /// Apply `fault` to every message delivered from now on.
+    pub fn inject(&mut self, fault: Fault) {
+        self.faults.push(fault);
+    }
+
+    /// The dealer sends every participant its key bundle.
+    pub fn distribute_keys(&mut self) {
+        for recipient in std::mem::take(&mut self.dealer_keys) {
+            let message = Message::<E>::KeyBundle {
+                identifier: recipient.identifier,
+                private_key: recipient.key_pair.private_key,
+                public_key: recipient.key_pair.public_key,
+            };
+            self.send(Address::Recipient(recipient.identifier), message);
+        }
+    }
+
+    /// The dealer adds `identifiers` to the revoked participants and publishes the new list.
+    pub fn revoke(&mut self, identifiers: &[usize]) {
+        let previous = Message::<E>::RevocationList {
+            version: self.dealer_version,
+            revoked: self.dealer_revoked.clone(),
+        };
+
+        self.dealer_version += 1;
+        self.dealer_revoked.extend_from_slice(identifiers);
+        let current = Message::<E>::RevocationList {
+            version: self.dealer_version,
+            revoked: self.dealer_revoked.clone(),
+        };
+
+        self.send(Address::Broadcaster, current);
+        if self.faults.contains(&Fault::ReplayRevocationLists) {
+            self.send(Address::Broadcaster, previous);
+        }
+    }
+
+    /// The broadcaster encrypts a fresh key for every participant it believes not revoked and
+    /// sends the header to all participants. Returns the round number and the key.
+    pub fn broadcast(&mut self) -> (u64, SharedKey<E>) {
+        self.round += 1;
+        let broadcaster = &self.broadcaster;
+        let recipients: Vec<usize> = broadcaster
+            .channel
+            .issued()
+            .filter(|identifier| !broadcaster.revoked.contains(identifier))
+            .collect();
+        // the set only contains issued participants and the channel has no policy
+        let (header, key) = broadcaster
+            .channel
+            .encrypt(&recipients, &mut self.rng)
+            .unwrap();
+
+        for identifier in broadcaster.channel.issued() {
+            let message = Message::Broadcast {
+                round: self.round,
+                recipients: recipients.clone(),
+                header,
+            };
+            self.send(Address::Recipient(identifier), message);
+        }
+
+        (self.round, key)
+    }
+
+    /// Deliver every message in flight, applying the injected faults.
+    pub fn run(&mut self) {
+        while let Some((address, bytes)) = self.network.pop_front() {
+            // messages are only produced by `send`, so they always decode
+            let message = Message::<E>::decode(&bytes).unwrap();
+            if self.dropped(&message) {
+                continue;
+            }
+
+            match (address, message) {
+                // lists may arrive out of order, only a newer one replaces the current one
+                (Address::Broadcaster, Message::RevocationList { version, revoked })
+                    if version > self.broadcaster.version =>
+                {
+                    self.broadcaster.version = version;
+                    self.broadcaster.revoked = revoked;
+                }
+                (
+                    Address::Recipient(identifier),
+                    Message::KeyBundle {
+                        identifier: bundle_identifier,
+                        private_key,
+                        public_key,
+                    },
+                ) if identifier == bundle_identifier => {
+                    self.recipients[identifier - 1].recipient = Some(Recipient {
+                        identifier,
+                        key_pair: KeyPair {
+                            public_key,
+                            private_key,
+                        },
+                        budget: None,
+                    });
+                }
+                (
+                    Address::Recipient(identifier),
+                    Message::Broadcast {
+                        round,
+                        recipients,
+                        header,
+                    },
+                ) => {
+                    let actor = &mut self.recipients[identifier - 1];
+                    if let Some(recipient) = &actor.recipient {
+                        if let Ok(key) = recipient.decrypt(&recipients, &actor.channel, &header) {
+                            actor.recovered.insert(round, key);
+                        }
+                    }
+                }
+                // the actors ignore messages that are not meant for them
+                _ => {}
+            }
+        }
+    }
+
+    /// Key recovered by participant `identifier` in `round`, if any.
+    pub fn recovered_key(&self, identifier: usize, round: u64) -> Option<&SharedKey<E>> {
+        self.recipients
+            .get(identifier.checked_sub(1)?)?
+            .recovered
+            .get(&round)
+    }
+
+    fn send(&mut self, address: Address, message: Message<E>) {
+        self.network.push_back((address, message.encode()));
+    }
+
+    fn dropped(&self, message: &Message<E>) -> bool {
+        self.faults.iter().any(|fault| match (fault, message) {
+            (Fault::DropKeyBundle(dropped), Message::KeyBundle { identifier, .. }) => {
+                dropped == identifier
+            }
+            (Fault::DropRevocationLists, Message::RevocationList { .. }) => true,
+            _ => false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+
+    #[test]
+    fn revoked_participants_lose_access() {
+        let mut simulation = Simulation::<Bls12_381>::new(4, 0);
+        simulation.distribute_keys();
+        simulation.run();
+
+        let (round, key) = simulation.broadcast();
+        simulation.run();
+        for identifier in 1..=4 {
+            assert_eq!(simulation.recovered_key(identifier, round), Some(&key));
+        }
+
+        simulation.revoke(&[2]);
+        simulation.run();
+        let (round, key) = simulation.broadcast();
+        simulation.run();
+        assert_eq!(simulation.recovered_key(1, round), Some(&key));
+        assert_eq!(simulation.recovered_key(2, round), None);
+    }
+
+    #[test]
+    fn faults_are_applied() {
+        let mut simulation = Simulation::<Bls12_381>::new(4, 1);
+        simulation.inject(Fault::DropKeyBundle(3));
+        simulation.inject(Fault::ReplayRevocationLists);
+        simulation.distribute_keys();
+        simulation.revoke(&[1]);
+        simulation.run();
+
+        // the replayed list is older, so the broadcaster keeps the current one
+        let (round, key) = simulation.broadcast();
+        simulation.run();
+        assert_eq!(simulation.recovered_key(1, round), None);
+        assert_eq!(simulation.recovered_key(2, round), Some(&key));
+        assert_eq!(simulation.recovered_key(3, round), None);
+
+        let mut stale = Simulation::<Bls12_381>::new(4, 2);
+        stale.inject(Fault::DropRevocationLists);
+        stale.distribute_keys();
+        stale.revoke(&[1]);
+        stale.run();
+        let (round, key) = stale.broadcast();
+        stale.run();
+        assert_eq!(stale.recovered_key(1, round), Some(&key));
+    }
+}
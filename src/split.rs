@@ -0,0 +1,228 @@
+//! Setup split between two semi-honest dealers.
+//!
+//! Whoever knows alpha decrypts every header on its own, as the key is `e(alpha^(n+1) P, ctx_1)`,
+//! and whoever knows gamma holds every private key. Giving alpha to one party and gamma to the
+//! other is therefore not enough: each secret has to be shared. Each dealer contributes to both:
+//!
+//! 1. The first dealer produces the powers of its share of alpha in an [`SrsTranscript`], the
+//!    second one raises them to the powers of its own share with [`SrsTranscript::update`]. Neither
+//!    learns the combined alpha, and the point at position `n + 1` is never computed.
+//! 2. Each dealer picks a share of gamma in a [`KeyDealer`], publishes `gamma_j P` and issues
+//!    every participant the share `gamma_j P_i` of its private key. Recipients add up the shares
+//!    with [`Recipient::combine`], which checks the result against the channel.
+//!
+//! Both dealers are trusted to follow the protocol, but no single one can decrypt.
+
+use alloc::collections::BTreeSet;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use ark_ec::{PairingEngine, ProjectiveCurve};
+use ark_ff::{UniformRand, Zero};
+use rand_core::{CryptoRng, RngCore};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::policy::Policy;
+use crate::session::EncryptionTables;
+use crate::{BroadcastChannel, KeyPair, Recipient};
+
+/// Powers of alpha in G1 and G2, as built up by the successive contributions.
+#[derive(Clone)]
+pub struct SrsTranscript<E: PairingEngine> {
+    pub(crate) number_participants: usize,
+    pub(crate) g1_points: Vec<E::G1Projective>,
+    pub(crate) g2_points: Vec<E::G2Projective>,
+}
+
+impl<E: PairingEngine> SrsTranscript<E> {
+    /// First contribution, for a channel of `n` participants.
+    pub fn new<R: RngCore + CryptoRng>(n: usize, rng: &mut R) -> Self {
+        let mut transcript = Self::initial(n);
+        transcript.update(rng);
+        transcript
+    }
+
+    /// Transcript before any contribution, whose secret is one, for a channel of `n`
+    /// participants.
+    pub fn initial(n: usize) -> Self {
+        let mut transcript = SrsTranscript {
+            number_participants: n,
+            g1_points: vec![E::G1Projective::prime_subgroup_generator(); 2 * n + 1],
+            g2_points: vec![E::G2Projective::prime_subgroup_generator(); n + 1],
+        };
+        transcript.g1_points[n + 1] = E::G1Projective::zero();
+        transcript
+    }
+
+    /// Multiply the secret of the transcript by a fresh share, which is forgotten afterwards.
+    pub fn update<R: RngCore + CryptoRng>(&mut self, rng: &mut R) {
+        let mut share = E::Fr::rand(rng);
+        self.update_with(share);
+        share.zeroize();
+    }
+
+    /// Multiply the secret of the transcript by `share`.
+    pub(crate) fn update_with(&mut self, share: E::Fr) {
+        let mut power = share;
+        for point in self.g1_points.iter_mut().skip(1) {
+            *point *= power;
+            power *= &share;
+        }
+
+        let mut power = share;
+        for point in self.g2_points.iter_mut().skip(1) {
+            *point *= power;
+            power *= &share;
+        }
+    }
+}
+
+/// Share of gamma held by a dealer.
+pub struct KeyDealer<E: PairingEngine> {
+    gamma: E::Fr,
+}
+
+impl<E: PairingEngine> Drop for KeyDealer<E> {
+    fn drop(&mut self) {
+        self.gamma.zeroize();
+    }
+}
+
+impl<E: PairingEngine> ZeroizeOnDrop for KeyDealer<E> {}
+
+/// Share of the private key of a participant, issued by one [`KeyDealer`].
+#[derive(Clone, Copy)]
+pub struct KeyShare<E: PairingEngine> {
+    identifier: usize,
+    pub(crate) point: E::G1Projective,
+}
+
+impl<E: PairingEngine> KeyDealer<E> {
+    /// Dealer with a fresh share of gamma.
+    pub fn new<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        KeyDealer {
+            gamma: E::Fr::rand(rng),
+        }
+    }
+
+    /// Public share of the point `V`.
+    pub fn point_v(&self) -> E::G1Projective {
+        let mut point_v = E::G1Projective::prime_subgroup_generator();
+        point_v *= self.gamma;
+        point_v
+    }
+
+    /// Share of the private key of participant `identifier`, which must be in `1..=n`.
+    pub fn issue(&self, srs: &SrsTranscript<E>, identifier: usize) -> Option<KeyShare<E>> {
+        if identifier == 0 || identifier > srs.number_participants {
+            return None;
+        }
+
+        Some(self.issue_point(srs.g1_points[identifier], identifier))
+    }
+
+    /// Share of participant `identifier`, whose point of the SRS is `point`.
+    pub(crate) fn issue_point(&self, mut point: E::G1Projective, identifier: usize) -> KeyShare<E> {
+        point *= self.gamma;
+        KeyShare { identifier, point }
+    }
+}
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Channel for the final `srs` and the public shares of `V` of all dealers.
+    pub fn from_split(srs: SrsTranscript<E>, point_v_shares: &[E::G1Projective]) -> Self {
+        let point_v = point_v_shares
+            .iter()
+            .fold(E::G1Projective::zero(), |sum, share| sum + share);
+
+        let broadcaster_pk_g2 = E::G2Projective::batch_normalization_into_affine(&srs.g2_points);
+        let encryption_tables = Arc::new(EncryptionTables::new(&broadcaster_pk_g2));
+        let mut channel = BroadcastChannel {
+            number_participants: srs.number_participants,
+            broadcaster_pk_g1: Arc::new(E::G1Projective::batch_normalization_into_affine(
+                &srs.g1_points,
+            )),
+            broadcaster_pk_g2: Arc::new(broadcaster_pk_g2),
+            point_v,
+            issued: srs.number_participants,
+            event_sink: None,
+            policy: Policy::default(),
+            retired: BTreeSet::new(),
+            epoch: 0,
+            issued_aggregate: E::G1Projective::zero(),
+            encryption_tables,
+            #[cfg(feature = "std")]
+            aggregate_cache: None,
+        };
+        channel.refresh_issued_aggregate();
+        channel
+    }
+}
+
+impl<E: PairingEngine> Recipient<E> {
+    /// Private key of participant `identifier` from the shares issued by every dealer. Returns
+    /// `None` if a share is missing, for another participant, or wrong.
+    pub fn combine(
+        channel: &BroadcastChannel<E>,
+        identifier: usize,
+        shares: &[KeyShare<E>],
+    ) -> Option<Self> {
+        if shares.iter().any(|share| share.identifier != identifier) {
+            return None;
+        }
+
+        let recipient = Recipient {
+            identifier,
+            key_pair: KeyPair {
+                public_key: channel.public_key(identifier)?.point,
+                private_key: shares
+                    .iter()
+                    .fold(E::G1Projective::zero(), |sum, share| sum + share.point),
+            },
+            budget: None,
+        };
+
+        if !recipient.validate(channel).is_valid() {
+            return None;
+        }
+        Some(recipient)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn two_dealers_set_up_channel() {
+        let mut rng = thread_rng();
+
+        let mut srs = SrsTranscript::<Bls12_381>::new(4, &mut rng);
+        srs.update(&mut rng);
+
+        let first = KeyDealer::<Bls12_381>::new(&mut rng);
+        let second = KeyDealer::<Bls12_381>::new(&mut rng);
+        let shares: Vec<Vec<KeyShare<Bls12_381>>> = (1..=4)
+            .map(|identifier| {
+                vec![
+                    first.issue(&srs, identifier).unwrap(),
+                    second.issue(&srs, identifier).unwrap(),
+                ]
+            })
+            .collect();
+        assert!(first.issue(&srs, 5).is_none());
+
+        let channel = BroadcastChannel::from_split(srs, &[first.point_v(), second.point_v()]);
+        assert!(channel.validate().is_valid());
+
+        let recipient = Recipient::combine(&channel, 2, &shares[1]).unwrap();
+        let (header, key) = channel.encrypt(&[2, 3], &mut rng).unwrap();
+        assert_eq!(recipient.decrypt(&[2, 3], &channel, &header), Ok(key));
+
+        assert!(Recipient::combine(&channel, 2, &shares[1][..1]).is_none());
+        assert!(Recipient::combine(&channel, 2, &shares[2]).is_none());
+    }
+}
@@ -0,0 +1,657 @@
+//! Encryption and decryption against a pluggable store of public parameters.
+//!
+//! For large channels the public parameters can be too big to ship with every encryptor. A
+//! [`ParamStore`] only has to hand out contiguous ranges of points, and the functions in this
+//! module fetch just the ranges needed for the given recipient set. A [`PointsProvider`] hands
+//! out single points instead, and a [`PointStore`] fetches them one by one.
+//!
+//! The functions check the sets as [`BroadcastChannel::encrypt`] and [`Recipient::decrypt`] do,
+//! before fetching anything, and refuse the points of a store that hands out fewer or more of
+//! them than asked for.
+//!
+//! The points decide who can compute the keys, so a store must be authenticated. A
+//! [`VerifiedStore`] accepts points from any source and checks each one against
+//! [`TrustedPoints`] obtained from the broadcaster, with a pairing.
+//!
+//! The `remote_params` example implements the store over HTTP, for thin clients fetching the
+//! few points an operation needs from a server instead of syncing the whole parameters. With the
+//! `object-store` feature, [`crate::object`] reads the parameters by ranges from S3 or GCS.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::convert::Infallible;
+use core::ops::Range;
+
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{One, Zero};
+
+use rand_core::{CryptoRng, RngCore};
+
+use crate::error::{DecryptError, EncryptError, ParamStoreError, VerifiedStoreError};
+use crate::keystore::key_of_header;
+use crate::policy::Policy;
+use crate::session::EphemeralScalar;
+use crate::set;
+use crate::{BroadcastChannel, Header, Recipient, SharedKey};
+
+/// Header and key of an encryption against the store `S`, or why it failed.
+pub type StoreEncryption<E, S> =
+    Result<(Header<E>, SharedKey<E>), ParamStoreError<<S as ParamStore<E>>::Error>>;
+
+/// Source of the public parameters of a broadcast channel.
+///
+/// Whoever chooses the points of a store can compute the keys of the headers built from them, so
+/// a store must be authenticated: read from a trusted source, or wrapped in a [`VerifiedStore`].
+pub trait ParamStore<E: PairingEngine> {
+    /// Error returned when the points cannot be fetched
+    type Error;
+
+    /// Number of participants of the channel.
+    fn number_participants(&self) -> usize;
+
+    /// Points of the G1 vector at positions `range`.
+    fn g1_points(&self, range: Range<usize>) -> Result<Vec<E::G1Affine>, Self::Error>;
+
+    /// Points of the G2 vector at positions `range`.
+    fn g2_points(&self, range: Range<usize>) -> Result<Vec<E::G2Affine>, Self::Error>;
+
+    /// The point `V` used to build the headers.
+    fn point_v(&self) -> Result<E::G1Projective, Self::Error>;
+
+    /// Limits checked by the encryption, none unless the store knows them.
+    fn policy(&self) -> Policy {
+        Policy::default()
+    }
+
+    /// Whether the key of `identifier` has been retired, refusing it in the encryption. No key is
+    /// unless the store knows about them.
+    fn is_retired(&self, _identifier: usize) -> bool {
+        false
+    }
+}
+
+/// A range past the end of a vector gives no points, which the functions of this module refuse.
+impl<E: PairingEngine> ParamStore<E> for BroadcastChannel<E> {
+    type Error = Infallible;
+
+    fn number_participants(&self) -> usize {
+        self.number_participants
+    }
+
+    fn g1_points(&self, range: Range<usize>) -> Result<Vec<E::G1Affine>, Infallible> {
+        Ok(self.broadcaster_pk_g1.get(range).unwrap_or(&[]).to_vec())
+    }
+
+    fn g2_points(&self, range: Range<usize>) -> Result<Vec<E::G2Affine>, Infallible> {
+        Ok(self.broadcaster_pk_g2.get(range).unwrap_or(&[]).to_vec())
+    }
+
+    fn point_v(&self) -> Result<E::G1Projective, Infallible> {
+        Ok(self.point_v)
+    }
+
+    fn policy(&self) -> Policy {
+        self.policy
+    }
+
+    fn is_retired(&self, identifier: usize) -> bool {
+        self.retired.contains(&identifier)
+    }
+}
+
+/// Source of the public parameters handing out one point at a time, for services that address
+/// points by position rather than by range. Wrapped in a [`PointStore`], a provider is a
+/// [`ParamStore`] that [`encrypt`] and [`Recipient::decrypt_from_store`] run against.
+///
+/// As any store, a provider must be authenticated, or its [`PointStore`] wrapped in a
+/// [`VerifiedStore`]: points of an attacker's choosing give keys the attacker can compute.
+pub trait PointsProvider<E: PairingEngine> {
+    /// Error returned when a point cannot be fetched
+    type Error;
+
+    /// Number of participants of the channel.
+    fn number_participants(&self) -> usize;
+
+    /// Point of the G1 vector at position `index`, in `0..=2n`.
+    fn g1_point(&self, index: usize) -> Result<E::G1Affine, Self::Error>;
+
+    /// Point of the G2 vector at position `index`, in `0..=n`.
+    fn g2_point(&self, index: usize) -> Result<E::G2Affine, Self::Error>;
+
+    /// The point `V` used to build the headers.
+    fn point_v(&self) -> Result<E::G1Projective, Self::Error>;
+}
+
+/// Store fetching the points of a range from the provider `P`, one after the other.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PointStore<P>(pub P);
+
+impl<E: PairingEngine, P: PointsProvider<E>> ParamStore<E> for PointStore<P> {
+    type Error = P::Error;
+
+    fn number_participants(&self) -> usize {
+        self.0.number_participants()
+    }
+
+    fn g1_points(&self, range: Range<usize>) -> Result<Vec<E::G1Affine>, P::Error> {
+        range.map(|index| self.0.g1_point(index)).collect()
+    }
+
+    fn g2_points(&self, range: Range<usize>) -> Result<Vec<E::G2Affine>, P::Error> {
+        range.map(|index| self.0.g2_point(index)).collect()
+    }
+
+    fn point_v(&self) -> Result<E::G1Projective, P::Error> {
+        self.0.point_v()
+    }
+}
+
+/// Points of a channel that a client got from the broadcaster over an authenticated channel,
+/// or shipped with the application: the generator `P_0`, the point `P_n`, the point `V` and the
+/// `n + 1` points of G2. A [`VerifiedStore`] checks the points of G1 of any other store against
+/// them.
+#[derive(Clone)]
+pub struct TrustedPoints<E: PairingEngine> {
+    generator: E::G1Affine,
+    point_n: E::G1Affine,
+    point_v: E::G1Projective,
+    g2_points: Vec<E::G2Affine>,
+}
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Points the stores of thin clients are checked against, see [`VerifiedStore`].
+    pub fn trusted_points(&self) -> TrustedPoints<E> {
+        TrustedPoints {
+            generator: self.broadcaster_pk_g1[0],
+            point_n: self.broadcaster_pk_g1[self.number_participants],
+            point_v: self.point_v,
+            g2_points: self.broadcaster_pk_g2.to_vec(),
+        }
+    }
+}
+
+impl<E: PairingEngine> TrustedPoints<E> {
+    /// Number of participants of the channel.
+    pub fn number_participants(&self) -> usize {
+        self.g2_points.len() - 1
+    }
+
+    /// Whether `point` is `P_position`, that is `e(P_k, Q) = e(P_0, Q_k)` for `k` up to `n`, and
+    /// `e(P_k, Q) = e(P_n, Q_{k-n})` above `n + 1`.
+    fn holds(&self, position: usize, point: &E::G1Affine) -> bool {
+        let n = self.number_participants();
+        let (base, exponent) = match position {
+            0 => return *point == self.generator,
+            k if k <= n => (self.generator, self.g2_points[k]),
+            // P_{n+1} is never published
+            k if k == n + 1 => return point.is_zero(),
+            k if k <= 2 * n => (self.point_n, self.g2_points[k - n]),
+            _ => return false,
+        };
+
+        let pairs = [
+            ((*point).into(), self.g2_points[0].into()),
+            ((-base).into(), exponent.into()),
+        ];
+        E::product_of_pairings(pairs.iter()).is_one()
+    }
+}
+
+/// Store checking every point of G1 that the store `S` hands out against [`TrustedPoints`], for
+/// stores reached over a transport the client does not trust. Each point costs a product of two
+/// pairings. The points of G2, the point `V` and the number of participants are the trusted ones,
+/// and are never fetched from `S`.
+#[derive(Clone)]
+pub struct VerifiedStore<E: PairingEngine, S> {
+    store: S,
+    trusted: TrustedPoints<E>,
+}
+
+impl<E: PairingEngine, S: ParamStore<E>> VerifiedStore<E, S> {
+    /// Store handing out the points of `store` that match `trusted`.
+    pub fn new(store: S, trusted: TrustedPoints<E>) -> Self {
+        VerifiedStore { store, trusted }
+    }
+
+    /// The store the points are fetched from.
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+}
+
+impl<E: PairingEngine, S: ParamStore<E>> ParamStore<E> for VerifiedStore<E, S> {
+    type Error = VerifiedStoreError<S::Error>;
+
+    fn number_participants(&self) -> usize {
+        self.trusted.number_participants()
+    }
+
+    fn g1_points(&self, range: Range<usize>) -> Result<Vec<E::G1Affine>, Self::Error> {
+        let points = self
+            .store
+            .g1_points(range.clone())
+            .map_err(VerifiedStoreError::Store)?;
+        // every point handed out is checked, even past the end of the range
+        for (position, point) in (range.start..).zip(points.iter()) {
+            if !self.trusted.holds(position, point) {
+                return Err(VerifiedStoreError::Unverified(position));
+            }
+        }
+        Ok(points)
+    }
+
+    fn g2_points(&self, range: Range<usize>) -> Result<Vec<E::G2Affine>, Self::Error> {
+        Ok(self
+            .trusted
+            .g2_points
+            .get(range)
+            .unwrap_or_default()
+            .to_vec())
+    }
+
+    fn point_v(&self) -> Result<E::G1Projective, Self::Error> {
+        Ok(self.trusted.point_v)
+    }
+
+    fn policy(&self) -> Policy {
+        self.store.policy()
+    }
+
+    fn is_retired(&self, identifier: usize) -> bool {
+        self.store.is_retired(identifier)
+    }
+}
+
+/// Points handed out by a store for `range`, checked to be as many as asked for.
+fn checked<P, F>(
+    points: Result<Vec<P>, F>,
+    range: &Range<usize>,
+) -> Result<Vec<P>, ParamStoreError<F>> {
+    let points = points.map_err(ParamStoreError::Store)?;
+    if points.len() != range.len() {
+        return Err(ParamStoreError::WrongLength {
+            expected: range.len(),
+            found: points.len(),
+        });
+    }
+    Ok(points)
+}
+
+fn g1_points<E, S>(
+    store: &S,
+    range: Range<usize>,
+) -> Result<Vec<E::G1Affine>, ParamStoreError<S::Error>>
+where
+    E: PairingEngine,
+    S: ParamStore<E>,
+{
+    checked(store.g1_points(range.clone()), &range)
+}
+
+fn g2_points<E, S>(
+    store: &S,
+    range: Range<usize>,
+) -> Result<Vec<E::G2Affine>, ParamStoreError<S::Error>>
+where
+    E: PairingEngine,
+    S: ParamStore<E>,
+{
+    checked(store.g2_points(range.clone()), &range)
+}
+
+/// Check that `set_recipients` can be encrypted for with the parameters of `store`, as in
+/// [`BroadcastChannel::session`].
+fn check_encryption_set<E, S>(store: &S, set_recipients: &[usize]) -> Result<(), EncryptError>
+where
+    E: PairingEngine,
+    S: ParamStore<E>,
+{
+    set::check_encryption_set(
+        store.number_participants(),
+        set_recipients,
+        |identifier| store.is_retired(identifier),
+        &store.policy(),
+    )
+}
+
+/// Split `positions` into the minimal list of ascending, contiguous ranges covering them.
+fn contiguous_ranges(positions: &[usize]) -> Vec<Range<usize>> {
+    let mut sorted = positions.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for position in sorted {
+        match ranges.last_mut() {
+            Some(range) if range.end == position => range.end += 1,
+            _ => ranges.push(position..position + 1),
+        }
+    }
+    ranges
+}
+
+/// Sum of the G1 points at `positions`, fetching each contiguous range only once.
+fn sum_g1_points<E, S>(
+    store: &S,
+    positions: &[usize],
+) -> Result<E::G1Projective, ParamStoreError<S::Error>>
+where
+    E: PairingEngine,
+    S: ParamStore<E>,
+{
+    let mut fetched = BTreeMap::new();
+    for range in contiguous_ranges(positions) {
+        let start = range.start;
+        for (offset, point) in g1_points(store, range)?.into_iter().enumerate() {
+            fetched.insert(start + offset, point);
+        }
+    }
+
+    let mut sum = E::G1Projective::zero();
+    for position in positions.iter() {
+        // every position is in a range fetched in full above
+        sum.add_assign_mixed(&fetched[position]);
+    }
+    Ok(sum)
+}
+
+/// Encrypt for set of recipients using the parameters available in `store`. The result is the
+/// same as for [`BroadcastChannel::encrypt`], and so are the sets refused, as far as the store
+/// knows the policy and the retired keys of the channel.
+pub fn encrypt<E, S, R>(store: &S, set_recipients: &[usize], rng: &mut R) -> StoreEncryption<E, S>
+where
+    E: PairingEngine,
+    S: ParamStore<E>,
+    R: RngCore + CryptoRng,
+{
+    check_encryption_set(store, set_recipients).map_err(ParamStoreError::Encrypt)?;
+
+    let n = store.number_participants();
+    let g2_points = g2_points(store, 0..2)?;
+    let point_n = g1_points(store, n..n + 1)?[0];
+
+    let ephemeral = EphemeralScalar::<E>::random(rng);
+    let mut g_2_point = g2_points[1].into_projective();
+    g_2_point *= ephemeral.k;
+    let K = E::pairing(point_n, g_2_point);
+
+    let mut header_point_in_g2 = g2_points[0].into_projective();
+    header_point_in_g2 *= ephemeral.k;
+
+    let positions: Vec<usize> = set_recipients.iter().map(|index| n + 1 - index).collect();
+    let point_v = store.point_v().map_err(ParamStoreError::Store)?;
+    let mut header_point_in_g1 = point_v + sum_g1_points(store, &positions)?;
+    header_point_in_g1 *= ephemeral.k;
+
+    let header = Header {
+        ctx_0: header_point_in_g1,
+        ctx_1: header_point_in_g2,
+    };
+
+    Ok((header, SharedKey::new(K)))
+}
+
+impl<E: PairingEngine> Recipient<E> {
+    /// Decrypt a header encrypted for set `set_recipients`, fetching the needed points from
+    /// `store` instead of a full [`BroadcastChannel`]. Fails if the set contains identifiers
+    /// outside of the channel or more than once, if the recipient is not in it, if a component of
+    /// the header is the identity, or if the usage budget of the recipient is spent.
+    pub fn decrypt_from_store<S: ParamStore<E>>(
+        &self,
+        set_recipients: &[usize],
+        store: &S,
+        header: &Header<E>,
+    ) -> Result<SharedKey<E>, ParamStoreError<S::Error>> {
+        let n = store.number_participants();
+        set::check_decryption_set(n, self.identifier, set_recipients)
+            .map_err(ParamStoreError::Decrypt)?;
+        if header.ctx_0.is_zero() || header.ctx_1.is_zero() {
+            return Err(ParamStoreError::Decrypt(DecryptError::IdentityPoint));
+        }
+        self.consume_use().map_err(ParamStoreError::Decrypt)?;
+
+        let positions: Vec<usize> = set_recipients
+            .iter()
+            .filter(|index| **index != self.identifier)
+            .map(|index| n + 1 - index + self.identifier)
+            .collect();
+
+        let aggregate = sum_g1_points(store, &positions)?;
+        Ok(SharedKey::new(key_of_header(
+            &self.key_pair.private_key,
+            &self.key_pair.public_key,
+            &aggregate,
+            header,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use core::cell::Cell;
+    use rand::thread_rng;
+
+    #[test]
+    fn ranges_are_merged() {
+        assert_eq!(contiguous_ranges(&[7, 3, 4, 9, 8, 3]), vec![3..5, 7..10]);
+        assert!(contiguous_ranges(&[]).is_empty());
+    }
+
+    #[test]
+    fn store_round_trip() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(8, &mut rng).unwrap();
+
+        let recipients = vec![1, 2, 3, 7];
+        let (header, key) = encrypt(&channel, &recipients, &mut rng).unwrap();
+
+        let dec_key = participants[2]
+            .decrypt_from_store(&recipients, &channel, &header)
+            .unwrap();
+        assert_eq!(key, dec_key);
+        assert_eq!(
+            Ok(key.clone()),
+            participants[6].decrypt(&recipients, &channel, &header)
+        );
+
+        let (header, key) = channel.encrypt(&recipients, &mut rng).unwrap();
+        let dec_key = participants[0]
+            .decrypt_from_store(&recipients, &channel, &header)
+            .unwrap();
+        assert_eq!(key, dec_key);
+    }
+
+    /// Store handing out one point less than asked for.
+    struct ShortStore(BroadcastChannel<Bls12_381>);
+
+    impl ParamStore<Bls12_381> for ShortStore {
+        type Error = Infallible;
+
+        fn number_participants(&self) -> usize {
+            self.0.number_participants()
+        }
+
+        fn g1_points(
+            &self,
+            range: Range<usize>,
+        ) -> Result<Vec<<Bls12_381 as PairingEngine>::G1Affine>, Infallible> {
+            let mut points = self.0.g1_points(range)?;
+            points.pop();
+            Ok(points)
+        }
+
+        fn g2_points(
+            &self,
+            range: Range<usize>,
+        ) -> Result<Vec<<Bls12_381 as PairingEngine>::G2Affine>, Infallible> {
+            self.0.g2_points(range)
+        }
+
+        fn point_v(&self) -> Result<<Bls12_381 as PairingEngine>::G1Projective, Infallible> {
+            self.0.point_v()
+        }
+    }
+
+    /// Provider counting the points it hands out, and failing with the position of a point that
+    /// does not exist.
+    struct Counted {
+        channel: BroadcastChannel<Bls12_381>,
+        fetched: Cell<usize>,
+    }
+
+    impl PointsProvider<Bls12_381> for Counted {
+        type Error = usize;
+
+        fn number_participants(&self) -> usize {
+            self.channel.number_participants()
+        }
+
+        fn g1_point(&self, index: usize) -> Result<<Bls12_381 as PairingEngine>::G1Affine, usize> {
+            self.fetched.set(self.fetched.get() + 1);
+            self.channel
+                .broadcaster_pk_g1
+                .get(index)
+                .copied()
+                .ok_or(index)
+        }
+
+        fn g2_point(&self, index: usize) -> Result<<Bls12_381 as PairingEngine>::G2Affine, usize> {
+            self.fetched.set(self.fetched.get() + 1);
+            self.channel
+                .broadcaster_pk_g2
+                .get(index)
+                .copied()
+                .ok_or(index)
+        }
+
+        fn point_v(&self) -> Result<<Bls12_381 as PairingEngine>::G1Projective, usize> {
+            Ok(self.channel.point_v)
+        }
+    }
+
+    #[test]
+    fn points_providers_are_stores() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(100, &mut rng).unwrap();
+        let provider = PointStore(Counted {
+            channel: channel.clone(),
+            fetched: Cell::new(0),
+        });
+
+        let recipients = [4, 50, 97];
+        let (header, key) = encrypt(&provider, &recipients, &mut rng).unwrap();
+        assert_eq!(
+            participants[96].decrypt(&recipients, &channel, &header),
+            Ok(key.clone())
+        );
+        assert_eq!(
+            participants[3].decrypt_from_store(&recipients, &provider, &header),
+            Ok(key.clone())
+        );
+        // six points to encrypt and two to decrypt, out of more than three hundred
+        assert_eq!(provider.0.fetched.get(), 8);
+        assert_eq!(ParamStore::g1_points(&provider, 199..202), Err(201));
+    }
+
+    #[test]
+    fn verified_stores_refuse_other_points() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(4, &mut rng).unwrap();
+        let (other, _) = BroadcastChannel::<Bls12_381>::init_participants(4, &mut rng).unwrap();
+
+        let verified = VerifiedStore::new(channel.clone(), channel.trusted_points());
+        let recipients = [1, 3, 4];
+        let (header, key) = encrypt(&verified, &recipients, &mut rng).unwrap();
+        for identifier in recipients.iter() {
+            assert_eq!(
+                participants[identifier - 1].decrypt_from_store(&recipients, &verified, &header),
+                Ok(key.clone())
+            );
+        }
+        assert_eq!(verified.g1_points(0..9).map(|points| points.len()), Ok(9));
+
+        // the points of another channel are refused, starting with P_n
+        let lying = VerifiedStore::new(other, channel.trusted_points());
+        assert_eq!(
+            encrypt(&lying, &recipients, &mut rng).map(|_| ()),
+            Err(ParamStoreError::Store(VerifiedStoreError::Unverified(4)))
+        );
+        assert_eq!(
+            participants[3].decrypt_from_store(&recipients, &lying, &header),
+            Err(ParamStoreError::Store(VerifiedStoreError::Unverified(6)))
+        );
+    }
+
+    #[test]
+    fn store_refuses_bad_sets_and_points() {
+        let mut rng = thread_rng();
+        let (mut channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(4, &mut rng).unwrap();
+        let encrypt_error = |set: &[usize], channel: &BroadcastChannel<Bls12_381>| {
+            encrypt(channel, set, &mut thread_rng()).map(|_| ())
+        };
+        for (set, error) in [
+            (&[][..], EncryptError::EmptySet),
+            (&[0, 1], EncryptError::IdentifierOutOfRange(0)),
+            (&[1, 6], EncryptError::IdentifierOutOfRange(6)),
+            (&[2, 1, 2], EncryptError::DuplicateRecipient(2)),
+        ] {
+            assert_eq!(
+                encrypt_error(set, &channel),
+                Err(ParamStoreError::Encrypt(error))
+            );
+        }
+        channel.retire(3).unwrap();
+        assert_eq!(
+            encrypt_error(&[1, 3], &channel),
+            Err(ParamStoreError::Encrypt(EncryptError::RetiredRecipient(3)))
+        );
+
+        let (header, _) = encrypt(&channel, &[1, 2], &mut rng).unwrap();
+        let decrypt = |set: &[usize], header: &Header<Bls12_381>| {
+            participants[0].decrypt_from_store(set, &channel, header)
+        };
+        assert_eq!(
+            decrypt(&[1, 9], &header),
+            Err(ParamStoreError::Decrypt(
+                DecryptError::IdentifierOutOfRange(9)
+            ))
+        );
+        assert_eq!(
+            decrypt(&[1, 2, 1], &header),
+            Err(ParamStoreError::Decrypt(DecryptError::DuplicateRecipient(
+                1
+            )))
+        );
+        assert_eq!(
+            decrypt(&[2, 4], &header),
+            Err(ParamStoreError::Decrypt(DecryptError::NotInSet))
+        );
+        let identity = Header {
+            ctx_0: header.ctx_0,
+            ctx_1: Zero::zero(),
+        };
+        assert_eq!(
+            decrypt(&[1, 2], &identity),
+            Err(ParamStoreError::Decrypt(DecryptError::IdentityPoint))
+        );
+
+        let short = ShortStore(channel.clone());
+        assert_eq!(
+            encrypt(&short, &[1, 2], &mut rng).map(|_| ()),
+            Err(ParamStoreError::WrongLength {
+                expected: 1,
+                found: 0
+            })
+        );
+        assert!(matches!(
+            participants[0].decrypt_from_store(&[1, 2], &short, &header),
+            Err(ParamStoreError::WrongLength { .. })
+        ));
+    }
+}
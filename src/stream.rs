@@ -0,0 +1,150 @@
+//! Encryption for sets read from iterators.
+//!
+//! Sets of millions of recipients usually come out of a database cursor or a bitmap, and
+//! collecting them into a slice first costs as much memory as the set. The functions here
+//! aggregate the points of the recipients as the identifiers are read, checking each of them
+//! against the channel and the policy on the way. Fallible sources yield `Result`s, and their
+//! errors stop the encryption with [`StreamError::Source`].
+//!
+//! Without the whole set at hand, neither the canonical hash of the set nor the aggregate cache
+//! can be used. Encryptions report an [`Event::EncryptedStream`] instead of an
+//! [`Event::Encrypted`], whose digest is the SHA-256 of a tag followed by the identifiers as
+//! little-endian `u64`s, in the order read. It only matches for sets streamed in the same order.
+
+use core::convert::Infallible;
+
+use ark_ec::{PairingEngine, ProjectiveCurve};
+use ark_ff::Zero;
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha256};
+
+use crate::error::{EncryptError, StreamError};
+use crate::events::Event;
+use crate::session::EncryptionSession;
+use crate::{BroadcastChannel, Header, SharedKey};
+
+/// Domain separation tag of the digests of streamed sets.
+const STREAM_DIGEST_TAG: &[u8] = b"DGS-BE-RECIPIENT-STREAM-V1";
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Session encrypting for the identifiers read from `identifiers`. Fails at the first error
+    /// of the source, the first identifier outside of the channel, or the first identifier beyond
+    /// the limit of the policy, in which case the reported size is that limit plus one.
+    pub fn session_from_stream<I, S>(
+        &self,
+        identifiers: I,
+    ) -> Result<EncryptionSession<'_, E>, StreamError<S>>
+    where
+        I: IntoIterator<Item = Result<usize, S>>,
+    {
+        let mut hasher = Sha256::new();
+        hasher.update(STREAM_DIGEST_TAG);
+        let mut aggregate = E::G1Projective::zero();
+        let mut recipients = 0;
+        for identifier in identifiers {
+            let identifier = identifier.map_err(StreamError::Source)?;
+            if identifier == 0 || identifier > self.number_participants {
+                return Err(StreamError::Encrypt(EncryptError::IdentifierOutOfRange(
+                    identifier,
+                )));
+            }
+            self.check_not_retired(&[identifier])
+                .map_err(StreamError::Encrypt)?;
+            recipients += 1;
+            match self.policy.max_set_size() {
+                Some(limit) if recipients > limit => {
+                    return Err(StreamError::Encrypt(EncryptError::SetTooLarge {
+                        size: recipients,
+                        limit,
+                    }))
+                }
+                _ => {}
+            }
+
+            hasher.update((identifier as u64).to_le_bytes());
+            aggregate.add_assign_mixed(
+                &self.broadcaster_pk_g1[self.number_participants + 1 - identifier],
+            );
+        }
+
+        let mut digest = [0u8; 32];
+        digest.copy_from_slice(&hasher.finalize());
+        let event = Event::EncryptedStream { recipients, digest };
+        Ok(EncryptionSession::new(self, aggregate, event, None))
+    }
+
+    /// Same as [`BroadcastChannel::encrypt`], for the identifiers read from `identifiers`.
+    pub fn encrypt_iter<I, R>(
+        &self,
+        identifiers: I,
+        rng: &mut R,
+    ) -> Result<(Header<E>, SharedKey<E>), EncryptError>
+    where
+        I: IntoIterator<Item = usize>,
+        R: RngCore + CryptoRng,
+    {
+        match self.encrypt_stream(identifiers.into_iter().map(Ok::<_, Infallible>), rng) {
+            Ok(encryption) => Ok(encryption),
+            Err(StreamError::Encrypt(error)) => Err(error),
+            Err(StreamError::Source(never)) => match never {},
+        }
+    }
+
+    /// Same as [`BroadcastChannel::encrypt`], for the identifiers read from the fallible source
+    /// `identifiers`.
+    pub fn encrypt_stream<I, S, R>(
+        &self,
+        identifiers: I,
+        rng: &mut R,
+    ) -> Result<(Header<E>, SharedKey<E>), StreamError<S>>
+    where
+        I: IntoIterator<Item = Result<usize, S>>,
+        R: RngCore + CryptoRng,
+    {
+        Ok(self.session_from_stream(identifiers)?.encrypt(rng))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::Policy;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn streamed_sets_decrypt() {
+        let mut rng = thread_rng();
+        let (mut channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(10, &mut rng).unwrap();
+
+        let (header, key) = channel
+            .encrypt_iter((1..=10).filter(|identifier| identifier % 3 == 0), &mut rng)
+            .unwrap();
+        assert_eq!(
+            participants[5].decrypt(&[3, 6, 9], &channel, &header),
+            Ok(key)
+        );
+        assert!(channel
+            .session_from_stream(vec![Ok::<_, ()>(2)])
+            .unwrap()
+            .set_hash()
+            .is_none());
+
+        let failing = vec![Ok(1), Err("cursor closed"), Ok(2)];
+        assert_eq!(
+            channel.encrypt_stream(failing, &mut rng).err(),
+            Some(StreamError::Source("cursor closed"))
+        );
+        assert_eq!(
+            channel.encrypt_iter(vec![4, 11], &mut rng).err(),
+            Some(EncryptError::IdentifierOutOfRange(11))
+        );
+
+        channel.set_policy(Policy::default().with_max_set_size(2));
+        assert_eq!(
+            channel.encrypt_iter(1.., &mut rng).err(),
+            Some(EncryptError::SetTooLarge { size: 3, limit: 2 })
+        );
+    }
+}
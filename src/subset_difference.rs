@@ -0,0 +1,503 @@
+//! Subset-difference broadcast encryption, after Naor, Naor and Lotspiech.
+//!
+//! The participants are the leaves of a complete binary tree, padded to a power of two with
+//! leaves nobody holds. A subset `S(i, j)` is the leaves under node `i` minus those under its
+//! descendant `j`, and the set of an encryption is covered by at most `2r - 1` such subsets,
+//! where `r` is the number of leaves left out. The header wraps a fresh key under the key of
+//! each subset of the cover, so it grows with `r` instead of staying constant, but only hashes
+//! are computed. One more subset holds every leaf, for the encryptions leaving nobody out.
+//!
+//! Every inner node `i` has a random label. The label of `S(i, j)` is derived from it by walking
+//! down to `j` with a pseudorandom generator, one output per direction, and the key of the subset
+//! is a third output. A participant holds the labels of the subsets `S(i, k)` where `i` is above
+//! its leaf and `k` hangs just off its path, `O(log² n)` of them, and derives the label of any
+//! subset containing it from those. A leaf under `j` has none of the labels above `j` on the
+//! path from `i`, so it cannot.
+//!
+//! Encrypting takes the labels, so only the [`SubsetDifferenceCenter`] does.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use rand_core::{CryptoRng, RngCore};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::error::{DecryptError, EncryptError, SetupError};
+use crate::kdf::{HkdfSha256, KeyDerivation};
+use crate::scheme::BroadcastScheme;
+use crate::set::first_repetition;
+
+/// Output of the generator towards the left child.
+const LEFT_TAG: &[u8] = b"DGS-BE-SD-LEFT-V1";
+
+/// Output of the generator towards the right child.
+const RIGHT_TAG: &[u8] = b"DGS-BE-SD-RIGHT-V1";
+
+/// Output of the generator giving the key of a subset.
+const KEY_TAG: &[u8] = b"DGS-BE-SD-KEY-V1";
+
+/// Prefix of the info of the wrapping of the key of a header.
+const WRAP_TAG: &[u8] = b"DGS-BE-SD-WRAP-V1";
+
+/// Label or key of 32 bytes.
+pub type Label = [u8; 32];
+
+/// Depth of node `node` of the tree, the root being `1` at depth zero.
+fn depth(node: usize) -> u32 {
+    usize::BITS - 1 - node.leading_zeros()
+}
+
+/// Whether `ancestor` is `node` or above it.
+fn is_ancestor(ancestor: usize, node: usize) -> bool {
+    depth(node) >= depth(ancestor) && node >> (depth(node) - depth(ancestor)) == ancestor
+}
+
+/// Output of the generator seeded with `label`, for `tag`.
+fn generate(tag: &[u8], label: &Label) -> Label {
+    let mut output = [0; 32];
+    HkdfSha256::derive(label, tag, &mut output).unwrap();
+    output
+}
+
+/// Label of node `to` derived from the label `label` of its ancestor `from`.
+fn derive_down(mut label: Label, from: usize, to: usize) -> Label {
+    for step in (0..depth(to) - depth(from)).rev() {
+        let tag = if (to >> step) & 1 == 0 {
+            LEFT_TAG
+        } else {
+            RIGHT_TAG
+        };
+        let next = generate(tag, &label);
+        label.zeroize();
+        label = next;
+    }
+    label
+}
+
+/// `key` of a header, masked with the key of a subset under `nonce`.
+fn wrap(subset_key: &Label, nonce: &Label, key: &Label) -> Label {
+    let mut info = WRAP_TAG.to_vec();
+    info.extend_from_slice(nonce);
+    let mut mask = [0; 32];
+    HkdfSha256::derive(subset_key, &info, &mut mask).unwrap();
+    let mut wrapped = *key;
+    for (byte, mask) in wrapped.iter_mut().zip(mask.iter()) {
+        *byte ^= mask;
+    }
+    mask.zeroize();
+    wrapped
+}
+
+/// Subset of the cover of a set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Subset {
+    /// Every leaf of the tree
+    All,
+    /// The leaves under `top` that are not under its descendant `removed`
+    Difference {
+        /// node the subset hangs from
+        top: usize,
+        /// node whose leaves are left out
+        removed: usize,
+    },
+}
+
+/// Shape of the tree of a channel, which is public.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SubsetDifferenceTree {
+    number_participants: usize,
+    /// number of leaves, the smallest power of two holding every participant
+    leaves: usize,
+}
+
+impl SubsetDifferenceTree {
+    /// Number of participants of the channel.
+    pub fn number_participants(&self) -> usize {
+        self.number_participants
+    }
+
+    /// Leaf of participant `identifier`.
+    fn leaf(&self, identifier: usize) -> usize {
+        self.leaves + identifier - 1
+    }
+
+    /// Participants under `node`, as a range of identifiers which may go past the channel.
+    fn identifiers_under(&self, node: usize) -> (usize, usize) {
+        let height = depth(self.leaves) - depth(node);
+        let first = node << height;
+        let last = (node + 1) << height;
+        (first - self.leaves + 1, last - self.leaves + 1)
+    }
+
+    /// Subsets covering `set`, sorted and of identifiers in the channel.
+    fn cover(&self, set: &[usize]) -> Vec<Subset> {
+        if set.len() == self.leaves {
+            return vec![Subset::All];
+        }
+        let mut subsets = Vec::new();
+        let bottom = self.chain(1, set, &mut subsets);
+        if bottom != 1 {
+            subsets.push(Subset::Difference {
+                top: 1,
+                removed: bottom,
+            });
+        }
+        subsets
+    }
+
+    /// Number of leaves under `node` that are left out of `set`.
+    fn left_out(&self, node: usize, set: &[usize]) -> usize {
+        let (first, last) = self.identifiers_under(node);
+        let members = set.partition_point(|identifier| *identifier < last)
+            - set.partition_point(|identifier| *identifier < first);
+        last - first - members
+    }
+
+    /// Add to `subsets` those covering the leaves of `set` under `node`, which has leaves left
+    /// out, except those hanging from `node` itself. Returns the node below which the leaves
+    /// left out are, which is `node` when they are under both of its children.
+    fn chain(&self, node: usize, set: &[usize], subsets: &mut Vec<Subset>) -> usize {
+        let (first, last) = self.identifiers_under(node);
+        if self.left_out(node, set) == last - first {
+            return node;
+        }
+        let children = [2 * node, 2 * node + 1];
+        let left_out: Vec<bool> = children
+            .iter()
+            .map(|child| self.left_out(*child, set) > 0)
+            .collect();
+        if left_out[0] && left_out[1] {
+            for child in children.iter() {
+                let bottom = self.chain(*child, set, subsets);
+                if bottom != *child {
+                    subsets.push(Subset::Difference {
+                        top: *child,
+                        removed: bottom,
+                    });
+                }
+            }
+            node
+        } else if left_out[0] {
+            self.chain(children[0], set, subsets)
+        } else {
+            self.chain(children[1], set, subsets)
+        }
+    }
+}
+
+/// Labels of the inner nodes of the tree, held by the authority, which encrypts.
+#[derive(Clone)]
+pub struct SubsetDifferenceCenter {
+    tree: SubsetDifferenceTree,
+    /// label of every inner node, at its index in the tree
+    labels: Vec<Label>,
+    /// key of the subset of every leaf
+    all_key: Label,
+}
+
+impl Drop for SubsetDifferenceCenter {
+    fn drop(&mut self) {
+        self.labels.zeroize();
+        self.all_key.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for SubsetDifferenceCenter {}
+
+/// Labels held by a participant.
+#[derive(Clone)]
+pub struct SubsetDifferenceKey {
+    identifier: usize,
+    /// label of `S(i, k)` for every `i` above the leaf and `k` hanging off the path below `i`,
+    /// sorted by `(i, k)`
+    labels: Vec<((usize, usize), Label)>,
+    all_key: Label,
+}
+
+impl Drop for SubsetDifferenceKey {
+    fn drop(&mut self) {
+        for (_, label) in self.labels.iter_mut() {
+            label.zeroize();
+        }
+        self.all_key.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for SubsetDifferenceKey {}
+
+/// Header of a subset-difference encryption.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SubsetDifferenceHeader {
+    nonce: Label,
+    /// subsets of the cover, with the key of the header wrapped under the key of each
+    subsets: Vec<(Subset, Label)>,
+}
+
+impl SubsetDifferenceHeader {
+    /// Subsets the header is encrypted for.
+    pub fn subsets(&self) -> impl Iterator<Item = Subset> + '_ {
+        self.subsets.iter().map(|(subset, _)| *subset)
+    }
+}
+
+impl SubsetDifferenceCenter {
+    /// Tree and authority of a channel of `n` participants. Fails if `n` is zero.
+    pub fn setup<R>(n: usize, rng: &mut R) -> Result<(SubsetDifferenceTree, Self), SetupError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if n == 0 {
+            return Err(SetupError::NoParticipants);
+        }
+        let tree = SubsetDifferenceTree {
+            number_participants: n,
+            leaves: n.next_power_of_two(),
+        };
+        let mut labels = vec![[0; 32]; tree.leaves];
+        for label in labels.iter_mut().skip(1) {
+            rng.fill_bytes(label);
+        }
+        let mut all_key = [0; 32];
+        rng.fill_bytes(&mut all_key);
+        let center = SubsetDifferenceCenter {
+            tree,
+            labels,
+            all_key,
+        };
+        Ok((tree, center))
+    }
+
+    /// Labels of participant `identifier`, if it is in the channel.
+    pub fn issue(&self, identifier: usize) -> Option<SubsetDifferenceKey> {
+        if identifier == 0 || identifier > self.tree.number_participants {
+            return None;
+        }
+        let leaf = self.tree.leaf(identifier);
+        let mut labels = Vec::new();
+        for above in 1..=depth(leaf) {
+            let top = leaf >> above;
+            for below in 1..=above {
+                // the child of the node on the path that is not on the path
+                let off_path = (leaf >> (below - 1)) ^ 1;
+                let label = derive_down(self.labels[top], top, off_path);
+                labels.push(((top, off_path), label));
+            }
+        }
+        labels.sort_unstable_by_key(|(position, _)| *position);
+        Some(SubsetDifferenceKey {
+            identifier,
+            labels,
+            all_key: self.all_key,
+        })
+    }
+
+    /// Key of `subset`.
+    fn subset_key(&self, subset: Subset) -> Label {
+        match subset {
+            Subset::All => self.all_key,
+            Subset::Difference { top, removed } => {
+                let mut label = derive_down(self.labels[top], top, removed);
+                let key = generate(KEY_TAG, &label);
+                label.zeroize();
+                key
+            }
+        }
+    }
+
+    /// Generate a key and its header for `set_recipients`. Fails if the set is empty, or
+    /// contains identifiers outside of the channel or more than once.
+    pub fn encrypt<R>(
+        &self,
+        set_recipients: &[usize],
+        rng: &mut R,
+    ) -> Result<(SubsetDifferenceHeader, Label), EncryptError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if let Some(index) = set_recipients
+            .iter()
+            .find(|index| **index == 0 || **index > self.tree.number_participants)
+        {
+            return Err(EncryptError::IdentifierOutOfRange(*index));
+        }
+        if set_recipients.is_empty() {
+            return Err(EncryptError::EmptySet);
+        }
+        if let Some(identifier) = first_repetition(set_recipients) {
+            return Err(EncryptError::DuplicateRecipient(identifier));
+        }
+        let mut set = set_recipients.to_vec();
+        set.sort_unstable();
+
+        let mut key = [0; 32];
+        rng.fill_bytes(&mut key);
+        let mut nonce = [0; 32];
+        rng.fill_bytes(&mut nonce);
+        let subsets = self
+            .tree
+            .cover(&set)
+            .into_iter()
+            .map(|subset| {
+                let mut subset_key = self.subset_key(subset);
+                let wrapped = wrap(&subset_key, &nonce, &key);
+                subset_key.zeroize();
+                (subset, wrapped)
+            })
+            .collect();
+        Ok((SubsetDifferenceHeader { nonce, subsets }, key))
+    }
+}
+
+impl SubsetDifferenceKey {
+    /// Identifier of the participant in the channel.
+    pub fn identifier(&self) -> usize {
+        self.identifier
+    }
+
+    /// Key of `subset`, if it contains the leaf `leaf` of the participant.
+    fn subset_key(&self, leaf: usize, subset: Subset) -> Option<Label> {
+        let (top, removed) = match subset {
+            Subset::All => return Some(self.all_key),
+            Subset::Difference { top, removed } => (top, removed),
+        };
+        if top == leaf
+            || top == removed
+            || !is_ancestor(top, leaf)
+            || !is_ancestor(top, removed)
+            || is_ancestor(removed, leaf)
+        {
+            return None;
+        }
+        // climb from `removed` to the child of the lowest node shared with the path of the leaf
+        let mut off_path = removed;
+        let mut on_path = leaf >> (depth(leaf) - depth(removed));
+        while off_path >> 1 != on_path >> 1 {
+            off_path >>= 1;
+            on_path >>= 1;
+        }
+        let index = self
+            .labels
+            .binary_search_by_key(&(top, off_path), |(position, _)| *position)
+            .ok()?;
+        let mut label = derive_down(self.labels[index].1, off_path, removed);
+        let key = generate(KEY_TAG, &label);
+        label.zeroize();
+        Some(key)
+    }
+
+    /// Recover the key of `header`, of a channel of shape `tree`. Fails if the participant is in
+    /// none of the subsets of the header.
+    pub fn decrypt(
+        &self,
+        tree: &SubsetDifferenceTree,
+        header: &SubsetDifferenceHeader,
+    ) -> Result<Label, DecryptError> {
+        let leaf = tree.leaf(self.identifier);
+        for (subset, wrapped) in header.subsets.iter() {
+            if let Some(mut subset_key) = self.subset_key(leaf, *subset) {
+                let key = wrap(&subset_key, &header.nonce, wrapped);
+                subset_key.zeroize();
+                return Ok(key);
+            }
+        }
+        Err(DecryptError::NotInSet)
+    }
+}
+
+/// The subset-difference scheme, for [`BroadcastScheme`].
+pub struct SubsetDifference;
+
+impl BroadcastScheme for SubsetDifference {
+    type Params = SubsetDifferenceTree;
+    type Authority = SubsetDifferenceCenter;
+    type Encryptor = SubsetDifferenceCenter;
+    type Decryptor = SubsetDifferenceKey;
+    type Header = SubsetDifferenceHeader;
+    type Key = Label;
+
+    fn setup<R: RngCore + CryptoRng>(
+        n: usize,
+        rng: &mut R,
+    ) -> Result<(Self::Params, Self::Authority), SetupError> {
+        SubsetDifferenceCenter::setup(n, rng)
+    }
+
+    fn keygen(
+        params: &Self::Params,
+        authority: &Self::Authority,
+        identifier: usize,
+    ) -> Option<Self::Decryptor> {
+        if *params != authority.tree {
+            return None;
+        }
+        authority.issue(identifier)
+    }
+
+    fn encryptor(_: &Self::Params, authority: &Self::Authority) -> Self::Encryptor {
+        authority.clone()
+    }
+
+    fn encrypt<R: RngCore + CryptoRng>(
+        encryptor: &Self::Encryptor,
+        set_recipients: &[usize],
+        rng: &mut R,
+    ) -> Result<(Self::Header, Self::Key), EncryptError> {
+        encryptor.encrypt(set_recipients, rng)
+    }
+
+    /// The set is carried by the header, so `set_recipients` is not used.
+    fn decrypt(
+        params: &Self::Params,
+        decryptor: &Self::Decryptor,
+        _: &[usize],
+        header: &Self::Header,
+    ) -> Result<Self::Key, DecryptError> {
+        decryptor.decrypt(params, header)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::thread_rng;
+
+    #[test]
+    fn covers_leave_out_exactly_the_others() {
+        let mut rng = thread_rng();
+        let (tree, center) = SubsetDifferenceCenter::setup(11, &mut rng).unwrap();
+        let keys: Vec<SubsetDifferenceKey> = (1..=11)
+            .map(|identifier| center.issue(identifier).unwrap())
+            .collect();
+        assert!(center.issue(12).is_none());
+
+        let sets: [&[usize]; 4] = [&[3], &[1, 2, 3, 4, 5, 6, 7, 8], &[2, 5, 6, 11], &[11, 1]];
+        for set in sets.iter() {
+            let (header, key) = center.encrypt(set, &mut rng).unwrap();
+            // 16 leaves, so at least the five unused ones are left out
+            let left_out = 16 - set.len();
+            assert!(header.subsets().count() < 2 * left_out);
+            for participant in keys.iter() {
+                let decrypted = participant.decrypt(&tree, &header);
+                if set.contains(&participant.identifier()) {
+                    assert_eq!(decrypted, Ok(key));
+                } else {
+                    assert_eq!(decrypted, Err(DecryptError::NotInSet));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn full_sets_use_one_subset() {
+        let mut rng = thread_rng();
+        let (tree, center) = SubsetDifferenceCenter::setup(4, &mut rng).unwrap();
+        let (header, key) = center.encrypt(&[4, 2, 1, 3], &mut rng).unwrap();
+        assert!(header.subsets().eq(vec![Subset::All]));
+        assert_eq!(center.issue(3).unwrap().decrypt(&tree, &header), Ok(key));
+
+        assert!(matches!(
+            center.encrypt(&[2, 2], &mut rng),
+            Err(EncryptError::DuplicateRecipient(2))
+        ));
+    }
+}
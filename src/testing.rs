@@ -0,0 +1,148 @@
+//! Utilities for testing code that builds on this crate. Enabled by the `testing` feature.
+//!
+//! Everything here is deterministic: channels are generated from a seed, so downstream tests get
+//! the same parameters and keys on every run without having to ship them. Nothing in this module
+//! should ever be used outside of tests.
+
+use ark_ec::{PairingEngine, ProjectiveCurve};
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+
+use crate::error::SetupError;
+use crate::{BroadcastChannel, Header, Recipient};
+
+/// Deterministic RNG used by the testing utilities.
+pub type MockRng = ChaCha20Rng;
+
+/// Number of participants of the channels returned by [`tiny_channel`].
+pub const TINY_CHANNEL_SIZE: usize = 4;
+
+/// Deterministic RNG seeded from `seed`.
+pub fn mock_rng(seed: u64) -> MockRng {
+    MockRng::seed_from_u64(seed)
+}
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Same as [`BroadcastChannel::init_participants`], with alpha and gamma drawn from a
+    /// [`MockRng`] seeded with `seed`, for test vectors. Anyone knowing the seed can decrypt
+    /// every header of the channel.
+    pub fn init_participants_from_seed(
+        n: usize,
+        seed: [u8; 32],
+    ) -> Result<(Self, Vec<Recipient<E>>), SetupError> {
+        Self::init_participants(n, &mut MockRng::from_seed(seed))
+    }
+}
+
+/// Channel with [`TINY_CHANNEL_SIZE`] participants over any pairing engine, generated from
+/// `seed`. The same seed always produces the same channel and keys.
+pub fn tiny_channel<E: PairingEngine>(seed: u64) -> (BroadcastChannel<E>, Vec<Recipient<E>>) {
+    // the size is not zero
+    BroadcastChannel::init_participants(TINY_CHANNEL_SIZE, &mut mock_rng(seed)).unwrap()
+}
+
+/// Tiny channel over BLS12-381 generated from a fixed seed.
+pub fn tiny_bls12_381() -> (
+    BroadcastChannel<ark_bls12_381::Bls12_381>,
+    Vec<Recipient<ark_bls12_381::Bls12_381>>,
+) {
+    tiny_channel(0)
+}
+
+/// Component of a header to corrupt.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HeaderComponent {
+    /// The component in G1
+    G1,
+    /// The component in G2
+    G2,
+}
+
+/// Copy of `header` where `component` is shifted by the group generator. The result is still a
+/// valid pair of group elements, but no recipient recovers the original key from it.
+pub fn corrupt_header<E: PairingEngine>(
+    header: &Header<E>,
+    component: HeaderComponent,
+) -> Header<E> {
+    let mut corrupted = *header;
+    match component {
+        HeaderComponent::G1 => corrupted.ctx_0 += E::G1Projective::prime_subgroup_generator(),
+        HeaderComponent::G2 => corrupted.ctx_1 += E::G2Projective::prime_subgroup_generator(),
+    }
+    corrupted
+}
+
+/// Copy of `recipient` whose private key is shifted by the group generator, so that it no longer
+/// matches its public key.
+pub fn corrupt_private_key<E: PairingEngine>(recipient: &Recipient<E>) -> Recipient<E> {
+    let mut corrupted = recipient.clone();
+    corrupted.key_pair.private_key += E::G1Projective::prime_subgroup_generator();
+    corrupted
+}
+
+/// Copy of `recipient` claiming to be participant `identifier`, while keeping its original keys.
+pub fn with_identifier<E: PairingEngine>(
+    recipient: &Recipient<E>,
+    identifier: usize,
+) -> Recipient<E> {
+    let mut relabelled = recipient.clone();
+    relabelled.identifier = identifier;
+    relabelled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+
+    #[test]
+    fn tiny_channels_are_deterministic() {
+        let (channel, participants) = tiny_bls12_381();
+        let (same_channel, _) = tiny_channel::<Bls12_381>(0);
+        assert_eq!(
+            channel.view().fingerprint(),
+            same_channel.view().fingerprint()
+        );
+
+        let recipients = [1, 2, 4];
+        let (header, key) = channel.encrypt(&recipients, &mut mock_rng(1)).unwrap();
+        assert!(channel.encrypt(&recipients, &mut mock_rng(1)).unwrap().0 == header);
+        assert_eq!(
+            participants[0].decrypt(&recipients, &channel, &header),
+            Ok(key.clone())
+        );
+
+        for component in [HeaderComponent::G1, HeaderComponent::G2].iter() {
+            let corrupted = corrupt_header(&header, *component);
+            assert_ne!(
+                participants[0].decrypt(&recipients, &channel, &corrupted),
+                Ok(key.clone())
+            );
+        }
+
+        let corrupted = corrupt_private_key(&participants[1]);
+        assert_ne!(
+            corrupted.decrypt(&recipients, &channel, &header),
+            Ok(key.clone())
+        );
+
+        let relabelled = with_identifier(&participants[0], 4);
+        assert_ne!(
+            relabelled.decrypt(&recipients, &channel, &header),
+            Ok(key.clone())
+        );
+
+        let (seeded, seeded_participants) =
+            BroadcastChannel::<Bls12_381>::init_participants_from_seed(3, [7; 32]).unwrap();
+        let (same, same_participants) =
+            BroadcastChannel::<Bls12_381>::init_participants_from_seed(3, [7; 32]).unwrap();
+        assert_eq!(seeded.to_bytes(), same.to_bytes());
+        assert_eq!(
+            seeded_participants[2].to_bytes(),
+            same_participants[2].to_bytes()
+        );
+        let (other, _) =
+            BroadcastChannel::<Bls12_381>::init_participants_from_seed(3, [8; 32]).unwrap();
+        assert_ne!(seeded.to_bytes(), other.to_bytes());
+    }
+}
@@ -0,0 +1,225 @@
+//! Conformance harness running directories of known-answer tests.
+//!
+//! Every file with the extension `kat` in a directory is read as in [`crate::kat::import`], and
+//! every known answer in it is checked against this crate: the setup from the seed, the
+//! encryption with the fixed `k` and the derivation of the key must give the recorded outputs
+//! byte for byte, and every recipient of the set must decrypt the recorded header to the
+//! recorded key with its recorded key pair. The last check runs on the values of the file, so
+//! that headers and keys produced by another implementation are decrypted by this one.
+//!
+//! A file that cannot be read or parsed does not stop the run: it is reported with its error,
+//! and the other files are checked.
+//!
+//! Only available with the `testvectors` feature.
+
+use std::convert::TryFrom;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ark_ec::PairingEngine;
+
+use crate::encoding::to_bytes;
+use crate::error::{KatError, TestVectorError};
+use crate::kat::{import, KnownAnswer};
+use crate::{BroadcastChannel, Header, Recipient};
+
+/// Extension of the files read by [`run_directory`].
+pub const VECTOR_EXTENSION: &str = "kat";
+
+/// Output that differs from the recorded one in a known answer of a file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    /// position of the known answer in its file, from zero
+    pub count: usize,
+    /// name of the field that differs, or `decrypt` when a recipient of the set does not
+    /// decrypt the recorded header to the recorded key
+    pub field: &'static str,
+}
+
+/// Outcome of the known answers of a file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileReport {
+    /// path of the file
+    pub path: PathBuf,
+    /// number of known answers checked, and the mismatches found, or why the file could not be
+    /// checked
+    pub outcome: Result<(usize, Vec<Mismatch>), TestVectorError>,
+}
+
+/// Outcome of the files of a directory, in the order of their names.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Report {
+    /// outcome of every file
+    pub files: Vec<FileReport>,
+}
+
+impl Report {
+    /// Number of known answers checked, over all files.
+    pub fn vectors(&self) -> usize {
+        self.files
+            .iter()
+            .filter_map(|file| file.outcome.as_ref().ok())
+            .map(|(vectors, _)| vectors)
+            .sum()
+    }
+
+    /// Whether every file was checked, and matched.
+    pub fn is_conformant(&self) -> bool {
+        self.files.iter().all(|file| match &file.outcome {
+            Ok((_, mismatches)) => mismatches.is_empty(),
+            Err(_) => false,
+        })
+    }
+}
+
+/// Fields of `answer` that differ from what this crate computes, in the order of the format.
+pub fn check<E: PairingEngine>(answer: &KnownAnswer) -> Result<Vec<&'static str>, KatError> {
+    let computed = KnownAnswer::compute::<E>(&answer.seed, answer.participants, &answer.set)?;
+    let mut mismatches = Vec::new();
+    let fields: [(&'static str, bool); 8] = [
+        ("alpha", computed.alpha == answer.alpha),
+        ("gamma", computed.gamma == answer.gamma),
+        ("k", computed.k == answer.k),
+        ("parameters", computed.parameters == answer.parameters),
+        ("recipient", computed.recipients == answer.recipients),
+        ("header", computed.header == answer.header),
+        ("key", computed.key == answer.key),
+        ("derived_key", computed.derived_key == answer.derived_key),
+    ];
+    mismatches.extend(
+        fields
+            .iter()
+            .filter(|(_, matches)| !matches)
+            .map(|(field, _)| *field),
+    );
+    if !decrypts::<E>(answer) {
+        mismatches.push("decrypt");
+    }
+    Ok(mismatches)
+}
+
+/// Whether every recipient of the set of `answer` decrypts its header to its key.
+fn decrypts<E: PairingEngine>(answer: &KnownAnswer) -> bool {
+    let channel = match BroadcastChannel::<E>::try_from(answer.parameters.as_slice()) {
+        Ok(channel) => channel,
+        Err(_) => return false,
+    };
+    let header = match Header::<E>::try_from(answer.header.as_slice()) {
+        Ok(header) => header,
+        Err(_) => return false,
+    };
+    answer.set.iter().all(|identifier| {
+        answer
+            .recipients
+            .get(identifier - 1)
+            .and_then(|bytes| Recipient::<E>::try_from(bytes.as_slice()).ok())
+            .and_then(|recipient| {
+                recipient
+                    .decrypt(answer.set.as_slice(), &channel, &header)
+                    .ok()
+            })
+            .is_some_and(|key| to_bytes(&key) == answer.key)
+    })
+}
+
+/// Mismatches of the known answers of `text`, with the number of known answers.
+pub fn run_text<E: PairingEngine>(text: &str) -> Result<(usize, Vec<Mismatch>), KatError> {
+    let answers = import(text)?;
+    let mut mismatches = Vec::new();
+    for (count, answer) in answers.iter().enumerate() {
+        for field in check::<E>(answer)? {
+            mismatches.push(Mismatch { count, field });
+        }
+    }
+    Ok((answers.len(), mismatches))
+}
+
+/// Outcome of the known answers of the file at `path`.
+pub fn run_file<E: PairingEngine>(path: &Path) -> FileReport {
+    let outcome = fs::read_to_string(path)
+        .map_err(|error| TestVectorError::Io(error.kind()))
+        .and_then(|text| run_text::<E>(&text).map_err(TestVectorError::Kat));
+    FileReport {
+        path: path.to_path_buf(),
+        outcome,
+    }
+}
+
+/// Outcome of the files with the extension [`VECTOR_EXTENSION`] in the directory at `path`.
+/// Only the listing of the directory failing is an error.
+pub fn run_directory<E: PairingEngine>(path: &Path) -> Result<Report, TestVectorError> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(path).map_err(|error| TestVectorError::Io(error.kind()))? {
+        let path = entry
+            .map_err(|error| TestVectorError::Io(error.kind()))?
+            .path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == VECTOR_EXTENSION) {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(Report {
+        files: paths.iter().map(|path| run_file::<E>(path)).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kat::export;
+    use crate::set::RecipientSet;
+    use ark_bls12_381::Bls12_381;
+
+    #[test]
+    fn directory_of_vectors_is_checked() {
+        let answers = vec![
+            KnownAnswer::compute::<Bls12_381>(b"first", 3, &RecipientSet::new(&[1, 3])).unwrap(),
+            KnownAnswer::compute::<Bls12_381>(b"second", 4, &RecipientSet::new(&[2])).unwrap(),
+        ];
+        let text = export(&answers);
+        let mut wrong = answers.clone();
+        wrong[1].derived_key[0] ^= 1;
+        wrong[1].key = wrong[0].key.clone();
+
+        let path = std::env::temp_dir().join(format!("dgs-be-vectors-{}", std::process::id()));
+        fs::create_dir_all(&path).unwrap();
+        fs::write(path.join("a.kat"), &text).unwrap();
+        fs::write(path.join("b.kat"), export(&wrong)).unwrap();
+        fs::write(path.join("c.kat"), "count = 0\nseed = zz\n").unwrap();
+        fs::write(path.join("notes.txt"), "not a vector file").unwrap();
+        let report = run_directory::<Bls12_381>(&path).unwrap();
+        fs::remove_dir_all(&path).unwrap();
+
+        assert_eq!(report.files.len(), 3);
+        assert_eq!(report.files[0].outcome, Ok((2, Vec::new())));
+        assert_eq!(
+            report.files[1].outcome,
+            Ok((
+                2,
+                vec![
+                    Mismatch {
+                        count: 1,
+                        field: "key"
+                    },
+                    Mismatch {
+                        count: 1,
+                        field: "derived_key"
+                    },
+                    Mismatch {
+                        count: 1,
+                        field: "decrypt"
+                    },
+                ]
+            ))
+        );
+        assert_eq!(
+            report.files[2].outcome,
+            Err(TestVectorError::Kat(KatError::InvalidValue(2)))
+        );
+        assert_eq!(report.vectors(), 4);
+        assert!(!report.is_conformant());
+
+        // only a directory that cannot be listed is an error
+        assert!(run_directory::<Bls12_381>(&path).is_err());
+    }
+}
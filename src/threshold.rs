@@ -0,0 +1,383 @@
+//! Threshold decryption with a private key split across devices.
+//!
+//! A participant holding its key on several devices splits it with [`KeyPair::split`] into `m`
+//! Shamir shares, `t` of which decrypt. The private key `d_i` is a point of G1, so the shares
+//! are the values at `1..=m` of a polynomial `f(x) = d_i + sum_{0 < k < t} x^k C_k` whose
+//! coefficients are random points, and any `t - 1` of them are independent of `d_i`.
+//!
+//! The key of a header is `e(ctx_0, Q_i) e(-(d_i + A), ctx_1)`, with `A` the sum of the points
+//! of the other recipients. Each device computes the same with its share in place of `d_i`, which
+//! is a [`PartialDecryption`], and [`combine_partials`] raises `t` of them to their Lagrange
+//! coefficients at zero: the coefficients sum to one, so the product is the key of the header.
+//! The key is only ever recovered in the target group, and the private key is never rebuilt.
+//!
+//! Shares and partial decryptions have compressed encodings, checked when decoded, to be stored
+//! on the devices and sent to the one combining them.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use ark_ec::{PairingEngine, ProjectiveCurve};
+use ark_ff::{Field, One, PrimeField, UniformRand, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use rand_core::{CryptoRng, RngCore};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::encoding::{from_bytes, to_bytes};
+use crate::error::{DecryptError, ThresholdError};
+use crate::{BroadcastChannel, Header, KeyPair, SharedKey};
+
+/// Share of the private key of a participant, held by one device.
+#[derive(Clone)]
+pub struct KeyPairShare<E: PairingEngine> {
+    /// point at which the polynomial was evaluated, in `1..=m`
+    index: usize,
+    /// number of shares needed to decrypt
+    threshold: usize,
+    /// public key of the participant
+    public_key: E::G2Projective,
+    /// value of the polynomial at `index`
+    point: E::G1Projective,
+}
+
+impl<E: PairingEngine> Drop for KeyPairShare<E> {
+    fn drop(&mut self) {
+        self.point.zeroize();
+    }
+}
+
+impl<E: PairingEngine> ZeroizeOnDrop for KeyPairShare<E> {}
+
+/// Decryption of a header with one share of a private key.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct PartialDecryption<E: PairingEngine> {
+    index: usize,
+    threshold: usize,
+    value: E::Fqk,
+}
+
+/// Lagrange coefficients at zero of the polynomials through the distinct nonzero `indices`.
+pub(crate) fn lagrange_at_zero<F: Field>(indices: &[usize]) -> Vec<F> {
+    indices
+        .iter()
+        .map(|index| {
+            let x = F::from(*index as u64);
+            let mut numerator = F::one();
+            let mut denominator = F::one();
+            for other in indices.iter().filter(|other| *other != index) {
+                let other = F::from(*other as u64);
+                numerator *= other;
+                denominator *= other - x;
+            }
+            // the indices are distinct, so the denominator is not zero
+            numerator * denominator.inverse().unwrap()
+        })
+        .collect()
+}
+
+/// Check that `threshold` out of `shares` is a valid sharing.
+pub(crate) fn check_threshold(threshold: usize, shares: usize) -> Result<(), ThresholdError> {
+    if threshold == 0 || threshold > shares {
+        return Err(ThresholdError::InvalidThreshold { threshold, shares });
+    }
+    Ok(())
+}
+
+impl<E: PairingEngine> KeyPair<E> {
+    /// Split the private key into `shares` shares, `threshold` of which decrypt. Fails if
+    /// `threshold` is zero or larger than `shares`.
+    pub fn split<R>(
+        &self,
+        threshold: usize,
+        shares: usize,
+        rng: &mut R,
+    ) -> Result<Vec<KeyPairShare<E>>, ThresholdError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        check_threshold(threshold, shares)?;
+        let mut coefficients: Vec<E::G1Projective> = (1..threshold)
+            .map(|_| {
+                let mut coefficient = E::G1Projective::prime_subgroup_generator();
+                coefficient *= E::Fr::rand(rng);
+                coefficient
+            })
+            .collect();
+
+        let key_shares = (1..=shares)
+            .map(|index| {
+                // Horner's rule, from the highest coefficient down to the private key
+                let x = E::Fr::from(index as u64);
+                let mut point = E::G1Projective::zero();
+                for coefficient in coefficients.iter().rev() {
+                    point += coefficient;
+                    point *= x;
+                }
+                point += self.private_key;
+                KeyPairShare {
+                    index,
+                    threshold,
+                    public_key: self.public_key,
+                    point,
+                }
+            })
+            .collect();
+        coefficients.zeroize();
+        Ok(key_shares)
+    }
+}
+
+impl<E: PairingEngine> KeyPairShare<E> {
+    /// Point at which the share was evaluated, in `1..=m`.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Number of shares needed to decrypt.
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// Compressed encoding of the share, point of the polynomial included.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+
+    /// Partial decryption of a header encrypted for `set_recipients`, by participant
+    /// `identifier` whose key this is a share of. Fails if the set or the header are refused as
+    /// by [`crate::Recipient::decrypt`].
+    pub fn partial_decrypt(
+        &self,
+        identifier: usize,
+        set_recipients: &[usize],
+        channel: &BroadcastChannel<E>,
+        header: &Header<E>,
+    ) -> Result<PartialDecryption<E>, DecryptError> {
+        channel.check_decryption(identifier, set_recipients, header)?;
+        let aggregate = channel.decryption_aggregate(set_recipients, identifier);
+
+        let pairs = [
+            (
+                header.ctx_0.into_affine().into(),
+                self.public_key.into_affine().into(),
+            ),
+            (
+                (-(self.point + aggregate)).into_affine().into(),
+                header.ctx_1.into_affine().into(),
+            ),
+        ];
+        Ok(PartialDecryption {
+            index: self.index,
+            threshold: self.threshold,
+            value: E::product_of_pairings(pairs.iter()),
+        })
+    }
+}
+
+impl<E: PairingEngine> PartialDecryption<E> {
+    /// Index of the share the decryption was computed with.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Compressed encoding of the partial decryption.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+}
+
+/// Index and threshold of a share, both of which start at 1.
+fn share_position<R: Read>(mut reader: R) -> Result<(usize, usize), SerializationError> {
+    let index = usize::deserialize(&mut reader)?;
+    let threshold = usize::deserialize(&mut reader)?;
+    if index == 0 || threshold == 0 {
+        return Err(SerializationError::InvalidData);
+    }
+    Ok((index, threshold))
+}
+
+impl<E: PairingEngine> CanonicalSerialize for KeyPairShare<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.index.serialize(&mut writer)?;
+        self.threshold.serialize(&mut writer)?;
+        self.public_key.serialize(&mut writer)?;
+        self.point.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.index.serialized_size()
+            + self.threshold.serialized_size()
+            + self.public_key.serialized_size()
+            + self.point.serialized_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for KeyPairShare<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let (index, threshold) = share_position(&mut reader)?;
+        Ok(KeyPairShare {
+            index,
+            threshold,
+            public_key: E::G2Projective::deserialize(&mut reader)?,
+            point: E::G1Projective::deserialize(&mut reader)?,
+        })
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for KeyPairShare<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+impl<E: PairingEngine> CanonicalSerialize for PartialDecryption<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.index.serialize(&mut writer)?;
+        self.threshold.serialize(&mut writer)?;
+        self.value.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.index.serialized_size()
+            + self.threshold.serialized_size()
+            + self.value.serialized_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for PartialDecryption<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let (index, threshold) = share_position(&mut reader)?;
+        let value = E::Fqk::deserialize(&mut reader)?;
+        // a product of pairings is never zero, and raising zero would give a zero key
+        if value.is_zero() {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(PartialDecryption {
+            index,
+            threshold,
+            value,
+        })
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for PartialDecryption<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+/// Key of a header from the partial decryptions of at least a threshold of distinct shares. Fails
+/// if there are fewer, or if two partial decryptions come from the same share.
+pub fn combine_partials<E: PairingEngine>(
+    partials: &[PartialDecryption<E>],
+) -> Result<SharedKey<E>, ThresholdError> {
+    let threshold = partials.first().map_or(1, |partial| partial.threshold);
+    if partials.len() < threshold {
+        return Err(ThresholdError::NotEnoughShares {
+            shares: partials.len(),
+            threshold,
+        });
+    }
+    if let Some(position) = (1..partials.len()).find(|position| {
+        partials[..*position]
+            .iter()
+            .any(|partial| partial.index == partials[*position].index)
+    }) {
+        return Err(ThresholdError::DuplicateShare(partials[position].index));
+    }
+
+    let partials = &partials[..threshold];
+    let indices: Vec<usize> = partials.iter().map(|partial| partial.index).collect();
+    let coefficients = lagrange_at_zero::<E::Fr>(&indices);
+    let mut key = E::Fqk::one();
+    for (partial, coefficient) in partials.iter().zip(coefficients.iter()) {
+        key *= partial.value.pow(coefficient.into_repr());
+    }
+    Ok(SharedKey::new(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn threshold_of_shares_decrypts() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(4, &mut rng).unwrap();
+        let set = [1, 3, 4];
+        let (header, key) = channel.encrypt(&set, &mut rng).unwrap();
+
+        let shares = participants[2].key_pair().split(3, 5, &mut rng).unwrap();
+        let partials: Vec<PartialDecryption<Bls12_381>> = shares
+            .iter()
+            .map(|share| share.partial_decrypt(3, &set, &channel, &header).unwrap())
+            .collect();
+        assert_eq!(combine_partials(&partials[..3]), Ok(key.clone()));
+        assert_eq!(
+            combine_partials(&[partials[4], partials[0], partials[2]]),
+            Ok(key.clone())
+        );
+        assert_eq!(
+            combine_partials(&partials[..2]),
+            Err(ThresholdError::NotEnoughShares {
+                shares: 2,
+                threshold: 3
+            })
+        );
+        assert_eq!(
+            combine_partials(&[partials[1], partials[3], partials[1]]),
+            Err(ThresholdError::DuplicateShare(2))
+        );
+
+        assert!(matches!(
+            participants[0].key_pair().split(4, 3, &mut rng),
+            Err(ThresholdError::InvalidThreshold {
+                threshold: 4,
+                shares: 3
+            })
+        ));
+        let single = participants[0].key_pair().split(1, 1, &mut rng).unwrap();
+        let partial = single[0]
+            .partial_decrypt(1, &set, &channel, &header)
+            .unwrap();
+        assert_eq!(combine_partials(&[partial]), Ok(key.clone()));
+    }
+
+    #[test]
+    fn shares_and_partials_round_trip() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(3, &mut rng).unwrap();
+        let (header, key) = channel.encrypt(&[1, 2], &mut rng).unwrap();
+
+        let shares = participants[1].key_pair().split(2, 3, &mut rng).unwrap();
+        let partials: Vec<PartialDecryption<Bls12_381>> = shares[1..]
+            .iter()
+            .map(|share| {
+                let share =
+                    KeyPairShare::<Bls12_381>::try_from(share.to_bytes().as_slice()).unwrap();
+                let partial = share
+                    .partial_decrypt(2, &[1, 2], &channel, &header)
+                    .unwrap();
+                PartialDecryption::try_from(partial.to_bytes().as_slice()).unwrap()
+            })
+            .collect();
+        assert_eq!(partials[0].index(), 2);
+        assert_eq!(combine_partials(&partials), Ok(key.clone()));
+
+        // index 0 would be the private key itself
+        let mut bytes = shares[0].to_bytes();
+        bytes[..8].copy_from_slice(&0u64.to_le_bytes());
+        assert!(KeyPairShare::<Bls12_381>::try_from(bytes.as_slice()).is_err());
+        let mut bytes = partials[0].to_bytes();
+        bytes.push(0);
+        assert!(PartialDecryption::<Bls12_381>::try_from(bytes.as_slice()).is_err());
+    }
+}
@@ -0,0 +1,90 @@
+//! Tracing of leaked keys from pirate decoders.
+//!
+//! A pirate decoder is anything that decrypts headers without being a participant, typically a
+//! box built from one leaked private key. [`BroadcastChannel::trace`] runs the linear tracing
+//! procedure against it: the probe sets are the participants from `i` onwards, for every `i`, and
+//! each is encrypted for `trials` times. A box built from the key of participant `j` decrypts the
+//! probes for every `i` up to `j` and none after, so its rate of success drops at `j`, and the
+//! identifier where the rate drops the most is reported. Boxes that only decrypt some headers are
+//! traced as long as the drop stands out of the noise of the trials.
+//!
+//! Probes are ordinary headers, produced by the encryption of the channel, so a box cannot tell
+//! them from the broadcast it was built for. The set of a header travels with it, so the box is
+//! handed both. Keys that are retired are left out of every probe set, as their holders are
+//! already excluded.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use ark_ec::PairingEngine;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::error::EncryptError;
+use crate::{BroadcastChannel, Header, SharedKey};
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Identifier of the participant whose key `pirate` decrypts with, probing every set
+    /// `trials` times. Returns `None` if the box never recovers the key of a probe. Fails if a
+    /// probe set breaks the policy of the channel.
+    pub fn trace<F, R>(
+        &self,
+        mut pirate: F,
+        trials: usize,
+        rng: &mut R,
+    ) -> Result<Option<usize>, EncryptError>
+    where
+        F: FnMut(&[usize], &Header<E>) -> Option<SharedKey<E>>,
+        R: RngCore + CryptoRng,
+    {
+        let suspects: Vec<usize> = self
+            .issued()
+            .filter(|identifier| !self.retired.contains(identifier))
+            .collect();
+
+        // successes for the probe sets made of the suspects from each position onwards, the
+        // empty set at the end being decrypted by nobody
+        let mut successes = vec![0; suspects.len() + 1];
+        for (position, count) in successes.iter_mut().enumerate().take(suspects.len()) {
+            let set = &suspects[position..];
+            let session = self.session(set)?;
+            for _ in 0..trials {
+                let (header, key) = session.encrypt(rng);
+                if pirate(set, &header) == Some(key) {
+                    *count += 1;
+                }
+            }
+        }
+
+        let mut traced = None;
+        let mut largest_drop = 0;
+        for (position, pair) in successes.windows(2).enumerate() {
+            if pair[0] > pair[1] + largest_drop {
+                largest_drop = pair[0] - pair[1];
+                traced = Some(suspects[position]);
+            }
+        }
+        Ok(traced)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn leaked_keys_are_traced() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(6, &mut rng).unwrap();
+
+        let leaked = participants[3].clone();
+        let pirate =
+            |set: &[usize], header: &Header<Bls12_381>| leaked.decrypt(set, &channel, header).ok();
+        assert_eq!(channel.trace(pirate, 2, &mut rng), Ok(Some(4)));
+
+        let useless = |_: &[usize], _: &Header<Bls12_381>| None;
+        assert_eq!(channel.trace(useless, 2, &mut rng), Ok(None));
+    }
+}
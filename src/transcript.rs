@@ -0,0 +1,300 @@
+//! Append-only transcript of the headers a broadcaster issued.
+//!
+//! Compliance teams need to show, long after the fact, what was broadcast to whom, without
+//! keeping the plaintexts or the keys. A [`Transcript`] records, for each header, the time given
+//! by the caller, a commitment to the set of recipients and the hash of the header, and chains
+//! the records: the digest of each entry covers the digest of the one before, so that the
+//! [`Transcript::head`] of the transcript, once published or signed, fixes every entry up to it.
+//! Removing, reordering or editing an entry changes every digest after it.
+//!
+//! [`Transcript::export`] encodes the entries, and [`verify`] recomputes the chain of an export,
+//! returning its head. Whoever holds a header and its set, for instance from a recipient, then
+//! checks with [`Transcript::proves`] that they are the ones of an entry.
+//!
+//! The commitment to a set is its SHA-256 hash: it binds the set, but does not hide it from
+//! someone guessing the sets of a small channel. The timestamps are whatever the caller passes,
+//! usually seconds since the Unix epoch, and must not decrease.
+
+use alloc::vec::Vec;
+
+use ark_ec::PairingEngine;
+use sha2::{Digest, Sha256};
+
+use crate::error::TranscriptError;
+use crate::set::RecipientSet;
+use crate::wire::{take, take_u64};
+use crate::Header;
+
+/// Domain separation tag of the digests of the chain.
+const CHAIN_TAG: &[u8] = b"DGS-BE-TRANSCRIPT-CHAIN-V1";
+
+/// Domain separation tag of the commitments to sets.
+const SET_TAG: &[u8] = b"DGS-BE-TRANSCRIPT-SET-V1";
+
+/// Domain separation tag of the hashes of headers.
+const HEADER_TAG: &[u8] = b"DGS-BE-TRANSCRIPT-HEADER-V1";
+
+/// Length of an exported entry: the timestamp, the commitment, the hash and the digest.
+const ENTRY_LENGTH: usize = 8 + 3 * 32;
+
+/// SHA-256 digest.
+pub type Hash = [u8; 32];
+
+fn hash(tag: &[u8], parts: &[&[u8]]) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update(tag);
+    for part in parts {
+        hasher.update(part);
+    }
+
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&hasher.finalize());
+    hash
+}
+
+/// Commitment to `set_recipients`, in any order and with repetitions.
+pub fn set_commitment(set_recipients: &[usize]) -> Hash {
+    hash(
+        SET_TAG,
+        &[&RecipientSet::new(set_recipients).canonical_bytes()],
+    )
+}
+
+/// Hash of the encoding of `header`.
+pub fn header_hash<E: PairingEngine>(header: &Header<E>) -> Hash {
+    hash(HEADER_TAG, &[&header.to_bytes()])
+}
+
+/// Digest of the empty transcript, which the first entry chains to.
+pub fn genesis() -> Hash {
+    hash(CHAIN_TAG, &[])
+}
+
+/// Record of one header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TranscriptEntry {
+    timestamp: u64,
+    set_commitment: Hash,
+    header_hash: Hash,
+    digest: Hash,
+}
+
+impl TranscriptEntry {
+    /// Time the header was issued at.
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// Commitment to the set of recipients of the header.
+    pub fn set_commitment(&self) -> &Hash {
+        &self.set_commitment
+    }
+
+    /// Hash of the header.
+    pub fn header_hash(&self) -> &Hash {
+        &self.header_hash
+    }
+
+    /// Digest of the transcript up to and including this entry.
+    pub fn digest(&self) -> &Hash {
+        &self.digest
+    }
+
+    fn chained(previous: &Hash, timestamp: u64, set_commitment: Hash, header_hash: Hash) -> Self {
+        let digest = hash(
+            CHAIN_TAG,
+            &[
+                previous,
+                &timestamp.to_le_bytes(),
+                &set_commitment,
+                &header_hash,
+            ],
+        );
+        TranscriptEntry {
+            timestamp,
+            set_commitment,
+            header_hash,
+            digest,
+        }
+    }
+}
+
+/// Chain of the records of the headers issued, in the order they were issued.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Transcript {
+    entries: Vec<TranscriptEntry>,
+}
+
+impl Transcript {
+    /// Empty transcript.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record `header`, encrypted for `set_recipients` at `timestamp`, returning the new head.
+    /// Fails, leaving the transcript unchanged, if `timestamp` is before the one of the last
+    /// entry.
+    pub fn record<E: PairingEngine>(
+        &mut self,
+        header: &Header<E>,
+        set_recipients: &[usize],
+        timestamp: u64,
+    ) -> Result<Hash, TranscriptError> {
+        if let Some(last) = self.entries.last() {
+            if timestamp < last.timestamp {
+                return Err(TranscriptError::OutOfOrder(self.entries.len()));
+            }
+        }
+        let entry = TranscriptEntry::chained(
+            &self.head(),
+            timestamp,
+            set_commitment(set_recipients),
+            header_hash(header),
+        );
+        self.entries.push(entry);
+        Ok(entry.digest)
+    }
+
+    /// Digest of the whole transcript, the one of [`genesis`] if it is empty.
+    pub fn head(&self) -> Hash {
+        self.entries
+            .last()
+            .map(|entry| entry.digest)
+            .unwrap_or_else(genesis)
+    }
+
+    /// Entries, from the first one recorded.
+    pub fn entries(&self) -> &[TranscriptEntry] {
+        &self.entries
+    }
+
+    /// Number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no header has been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Whether entry `index` records `header`, encrypted for `set_recipients`.
+    pub fn proves<E: PairingEngine>(
+        &self,
+        index: usize,
+        header: &Header<E>,
+        set_recipients: &[usize],
+    ) -> bool {
+        self.entries.get(index).is_some_and(|entry| {
+            entry.header_hash == header_hash(header)
+                && entry.set_commitment == set_commitment(set_recipients)
+        })
+    }
+
+    /// Encoding of the transcript: the number of entries as a little-endian `u64`, then, for each
+    /// entry, its timestamp as a little-endian `u64`, the commitment to its set, the hash of its
+    /// header and its digest.
+    pub fn export(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(8 + self.entries.len() * ENTRY_LENGTH);
+        bytes.extend_from_slice(&(self.entries.len() as u64).to_le_bytes());
+        for entry in self.entries.iter() {
+            bytes.extend_from_slice(&entry.timestamp.to_le_bytes());
+            bytes.extend_from_slice(&entry.set_commitment);
+            bytes.extend_from_slice(&entry.header_hash);
+            bytes.extend_from_slice(&entry.digest);
+        }
+        bytes
+    }
+
+    /// Transcript exported by [`Transcript::export`], once its chain is verified.
+    pub fn import(bytes: &[u8]) -> Result<Self, TranscriptError> {
+        let malformed = |_| TranscriptError::Malformed;
+        let (count, mut bytes) = take_u64(bytes).map_err(malformed)?;
+        if (bytes.len() as u64) != count.saturating_mul(ENTRY_LENGTH as u64) {
+            return Err(TranscriptError::Malformed);
+        }
+
+        let mut transcript = Transcript::new();
+        for index in 0..count as usize {
+            let (timestamp, rest) = take_u64(bytes).map_err(malformed)?;
+            let (set_commitment, rest) = take(rest, 32).map_err(malformed)?;
+            let (header_hash, rest) = take(rest, 32).map_err(malformed)?;
+            let (digest, rest) = take(rest, 32).map_err(malformed)?;
+            bytes = rest;
+
+            if transcript
+                .entries
+                .last()
+                .is_some_and(|last| timestamp < last.timestamp)
+            {
+                return Err(TranscriptError::OutOfOrder(index));
+            }
+            let mut commitment = [0u8; 32];
+            commitment.copy_from_slice(set_commitment);
+            let mut hash = [0u8; 32];
+            hash.copy_from_slice(header_hash);
+            let entry = TranscriptEntry::chained(&transcript.head(), timestamp, commitment, hash);
+            if entry.digest[..] != *digest {
+                return Err(TranscriptError::BrokenChain(index));
+            }
+            transcript.entries.push(entry);
+        }
+        Ok(transcript)
+    }
+}
+
+/// Recompute the chain of a transcript exported by [`Transcript::export`], returning its head.
+/// Fails at the first entry whose digest does not follow from the ones before, or whose
+/// timestamp is before the one of the entry before.
+pub fn verify(bytes: &[u8]) -> Result<Hash, TranscriptError> {
+    Transcript::import(bytes).map(|transcript| transcript.head())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BroadcastChannel;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn transcripts_chain_and_verify() {
+        let mut rng = thread_rng();
+        let (channel, _) = BroadcastChannel::<Bls12_381>::init_participants(4, &mut rng).unwrap();
+        let mut transcript = Transcript::new();
+        assert_eq!(transcript.head(), genesis());
+
+        let sets: [&[usize]; 3] = [&[1, 2], &[4, 3], &[2]];
+        let mut headers = Vec::new();
+        for (timestamp, set) in sets.iter().enumerate() {
+            let (header, _) = channel.encrypt(set, &mut rng).unwrap();
+            let head = transcript.record(&header, set, timestamp as u64).unwrap();
+            assert_eq!(transcript.head(), head);
+            headers.push(header);
+        }
+        assert_eq!(
+            transcript.record(&headers[0], &[1], 1),
+            Err(TranscriptError::OutOfOrder(3))
+        );
+        assert_eq!(transcript.len(), 3);
+        assert!(transcript.proves(1, &headers[1], &[3, 4]));
+        assert!(!transcript.proves(1, &headers[1], &[3]));
+        assert!(!transcript.proves(1, &headers[0], &[3, 4]));
+
+        let exported = transcript.export();
+        assert_eq!(verify(&exported), Ok(transcript.head()));
+        assert_eq!(Transcript::import(&exported), Ok(transcript.clone()));
+
+        // editing the set of the second entry breaks the chain there
+        let mut edited = exported.clone();
+        edited[8 + ENTRY_LENGTH + 8] ^= 1;
+        assert_eq!(verify(&edited), Err(TranscriptError::BrokenChain(1)));
+        let mut rewound = exported.clone();
+        rewound[8 + 2 * ENTRY_LENGTH] = 0;
+        assert_eq!(verify(&rewound), Err(TranscriptError::OutOfOrder(2)));
+        assert_eq!(
+            verify(&exported[..exported.len() - 1]),
+            Err(TranscriptError::Malformed)
+        );
+    }
+}
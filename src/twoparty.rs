@@ -0,0 +1,268 @@
+//! Recipient keys split between two devices.
+//!
+//! [`Recipient::split_in_two`] turns the private key `d_i` into two additive shares `s_1 + s_2`
+//! held by different devices, say a phone and a laptop. Each share alone is a uniformly random
+//! point, useless for decryption. Decrypting takes two rounds:
+//!
+//! 1. the device that wants the key sends the set and the header to the other device;
+//! 2. the other device answers with its [`PartialDecryption`], `e(s, ctx_1)` for its share `s`,
+//!    and the first device completes the decryption with [`KeyHalf::co_decrypt`].
+//!
+//! The partial decryption is not verifiable: a wrong answer yields a wrong key, which the
+//! symmetric layer detects. The channel between the devices should be authenticated. Halves
+//! and partial decryptions have compressed encodings, checked when decoded, to be stored on the
+//! devices and sent between them.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use ark_ec::PairingEngine;
+use ark_ff::{UniformRand, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use rand_core::{CryptoRng, RngCore};
+
+use crate::encoding::{from_bytes, identifier, to_bytes};
+use crate::error::DecryptError;
+use crate::events::Event;
+use crate::keystore::key_of_header;
+use crate::{BroadcastChannel, Header, Recipient, SharedKey};
+
+/// One of the two shares of a recipient key.
+#[derive(Clone)]
+pub struct KeyHalf<E: PairingEngine> {
+    identifier: usize,
+    public_key: E::G2Projective,
+    share: E::G1Projective,
+}
+
+/// Contribution of one device to a decryption.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PartialDecryption<E: PairingEngine> {
+    identifier: usize,
+    value: E::Fqk,
+}
+
+impl<E: PairingEngine> Recipient<E> {
+    /// Split the private key into two shares, for two devices. The recipient is consumed, so
+    /// that the whole key does not outlive the split by mistake.
+    pub fn split_in_two<R>(self, rng: &mut R) -> (KeyHalf<E>, KeyHalf<E>)
+    where
+        R: RngCore + CryptoRng,
+    {
+        let first = E::G1Projective::rand(rng);
+        let half = |share| KeyHalf {
+            identifier: self.identifier,
+            public_key: self.key_pair.public_key,
+            share,
+        };
+        (half(first), half(self.key_pair.private_key - first))
+    }
+}
+
+impl<E: PairingEngine> KeyHalf<E> {
+    /// Identifier of the recipient the share belongs to.
+    pub fn identifier(&self) -> usize {
+        self.identifier
+    }
+
+    /// Compressed encoding of the half, share included.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+
+    /// Second round: contribution of this share to the decryption of `header`, for the other
+    /// device.
+    pub fn partial_decrypt(
+        &self,
+        set_recipients: &[usize],
+        channel: &BroadcastChannel<E>,
+        header: &Header<E>,
+    ) -> Result<PartialDecryption<E>, DecryptError> {
+        self.check(set_recipients, channel, header)?;
+        Ok(PartialDecryption {
+            identifier: self.identifier,
+            value: E::pairing(self.share, header.ctx_1),
+        })
+    }
+
+    /// Complete the decryption of `header` with the contribution `other` of the other share.
+    pub fn co_decrypt(
+        &self,
+        set_recipients: &[usize],
+        channel: &BroadcastChannel<E>,
+        header: &Header<E>,
+        other: &PartialDecryption<E>,
+    ) -> Result<SharedKey<E>, DecryptError> {
+        self.check(set_recipients, channel, header)?;
+        if other.identifier != self.identifier {
+            return Err(DecryptError::NotInSet);
+        }
+
+        let aggregate = channel.decryption_aggregate(set_recipients, self.identifier);
+        let key = key_of_header(&self.share, &self.public_key, &aggregate, header);
+        Ok(SharedKey::new(key / other.value))
+    }
+
+    fn check(
+        &self,
+        set_recipients: &[usize],
+        channel: &BroadcastChannel<E>,
+        header: &Header<E>,
+    ) -> Result<(), DecryptError> {
+        let result = channel.check_decryption(self.identifier, set_recipients, header);
+        if let Err(error) = result {
+            channel.emit(Event::DecryptFailed {
+                identifier: self.identifier,
+                reason: error,
+            });
+        }
+        result
+    }
+}
+
+impl<E: PairingEngine> PartialDecryption<E> {
+    /// Compressed encoding of the partial decryption.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+}
+
+impl<E: PairingEngine> CanonicalSerialize for KeyHalf<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.identifier.serialize(&mut writer)?;
+        self.public_key.serialize(&mut writer)?;
+        self.share.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.identifier.serialized_size()
+            + self.public_key.serialized_size()
+            + self.share.serialized_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for KeyHalf<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Ok(KeyHalf {
+            identifier: identifier(&mut reader)?,
+            public_key: E::G2Projective::deserialize(&mut reader)?,
+            share: E::G1Projective::deserialize(&mut reader)?,
+        })
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for KeyHalf<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+impl<E: PairingEngine> CanonicalSerialize for PartialDecryption<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.identifier.serialize(&mut writer)?;
+        self.value.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.identifier.serialized_size() + self.value.serialized_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for PartialDecryption<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let identifier = identifier(&mut reader)?;
+        let value = E::Fqk::deserialize(&mut reader)?;
+        // a pairing is never zero, and dividing by zero would give no key at all
+        if value.is_zero() {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(PartialDecryption { identifier, value })
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for PartialDecryption<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use ark_ff::One;
+    use rand::thread_rng;
+
+    #[test]
+    fn halves_decrypt_together() {
+        let mut rng = thread_rng();
+        let (channel, mut participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(3, &mut rng).unwrap();
+        let (header, key) = channel.encrypt(&[1, 3], &mut rng).unwrap();
+        let (phone, laptop) = participants.remove(2).split_in_two(&mut rng);
+
+        let partial = laptop.partial_decrypt(&[1, 3], &channel, &header).unwrap();
+        assert_eq!(
+            phone.co_decrypt(&[1, 3], &channel, &header, &partial),
+            Ok(key.clone())
+        );
+        let partial = phone.partial_decrypt(&[1, 3], &channel, &header).unwrap();
+        assert_eq!(
+            laptop.co_decrypt(&[1, 3], &channel, &header, &partial),
+            Ok(key.clone())
+        );
+
+        // one share alone does not decrypt
+        let missing = PartialDecryption {
+            identifier: 3,
+            value: <Bls12_381 as PairingEngine>::Fqk::one(),
+        };
+        assert_ne!(
+            phone.co_decrypt(&[1, 3], &channel, &header, &missing),
+            Ok(key.clone())
+        );
+
+        let (other_phone, _) = participants.remove(0).split_in_two(&mut rng);
+        let foreign = other_phone
+            .partial_decrypt(&[1, 3], &channel, &header)
+            .unwrap();
+        assert_eq!(
+            phone.co_decrypt(&[1, 3], &channel, &header, &foreign),
+            Err(DecryptError::NotInSet)
+        );
+        assert_eq!(
+            phone.partial_decrypt(&[1, 2], &channel, &header),
+            Err(DecryptError::NotInSet)
+        );
+    }
+
+    #[test]
+    fn halves_and_partials_round_trip() {
+        let mut rng = thread_rng();
+        let (channel, mut participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(2, &mut rng).unwrap();
+        let (header, key) = channel.encrypt(&[1, 2], &mut rng).unwrap();
+        let (phone, laptop) = participants.remove(0).split_in_two(&mut rng);
+
+        // the phone stores its half, the laptop sends its partial decryption over
+        let phone = KeyHalf::<Bls12_381>::try_from(phone.to_bytes().as_slice()).unwrap();
+        assert_eq!(phone.identifier(), 1);
+        let partial = laptop.partial_decrypt(&[1, 2], &channel, &header).unwrap();
+        let bytes = partial.to_bytes();
+        let received = PartialDecryption::try_from(bytes.as_slice()).unwrap();
+        assert_eq!(received, partial);
+        assert_eq!(
+            phone.co_decrypt(&[1, 2], &channel, &header, &received),
+            Ok(key.clone())
+        );
+
+        let mut zero_identifier = laptop.to_bytes();
+        zero_identifier[..8].copy_from_slice(&0u64.to_le_bytes());
+        assert!(KeyHalf::<Bls12_381>::try_from(zero_identifier.as_slice()).is_err());
+        assert!(PartialDecryption::<Bls12_381>::try_from(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
@@ -0,0 +1,277 @@
+//! Consistency checks for imported channels and recipients.
+//!
+//! Key material loaded from disk or received from another party may be truncated, corrupted or
+//! belong to a different channel. [`BroadcastChannel::validate`] and [`Recipient::validate`] run
+//! every structural and pairing-based check available and report all the failures found, rather
+//! than stopping at the first one.
+
+use alloc::vec::Vec;
+
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{FpParameters, PrimeField, Zero};
+
+use crate::{BroadcastChannel, Recipient};
+
+/// A single failed check.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ValidationFailure {
+    /// The vector of G1 points does not have `2n + 1` entries
+    G1Length {
+        /// expected number of points
+        expected: usize,
+        /// number of points found
+        found: usize,
+    },
+    /// The vector of G2 points does not have `n + 1` entries
+    G2Length {
+        /// expected number of points
+        expected: usize,
+        /// number of points found
+        found: usize,
+    },
+    /// More keys are recorded as issued than the channel has slots
+    IssuedExceedsCapacity,
+    /// The first point of a vector is not the group generator
+    NotGenerator,
+    /// The point at position `n + 1`, which must never be published, is not the identity
+    WithheldPointPublished,
+    /// A G1 point is not in the prime order subgroup
+    G1NotInSubgroup(usize),
+    /// A G2 point is not in the prime order subgroup
+    G2NotInSubgroup(usize),
+    /// The point `V` is the identity or not in the prime order subgroup
+    InvalidPointV,
+    /// The G1 point at this position is not the expected power of alpha
+    G1NotConsecutive(usize),
+    /// The G2 point at this position is not the expected power of alpha
+    G2NotConsecutive(usize),
+    /// The identifier of the recipient is not an issued slot of the channel
+    IdentifierOutOfRange(usize),
+    /// The public key of the recipient differs from the one published by the channel
+    PublicKeyMismatch,
+    /// The private key is the identity or not in the prime order subgroup
+    InvalidPrivateKey,
+    /// The private key does not match the public key and the point `V`
+    KeyMismatch,
+}
+
+/// Outcome of a validation, listing every check that failed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    failures: Vec<ValidationFailure>,
+}
+
+impl ValidationReport {
+    /// Whether all checks passed.
+    pub fn is_valid(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// The checks that failed, in the order they were run.
+    pub fn failures(&self) -> &[ValidationFailure] {
+        &self.failures
+    }
+
+    pub(crate) fn check(&mut self, condition: bool, failure: ValidationFailure) {
+        if !condition {
+            self.failures.push(failure);
+        }
+    }
+}
+
+/// Whether `point` lies in the subgroup of prime order `r`, i.e. whether `r * point` is zero.
+pub(crate) fn in_subgroup<G: ProjectiveCurve>(point: &G) -> bool {
+    let modulus = <<G::ScalarField as PrimeField>::Params as FpParameters>::MODULUS;
+    point.into_affine().mul(modulus).is_zero()
+}
+
+/// Check that `g1` and `g2`, of the lengths of a channel with `n` participants, start with the
+/// generators, withhold the point at position `n + 1`, lie in the subgroup and contain consecutive
+/// powers of the same alpha.
+pub(crate) fn check_powers<E: PairingEngine>(
+    n: usize,
+    g1: &[E::G1Affine],
+    g2: &[E::G2Affine],
+    report: &mut ValidationReport,
+) {
+    report.check(
+        g1[0] == E::G1Affine::prime_subgroup_generator()
+            && g2[0] == E::G2Affine::prime_subgroup_generator(),
+        ValidationFailure::NotGenerator,
+    );
+    report.check(
+        g1[n + 1].is_zero(),
+        ValidationFailure::WithheldPointPublished,
+    );
+
+    for (position, point) in g1.iter().enumerate() {
+        report.check(
+            in_subgroup(&point.into_projective()),
+            ValidationFailure::G1NotInSubgroup(position),
+        );
+    }
+    for (position, point) in g2.iter().enumerate() {
+        report.check(
+            in_subgroup(&point.into_projective()),
+            ValidationFailure::G2NotInSubgroup(position),
+        );
+    }
+
+    // e(P_i, Q) = e(P_{i-1}, Q_1), except right after the withheld position, where the
+    // previous published point is two powers behind
+    for position in (1..2 * n + 1).filter(|position| *position != n + 1) {
+        let (previous, step) = if position == n + 2 {
+            (g1[n], g2[2])
+        } else {
+            (g1[position - 1], g2[1])
+        };
+        report.check(
+            E::pairing(g1[position], g2[0]) == E::pairing(previous, step),
+            ValidationFailure::G1NotConsecutive(position),
+        );
+    }
+
+    // e(P, Q_i) = e(P_1, Q_{i-1})
+    for position in 1..n + 1 {
+        report.check(
+            E::pairing(g1[0], g2[position]) == E::pairing(g1[1], g2[position - 1]),
+            ValidationFailure::G2NotConsecutive(position),
+        );
+    }
+}
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Check that the public parameters are well formed: vector lengths, subgroup membership,
+    /// and, through pairings, that both vectors contain consecutive powers of the same alpha.
+    pub fn validate(&self) -> ValidationReport {
+        let n = self.number_participants;
+        let g1 = &self.broadcaster_pk_g1;
+        let g2 = &self.broadcaster_pk_g2;
+
+        let mut report = ValidationReport::default();
+        report.check(
+            g1.len() == 2 * n + 1,
+            ValidationFailure::G1Length {
+                expected: 2 * n + 1,
+                found: g1.len(),
+            },
+        );
+        report.check(
+            g2.len() == n + 1,
+            ValidationFailure::G2Length {
+                expected: n + 1,
+                found: g2.len(),
+            },
+        );
+        report.check(self.issued <= n, ValidationFailure::IssuedExceedsCapacity);
+        report.check(
+            !self.point_v.is_zero() && in_subgroup(&self.point_v),
+            ValidationFailure::InvalidPointV,
+        );
+        if !report.is_valid() {
+            // the remaining checks index into the vectors
+            return report;
+        }
+
+        check_powers::<E>(n, g1, g2, &mut report);
+
+        report
+    }
+}
+
+impl<E: PairingEngine> Recipient<E> {
+    /// Check that this recipient holds a valid key for `channel`: its identifier is an issued
+    /// slot, its public key is the one published for that slot, and its private key pairs
+    /// correctly with `V`. The parameters of the channel are not checked, see
+    /// [`BroadcastChannel::validate`].
+    pub fn validate(&self, channel: &BroadcastChannel<E>) -> ValidationReport {
+        let mut report = ValidationReport::default();
+
+        let published = channel.public_key(self.identifier);
+        report.check(
+            published.is_some(),
+            ValidationFailure::IdentifierOutOfRange(self.identifier),
+        );
+        report.check(
+            published.is_none() || published == Some(self.public_key()),
+            ValidationFailure::PublicKeyMismatch,
+        );
+
+        let private_key = &self.key_pair.private_key;
+        let valid_private_key = !private_key.is_zero() && in_subgroup(private_key);
+        report.check(valid_private_key, ValidationFailure::InvalidPrivateKey);
+
+        // the private key is gamma * P_i and the public key is Q_i, so that
+        // e(private, Q) = e(V, public)
+        if valid_private_key {
+            report.check(
+                E::pairing(*private_key, E::G2Projective::prime_subgroup_generator())
+                    == E::pairing(channel.point_v, self.key_pair.public_key),
+                ValidationFailure::KeyMismatch,
+            );
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+    use std::sync::Arc;
+
+    #[test]
+    fn reports_failures() {
+        let mut rng = thread_rng();
+        let (channel, participants) =
+            BroadcastChannel::<Bls12_381>::init_participants(4, &mut rng).unwrap();
+
+        assert!(channel.validate().is_valid());
+        for participant in participants.iter() {
+            assert!(participant.validate(&channel).is_valid());
+        }
+
+        let mut tampered = channel.clone();
+        let g1 = Arc::make_mut(&mut tampered.broadcaster_pk_g1);
+        g1[3] = g1[3].into_projective().add_mixed(&g1[1]).into_affine();
+        assert_eq!(
+            tampered.validate().failures(),
+            &[
+                ValidationFailure::G1NotConsecutive(3),
+                ValidationFailure::G1NotConsecutive(4),
+            ]
+        );
+
+        let mut tampered = channel.clone();
+        let g1 = Arc::make_mut(&mut tampered.broadcaster_pk_g1);
+        g1[5] = g1[4];
+        assert!(tampered
+            .validate()
+            .failures()
+            .contains(&ValidationFailure::WithheldPointPublished));
+
+        let mut truncated = channel.clone();
+        Arc::make_mut(&mut truncated.broadcaster_pk_g2).pop();
+        assert!(!truncated.validate().is_valid());
+
+        let mut wrong_key = participants[0].clone();
+        wrong_key.key_pair.private_key = participants[1].key_pair.private_key;
+        assert_eq!(
+            wrong_key.validate(&channel).failures(),
+            &[ValidationFailure::KeyMismatch]
+        );
+
+        let mut wrong_slot = participants[0].clone();
+        wrong_slot.identifier = 2;
+        assert_eq!(
+            wrong_slot.validate(&channel).failures(),
+            &[ValidationFailure::PublicKeyMismatch]
+        );
+
+        let (other_channel, _) =
+            BroadcastChannel::<Bls12_381>::init_participants(4, &mut rng).unwrap();
+        assert!(!participants[0].validate(&other_channel).is_valid());
+    }
+}
@@ -0,0 +1,274 @@
+//! Versions of the parameters of a channel, with a window in which they overlap.
+//!
+//! Rotating gamma, growing a channel or running a new setup gives new parameters, and new keys,
+//! while headers produced with the previous ones are still in flight. A [`VersionedChannel`]
+//! keeps the last few versions of the parameters, numbered from zero:
+//! [`VersionedChannel::publish`] makes new parameters the current version, and the oldest version
+//! leaves once more than `window` versions are kept. Encryption always uses the current version,
+//! and tags the header with its number.
+//!
+//! A [`VersionedRecipient`] holds the key of a participant for each version it was issued one
+//! for, and [`VersionedRecipient::decrypt`] picks the parameters and the key of the version of
+//! the header, so that decryptors go through a rotation without noticing it. Headers of a version
+//! that has left the window are refused, as are headers of a version the recipient holds no key
+//! for.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use ark_ec::PairingEngine;
+use ark_serialize::{CanonicalSerialize, SerializationError};
+use rand_core::{CryptoRng, RngCore};
+
+use crate::encoding::from_bytes;
+use crate::error::{DecryptError, EncryptError};
+use crate::wire::take_u64;
+use crate::{BroadcastChannel, Header, Recipient, SharedKey};
+
+/// Header tagged with the version of the parameters it was produced with.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct VersionedHeader<E: PairingEngine> {
+    version: u64,
+    header: Header<E>,
+}
+
+impl<E: PairingEngine> VersionedHeader<E> {
+    /// Version of the parameters of the header.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Header, without its version.
+    pub fn header(&self) -> &Header<E> {
+        &self.header
+    }
+
+    /// Encoding of the header: the version as a little-endian `u64`, then the compressed header.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(8 + self.header.serialized_size());
+        bytes.extend_from_slice(&self.version.to_le_bytes());
+        bytes.extend_from_slice(&self.header.to_bytes());
+        bytes
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for VersionedHeader<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        let (version, bytes) = take_u64(bytes)?;
+        Ok(VersionedHeader {
+            version,
+            header: from_bytes(bytes)?,
+        })
+    }
+}
+
+/// Last versions of the parameters of a channel.
+#[derive(Clone)]
+pub struct VersionedChannel<E: PairingEngine> {
+    versions: BTreeMap<u64, BroadcastChannel<E>>,
+    window: usize,
+}
+
+impl<E: PairingEngine> VersionedChannel<E> {
+    /// Version zero of the parameters, `channel`, keeping up to `window` versions. A window of
+    /// zero is taken as one.
+    pub fn new(channel: BroadcastChannel<E>, window: usize) -> Self {
+        let mut versions = BTreeMap::new();
+        versions.insert(0, channel);
+        VersionedChannel {
+            versions,
+            window: window.max(1),
+        }
+    }
+
+    /// Make `channel` the current version of the parameters, returning its number. The oldest
+    /// versions leave the window if there are more than `window`.
+    pub fn publish(&mut self, channel: BroadcastChannel<E>) -> u64 {
+        let version = self.current_version() + 1;
+        self.versions.insert(version, channel);
+        self.evict();
+        version
+    }
+
+    /// Change the number of versions kept, dropping the oldest ones if there are more. A window
+    /// of zero is taken as one.
+    pub fn set_window(&mut self, window: usize) {
+        self.window = window.max(1);
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        while self.versions.len() > self.window {
+            let oldest = *self.versions.keys().next().unwrap();
+            self.versions.remove(&oldest);
+        }
+    }
+
+    /// Number of versions kept.
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    /// Number of the current version.
+    pub fn current_version(&self) -> u64 {
+        // there is always at least one version
+        *self.versions.keys().next_back().unwrap()
+    }
+
+    /// Parameters of the current version.
+    pub fn current(&self) -> &BroadcastChannel<E> {
+        self.versions.values().next_back().unwrap()
+    }
+
+    /// Parameters of `version`, if it is in the window.
+    pub fn get(&self, version: u64) -> Option<&BroadcastChannel<E>> {
+        self.versions.get(&version)
+    }
+
+    /// Mutable parameters of `version`, if it is in the window, to retire keys or grow it.
+    pub fn get_mut(&mut self, version: u64) -> Option<&mut BroadcastChannel<E>> {
+        self.versions.get_mut(&version)
+    }
+
+    /// Numbers of the versions in the window, from the oldest.
+    pub fn versions(&self) -> impl Iterator<Item = u64> + '_ {
+        self.versions.keys().copied()
+    }
+
+    /// Same as [`BroadcastChannel::encrypt`] with the current version, with the header tagged
+    /// with its number.
+    pub fn encrypt<R>(
+        &self,
+        set_recipients: &[usize],
+        rng: &mut R,
+    ) -> Result<(VersionedHeader<E>, SharedKey<E>), EncryptError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let (header, key) = self.current().encrypt(set_recipients, rng)?;
+        let header = VersionedHeader {
+            version: self.current_version(),
+            header,
+        };
+        Ok((header, key))
+    }
+}
+
+/// Keys of a participant for several versions of the parameters.
+#[derive(Clone)]
+pub struct VersionedRecipient<E: PairingEngine> {
+    keys: BTreeMap<u64, Recipient<E>>,
+}
+
+impl<E: PairingEngine> Default for VersionedRecipient<E> {
+    fn default() -> Self {
+        VersionedRecipient {
+            keys: BTreeMap::new(),
+        }
+    }
+}
+
+impl<E: PairingEngine> VersionedRecipient<E> {
+    /// No keys.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hold `recipient` as the key for `version`, returning the key held before, if any.
+    pub fn insert(&mut self, version: u64, recipient: Recipient<E>) -> Option<Recipient<E>> {
+        self.keys.insert(version, recipient)
+    }
+
+    /// Stop holding the key for `version`, returning it.
+    pub fn remove(&mut self, version: u64) -> Option<Recipient<E>> {
+        self.keys.remove(&version)
+    }
+
+    /// Key for `version`.
+    pub fn get(&self, version: u64) -> Option<&Recipient<E>> {
+        self.keys.get(&version)
+    }
+
+    /// Numbers of the versions a key is held for, from the oldest.
+    pub fn versions(&self) -> impl Iterator<Item = u64> + '_ {
+        self.keys.keys().copied()
+    }
+
+    /// Drop the keys of the versions that have left the window of `channel`.
+    pub fn prune(&mut self, channel: &VersionedChannel<E>) {
+        self.keys
+            .retain(|version, _| channel.versions.contains_key(version));
+    }
+
+    /// Same as [`Recipient::decrypt`], with the parameters and the key of the version of
+    /// `header`. Fails if the version is not in the window of `channel`, or if no key is held for
+    /// it.
+    pub fn decrypt(
+        &self,
+        set_recipients: &[usize],
+        channel: &VersionedChannel<E>,
+        header: &VersionedHeader<E>,
+    ) -> Result<SharedKey<E>, DecryptError> {
+        let version = header.version;
+        match (channel.get(version), self.keys.get(&version)) {
+            (Some(parameters), Some(recipient)) => {
+                recipient.decrypt(set_recipients, parameters, &header.header)
+            }
+            _ => Err(DecryptError::UnknownVersion(version)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn decryptors_follow_rotations_within_the_window() {
+        let mut rng = thread_rng();
+        let (channel, participants, mut secret) =
+            BroadcastChannel::<Bls12_381>::init_with_secret(3, &mut rng).unwrap();
+        let mut rotated = channel.clone();
+        let mut versions = VersionedChannel::new(channel, 2);
+        let mut recipient = VersionedRecipient::new();
+        recipient.insert(0, participants[1].clone());
+
+        let (old, old_key) = versions.encrypt(&[1, 2], &mut rng).unwrap();
+        let keys = rotated.rotate(&mut secret, &mut rng).unwrap();
+        assert_eq!(versions.publish(rotated), 1);
+        recipient.insert(1, keys[1].clone());
+
+        // headers of both versions decrypt during the overlap
+        let (new, new_key) = versions.encrypt(&[2, 3], &mut rng).unwrap();
+        assert_eq!(new.version(), 1);
+        assert_eq!(recipient.decrypt(&[1, 2], &versions, &old), Ok(old_key));
+        assert_eq!(recipient.decrypt(&[2, 3], &versions, &new), Ok(new_key));
+        let decoded = VersionedHeader::try_from(new.to_bytes().as_slice()).unwrap();
+        assert!(decoded == new);
+
+        // the first version leaves the window with the third one
+        let (third, _, _) = BroadcastChannel::<Bls12_381>::init_with_secret(3, &mut rng).unwrap();
+        assert_eq!(versions.publish(third), 2);
+        assert!(versions.versions().eq(vec![1, 2]));
+        assert_eq!(
+            recipient.decrypt(&[1, 2], &versions, &old),
+            Err(DecryptError::UnknownVersion(0))
+        );
+        let (newest, _) = versions.encrypt(&[2], &mut rng).unwrap();
+        assert_eq!(
+            recipient.decrypt(&[2], &versions, &newest),
+            Err(DecryptError::UnknownVersion(2))
+        );
+        recipient.prune(&versions);
+        assert!(recipient.versions().eq(vec![1]));
+
+        versions.set_window(0);
+        assert_eq!(versions.window(), 1);
+        assert!(versions.versions().eq(vec![2]));
+    }
+}
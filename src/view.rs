@@ -0,0 +1,167 @@
+//! Read-only view over the public parameters of a channel.
+//!
+//! A [`ChannelView`] borrows a [`BroadcastChannel`] and only exposes what is public: the points
+//! of the SRS, the point `V`, the capacity, the retired keys and a fingerprint identifying the
+//! parameters. It offers no way to mutate the channel, and the channel holds no secrets, so it can
+//! be handed to external auditors and monitoring tools.
+
+use core::convert::TryFrom;
+
+use ark_ec::PairingEngine;
+use ark_serialize::{CanonicalSerialize, SerializationError};
+use sha2::{Digest, Sha256};
+
+use crate::policy::Policy;
+use crate::BroadcastChannel;
+
+/// Domain separation tag of the parameter fingerprint.
+const FINGERPRINT_TAG: &[u8] = b"DGS-BE-CHANNEL-FINGERPRINT-V1";
+
+/// Separates the retired keys from the policy in the fingerprint.
+const RETIRED_TAG: &[u8] = b"DGS-BE-RETIRED";
+
+/// SHA-256 fingerprint identifying the public parameters of a channel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Fingerprint([u8; 32]);
+
+impl Fingerprint {
+    /// Bytes of the fingerprint.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+}
+
+impl AsRef<[u8]> for Fingerprint {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<[u8; 32]> for Fingerprint {
+    fn from(bytes: [u8; 32]) -> Self {
+        Fingerprint(bytes)
+    }
+}
+
+impl From<Fingerprint> for [u8; 32] {
+    fn from(fingerprint: Fingerprint) -> Self {
+        fingerprint.0
+    }
+}
+
+impl TryFrom<&[u8]> for Fingerprint {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        let bytes = <[u8; 32]>::try_from(bytes).map_err(|_| SerializationError::InvalidData)?;
+        Ok(Fingerprint(bytes))
+    }
+}
+
+/// Borrowed, read-only view over the public parameters of a [`BroadcastChannel`].
+#[derive(Clone, Copy)]
+pub struct ChannelView<'a, E: PairingEngine> {
+    channel: &'a BroadcastChannel<E>,
+}
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Read-only view over the public parameters of the channel.
+    pub fn view(&self) -> ChannelView<'_, E> {
+        ChannelView { channel: self }
+    }
+}
+
+impl<'a, E: PairingEngine> ChannelView<'a, E> {
+    /// Maximum number of participants of the channel.
+    pub fn capacity(&self) -> usize {
+        self.channel.number_participants
+    }
+
+    /// Published points in G1 together with their position. Position `n + 1` is never published,
+    /// so it is skipped.
+    pub fn g1_points(&self) -> impl Iterator<Item = (usize, &'a E::G1Affine)> {
+        let withheld = self.channel.number_participants + 1;
+        self.channel
+            .broadcaster_pk_g1
+            .iter()
+            .enumerate()
+            .filter(move |(position, _)| *position != withheld)
+    }
+
+    /// Published points in G2, in order of position.
+    pub fn g2_points(&self) -> impl Iterator<Item = &'a E::G2Affine> {
+        self.channel.broadcaster_pk_g2.iter()
+    }
+
+    /// The point `V` used to build the headers.
+    pub fn point_v(&self) -> &'a E::G1Projective {
+        &self.channel.point_v
+    }
+
+    /// Policy enforced by the encryption.
+    pub fn policy(&self) -> &'a Policy {
+        &self.channel.policy
+    }
+
+    /// Identifiers whose keys have been retired, in increasing order.
+    pub fn retired(&self) -> impl Iterator<Item = usize> + 'a {
+        self.channel.retired.iter().copied()
+    }
+
+    /// SHA-256 fingerprint of the public parameters, including the policy. Two channels have the
+    /// same fingerprint if and only if they publish the same parameters.
+    pub fn fingerprint(&self) -> Fingerprint {
+        let mut bytes = FINGERPRINT_TAG.to_vec();
+        bytes.extend_from_slice(&(self.capacity() as u64).to_le_bytes());
+
+        // serializing into a vector cannot fail
+        for (_, point) in self.g1_points() {
+            point.serialize(&mut bytes).unwrap();
+        }
+        for point in self.g2_points() {
+            point.serialize(&mut bytes).unwrap();
+        }
+        self.point_v().serialize(&mut bytes).unwrap();
+
+        // channels without policy keep the fingerprint they had before policies existed
+        if let Some(limit) = self.policy().max_set_size() {
+            bytes.extend_from_slice(&(limit as u64).to_le_bytes());
+        }
+        // and so do channels without retired keys
+        if !self.channel.retired.is_empty() {
+            bytes.extend_from_slice(RETIRED_TAG);
+            bytes.extend_from_slice(&(self.channel.retired.len() as u64).to_le_bytes());
+            for identifier in self.retired() {
+                bytes.extend_from_slice(&(identifier as u64).to_le_bytes());
+            }
+        }
+
+        let mut fingerprint = [0u8; 32];
+        fingerprint.copy_from_slice(&Sha256::digest(&bytes));
+        Fingerprint(fingerprint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn view_exposes_public_parameters() {
+        let mut rng = thread_rng();
+        let (channel, _) = BroadcastChannel::<Bls12_381>::init_participants(5, &mut rng).unwrap();
+        let view = channel.view();
+
+        assert_eq!(view.capacity(), 5);
+        assert_eq!(view.g1_points().count(), 2 * 5);
+        assert!(view.g1_points().all(|(position, _)| position != 6));
+        assert_eq!(view.g2_points().count(), 5 + 1);
+        assert_eq!(view.fingerprint(), channel.clone().view().fingerprint());
+
+        let (other_channel, _) =
+            BroadcastChannel::<Bls12_381>::init_participants(5, &mut rng).unwrap();
+        assert_ne!(view.fingerprint(), other_channel.view().fingerprint());
+    }
+}
@@ -0,0 +1,123 @@
+//! Bindings for recipients running in the browser.
+//!
+//! The wrappers here expose the decryption on BLS12-381 to JavaScript through `wasm-bindgen`,
+//! once the crate is compiled for `wasm32-unknown-unknown`. Parameters, keys and headers are
+//! handed over in their compressed encodings, as produced by the crate on the broadcaster side,
+//! and sets as `Uint32Array`s of identifiers. Errors are thrown as strings.
+//!
+//! The key of a header lives in the target group and has no JavaScript counterpart, so
+//! [`WasmRecipient::decrypt`] returns a 32-byte key derived from it with [`HkdfSha256`] under an
+//! info chosen by the application, to import into WebCrypto. Payloads sealed with
+//! [`BroadcastChannel::encrypt_message`] are opened with [`WasmRecipient::decrypt_message`].
+//! Decrypting takes no randomness, so nothing here depends on the randomness of the browser.
+//!
+//! Only available with the `wasm` feature.
+
+use std::convert::TryFrom;
+
+use ark_bls12_381::Bls12_381;
+use wasm_bindgen::prelude::*;
+
+use crate::kdf::{derive_key, HkdfSha256};
+use crate::{BroadcastChannel, Header, Recipient};
+
+fn to_js<T: std::fmt::Display>(error: T) -> JsValue {
+    JsValue::from_str(&error.to_string())
+}
+
+fn to_set(set_recipients: &[u32]) -> Vec<usize> {
+    set_recipients
+        .iter()
+        .map(|identifier| *identifier as usize)
+        .collect()
+}
+
+/// Public parameters of a channel.
+#[wasm_bindgen]
+pub struct WasmChannel(BroadcastChannel<Bls12_381>);
+
+#[wasm_bindgen]
+impl WasmChannel {
+    /// Parameters from their compressed encoding.
+    #[wasm_bindgen(constructor)]
+    pub fn new(bytes: &[u8]) -> Result<WasmChannel, JsValue> {
+        BroadcastChannel::try_from(bytes)
+            .map(WasmChannel)
+            .map_err(to_js)
+    }
+
+    /// Number of participants of the channel.
+    #[wasm_bindgen(js_name = numberParticipants)]
+    pub fn number_participants(&self) -> u32 {
+        self.0.number_participants as u32
+    }
+}
+
+/// Header of an encryption.
+#[wasm_bindgen]
+pub struct WasmHeader(Header<Bls12_381>);
+
+#[wasm_bindgen]
+impl WasmHeader {
+    /// Header from its compressed encoding.
+    #[wasm_bindgen(constructor)]
+    pub fn new(bytes: &[u8]) -> Result<WasmHeader, JsValue> {
+        Header::try_from(bytes).map(WasmHeader).map_err(to_js)
+    }
+
+    /// Compressed encoding of the header.
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes()
+    }
+}
+
+/// Key pair of a participant.
+#[wasm_bindgen]
+pub struct WasmRecipient(Recipient<Bls12_381>);
+
+#[wasm_bindgen]
+impl WasmRecipient {
+    /// Key pair from its compressed encoding.
+    #[wasm_bindgen(constructor)]
+    pub fn new(bytes: &[u8]) -> Result<WasmRecipient, JsValue> {
+        Recipient::try_from(bytes).map(WasmRecipient).map_err(to_js)
+    }
+
+    /// Identifier of the participant.
+    pub fn identifier(&self) -> u32 {
+        self.0.identifier as u32
+    }
+
+    /// 32-byte key derived with `info` from the key of `header`, encrypted for `set_recipients`.
+    pub fn decrypt(
+        &self,
+        set_recipients: &[u32],
+        channel: &WasmChannel,
+        header: &WasmHeader,
+        info: &[u8],
+    ) -> Result<Vec<u8>, JsValue> {
+        let key = self
+            .0
+            .decrypt(&to_set(set_recipients), &channel.0, &header.0)
+            .map_err(to_js)?;
+        let mut output = vec![0u8; 32];
+        // 32 bytes are within the output of HKDF
+        derive_key::<Bls12_381, HkdfSha256>(key.key(), info, &mut output).unwrap();
+        Ok(output)
+    }
+
+    /// Payload `sealed` with `header`, encrypted for `set_recipients`.
+    #[wasm_bindgen(js_name = decryptMessage)]
+    pub fn decrypt_message(
+        &self,
+        set_recipients: &[u32],
+        channel: &WasmChannel,
+        header: &WasmHeader,
+        sealed: &[u8],
+    ) -> Result<Vec<u8>, JsValue> {
+        self.0
+            .decrypt_message(&to_set(set_recipients), &channel.0, &header.0, sealed)
+            .map_err(to_js)
+    }
+}
@@ -0,0 +1,212 @@
+//! Versioned wire format of encrypted messages.
+//!
+//! A [`WireMessage`] is what travels from the broadcaster to the recipients: the header, the set
+//! it was encrypted for when the recipients cannot tell it from context, and the ciphertext of
+//! the payload under the key of the header, such as the output of
+//! [`crate::BroadcastChannel::encrypt_message`]. Its encoding is meant to be implemented
+//! elsewhere, so it is specified byte for byte:
+//!
+//! - the version of the format, [`WIRE_FORMAT_VERSION`], in one byte;
+//! - the compressed `C_0` in G1, then the compressed `C_1` in G2, that is 48 and 96 bytes on
+//!   BLS12-381, as in the encoding of a [`Header`];
+//! - one byte, 0 if the set is absent and 1 if it is present, followed in that case by the
+//!   canonical encoding of the [`RecipientSet`];
+//! - the length of the ciphertext as a little-endian `u64`, then the ciphertext itself.
+//!
+//! Decoding is strict: any other version or presence byte, points that are not in their
+//! subgroups, sets that are not canonical, lengths that do not match and trailing bytes are all
+//! rejected, so that an encoded message has a single valid reading.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use ark_ec::PairingEngine;
+use ark_ff::Zero;
+use ark_serialize::{CanonicalSerialize, SerializationError};
+
+use crate::encoding::from_bytes;
+use crate::set::RecipientSet;
+use crate::Header;
+
+/// Version of the wire format.
+pub const WIRE_FORMAT_VERSION: u8 = 1;
+
+/// Header, optional set and ciphertext of a message, as transmitted.
+#[derive(Clone, PartialEq, Eq)]
+pub struct WireMessage<E: PairingEngine> {
+    header: Header<E>,
+    set: Option<RecipientSet>,
+    ciphertext: Vec<u8>,
+}
+
+impl<E: PairingEngine> WireMessage<E> {
+    /// Message of `ciphertext` under `header`, for a set the recipients know from context.
+    pub fn new(header: Header<E>, ciphertext: Vec<u8>) -> Self {
+        WireMessage {
+            header,
+            set: None,
+            ciphertext,
+        }
+    }
+
+    /// Message of `ciphertext` under `header`, carrying the set it is encrypted for.
+    pub fn with_set(header: Header<E>, set: RecipientSet, ciphertext: Vec<u8>) -> Self {
+        WireMessage {
+            header,
+            set: Some(set),
+            ciphertext,
+        }
+    }
+
+    /// Header of the message.
+    pub fn header(&self) -> &Header<E> {
+        &self.header
+    }
+
+    /// Set the message is encrypted for, if it carries one.
+    pub fn set(&self) -> Option<&RecipientSet> {
+        self.set.as_ref()
+    }
+
+    /// Ciphertext of the payload.
+    pub fn ciphertext(&self) -> &[u8] {
+        &self.ciphertext
+    }
+
+    /// Encoding of the message in the wire format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let set = self.set.as_ref().map(RecipientSet::canonical_bytes);
+        let mut bytes = Vec::with_capacity(
+            1 + self.header.serialized_size()
+                + 1
+                + set.as_ref().map_or(0, Vec::len)
+                + 8
+                + self.ciphertext.len(),
+        );
+        bytes.push(WIRE_FORMAT_VERSION);
+        bytes.extend_from_slice(&self.header.to_bytes());
+        match set {
+            Some(set) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&set);
+            }
+            None => bytes.push(0),
+        }
+        bytes.extend_from_slice(&(self.ciphertext.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&self.ciphertext);
+        bytes
+    }
+}
+
+/// First `length` bytes of `bytes`, and the rest.
+pub(crate) fn take(bytes: &[u8], length: usize) -> Result<(&[u8], &[u8]), SerializationError> {
+    if bytes.len() < length {
+        return Err(SerializationError::InvalidData);
+    }
+    Ok(bytes.split_at(length))
+}
+
+/// Little-endian `u64` at the start of `bytes`, and the rest.
+pub(crate) fn take_u64(bytes: &[u8]) -> Result<(u64, &[u8]), SerializationError> {
+    let (word, rest) = take(bytes, 8)?;
+    let mut le_bytes = [0u8; 8];
+    le_bytes.copy_from_slice(word);
+    Ok((u64::from_le_bytes(le_bytes), rest))
+}
+
+/// Canonically encoded set at the start of `bytes`, and the rest.
+pub(crate) fn take_set(bytes: &[u8]) -> Result<(RecipientSet, &[u8]), SerializationError> {
+    // the version byte and the number of identifiers, then the identifiers
+    let (count, _) = take_u64(take(bytes, 1)?.1)?;
+    let length = usize::try_from(count)
+        .ok()
+        .and_then(|count| count.checked_mul(8))
+        .and_then(|length| length.checked_add(9))
+        .ok_or(SerializationError::InvalidData)?;
+    let (set, bytes) = take(bytes, length)?;
+    Ok((RecipientSet::try_from(set)?, bytes))
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for WireMessage<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        let (version, bytes) = take(bytes, 1)?;
+        if version[0] != WIRE_FORMAT_VERSION {
+            return Err(SerializationError::InvalidData);
+        }
+
+        let header_size =
+            E::G1Projective::zero().serialized_size() + E::G2Projective::zero().serialized_size();
+        let (header, bytes) = take(bytes, header_size)?;
+        let header = from_bytes(header)?;
+
+        let (presence, bytes) = take(bytes, 1)?;
+        let (set, bytes) = match presence[0] {
+            0 => (None, bytes),
+            1 => {
+                let (set, bytes) = take_set(bytes)?;
+                (Some(set), bytes)
+            }
+            _ => return Err(SerializationError::InvalidData),
+        };
+
+        let (length, ciphertext) = take_u64(bytes)?;
+        if length != ciphertext.len() as u64 {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(WireMessage {
+            header,
+            set,
+            ciphertext: ciphertext.to_vec(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BroadcastChannel;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn wire_format_is_strict() {
+        let mut rng = thread_rng();
+        let (channel, _) = BroadcastChannel::<Bls12_381>::init_participants(4, &mut rng).unwrap();
+        let (header, _) = channel.encrypt(&[1, 3], &mut rng).unwrap();
+        let ciphertext = vec![7u8; 20];
+
+        let message = WireMessage::new(header, ciphertext.clone());
+        let bytes = message.to_bytes();
+        assert_eq!(bytes.len(), 1 + 48 + 96 + 1 + 8 + 20);
+        assert_eq!(bytes[0], WIRE_FORMAT_VERSION);
+        assert_eq!(&bytes[1..145], header.to_bytes().as_slice());
+        assert!(WireMessage::<Bls12_381>::try_from(bytes.as_slice()).unwrap() == message);
+
+        let set = RecipientSet::new(&[3, 1]);
+        let message = WireMessage::with_set(header, set.clone(), ciphertext);
+        let bytes = message.to_bytes();
+        assert_eq!(bytes.len(), 1 + 48 + 96 + 1 + (1 + 8 * 3) + 8 + 20);
+        let decoded = WireMessage::<Bls12_381>::try_from(bytes.as_slice()).unwrap();
+        assert_eq!(decoded.set(), Some(&set));
+        assert_eq!(decoded.ciphertext(), &[7u8; 20][..]);
+        assert!(decoded == message);
+
+        let refused = |bytes: &[u8]| WireMessage::<Bls12_381>::try_from(bytes).is_err();
+        let mut other_version = bytes.clone();
+        other_version[0] = 2;
+        assert!(refused(&other_version));
+        let mut other_presence = bytes.clone();
+        other_presence[145] = 2;
+        assert!(refused(&other_presence));
+        let mut longer = bytes.clone();
+        longer.push(0);
+        assert!(refused(&longer));
+        assert!(refused(&bytes[..bytes.len() - 1]));
+        // a set that is not sorted
+        let mut unsorted = bytes.clone();
+        unsorted.swap(155, 163);
+        assert!(refused(&unsorted));
+    }
+}
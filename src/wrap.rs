@@ -0,0 +1,397 @@
+//! Delivery of private keys encrypted to the recipient.
+//!
+//! Keys issued by the trusted entity have to reach remote participants somehow, and the encoding
+//! of a [`Recipient`] holds the private key in the clear. A participant that generated an X25519
+//! key pair for the purpose sends its public key to the entity, which issues the key with
+//! [`BroadcastChannel::issue_key_wrapped`] and sends back the [`WrappedKey`], readable by the
+//! holder of the X25519 secret only, with [`WrappedKey::open`]. Onboarding flows issue the keys
+//! of a whole batch of reserved slots at once with [`BroadcastChannel::issue_keys`].
+//!
+//! The construction follows the base mode of HPKE with DHKEM(X25519), without being byte
+//! compatible with it: a fresh ephemeral key is agreed with the key of the recipient, a 32-byte
+//! key is derived from the shared secret and both public keys with [`HkdfSha256`], and the
+//! encoding of the key pair is sealed with ChaCha20-Poly1305. The fingerprint of the channel and
+//! the identifier are the associated data, so a wrapped key is refused for another channel or
+//! slot, and opening checks that the public key is the one of the slot. Transport keys of small
+//! order, for which the shared secret is known to everyone, are refused.
+//!
+//! The transport key itself is not authenticated here: the entity must obtain it from the
+//! participant over a channel it trusts. Only available with the `key-wrap` feature.
+
+use std::convert::TryFrom;
+use std::marker::PhantomData;
+use std::ops::Range;
+
+use ark_ec::msm::FixedBaseMSM;
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{Field, PrimeField};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use chacha20poly1305::aead::{Aead, NewAead, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand_core::{CryptoRng, RngCore};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use x25519_dalek::{EphemeralSecret, PublicKey, StaticSecret};
+use zeroize::Zeroize;
+
+use crate::encoding::{from_bytes, to_bytes};
+use crate::error::{SetupError, WrapError};
+use crate::events::Event;
+use crate::kdf::{HkdfSha256, KeyDerivation};
+use crate::secret::BroadcasterSecret;
+use crate::view::Fingerprint;
+use crate::{BroadcastChannel, KeyPair, Recipient};
+
+/// Info of the derivation of the wrapping keys.
+const WRAP_INFO: &[u8] = b"DGS-BE-KEY-WRAP-X25519-CHACHA20POLY1305";
+
+/// Key pair of a participant, encrypted to its X25519 transport key.
+#[derive(Clone, PartialEq, Eq)]
+pub struct WrappedKey<E: PairingEngine> {
+    identifier: usize,
+    /// ephemeral X25519 public key
+    ephemeral: [u8; 32],
+    /// sealed encoding of the key pair
+    ciphertext: Vec<u8>,
+    _engine: PhantomData<E>,
+}
+
+/// Cipher of the wrapping key derived from `shared`, between `ephemeral` and `transport`.
+fn cipher(shared: &[u8; 32], ephemeral: &PublicKey, transport: &PublicKey) -> ChaCha20Poly1305 {
+    let mut info = WRAP_INFO.to_vec();
+    info.extend_from_slice(ephemeral.as_bytes());
+    info.extend_from_slice(transport.as_bytes());
+    let mut bytes = [0u8; 32];
+    // 32 bytes are within the output of HKDF
+    HkdfSha256::derive(shared, &info, &mut bytes).unwrap();
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(&bytes));
+    bytes.zeroize();
+    cipher
+}
+
+/// Associated data of the wrapped key of `identifier` in the channel with `fingerprint`.
+fn associated_data(fingerprint: &Fingerprint, identifier: usize) -> Vec<u8> {
+    let mut data = fingerprint.as_ref().to_vec();
+    data.extend_from_slice(&(identifier as u64).to_le_bytes());
+    data
+}
+
+impl<E: PairingEngine> BroadcastChannel<E> {
+    /// Key of participant `identifier`, encrypted to its X25519 key `transport`. Fails if
+    /// `secret` is not the one of the channel, if no key is issued for `identifier`, or if
+    /// `transport` has small order.
+    pub fn issue_key_wrapped<R>(
+        &self,
+        secret: &BroadcasterSecret<E>,
+        identifier: usize,
+        transport: &PublicKey,
+        rng: &mut R,
+    ) -> Result<WrappedKey<E>, WrapError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if !secret.belongs_to(self) {
+            return Err(WrapError::Setup(SetupError::WrongSecret));
+        }
+        if self.public_key(identifier).is_none() {
+            return Err(WrapError::Setup(SetupError::NotIssued(identifier)));
+        }
+
+        let ephemeral_secret = EphemeralSecret::new(&mut *rng);
+        let recipient = self.issue_key(secret, identifier);
+        wrap(
+            &self.view().fingerprint(),
+            identifier,
+            &recipient.key_pair,
+            ephemeral_secret,
+            transport,
+        )
+    }
+
+    /// Keys of the next unused slots `identifiers`, encrypted to the X25519 keys `transports`
+    /// in the same order, which are issued in one go. The private keys are multiplications of
+    /// the same generator, which share a single window table, and with the `parallel` feature
+    /// the keys are derived and wrapped on the global [`rayon`] pool. Fails, issuing nothing, if
+    /// `secret` is not the one of the channel, if `identifiers` does not start at the first
+    /// unused slot or goes beyond the channel, if there is not one transport key per identifier,
+    /// or if one of them has small order.
+    pub fn issue_keys<R>(
+        &mut self,
+        secret: &BroadcasterSecret<E>,
+        identifiers: Range<usize>,
+        transports: &[PublicKey],
+        rng: &mut R,
+    ) -> Result<Vec<WrappedKey<E>>, WrapError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if !secret.belongs_to(self) {
+            return Err(WrapError::Setup(SetupError::WrongSecret));
+        }
+        if identifiers.start != self.issued + 1 {
+            return Err(WrapError::NotUnusedSlots(self.issued + 1));
+        }
+        if identifiers.end > self.number_participants + 1 {
+            return Err(WrapError::Setup(SetupError::NoUnusedSlot));
+        }
+        if transports.len() != identifiers.len() {
+            return Err(WrapError::TransportKeyCount(identifiers.len()));
+        }
+
+        // alpha^i gamma for every identifier i of the batch, as in the setup
+        let mut scalars = Vec::with_capacity(identifiers.len());
+        let mut scalar = secret.alpha.pow([identifiers.start as u64]) * secret.gamma;
+        for _ in identifiers.clone() {
+            scalars.push(scalar);
+            scalar *= secret.alpha;
+        }
+        scalar.zeroize();
+        let scalar_size = E::Fr::size_in_bits();
+        let window = FixedBaseMSM::get_mul_window_size(scalars.len());
+        let table = FixedBaseMSM::get_window_table(
+            scalar_size,
+            window,
+            E::G1Projective::prime_subgroup_generator(),
+        );
+        let private_keys: Vec<E::G1Projective> =
+            FixedBaseMSM::multi_scalar_mul(scalar_size, window, &table, &scalars);
+        scalars.zeroize();
+
+        // drawn here, so the generator need not be shared between threads
+        let ephemerals: Vec<EphemeralSecret> = transports
+            .iter()
+            .map(|_| EphemeralSecret::new(&mut *rng))
+            .collect();
+        let batch: Vec<_> = identifiers
+            .clone()
+            .zip(private_keys)
+            .zip(ephemerals.into_iter().zip(transports))
+            .collect();
+        let fingerprint = self.view().fingerprint();
+        #[cfg(feature = "parallel")]
+        let batch = batch.into_par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let batch = batch.into_iter();
+        let wrapped = batch
+            .map(|((identifier, private_key), (ephemeral, transport))| {
+                let key_pair = KeyPair {
+                    public_key: self.broadcaster_pk_g2[identifier].into_projective(),
+                    private_key,
+                };
+                wrap(&fingerprint, identifier, &key_pair, ephemeral, transport)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for identifier in identifiers {
+            self.issued += 1;
+            self.issued_aggregate.add_assign_mixed(
+                &self.broadcaster_pk_g1[self.number_participants + 1 - identifier],
+            );
+            self.emit(Event::KeyIssued { identifier });
+        }
+        Ok(wrapped)
+    }
+}
+
+/// `key_pair` of participant `identifier` in the channel with `fingerprint`, encrypted to
+/// `transport` with `ephemeral_secret`.
+fn wrap<E: PairingEngine>(
+    fingerprint: &Fingerprint,
+    identifier: usize,
+    key_pair: &KeyPair<E>,
+    ephemeral_secret: EphemeralSecret,
+    transport: &PublicKey,
+) -> Result<WrappedKey<E>, WrapError> {
+    let ephemeral = PublicKey::from(&ephemeral_secret);
+    let shared = ephemeral_secret.diffie_hellman(transport);
+    if shared.as_bytes() == &[0u8; 32] {
+        return Err(WrapError::WeakTransportKey);
+    }
+
+    let mut plaintext = to_bytes(key_pair);
+    let aad = associated_data(fingerprint, identifier);
+    // each wrapping key is fresh and seals a single key pair, so the nonce is fixed
+    let ciphertext = cipher(shared.as_bytes(), &ephemeral, transport)
+        .encrypt(
+            Nonce::from_slice(&[0u8; 12]),
+            Payload {
+                msg: &plaintext,
+                aad: &aad,
+            },
+        )
+        .unwrap();
+    plaintext.zeroize();
+
+    Ok(WrappedKey {
+        identifier,
+        ephemeral: ephemeral.to_bytes(),
+        ciphertext,
+        _engine: PhantomData,
+    })
+}
+
+impl<E: PairingEngine> WrappedKey<E> {
+    /// Identifier of the participant the key is for.
+    pub fn identifier(&self) -> usize {
+        self.identifier
+    }
+
+    /// Participant wrapped to the X25519 key of `transport`, for `channel`. Fails if the key was
+    /// wrapped to another transport key or for another channel, if it was modified, or if it is
+    /// not the key of its slot.
+    pub fn open(
+        &self,
+        transport: &StaticSecret,
+        channel: &BroadcastChannel<E>,
+    ) -> Result<Recipient<E>, WrapError> {
+        let ephemeral = PublicKey::from(self.ephemeral);
+        let shared = transport.diffie_hellman(&ephemeral);
+        let aad = associated_data(&channel.view().fingerprint(), self.identifier);
+        let mut plaintext = cipher(shared.as_bytes(), &ephemeral, &PublicKey::from(transport))
+            .decrypt(
+                Nonce::from_slice(&[0u8; 12]),
+                Payload {
+                    msg: &self.ciphertext,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| WrapError::Authentication)?;
+        let key_pair = from_bytes::<KeyPair<E>>(&plaintext);
+        plaintext.zeroize();
+        let key_pair = key_pair.map_err(|_| WrapError::InvalidKey)?;
+
+        let recipient = Recipient {
+            identifier: self.identifier,
+            key_pair,
+            budget: None,
+        };
+        if channel.public_key(self.identifier) != Some(recipient.public_key()) {
+            return Err(WrapError::InvalidKey);
+        }
+        Ok(recipient)
+    }
+
+    /// Encoding of the wrapped key.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+}
+
+impl<E: PairingEngine> CanonicalSerialize for WrappedKey<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.identifier.serialize(&mut writer)?;
+        writer.write_all(&self.ephemeral)?;
+        self.ciphertext.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.identifier.serialized_size() + self.ephemeral.len() + self.ciphertext.serialized_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for WrappedKey<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let identifier = usize::deserialize(&mut reader)?;
+        let mut ephemeral = [0u8; 32];
+        reader.read_exact(&mut ephemeral)?;
+        Ok(WrappedKey {
+            identifier,
+            ephemeral,
+            ciphertext: Vec::deserialize(&mut reader)?,
+            _engine: PhantomData,
+        })
+    }
+}
+
+impl<E: PairingEngine> TryFrom<&[u8]> for WrappedKey<E> {
+    type Error = SerializationError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, SerializationError> {
+        from_bytes(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use rand::thread_rng;
+
+    #[test]
+    fn wrapped_keys_open_for_their_holder() {
+        let mut rng = thread_rng();
+        let (channel, _, secret) =
+            BroadcastChannel::<Bls12_381>::init_with_secret(3, &mut rng).unwrap();
+        let transport = StaticSecret::new(&mut rng);
+
+        let wrapped = channel
+            .issue_key_wrapped(&secret, 2, &PublicKey::from(&transport), &mut rng)
+            .unwrap();
+        let received = WrappedKey::try_from(wrapped.to_bytes().as_slice()).unwrap();
+        let recipient = received.open(&transport, &channel).unwrap();
+        let (header, key) = channel.encrypt(&[2, 3], &mut rng).unwrap();
+        assert_eq!(
+            recipient.decrypt(&[2, 3], &channel, &header),
+            Ok(key.clone())
+        );
+
+        let other = StaticSecret::new(&mut rng);
+        assert!(matches!(
+            wrapped.open(&other, &channel),
+            Err(WrapError::Authentication)
+        ));
+        let mut moved = wrapped.clone();
+        moved.identifier = 1;
+        assert!(matches!(
+            moved.open(&transport, &channel),
+            Err(WrapError::Authentication)
+        ));
+        assert!(matches!(
+            channel.issue_key_wrapped(&secret, 4, &PublicKey::from(&transport), &mut rng),
+            Err(WrapError::Setup(SetupError::NotIssued(4)))
+        ));
+    }
+
+    #[test]
+    fn batches_of_keys_are_issued() {
+        let mut rng = thread_rng();
+        let (mut channel, _, secret) =
+            BroadcastChannel::<Bls12_381>::init_with_reserve(2, 4, &mut rng).unwrap();
+        let transports: Vec<StaticSecret> = (0..3).map(|_| StaticSecret::new(&mut rng)).collect();
+        let public: Vec<PublicKey> = transports.iter().map(PublicKey::from).collect();
+
+        assert_eq!(
+            channel.issue_keys(&secret, 4..7, &public, &mut rng).err(),
+            Some(WrapError::NotUnusedSlots(3))
+        );
+        assert_eq!(
+            channel.issue_keys(&secret, 3..5, &public, &mut rng).err(),
+            Some(WrapError::TransportKeyCount(2))
+        );
+        let wrapped = channel
+            .issue_keys(&secret, 3..6, &public, &mut rng)
+            .unwrap();
+        assert_eq!(channel.issued().collect::<Vec<_>>(), vec![1, 2, 3, 4, 5]);
+        let recipients: Vec<Recipient<Bls12_381>> = wrapped
+            .iter()
+            .zip(&transports)
+            .map(|(wrapped, transport)| wrapped.open(transport, &channel).unwrap())
+            .collect();
+        let (header, key) = channel.encrypt(&[1, 4, 5], &mut rng).unwrap();
+        assert_eq!(
+            recipients[1].decrypt(&[1, 4, 5], &channel, &header),
+            Ok(key.clone())
+        );
+        assert_eq!(
+            recipients[2].decrypt(&[1, 4, 5], &channel, &header),
+            Ok(key.clone())
+        );
+
+        assert_eq!(
+            channel
+                .issue_keys(&secret, 6..8, &public[..2], &mut rng)
+                .err(),
+            Some(WrapError::Setup(SetupError::NoUnusedSlot))
+        );
+    }
+}